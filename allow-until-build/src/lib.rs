@@ -0,0 +1,190 @@
+//! Surfaces the annotations of [`allow-until`](https://crates.io/crates/allow-until) that are about
+//! to be removed as `cargo:warning=` lines from a build script, where the warnings of the proc macro
+//! itself don't reach.
+//!
+//! ```no_run
+//! // in the `main` function of build.rs
+//! allow_until_build::emit_warnings();
+//! ```
+//!
+//! The annotations are read from a report written by the proc macro, see
+//! [`ALLOW_UNTIL_REPORT`](https://github.com/DexterHill0/allow-until#report-format), and those in
+//! their warning window, expired but within their `grace` period or about to expire within
+//! `warn_ahead`, are warned about.
+//!
+//! # Ordering
+//!
+//! A build script runs before the crate it belongs to is compiled, so it can never read the entries
+//! of the compilation it is part of. [`emit_warnings`] reads the report of an earlier build
+//! instead, [`REPORT_FILE`] in the package root, which the package records and publishes with its
+//! source:
+//!
+//! ```sh
+//! rm -f allow-until.jsonl && ALLOW_UNTIL_REPORT=$PWD/allow-until.jsonl cargo build
+//! ```
+//!
+//! A report recorded at another version than the package's is warned about, as its entries may no
+//! longer be in their window. Once the report exists, the build script is rerun whenever it
+//! changes.
+
+#![warn(missing_docs)]
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use allow_until_report::Report;
+
+/// The name of the report [`emit_warnings`] reads in the package root.
+pub const REPORT_FILE: &str = "allow-until.jsonl";
+
+/// Names the report [`emit_warnings`] reads instead of [`REPORT_FILE`], relative to the package
+/// root.
+pub const REPORT_VAR: &str = "ALLOW_UNTIL_BUILD_REPORT";
+
+/// Prints a `cargo:warning=` line for each annotation of the package's report in its warning
+/// window. Nothing is printed while the package has no report.
+///
+/// The report is [`REPORT_FILE`] in the package root, or the file named by [`REPORT_VAR`]. A report
+/// that can't be read produces a single warning, it never fails the build.
+pub fn emit_warnings() {
+    let root = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let path = root.join(env::var_os(REPORT_VAR).unwrap_or_else(|| REPORT_FILE.into()));
+
+    if path.exists() {
+        println!("cargo:rerun-if-changed={}", path.display());
+        println!("cargo:rerun-if-env-changed={}", REPORT_VAR);
+    }
+    emit_warnings_from(&path);
+}
+
+/// Prints a `cargo:warning=` line for each annotation of the report at `path` in its warning
+/// window, as [`emit_warnings`] does for the package's report.
+pub fn emit_warnings_from(path: &Path) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+        Err(e) => {
+            println!(
+                "cargo:warning=failed to read the allow-until report `{}`: {}",
+                path.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    let report = match Report::parse(&text) {
+        Ok(report) => report,
+        Err(e) => {
+            println!(
+                "cargo:warning=the allow-until report `{}` is corrupted: {}",
+                path.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    let version = env::var("CARGO_PKG_VERSION").ok();
+    for warning in warnings(&report, version.as_deref()) {
+        println!("cargo:warning={}", warning);
+    }
+}
+
+/// The warnings about the annotations of `report` in their warning window, one line each, preceded
+/// by one about the report being stale when it was recorded at another version than `version`.
+/// Annotations with `silent = true` are left out.
+pub fn warnings(report: &Report, version: Option<&str>) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let recorded = report
+        .annotations
+        .iter()
+        .map(|a| a.condition.version.as_str())
+        .find(|recorded| version.is_some_and(|version| version != *recorded));
+    if let (Some(recorded), Some(version)) = (recorded, version) {
+        warnings.push(format!(
+            "the allow-until report was recorded at version {}, but the package is at {}, record \
+             it again with `ALLOW_UNTIL_REPORT`",
+            recorded, version
+        ));
+    }
+
+    for annotation in report.in_window().filter(|a| !a.outcome.silent) {
+        let what = match &annotation.item {
+            Some(item) => format!("{} `{}`", annotation.kind, item),
+            None => annotation.kind.clone(),
+        };
+        let mut warning = if annotation.outcome.matched {
+            format!(
+                "allow-until: {} has expired (`{}` matches {}) and is about to be removed",
+                what, annotation.condition.requirement, annotation.condition.version
+            )
+        } else {
+            format!(
+                "allow-until: {} is about to be removed, from the first version matching `{}`",
+                what, annotation.condition.requirement
+            )
+        };
+        if let Some(reason) = annotation.reason.as_deref().and_then(|r| r.lines().next()) {
+            warning.push_str(&format!(": {}", reason));
+        }
+        if annotation.line != 0 {
+            warning.push_str(&format!(" ({}:{})", annotation.file, annotation.line));
+        }
+        warnings.push(warning);
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(requirement: &str, matched: bool, window: bool, silent: bool) -> String {
+        format!(
+            r#"{{"schema_version":2,"requirement":"{}","version":"2.0.0","matched":{},"severity":"error","silent":{},"window":{},"reason":"use `Json`\nsee the docs","id":null,"tags":[],"owner":null,"issue":null,"migration":null,"kind":"struct","item":"Xml","symbols":[],"file":"src/lib.rs","line":4,"column":1,"blame":null}}"#,
+            requirement, matched, silent, window
+        )
+    }
+
+    #[test]
+    fn in_window() {
+        let report = Report::parse(
+            &[
+                entry(">=2.0", true, true, false),
+                entry(">=2.1", false, true, false),
+                entry(">=3.0", false, false, false),
+                entry(">=1.0", true, false, false),
+                entry(">=2.1", false, true, true),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            warnings(&report, Some("2.0.0")),
+            [
+                "allow-until: struct `Xml` has expired (`>=2.0` matches 2.0.0) and is about to \
+                 be removed: use `Json` (src/lib.rs:4)",
+                "allow-until: struct `Xml` is about to be removed, from the first version \
+                 matching `>=2.1`: use `Json` (src/lib.rs:4)",
+            ]
+        );
+    }
+
+    #[test]
+    fn stale() {
+        let report = Report::parse(&entry(">=3.0", false, false, false)).unwrap();
+
+        assert_eq!(
+            warnings(&report, Some("2.1.0")),
+            ["the allow-until report was recorded at version 2.0.0, but the package is at 2.1.0, \
+              record it again with `ALLOW_UNTIL_REPORT`"]
+        );
+        assert!(warnings(&report, None).is_empty());
+    }
+}
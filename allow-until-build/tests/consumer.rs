@@ -0,0 +1,119 @@
+//! A consumer crate whose build script calls `emit_warnings`, built with cargo, recording its
+//! report in one build and warning about it in the next.
+
+use std::{env, fs, path::Path, process::Command};
+
+const MANIFEST: &str = r#"[package]
+name = "consumer"
+version = "2.0.0"
+edition = "2021"
+
+[dependencies]
+allow-until = { path = "ROOT" }
+
+[build-dependencies]
+allow-until-build = { path = "ROOT/allow-until-build" }
+
+[package.metadata.allow-until]
+warn_ahead = "1 minor"
+"#;
+
+const BUILD: &str = "fn main() {\n    allow_until_build::emit_warnings();\n}\n";
+
+const LIB: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", grace = "1 minor", reason = "use `Json`")]
+pub struct Xml;
+
+#[allow_until::allow_until(version = ">=2.1")]
+pub fn connect() {}
+
+#[allow_until::allow_until(version = ">=3.0")]
+pub const TIMEOUT: u32 = 30;
+"#;
+
+fn build(dir: &Path, report: Option<&Path>) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let mut cmd = Command::new(env!("CARGO"));
+    cmd.current_dir(dir)
+        .args(["build", "--offline", "--target-dir"])
+        .arg(root.join("target").join("fixtures").join("consumer"));
+    // don't let the environment of this test run leak into the consumer
+    for (key, _) in env::vars() {
+        if key.starts_with("ALLOW_UNTIL_") || key.starts_with("CARGO_") || key == "RUSTFLAGS" {
+            cmd.env_remove(key);
+        }
+    }
+    if let Some(report) = report {
+        cmd.env("ALLOW_UNTIL_REPORT", report);
+    }
+
+    let output = cmd.output().expect("failed to run cargo");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "failed to build:\n{}", stderr);
+    stderr
+}
+
+#[test]
+fn warnings_of_the_recorded_report() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let dir = env::temp_dir().join(format!("allow-until-consumer-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        MANIFEST.replace("ROOT", &root.display().to_string()),
+    )
+    .unwrap();
+    fs::write(dir.join("build.rs"), BUILD).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), LIB).unwrap();
+    fs::copy(root.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+
+    // the build script runs before the crate is compiled, so the report isn't there yet
+    let report = dir.join("allow-until.jsonl");
+    let stderr = build(&dir, Some(&report));
+    assert!(
+        !stderr.contains("warning: consumer@2.0.0: allow-until:"),
+        "{}",
+        stderr
+    );
+    assert!(report.exists());
+
+    let stderr = build(&dir, None);
+    assert!(
+        stderr.contains(
+            "warning: consumer@2.0.0: allow-until: struct `Xml` has expired (`>=2.0` matches \
+             2.0.0) and is about to be removed: use `Json` (src/lib.rs:2)"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(
+            "warning: consumer@2.0.0: allow-until: fn `connect` is about to be removed, from the \
+             first version matching `>=2.1` (src/lib.rs:5)"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("TIMEOUT"), "{}", stderr);
+
+    // the report is stale once the version changes
+    fs::write(
+        dir.join("Cargo.toml"),
+        MANIFEST
+            .replace("ROOT", &root.display().to_string())
+            .replace("2.0.0", "2.0.1"),
+    )
+    .unwrap();
+    let stderr = build(&dir, None);
+    assert!(
+        stderr.contains(
+            "warning: consumer@2.0.1: the allow-until report was recorded at version 2.0.0, but \
+             the package is at 2.0.1"
+        ),
+        "{}",
+        stderr
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
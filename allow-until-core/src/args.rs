@@ -0,0 +1,1482 @@
+//! The arguments of annotations, parsed from `proc_macro2` tokens so that the parser also runs
+//! outside of macro expansion, as in its fuzz tests. Errors are turned into diagnostics, and the
+//! crate's defaults applied, by the caller.
+//!
+//! ```
+//! let parsed = allow_until_core::args::parse(r#"version = ">=2.0", owner = "io-team""#.parse().unwrap())
+//!     .unwrap();
+//! assert_eq!(parsed.owner.as_deref(), Some("io-team"));
+//! ```
+
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use semver::{Version, VersionReq};
+use syn::{
+    parse::Parser, punctuated::Punctuated, spanned::Spanned, Expr, ExprLit, Lit, Meta, Token,
+};
+
+use crate::{
+    changelog,
+    code::Code,
+    hash,
+    lint::Lint,
+    regex::Regex,
+    severity::{Docsrs, DoctestFence, Enforce, OnExpiry, PublicOnly, Severity, When},
+    suggest,
+    version::{self, Distance, Format, Unit, VersionFrom, VersionSource},
+};
+
+/// The arguments of annotations, in the order they are documented.
+pub const ARGS: &[&str] = &[
+    "version",
+    "reason",
+    "reason_key",
+    "id",
+    "tags",
+    "owner",
+    "issue",
+    "severity",
+    "grace",
+    "docsrs",
+    "version_source",
+    "allow_lint",
+    "changelog",
+    "public_only",
+    "silent",
+    "migration",
+    "test_guard",
+    "approved_by",
+    "deprecate_from",
+    "min_notice",
+    "superseded_by_feature",
+    "remove_with",
+    "not_before",
+    "watch",
+    "watch_hash",
+    "version_from",
+    "on_expiry",
+    "match_precision",
+    "branch",
+    "branch_pattern",
+    "require_deprecated",
+    "since",
+    "replacement",
+    "no_extend",
+    "horizon_ok",
+    "escalate_env",
+    "generic_default",
+    "doctest_ignore_from",
+    "doctest_fence",
+    "display_name",
+    "cargo",
+    "tool",
+    "tool_version",
+    "feature",
+    "cfg",
+    "doc_cfg",
+    "enforce",
+];
+
+/// The arguments of `changes_at`, in the order they are documented.
+pub const CHANGES_AT_ARGS: &[&str] = &[
+    "version",
+    "note",
+    "id",
+    "tags",
+    "owner",
+    "issue",
+    "version_source",
+];
+
+/// The arguments of `default_body_until`, in the order they are documented.
+pub const DEFAULT_BODY_UNTIL_ARGS: &[&str] = &[
+    "version",
+    "reason",
+    "id",
+    "tags",
+    "owner",
+    "issue",
+    "version_source",
+];
+
+/// The arguments of `attr_until`, in the order they are documented.
+pub const ATTR_UNTIL_ARGS: &[&str] = &[
+    "version",
+    "attr",
+    "when",
+    "id",
+    "tags",
+    "owner",
+    "issue",
+    "version_source",
+];
+
+/// Arguments that can be given more than once, each occurrence adding to the others.
+const REPEATABLE_ARGS: &[&str] = &["allow_lint"];
+
+/// Pairs of arguments that contradict each other, with the help given when both are.
+pub(crate) const EXCLUSIVE_ARGS: &[(&str, &str, &str)] = &[
+    (
+        "reason",
+        "reason_key",
+        "give the reason either inline with `reason`, or from the catalogue with `reason_key`",
+    ),
+    (
+        "silent",
+        "severity",
+        "silent annotations are never reported, remove `severity` or `silent`",
+    ),
+    (
+        "migration",
+        "version",
+        "the migration defines the requirement, remove `version` or `migration`",
+    ),
+    (
+        "migration",
+        "issue",
+        "the migration defines the issue, remove `issue` or `migration`",
+    ),
+    (
+        "migration",
+        "owner",
+        "the migration defines the owner, remove `owner` or `migration`",
+    ),
+    (
+        "version_source",
+        "version_from",
+        "the version is read from the file of `version_from`, remove `version_source` or \
+         `version_from`",
+    ),
+    (
+        "test_guard",
+        "on_expiry",
+        "expiry either fails a generated test or ignores the annotated one, remove `test_guard` \
+         or `on_expiry`",
+    ),
+    (
+        "branch",
+        "branch_pattern",
+        "give the branch either by name with `branch`, or as a pattern with `branch_pattern`",
+    ),
+    (
+        "enforce",
+        "severity",
+        "`enforce` decides the severity from the release tags of the commit, remove `severity` \
+         or `enforce`",
+    ),
+];
+
+/// "valid arguments are `version`, ... and `allow_lint`".
+pub fn valid_args() -> String {
+    valid(ARGS)
+}
+
+fn valid(args: &[&str]) -> String {
+    let (last, rest) = args.split_last().unwrap();
+    format!(
+        "valid arguments are {} and `{}`",
+        rest.iter()
+            .map(|arg| format!("`{}`", arg))
+            .collect::<Vec<_>>()
+            .join(", "),
+        last
+    )
+}
+
+/// The arguments as written, before the crate's defaults apply.
+#[derive(Default)]
+pub struct Parsed {
+    /// The requirement, with the span of its literal and the literal's value.
+    pub version: Option<(VersionReq, Span, String)>,
+    /// The message shown when the requirement matches.
+    pub reason: Option<String>,
+    /// The span of the `reason` literal.
+    pub reason_span: Option<Span>,
+    /// The key of a reason in the crate's catalogue, with the span of its literal.
+    pub reason_key: Option<(String, Span)>,
+    /// A stable identifier for the annotation.
+    pub id: Option<String>,
+    /// Tags grouping annotations, e.g. `["config"]`.
+    pub tags: Option<Vec<String>>,
+    /// Who is responsible for the removal.
+    pub owner: Option<String>,
+    /// The issue tracking the removal, with the span of its literal.
+    pub issue: Option<(String, Span)>,
+    /// How expiry is reported.
+    pub severity: Option<Severity>,
+    /// How long expiry is only a warning for.
+    pub grace: Option<Distance>,
+    /// How expiry is reported on docs.rs.
+    pub docsrs: Option<Docsrs>,
+    /// Where the current version is taken from.
+    pub version_source: Option<VersionSource>,
+    /// The lints not reported for the annotation, from every `allow_lint`.
+    pub allow_lint: Vec<Lint>,
+    /// The changelog, relative to the package root, that must mention the removal once the
+    /// requirement matches. `changelog = true` is [`changelog::DEFAULT_PATH`].
+    pub changelog: Option<String>,
+    /// What happens to expiry when the item isn't `pub`, from `public_only`.
+    pub public_only: Option<PublicOnly>,
+    /// Whether the annotation is only recorded in reports, never reported by the compiler.
+    pub silent: bool,
+    /// The name of a migration in the crate's configuration, which gives the requirement, reason,
+    /// issue and owner, with the span of its literal.
+    pub migration: Option<(String, Span)>,
+    /// Whether expiry fails a generated test rather than the build.
+    pub test_guard: bool,
+    /// Who signed off on relaxing the annotation once expired, with the span of its literal.
+    pub approved_by: Option<(String, Span)>,
+    /// The version the item was deprecated in, with the span of its literal.
+    pub deprecate_from: Option<(Version, Span)>,
+    /// How long the item must be deprecated for before its removal.
+    pub min_notice: Option<Distance>,
+    /// The feature of the package the functionality moved behind, with the span of its literal.
+    pub superseded_by_feature: Option<(String, Span)>,
+    /// The id of the annotation this one must be removed together with.
+    pub remove_with: Option<String>,
+    /// The oldest version the annotation belongs to, with the span of its literal.
+    pub not_before: Option<(Version, Span)>,
+    /// The file, relative to the package root, whose change expires the annotation, with the span
+    /// of its literal.
+    pub watch: Option<(String, Span)>,
+    /// The hash the watched file had when the annotation was written, as written by
+    /// [`hash::sha256`], with the span of its literal.
+    pub watch_hash: Option<(String, Span)>,
+    /// The file and key the current version is read from, with the span of the table.
+    pub version_from: Option<(VersionFrom, Span)>,
+    /// What happens instead of the error once the annotation expires, with the span of its
+    /// literal.
+    pub on_expiry: Option<(OnExpiry, Span)>,
+    /// The precision the version and the requirement are compared at.
+    pub match_precision: Option<Unit>,
+    /// The git branch the annotation is enforced on.
+    pub branch: Option<String>,
+    /// The pattern of the git branches the annotation is enforced on.
+    pub branch_pattern: Option<Regex>,
+    /// Whether the item must be `#[deprecated]`, overriding the crate's configuration.
+    pub require_deprecated: Option<bool>,
+    /// The version the annotation was added in, with the span of its literal.
+    pub since: Option<(Version, Span)>,
+    /// What replaces the item, such as the name of the field to use instead.
+    pub replacement: Option<String>,
+    /// Whether the requirement is evaluated as written, even with `extend_all_by` configured.
+    pub no_extend: bool,
+    /// Whether the requirement may first match further ahead than `max_horizon`.
+    pub horizon_ok: bool,
+    /// The environment variable whose being set raises the severity of the annotation.
+    pub escalate_env: Option<String>,
+    /// The generic parameter whose default is going away, and the span of its name.
+    pub generic_default: Option<(String, Span)>,
+    /// The requirement from which the examples in the documentation of the item are no longer
+    /// tested, with the span of its literal.
+    pub doctest_ignore_from: Option<(VersionReq, Span)>,
+    /// How the examples are no longer tested, with the span of its literal.
+    pub doctest_fence: Option<(DoctestFence, Span)>,
+    /// What diagnostics, reports and schedules call the item instead of its kind and name.
+    pub display_name: Option<String>,
+    /// The requirement the version of the Cargo building the crate must satisfy, with the span of
+    /// its literal.
+    pub cargo: Option<(VersionReq, Span)>,
+    /// The external tool whose version `tool_version` is a requirement on, with the span of its
+    /// literal.
+    pub tool: Option<(String, Span)>,
+    /// The requirement the version of `tool` must satisfy, with the span of its literal.
+    pub tool_version: Option<(VersionReq, Span)>,
+    /// The feature the item only exists with, with the span of its literal.
+    pub feature: Option<(String, Span)>,
+    /// The predicate of the `cfg` the item only exists under, as in `all(unix, feature = "x")`,
+    /// with the span of its literal.
+    pub cfg: Option<(TokenStream, Span)>,
+    /// Whether the item is given the `doc(cfg)` of `feature` and `cfg`, overriding the crate's
+    /// configuration, with the span of its value.
+    pub doc_cfg: Option<(bool, Span)>,
+    /// Which builds fail on expiry, overriding the crate's configuration.
+    pub enforce: Option<Enforce>,
+    /// What changes, from the `note` of `changes_at`.
+    pub note: Option<String>,
+    /// The attribute `attr_until` attaches, without its `#[]`, as its tokens were written, with
+    /// the span of the `attr` argument.
+    pub attr: Option<(TokenStream, Span)>,
+    /// When `attr_until` attaches the attribute.
+    pub when: Option<When>,
+    /// The members `allow_members_until` applies to, with the spans of their literals.
+    pub members: Option<Vec<(String, Span)>>,
+}
+
+/// An invalid argument.
+#[derive(Debug)]
+pub struct Error {
+    /// Where the error is.
+    pub span: Span,
+    /// The code of the diagnostic reporting it.
+    pub code: Code,
+    /// What is wrong.
+    pub message: String,
+    /// How to fix it.
+    pub help: Option<String>,
+    /// A note pointing elsewhere, such as at the first of duplicate arguments.
+    pub note: Option<(Span, String)>,
+}
+
+impl Error {
+    fn new(span: Span, code: Code, message: impl Into<String>) -> Error {
+        Error {
+            span,
+            code,
+            message: message.into(),
+            help: None,
+            note: None,
+        }
+    }
+
+    fn help(mut self, help: impl Into<String>) -> Error {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// Parses the `name = "value"` pairs of an annotation.
+pub fn parse(tokens: TokenStream) -> Result<Parsed, Error> {
+    parse_only(tokens, ARGS)
+}
+
+/// Parses the `name = "value"` pairs of a `changes_at` attribute, which only takes
+/// [`CHANGES_AT_ARGS`].
+pub fn parse_changes_at(tokens: TokenStream) -> Result<Parsed, Error> {
+    parse_only(tokens, CHANGES_AT_ARGS)
+}
+
+/// Parses the `name = "value"` pairs of a `default_body_until` attribute, which only takes
+/// [`DEFAULT_BODY_UNTIL_ARGS`].
+pub fn parse_default_body_until(tokens: TokenStream) -> Result<Parsed, Error> {
+    parse_only(tokens, DEFAULT_BODY_UNTIL_ARGS)
+}
+
+/// Parses the arguments of an `attr_until` attribute, which only takes [`ATTR_UNTIL_ARGS`]. The
+/// tokens of `attr`, up to the next comma outside of delimiters, are kept as written rather than
+/// parsed, so any attribute can be given.
+pub fn parse_attr_until(tokens: TokenStream) -> Result<Parsed, Error> {
+    let (rest, attr) = extract(tokens, "attr")?;
+    let attr = match attr {
+        Some((value, _, eq)) if value.is_empty() => {
+            return Err(Error::new(
+                eq,
+                Code::InvalidInput,
+                "expected an attribute after `attr =`, such as `deprecated(note = \"...\")`",
+            ))
+        }
+        Some((value, name, _)) => Some((value, name)),
+        None => None,
+    };
+
+    let mut parsed = parse_only(rest, ATTR_UNTIL_ARGS)?;
+    parsed.attr = attr;
+    Ok(parsed)
+}
+
+/// Parses the arguments of an `allow_members_until` attribute, which takes those of an annotation
+/// and the `members` of the block it applies to.
+pub fn parse_allow_members_until(tokens: TokenStream) -> Result<Parsed, Error> {
+    const HELP: &str = "list the names of the members, as in `members = [\"connect_tls\"]`";
+
+    let (rest, members) = extract(tokens, "members")?;
+    let members = match members {
+        Some((value, _, eq)) => {
+            let expr = syn::parse2::<Expr>(value)
+                .map_err(|e| Error::new(eq, Code::InvalidInput, e.to_string()).help(HELP))?;
+            let Expr::Array(array) = &expr else {
+                return Err(
+                    Error::new(expr.span(), Code::InvalidInput, "expected a list").help(HELP),
+                );
+            };
+            if array.elems.is_empty() {
+                return Err(Error::new(
+                    array.span(),
+                    Code::InvalidInput,
+                    "expected at least one member",
+                )
+                .help(HELP));
+            }
+
+            let mut members: Vec<(String, Span)> = Vec::new();
+            for elem in &array.elems {
+                let name = string_value(elem)?;
+                if let Some((_, first)) = members.iter().find(|(member, _)| *member == name) {
+                    return Err(Error {
+                        note: Some((*first, "first given here".into())),
+                        ..Error::new(
+                            elem.span(),
+                            Code::InvalidInput,
+                            format!("duplicate member `{}`", name),
+                        )
+                    });
+                }
+                members.push((name, elem.span()));
+            }
+            Some(members)
+        }
+        None => None,
+    };
+
+    let mut parsed = parse_only(rest, ARGS)?;
+    parsed.members = members;
+    Ok(parsed)
+}
+
+/// The value of an argument taken out of the others, with the spans of its name and of the `=`.
+type Extracted = (TokenStream, Span, Span);
+
+/// Takes the `name = ...` argument out of `tokens`, returning the other arguments and its value,
+/// up to the next comma outside of delimiters.
+fn extract(tokens: TokenStream, name: &str) -> Result<(TokenStream, Option<Extracted>), Error> {
+    let mut rest = Vec::new();
+    let mut found: Option<Extracted> = None;
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        let is_arg = matches!(&token, TokenTree::Ident(ident) if ident == name)
+            && matches!(tokens.peek(), Some(TokenTree::Punct(eq)) if eq.as_char() == '=');
+        if !is_arg {
+            rest.push(token);
+            continue;
+        }
+
+        let span = token.span();
+        let eq = tokens.next().unwrap().span();
+        let mut value = Vec::new();
+        for token in tokens.by_ref() {
+            if matches!(&token, TokenTree::Punct(comma) if comma.as_char() == ',') {
+                break;
+            }
+            value.push(token);
+        }
+        if let Some((_, first, _)) = found {
+            return Err(Error {
+                note: Some((first, "first given here".into())),
+                ..Error::new(
+                    span,
+                    Code::InvalidInput,
+                    format!("duplicate `{}` argument", name),
+                )
+            });
+        }
+        found = Some((value.into_iter().collect(), span, eq));
+    }
+
+    Ok((rest.into_iter().collect(), found))
+}
+
+fn parse_only(tokens: TokenStream, args: &'static [&'static str]) -> Result<Parsed, Error> {
+    let (tokens, unquoted) = quote_requirement(tokens)?;
+    let tokens = bracket_tables(tokens);
+    let metas = Punctuated::<Meta, Token![,]>::parse_terminated
+        .parse2(tokens)
+        .map_err(|e| Error::new(e.span(), Code::InvalidInput, e.to_string()).help(valid(args)))?;
+
+    let mut parsed = Parsed::default();
+    let mut seen: Vec<(&str, Span)> = Vec::new();
+
+    for meta in metas {
+        let (path, value) = match meta {
+            Meta::NameValue(nv) => (nv.path, nv.value),
+            meta => {
+                return Err(Error::new(
+                    meta.path().span(),
+                    Code::InvalidInput,
+                    "expected `=`",
+                ))
+            }
+        };
+        let Some(ident) = path.get_ident() else {
+            return Err(
+                Error::new(path.span(), Code::InvalidInput, "expected ident").help(valid(args)),
+            );
+        };
+        let span = value.span();
+
+        // the ident is only turned into a string once, the rest of the loop uses the entry of `args`
+        let ident_name = ident.to_string();
+        let Some(&name) = args.iter().find(|&&arg| arg == ident_name) else {
+            return Err(Error::new(
+                ident.span(),
+                Code::InvalidInput,
+                format!("unknown argument `{}`", ident_name),
+            )
+            .help(match suggest::closest(&ident_name, args) {
+                Some(arg) => format!("did you mean `{}`?", arg),
+                None => valid(args),
+            }));
+        };
+
+        // a messy merge leaving two `version`s shouldn't silently keep either
+        match seen.iter().find(|&&(seen, _)| seen == name) {
+            Some((_, first)) if !REPEATABLE_ARGS.contains(&name) => {
+                return Err(Error {
+                    note: Some((*first, "first given here".into())),
+                    ..Error::new(
+                        ident.span(),
+                        Code::InvalidInput,
+                        format!("duplicate `{}` argument", name),
+                    )
+                })
+            }
+            _ => seen.push((name, ident.span())),
+        }
+
+        let invalid = |e| Error::new(span, Code::InvalidInput, e);
+        match name {
+            "version" => {
+                let v = string_value(&value)?;
+                let req = version::parse_req(&v).map_err(|e| {
+                    let offset = version::error_offset(&v, &e);
+                    // the characters of an unquoted requirement aren't those of the source
+                    let at = (!unquoted).then(|| char_span(&value, offset)).flatten();
+                    Error::new(
+                        at.unwrap_or(span),
+                        Code::InvalidRequirement,
+                        format!("invalid semver requirement `{}`: {}", v, e),
+                    )
+                    .help(
+                        wildcard_help(&v)
+                            .unwrap_or("requirements look like `\">=2.0\"` or `\">=1.4, <2\"`"),
+                    )
+                })?;
+                parsed.version = Some((req, span, v));
+            }
+            "reason" => {
+                parsed.reason = Some(string_value(&value)?);
+                parsed.reason_span = Some(span);
+            }
+            "reason_key" => parsed.reason_key = Some((string_value(&value)?, span)),
+            "id" => parsed.id = Some(string_value(&value)?),
+            "tags" => parsed.tags = Some(string_list(&value)?),
+            "owner" => parsed.owner = Some(string_value(&value)?),
+            "issue" => parsed.issue = Some((string_value(&value)?, span)),
+            "severity" => {
+                parsed.severity = Some(Severity::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "enforce" => {
+                parsed.enforce = Some(Enforce::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "grace" => {
+                parsed.grace = Some(Distance::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "docsrs" => {
+                parsed.docsrs = Some(Docsrs::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "version_source" => {
+                parsed.version_source =
+                    Some(VersionSource::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "allow_lint" => {
+                for name in string_list(&value)? {
+                    parsed.allow_lint.push(Lint::parse(&name).map_err(invalid)?);
+                }
+            }
+            "changelog" => {
+                parsed.changelog = match bool_value(&value) {
+                    Some(enabled) => enabled.then(|| changelog::DEFAULT_PATH.into()),
+                    None => Some(string_value(&value)?),
+                }
+            }
+            "public_only" => {
+                parsed.public_only = match bool_value(&value) {
+                    Some(enabled) => enabled.then_some(PublicOnly::Warning),
+                    None => Some(PublicOnly::parse(&string_value(&value)?).map_err(invalid)?),
+                }
+            }
+            "silent" => {
+                parsed.silent = bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?
+            }
+            "migration" => parsed.migration = Some((string_value(&value)?, span)),
+            "test_guard" => {
+                parsed.test_guard = bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?
+            }
+            "approved_by" => parsed.approved_by = Some((string_value(&value)?, span)),
+            "deprecate_from" => {
+                let v = version::parse_lenient(&string_value(&value)?).map_err(invalid)?;
+                parsed.deprecate_from = Some((v, span));
+            }
+            "min_notice" => {
+                parsed.min_notice = Some(Distance::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "superseded_by_feature" => {
+                parsed.superseded_by_feature = Some((string_value(&value)?, span))
+            }
+            "remove_with" => parsed.remove_with = Some(string_value(&value)?),
+            "not_before" => {
+                let v = version::parse_lenient(&string_value(&value)?).map_err(invalid)?;
+                parsed.not_before = Some((v, span));
+            }
+            "watch" => parsed.watch = Some((string_value(&value)?, span)),
+            "watch_hash" => {
+                let h = hash::parse(&string_value(&value)?).map_err(invalid)?;
+                parsed.watch_hash = Some((h, span));
+            }
+            "version_from" => parsed.version_from = Some((version_from(&value)?, span)),
+            "on_expiry" => {
+                let action = OnExpiry::parse(&string_value(&value)?).map_err(invalid)?;
+                parsed.on_expiry = Some((action, span));
+            }
+            "match_precision" => {
+                parsed.match_precision =
+                    Some(Unit::parse_precision(&string_value(&value)?).map_err(invalid)?)
+            }
+            "branch" => parsed.branch = Some(string_value(&value)?),
+            "branch_pattern" => {
+                parsed.branch_pattern = Some(Regex::parse(&string_value(&value)?).map_err(invalid)?)
+            }
+            "require_deprecated" => {
+                parsed.require_deprecated = Some(bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?)
+            }
+            "since" => {
+                let v = version::parse_lenient(&string_value(&value)?).map_err(invalid)?;
+                parsed.since = Some((v, span));
+            }
+            "replacement" => parsed.replacement = Some(string_value(&value)?),
+            "no_extend" => {
+                parsed.no_extend = bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?
+            }
+            "horizon_ok" => {
+                parsed.horizon_ok = bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?
+            }
+            "escalate_env" => {
+                let var = string_value(&value)?;
+                check_env_name(&var).map_err(invalid)?;
+                parsed.escalate_env = Some(var);
+            }
+            "generic_default" => {
+                let name = string_value(&value)?;
+                let name = name.strip_prefix('\'').unwrap_or(&name);
+                if !is_ident(name) {
+                    return Err(invalid(format!(
+                        "invalid generic parameter name `{}`, expected an identifier or a \
+                         lifetime such as `'a`",
+                        name
+                    )));
+                }
+                parsed.generic_default = Some((name.into(), span));
+            }
+            "doctest_ignore_from" => {
+                let v = string_value(&value)?;
+                let req = version::parse_req(&v).map_err(|e| {
+                    Error::new(
+                        span,
+                        Code::InvalidRequirement,
+                        format!("invalid semver requirement `{}`: {}", v, e),
+                    )
+                })?;
+                parsed.doctest_ignore_from = Some((req, span));
+            }
+            "doctest_fence" => {
+                let fence = DoctestFence::parse(&string_value(&value)?).map_err(invalid)?;
+                parsed.doctest_fence = Some((fence, span));
+            }
+            "display_name" => {
+                let name = string_value(&value)?;
+                if name.trim().is_empty() {
+                    return Err(invalid("`display_name` can't be empty".into()));
+                }
+                parsed.display_name = Some(name);
+            }
+            "cargo" => {
+                let v = string_value(&value)?;
+                let req = version::parse_req(&v).map_err(|e| {
+                    Error::new(
+                        span,
+                        Code::InvalidRequirement,
+                        format!("invalid semver requirement `{}`: {}", v, e),
+                    )
+                })?;
+                parsed.cargo = Some((req, span));
+            }
+            "tool" => {
+                let name = string_value(&value)?;
+                if name.is_empty()
+                    || name.contains(|c: char| c.is_whitespace() || c == '/' || c == '\\')
+                {
+                    return Err(invalid(format!(
+                        "invalid tool name `{}`, expected the name of a binary on `PATH` such as \
+                         `protoc`",
+                        name
+                    )));
+                }
+                parsed.tool = Some((name, span));
+            }
+            "tool_version" => {
+                let v = string_value(&value)?;
+                let req = version::parse_req(&v).map_err(|e| {
+                    Error::new(
+                        span,
+                        Code::InvalidRequirement,
+                        format!("invalid semver requirement `{}`: {}", v, e),
+                    )
+                })?;
+                parsed.tool_version = Some((req, span));
+            }
+            "feature" => {
+                let name = string_value(&value)?;
+                if name.is_empty() {
+                    return Err(invalid("`feature` can't be empty".into()));
+                }
+                parsed.feature = Some((name, span));
+            }
+            "cfg" => {
+                let predicate = string_value(&value)?;
+                let tokens = syn::parse_str::<Meta>(&predicate).map_err(|_| {
+                    invalid(format!(
+                        "invalid `cfg` predicate `{}`, expected one such as `unix` or \
+                         `all(unix, feature = \"legacy\")`",
+                        predicate
+                    ))
+                })?;
+                parsed.cfg = Some((tokens.to_token_stream(), span));
+            }
+            "doc_cfg" => {
+                let enabled = bool_value(&value).ok_or_else(|| {
+                    Error::new(span, Code::InvalidInput, "expected `true` or `false`")
+                })?;
+                parsed.doc_cfg = Some((enabled, span));
+            }
+            "note" => parsed.note = Some(string_value(&value)?),
+            "when" => parsed.when = Some(When::parse(&string_value(&value)?).map_err(invalid)?),
+            _ => unreachable!("`{}` is in `ARGS` but not parsed", name),
+        }
+    }
+
+    for &(a, b, help) in EXCLUSIVE_ARGS {
+        // `silent = false` and `test_guard = false` don't contradict anything
+        if (a == "silent" && !parsed.silent) || (a == "test_guard" && !parsed.test_guard) {
+            continue;
+        }
+        // the first of the two is noted, whichever order they're in
+        if let [(first, first_span), (second, second_span)] = seen
+            .iter()
+            .filter(|&&(name, _)| name == a || name == b)
+            .collect::<Vec<_>>()[..]
+        {
+            return Err(Error {
+                note: Some((*first_span, format!("`{}` given here", first))),
+                ..Error::new(
+                    *second_span,
+                    Code::InvalidInput,
+                    format!("`{}` can't be combined with `{}`", second, first),
+                )
+                .help(help)
+            });
+        }
+    }
+
+    // only an explicit error contradicts the test, which replaces it
+    for (set, arg, test) in [
+        (parsed.test_guard, "test_guard", "generated"),
+        (parsed.on_expiry.is_some(), "on_expiry", "ignored"),
+    ] {
+        if set && parsed.severity == Some(Severity::Error) {
+            let span = |arg| seen.iter().find(|&&(name, _)| name == arg).unwrap().1;
+            return Err(Error {
+                note: Some((span("severity"), "`severity = \"error\"` given here".into())),
+                ..Error::new(
+                    span(arg),
+                    Code::InvalidInput,
+                    format!("`{}` can't be combined with `severity = \"error\"`", arg),
+                )
+                .help(format!(
+                    "the {} test replaces the error, remove `severity` or `{}`",
+                    test, arg
+                ))
+            });
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// The help for the wildcards of `req` that can't be desugared, if any.
+fn wildcard_help(req: &str) -> Option<&'static str> {
+    let wildcard = |part: &str| matches!(part, "*" | "x" | "X");
+    for comparator in req.split(',').map(str::trim) {
+        let version = comparator.trim_start_matches(['>', '<', '=', '^', '~', ' ']);
+        let parts: Vec<_> = version.split('.').collect();
+        let strict = comparator
+            .strip_prefix(['>', '<'])
+            .is_some_and(|rest| !rest.starts_with('='));
+        if strict && parts.iter().all(|part| wildcard(part)) {
+            return Some("no version is above or below every version, write `*` for all of them");
+        }
+        if parts
+            .iter()
+            .skip_while(|part| !wildcard(part))
+            .any(|part| !wildcard(part))
+        {
+            return Some(
+                "only wildcards can follow a wildcard, as in `>=1.x` for every version from 1.0.0, \
+                 or give the numbers, as in `>=1.0.2`",
+            );
+        }
+    }
+    None
+}
+
+/// Replaces an unquoted requirement, as in `version = >=1.0, <2.0`, with the string literal it
+/// stands for, spanning the tokens it was collected from, and tells whether there was one. The value
+/// extends up to the next comma that is followed by another `name =` argument, so the commas
+/// between comparators are part of it. Only values starting with an operator, such as `>=` or `*`,
+/// are unquoted requirements: others are left as they are.
+/// Turns the braced tables given to arguments, as in `version_from = { file = "..." }`, into
+/// brackets, which `syn` parses as an array of assignments rather than as a block.
+fn bracket_tables(tokens: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for i in 1..tokens.len() {
+        let after_eq = matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == '=');
+        if let TokenTree::Group(group) = &tokens[i] {
+            if after_eq && group.delimiter() == Delimiter::Brace {
+                let mut bracketed = Group::new(Delimiter::Bracket, group.stream());
+                bracketed.set_span(group.span());
+                tokens[i] = bracketed.into();
+            }
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+/// The `{ file = "...", key = "..." }` table of `version_from`.
+fn version_from(expr: &Expr) -> Result<VersionFrom, Error> {
+    const HELP: &str = "read the version with `version_from = { file = \"config.toml\", key = \
+                        \"package.version\" }`";
+
+    let Expr::Array(table) = expr else {
+        return Err(Error::new(expr.span(), Code::InvalidInput, "expected a table").help(HELP));
+    };
+    let (mut file, mut key) = (None, None);
+    for entry in &table.elems {
+        let Expr::Assign(assign) = entry else {
+            return Err(Error::new(
+                entry.span(),
+                Code::InvalidInput,
+                "expected `name = \"value\"`",
+            )
+            .help(HELP));
+        };
+        let slot = match &*assign.left {
+            Expr::Path(p) if p.path.is_ident("file") => &mut file,
+            Expr::Path(p) if p.path.is_ident("key") => &mut key,
+            left => {
+                return Err(Error::new(
+                    left.span(),
+                    Code::InvalidInput,
+                    "unknown key, `version_from` takes `file` and `key`",
+                ))
+            }
+        };
+        if slot.is_some() {
+            return Err(Error::new(
+                assign.left.span(),
+                Code::InvalidInput,
+                "duplicate key in `version_from`",
+            ));
+        }
+        *slot = Some((string_value(&assign.right)?, assign.right.span()));
+    }
+
+    match (file, key) {
+        (Some((file, file_span)), Some((key, key_span))) => {
+            let format =
+                Format::of(&file).map_err(|e| Error::new(file_span, Code::InvalidInput, e))?;
+            version::check_key(&key).map_err(|e| Error::new(key_span, Code::InvalidInput, e))?;
+            Ok(VersionFrom { file, key, format })
+        }
+        (None, _) => Err(Error::new(
+            expr.span(),
+            Code::InvalidInput,
+            "`version_from` is missing its `file`",
+        )
+        .help(HELP)),
+        (_, None) => Err(Error::new(
+            expr.span(),
+            Code::InvalidInput,
+            "`version_from` is missing its `key`",
+        )
+        .help(HELP)),
+    }
+}
+
+fn quote_requirement(tokens: TokenStream) -> Result<(TokenStream, bool), Error> {
+    let is_punct =
+        |tok: Option<&TokenTree>, c| matches!(tok, Some(TokenTree::Punct(p)) if p.as_char() == c);
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut quoted = Vec::with_capacity(tokens.len());
+    let mut unquoted = false;
+    let mut arg_start = true;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let is_version = arg_start
+            && matches!(&tokens[i], TokenTree::Ident(ident) if ident == "version")
+            && is_punct(tokens.get(i + 1), '=');
+        if !is_version {
+            arg_start = is_punct(tokens.get(i), ',');
+            quoted.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+        arg_start = false;
+        quoted.extend_from_slice(&tokens[i..i + 2]);
+        let eq = tokens[i + 1].span();
+        i += 2;
+
+        let mut end = i;
+        while end < tokens.len() {
+            let next_arg = matches!(tokens.get(end + 1), Some(TokenTree::Ident(_)))
+                && is_punct(tokens.get(end + 2), '=');
+            if is_punct(tokens.get(end), ',') && (next_arg || end + 1 == tokens.len()) {
+                break;
+            }
+            end += 1;
+        }
+        let value = &tokens[i..end];
+
+        match value.first() {
+            None => {
+                return Err(
+                    Error::new(eq, Code::InvalidInput, "expected a requirement after `=`").help(
+                        "write the requirement, e.g. `version = \">=2.0\"` or `version = >=2.0`",
+                    ),
+                )
+            }
+            Some(TokenTree::Punct(_)) => (),
+            Some(_) => continue,
+        }
+
+        let mut text = String::new();
+        for tok in value {
+            match tok {
+                TokenTree::Punct(p) if p.as_char() == ',' => text.push_str(", "),
+                TokenTree::Punct(p) => text.push(p.as_char()),
+                TokenTree::Ident(ident) => text.push_str(&ident.to_string()),
+                // only numbers are parts of requirements, the others are stray literals
+                TokenTree::Literal(lit)
+                    if lit.to_string().starts_with(|c: char| c.is_ascii_digit()) =>
+                {
+                    text.push_str(&lit.to_string())
+                }
+                tok => {
+                    return Err(Error::new(
+                        tok.span(),
+                        Code::InvalidInput,
+                        format!("unexpected `{}` in an unquoted requirement", tok),
+                    )
+                    .help(
+                        "requirements are made of comparators like `>=1.4, <2`, quote the \
+                           requirement to use anything else",
+                    ))
+                }
+            }
+        }
+
+        let first = value[0].span();
+        let span = first.join(value[value.len() - 1].span()).unwrap_or(first);
+        let mut literal = Literal::string(&text);
+        literal.set_span(span);
+        quoted.push(literal.into());
+        unquoted = true;
+        i = end;
+    }
+
+    Ok((quoted.into_iter().collect(), unquoted))
+}
+
+/// Checks that `name` is a plausible environment variable name, such as `RELEASE_CUT`: ASCII
+/// letters, digits and underscores, not starting with a digit.
+fn check_env_name(name: &str) -> Result<(), String> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "invalid environment variable name `{}`, expected letters, digits and underscores, \
+             not starting with a digit",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `name` is an identifier, such as the name of a generic parameter.
+fn is_ident(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && name != "_"
+}
+
+/// The value of `true` or `false`, for arguments that take a boolean or a string.
+fn bool_value(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(b), ..
+        }) => Some(b.value),
+        _ => None,
+    }
+}
+
+fn string_value(expr: &Expr) -> Result<String, Error> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Ok(s.value()),
+        Expr::Lit(ExprLit {
+            lit: lit @ (Lit::ByteStr(_) | Lit::CStr(_)),
+            ..
+        }) => Err(Error::new(
+            lit.span(),
+            Code::InvalidInput,
+            "expected string literal, found a byte string",
+        )
+        .help("remove the `b` or `c` prefix")),
+        Expr::Lit(ExprLit {
+            lit: lit @ (Lit::Int(_) | Lit::Float(_)),
+            ..
+        }) => {
+            let number = match lit {
+                Lit::Int(i) => i.token().to_string(),
+                Lit::Float(f) => f.token().to_string(),
+                _ => unreachable!(),
+            };
+            Err(Error::new(
+                lit.span(),
+                Code::InvalidInput,
+                "expected string literal, found a number",
+            )
+            .help(format!("write it as a string, `\"{}\"`", number)))
+        }
+        Expr::Lit(ExprLit { lit, .. }) => {
+            let found = match lit {
+                Lit::Char(_) => ", found a character",
+                Lit::Byte(_) => ", found a byte",
+                Lit::Bool(_) => ", found a boolean",
+                _ => "",
+            };
+            Err(Error::new(
+                lit.span(),
+                Code::InvalidInput,
+                format!("expected string literal{}", found),
+            ))
+        }
+        e => Err(Error::new(e.span(), Code::InvalidInput, "expected literal")),
+    }
+}
+
+/// The span of the character at byte `offset` of the value of a string literal, or of its closing
+/// quote for the end of the value. Only known on nightly, and when the value is written verbatim,
+/// without escapes or line endings that moves characters around.
+fn char_span(expr: &Expr, offset: usize) -> Option<Span> {
+    let Expr::Lit(ExprLit {
+        lit: Lit::Str(s), ..
+    }) = expr
+    else {
+        return None;
+    };
+    let value = s.value();
+    let repr = s.token().to_string();
+    let start = repr.find('"')? + 1;
+    if repr.get(start..start + value.len()) != Some(&value[..]) {
+        return None;
+    }
+    let len = repr[start + offset..].chars().next()?.len_utf8();
+
+    s.token().subspan(start + offset..start + offset + len)
+}
+
+/// Parses either a single string or a `["a", "b"]` list of strings.
+fn string_list(expr: &Expr) -> Result<Vec<String>, Error> {
+    match expr {
+        Expr::Array(array) => array.elems.iter().map(string_value).collect(),
+        e => string_value(e).map(|s| vec![s]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Parsed, Error> {
+        parse(args.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_outside_of_macros() {
+        let parsed =
+            parse_str(r#"version = ">=1.0", tags = ["a", "b"], allow_lint = "comparator_style""#)
+                .unwrap();
+        assert_eq!(parsed.version.unwrap().2, ">=1.0");
+        assert_eq!(parsed.tags.unwrap(), ["a", "b"]);
+        assert!(parsed.allow_lint.contains(&Lint::ComparatorStyle));
+
+        let Err(error) = parse_str(r#"verson = ">=1.0""#) else {
+            panic!("`verson` was accepted");
+        };
+        assert_eq!(error.code, Code::InvalidInput);
+        assert_eq!(error.help.as_deref(), Some("did you mean `version`?"));
+    }
+
+    #[test]
+    fn changes_at_takes_its_own_arguments() {
+        let parsed =
+            parse_changes_at(r#"version = ">=2.0", note = "returns `Err`""#.parse().unwrap())
+                .unwrap();
+        assert_eq!(parsed.note.as_deref(), Some("returns `Err`"));
+
+        let Err(error) =
+            parse_changes_at(r#"version = ">=2.0", severity = "error""#.parse().unwrap())
+        else {
+            panic!("`severity` was accepted by `changes_at`");
+        };
+        assert_eq!(error.message, "unknown argument `severity`");
+        assert!(parse_str(r#"version = ">=2.0", note = "returns `Err`""#).is_err());
+    }
+
+    #[test]
+    fn reason_and_reason_key_are_exclusive() {
+        let parsed = parse_str(r#"version = ">=1.0", reason_key = "xml-removal""#).unwrap();
+        assert_eq!(parsed.reason_key.unwrap().0, "xml-removal");
+
+        let Err(error) =
+            parse_str(r#"reason_key = "xml-removal", version = ">=1.0", reason = "no""#)
+        else {
+            panic!("`reason` and `reason_key` were accepted together");
+        };
+        assert_eq!(
+            error.message,
+            "`reason` can't be combined with `reason_key`"
+        );
+        assert_eq!(error.note.unwrap().1, "`reason_key` given here");
+    }
+
+    #[test]
+    fn silent_and_severity_are_exclusive() {
+        assert!(
+            parse_str(r#"version = ">=1.0", silent = true"#)
+                .unwrap()
+                .silent
+        );
+        assert!(parse_str(r#"version = ">=1.0", silent = false, severity = "warning""#).is_ok());
+
+        let Err(error) = parse_str(r#"version = ">=1.0", silent = true, severity = "warning""#)
+        else {
+            panic!("`silent` and `severity` were accepted together");
+        };
+        assert_eq!(error.message, "`severity` can't be combined with `silent`");
+    }
+
+    #[test]
+    fn attr_until_keeps_the_attribute_verbatim() {
+        let parse = |args: &str| parse_attr_until(args.parse().unwrap());
+
+        let parsed = parse(
+            r#"version = ">=2.0", attr = deprecated(note = "going away, use `b`"), when = "unmatched""#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.attr.unwrap().0.to_string(),
+            r#"deprecated (note = "going away, use `b`")"#
+        );
+        assert_eq!(parsed.when, Some(When::Unmatched));
+
+        let parsed = parse(r#"attr = doc = "hidden until 2.0", version = ">=2.0""#).unwrap();
+        assert_eq!(
+            parsed.attr.unwrap().0.to_string(),
+            r#"doc = "hidden until 2.0""#
+        );
+        assert_eq!(parsed.version.unwrap().2, ">=2.0");
+
+        let error = |args: &str| parse(args).err().unwrap().message;
+        assert_eq!(
+            error(r#"version = ">=2.0", attr = "#),
+            "expected an attribute after `attr =`, such as `deprecated(note = \"...\")`"
+        );
+        assert_eq!(
+            error(r#"attr = must_use, attr = inline"#),
+            "duplicate `attr` argument"
+        );
+        assert_eq!(
+            error(r#"version = ">=2.0", when = "later""#),
+            "invalid `when` value `later`, expected `\"matched\"` or `\"unmatched\"`"
+        );
+        assert_eq!(
+            error(r#"version = ">=2.0", reason = "x""#),
+            "unknown argument `reason`"
+        );
+    }
+
+    #[test]
+    fn allow_members_until_lists_members() {
+        let parse = |args: &str| parse_allow_members_until(args.parse().unwrap());
+
+        let parsed =
+            parse(r#"version = ">=2.0", members = ["connect_tls", "TIMEOUT"], id = "tls""#)
+                .unwrap();
+        let members = parsed.members.unwrap();
+        let names: Vec<_> = members.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["connect_tls", "TIMEOUT"]);
+        assert_eq!(parsed.id.as_deref(), Some("tls"));
+
+        let error = |args: &str| parse(args).err().unwrap().message;
+        assert_eq!(error(r#"members = "connect_tls""#), "expected a list");
+        assert_eq!(error(r#"members = []"#), "expected at least one member");
+        assert_eq!(
+            error(r#"members = ["connect_tls", "connect_tls"]"#),
+            "duplicate member `connect_tls`"
+        );
+        assert_eq!(
+            error(r#"members = ["a"], members = ["b"]"#),
+            "duplicate `members` argument"
+        );
+        assert_eq!(
+            parse_str(r#"version = ">=2.0", members = ["a"]"#)
+                .err()
+                .unwrap()
+                .message,
+            "unknown argument `members`"
+        );
+    }
+
+    #[test]
+    fn test_guard_contradicts_error_severity() {
+        assert!(
+            parse_str(r#"version = ">=1.0", test_guard = true, severity = "warning""#)
+                .unwrap()
+                .test_guard
+        );
+        assert!(parse_str(r#"version = ">=1.0", test_guard = false, severity = "error""#).is_ok());
+
+        let Err(error) = parse_str(r#"severity = "error", version = ">=1.0", test_guard = true"#)
+        else {
+            panic!("`test_guard` and `severity = \"error\"` were accepted together");
+        };
+        assert_eq!(
+            error.message,
+            "`test_guard` can't be combined with `severity = \"error\"`"
+        );
+    }
+
+    #[test]
+    fn migration_replaces_version_issue_and_owner() {
+        let parsed = parse_str(r#"migration = "drop-xml", reason = "use `Json`""#).unwrap();
+        assert_eq!(parsed.migration.unwrap().0, "drop-xml");
+        assert!(parsed.version.is_none());
+
+        for arg in [
+            r#"version = ">=1.0""#,
+            r#"issue = "CORE-1""#,
+            r#"owner = "core""#,
+        ] {
+            let Err(error) = parse_str(&format!(r#"migration = "drop-xml", {}"#, arg)) else {
+                panic!("`{}` was accepted with `migration`", arg);
+            };
+            assert!(error
+                .message
+                .ends_with("can't be combined with `migration`"));
+        }
+    }
+
+    #[test]
+    fn unquoted_requirements() {
+        for (unquoted, text) in [
+            (">=1.0", ">=1.0"),
+            ("^0.4", "^0.4"),
+            (">=1.0, <2.0", ">=1.0, <2.0"),
+            (">= 1.0.0-rc.1 , < 2", ">=1.0.0-rc.1, <2"),
+            ("*", "*"),
+        ] {
+            let parsed = parse_str(&format!(
+                "version = {}, reason = \"use `Json`\", id = \"xml\"",
+                unquoted
+            ))
+            .unwrap();
+            assert_eq!(parsed.version.unwrap().2, text, "{}", unquoted);
+            assert_eq!(parsed.reason.as_deref(), Some("use `Json`"));
+            assert_eq!(parsed.id.as_deref(), Some("xml"));
+
+            // last, with and without a trailing comma
+            let parsed = parse_str(&format!("id = \"xml\", version = {},", unquoted)).unwrap();
+            assert_eq!(parsed.version.unwrap().2, text);
+        }
+
+        // quoted requirements and other arguments are left alone
+        assert_eq!(
+            parse_str(r#"reason = "version = >=1.0", version = ">=2.0""#)
+                .unwrap()
+                .version
+                .unwrap()
+                .2,
+            ">=2.0"
+        );
+        assert_eq!(
+            parse_str("version = 2.0").err().unwrap().message,
+            "expected string literal, found a number"
+        );
+    }
+
+    #[test]
+    fn ambiguous_wildcards() {
+        let help = |req: &str| {
+            parse_str(&format!("version = \"{}\"", req))
+                .err()
+                .and_then(|e| e.help)
+        };
+
+        assert!(parse_str(r#"version = ">= 1.0.x""#).is_ok());
+        assert!(help(">=1.x.0")
+            .unwrap()
+            .starts_with("only wildcards can follow a wildcard"));
+        assert!(help("<*")
+            .unwrap()
+            .starts_with("no version is above or below"));
+        assert_eq!(
+            help(">=1.0, <soon").as_deref(),
+            Some("requirements look like `\">=2.0\"` or `\">=1.4, <2\"`")
+        );
+    }
+
+    #[test]
+    fn version_from_tables() {
+        let parsed = parse_str(
+            r#"version = ">=2.0", version_from = { file = "config.toml", key = "tool.api.version" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.version_from.unwrap().0,
+            VersionFrom {
+                file: "config.toml".into(),
+                key: "tool.api.version".into(),
+                format: Format::Toml,
+            }
+        );
+
+        let message = |args: &str| parse_str(args).err().map(|e| e.message);
+        assert_eq!(
+            message(r#"version_from = { file = "api.yaml", key = "version" }"#).as_deref(),
+            Some(
+                "`api.yaml` is neither a `.toml` nor a `.json` file, the formats `version_from` \
+                 reads"
+            )
+        );
+        assert_eq!(
+            message(r#"version_from = { file = "api.json", key = "api..version" }"#).as_deref(),
+            Some("invalid key `api..version`, expected names separated by dots, such as `package.version`")
+        );
+        assert_eq!(
+            message(r#"version_from = { file = "api.json" }"#).as_deref(),
+            Some("`version_from` is missing its `key`")
+        );
+        assert_eq!(
+            message(r#"version_from = "api.json""#).as_deref(),
+            Some("expected a table")
+        );
+    }
+
+    #[test]
+    fn invalid_unquoted_requirements() {
+        for (args, code, message) in [
+            (
+                "version = , id = \"x\"",
+                Code::InvalidInput,
+                "expected a requirement after `=`",
+            ),
+            (
+                "version =",
+                Code::InvalidInput,
+                "expected a requirement after `=`",
+            ),
+            (
+                "version = >=1.0 (2)",
+                Code::InvalidInput,
+                "unexpected `(2)` in an unquoted requirement",
+            ),
+            (
+                "version = >= \"1.0\"",
+                Code::InvalidInput,
+                "unexpected `\"1.0\"` in an unquoted requirement",
+            ),
+            (
+                "version = >=1.0 <2",
+                Code::InvalidRequirement,
+                "invalid semver requirement `>=1.0<2`",
+            ),
+            (
+                "version = >=1.0, id",
+                Code::InvalidRequirement,
+                "invalid semver requirement `>=1.0, id`",
+            ),
+        ] {
+            let error = parse_str(args).err().unwrap();
+            assert_eq!(error.code, code, "{}", args);
+            assert!(
+                error.message.starts_with(message),
+                "{}: {}",
+                args,
+                error.message
+            );
+        }
+    }
+
+    /// Parses `args`, if they are valid tokens, ignoring the result: only panics matter.
+    fn fuzz(args: &str) {
+        if let Ok(tokens) = args.parse() {
+            let _ = parse(tokens);
+        }
+    }
+
+    /// Pieces of arguments, shuffled into well-formed and malformed argument lists alike.
+    fn fragment() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::sample::select(ARGS).prop_map(String::from),
+            "[a-z_]{1,12}",
+            Just("=".to_string()),
+            Just(",".to_string()),
+            Just("[".to_string()),
+            Just("]".to_string()),
+            Just("(".to_string()),
+            Just(")".to_string()),
+            Just("::".to_string()),
+            Just("-".to_string()),
+            Just("true".to_string()),
+            "[0-9]{1,4}(\\.[0-9]{1,3}){0,3}",
+            any::<char>().prop_map(|c| format!("{:?}", c)),
+            any::<String>().prop_map(|s| format!("{:?}", s)),
+            "[<>=^~*., 0-9a-z+-]{0,16}".prop_map(|s| format!("{:?}", s)),
+            "[<>=^~*., 0-9a-z\"+-]{0,16}".prop_map(|s| format!("r#\"{}\"#", s)),
+            "[<>=.0-9]{0,8}".prop_map(|s| format!("b\"{}\"", s)),
+            "[<>=.0-9\r\n€]{0,8}".prop_map(|s| format!("\"{}\"", s)),
+            "[<>=^~*., 0-9a-z+-]{0,16}",
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(fragments in prop::collection::vec(fragment(), 0..12)) {
+            fuzz(&fragments.join(" "));
+        }
+
+        #[test]
+        fn never_panics_on_requirements(req in any::<String>()) {
+            fuzz(&format!("version = {:?}", req));
+        }
+
+        #[test]
+        fn never_panics_on_raw_requirements(req in "[^\"]*") {
+            fuzz(&format!("version = r#\"{}\"#", req));
+        }
+    }
+}
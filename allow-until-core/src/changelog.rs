@@ -0,0 +1,94 @@
+//! Finding an annotation's removal in the Unreleased section of a changelog, for annotations with
+//! `changelog = true`.
+//!
+//! Changelogs are read leniently: the Unreleased section is the first Markdown heading whose title
+//! contains "unreleased", in any case, up to the next heading of the same or a higher level, as in
+//! the [Keep a Changelog](https://keepachangelog.com) format. Anything else in the file is ignored.
+
+/// The changelog checked by `changelog = true`, relative to the package root.
+pub const DEFAULT_PATH: &str = "CHANGELOG.md";
+
+/// The level and title of an ATX heading such as `## [Unreleased]`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim();
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = &line[level..];
+
+    (level > 0 && (title.is_empty() || title.starts_with([' ', '\t']))).then_some((level, title))
+}
+
+/// The Unreleased section of `changelog`, without its heading, or `None` if it has none.
+///
+/// ```
+/// let changelog = "# Changelog\n\n## [Unreleased]\n- Removed `Xml`\n\n## [1.0.0]\n- Added `Xml`\n";
+/// assert_eq!(
+///     allow_until_core::changelog::unreleased(changelog),
+///     Some("- Removed `Xml`\n\n")
+/// );
+/// ```
+pub fn unreleased(changelog: &str) -> Option<&str> {
+    let mut lines = changelog.split_inclusive('\n');
+    let mut start = 0;
+
+    let level = loop {
+        let line = lines.next()?;
+        start += line.len();
+        if let Some((level, title)) = heading(line) {
+            if title.to_lowercase().contains("unreleased") {
+                break level;
+            }
+        }
+    };
+
+    let mut end = start;
+    for line in lines {
+        if heading(line).is_some_and(|(l, _)| l <= level) {
+            break;
+        }
+        end += line.len();
+    }
+
+    Some(&changelog[start..end])
+}
+
+/// Whether `name` appears in `text` as a whole word, rather than as part of a longer identifier.
+pub fn mentions(text: &str, name: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    !name.is_empty()
+        && text.match_indices(name).any(|(i, _)| {
+            !text[..i].ends_with(is_word) && !text[i + name.len()..].starts_with(is_word)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreleased_section() {
+        let changelog = "# Changelog\n\
+                         ## Unreleased\n\
+                         ### Removed\n\
+                         - `Xml`\n\
+                         ## 1.0.0 - 2024-01-01\n\
+                         - `Json`\n";
+        assert_eq!(unreleased(changelog), Some("### Removed\n- `Xml`\n"));
+
+        assert_eq!(unreleased("## [unreleased]"), Some(""));
+        assert_eq!(unreleased("# Changelog\n## 1.0.0\n- `Xml`\n"), None);
+        // not headings
+        assert_eq!(unreleased("#Unreleased\n- `Xml`"), None);
+        assert_eq!(unreleased("- Unreleased changes\n"), None);
+    }
+
+    #[test]
+    fn whole_words() {
+        assert!(mentions("- Removed `Xml`.", "Xml"));
+        assert!(mentions("- Removed Options::legacy", "Options::legacy"));
+        assert!(mentions("- drop-xml-config", "drop-xml-config"));
+        assert!(!mentions("- Removed `XmlReader`", "Xml"));
+        assert!(!mentions("- Removed `read_xml`", "xml"));
+        assert!(!mentions("- anything", ""));
+    }
+}
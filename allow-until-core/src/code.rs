@@ -0,0 +1,604 @@
+//! Stable codes identifying each kind of diagnostic, so tools scraping build logs don't depend on
+//! the wording of messages. Once assigned, a code is never reused for another kind of diagnostic:
+//! new kinds get the next free number, and retired ones keep their entry.
+
+/// The code of a kind of diagnostic, such as `AU0001` for [`Code::Expired`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Code {
+    /// An annotation has expired.
+    Expired,
+    /// The `version` argument is not a valid requirement.
+    InvalidRequirement,
+    /// The current version could not be determined.
+    NoVersion,
+    /// The version source is unavailable, so `CARGO_PKG_VERSION` is used.
+    VersionFallback,
+    /// The arguments or input of a macro are malformed.
+    InvalidInput,
+    /// An annotation has no `version` argument.
+    MissingVersion,
+    /// An annotation lacks arguments the crate requires.
+    MissingRequired,
+    /// The configuration in `Cargo.toml` is invalid.
+    InvalidConfig,
+    /// An environment variable has an invalid value.
+    InvalidEnv,
+    /// Expired annotations are downgraded by `ALLOW_UNTIL_DOWNGRADE`.
+    Downgraded,
+    /// An annotation would expire at a simulated version.
+    Simulated,
+    /// The crate has more annotations than `ALLOW_UNTIL_MAX` allows.
+    OverBudget,
+    /// An annotation is not in the baseline.
+    NotInBaseline,
+    /// A version matching the requirement has already been published.
+    Published,
+    /// The registry index could not be read.
+    IndexUnavailable,
+    /// Expired annotations were suppressed by `ALLOW_UNTIL_SUPPRESS`.
+    Suppressed,
+    /// `ALLOW_UNTIL_SUPPRESS` lists ids that match no annotation.
+    StaleSuppression,
+    /// The overview reported by `allow_until_summary!`.
+    Summary,
+    /// How an annotation was evaluated.
+    Trace,
+    /// A file could not be read or written.
+    FileAccess,
+    /// The macro panicked.
+    Internal,
+    /// The `version` argument matches every version.
+    Unbounded,
+    /// An annotation can never fire.
+    Dead,
+    /// The `version` argument uses a bare version, `^` or `~`.
+    ComparatorStyle,
+    /// An annotation expires within the `warn_ahead` distance.
+    ExpiringSoon,
+    /// The changelog doesn't mention the removal of an expired annotation.
+    NotInChangelog,
+    /// An annotation that `ALLOW_UNTIL_EXPECTED` lists has not expired.
+    NotExpired,
+    /// The issue an annotation links to doesn't exist.
+    IssueNotFound,
+    /// Two annotations declare the same id.
+    DuplicateId,
+    /// A `renamed_until` requirement matches, so the old name is no longer generated.
+    RenameExpired,
+    /// More annotations expired than the limit of errors reported in full.
+    ErrorsOmitted,
+    /// An expired annotation is relaxed without the sign-off of an approver.
+    Unapproved,
+    /// The status of an annotation, noted on every build with `always_note`.
+    Allowance,
+    /// A source outside the source tree is not consulted in the offline mode.
+    Offline,
+    /// An expired item was not deprecated for the `min_notice` before its removal.
+    ShortNotice,
+    /// Annotations linked with `remove_with` aren't removed together.
+    LinkMismatch,
+    /// The version is before the `not_before` of an annotation, which was likely backported.
+    WrongBranch,
+    /// The behavior of an item changes at a version that matches, as noted with `changes_at`.
+    BehaviorChange,
+    /// A `reason` is too short or contains a denied pattern.
+    PoorReason,
+    /// A file watched with `watch` no longer has its `watch_hash`.
+    WatchedFileChanged,
+    /// The current git branch is unknown, so `branch` and `branch_pattern` are treated as
+    /// matching.
+    UnknownBranch,
+    /// An annotated item isn't `#[deprecated]`, with `require_deprecated`.
+    NotDeprecated,
+    /// A trait method still has the default body `default_body_until` removes.
+    DefaultBody,
+    /// An annotation has been allowed for longer than `max_age`, or has no `since` to tell.
+    Stale,
+    /// Two annotations without an `id` have the same fingerprint.
+    FingerprintCollision,
+    /// An annotation's requirement first matches further ahead than `max_horizon`.
+    DistantDeadline,
+    /// The version of the Cargo building the crate is unknown, so `cargo` is treated as not
+    /// satisfied.
+    UnknownCargo,
+    /// The version of the tool of `tool` is unknown, so `tool_version` is treated as not
+    /// satisfied.
+    UnknownTool,
+    /// More annotations are pending for a milestone than `max_pending!` allows.
+    PendingOverBudget,
+    /// An expired annotation whose item `ALLOW_UNTIL_STRIP` can't remove.
+    StripUnsupported,
+}
+
+impl Code {
+    /// Every code, in numeric order.
+    pub const ALL: &'static [Code] = &[
+        Code::Expired,
+        Code::InvalidRequirement,
+        Code::NoVersion,
+        Code::VersionFallback,
+        Code::InvalidInput,
+        Code::MissingVersion,
+        Code::MissingRequired,
+        Code::InvalidConfig,
+        Code::InvalidEnv,
+        Code::Downgraded,
+        Code::Simulated,
+        Code::OverBudget,
+        Code::NotInBaseline,
+        Code::Published,
+        Code::IndexUnavailable,
+        Code::Suppressed,
+        Code::StaleSuppression,
+        Code::Summary,
+        Code::Trace,
+        Code::FileAccess,
+        Code::Internal,
+        Code::Unbounded,
+        Code::Dead,
+        Code::ComparatorStyle,
+        Code::ExpiringSoon,
+        Code::NotInChangelog,
+        Code::NotExpired,
+        Code::IssueNotFound,
+        Code::DuplicateId,
+        Code::RenameExpired,
+        Code::ErrorsOmitted,
+        Code::Unapproved,
+        Code::Allowance,
+        Code::Offline,
+        Code::ShortNotice,
+        Code::LinkMismatch,
+        Code::WrongBranch,
+        Code::BehaviorChange,
+        Code::PoorReason,
+        Code::WatchedFileChanged,
+        Code::UnknownBranch,
+        Code::NotDeprecated,
+        Code::DefaultBody,
+        Code::Stale,
+        Code::FingerprintCollision,
+        Code::DistantDeadline,
+        Code::UnknownCargo,
+        Code::UnknownTool,
+        Code::PendingOverBudget,
+        Code::StripUnsupported,
+    ];
+
+    /// The code as shown in diagnostics, e.g. `AU0001`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Code::Expired => "AU0001",
+            Code::InvalidRequirement => "AU0002",
+            Code::NoVersion => "AU0003",
+            Code::VersionFallback => "AU0004",
+            Code::InvalidInput => "AU0005",
+            Code::MissingVersion => "AU0006",
+            Code::MissingRequired => "AU0007",
+            Code::InvalidConfig => "AU0008",
+            Code::InvalidEnv => "AU0009",
+            Code::Downgraded => "AU0010",
+            Code::Simulated => "AU0011",
+            Code::OverBudget => "AU0012",
+            Code::NotInBaseline => "AU0013",
+            Code::Published => "AU0014",
+            Code::IndexUnavailable => "AU0015",
+            Code::Suppressed => "AU0016",
+            Code::StaleSuppression => "AU0017",
+            Code::Summary => "AU0018",
+            Code::Trace => "AU0019",
+            Code::FileAccess => "AU0020",
+            Code::Internal => "AU0021",
+            Code::Unbounded => "AU0022",
+            Code::Dead => "AU0023",
+            Code::ComparatorStyle => "AU0024",
+            Code::ExpiringSoon => "AU0025",
+            Code::NotInChangelog => "AU0026",
+            Code::NotExpired => "AU0027",
+            Code::IssueNotFound => "AU0028",
+            Code::DuplicateId => "AU0029",
+            Code::RenameExpired => "AU0030",
+            Code::ErrorsOmitted => "AU0031",
+            Code::Unapproved => "AU0032",
+            Code::Allowance => "AU0033",
+            Code::Offline => "AU0034",
+            Code::ShortNotice => "AU0035",
+            Code::LinkMismatch => "AU0036",
+            Code::WrongBranch => "AU0037",
+            Code::BehaviorChange => "AU0038",
+            Code::PoorReason => "AU0039",
+            Code::WatchedFileChanged => "AU0040",
+            Code::UnknownBranch => "AU0041",
+            Code::NotDeprecated => "AU0042",
+            Code::DefaultBody => "AU0043",
+            Code::Stale => "AU0044",
+            Code::FingerprintCollision => "AU0045",
+            Code::DistantDeadline => "AU0046",
+            Code::UnknownCargo => "AU0047",
+            Code::UnknownTool => "AU0048",
+            Code::PendingOverBudget => "AU0049",
+            Code::StripUnsupported => "AU0050",
+        }
+    }
+
+    /// The code called `code`, ignoring case and surrounding whitespace.
+    pub fn parse(code: &str) -> Option<Code> {
+        Code::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// The long-form explanation of the code, starting with a one-line summary.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Code::Expired => {
+                "An annotation has expired.\n\n\
+                 The requirement in its `version` argument matches the current version of the \
+                 crate, so the annotated item was due to be removed. Remove the item, or change \
+                 the requirement if the removal is postponed. The diagnostic is a warning during \
+                 the `grace` period and with `severity = \"warning\"`."
+            }
+            Code::InvalidRequirement => {
+                "The `version` argument is not a valid semver requirement.\n\n\
+                 It is parsed with the `semver` crate, as for dependencies in `Cargo.toml`, e.g. \
+                 `\">=2.0\"` or `\"^1.4\"`."
+            }
+            Code::NoVersion => {
+                "The current version of the crate could not be determined.\n\n\
+                 Annotations are compared against `CARGO_PKG_VERSION` by default, or against the \
+                 source set by `version_source` or `version_from`. Set `ALLOW_UNTIL_VERSION` to the version they \
+                 should be compared against when that source is unavailable."
+            }
+            Code::VersionFallback => {
+                "The version source of an annotation is unavailable, so `CARGO_PKG_VERSION` is \
+                 used instead.\n\n\
+                 This happens for `version_source = \"git\"` outside of a git checkout, or when no \
+                 tag is a version."
+            }
+            Code::InvalidInput => {
+                "The arguments or input of a macro are malformed.\n\n\
+                 Arguments are `name = \"value\"` pairs, and only the documented names are \
+                 accepted. `allow_until_summary!` takes no arguments, and `explain!` a code such \
+                 as `\"AU0001\"`."
+            }
+            Code::MissingVersion => {
+                "An annotation has no `version` argument.\n\n\
+                 Every annotation needs the requirement from which its item is no longer allowed. \
+                 The optional arguments are described in the Arguments section of the README."
+            }
+            Code::MissingRequired => {
+                "An annotation lacks arguments that the crate requires.\n\n\
+                 `required` in `[package.metadata.allow-until]` lists the arguments every \
+                 annotation must set, such as `owner` or `issue`, whether or not it has expired."
+            }
+            Code::InvalidConfig => {
+                "The configuration in `[package.metadata.allow-until]`, or in the workspace's \
+                 `.allow-until.toml` or `[workspace.metadata.allow-until]`, is invalid.\n\n\
+                 Unknown keys and invalid values are ignored, and the rest of the configuration \
+                 still applies."
+            }
+            Code::InvalidEnv => {
+                "An `ALLOW_UNTIL_*` environment variable has an invalid value.\n\n\
+                 The variable is ignored, as if it was unset."
+            }
+            Code::Downgraded => {
+                "`ALLOW_UNTIL_DOWNGRADE` or `--cfg allow_until_disabled` is set, so expired \
+                 annotations are reported as warnings or not at all.\n\n\
+                 It is meant for emergency releases, and should be unset afterwards."
+            }
+            Code::Simulated => {
+                "An annotation would expire at a version listed in `ALLOW_UNTIL_SIMULATE`.\n\n\
+                 Simulated expiries are always warnings, whatever the real version is."
+            }
+            Code::OverBudget => {
+                "The crate contains more annotations than `ALLOW_UNTIL_MAX` allows.\n\n\
+                 Remove expired annotations, or raise the budget deliberately."
+            }
+            Code::NotInBaseline => {
+                "An annotation is not in the baseline set by `ALLOW_UNTIL_BASELINE`.\n\n\
+                 The baseline freezes the set of annotations. If the new one is approved, record \
+                 the baseline again with `ALLOW_UNTIL_BASELINE_RECORD=1`."
+            }
+            Code::Published => {
+                "A version matching the requirement of an annotation has already been \
+                 published.\n\n\
+                 The item was meant to be removed before that release."
+            }
+            Code::IndexUnavailable => {
+                "The published versions of the crate could not be read from the registry index, \
+                 so they were not checked against annotations."
+            }
+            Code::Suppressed => {
+                "Expired annotations were suppressed by `ALLOW_UNTIL_SUPPRESS`.\n\n\
+                 Reported by `allow_until_summary!`, so suppressions don't go unnoticed."
+            }
+            Code::StaleSuppression => {
+                "`ALLOW_UNTIL_SUPPRESS` lists ids that match no annotation.\n\n\
+                 Remove them from the variable if the annotations were deleted."
+            }
+            Code::Summary => {
+                "An overview of the annotations of the crate, reported by \
+                 `allow_until_summary!`."
+            }
+            Code::Trace => {
+                "How an annotation was evaluated, reported when `ALLOW_UNTIL_TRACE` is set."
+            }
+            Code::FileAccess => {
+                "A file used by the crate could not be read or written, such as the baseline, \
+                 the report, the SARIF log, the deprecation schedule, the file of \
+                 `version_from`, the state of `ALLOW_UNTIL_WARN_INTERVAL` or the listing of \
+                 `ALLOW_UNTIL_PUBLIC_API`."
+            }
+            Code::Internal => {
+                "The macro panicked.\n\n\
+                 This is a bug in `allow-until`, please report it with the code that triggers it."
+            }
+            Code::Unbounded => {
+                "The `version` argument matches every version, such as `*` or `>=0.0.0`.\n\n\
+                 The annotation then fires on every build, which is rarely what was meant. Use \
+                 the version from which the item is no longer allowed as a lower bound, e.g. \
+                 `\">=2.0\"`."
+            }
+            Code::Dead => {
+                "An annotation can never fire.\n\n\
+                 Its requirement matches neither the current version nor any later one, e.g. \
+                 `\"<0.5\"` in a crate at 1.2.0, so the scheduled removal is never enforced. \
+                 Delete the annotation, or correct the requirement."
+            }
+            Code::ComparatorStyle => {
+                "The `version` argument uses a bare version, `^` or `~`.\n\n\
+                 These have an upper bound: `\"1.0\"` means `^1.0`, which only matches versions \
+                 before 2.0.0, and `~1.2` only matches versions before 1.3.0. Annotations are \
+                 usually meant to fire from a version on, which `>=` expresses. Add \
+                 `allow_lint = \"comparator_style\"` to the annotation, or to \
+                 `[package.metadata.allow-until]` as a list, if the upper bound is intended."
+            }
+            Code::ExpiringSoon => {
+                "An annotation expires soon.\n\n\
+                 The version from which its requirement matches is within the `warn_ahead` \
+                 distance of `[package.metadata.allow-until]` from the current version, e.g. \
+                 `>=1.5` in a crate at 1.4.2 with `warn_ahead = \"1 minor\"`."
+            }
+            Code::NotInChangelog => {
+                "The changelog doesn't mention the removal of an expired annotation.\n\n\
+                 Annotations with `changelog = true`, or a path to the changelog, check that the \
+                 Unreleased section of `CHANGELOG.md` names the item or the annotation's `id` once \
+                 their requirement matches, so the removal is announced in the release that makes \
+                 it. Add an entry for it under the Unreleased heading."
+            }
+            Code::NotExpired => {
+                "An annotation that `ALLOW_UNTIL_EXPECTED` lists has not expired.\n\n\
+                 The variable lists the ids of annotations a release must remove, so the build \
+                 proves they fire. Reported by `allow_until_summary!` when an annotation's \
+                 requirement doesn't match the version being built, when no annotation has the \
+                 id, or when the id is also in `ALLOW_UNTIL_SUPPRESS`, which would hide its \
+                 expiry."
+            }
+            Code::IssueNotFound => {
+                "The issue an annotation links to doesn't exist.\n\n\
+                 With the `online-checks` feature and `ALLOW_UNTIL_VERIFY_ISSUES=1`, the URL of \
+                 every `issue` is requested, and this warning is emitted when the tracker answers \
+                 404. Fix the URL, or point it at the issue's new location. Network errors are \
+                 never reported."
+            }
+            Code::DuplicateId => {
+                "Two annotations declare the same id.\n\n\
+                 Ids identify annotations in `ALLOW_UNTIL_SUPPRESS`, `ALLOW_UNTIL_EXPECTED` and \
+                 baselines, which would otherwise apply to both. The warning is emitted at the \
+                 second annotation and names the location of the first; give one of them a new \
+                 id. An annotation that is expanded more than once, e.g. from a file included \
+                 by two modules, isn't a duplicate of itself."
+            }
+            Code::RenameExpired => {
+                "A `renamed_until` requirement matches, so the old name is no longer generated.\n\n\
+                 `#[renamed_until(old = \"...\", version = \"...\")]` keeps a deprecated function \
+                 under the old name of the function it is applied to while the requirement doesn't \
+                 match. Now that it does, callers must use the new name, and the attribute can be \
+                 removed."
+            }
+            Code::ErrorsOmitted => {
+                "More annotations expired than the limit of errors reported in full.\n\n\
+                 Only the first `max_errors` expiry errors of a crate, 10 unless configured \
+                 otherwise, are reported in full, so a release boundary doesn't bury the build \
+                 output. The annotation over the limit reports this error instead, and \
+                 `allow_until_summary!()` counts the omitted ones. Set `ALLOW_UNTIL_VERBOSE=1` to \
+                 report every expired annotation in full. With `ALLOW_UNTIL_FAIL_FAST=1`, only the \
+                 first is reported in full and the others are noted briefly, which the summary \
+                 counts under this code."
+            }
+            Code::Unapproved => {
+                "An expired annotation is relaxed without the sign-off of an approver.\n\n\
+                 When the crate's configuration lists `approvers`, an expired annotation can only \
+                 be reported as a warning with `severity = \"warning\"`, or downgraded by \
+                 `ALLOW_UNTIL_DOWNGRADE` or `--cfg allow_until_disabled`, if its `approved_by` \
+                 names one of them, so slipped deadlines leave a trace in the source. Without it, \
+                 the relaxation is ignored and the annotation is reported as an error. Add \
+                 `approved_by = \"name\"` once an approver agreed."
+            }
+            Code::Allowance => {
+                "The status of an annotation, noted on every build with `always_note`.\n\n\
+                 With `always_note = true` in the crate's configuration, every evaluated \
+                 annotation is noted with its requirement, its reason and whether it is active, \
+                 in its warning window or expired, so audits see every allowance in the compiler \
+                 output. Only the first `max_errors` annotations are noted, unless \
+                 `ALLOW_UNTIL_VERBOSE=1` is set, and `allow_until_summary!()` counts the others."
+            }
+            Code::Offline => {
+                "A source outside the source tree is not consulted in the offline mode.\n\n\
+                 With `ALLOW_UNTIL_OFFLINE=1` or the `deterministic` feature, the expansion only \
+                 depends on the source and the environment, as hermetic builds require. Issue \
+                 verification, the registry index check and `git blame` are skipped, each with a \
+                 single warning; unset the variable asking for them. `version_source = \"git\"` \
+                 falls back to `CARGO_PKG_VERSION` with an `AU0004` warning, unless \
+                 `ALLOW_UNTIL_VERSION` gives the version."
+            }
+            Code::ShortNotice => {
+                "An expired item was not deprecated for the `min_notice` before its removal.\n\n\
+                 With `min_notice`, an annotation must say when its item was deprecated, with \
+                 `deprecate_from` or the `since` of a `#[deprecated]` attribute, and the first \
+                 version its requirement matches must be at least that far after it. Deprecate \
+                 the item earlier, or move the removal later, e.g. `deprecate_from = \"1.4\"` \
+                 and `version = \">=1.5\"` with `min_notice = \"1 minor\"`."
+            }
+            Code::LinkMismatch => {
+                "Annotations linked with `remove_with` aren't removed together.\n\n\
+                 `remove_with = \"id\"` links an annotation to the one with that `id`, as their \
+                 items must be removed in the same release. The first versions their \
+                 requirements match differ, or no annotation of the crate declares the id, as \
+                 reported by `allow_until_summary!()`. Align the requirements, or fix the id."
+            }
+            Code::WrongBranch => {
+                "The version is before the `not_before` of an annotation, which was likely \
+                 backported.\n\n\
+                 `not_before = \"1.5.0\"` gives the oldest version an annotation belongs to, \
+                 such as the release it was written for. On an older release branch, an \
+                 annotation cherry-picked along with a patch is noise, so it isn't evaluated and \
+                 this warning asks to remove it instead."
+            }
+            Code::BehaviorChange => {
+                "The behavior of an item changes at a version that matches, as noted with \
+                 `changes_at`.\n\n\
+                 `#[changes_at(version = \">=2.0\", note = \"...\")]` marks an item that stays \
+                 but behaves differently from the requirement on, e.g. returning `Err` instead \
+                 of panicking. It never fails the build: once the requirement matches, it warns \
+                 once per item until the new behavior is implemented and documented, and the \
+                 attribute removed."
+            }
+            Code::PoorReason => {
+                "A `reason` is too short or contains a denied pattern.\n\n\
+                 With `reason_min_words` or `reason_deny_patterns` in \
+                 `[package.metadata.allow-until]`, reasons such as \"TODO\" or \"fix later\" \
+                 are reported where they are written, whether or not the annotation has \
+                 expired. They are warnings unless `reason_severity = \"error\"`. Say why the \
+                 item goes and what replaces it instead."
+            }
+            Code::WatchedFileChanged => {
+                "A file watched with `watch` no longer has its `watch_hash`.\n\n\
+                 Workarounds for a schema, a vendored file or generated code can be tied to that \
+                 file rather than to a version: `watch` names the file relative to the package \
+                 root, and `watch_hash` its SHA-256 hash when the workaround was written. Once \
+                 the file changes, the annotation expires with its `severity`, and the message \
+                 gives the new hash. Revisit the workaround, then remove it or update \
+                 `watch_hash`."
+            }
+            Code::UnknownBranch => {
+                "The current git branch is unknown, so `branch` and `branch_pattern` are treated \
+                 as matching.\n\n\
+                 The branch comes from `ALLOW_UNTIL_BRANCH`, or else from \
+                 `git rev-parse --abbrev-ref HEAD` in the package root. Outside of a git \
+                 checkout, such as vendored sources, on a detached `HEAD` as in many CI \
+                 checkouts, or in the offline mode, annotations limited to a branch are enforced \
+                 as if it matched. Set `ALLOW_UNTIL_BRANCH` to the branch being built."
+            }
+            Code::NotDeprecated => {
+                "An annotated item isn't marked `#[deprecated]`.\n\n\
+                 With `require_deprecated = true`, in the crate's configuration or on the \
+                 annotation, nothing may get a removal deadline before its users were warned \
+                 by a deprecation. The item's attributes must include `#[deprecated]`, possibly \
+                 within a `#[cfg_attr(...)]`, or the annotation must set `deprecate_from`. This \
+                 is checked whether or not the requirement matches yet."
+            }
+            Code::DefaultBody => {
+                "A trait method still has its default body, which `default_body_until` \
+                 removes at a version that matches.\n\n\
+                 `#[default_body_until(version = \">=2.0\")]` marks a method whose default \
+                 body is only kept for backwards compatibility, and which becomes required. \
+                 Once the requirement matches, delete the body, leaving `fn name(...);`, so \
+                 implementors have to supply it, and remove the attribute."
+            }
+            Code::Stale => {
+                "An annotation that hasn't expired has been allowed for longer than the \
+                 crate's `max_age`.\n\n\
+                 With `max_age = \"2 minors\"` in the crate's configuration, an annotation whose \
+                 `since` is more than two minor releases behind the current version is stale: \
+                 its deadline has likely slipped more than once. Remove the item now, or snooze \
+                 the annotation explicitly by moving `since` to the current version, with \
+                 `approved_by` naming who agreed. Annotations without `since` are warned about \
+                 too, as their age is unknown."
+            }
+            Code::FingerprintCollision => {
+                "Two annotations without an `id` have the same fingerprint.\n\n\
+                 Annotations without an `id` are identified in baselines, reports and \
+                 `ALLOW_UNTIL_SUPPRESS` by a fingerprint derived from their requirement, reason \
+                 and annotated item, which only identical annotations on items of the same kind \
+                 and name share, such as two `fn new` in different impl blocks. The warning is \
+                 emitted at the second annotation and names the location of the first; give one \
+                 of them an `id`."
+            }
+            Code::DistantDeadline => {
+                "An annotation's requirement first matches further ahead than the crate's \
+                 `max_horizon`.\n\n\
+                 With `max_horizon = \"2 majors\"` in the crate's configuration, an annotation \
+                 with `version = \">=9.0\"` in a crate at 1.2.0 is warned about, as a deadline \
+                 seven major releases away is effectively never. Aim for a nearer milestone, or \
+                 add `horizon_ok = true` to the annotation if the deadline is that far on \
+                 purpose."
+            }
+            Code::UnknownCargo => {
+                "The version of the Cargo building the crate is unknown, so the `cargo` \
+                 requirements of annotations are treated as not satisfied.\n\n\
+                 The version comes from `ALLOW_UNTIL_CARGO_VERSION`, or else from running the \
+                 binary in the `CARGO` environment variable with `--version`. When Cargo doesn't \
+                 set `CARGO`, as when rustc is run directly, or the binary can't be run, \
+                 annotations conditioned on the Cargo version don't fire. Set \
+                 `ALLOW_UNTIL_CARGO_VERSION` to the version of the Cargo building the crate."
+            }
+            Code::UnknownTool => {
+                "The version of the external tool an annotation names with `tool` is unknown, so \
+                 its `tool_version` requirement is treated as not satisfied.\n\n\
+                 The tool is run with `--version`, from `PATH` or from the binary given by \
+                 `ALLOW_UNTIL_TOOL_<NAME>`, such as `ALLOW_UNTIL_TOOL_PROTOC` for `protoc`, and \
+                 the first version in its output is taken. When the tool isn't installed, fails, \
+                 or prints no version, annotations conditioned on it don't fire, and the warning \
+                 is emitted once per tool. Install the tool, or point the variable at it."
+            }
+            Code::PendingOverBudget => {
+                "More annotations are pending for a milestone than the budget of `max_pending!` \
+                 allows.\n\n\
+                 `max_pending!(5, version = \">=2.0\")` counts the annotations expanded before it \
+                 whose requirement first matches at or before 2.0.0, the first version the \
+                 milestone's requirement matches, and errors when there are more than 5, with a \
+                 note for each. Remove the items that are due, or raise the budget deliberately."
+            }
+            Code::StripUnsupported => {
+                "An expired annotation can't be stripped by `ALLOW_UNTIL_STRIP`.\n\n\
+                 `ALLOW_UNTIL_STRIP=1` removes the items of expired annotations from the expansion \
+                 to preview their removal, but the derive can't remove the fields and variants it \
+                 is given, and the item of a `generic_default` annotation stays while only the \
+                 default goes. Such annotations are reported as usual instead. Remove the field, \
+                 variant or default by hand to see the effect of its removal."
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        for (i, a) in Code::ALL.iter().enumerate() {
+            for b in &Code::ALL[i + 1..] {
+                assert_ne!(a.as_str(), b.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn codes_are_sequential() {
+        for (i, code) in Code::ALL.iter().enumerate() {
+            assert_eq!(code.as_str(), format!("AU{:04}", i + 1));
+        }
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        for &code in Code::ALL {
+            assert_eq!(Code::parse(code.as_str()), Some(code));
+        }
+        assert_eq!(Code::parse("au0001"), Some(Code::Expired));
+        assert_eq!(Code::parse("AU9999"), None);
+    }
+}
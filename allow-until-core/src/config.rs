@@ -0,0 +1,1197 @@
+//! The crate-wide configuration, read from the `[package.metadata.allow-until]` table of the
+//! package's `Cargo.toml` on top of the configuration shared by the whole workspace. The proc macro
+//! and `cargo allow-until verify` both read it with [`load`], so they never disagree about it.
+//!
+//! The workspace configuration is the first `.allow-until.toml` found walking up from the package
+//! to the workspace root, or else the `[workspace.metadata.allow-until]` table of the root's
+//! `Cargo.toml`. It takes the same keys, and the package's values override it key by key, except
+//! for `reasons`, `migrations` and `contexts`, which are merged entry by entry.
+//!
+//! Every key and value is checked against what it accepts. What is wrong is collected as
+//! [`Problem`]s rather than failing, each located at the line and column of the offending entry
+//! with the spans of `toml_edit`, and the rest of the configuration still applies.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use semver::{Version, VersionReq};
+
+use crate::{
+    issue::Pattern,
+    lint::Lint,
+    severity::{Context, Docsrs, Enforce, Severity},
+    suggest,
+    version::{self, Distance, Unit, VersionSource},
+};
+
+/// A parsed `Cargo.toml` or `.allow-until.toml`, `None` when there is no file at the path.
+pub type Manifest = Result<Option<Arc<Document>>, String>;
+
+/// A parsed TOML file, which dereferences to its table, with its text to locate its entries in.
+#[derive(Debug)]
+pub struct Document {
+    table: toml::Table,
+    text: String,
+}
+
+impl Document {
+    /// Parses the TOML `text`.
+    pub fn parse(text: String) -> Result<Document, toml::de::Error> {
+        Ok(Document {
+            table: text.parse()?,
+            text,
+        })
+    }
+
+    /// The line and column, from 1, of the entry at `steps`: of its key, or of the element of an
+    /// array. When it has no position, such as the implicit `package` table of
+    /// `[package.metadata]`, that of the closest entry containing it is returned.
+    fn locate(&self, steps: &[Step]) -> Option<(usize, usize)> {
+        let document = toml_edit::ImDocument::parse(self.text.as_str()).ok()?;
+        let mut item = document.as_item();
+        let mut span = None;
+        for step in steps {
+            match *step {
+                Step::Key(key) => {
+                    let (key, value) = item.as_table_like()?.get_key_value(key)?;
+                    span = key.span().or(span);
+                    item = value;
+                }
+                Step::Index(i) => {
+                    span = item.as_array()?.get(i)?.span().or(span);
+                    break;
+                }
+            }
+        }
+
+        let offset = span?.start;
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Some((
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        ))
+    }
+}
+
+impl Deref for Document {
+    type Target = toml::Table;
+
+    fn deref(&self) -> &toml::Table {
+        &self.table
+    }
+}
+
+/// A step from a table or array to one of its entries.
+#[derive(Clone, Copy)]
+enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Where an entry of the configuration is written.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Location {
+    /// The file, as the path it was read from.
+    pub path: PathBuf,
+    /// The line, from 1.
+    pub line: usize,
+    /// The column, from 1, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// Something wrong with the configuration, which is ignored.
+#[derive(Clone, Debug)]
+pub struct Problem {
+    /// What is wrong, naming the key and the table it is in.
+    pub message: String,
+    /// Where the offending entry is written, `None` for files that can't be read or parsed.
+    pub location: Option<Location>,
+    /// A likely fix, such as the key a misspelled one was meant to be.
+    pub help: Option<String>,
+}
+
+impl Problem {
+    fn new(message: impl Into<String>) -> Problem {
+        Problem {
+            message: message.into(),
+            location: None,
+            help: None,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    /// The problem as `path:line:column: message`, without its location when it has none.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(help) = &self.help {
+            write!(f, " ({})", help)?;
+        }
+        Ok(())
+    }
+}
+
+/// Values used for arguments an annotation doesn't specify itself.
+#[derive(Default)]
+pub struct Defaults {
+    /// The requirement of annotations without `version` or `migration`, from `default_version`.
+    pub version: Option<VersionReq>,
+    /// How expiry is reported.
+    pub severity: Option<Severity>,
+    /// How long expiry is only a warning for.
+    pub grace: Option<Distance>,
+    /// How expiry is reported on docs.rs.
+    pub docsrs: Option<Docsrs>,
+    /// Who is responsible for removals.
+    pub owner: Option<String>,
+    /// Where the current version is taken from.
+    pub version_source: Option<VersionSource>,
+    /// How long items must be deprecated for before their removal.
+    pub min_notice: Option<Distance>,
+    /// The precision versions and requirements are compared at.
+    pub match_precision: Option<Unit>,
+    /// Whether annotated items must be `#[deprecated]`.
+    pub require_deprecated: Option<bool>,
+    /// Whether annotated items are given the `doc(cfg)` of their `cfg` attributes.
+    pub doc_cfg: Option<bool>,
+    /// Which builds fail on expiry.
+    pub enforce: Option<Enforce>,
+}
+
+/// A removal covering many items, whose annotations refer to it with `migration = "name"`.
+pub struct Migration {
+    /// The requirement of the annotations.
+    pub version: VersionReq,
+    /// The reason of the annotations, unless they give their own.
+    pub reason: Option<String>,
+    /// The issue tracking the removal.
+    pub issue: Option<String>,
+    /// Who is responsible for the removal.
+    pub owner: Option<String>,
+}
+
+/// The configuration of a package.
+#[derive(Default)]
+pub struct Config {
+    /// Values used for arguments an annotation doesn't specify itself.
+    pub defaults: Defaults,
+    /// The arguments every annotation must set.
+    pub required: Vec<String>,
+    /// The lints allowed for every annotation.
+    pub allow_lint: Vec<Lint>,
+    /// How long before annotations expire to start warning about them.
+    pub warn_ahead: Option<Distance>,
+    /// The reasons annotations can refer to with `reason_key`, by key.
+    pub reasons: BTreeMap<String, String>,
+    /// The migrations annotations can refer to with `migration`, by name.
+    pub migrations: BTreeMap<String, Migration>,
+    /// The shorthands accepted for `issue` besides URLs.
+    pub issue_patterns: Vec<Pattern>,
+    /// How many expiry errors are reported in full, [`DEFAULT_MAX_ERRORS`] if not configured.
+    pub max_errors: Option<usize>,
+    /// Who can sign off on relaxing expired annotations with `approved_by`. Relaxations need no
+    /// sign-off if not configured.
+    pub approvers: Option<Vec<String>>,
+    /// Whether every evaluated annotation is noted, whether or not it expired.
+    pub always_note: bool,
+    /// How many words a `reason` must have at least.
+    pub reason_min_words: Option<usize>,
+    /// The patterns a `reason` must not contain, in lowercase.
+    pub reason_deny_patterns: Vec<String>,
+    /// How reasons failing `reason_min_words` or `reason_deny_patterns` are reported, warnings if
+    /// not configured.
+    pub reason_severity: Option<Severity>,
+    /// How long annotations may be allowed for before they are stale.
+    pub max_age: Option<Distance>,
+    /// How far ahead of the current version requirements may first match.
+    pub max_horizon: Option<Distance>,
+    /// How expired annotations on items of the public API listed by `ALLOW_UNTIL_PUBLIC_API` are
+    /// reported at least.
+    pub public_api: Option<Severity>,
+    /// How far the requirement of every annotation is shifted outward before it is evaluated,
+    /// for branches that keep deprecated items around for longer.
+    pub extend_all_by: Option<Distance>,
+    /// How expired annotations are reported in each context, with the values `docsrs` takes.
+    pub contexts: BTreeMap<Context, Docsrs>,
+    /// The file name of the changelog fragments written by `cargo allow-until fragments`,
+    /// [`DEFAULT_FRAGMENT_NAME`] if not configured.
+    pub fragment_name: Option<String>,
+    /// The text of the changelog fragments, [`DEFAULT_FRAGMENT_TEXT`] if not configured.
+    pub fragment_text: Option<String>,
+    /// The pattern of the git tags marking releases for `enforce = "tagged-builds"`, as
+    /// `git tag --list` matches it, [`DEFAULT_RELEASE_TAGS`] if not configured.
+    pub release_tags: Option<String>,
+    /// What is wrong with the configuration, such as unknown keys or invalid values, which are
+    /// ignored.
+    pub problems: Vec<Problem>,
+    /// The version of the package, for the examples of problems.
+    version: Option<Version>,
+}
+
+impl Config {
+    /// What is wrong with `reason` according to `reason_min_words` and `reason_deny_patterns`,
+    /// ignoring case.
+    pub fn reason_problem(&self, reason: &str) -> Option<String> {
+        let lowercase = reason.to_lowercase();
+        let words = reason.split_whitespace().count();
+
+        if let Some(pattern) = self
+            .reason_deny_patterns
+            .iter()
+            .find(|pattern| lowercase.contains(pattern.as_str()))
+        {
+            Some(format!(
+                "the reason contains `{}`, which `reason_deny_patterns` denies",
+                pattern
+            ))
+        } else {
+            self.reason_min_words.filter(|&min| words < min).map(|min| {
+                format!(
+                    "the reason has {} word{}, but `reason_min_words` requires {}",
+                    words,
+                    if words == 1 { "" } else { "s" },
+                    min
+                )
+            })
+        }
+    }
+}
+
+/// How many expiry errors are reported in full unless `max_errors` is configured.
+pub const DEFAULT_MAX_ERRORS: usize = 10;
+
+/// The pattern of release tags unless `release_tags` is configured.
+pub const DEFAULT_RELEASE_TAGS: &str = "v*";
+
+/// The file name of changelog fragments unless `name` is configured in `fragments`.
+pub const DEFAULT_FRAGMENT_NAME: &str = "{id}.removal.md";
+
+/// The text of changelog fragments unless `text` is configured in `fragments`.
+pub const DEFAULT_FRAGMENT_TEXT: &str = "{notice}";
+
+/// The placeholders of the `name` and `text` of changelog fragments.
+pub const FRAGMENT_FIELDS: &[&str] = &[
+    "id",
+    "item",
+    "reason",
+    "replacement",
+    "version",
+    "requirement",
+    "notice",
+];
+
+/// The keys of `[package.metadata.allow-until.fragments]`.
+const FRAGMENT_KEYS: &[&str] = &["name", "text"];
+
+/// The placeholders of `template`, such as `id` for `{id}.removal.md`, or the first that isn't
+/// one of [`FRAGMENT_FIELDS`].
+pub fn fragment_fields(template: &str) -> Result<Vec<&str>, String> {
+    let mut fields = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed `{{` in `{}`", template))?;
+        let field = &rest[start + 1..start + end];
+        if !FRAGMENT_FIELDS.contains(&field) {
+            return Err(format!(
+                "unknown placeholder `{{{}}}` in `{}`, expected one of {}",
+                field,
+                template,
+                FRAGMENT_FIELDS
+                    .iter()
+                    .map(|f| format!("`{{{}}}`", f))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        fields.push(field);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(fields)
+}
+
+/// The name of the workspace configuration file.
+pub const WORKSPACE_FILE: &str = ".allow-until.toml";
+
+/// Which table of configuration is read, to name it in problems.
+enum Kind {
+    Package,
+    /// The `[workspace.metadata.allow-until]` table of a `Cargo.toml`.
+    Workspace,
+    /// A [`WORKSPACE_FILE`].
+    File,
+}
+
+/// A table of configuration being read, to name and locate its entries in problems.
+struct Source<'a> {
+    kind: Kind,
+    /// The file the table is in.
+    path: &'a Path,
+    document: &'a Document,
+}
+
+impl Source<'_> {
+    /// The name of the subtable `key`, or of the table itself, e.g.
+    /// "`[package.metadata.allow-until.reasons]`".
+    fn table(&self, key: Option<&str>) -> String {
+        let key = key.map_or(String::new(), |key| format!(".{}", key));
+        match self.kind {
+            Kind::Package => format!("`[package.metadata.allow-until{}]`", key),
+            Kind::Workspace => format!(
+                "`[workspace.metadata.allow-until{}]` of `{}`",
+                key,
+                self.path.display()
+            ),
+            Kind::File if key.is_empty() => format!("`{}`", self.path.display()),
+            Kind::File => format!("`[{}]` of `{}`", &key[1..], self.path.display()),
+        }
+    }
+
+    /// Where the entry at `steps` from the table is written.
+    fn locate(&self, steps: &[Step]) -> Option<Location> {
+        let root: &[Step] = match self.kind {
+            Kind::Package => &[
+                Step::Key("package"),
+                Step::Key("metadata"),
+                Step::Key("allow-until"),
+            ],
+            Kind::Workspace => &[
+                Step::Key("workspace"),
+                Step::Key("metadata"),
+                Step::Key("allow-until"),
+            ],
+            Kind::File => &[],
+        };
+        let steps: Vec<Step> = root.iter().chain(steps).copied().collect();
+        let (line, column) = self.document.locate(&steps)?;
+        Some(Location {
+            path: self.path.into(),
+            line,
+            column,
+        })
+    }
+}
+
+/// The keys accepted in `[package.metadata.allow-until]`.
+const KEYS: &[&str] = &[
+    "severity",
+    "grace",
+    "docsrs",
+    "owner",
+    "version_source",
+    "required",
+    "allow_lint",
+    "warn_ahead",
+    "reasons",
+    "issue_patterns",
+    "migrations",
+    "max_errors",
+    "approvers",
+    "default_version",
+    "always_note",
+    "min_notice",
+    "reason_min_words",
+    "reason_deny_patterns",
+    "reason_severity",
+    "match_precision",
+    "require_deprecated",
+    "max_age",
+    "contexts",
+    "extend_all_by",
+    "max_horizon",
+    "public_api",
+    "doc_cfg",
+    "fragments",
+    "enforce",
+    "release_tags",
+];
+
+/// The keys accepted in a migration.
+const MIGRATION_KEYS: &[&str] = &["version", "reason", "issue", "owner"];
+
+/// The arguments that can be listed in `required`.
+const REQUIRABLE: &[&str] = &[
+    "reason",
+    "id",
+    "tags",
+    "owner",
+    "issue",
+    "severity",
+    "grace",
+    "docsrs",
+    "version_source",
+];
+
+/// The keys of `[package.metadata.allow-until]` whose values are tables.
+const TABLE_KEYS: &[&str] = &["reasons", "migrations", "contexts", "fragments"];
+
+/// Reads the configuration of the package in `dir`, whose version is `version`, reading files
+/// with `read`, which the proc macro caches.
+pub fn load(dir: &Path, version: Option<&Version>, read: impl Fn(&Path) -> Manifest) -> Config {
+    let mut config = Config {
+        version: version.cloned(),
+        ..Config::default()
+    };
+    config.read_workspace(dir, &read);
+
+    let path = dir.join("Cargo.toml");
+    match read(&path) {
+        Ok(Some(manifest)) => {
+            let source = Source {
+                kind: Kind::Package,
+                path: &path,
+                document: &manifest,
+            };
+            let table = manifest
+                .get("package")
+                .and_then(|p| p.get("metadata"))
+                .and_then(|m| m.get("allow-until"));
+            match table {
+                Some(toml::Value::Table(table)) => config.read(table, &source),
+                Some(value) => {
+                    config.problem(
+                        &source,
+                        &[],
+                        format!(
+                            "`package.metadata.allow-until` in `Cargo.toml` must be a table, \
+                             found {}",
+                            kind(value)
+                        ),
+                    );
+                }
+                None => (),
+            }
+        }
+        Ok(None) => (),
+        Err(e) => config.problems.push(Problem::new(format!(
+            "{}, ignoring `[package.metadata.allow-until]`",
+            e
+        ))),
+    }
+
+    config
+}
+
+impl Config {
+    /// Reads the workspace configuration for the package in `dir`, if there is one.
+    fn read_workspace(&mut self, dir: &Path, read: impl Fn(&Path) -> Manifest) {
+        for dir in dir.ancestors() {
+            let file = dir.join(WORKSPACE_FILE);
+            match read(&file) {
+                Ok(Some(document)) => {
+                    let source = Source {
+                        kind: Kind::File,
+                        path: &file,
+                        document: &document,
+                    };
+                    return self.read(&document, &source);
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    return self.problems.push(Problem::new(format!(
+                        "{}, ignoring the workspace configuration",
+                        e
+                    )))
+                }
+            }
+
+            // the workspace root ends the search, whether or not it configures anything
+            let manifest = dir.join("Cargo.toml");
+            let Ok(Some(root)) = read(&manifest) else {
+                continue;
+            };
+            let Some(workspace) = root.get("workspace") else {
+                continue;
+            };
+            let source = Source {
+                kind: Kind::Workspace,
+                path: &manifest,
+                document: &root,
+            };
+            match workspace.get("metadata").and_then(|m| m.get("allow-until")) {
+                Some(toml::Value::Table(table)) => self.read(table, &source),
+                Some(value) => {
+                    self.problem(
+                        &source,
+                        &[],
+                        format!(
+                            "`workspace.metadata.allow-until` in `{}` must be a table, found {}",
+                            manifest.display(),
+                            kind(value)
+                        ),
+                    );
+                }
+                None => (),
+            }
+            return;
+        }
+    }
+
+    /// Records `message` as a problem with the entry at `steps` from the table of `source`.
+    fn problem(&mut self, source: &Source, steps: &[Step], message: String) -> &mut Problem {
+        self.problems.push(Problem {
+            location: source.locate(steps),
+            ..Problem::new(message)
+        });
+        self.problems.last_mut().unwrap()
+    }
+
+    /// Records that the value of `key` isn't `expected`, e.g. "a string".
+    fn wrong_type(&mut self, source: &Source, key: &str, expected: &str, value: &toml::Value) {
+        self.problem(
+            source,
+            &[Step::Key(key)],
+            format!(
+                "`{}` in {} must be {}, found {}",
+                key,
+                source.table(None),
+                expected,
+                kind(value)
+            ),
+        );
+    }
+
+    /// Reads the keys of `table`, overriding those read before.
+    fn read(&mut self, table: &toml::Table, source: &Source) {
+        for (key, value) in table {
+            if !KEYS.contains(&&key[..]) {
+                let message = match value {
+                    toml::Value::Table(_) => format!(
+                        "unknown table {}, expected one of {}",
+                        source.table(Some(key)),
+                        list_keys(TABLE_KEYS)
+                    ),
+                    _ => format!(
+                        "unknown key `{}` in {}, expected one of {}",
+                        key,
+                        source.table(None),
+                        list_keys(KEYS)
+                    ),
+                };
+                self.problem(source, &[Step::Key(key)], message).help = suggest::closest(key, KEYS)
+                    .map(|closest| format!("did you mean `{}`?", closest));
+                continue;
+            }
+
+            if key == "required" {
+                self.read_required(value, source);
+                continue;
+            }
+            if key == "allow_lint" {
+                self.read_allow_lint(value, source);
+                continue;
+            }
+            if key == "reasons" {
+                self.read_reasons(value, source);
+                continue;
+            }
+            if key == "migrations" {
+                self.read_migrations(value, source);
+                continue;
+            }
+            if key == "issue_patterns" {
+                self.read_issue_patterns(value, source);
+                continue;
+            }
+            if key == "approvers" {
+                self.read_approvers(value, source);
+                continue;
+            }
+            if key == "fragments" {
+                self.read_fragments(value, source);
+                continue;
+            }
+            if key == "contexts" {
+                self.read_contexts(value, source);
+                continue;
+            }
+            if key == "always_note" {
+                match value.as_bool() {
+                    Some(enabled) => self.always_note = enabled,
+                    None => self.wrong_type(source, key, "`true` or `false`", value),
+                }
+                continue;
+            }
+            if key == "require_deprecated" {
+                match value.as_bool() {
+                    Some(enabled) => self.defaults.require_deprecated = Some(enabled),
+                    None => self.wrong_type(source, key, "`true` or `false`", value),
+                }
+                continue;
+            }
+            if key == "doc_cfg" {
+                match value.as_bool() {
+                    Some(enabled) => self.defaults.doc_cfg = Some(enabled),
+                    None => self.wrong_type(source, key, "`true` or `false`", value),
+                }
+                continue;
+            }
+            if key == "reason_min_words" {
+                match value.as_integer().filter(|&n| n > 0) {
+                    Some(n) => self.reason_min_words = Some(n as usize),
+                    None => self.wrong_type(source, key, "a positive integer", value),
+                }
+                continue;
+            }
+            if key == "reason_deny_patterns" {
+                self.read_reason_deny_patterns(value, source);
+                continue;
+            }
+            if key == "max_errors" {
+                match value.as_integer().filter(|&n| n > 0) {
+                    Some(n) => self.max_errors = Some(n as usize),
+                    None => self.wrong_type(source, key, "a positive integer", value),
+                }
+                continue;
+            }
+
+            let Some(s) = value.as_str() else {
+                self.wrong_type(source, key, "a string", value);
+                continue;
+            };
+
+            let result = match &key[..] {
+                "severity" => Severity::parse(s).map(|v| self.defaults.severity = Some(v)),
+                "grace" => Distance::parse(s).map(|v| self.defaults.grace = Some(v)),
+                "docsrs" => Docsrs::parse(s).map(|v| self.defaults.docsrs = Some(v)),
+                "enforce" => Enforce::parse(s).map(|v| self.defaults.enforce = Some(v)),
+                "release_tags" if s.trim().is_empty() => Err("the pattern is empty".into()),
+                "release_tags" => {
+                    self.release_tags = Some(s.into());
+                    Ok(())
+                }
+                "version_source" => {
+                    VersionSource::parse(s).map(|v| self.defaults.version_source = Some(v))
+                }
+                "warn_ahead" => Distance::parse(s).map(|v| self.warn_ahead = Some(v)),
+                "max_age" => Distance::parse(s).map(|v| self.max_age = Some(v)),
+                "extend_all_by" => Distance::parse(s).map(|v| self.extend_all_by = Some(v)),
+                "max_horizon" => Distance::parse(s).map(|v| self.max_horizon = Some(v)),
+                "public_api" => Severity::parse(s).map(|v| self.public_api = Some(v)),
+                "min_notice" => Distance::parse(s).map(|v| self.defaults.min_notice = Some(v)),
+                "reason_severity" => Severity::parse(s).map(|v| self.reason_severity = Some(v)),
+                "match_precision" => {
+                    Unit::parse_precision(s).map(|v| self.defaults.match_precision = Some(v))
+                }
+                "default_version" => default_version(s, self.version.as_ref())
+                    .map(|v| self.defaults.version = Some(v)),
+                _ => {
+                    self.defaults.owner = Some(s.into());
+                    Ok(())
+                }
+            };
+
+            if let Err(e) = result {
+                let message = format!("`{}` in {}: {}", key, source.table(None), e);
+                self.problem(source, &[Step::Key(key)], message);
+            }
+        }
+    }
+
+    fn read_required(&mut self, value: &toml::Value, source: &Source) {
+        let Some(list) = value.as_array() else {
+            return self.wrong_type(source, "required", "an array", value);
+        };
+
+        self.required.clear();
+        for (i, name) in list.iter().enumerate() {
+            match name.as_str() {
+                Some(name) if REQUIRABLE.contains(&name) => self.required.push(name.into()),
+                _ => {
+                    let message = format!(
+                        "ignoring `{}` in `required`, expected one of {}",
+                        name,
+                        list_keys(REQUIRABLE)
+                    );
+                    self.problem(source, &[Step::Key("required"), Step::Index(i)], message)
+                        .help = name
+                        .as_str()
+                        .and_then(|name| suggest::closest(name, REQUIRABLE))
+                        .map(|closest| format!("did you mean `{}`?", closest));
+                }
+            }
+        }
+    }
+
+    /// The strings of the array `value`, or the steps to and the kind of what isn't one.
+    fn strings(value: &toml::Value) -> Result<Vec<&str>, (Option<usize>, &'static str)> {
+        let list = value.as_array().ok_or((None, kind(value)))?;
+        list.iter()
+            .enumerate()
+            .map(|(i, item)| item.as_str().ok_or((Some(i), kind(item))))
+            .collect()
+    }
+
+    /// Records that `key` isn't an array of `what`, at the first element that isn't one.
+    fn not_strings(&mut self, source: &Source, key: &str, what: &str, at: (Option<usize>, &str)) {
+        let message = format!(
+            "`{}` in {} must be an array of {}, found {}",
+            key,
+            source.table(None),
+            what,
+            at.1
+        );
+        match at.0 {
+            Some(i) => self.problem(source, &[Step::Key(key), Step::Index(i)], message),
+            None => self.problem(source, &[Step::Key(key)], message),
+        };
+    }
+
+    fn read_approvers(&mut self, value: &toml::Value, source: &Source) {
+        match Config::strings(value) {
+            Ok(names) => self.approvers = Some(names.into_iter().map(String::from).collect()),
+            Err(at) => self.not_strings(source, "approvers", "names", at),
+        }
+    }
+
+    fn read_reason_deny_patterns(&mut self, value: &toml::Value, source: &Source) {
+        match Config::strings(value) {
+            Ok(patterns) => {
+                self.reason_deny_patterns = patterns.into_iter().map(str::to_lowercase).collect()
+            }
+            Err(at) => self.not_strings(source, "reason_deny_patterns", "strings", at),
+        }
+    }
+
+    fn read_allow_lint(&mut self, value: &toml::Value, source: &Source) {
+        let Some(list) = value.as_array() else {
+            return self.wrong_type(source, "allow_lint", "an array", value);
+        };
+
+        self.allow_lint.clear();
+        for (i, name) in list.iter().enumerate() {
+            match name
+                .as_str()
+                .ok_or_else(|| format!("`{}` is not a string", name))
+                .and_then(Lint::parse)
+            {
+                Ok(lint) => self.allow_lint.push(lint),
+                Err(e) => {
+                    let message = format!("ignoring `{}` in `allow_lint`: {}", name, e);
+                    self.problem(source, &[Step::Key("allow_lint"), Step::Index(i)], message);
+                }
+            }
+        }
+    }
+
+    fn read_issue_patterns(&mut self, value: &toml::Value, source: &Source) {
+        let Some(list) = value.as_array() else {
+            return self.wrong_type(source, "issue_patterns", "an array", value);
+        };
+
+        self.issue_patterns.clear();
+        for (i, pattern) in list.iter().enumerate() {
+            match pattern
+                .as_str()
+                .ok_or_else(|| format!("`{}` is not a string", pattern))
+                .and_then(Pattern::parse)
+            {
+                Ok(pattern) => self.issue_patterns.push(pattern),
+                Err(e) => {
+                    let message = format!("ignoring `{}` in `issue_patterns`: {}", pattern, e);
+                    self.problem(
+                        source,
+                        &[Step::Key("issue_patterns"), Step::Index(i)],
+                        message,
+                    );
+                }
+            }
+        }
+    }
+
+    fn read_migrations(&mut self, value: &toml::Value, source: &Source) {
+        let Some(table) = value.as_table() else {
+            return self.wrong_type(source, "migrations", "a table of tables", value);
+        };
+
+        for (name, migration) in table {
+            match Migration::read(migration) {
+                Ok(migration) => {
+                    self.migrations.insert(name.clone(), migration);
+                }
+                Err((e, key)) => {
+                    let message = format!(
+                        "ignoring migration `{}` in {}: {}",
+                        name,
+                        source.table(Some("migrations")),
+                        e
+                    );
+                    let mut steps = vec![Step::Key("migrations"), Step::Key(name)];
+                    steps.extend(key.map(Step::Key));
+                    self.problem(source, &steps, message).help = key
+                        .filter(|key| !MIGRATION_KEYS.contains(key))
+                        .and_then(|key| suggest::closest(key, MIGRATION_KEYS))
+                        .map(|closest| format!("did you mean `{}`?", closest));
+                }
+            }
+        }
+    }
+
+    fn read_contexts(&mut self, value: &toml::Value, source: &Source) {
+        let Some(table) = value.as_table() else {
+            return self.wrong_type(source, "contexts", "a table of strings", value);
+        };
+
+        let contexts = Context::ALL.map(Context::as_str);
+        for (name, handling) in table {
+            let steps = [Step::Key("contexts"), Step::Key(name)];
+            let Some(context) = Context::parse(name) else {
+                let message = format!(
+                    "ignoring unknown context `{}` in {}, expected one of {}",
+                    name,
+                    source.table(Some("contexts")),
+                    list_keys(&contexts)
+                );
+                self.problem(source, &steps, message).help = suggest::closest(name, &contexts)
+                    .map(|closest| format!("did you mean `{}`?", closest));
+                continue;
+            };
+            match handling.as_str().and_then(|s| Docsrs::parse(s).ok()) {
+                Some(handling) => {
+                    self.contexts.insert(context, handling);
+                }
+                None => {
+                    let message = format!(
+                        "ignoring context `{}` in {}, it must be `\"ignore\"`, `\"warning\"` or \
+                         `\"error\"`, found {}",
+                        name,
+                        source.table(Some("contexts")),
+                        match handling.as_str() {
+                            Some(s) => format!("`\"{}\"`", s),
+                            None => kind(handling).into(),
+                        }
+                    );
+                    self.problem(source, &steps, message);
+                }
+            }
+        }
+    }
+
+    fn read_fragments(&mut self, value: &toml::Value, source: &Source) {
+        let Some(table) = value.as_table() else {
+            return self.wrong_type(source, "fragments", "a table of strings", value);
+        };
+
+        for (key, template) in table {
+            let steps = [Step::Key("fragments"), Step::Key(key)];
+            if !FRAGMENT_KEYS.contains(&&key[..]) {
+                let message = format!(
+                    "unknown key `{}` in {}, expected one of {}",
+                    key,
+                    source.table(Some("fragments")),
+                    list_keys(FRAGMENT_KEYS)
+                );
+                self.problem(source, &steps, message).help = suggest::closest(key, FRAGMENT_KEYS)
+                    .map(|closest| format!("did you mean `{}`?", closest));
+                continue;
+            }
+            let Some(template) = template.as_str() else {
+                let message = format!(
+                    "`{}` in {} must be a string, found {}",
+                    key,
+                    source.table(Some("fragments")),
+                    kind(template)
+                );
+                self.problem(source, &steps, message);
+                continue;
+            };
+
+            let result = fragment_fields(template).and_then(|fields| {
+                if key == "name" && !fields.contains(&"id") {
+                    return Err(format!(
+                        "`{}` has no `{{id}}`, which gives each annotation its own fragment",
+                        template
+                    ));
+                }
+                Ok(())
+            });
+            match result {
+                Ok(()) if key == "name" => self.fragment_name = Some(template.into()),
+                Ok(()) => self.fragment_text = Some(template.into()),
+                Err(e) => {
+                    let message =
+                        format!("`{}` in {}: {}", key, source.table(Some("fragments")), e);
+                    self.problem(source, &steps, message);
+                }
+            }
+        }
+    }
+
+    fn read_reasons(&mut self, value: &toml::Value, source: &Source) {
+        let Some(table) = value.as_table() else {
+            return self.wrong_type(source, "reasons", "a table of strings", value);
+        };
+
+        for (key, reason) in table {
+            match reason.as_str() {
+                Some(reason) => {
+                    self.reasons.insert(key.clone(), reason.into());
+                }
+                None => {
+                    let message = format!(
+                        "ignoring reason `{}` in {}, it must be a string, found {}",
+                        key,
+                        source.table(Some("reasons")),
+                        kind(reason)
+                    );
+                    self.problem(source, &[Step::Key("reasons"), Step::Key(key)], message);
+                }
+            }
+        }
+    }
+}
+
+impl Migration {
+    /// The migration in `value`, or what is wrong with it and the key it is wrong about.
+    fn read(value: &toml::Value) -> Result<Migration, (String, Option<&str>)> {
+        let table = value
+            .as_table()
+            .ok_or_else(|| (format!("it must be a table, found {}", kind(value)), None))?;
+
+        let mut strings = BTreeMap::new();
+        for (key, value) in table {
+            if !MIGRATION_KEYS.contains(&&key[..]) {
+                return Err((
+                    format!(
+                        "unknown key `{}`, expected one of {}",
+                        key,
+                        list_keys(MIGRATION_KEYS)
+                    ),
+                    Some(key),
+                ));
+            }
+            let value = value.as_str().ok_or_else(|| {
+                (
+                    format!("`{}` must be a string, found {}", key, kind(value)),
+                    Some(&key[..]),
+                )
+            })?;
+            strings.insert(&key[..], value.to_string());
+        }
+
+        let text = strings
+            .remove("version")
+            .ok_or(("missing required key `version`".into(), None))?;
+        let version = version::parse_req(&text).map_err(|e| {
+            (
+                format!("invalid semver requirement `{}`: {}", text, e),
+                Some("version"),
+            )
+        })?;
+
+        Ok(Migration {
+            version,
+            reason: strings.remove("reason"),
+            issue: strings.remove("issue"),
+            owner: strings.remove("owner"),
+        })
+    }
+}
+
+/// What `value` is, for problems about values of the wrong type, e.g. "an integer".
+fn kind(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::String(_) => "a string",
+        toml::Value::Integer(_) => "an integer",
+        toml::Value::Float(_) => "a float",
+        toml::Value::Boolean(_) => "a boolean",
+        toml::Value::Datetime(_) => "a date",
+        toml::Value::Array(_) => "an array",
+        toml::Value::Table(_) => "a table",
+    }
+}
+
+/// The requirement of `default_version`. Deadlines relative to the current version, such as
+/// "next-major", are rejected: they would move along with the version and never be reached.
+fn default_version(text: &str, current: Option<&Version>) -> Result<VersionReq, String> {
+    if text.trim().starts_with("next") {
+        let example = current.map_or(">=2.0.0".into(), |v| format!(">={}.0.0", v.major + 1));
+        return Err(format!(
+            "`default_version = \"{}\"` would move along with the version and never be reached, \
+             write the requirement instead, e.g. `default_version = \"{}\"`",
+            text, example
+        ));
+    }
+    version::parse_req(text).map_err(|e| format!("invalid semver requirement `{}`: {}", text, e))
+}
+
+fn list_keys(keys: &[&str]) -> String {
+    keys.iter()
+        .map(|k| format!("`{}`", k))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_from(files: &[(&str, &str)]) -> Config {
+        let files: BTreeMap<PathBuf, Arc<Document>> = files
+            .iter()
+            .map(|(path, text)| {
+                let document = Document::parse(text.to_string()).unwrap();
+                (PathBuf::from(path), Arc::new(document))
+            })
+            .collect();
+        load(Path::new("/ws/core"), None, |path| {
+            Ok(files.get(path).cloned())
+        })
+    }
+
+    #[test]
+    fn package_overrides_workspace() {
+        let config = load_from(&[
+            (
+                "/ws/.allow-until.toml",
+                "owner = \"platform\"\nreason_min_words = 3\n[reasons]\nxml = \"use `Json`\"",
+            ),
+            (
+                "/ws/core/Cargo.toml",
+                "[package.metadata.allow-until]\nowner = \"core\"\nbogus = 1\n\
+                 [package.metadata.allow-until.reasons]\nyaml = \"use `Toml`\"",
+            ),
+        ]);
+
+        assert_eq!(config.defaults.owner.as_deref(), Some("core"));
+        assert_eq!(config.reasons.len(), 2);
+        assert_eq!(config.problems.len(), 1);
+        assert!(config.problems[0]
+            .message
+            .starts_with("unknown key `bogus`"));
+        assert_eq!(
+            config.reason_problem("use `Json`").as_deref(),
+            Some("the reason has 2 words, but `reason_min_words` requires 3")
+        );
+    }
+
+    #[test]
+    fn contexts() {
+        let config = load_from(&[
+            (
+                "/ws/.allow-until.toml",
+                "[contexts]\nci = \"error\"\nlocal = \"warn\"",
+            ),
+            (
+                "/ws/core/Cargo.toml",
+                "[package.metadata.allow-until.contexts]\nlocal = \"ignore\"\nide = \"warn\"\n\
+                 docsrs = \"quiet\"",
+            ),
+        ]);
+
+        assert_eq!(
+            config.contexts.into_iter().collect::<Vec<_>>(),
+            [
+                (Context::Ci, Docsrs::Severity(Severity::Error)),
+                (Context::Local, Docsrs::Ignore),
+            ]
+        );
+        assert_eq!(config.problems.len(), 2);
+        assert!(config.problems[0]
+            .message
+            .starts_with("ignoring context `docsrs`"));
+        assert!(config.problems[1]
+            .message
+            .starts_with("ignoring unknown context `ide`"));
+    }
+
+    #[test]
+    fn locations() {
+        let config = load_from(&[(
+            "/ws/core/Cargo.toml",
+            "[package]\nname = \"core\"\n\n[package.metadata.allow-until]\nownr = \"core\"\n\
+             required = [\"issue\", \"reasn\"]\ngrace = 3\n\n\
+             [package.metadata.allow-until.migrations.json]\nversion = \">=2\"\nreson = \"x\"\n",
+        )]);
+
+        let problems: Vec<_> = config.problems.iter().map(ToString::to_string).collect();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(
+            problems[0],
+            "/ws/core/Cargo.toml:7:1: `grace` in `[package.metadata.allow-until]` must be a string, \
+             found an integer"
+        );
+        assert_eq!(
+            problems[1],
+            "/ws/core/Cargo.toml:11:1: ignoring migration `json` in \
+             `[package.metadata.allow-until.migrations]`: unknown key `reson`, expected one of \
+             `version`, `reason`, `issue`, `owner` (did you mean `reason`?)"
+        );
+        assert!(problems[2].starts_with(
+            "/ws/core/Cargo.toml:5:1: unknown key `ownr` in `[package.metadata.allow-until]`"
+        ));
+        assert!(problems[2].ends_with("(did you mean `owner`?)"));
+        assert!(
+            problems[3].starts_with("/ws/core/Cargo.toml:6:22: ignoring `\"reasn\"` in `required`")
+        );
+        assert!(problems[3].ends_with("(did you mean `reason`?)"));
+    }
+
+    #[test]
+    fn unknown_tables() {
+        let config = load_from(&[(
+            "/ws/core/Cargo.toml",
+            "[package.metadata.allow-until.reason]\nxml = \"use `Json`\"\n",
+        )]);
+
+        assert_eq!(config.problems.len(), 1);
+        assert_eq!(
+            config.problems[0].message,
+            "unknown table `[package.metadata.allow-until.reason]`, expected one of `reasons`, \
+             `migrations`, `contexts`, `fragments`"
+        );
+        assert_eq!(
+            config.problems[0].help.as_deref(),
+            Some("did you mean `reasons`?")
+        );
+        assert_eq!(config.problems[0].location.as_ref().unwrap().line, 1);
+    }
+
+    #[test]
+    fn fragments() {
+        assert_eq!(
+            fragment_fields("{version}/{id}.removal.md"),
+            Ok(vec!["version", "id"])
+        );
+        assert!(fragment_fields("{notice")
+            .unwrap_err()
+            .starts_with("unclosed `{`"));
+
+        let config = load_from(&[(
+            "/ws/core/Cargo.toml",
+            "[package.metadata.allow-until.fragments]\nname = \"{item}.md\"\n\
+             text = \"- {notes}\"\ntitle = \"x\"\n",
+        )]);
+
+        let problems: Vec<_> = config.problems.iter().map(|p| &p.message[..]).collect();
+        assert_eq!(
+            problems,
+            [
+                "`name` in `[package.metadata.allow-until.fragments]`: `{item}.md` has no `{id}`, \
+                 which gives each annotation its own fragment",
+                "`text` in `[package.metadata.allow-until.fragments]`: unknown placeholder \
+                 `{notes}` in `- {notes}`, expected one of `{id}`, `{item}`, `{reason}`, \
+                 `{replacement}`, `{version}`, `{requirement}`, `{notice}`",
+                "unknown key `title` in `[package.metadata.allow-until.fragments]`, expected one \
+                 of `name`, `text`",
+            ]
+        );
+        assert_eq!((config.fragment_name, config.fragment_text), (None, None));
+    }
+}
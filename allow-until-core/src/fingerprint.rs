@@ -0,0 +1,44 @@
+//! Fingerprints identifying annotations that have no `id`, such as `au-3f0c8e1d52a9b6e4`, the same
+//! for the proc macro and `cargo allow-until`.
+//!
+//! ```
+//! let req = semver::VersionReq::parse(">=2.0").unwrap();
+//! let fingerprint = allow_until_core::fingerprint::of(&req, None, "struct", Some("Xml"), None);
+//! assert!(fingerprint.starts_with("au-"));
+//! assert_eq!(fingerprint.len(), 19);
+//! ```
+
+use semver::VersionReq;
+
+/// The fingerprint of the annotation with the requirement `req` and `reason` on the item of `kind`
+/// and `name`, such as `"struct"` and `"Xml"`, and about its `generic_default` parameter, if any.
+///
+/// It is derived from these rather than from where the annotation is written, so formatting
+/// changes and unrelated edits elsewhere in the file don't change it, while changing the
+/// requirement does.
+pub fn of(
+    req: &VersionReq,
+    reason: Option<&str>,
+    kind: &str,
+    name: Option<&str>,
+    generic_default: Option<&str>,
+) -> String {
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let req = req.to_string();
+    let mut parts = vec![
+        &req[..],
+        reason.unwrap_or_default(),
+        kind,
+        name.unwrap_or_default(),
+    ];
+    // so the default of a parameter and the item itself can be scheduled apart
+    parts.extend(generic_default);
+
+    for byte in parts.join("\0").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("au-{:016x}", hash)
+}
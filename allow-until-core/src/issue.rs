@@ -0,0 +1,223 @@
+//! Validation of the `issue` argument, which must link to the issue tracking a removal: either an
+//! absolute http(s) URL, or a shorthand matching one of the crate's `issue_patterns`.
+
+/// A shorthand for issues, such as `{name}/{name}#{number}` for `DexterHill0/allow-until#12`.
+pub struct Pattern {
+    /// The pattern as written, for messages.
+    pub text: String,
+    parts: Vec<Part>,
+}
+
+enum Part {
+    Literal(char),
+    /// `{name}`: letters, digits, `-`, `_` and `.`.
+    Name,
+    /// `{number}`: digits.
+    Number,
+}
+
+impl Pattern {
+    /// Parses a pattern such as `PLAT-{number}`, made of literal characters and the `{name}` and
+    /// `{number}` placeholders.
+    pub fn parse(text: &str) -> Result<Pattern, String> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+
+        while let Some(c) = rest.chars().next() {
+            if c == '{' {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| format!("unclosed placeholder in issue pattern `{}`", text))?;
+                parts.push(match &rest[1..end] {
+                    "name" => Part::Name,
+                    "number" => Part::Number,
+                    other => {
+                        return Err(format!(
+                            "unknown placeholder `{{{}}}` in issue pattern `{}`, expected \
+                             `{{name}}` or `{{number}}`",
+                            other, text
+                        ))
+                    }
+                });
+                rest = &rest[end + 1..];
+            } else {
+                parts.push(Part::Literal(c));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+
+        if parts.is_empty() {
+            return Err("issue patterns can't be empty".into());
+        }
+        Ok(Pattern {
+            text: text.into(),
+            parts,
+        })
+    }
+
+    /// Whether `issue` matches the whole pattern.
+    pub fn matches(&self, issue: &str) -> bool {
+        matches(&self.parts, &issue.chars().collect::<Vec<_>>())
+    }
+}
+
+/// Whether `parts` match all of `chars`, placeholders taking as many characters as they can while
+/// leaving a match for the rest.
+fn matches(parts: &[Part], chars: &[char]) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return chars.is_empty();
+    };
+
+    let allowed: fn(&char) -> bool = match part {
+        Part::Literal(c) => return chars.first() == Some(c) && matches(rest, &chars[1..]),
+        Part::Name => |c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'),
+        Part::Number => char::is_ascii_digit,
+    };
+    let longest = chars.iter().take_while(|c| allowed(c)).count();
+    (1..=longest).rev().any(|len| matches(rest, &chars[len..]))
+}
+
+/// Checks that `issue` is an absolute http(s) URL or matches one of `patterns`, describing what is
+/// wrong with it otherwise.
+pub fn check(issue: &str, patterns: &[Pattern]) -> Result<(), String> {
+    if patterns.iter().any(|p| p.matches(issue)) {
+        return Ok(());
+    }
+
+    let invalid = |why: &str| Err(format!("invalid `issue` `{}`: {}", issue, why));
+
+    let Some((scheme, rest)) = issue.split_once("://") else {
+        return invalid(if patterns.is_empty() {
+            "expected an absolute http(s) URL"
+        } else {
+            "expected an absolute http(s) URL or a shorthand matching `issue_patterns`"
+        });
+    };
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return invalid("only `http` and `https` URLs are accepted");
+    }
+    if let Some(c) = issue.chars().find(|c| c.is_whitespace() || c.is_control()) {
+        return invalid(&format!("URLs can't contain {:?}", c));
+    }
+
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(end);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    // IPv6 addresses are bracketed as they contain colons themselves
+    let (host, port) = match host.find(']') {
+        Some(i)
+            if host.starts_with('[') && (i + 1 == host.len() || host[i + 1..].starts_with(':')) =>
+        {
+            (&host[..=i], host[i + 1..].strip_prefix(':'))
+        }
+        _ => match host.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host, None),
+        },
+    };
+    if let Some(port) = port {
+        if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+            return invalid(&format!("invalid port `{}`", port));
+        }
+    }
+
+    let valid_host = host.starts_with('[')
+        || (host == "localhost")
+        || (host.contains('.')
+            && host.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+            }));
+    if !valid_host {
+        return invalid(&format!("`{}` is not a valid host", host));
+    }
+    if path.trim_start_matches('/').is_empty() {
+        return invalid(&format!(
+            "the URL points to `{}` rather than an issue",
+            host
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls() {
+        for good in [
+            "https://github.com/DexterHill0/allow-until/issues/12",
+            "http://tracker.internal.example.com/browse/PLAT-12",
+            "HTTPS://gitlab.com/group/project/-/issues/3",
+            "https://localhost:8080/issues/1",
+            "https://user@jira.example.org/browse/A-1?focus=comments",
+        ] {
+            assert_eq!(check(good, &[]), Ok(()), "{}", good);
+        }
+
+        for (bad, why) in [
+            ("TODO", "expected an absolute http(s) URL"),
+            (
+                "github.com/org/repo/issues/1",
+                "expected an absolute http(s) URL",
+            ),
+            ("ftp://example.com/issue", "only `http` and `https`"),
+            ("https://", "`` is not a valid host"),
+            ("https://github/org/repo", "`github` is not a valid host"),
+            (
+                "https://github..com/org",
+                "`github..com` is not a valid host",
+            ),
+            (
+                "https://github.com",
+                "points to `github.com` rather than an issue",
+            ),
+            (
+                "https://github.com/",
+                "points to `github.com` rather than an issue",
+            ),
+            ("https://github.com:/org", "invalid port ``"),
+            ("https://github.com/org repo", "can't contain ' '"),
+        ] {
+            let e = check(bad, &[]).unwrap_err();
+            assert!(e.contains(why), "{}: {}", bad, e);
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        let patterns = [
+            Pattern::parse("{name}/{name}#{number}").unwrap(),
+            Pattern::parse("PLAT-{number}").unwrap(),
+        ];
+
+        for good in [
+            "DexterHill0/allow-until#12",
+            "rust-lang/rust.vim#1",
+            "PLAT-7",
+        ] {
+            assert_eq!(check(good, &patterns), Ok(()), "{}", good);
+        }
+        for bad in [
+            "allow-until#12",
+            "org/repo#",
+            "org/repo#12a",
+            "PLAT-",
+            "plat-7",
+        ] {
+            assert!(check(bad, &patterns)
+                .unwrap_err()
+                .contains("or a shorthand matching `issue_patterns`"));
+        }
+
+        assert!(Pattern::parse("{org}/{repo}").is_err());
+        assert!(Pattern::parse("{name").is_err());
+        assert!(Pattern::parse("").is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! The logic of [`allow-until`](https://crates.io/crates/allow-until) that doesn't depend on
+//! `proc_macro`, so it can be unit tested and reused by other tools such as `cargo allow-until`.
+//!
+//! The proc macro crate is an adapter around it: it converts spans and emits the diagnostics, while
+//! this crate parses the arguments of annotations from `proc_macro2` tokens, resolves the version
+//! they are compared against, evaluates their requirements and formats their messages.
+//!
+//! ```
+//! use allow_until_core::{args, message};
+//! use semver::Version;
+//!
+//! let parsed = args::parse(r#"version = ">=2.0", reason = "use `Modern`""#.parse().unwrap())
+//!     .unwrap();
+//! let (req, _, _) = parsed.version.unwrap();
+//!
+//! let current = Version::new(2, 1, 0);
+//! assert!(req.matches(&current));
+//! assert_eq!(
+//!     message::expired("struct `Legacy`", parsed.reason.as_deref(), &current, &req),
+//!     "use `Modern` (version 2.1.0 matches >=2.0)"
+//! );
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod args;
+pub mod changelog;
+pub mod code;
+pub mod config;
+pub mod fingerprint;
+pub mod hash;
+pub mod issue;
+pub mod lint;
+pub mod message;
+pub mod regex;
+pub mod scan;
+pub mod severity;
+pub mod suggest;
+pub mod version;
+pub mod wrapper;
@@ -0,0 +1,182 @@
+//! Lints about the arguments of annotations, which are on by default and can be allowed with
+//! `allow_lint`, or for the whole crate with `allow_lint` in `[package.metadata.allow-until]`.
+
+use semver::{Comparator, Op, Version, VersionReq};
+
+/// A lint that can be allowed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lint {
+    /// Requirements using bare versions, `^` or `~`, which have an upper bound.
+    ComparatorStyle,
+}
+
+impl Lint {
+    /// Every lint.
+    pub const ALL: &'static [Lint] = &[Lint::ComparatorStyle];
+
+    /// Parses the name of a lint, as given to `allow_lint`.
+    pub fn parse(s: &str) -> Result<Lint, String> {
+        Lint::ALL
+            .iter()
+            .copied()
+            .find(|lint| lint.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown lint `{}`, expected one of {}",
+                    s,
+                    Lint::ALL
+                        .iter()
+                        .map(|lint| format!("`{}`", lint.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+
+    /// The name of the lint.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lint::ComparatorStyle => "comparator_style",
+        }
+    }
+}
+
+/// A lint's message and help, which the caller reports as a warning.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// The message of the warning.
+    pub message: String,
+    /// How to fix or allow it.
+    pub help: String,
+}
+
+/// Lints a requirement written as `text` that uses caret or tilde comparators, bare versions
+/// being caret requirements, as annotations are usually meant to fire from a version on rather
+/// than only until the next major or minor release.
+pub fn comparator_style(req: &VersionReq, text: &str) -> Option<Finding> {
+    let c = req
+        .comparators
+        .iter()
+        .find(|c| matches!(c.op, Op::Caret | Op::Tilde))?;
+
+    let style = match c.op {
+        Op::Tilde => "`~` requirement",
+        _ if text
+            .trim_start()
+            .starts_with(|ch: char| ch.is_ascii_digit()) =>
+        {
+            "bare version, which semver treats as a `^` requirement,"
+        }
+        _ => "`^` requirement",
+    };
+
+    let explicit = req
+        .comparators
+        .iter()
+        .map(|c| match c.op {
+            Op::Caret | Op::Tilde => format!(">={}", partial(c)),
+            _ => c.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(Finding {
+        message: format!(
+            "`{}` is a {} matching only versions from {} up to, but excluding, {}",
+            text.trim(),
+            style,
+            lower_bound(c),
+            upper_bound(c)
+        ),
+        help: format!(
+            "use `\"{}\"` to match every version from {} on, or `allow_lint = \"{}\"` to keep it",
+            explicit,
+            lower_bound(c),
+            Lint::ComparatorStyle.as_str()
+        ),
+    })
+}
+
+/// The version of a comparator as written, e.g. `1.2` for `^1.2`.
+fn partial(c: &Comparator) -> String {
+    let mut version = c.major.to_string();
+    if let Some(minor) = c.minor {
+        version.push_str(&format!(".{}", minor));
+    }
+    if let Some(patch) = c.patch {
+        version.push_str(&format!(".{}", patch));
+    }
+    if !c.pre.is_empty() {
+        version.push_str(&format!("-{}", c.pre));
+    }
+    version
+}
+
+fn lower_bound(c: &Comparator) -> Version {
+    Version {
+        pre: c.pre.clone(),
+        ..Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0))
+    }
+}
+
+/// The first version a caret or tilde comparator no longer matches.
+fn upper_bound(c: &Comparator) -> Version {
+    match (c.op, c.minor, c.patch) {
+        (Op::Tilde, Some(minor), _) => Version::new(c.major, minor + 1, 0),
+        (Op::Tilde, None, _) => Version::new(c.major + 1, 0, 0),
+        _ if c.major > 0 => Version::new(c.major + 1, 0, 0),
+        (_, Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+        (_, Some(0), None) => Version::new(0, 1, 0),
+        (_, Some(minor), _) => Version::new(0, minor + 1, 0),
+        (_, None, _) => Version::new(1, 0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(req: &str) -> (String, String) {
+        let c = &VersionReq::parse(req).unwrap().comparators[0];
+        (lower_bound(c).to_string(), upper_bound(c).to_string())
+    }
+
+    #[test]
+    fn caret_bounds() {
+        assert_eq!(bounds("1.0"), ("1.0.0".into(), "2.0.0".into()));
+        assert_eq!(bounds("^1.2.3"), ("1.2.3".into(), "2.0.0".into()));
+        assert_eq!(bounds("^0.2"), ("0.2.0".into(), "0.3.0".into()));
+        assert_eq!(bounds("^0.0.3"), ("0.0.3".into(), "0.0.4".into()));
+        assert_eq!(bounds("^0.0"), ("0.0.0".into(), "0.1.0".into()));
+        assert_eq!(bounds("^0"), ("0.0.0".into(), "1.0.0".into()));
+    }
+
+    #[test]
+    fn tilde_bounds() {
+        assert_eq!(bounds("~1.2"), ("1.2.0".into(), "1.3.0".into()));
+        assert_eq!(bounds("~1.2.3"), ("1.2.3".into(), "1.3.0".into()));
+        assert_eq!(bounds("~1"), ("1.0.0".into(), "2.0.0".into()));
+    }
+
+    #[test]
+    fn findings() {
+        let lint = |req: &str| comparator_style(&VersionReq::parse(req).unwrap(), req);
+
+        assert_eq!(lint(">=1.0, <2"), None);
+        assert!(lint("1.0").unwrap().message.contains("bare version"));
+        assert!(lint("^1.0").unwrap().message.contains("`^` requirement"));
+        assert_eq!(
+            lint("~1.2, <1.2.5").unwrap().help,
+            "use `\">=1.2, <1.2.5\"` to match every version from 1.2.0 on, or \
+             `allow_lint = \"comparator_style\"` to keep it"
+        );
+    }
+
+    #[test]
+    fn lint_names() {
+        for &lint in Lint::ALL {
+            assert_eq!(Lint::parse(lint.as_str()), Ok(lint));
+        }
+        assert!(Lint::parse("style").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! The wording of the diagnostics about annotations, shared by the compiler diagnostics and the
+//! reports written for other tools.
+
+use semver::{Version, VersionReq};
+
+use crate::version::{self, Distance};
+
+/// The message of an annotation on `described`, e.g. "struct `Legacy`", whose requirement
+/// `req` matches `version`: its `reason`, or that the item is not allowed.
+pub fn expired(
+    described: &str,
+    reason: Option<&str>,
+    version: &Version,
+    req: &VersionReq,
+) -> String {
+    match reason {
+        None => format!(
+            "{} not allowed! (version {} matches {})",
+            described, version, req
+        ),
+        Some(r) => format!("{} (version {} matches {})", r, version, req),
+    }
+}
+
+/// The message of an annotation on `described` whose only condition is the requirement `req` on
+/// the version of a tool, such as Cargo or `protoc`, which `version` matches.
+pub fn expired_tool(
+    described: &str,
+    reason: Option<&str>,
+    tool: &str,
+    version: &Version,
+    req: &VersionReq,
+) -> String {
+    match reason {
+        None => format!(
+            "{} not allowed! ({} {} matches {})",
+            described, tool, version, req
+        ),
+        Some(r) => format!("{} ({} {} matches {})", r, tool, version, req),
+    }
+}
+
+/// The message of an annotation on `described` whose requirement `req` doesn't match `version`.
+pub fn allowed(described: &str, version: &Version, req: &VersionReq) -> String {
+    format!(
+        "{} is allowed until {} (version {})",
+        described, req, version
+    )
+}
+
+/// How long ago `req` first matched, e.g. "expired since 1.0.0, 4 minor releases ago". `None`
+/// when it doesn't match `version` or any earlier version.
+pub fn expired_since(req: &VersionReq, version: &Version) -> Option<String> {
+    let since = version::minimal_version(req).filter(|since| since <= version)?;
+
+    Some(match Distance::between(&since, version) {
+        Some(distance) => format!("expired since {}, {} ago", since, distance.releases()),
+        None => format!("expired since {}, the current version", since),
+    })
+}
+
+/// Splits a reason into its first line, which goes in the message, and the lines after it, which
+/// go in a note since rustc indents the continuation lines of notes but not of messages.
+pub fn first_line(reason: &str) -> (&str, Option<&str>) {
+    match reason.split_once('\n') {
+        Some((first, rest)) if !rest.trim().is_empty() => (first.trim_end(), Some(rest)),
+        _ => (reason.trim_end(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(req: &str) -> VersionReq {
+        VersionReq::parse(req).unwrap()
+    }
+
+    #[test]
+    fn expired_messages() {
+        let version = Version::new(2, 0, 0);
+        assert_eq!(
+            expired("struct `Legacy`", None, &version, &req(">=2.0")),
+            "struct `Legacy` not allowed! (version 2.0.0 matches >=2.0)"
+        );
+        assert_eq!(
+            expired(
+                "struct `Legacy`",
+                Some("use `Modern`"),
+                &version,
+                &req(">=2.0")
+            ),
+            "use `Modern` (version 2.0.0 matches >=2.0)"
+        );
+        assert_eq!(
+            expired_tool(
+                "struct `Legacy`",
+                None,
+                "Cargo",
+                &Version::new(1, 75, 0),
+                &req(">=1.70")
+            ),
+            "struct `Legacy` not allowed! (Cargo 1.75.0 matches >=1.70)"
+        );
+        assert_eq!(
+            expired_tool(
+                "fn `shim`",
+                Some("regenerate with protoc 25"),
+                "protoc",
+                &Version::new(25, 1, 0),
+                &req(">=25")
+            ),
+            "regenerate with protoc 25 (protoc 25.1.0 matches >=25)"
+        );
+        assert_eq!(
+            allowed("fn `old`", &Version::new(1, 4, 0), &req(">=2.0")),
+            "fn `old` is allowed until >=2.0 (version 1.4.0)"
+        );
+    }
+
+    #[test]
+    fn time_since_expiry() {
+        let since = |r, v| expired_since(&req(r), &Version::parse(v).unwrap());
+
+        assert_eq!(
+            since(">=1.0", "1.4.2").as_deref(),
+            Some("expired since 1.0.0, 4 minor releases ago")
+        );
+        assert_eq!(
+            since(">=1.4.1", "1.4.2").as_deref(),
+            Some("expired since 1.4.1, 1 patch release ago")
+        );
+        assert_eq!(
+            since(">=1.4", "1.4.0").as_deref(),
+            Some("expired since 1.4.0, the current version")
+        );
+        assert_eq!(since(">=2.0", "1.4.2"), None);
+    }
+
+    #[test]
+    fn reason_lines() {
+        assert_eq!(first_line("gone"), ("gone", None));
+        assert_eq!(first_line("gone\n"), ("gone", None));
+        assert_eq!(
+            first_line("gone\r\nuse `Modern`\nsoon"),
+            ("gone", Some("use `Modern`\nsoon"))
+        );
+    }
+}
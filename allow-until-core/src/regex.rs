@@ -0,0 +1,181 @@
+//! Regular expressions matched against whole strings, such as the branch names of
+//! `branch_pattern`. Patterns are parsed with `regex-syntax` and matched by backtracking, which is
+//! plenty for inputs as short as a branch name.
+//!
+//! ```
+//! let pattern = allow_until_core::regex::Regex::parse("release/.*").unwrap();
+//! assert!(pattern.is_match("release/1.x"));
+//! assert!(!pattern.is_match("main"));
+//! ```
+
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+
+/// A parsed regular expression.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    /// The pattern as written, for messages.
+    pub text: String,
+    hir: Hir,
+}
+
+impl PartialEq for Regex {
+    fn eq(&self, other: &Regex) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Regex {
+    /// Parses `text`, with the syntax of the `regex` crate.
+    pub fn parse(text: &str) -> Result<Regex, String> {
+        let hir = regex_syntax::parse(text)
+            .map_err(|e| format!("invalid pattern `{}`: {}", text, message(&e.to_string())))?;
+        Ok(Regex {
+            text: text.into(),
+            hir,
+        })
+    }
+
+    /// Whether the pattern matches the whole of `input`.
+    pub fn is_match(&self, input: &str) -> bool {
+        matches(&self.hir, input, 0, &mut |end| end == input.len())
+    }
+}
+
+/// The last line of a `regex-syntax` error, which comes after the pattern and a caret pointing
+/// into it.
+fn message(error: &str) -> &str {
+    error
+        .lines()
+        .last()
+        .unwrap_or(error)
+        .trim_start_matches("error: ")
+}
+
+/// Whether `hir` matches `input` from `at` up to an end accepted by `then`.
+fn matches(hir: &Hir, input: &str, at: usize, then: &mut dyn FnMut(usize) -> bool) -> bool {
+    match hir.kind() {
+        HirKind::Empty => then(at),
+        HirKind::Literal(literal) => {
+            input.as_bytes()[at..].starts_with(&literal.0) && then(at + literal.0.len())
+        }
+        // byte classes may stop within a character, which no character then matches
+        HirKind::Class(Class::Unicode(class)) => {
+            match input.get(at..).and_then(|s| s.chars().next()) {
+                Some(c)
+                    if class
+                        .ranges()
+                        .iter()
+                        .any(|r| r.start() <= c && c <= r.end()) =>
+                {
+                    then(at + c.len_utf8())
+                }
+                _ => false,
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => match input.as_bytes().get(at) {
+            Some(&b)
+                if class
+                    .ranges()
+                    .iter()
+                    .any(|r| r.start() <= b && b <= r.end()) =>
+            {
+                then(at + 1)
+            }
+            _ => false,
+        },
+        HirKind::Look(look) => looks(*look, input, at) && then(at),
+        HirKind::Capture(capture) => matches(&capture.sub, input, at, then),
+        HirKind::Concat(hirs) => concat(hirs, input, at, then),
+        HirKind::Alternation(hirs) => hirs.iter().any(|hir| matches(hir, input, at, then)),
+        HirKind::Repetition(rep) => {
+            repeat(&rep.sub, (rep.min, rep.max, rep.greedy), 0, input, at, then)
+        }
+    }
+}
+
+fn concat(hirs: &[Hir], input: &str, at: usize, then: &mut dyn FnMut(usize) -> bool) -> bool {
+    match hirs {
+        [] => then(at),
+        [first, rest @ ..] => matches(first, input, at, &mut |next| {
+            concat(rest, input, next, then)
+        }),
+    }
+}
+
+/// Matches `sub` repeatedly, `count` times having matched already.
+fn repeat(
+    sub: &Hir,
+    (min, max, greedy): (u32, Option<u32>, bool),
+    count: u32,
+    input: &str,
+    at: usize,
+    then: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    let done = count >= min;
+    if done && !greedy && then(at) {
+        return true;
+    }
+    // a repetition that matched nothing can't make progress, e.g. `(a*)*`
+    if max.is_none_or(|max| count < max)
+        && matches(sub, input, at, &mut |next| {
+            (next != at || !done) && repeat(sub, (min, max, greedy), count + 1, input, next, then)
+        })
+    {
+        return true;
+    }
+    done && greedy && then(at)
+}
+
+fn looks(look: Look, input: &str, at: usize) -> bool {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let before = word(input.get(..at).and_then(|s| s.chars().next_back()));
+    let after = word(input.get(at..).and_then(|s| s.chars().next()));
+    match look {
+        Look::Start => at == 0,
+        Look::End => at == input.len(),
+        Look::StartLF | Look::StartCRLF => at == 0 || input.as_bytes()[at - 1] == b'\n',
+        Look::EndLF | Look::EndCRLF => {
+            at == input.len() || matches!(input.as_bytes()[at], b'\r' | b'\n')
+        }
+        Look::WordAscii | Look::WordUnicode => before != after,
+        Look::WordAsciiNegate | Look::WordUnicodeNegate => before == after,
+        Look::WordStartAscii | Look::WordStartUnicode => !before && after,
+        Look::WordEndAscii | Look::WordEndUnicode => before && !after,
+        Look::WordStartHalfAscii | Look::WordStartHalfUnicode => !before,
+        Look::WordEndHalfAscii | Look::WordEndHalfUnicode => !after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, input: &str) -> bool {
+        Regex::parse(pattern).unwrap().is_match(input)
+    }
+
+    #[test]
+    fn whole_strings() {
+        assert!(is_match("release/.*", "release/1.x"));
+        assert!(is_match("release/.*", "release/"));
+        assert!(!is_match("release/.*", "old-release/1.x"));
+        assert!(!is_match("main", "main-2"));
+        assert!(is_match("main|master", "master"));
+        assert!(is_match("release/[0-9]+\\.x", "release/12.x"));
+        assert!(!is_match("release/[0-9]+\\.x", "release/.x"));
+        assert!(is_match("(feature|fix)/.+", "fix/parser"));
+        assert!(is_match("v?1\\.\\d{1,2}", "1.42"));
+        assert!(!is_match("v?1\\.\\d{1,2}", "v1.420"));
+        assert!(is_match("(a*)*b", "aaab"));
+        assert!(is_match(".*?-rc", "2.0-rc"));
+        assert!(is_match("^main$", "main"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert_eq!(
+            Regex::parse("release/(.*").unwrap_err(),
+            "invalid pattern `release/(.*`: unclosed group"
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Finding and evaluating the `#[allow_until(...)]` helper attributes on the fields and variants of
+//! a derive input, as `#[derive(AllowUntil)]` does, for other derive macros that want to honour
+//! them and report their findings with their own diagnostics.
+//!
+//! ```
+//! use allow_until_core::scan::{self, Config, Outcome};
+//! use semver::Version;
+//!
+//! let input = r#"
+//!     struct Options {
+//!         #[allow_until(version = ">=2.0", reason = "use `format`")]
+//!         legacy_mode: bool,
+//!     }
+//! "#;
+//! let findings = scan::scan_and_check(input.parse().unwrap(), &Config::new(Version::new(2, 1, 0)))
+//!     .unwrap();
+//!
+//! assert_eq!(findings[0].member.describe(), "field `Options::legacy_mode`");
+//! match &findings[0].outcome {
+//!     Outcome::Expired { message, .. } => {
+//!         assert_eq!(message, "use `format` (version 2.1.0 matches >=2.0)")
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! Only the annotation itself is evaluated: the crate's configuration isn't read, so
+//! `reason_key` doesn't give a reason and annotations with a `migration` are
+//! [`Outcome::Unresolved`], as are those a `cargo` or `tool_version` requirement decides, and
+//! environment variables such as `ALLOW_UNTIL_DOWNGRADE` are left to the caller.
+
+use proc_macro2::{Span, TokenStream};
+use semver::Version;
+use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Fields, Meta};
+
+use crate::{
+    args::{self, Parsed},
+    code::Code,
+    message,
+    severity::Severity,
+    version,
+};
+
+/// How annotations are evaluated.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The version requirements are compared against, such as the one
+    /// [`version::from_env`] resolves.
+    pub version: Version,
+    /// The severity of annotations that don't set one, [`Severity::Error`] by default.
+    pub severity: Severity,
+    /// The name of the helper attribute, `allow_until` by default.
+    pub attribute: String,
+}
+
+impl Config {
+    /// The default configuration, comparing requirements against `version`.
+    pub fn new(version: Version) -> Config {
+        Config {
+            version,
+            severity: Severity::Error,
+            attribute: "allow_until".into(),
+        }
+    }
+}
+
+/// Whether an annotated member is a field or a variant.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    /// A field of a struct, a union or a variant.
+    Field,
+    /// A variant of an enum.
+    Variant,
+}
+
+/// The member of the derive input an annotation is on.
+#[derive(Clone, Debug)]
+pub struct Member {
+    /// Whether it is a field or a variant.
+    pub kind: Kind,
+    /// Its path from the type, such as `Options::legacy`, `Shape::Circle::radius` or `Pair::0`.
+    pub path: String,
+    /// The span of its name, or of the type of a tuple field.
+    pub span: Span,
+}
+
+impl Member {
+    /// The member as named in messages, e.g. "field `Options::legacy`".
+    pub fn describe(&self) -> String {
+        let kind = match self.kind {
+            Kind::Field => "field",
+            Kind::Variant => "variant",
+        };
+        format!("{} `{}`", kind, self.path)
+    }
+}
+
+/// What evaluating an annotation found.
+pub enum Outcome {
+    /// The arguments are invalid, see [`Finding::args`].
+    Invalid,
+    /// The requirement comes from a migration of the crate's configuration, which isn't read, or
+    /// the outcome depends on the version of Cargo or of another tool.
+    Unresolved,
+    /// The requirement doesn't match, the member is still allowed.
+    Allowed,
+    /// The requirement matches, the member must be removed.
+    Expired {
+        /// The severity it is reported with, lowered to a warning during its grace period.
+        severity: Severity,
+        /// The message of the diagnostic, as `#[derive(AllowUntil)]` words it.
+        message: String,
+    },
+}
+
+/// An annotation on a member of the derive input.
+pub struct Finding {
+    /// The span of the attribute.
+    pub span: Span,
+    /// The member it is on.
+    pub member: Member,
+    /// Its arguments, or why they are invalid.
+    pub args: Result<Parsed, args::Error>,
+    /// What evaluating it found.
+    pub outcome: Outcome,
+}
+
+/// Evaluates the annotations on the fields and variants of `input`, the input of a derive macro,
+/// in source order. Only fails if `input` isn't a struct, enum or union.
+pub fn scan_and_check(input: TokenStream, config: &Config) -> Result<Vec<Finding>, syn::Error> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let parent = input.ident.to_string();
+    let mut findings = Vec::new();
+
+    match &input.data {
+        Data::Struct(data) => fields(&data.fields, &parent, config, &mut findings),
+        Data::Union(data) => fields(
+            &Fields::Named(data.fields.clone()),
+            &parent,
+            config,
+            &mut findings,
+        ),
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let member = Member {
+                    kind: Kind::Variant,
+                    path: format!("{}::{}", parent, variant.ident),
+                    span: variant.ident.span(),
+                };
+                attrs(&variant.attrs, &member, config, &mut findings);
+
+                let parent = member.path;
+                fields(&variant.fields, &parent, config, &mut findings);
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+fn fields(fields: &Fields, parent: &str, config: &Config, findings: &mut Vec<Finding>) {
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member {
+                kind: Kind::Field,
+                path: format!("{}::{}", parent, ident),
+                span: ident.span(),
+            },
+            None => Member {
+                kind: Kind::Field,
+                path: format!("{}::{}", parent, index),
+                span: field.ty.span(),
+            },
+        };
+        attrs(&field.attrs, &member, config, findings);
+    }
+}
+
+fn attrs(attrs: &[Attribute], member: &Member, config: &Config, findings: &mut Vec<Finding>) {
+    for attr in attrs {
+        if !attr.path().is_ident(&config.attribute) {
+            continue;
+        }
+
+        let args = match &attr.meta {
+            Meta::List(list) => args::parse(list.tokens.clone()),
+            // a bare `#[allow_until]` is missing its version, as `#[allow_until()]` is
+            Meta::Path(_) => args::parse(TokenStream::new()),
+            Meta::NameValue(nv) => Err(args::Error {
+                span: nv.eq_token.span,
+                code: Code::InvalidInput,
+                message: "expected arguments in parentheses".into(),
+                help: Some(format!(
+                    "write `#[{}(version = \"...\")]`",
+                    config.attribute
+                )),
+                note: None,
+            }),
+        };
+
+        // the version may only be missing when a migration gives it, or with `cargo` or `tool`
+        let args = args.and_then(|parsed| match parsed {
+            Parsed {
+                version: None,
+                migration: None,
+                cargo: None,
+                tool: None,
+                ..
+            } => Err(args::Error {
+                span: attr.span(),
+                code: Code::MissingVersion,
+                message: "missing required `version` argument".into(),
+                help: Some(format!(
+                    "annotations need at least a requirement, e.g. #[{}(version = \">=1.0\")]",
+                    config.attribute
+                )),
+                note: None,
+            }),
+            parsed => Ok(parsed),
+        });
+
+        findings.push(Finding {
+            span: attr.span(),
+            member: member.clone(),
+            outcome: match &args {
+                Ok(parsed) => evaluate(parsed, member, config),
+                Err(_) => Outcome::Invalid,
+            },
+            args,
+        });
+    }
+}
+
+fn evaluate(parsed: &Parsed, member: &Member, config: &Config) -> Outcome {
+    let current = &config.version;
+    let req = match &parsed.version {
+        Some((req, _, _)) if !req.matches(current) => return Outcome::Allowed,
+        // the versions of the Cargo building the embedding crate and of its tools aren't known
+        _ if parsed.cargo.is_some() || parsed.tool.is_some() => return Outcome::Unresolved,
+        Some((req, _, _)) => req,
+        None => return Outcome::Unresolved,
+    };
+
+    let in_grace = parsed
+        .grace
+        .zip(version::minimal_version(req))
+        .is_some_and(|(grace, boundary)| *current < grace.add_to(&boundary));
+    let severity = if in_grace {
+        Severity::Warning
+    } else {
+        parsed.severity.unwrap_or(config.severity)
+    };
+
+    Outcome::Expired {
+        severity,
+        message: message::expired(
+            &member.describe(),
+            parsed.reason.as_deref().map(|r| message::first_line(r).0),
+            current,
+            req,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::{quote, quote_spanned};
+
+    use super::*;
+
+    /// A derive that turns the findings into `compile_error!`s, as an embedder would.
+    fn fake_derive(input: TokenStream, version: &str) -> TokenStream {
+        let config = Config::new(Version::parse(version).unwrap());
+        let findings = match scan_and_check(input, &config) {
+            Ok(findings) => findings,
+            Err(e) => return e.to_compile_error(),
+        };
+
+        findings
+            .into_iter()
+            .filter_map(|finding| {
+                let message = match (&finding.args, finding.outcome) {
+                    (Err(e), _) => format!("{}: {}", finding.member.describe(), e.message),
+                    (_, Outcome::Expired { severity, message }) => {
+                        format!("{}: {}", severity.as_str(), message)
+                    }
+                    _ => return None,
+                };
+                Some(quote_spanned!(finding.span=> compile_error!(#message);))
+            })
+            .collect()
+    }
+
+    fn errors(output: TokenStream) -> Vec<String> {
+        syn::parse2::<syn::File>(output)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|item| match item {
+                syn::Item::Macro(m) => syn::parse2::<syn::LitStr>(m.mac.tokens).unwrap().value(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fields_and_variants() {
+        let input = quote! {
+            enum Shape {
+                #[allow_until(version = ">=2.0")]
+                Circle { radius: f64 },
+                Pair(
+                    u8,
+                    #[allow_until(version = ">=1.0", grace = "1 minor", reason = "use `u16`\nsee #4")]
+                    u8,
+                ),
+                #[serde(rename = "sq")]
+                #[allow_until(version = ">=3.0")]
+                Square,
+            }
+        };
+
+        assert_eq!(
+            errors(fake_derive(input.clone(), "2.0.0")),
+            [
+                "error: variant `Shape::Circle` not allowed! (version 2.0.0 matches >=2.0)",
+                "error: use `u16` (version 2.0.0 matches >=1.0)",
+            ]
+        );
+        assert_eq!(
+            errors(fake_derive(input, "1.0.0")),
+            ["warning: use `u16` (version 1.0.0 matches >=1.0)"]
+        );
+    }
+
+    #[test]
+    fn outcomes() {
+        let input = quote! {
+            struct Options {
+                #[allow_until(version = ">=2.0", severity = "warning", id = "legacy")]
+                legacy: bool,
+                #[allow_until(migration = "drop-xml")]
+                xml: bool,
+                #[allow_until(version = ">=3.0")]
+                later: bool,
+                #[allow_until]
+                bare: bool,
+                #[allow_until(cargo = ">=1.70")]
+                sparse: bool,
+                #[allow_until(version = ">=3.0", cargo = ">=1.70")]
+                both: bool,
+                #[allow_until(version = ">=2.0", tool = "protoc", tool_version = ">=25")]
+                protoc: bool,
+            }
+        };
+        let findings = scan_and_check(input, &Config::new(Version::new(2, 0, 0))).unwrap();
+
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|f| {
+                let outcome = match &f.outcome {
+                    Outcome::Invalid => "invalid",
+                    Outcome::Unresolved => "unresolved",
+                    Outcome::Allowed => "allowed",
+                    Outcome::Expired { severity, .. } => severity.as_str(),
+                };
+                (f.member.path.as_str(), outcome)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Options::legacy", "warning"),
+                ("Options::xml", "unresolved"),
+                ("Options::later", "allowed"),
+                ("Options::bare", "invalid"),
+                ("Options::sparse", "unresolved"),
+                ("Options::both", "allowed"),
+                ("Options::protoc", "unresolved"),
+            ]
+        );
+
+        assert_eq!(
+            findings[0].args.as_ref().unwrap().id.as_deref(),
+            Some("legacy")
+        );
+        let e = findings[3].args.as_ref().err().unwrap();
+        assert_eq!(e.code, Code::MissingVersion);
+    }
+
+    #[test]
+    fn custom_attribute() {
+        let input = quote! {
+            struct Options {
+                #[deprecated_until(version = ">=1.0")]
+                a: bool,
+                #[allow_until(version = ">=1.0")]
+                b: bool,
+                #[deprecated_until = ">=1.0"]
+                c: bool,
+            }
+        };
+        let config = Config {
+            attribute: "deprecated_until".into(),
+            ..Config::new(Version::new(1, 0, 0))
+        };
+
+        let findings = scan_and_check(input, &config).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].member.path, "Options::a");
+        assert_eq!(
+            findings[1].args.as_ref().err().unwrap().help.as_deref(),
+            Some("write `#[deprecated_until(version = \"...\")]`")
+        );
+    }
+
+    #[test]
+    fn not_a_derive_input() {
+        assert!(scan_and_check(
+            quote!(
+                fn f() {}
+            ),
+            &Config::new(Version::new(1, 0, 0))
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! How severely expired annotations are reported.
+
+/// The level of a diagnostic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Level {
+    /// Fails the build.
+    Error,
+    /// Reported without failing the build.
+    Warning,
+    /// Informational, such as summaries and traces.
+    Note,
+    /// A suggestion attached to another diagnostic.
+    Help,
+}
+
+/// The `severity` of an annotation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    /// Expiry is reported as a warning.
+    Warning,
+    /// Expiry fails the build, the default.
+    Error,
+}
+
+impl Severity {
+    /// Parses `"error"`, or `"warning"` and its alias `"warn"`.
+    pub fn parse(s: &str) -> Result<Severity, String> {
+        match s {
+            "error" => Ok(Severity::Error),
+            "warning" | "warn" => Ok(Severity::Warning),
+            _ => Err(format!(
+                "invalid severity `{}`, expected `error` or `warning`",
+                s
+            )),
+        }
+    }
+
+    /// The severity as written in arguments.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// The level of the diagnostics reporting expiry.
+    pub fn level(self) -> Level {
+        match self {
+            Severity::Warning => Level::Warning,
+            Severity::Error => Level::Error,
+        }
+    }
+}
+
+/// Which builds fail on expiry, from `enforce`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Enforce {
+    /// Every build, at the `severity` of the annotation.
+    Always,
+    /// Builds of a commit with a release tag fail, and the others only warn.
+    TaggedBuilds,
+}
+
+impl Enforce {
+    /// Parses `"always"` or `"tagged-builds"`.
+    pub fn parse(s: &str) -> Result<Enforce, String> {
+        match s {
+            "always" => Ok(Enforce::Always),
+            "tagged-builds" => Ok(Enforce::TaggedBuilds),
+            _ => Err(format!(
+                "invalid `enforce` value `{}`, expected `\"always\"` or `\"tagged-builds\"`",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do with expired annotations when the crate is being documented on docs.rs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Docsrs {
+    /// Expired annotations are not reported.
+    Ignore,
+    /// Expired annotations are reported with the given severity.
+    Severity(Severity),
+}
+
+impl Docsrs {
+    /// Parses `"ignore"` or a [`Severity`].
+    pub fn parse(s: &str) -> Result<Docsrs, String> {
+        match s {
+            "ignore" => Ok(Docsrs::Ignore),
+            s => Severity::parse(s).map(Docsrs::Severity).map_err(|_| {
+                format!(
+                    "invalid docs.rs handling `{}`, expected `ignore`, `warning` or `error`",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+/// Where the macros are being expanded, for the `contexts` table of the configuration.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Context {
+    /// The crate is being documented on docs.rs.
+    Docsrs,
+    /// The expansion is rust-analyzer's.
+    RustAnalyzer,
+    /// The expansion is clippy's, as for `cargo clippy`.
+    Clippy,
+    /// The crate is being built in CI.
+    Ci,
+    /// None of the others.
+    Local,
+}
+
+impl Context {
+    /// Every context, in the order they are detected in.
+    pub const ALL: [Context; 5] = [
+        Context::Docsrs,
+        Context::RustAnalyzer,
+        Context::Clippy,
+        Context::Ci,
+        Context::Local,
+    ];
+
+    /// Parses the name of a context, such as `"rust-analyzer"`.
+    pub fn parse(s: &str) -> Option<Context> {
+        Context::ALL
+            .into_iter()
+            .find(|context| context.as_str() == s)
+    }
+
+    /// The name of the context, as written in the `contexts` table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Context::Docsrs => "docsrs",
+            Context::RustAnalyzer => "rust-analyzer",
+            Context::Clippy => "clippy",
+            Context::Ci => "ci",
+            Context::Local => "local",
+        }
+    }
+}
+
+/// What `public_only` does with expired annotations on items that aren't `pub`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PublicOnly {
+    /// Expiry is reported as a warning, as `public_only = true` does.
+    Warning,
+    /// Expiry is not reported.
+    Ignore,
+}
+
+impl PublicOnly {
+    /// Parses `"warning"` (or `"warn"`) or `"ignore"`.
+    pub fn parse(s: &str) -> Result<PublicOnly, String> {
+        match s {
+            "warning" | "warn" => Ok(PublicOnly::Warning),
+            "ignore" => Ok(PublicOnly::Ignore),
+            _ => Err(format!(
+                "invalid `public_only` handling `{}`, expected `true`, `\"warning\"` or `\"ignore\"`",
+                s
+            )),
+        }
+    }
+}
+
+/// What happens instead of the error once an annotation expires, from `on_expiry`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OnExpiry {
+    /// The annotated `#[test]` function is ignored, with a warning.
+    IgnoreTest,
+}
+
+impl OnExpiry {
+    /// Parses `"ignore_test"`.
+    pub fn parse(s: &str) -> Result<OnExpiry, String> {
+        match s {
+            "ignore_test" => Ok(OnExpiry::IgnoreTest),
+            _ => Err(format!(
+                "invalid `on_expiry` action `{}`, expected `\"ignore_test\"`",
+                s
+            )),
+        }
+    }
+}
+
+/// When `attr_until` attaches its attribute, from `when`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum When {
+    /// While the requirement doesn't match, the default: the attribute is dropped at the version.
+    Unmatched,
+    /// Once the requirement matches: the attribute is added at the version.
+    Matched,
+}
+
+impl When {
+    /// Parses `"unmatched"` or `"matched"`.
+    pub fn parse(s: &str) -> Result<When, String> {
+        match s {
+            "unmatched" => Ok(When::Unmatched),
+            "matched" => Ok(When::Matched),
+            _ => Err(format!(
+                "invalid `when` value `{}`, expected `\"matched\"` or `\"unmatched\"`",
+                s
+            )),
+        }
+    }
+}
+
+/// The annotation `doctest_ignore_from` gives the Rust code blocks of the documentation, from
+/// `doctest_fence`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DoctestFence {
+    /// The examples are neither compiled nor run, the default.
+    #[default]
+    Ignore,
+    /// The examples are compiled but not run.
+    NoRun,
+}
+
+impl DoctestFence {
+    /// Parses `"ignore"` or `"no_run"`.
+    pub fn parse(s: &str) -> Result<DoctestFence, String> {
+        match s {
+            "ignore" => Ok(DoctestFence::Ignore),
+            "no_run" => Ok(DoctestFence::NoRun),
+            _ => Err(format!(
+                "invalid `doctest_fence` value `{}`, expected `\"ignore\"` or `\"no_run\"`",
+                s
+            )),
+        }
+    }
+
+    /// The annotation of the fence, as written after ```` ``` ````.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DoctestFence::Ignore => "ignore",
+            DoctestFence::NoRun => "no_run",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severities() {
+        assert_eq!(Severity::parse("error"), Ok(Severity::Error));
+        assert_eq!(Severity::parse("warn"), Ok(Severity::Warning));
+        assert_eq!(Severity::parse("warning").unwrap().level(), Level::Warning);
+        assert!(Severity::parse("fatal").is_err());
+    }
+
+    #[test]
+    fn docsrs_handling() {
+        assert_eq!(Docsrs::parse("ignore"), Ok(Docsrs::Ignore));
+        assert_eq!(
+            Docsrs::parse("warning"),
+            Ok(Docsrs::Severity(Severity::Warning))
+        );
+        assert_eq!(
+            Docsrs::parse("loud"),
+            Err("invalid docs.rs handling `loud`, expected `ignore`, `warning` or `error`".into())
+        );
+    }
+
+    #[test]
+    fn context_names() {
+        for context in Context::ALL {
+            assert_eq!(Context::parse(context.as_str()), Some(context));
+        }
+        assert_eq!(Context::parse("rust_analyzer"), None);
+    }
+
+    #[test]
+    fn public_only_handling() {
+        assert_eq!(PublicOnly::parse("warn"), Ok(PublicOnly::Warning));
+        assert_eq!(PublicOnly::parse("ignore"), Ok(PublicOnly::Ignore));
+        assert!(PublicOnly::parse("error").is_err());
+    }
+
+    #[test]
+    fn on_expiry_actions() {
+        assert_eq!(OnExpiry::parse("ignore_test"), Ok(OnExpiry::IgnoreTest));
+        assert!(OnExpiry::parse("ignore").is_err());
+    }
+}
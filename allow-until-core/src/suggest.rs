@@ -0,0 +1,53 @@
+//! "Did you mean" suggestions for misspelled names.
+
+/// The candidate closest to `name`, if it is close enough to be a likely typo: within an edit
+/// distance of a third of its length, and at least one.
+pub fn closest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|&c| (distance(name, c), c))
+        .filter(|&(d, c)| d <= (c.chars().count() / 3).max(1))
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, c)| c)
+}
+
+/// The Levenshtein distance between `a` and `b`, case-insensitively.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(distance("version", "version"), 0);
+        assert_eq!(distance("verson", "version"), 1);
+        assert_eq!(distance("Version", "version"), 0);
+        assert_eq!(distance("reasno", "reason"), 2);
+        assert_eq!(distance("", "id"), 2);
+    }
+
+    #[test]
+    fn closest_candidates() {
+        let names = &["version", "reason", "id", "version_source"];
+        assert_eq!(closest("verson", names), Some("version"));
+        assert_eq!(closest("versoin_source", names), Some("version_source"));
+        assert_eq!(closest("ix", names), Some("id"));
+        assert_eq!(closest("colour", names), None);
+    }
+}
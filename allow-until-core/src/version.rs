@@ -0,0 +1,945 @@
+//! The version annotations are compared against, and how requirements relate to it.
+
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+
+/// The variable that, when set, replaces `CARGO_PKG_VERSION` as the version to compare against.
+pub const OVERRIDE_VAR: &str = "ALLOW_UNTIL_VERSION";
+
+/// Where an annotation asks for the current version to be taken from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VersionSource {
+    /// `CARGO_PKG_VERSION`.
+    Cargo,
+    /// The most recent tag reported by `git describe --tags`, for crates whose `Cargo.toml`
+    /// version is a placeholder stamped at release time.
+    Git,
+}
+
+impl VersionSource {
+    /// Parses `"cargo"` or `"git"`.
+    pub fn parse(s: &str) -> Result<VersionSource, String> {
+        match s {
+            "cargo" => Ok(VersionSource::Cargo),
+            "git" => Ok(VersionSource::Git),
+            _ => Err(format!(
+                "invalid version source `{}`, expected `cargo` or `git`",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the current version was taken from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    /// [`OVERRIDE_VAR`].
+    Override,
+    /// `CARGO_PKG_VERSION`.
+    Cargo,
+    /// `CARGO_PKG_VERSION` was unparsable, so the version was rebuilt from
+    /// `CARGO_PKG_VERSION_MAJOR`/`_MINOR`/`_PATCH`.
+    CargoComponents,
+    /// The most recent git tag.
+    Git,
+    /// A key of a TOML or JSON file, from `version_from`.
+    File,
+}
+
+/// The format of a file read by `version_from`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// A `.toml` file.
+    Toml,
+    /// A `.json` file.
+    Json,
+}
+
+/// A file and the dotted key of the version in it, from
+/// `version_from = { file = "config.toml", key = "tool.api.version" }`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VersionFrom {
+    /// The file, relative to the package root.
+    pub file: String,
+    /// The path of tables to the version, such as `tool.api.version`.
+    pub key: String,
+    /// The format of the file, from its extension.
+    pub format: Format,
+}
+
+impl Format {
+    /// The format of `file` from its extension, or an error when it is neither a `.toml` nor a
+    /// `.json` file.
+    pub fn of(file: &str) -> Result<Format, String> {
+        match Path::new(file).extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("toml") => Ok(Format::Toml),
+            Some(e) if e.eq_ignore_ascii_case("json") => Ok(Format::Json),
+            _ => Err(format!(
+                "`{}` is neither a `.toml` nor a `.json` file, the formats `version_from` reads",
+                file
+            )),
+        }
+    }
+}
+
+/// Checks that `key` is a dotted key such as `package.version`, without empty segments.
+pub fn check_key(key: &str) -> Result<(), String> {
+    if key.split('.').any(|segment| segment.trim().is_empty()) {
+        return Err(format!(
+            "invalid key `{}`, expected names separated by dots, such as `package.version`",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// The version annotations are compared against.
+pub struct Current {
+    /// The version itself.
+    pub version: Version,
+    /// Where it was taken from.
+    pub source: Source,
+    /// The git tag the version was parsed from, for [`Source::Git`].
+    pub tag: Option<String>,
+    /// The file and key the version was read from, for [`Source::File`].
+    pub from: Option<VersionFrom>,
+}
+
+impl Current {
+    /// Where the version came from, e.g. "`CARGO_PKG_VERSION`".
+    pub fn describe_source(&self) -> String {
+        match self.source {
+            Source::Override => format!("the `{}` override", OVERRIDE_VAR),
+            Source::Cargo => "`CARGO_PKG_VERSION`".into(),
+            Source::CargoComponents => "`CARGO_PKG_VERSION_MAJOR`, `_MINOR` and `_PATCH`".into(),
+            Source::Git => format!("the git tag `{}`", self.tag.as_deref().unwrap_or_default()),
+            Source::File => match &self.from {
+                Some(from) => format!("`{}` in `{}`", from.key, from.file),
+                None => "a file".into(),
+            },
+        }
+    }
+}
+
+/// The version from the most recent tag of the git repository `dir` is in, with a leading `v`
+/// stripped, for [`VersionSource::Git`].
+pub fn from_git(dir: &Path) -> Result<Current, String> {
+    let output = std::process::Command::new("git")
+        .args(["describe", "--tags", "--abbrev=0"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run `git describe --tags`: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`git describe --tags` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let version = Version::parse(tag.strip_prefix('v').unwrap_or(&tag))
+        .map_err(|e| format!("the git tag `{}` is not a semver version: {}", tag, e))?;
+
+    Ok(Current {
+        version,
+        source: Source::Git,
+        tag: Some(tag),
+        from: None,
+    })
+}
+
+/// The version from [`OVERRIDE_VAR`], or else from `CARGO_PKG_VERSION`, falling back to its
+/// components when it doesn't parse. `Ok(None)` when neither is set, as outside of Cargo.
+pub fn from_env() -> Result<Option<Current>, String> {
+    from_vars(|key| std::env::var(key).ok())
+}
+
+/// [`from_env`] with the environment variables read through `var`, e.g. to declare them to the
+/// compiler.
+pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Current>, String> {
+    if let Some(v) = var(OVERRIDE_VAR) {
+        return Version::parse(trimmed(&v))
+            .map(|version| {
+                Some(Current {
+                    version,
+                    source: Source::Override,
+                    tag: None,
+                    from: None,
+                })
+            })
+            .map_err(|e| format!("invalid `{}` value `{}`: {}", OVERRIDE_VAR, v, e));
+    }
+
+    let Some(pkg_ver) = var("CARGO_PKG_VERSION") else {
+        return Ok(None);
+    };
+
+    match Version::parse(&pkg_ver) {
+        Ok(version) => Ok(Some(Current {
+            version,
+            source: Source::Cargo,
+            tag: None,
+            from: None,
+        })),
+        Err(e) => from_components(&var)
+            .map(|version| {
+                Some(Current {
+                    version,
+                    source: Source::CargoComponents,
+                    tag: None,
+                    from: None,
+                })
+            })
+            .ok_or_else(|| {
+                format!(
+                    "`CARGO_PKG_VERSION` value `{}` is not a valid semver version: {}",
+                    pkg_ver, e
+                )
+            }),
+    }
+}
+
+fn from_components(var: impl Fn(&str) -> Option<String>) -> Option<Version> {
+    let part = |name| var(name)?.parse::<u64>().ok();
+
+    Some(Version::new(
+        part("CARGO_PKG_VERSION_MAJOR")?,
+        part("CARGO_PKG_VERSION_MINOR")?,
+        part("CARGO_PKG_VERSION_PATCH")?,
+    ))
+}
+
+/// The unit of a [`Distance`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unit {
+    /// Major releases, e.g. 1.4.2 to 2.0.0.
+    Major,
+    /// Minor releases, e.g. 1.4.2 to 1.5.0.
+    Minor,
+    /// Patch releases, e.g. 1.4.2 to 1.4.3.
+    Patch,
+}
+
+impl Unit {
+    /// Parses `"major"`, `"minor"` or `"patch"`, as the precision of `match_precision`.
+    pub fn parse_precision(s: &str) -> Result<Unit, String> {
+        match s {
+            "major" => Ok(Unit::Major),
+            "minor" => Ok(Unit::Minor),
+            "patch" => Ok(Unit::Patch),
+            _ => Err(format!(
+                "invalid precision `{}`, expected `major`, `minor` or `patch`",
+                s
+            )),
+        }
+    }
+
+    /// `major`, `minor` or `patch`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Major => "major",
+            Unit::Minor => "minor",
+            Unit::Patch => "patch",
+        }
+    }
+}
+
+/// A distance between versions such as "1 minor" or "2 majors".
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Distance {
+    /// The number of releases.
+    pub amount: u64,
+    /// The kind of release counted.
+    pub unit: Unit,
+}
+
+impl Distance {
+    /// Parses an amount followed by a unit, e.g. `"1 minor"`, `"2 majors"` or `"3 patches"`.
+    pub fn parse(s: &str) -> Result<Distance, String> {
+        let invalid = || {
+            format!(
+                "invalid distance `{}`, expected e.g. `1 minor`, `2 majors` or `3 patches`",
+                s
+            )
+        };
+
+        let mut parts = s.split_whitespace();
+        let amount = parts
+            .next()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(invalid)?;
+        let unit = match parts.next() {
+            Some("major" | "majors") => Unit::Major,
+            Some("minor" | "minors") => Unit::Minor,
+            Some("patch" | "patches") => Unit::Patch,
+            _ => return Err(invalid()),
+        };
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(Distance { amount, unit })
+    }
+
+    /// The distance from `from` to the later `to`, in the most significant unit they differ in,
+    /// e.g. 2 minors from 1.0.3 to 1.2.0. `None` when they are the same release.
+    pub fn between(from: &Version, to: &Version) -> Option<Distance> {
+        let (amount, unit) = if to.major != from.major {
+            (to.major.checked_sub(from.major)?, Unit::Major)
+        } else if to.minor != from.minor {
+            (to.minor.checked_sub(from.minor)?, Unit::Minor)
+        } else {
+            (to.patch.checked_sub(from.patch)?, Unit::Patch)
+        };
+
+        (amount > 0).then_some(Distance { amount, unit })
+    }
+
+    /// "1 minor release" or "4 major releases".
+    pub fn releases(&self) -> String {
+        let unit = match self.unit {
+            Unit::Major => "major",
+            Unit::Minor => "minor",
+            Unit::Patch => "patch",
+        };
+
+        format!(
+            "{} {} release{}",
+            self.amount,
+            unit,
+            if self.amount == 1 { "" } else { "s" }
+        )
+    }
+
+    /// The first version more than `self` after `v`, e.g. 1.3.0 for "2 minors" after 1.0.3, as
+    /// 1.2.9 is only 2 minor releases after it.
+    pub fn exceeded_at(&self, v: &Version) -> Version {
+        Distance {
+            amount: self.amount + 1,
+            unit: self.unit,
+        }
+        .add_to(v)
+    }
+
+    /// The version `self` releases after `v`, e.g. 1.4.2 + "1 minor" is 1.5.0.
+    pub fn add_to(&self, v: &Version) -> Version {
+        match self.unit {
+            Unit::Major => Version::new(v.major + self.amount, 0, 0),
+            Unit::Minor => Version::new(v.major, v.minor + self.amount, 0),
+            Unit::Patch => Version::new(v.major, v.minor, v.patch + self.amount),
+        }
+    }
+}
+
+impl std::fmt::Display for Distance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match (self.unit, self.amount) {
+            (Unit::Major, 1) => "major",
+            (Unit::Major, _) => "majors",
+            (Unit::Minor, 1) => "minor",
+            (Unit::Minor, _) => "minors",
+            (Unit::Patch, 1) => "patch",
+            (Unit::Patch, _) => "patches",
+        };
+
+        write!(f, "{} {}", self.amount, unit)
+    }
+}
+
+/// Parses a requirement, reusing the result for text parsed before. Crates tend to repeat the same
+/// few requirements across many annotations, so most are only parsed once per compilation.
+pub fn parse_req(text: &str) -> Result<VersionReq, semver::Error> {
+    static PARSED: Mutex<BTreeMap<String, VersionReq>> = Mutex::new(BTreeMap::new());
+
+    let mut parsed = PARSED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(req) = parsed.get(text) {
+        return Ok(req.clone());
+    }
+
+    let req = VersionReq::parse(text).or_else(|e| match desugar(text) {
+        Some(desugared) => VersionReq::parse(&desugared).map_err(|_| e),
+        None => Err(e),
+    })?;
+    parsed.insert(text.into(), req.clone());
+    Ok(req)
+}
+
+/// Rewrites the comparators of `text` that semver rejects but that have an obvious meaning: an
+/// operator that includes every version in front of a lone wildcard, as in `>=*` or `<= x.x`,
+/// stands for the wildcard. `None` when there is nothing to rewrite. `>*` and `<*` match nothing,
+/// and a number after a wildcard, as in `>=1.x.2`, is ambiguous, so both are left as errors.
+fn desugar(text: &str) -> Option<String> {
+    let wildcard = |part: &str| matches!(part, "*" | "x" | "X");
+    let every = |comparator: &str| {
+        [">=", "<=", "=", "^", "~"]
+            .iter()
+            .find_map(|op| comparator.strip_prefix(op))
+            .is_some_and(|version| version.trim().split('.').all(wildcard))
+    };
+
+    let comparators: Vec<_> = text.split(',').map(str::trim).collect();
+    if !comparators.iter().any(|comparator| every(comparator)) {
+        return None;
+    }
+    // next to other comparators, one matching every version doesn't narrow anything down
+    let rest: Vec<_> = comparators
+        .into_iter()
+        .filter(|comparator| !every(comparator))
+        .collect();
+    Some(if rest.is_empty() {
+        "*".into()
+    } else {
+        rest.join(", ")
+    })
+}
+
+/// Parses a version, with a missing minor or patch number taken as 0, e.g. `1.4` as 1.4.0.
+/// Surrounding whitespace, such as the `\r` of a Windows line ending, and a byte order mark are
+/// ignored.
+pub fn parse_lenient(s: &str) -> Result<Version, String> {
+    let s = trimmed(s);
+    let padded = match s.split('.').count() {
+        1 => format!("{}.0.0", s),
+        2 => format!("{}.0", s),
+        _ => s.into(),
+    };
+    Version::parse(&padded).map_err(|e| format!("invalid version `{}`: {}", s, e))
+}
+
+/// The first version in the `--version` output of a tool, such as 25.1.0 for `libprotoc 25.1` or
+/// 1.21.0 for `go version go1.21.0 linux/amd64`: the first word whose leading number has a dot,
+/// after a prefix of letters such as `v`, or else the first word that is a number, as `v25`. Pre-release and
+/// build suffixes, and components after the patch number, are dropped, so `11.4.0-1ubuntu1` is
+/// 11.4.0.
+pub fn in_output(output: &str) -> Option<Version> {
+    // each number with whether it is a word of its own, as `25` or `v25` but not `amd64`
+    let numbers: Vec<(&str, bool)> = output
+        .split(|c: char| c.is_whitespace() || "(),;:/[]'\"".contains(c))
+        .filter_map(|word| {
+            let rest = word.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '-');
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            let number = rest[..end].trim_end_matches('.');
+            let alone =
+                end == rest.len() && matches!(&word[..word.len() - rest.len()], "" | "v" | "V");
+            (!number.is_empty() && !number.starts_with('.')).then_some((number, alone))
+        })
+        .collect();
+    let (number, _) = numbers
+        .iter()
+        .find(|(number, _)| number.contains('.'))
+        .or_else(|| numbers.iter().find(|&&(_, alone)| alone))?;
+
+    let mut parts = number.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some(Version::new(major, minor, patch))
+}
+
+/// `s` without surrounding whitespace and the byte order mark of a file it may have been read from.
+fn trimmed(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+}
+
+/// `version` with its components finer than `precision` zeroed, and without pre-release or build
+/// metadata, so that 1.4.7-rc.1 is 1.4.0 at minor precision.
+pub fn truncate(version: &Version, precision: Unit) -> Version {
+    match precision {
+        Unit::Major => Version::new(version.major, 0, 0),
+        Unit::Minor => Version::new(version.major, version.minor, 0),
+        Unit::Patch => version.clone(),
+    }
+}
+
+/// `req` with the components of its comparators finer than `precision` dropped, along with their
+/// pre-releases, so that `>=1.4.3` is `>=1.4` at minor precision.
+pub fn truncate_req(req: &VersionReq, precision: Unit) -> VersionReq {
+    if precision == Unit::Patch {
+        return req.clone();
+    }
+    let comparators = req
+        .comparators
+        .iter()
+        .map(|c| Comparator {
+            minor: c.minor.filter(|_| precision != Unit::Major),
+            patch: None,
+            pre: Prerelease::EMPTY,
+            ..c.clone()
+        })
+        .collect();
+    VersionReq { comparators }
+}
+
+/// The smallest version satisfying `req`, i.e. the version from which an annotation fires.
+pub fn minimal_version(req: &VersionReq) -> Option<Version> {
+    let mut min = Version::new(0, 0, 0);
+
+    for c in &req.comparators {
+        let (minor, patch) = (c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+
+        let lower = match c.op {
+            Op::Greater => match (c.minor, c.patch) {
+                (Some(_), Some(patch)) => Version::new(c.major, minor, patch + 1),
+                (Some(minor), None) => Version::new(c.major, minor + 1, 0),
+                _ => Version::new(c.major + 1, 0, 0),
+            },
+            Op::Less | Op::LessEq => continue,
+            _ => Version::new(c.major, minor, patch),
+        };
+
+        min = min.max(lower);
+    }
+
+    req.matches(&min).then_some(min)
+}
+
+/// The requirement extending the deadline of `req`, which `current` matches: its lower bounds
+/// replaced by the next major release after `current` if `req` expires at a major release, and by
+/// the next minor release otherwise, written as precisely as they were. Upper bounds are kept, and
+/// `None` is returned when they exclude the new deadline.
+pub fn extended(req: &VersionReq, current: &Version) -> Option<VersionReq> {
+    let boundary = minimal_version(req)?;
+    let target = if boundary.major > 0 && boundary.minor == 0 && boundary.patch == 0 {
+        Version::new(current.major + 1, 0, 0)
+    } else {
+        Version::new(current.major, current.minor + 1, 0)
+    };
+
+    let (lower, upper): (Vec<_>, Vec<_>) = req
+        .comparators
+        .iter()
+        .partition(|c| !matches!(c.op, Op::Less | Op::LessEq));
+    let mut comparators = vec![Comparator {
+        op: Op::GreaterEq,
+        major: target.major,
+        minor: (target.minor != 0 || lower.iter().any(|c| c.minor.is_some()))
+            .then_some(target.minor),
+        patch: lower.iter().any(|c| c.patch.is_some()).then_some(0),
+        pre: Prerelease::EMPTY,
+    }];
+    comparators.extend(upper.into_iter().cloned());
+
+    let extended = VersionReq { comparators };
+    (minimal_version(&extended) == Some(target)).then_some(extended)
+}
+
+/// `req` with every comparator moved `by` later, as [`Distance::add_to`] moves a version, written
+/// as precisely as it was, so that `>=2.0` is `>=3.0` and `>=1.4.2, <1.6` is `>=1.5.0, <1.7` after
+/// "1 major" and "1 minor" respectively. Pre-releases are dropped, as the shifted version has none.
+pub fn shifted(req: &VersionReq, by: &Distance) -> VersionReq {
+    let comparators = req
+        .comparators
+        .iter()
+        .map(|c| {
+            let to = by.add_to(&Version::new(
+                c.major,
+                c.minor.unwrap_or(0),
+                c.patch.unwrap_or(0),
+            ));
+            Comparator {
+                op: c.op,
+                major: to.major,
+                minor: c.minor.or((to.minor != 0).then_some(0)).map(|_| to.minor),
+                patch: c.patch.or((to.patch != 0).then_some(0)).map(|_| to.patch),
+                pre: Prerelease::EMPTY,
+            }
+        })
+        .collect();
+    VersionReq { comparators }
+}
+
+/// Whether every version satisfies `req`, such as `*` or `>=0.0.0`, in which case an annotation
+/// fires on every build.
+pub fn matches_every(req: &VersionReq) -> bool {
+    req.comparators.iter().all(|c| {
+        let zero = c.major == 0 && c.minor.unwrap_or(0) == 0 && c.patch.unwrap_or(0) == 0;
+        match c.op {
+            Op::GreaterEq => zero && c.pre.is_empty(),
+            // `0.*`, unlike `*` which has no comparators, doesn't match 1.0.0
+            _ => false,
+        }
+    })
+}
+
+/// Whether `req` is satisfied by `current` or any later version, i.e. whether an annotation can
+/// still fire. Prereleases of `current` count as the release itself.
+pub fn can_fire(req: &VersionReq, current: &Version) -> bool {
+    let mut from = req.clone();
+    from.comparators.push(Comparator {
+        op: Op::GreaterEq,
+        major: current.major,
+        minor: Some(current.minor),
+        patch: Some(current.patch),
+        pre: Prerelease::EMPTY,
+    });
+
+    minimal_version(&from).is_some()
+}
+
+/// The byte offset in `req` of the character `error` is about, which semver doesn't expose: the
+/// end of `req` for errors about missing input, and otherwise the last character of the shortest
+/// prefix that fails with the same error.
+pub fn error_offset(req: &str, error: &semver::Error) -> usize {
+    let message = error.to_string();
+    if message.contains("end of input") {
+        return req.trim_end().len();
+    }
+
+    req.char_indices()
+        .find(|&(i, c)| {
+            VersionReq::parse(&req[..i + c.len_utf8()]).is_err_and(|e| e.to_string() == message)
+        })
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal(req: &str) -> Option<String> {
+        minimal_version(&VersionReq::parse(req).unwrap()).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn minimal_version_of_lower_bounds() {
+        assert_eq!(minimal(">=1.4").as_deref(), Some("1.4.0"));
+        assert_eq!(minimal(">=1.4.2").as_deref(), Some("1.4.2"));
+        assert_eq!(minimal(">1.4").as_deref(), Some("1.5.0"));
+        assert_eq!(minimal(">1.4.2").as_deref(), Some("1.4.3"));
+        assert_eq!(minimal(">1").as_deref(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn minimal_version_of_caret_tilde_and_exact() {
+        assert_eq!(minimal("^1.2").as_deref(), Some("1.2.0"));
+        assert_eq!(minimal("1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(minimal("~0.3").as_deref(), Some("0.3.0"));
+        assert_eq!(minimal("~1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(minimal("=2.0.1").as_deref(), Some("2.0.1"));
+        assert_eq!(minimal("2.*").as_deref(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn minimal_version_of_ranges() {
+        assert_eq!(minimal(">=1.2, <2").as_deref(), Some("1.2.0"));
+        assert_eq!(minimal("<2").as_deref(), Some("0.0.0"));
+        assert_eq!(minimal(">=2, <1"), None);
+    }
+
+    #[test]
+    fn lenient_versions() {
+        let parse = |s| parse_lenient(s).map(|v| v.to_string());
+
+        assert_eq!(parse("1.4").as_deref(), Ok("1.4.0"));
+        assert_eq!(parse("2").as_deref(), Ok("2.0.0"));
+        assert_eq!(parse(" 1.4.2 ").as_deref(), Ok("1.4.2"));
+        assert_eq!(parse("\u{feff}2.1\r\n").as_deref(), Ok("2.1.0"));
+        assert_eq!(parse("1.4.0-beta.1").as_deref(), Ok("1.4.0-beta.1"));
+        assert!(parse("1.x").is_err());
+        assert!(parse("soon").is_err());
+    }
+
+    #[test]
+    fn versions_in_tool_output() {
+        let found = |output| in_output(output).map(|v| v.to_string());
+
+        for (output, version) in [
+            ("libprotoc 25.1\n", "25.1.0"),
+            ("libprotoc 3.21.12", "3.21.12"),
+            ("git version 2.39.2 (Apple Git-143)", "2.39.2"),
+            ("go version go1.21.0 linux/amd64", "1.21.0"),
+            ("node v20.5.0", "20.5.0"),
+            ("v18.17.1", "18.17.1"),
+            ("Python 3.11.4", "3.11.4"),
+            (
+                "cmake version 3.27.1\n\nCMake suite maintained by Kitware",
+                "3.27.1",
+            ),
+            ("Docker version 24.0.5, build ced0996", "24.0.5"),
+            ("terraform v1.5.7\non linux_amd64", "1.5.7"),
+            ("protoc-gen-go v1.31.0", "1.31.0"),
+            ("rustc 1.70.0 (90c541806 2023-05-31)", "1.70.0"),
+            ("cargo 1.72.0-nightly (0c14026aa 2023-06-14)", "1.72.0"),
+            (
+                "gcc (Ubuntu 11.4.0-1ubuntu1~22.04) 11.4.0\nCopyright (C) 2021",
+                "11.4.0",
+            ),
+            (
+                "clang version 16.0.6 (https://github.com/llvm/llvm-project 7cbf1a2)",
+                "16.0.6",
+            ),
+            (
+                "OpenSSL 3.0.2 15 Mar 2022 (Library: OpenSSL 3.0.2 15 Mar 2022)",
+                "3.0.2",
+            ),
+            ("javac 1.8.0_362", "1.8.0"),
+            ("GNU Make 4.3\nBuilt for x86_64-pc-linux-gnu", "4.3.0"),
+            ("openjdk 17.0.8 2023-07-18", "17.0.8"),
+            ("1.2.3.4", "1.2.3"),
+            ("jq-1.6", "1.6.0"),
+            ("protoc 25", "25.0.0"),
+        ] {
+            assert_eq!(found(output).as_deref(), Some(version), "{:?}", output);
+        }
+        assert_eq!(found("x86_64 amd64"), None);
+        assert_eq!(found("unknown option: --version"), None);
+        assert_eq!(found(""), None);
+    }
+
+    #[test]
+    fn parsed_requirements_are_reused() {
+        assert_eq!(
+            parse_req(">=1.2, <2").unwrap(),
+            VersionReq::parse(">=1.2, <2").unwrap()
+        );
+        assert_eq!(
+            parse_req(">=1.2, <2").unwrap(),
+            VersionReq::parse(">=1.2, <2").unwrap()
+        );
+        // errors aren't cached, each invalid requirement is reported
+        assert!(parse_req(">=1.x.2").is_err());
+        assert!(parse_req(">=1.x.2").is_err());
+    }
+
+    #[test]
+    fn wildcards_after_operators() {
+        let parse = |req| parse_req(req).map(|req| req.to_string()).ok();
+        let matches = |req, version| {
+            parse_req(req)
+                .unwrap()
+                .matches(&Version::parse(version).unwrap())
+        };
+
+        // the examples of the README
+        assert_eq!(parse(">= 1.0.x").as_deref(), Some(">=1.0"));
+        assert!(matches(">= 1.0.x", "1.0.0"));
+        assert!(!matches(">= 1.0.x", "0.9.9"));
+        assert_eq!(parse(">=1.0.*").as_deref(), Some(">=1.0"));
+        assert_eq!(parse(">= 1.0.*").as_deref(), Some(">=1.0"));
+        assert_eq!(parse(">=1.*.*").as_deref(), Some(">=1"));
+        assert_eq!(parse("<2.x").as_deref(), Some("<2"));
+        assert_eq!(parse(">=1.0.x, <2.x").as_deref(), Some(">=1.0, <2"));
+        assert_eq!(minimal(">1.0.x").as_deref(), Some("1.1.0"));
+
+        // an operator including every version in front of a lone wildcard is the wildcard
+        assert_eq!(parse(">=*").as_deref(), Some("*"));
+        assert_eq!(parse("<= x.x").as_deref(), Some("*"));
+        assert_eq!(parse(">=1.2, ^*").as_deref(), Some(">=1.2"));
+
+        // nothing is above or below every version, and a number after a wildcard is ambiguous
+        assert!(parse(">*").is_none());
+        assert!(parse("<*").is_none());
+        assert!(parse(">=1.x.0").is_none());
+    }
+
+    #[test]
+    fn requirements_matching_every_version() {
+        let every = |req: &str| matches_every(&VersionReq::parse(req).unwrap());
+
+        assert!(every("*"));
+        assert!(every(">=0.0.0"));
+        assert!(every(">=0"));
+        assert!(!every(">=1.0"));
+        assert!(!every(">=0.0.1"));
+        assert!(!every("<2"));
+        assert!(!every(">=0, <2"));
+        assert!(!every("0.*"));
+        assert!(!every("^0"));
+    }
+
+    #[test]
+    fn requirements_that_can_still_fire() {
+        let fires = |req: &str, current: &str| {
+            can_fire(
+                &VersionReq::parse(req).unwrap(),
+                &Version::parse(current).unwrap(),
+            )
+        };
+
+        assert!(fires(">=2.0", "1.2.0"));
+        assert!(fires("^1.0", "1.2.0"));
+        assert!(fires("<2", "1.2.0"));
+        assert!(fires("~1.2", "1.2.7"));
+        assert!(fires(">=1.3", "1.3.0-beta.1"));
+
+        assert!(!fires("<0.5", "1.2.0"));
+        assert!(!fires("<=1.1.9", "1.2.0"));
+        assert!(!fires("^1.0", "2.0.0"));
+        assert!(!fires("~1.1", "1.2.0"));
+        assert!(!fires("=1.1.0", "1.2.0"));
+        assert!(!fires("^0.2", "0.3.0"));
+        assert!(!fires(">=2, <1", "0.1.0"));
+    }
+
+    #[test]
+    fn extended_deadlines() {
+        let extended = |req: &str, current: &str| {
+            extended(
+                &VersionReq::parse(req).unwrap(),
+                &Version::parse(current).unwrap(),
+            )
+            .map(|req| req.to_string())
+        };
+
+        assert_eq!(extended(">=2.0", "2.0.0").as_deref(), Some(">=3.0"));
+        assert_eq!(extended(">=2", "2.4.1").as_deref(), Some(">=3"));
+        assert_eq!(extended(">=1.4", "1.4.0").as_deref(), Some(">=1.5"));
+        assert_eq!(extended(">=1.4.2", "1.6.0").as_deref(), Some(">=1.7.0"));
+        assert_eq!(extended(">1.3", "1.4.0").as_deref(), Some(">=1.5"));
+        assert_eq!(
+            extended(">=1.4, <3.0", "1.4.0").as_deref(),
+            Some(">=1.5, <3.0")
+        );
+
+        assert_eq!(extended(">=1.4, <1.5", "1.4.0"), None);
+        assert_eq!(extended(">=2, <1", "2.0.0"), None);
+    }
+
+    #[test]
+    fn shifted_requirements() {
+        let shifted = |req: &str, by: &str| {
+            shifted(
+                &VersionReq::parse(req).unwrap(),
+                &Distance::parse(by).unwrap(),
+            )
+            .to_string()
+        };
+
+        assert_eq!(shifted(">=2.0", "1 major"), ">=3.0");
+        assert_eq!(shifted(">=1.4.2", "1 major"), ">=2.0.0");
+        assert_eq!(shifted(">=1.4.2, <1.6", "1 minor"), ">=1.5.0, <1.7");
+        assert_eq!(shifted(">=2", "2 minors"), ">=2.2");
+        assert_eq!(shifted("^0.3.1", "1 patch"), "^0.3.2");
+        assert_eq!(shifted(">=2.0.0-rc.1", "1 major"), ">=3.0.0");
+        assert_eq!(shifted("*", "1 major"), "*");
+    }
+
+    #[test]
+    fn truncated_matching() {
+        let matches = |req: &str, current: &str, precision| {
+            truncate_req(&VersionReq::parse(req).unwrap(), precision)
+                .matches(&truncate(&Version::parse(current).unwrap(), precision))
+        };
+
+        // a patch in the requirement no longer delays it within the minor release
+        assert!(!matches(">=1.4.3", "1.4.1", Unit::Patch));
+        assert!(matches(">=1.4.3", "1.4.1", Unit::Minor));
+        assert!(!matches(">=1.4.3", "1.3.9", Unit::Minor));
+        assert!(matches(">=1.4.3", "1.0.0", Unit::Major));
+        // pre-releases count as their release
+        assert!(!matches(">=2.0", "2.0.0-rc.1", Unit::Patch));
+        assert!(matches(">=2.0", "2.0.0-rc.1", Unit::Minor));
+
+        let truncated = |req: &str, precision| {
+            truncate_req(&VersionReq::parse(req).unwrap(), precision).to_string()
+        };
+        assert_eq!(truncated(">=1.4.3, <2.0.1", Unit::Minor), ">=1.4, <2.0");
+        assert_eq!(truncated("=1.4.3-beta", Unit::Major), "=1");
+        assert_eq!(truncated(">=1.4.3", Unit::Patch), ">=1.4.3");
+
+        assert_eq!(Unit::parse_precision("minor"), Ok(Unit::Minor));
+        assert!(Unit::parse_precision("minors").is_err());
+    }
+
+    #[test]
+    fn distance_added_to_versions() {
+        let add = |distance: &str, v: &str| {
+            Distance::parse(distance)
+                .unwrap()
+                .add_to(&Version::parse(v).unwrap())
+                .to_string()
+        };
+
+        assert_eq!(add("1 minor", "1.4.2"), "1.5.0");
+        assert_eq!(add("2 patches", "1.4.2"), "1.4.4");
+        assert_eq!(add("1 major", "1.4.2"), "2.0.0");
+        assert_eq!(add("1 minor", "0.4.1"), "0.5.0");
+        assert_eq!(add("1 major", "0.9.3"), "1.0.0");
+        assert_eq!(add("1 minor", "2.0.0-rc.1"), "2.1.0");
+    }
+
+    #[test]
+    fn distance_exceeded() {
+        let exceeded = |distance: &str, since: &str, v: &str| {
+            let limit = Distance::parse(distance)
+                .unwrap()
+                .exceeded_at(&Version::parse(since).unwrap());
+            Version::parse(v).unwrap() >= limit
+        };
+
+        assert!(!exceeded("2 minors", "1.0.3", "1.2.9"));
+        assert!(exceeded("2 minors", "1.0.3", "1.3.0"));
+        assert!(exceeded("2 minors", "1.0.3", "2.0.0"));
+        assert!(!exceeded("2 minors", "1.0.3", "1.3.0-rc.1"));
+        assert!(!exceeded("1 major", "1.4.0", "2.9.0"));
+        assert!(exceeded("1 major", "1.4.0", "3.0.0"));
+        assert!(!exceeded("3 patches", "1.0.0", "1.0.3"));
+        assert!(exceeded("3 patches", "1.0.0", "1.0.4"));
+        assert!(exceeded("3 patches", "1.0.0", "1.1.0"));
+        assert!(!exceeded("1 minor", "0.4.0", "0.5.7"));
+        assert!(exceeded("1 minor", "0.4.0", "0.6.0"));
+    }
+
+    #[test]
+    fn distance_between_releases() {
+        let between = |from: &str, to: &str| {
+            Distance::between(&Version::parse(from).unwrap(), &Version::parse(to).unwrap())
+                .map(|d| d.releases())
+        };
+
+        assert_eq!(
+            between("1.0.0", "1.4.2").as_deref(),
+            Some("4 minor releases")
+        );
+        assert_eq!(
+            between("1.9.0", "3.0.0").as_deref(),
+            Some("2 major releases")
+        );
+        assert_eq!(
+            between("1.2.0", "1.2.1").as_deref(),
+            Some("1 patch release")
+        );
+        assert_eq!(between("1.2.0", "1.2.0"), None);
+        assert_eq!(between("2.0.0", "1.0.0"), None);
+    }
+
+    fn offset(req: &str) -> usize {
+        error_offset(req, &VersionReq::parse(req).unwrap_err())
+    }
+
+    #[test]
+    fn error_offsets() {
+        assert_eq!(offset("two"), 0);
+        assert_eq!(offset("=>1.0"), 1);
+        assert_eq!(offset(">=1.0,, <2"), 6);
+        assert_eq!(offset(">=1.0 <2"), 6);
+        assert_eq!(offset("1.0.0.0"), 5);
+        assert_eq!(offset(">=1.x.0"), 5);
+        assert_eq!(offset(">="), 2);
+        assert_eq!(offset(">=1.0, "), 6);
+    }
+
+    #[test]
+    fn parsed_distances() {
+        let parse = |s| Distance::parse(s).map(|d| d.to_string());
+
+        assert_eq!(parse("1 minor").as_deref(), Ok("1 minor"));
+        assert_eq!(parse(" 2  majors ").as_deref(), Ok("2 majors"));
+        assert_eq!(parse("3 patch").as_deref(), Ok("3 patches"));
+        assert!(parse("minor").is_err());
+        assert!(parse("1 minor 2").is_err());
+        assert!(parse("-1 minor").is_err());
+    }
+
+    #[test]
+    fn version_sources() {
+        assert_eq!(VersionSource::parse("cargo"), Ok(VersionSource::Cargo));
+        assert_eq!(VersionSource::parse("git"), Ok(VersionSource::Git));
+        assert!(VersionSource::parse("tag").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Attributes wrapping `allow_until` with an organisation's defaults, such as
+//! `#[acme_sunset(version = ">=2.0")]` applying `owner = "@acme/platform"` to every use that
+//! doesn't set an `owner` of its own.
+//!
+//! Proc macro crates can only export macros, so a wrapper is a proc macro crate of its own, which
+//! merges its [`Defaults`] into the arguments of each use with [`expand`]. That validates the
+//! defaults and re-emits the item under `#[allow_until::allow_until(...)]`, which evaluates the
+//! annotation and reports it as if it had been written out, so the crates using the wrapper
+//! depend on `allow-until` too. `examples/acme-sunset` in the repository is such a wrapper.
+//!
+//! Arguments given by a use win over the defaults, except that `tags` are combined and that
+//! `allow_lint` may be given by both. A default contradicting an argument of the use, such as
+//! `severity` with `silent = true`, is dropped.
+//!
+//! ```
+//! use allow_until_core::{args, wrapper::Defaults};
+//!
+//! let defaults = Defaults::new("acme_sunset")
+//!     .args(r#"owner = "@acme/platform", tags = ["acme"]"#.parse().unwrap())
+//!     .unwrap();
+//! let merged = defaults
+//!     .merge(r#"version = ">=2.0", tags = ["billing"]"#.parse().unwrap())
+//!     .unwrap();
+//!
+//! let parsed = args::parse(merged).unwrap();
+//! assert_eq!(parsed.owner.as_deref(), Some("@acme/platform"));
+//! assert_eq!(parsed.tags.unwrap(), ["acme", "billing"]);
+//! ```
+
+use proc_macro2::{Delimiter, Group, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+
+use crate::{
+    args::{self, Error, EXCLUSIVE_ARGS},
+    code::Code,
+};
+
+/// The arguments a wrapper applies to each of its uses, and the arguments each use must give.
+#[derive(Clone)]
+pub struct Defaults {
+    name: String,
+    attribute: TokenStream,
+    args: Vec<Arg>,
+    required: Vec<&'static str>,
+}
+
+/// An argument as written, from its name to the end of its value.
+#[derive(Clone)]
+struct Arg {
+    name: String,
+    value: TokenStream,
+    tokens: TokenStream,
+}
+
+impl Defaults {
+    /// No defaults, for the wrapper attribute `name`, such as `"acme_sunset"`, which diagnostics
+    /// about the wrapper itself refer to.
+    pub fn new(name: &str) -> Defaults {
+        Defaults {
+            name: name.into(),
+            attribute: quote!(::allow_until::allow_until),
+            args: Vec::new(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Applies the arguments `tokens`, written as those of an annotation, to each use that
+    /// doesn't give them. They are validated as an annotation's are.
+    pub fn args(mut self, tokens: TokenStream) -> Result<Defaults, Error> {
+        args::parse(tokens.clone())?;
+        self.args = split(tokens);
+        Ok(self)
+    }
+
+    /// Requires each use to give the arguments `names`, such as `reason` or `issue`, whether or
+    /// not the defaults do.
+    pub fn require(mut self, names: &[&'static str]) -> Defaults {
+        self.required.extend(names);
+        self
+    }
+
+    /// Re-emits the item under the attribute at `path` rather than `::allow_until::allow_until`,
+    /// for wrappers re-exporting it.
+    pub fn attribute(mut self, path: TokenStream) -> Defaults {
+        self.attribute = path;
+        self
+    }
+
+    /// The arguments of a use, `tokens`, with the defaults it doesn't override. Errors when it
+    /// lacks a required argument, leaving the rest of the validation to `allow_until`.
+    pub fn merge(&self, tokens: TokenStream) -> Result<TokenStream, Error> {
+        let mut merged = split(tokens);
+
+        if let Some(missing) = self
+            .required
+            .iter()
+            .find(|&&name| !merged.iter().any(|arg| arg.name == name))
+        {
+            return Err(Error {
+                span: Span::call_site(),
+                code: Code::MissingRequired,
+                message: format!("`{}` requires a `{}` argument", self.name, missing),
+                help: None,
+                note: None,
+            });
+        }
+
+        for default in &self.args {
+            let given = |name: &str| merged.iter().position(|arg| arg.name == name);
+            let contradicted = EXCLUSIVE_ARGS.iter().any(|&(a, b, _)| {
+                (default.name == a && given(b).is_some())
+                    || (default.name == b && given(a).is_some())
+            });
+            if contradicted {
+                continue;
+            }
+            match (default.name.as_str(), given(&default.name)) {
+                ("tags", Some(i)) => merged[i] = combine(default, &merged[i]),
+                ("allow_lint", _) | (_, None) => merged.push(default.clone()),
+                (_, Some(_)) => (),
+            }
+        }
+
+        let mut tokens = TokenStream::new();
+        for arg in merged {
+            tokens.extend(arg.tokens);
+            tokens.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        }
+        Ok(tokens)
+    }
+
+    /// The output of the wrapper given the arguments `args` of a use and the item `item`: the
+    /// item under `allow_until` with the merged arguments, or the error.
+    pub fn expand(&self, args: TokenStream, item: TokenStream) -> TokenStream {
+        let attribute = &self.attribute;
+        match self.merge(args) {
+            Ok(merged) => quote!(#[#attribute(#merged)] #item),
+            Err(e) => {
+                let error = e.to_compile_error();
+                quote!(#error #item)
+            }
+        }
+    }
+}
+
+impl Error {
+    /// A `compile_error!` at the span of the error, with its help and note after the message.
+    pub fn to_compile_error(&self) -> TokenStream {
+        let mut message = format!("[{}] {}", self.code.as_str(), self.message);
+        if let Some((_, note)) = &self.note {
+            message.push_str(&format!("\nnote: {}", note));
+        }
+        if let Some(help) = &self.help {
+            message.push_str(&format!("\nhelp: {}", help));
+        }
+        quote_spanned!(self.span=> ::core::compile_error! { #message })
+    }
+}
+
+/// Splits `tokens` into arguments. An argument starts with a name and `=`, so the commas of an
+/// unquoted requirement such as `>=1.4, <2` stay in its value.
+fn split(tokens: TokenStream) -> Vec<Arg> {
+    let mut args: Vec<Arg> = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while tokens.peek().is_some() {
+        let mut segment = Vec::new();
+        for token in tokens.by_ref() {
+            if matches!(&token, TokenTree::Punct(comma) if comma.as_char() == ',') {
+                break;
+            }
+            segment.push(token);
+        }
+
+        match (&segment[..], args.last_mut()) {
+            ([TokenTree::Ident(name), TokenTree::Punct(eq), value @ ..], _)
+                if eq.as_char() == '=' =>
+            {
+                args.push(Arg {
+                    name: name.to_string(),
+                    value: value.iter().cloned().collect(),
+                    tokens: segment.iter().cloned().collect(),
+                })
+            }
+            (_, Some(last)) => {
+                let rest: TokenStream = segment.into_iter().collect();
+                let comma = TokenTree::Punct(Punct::new(',', Spacing::Alone));
+                last.value
+                    .extend([comma.clone()].into_iter().chain(rest.clone()));
+                last.tokens.extend([comma].into_iter().chain(rest));
+            }
+            // not an argument, which `allow_until` reports
+            (_, None) => args.push(Arg {
+                name: String::new(),
+                value: TokenStream::new(),
+                tokens: segment.into_iter().collect(),
+            }),
+        }
+    }
+    args
+}
+
+/// The `tags` of a use after those of the defaults, each a list or a single string.
+fn combine(default: &Arg, given: &Arg) -> Arg {
+    let elements = |value: &TokenStream| {
+        let mut tokens = value.clone().into_iter();
+        match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Group(list)), None) if list.delimiter() == Delimiter::Bracket => {
+                list.stream()
+            }
+            _ => value.clone(),
+        }
+    };
+    let mut inner = elements(&default.value);
+    inner.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+    inner.extend(elements(&given.value));
+
+    let span = given
+        .value
+        .clone()
+        .into_iter()
+        .next()
+        .map_or(Span::call_site(), |t| t.span());
+    let mut list = Group::new(Delimiter::Bracket, inner);
+    list.set_span(span);
+
+    let mut tokens: Vec<TokenTree> = given.tokens.clone().into_iter().take(2).collect();
+    tokens.push(TokenTree::Group(list.clone()));
+    Arg {
+        name: given.name.clone(),
+        value: TokenTree::Group(list).into(),
+        tokens: tokens.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Defaults {
+        Defaults::new("acme_sunset")
+            .args(
+                r#"severity = "warning", owner = "@acme/platform", tags = "acme",
+                   allow_lint = "comparator_style""#
+                    .parse()
+                    .unwrap(),
+            )
+            .unwrap()
+    }
+
+    fn merge(args: &str) -> args::Parsed {
+        args::parse(defaults().merge(args.parse().unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn defaults_apply() {
+        let parsed = merge(r#"version = ">=2.0""#);
+        assert_eq!(parsed.owner.as_deref(), Some("@acme/platform"));
+        assert_eq!(parsed.tags.unwrap(), ["acme"]);
+        assert_eq!(parsed.allow_lint.len(), 1);
+        assert!(parsed.severity.is_some());
+    }
+
+    #[test]
+    fn uses_win() {
+        let parsed = merge(
+            r#"version = >=1.4, <2, owner = "@billing", tags = ["billing", "v2"],
+               allow_lint = "comparator_style""#,
+        );
+        assert_eq!(parsed.version.unwrap().0.to_string(), ">=1.4, <2");
+        assert_eq!(parsed.owner.as_deref(), Some("@billing"));
+        assert_eq!(parsed.tags.unwrap(), ["acme", "billing", "v2"]);
+        assert_eq!(parsed.allow_lint.len(), 2);
+    }
+
+    #[test]
+    fn contradicted_defaults_are_dropped() {
+        let parsed = merge(r#"version = ">=2.0", silent = true"#);
+        assert!(parsed.silent);
+        assert!(parsed.severity.is_none());
+    }
+
+    #[test]
+    fn invalid_defaults() {
+        let e = Defaults::new("acme_sunset")
+            .args(r#"severity = "loud""#.parse().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(e.code, Code::InvalidInput);
+    }
+
+    #[test]
+    fn required() {
+        let defaults = defaults().require(&["reason"]);
+        let e = defaults
+            .merge(r#"version = ">=2.0""#.parse().unwrap())
+            .unwrap_err();
+        assert_eq!(e.message, "`acme_sunset` requires a `reason` argument");
+        assert!(e
+            .to_compile_error()
+            .to_string()
+            .contains("[AU0007] `acme_sunset` requires a `reason` argument"));
+
+        defaults
+            .merge(r#"version = ">=2.0", reason = "use v2""#.parse().unwrap())
+            .unwrap();
+    }
+}
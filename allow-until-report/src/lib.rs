@@ -0,0 +1,358 @@
+//! The types of the report [`allow-until`](https://crates.io/crates/allow-until) appends to the
+//! file named by `ALLOW_UNTIL_REPORT`, one JSON [`Annotation`] per line, or to a file of each
+//! package in the directory it names, which [`Report::merge`] combines into one.
+//!
+//! The proc macro serializes its entries with these types, so reading a report with them never
+//! depends on guessing field names.
+//!
+//! ```
+//! use allow_until_report::Report;
+//!
+//! let report = Report::parse(
+//!     r#"{"schema_version":2,"requirement":">=2.0","version":"2.1.0","matched":true,"severity":"error","silent":false,"reason":"use `Json`","id":"drop-xml","tags":[],"owner":null,"issue":null,"migration":null,"kind":"struct","item":"XmlConfig","symbols":[],"file":"src/lib.rs","line":12,"column":1,"blame":null}"#,
+//! )
+//! .unwrap();
+//!
+//! let expired: Vec<_> = report.expired().collect();
+//! assert_eq!(expired[0].item.as_deref(), Some("XmlConfig"));
+//! assert_eq!(expired[0].condition.requirement, ">=2.0");
+//! ```
+//!
+//! # Compatibility
+//!
+//! Every entry carries the [`SCHEMA_VERSION`] of the layout it was written with:
+//!
+//! - Fields may be added without changing the schema version, so consumers must ignore fields
+//!   they don't know, as the types of this crate do.
+//! - Removing or renaming a field, changing its type or the meaning of its values increments the
+//!   schema version, along with the major version of this crate.
+//! - Entries written before the schema version was recorded have the layout of version 1, and
+//!   deserialize as such.
+
+#![warn(missing_docs)]
+
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the layout of the entries written by this version of the crate.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The [`file`](Annotation::file) of annotations whose source file isn't known, such as those in
+/// code generated by other macros.
+pub const UNKNOWN_FILE: &str = "unknown";
+
+/// The entries of a report, in the order they were appended.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// One entry per evaluated annotation.
+    pub annotations: Vec<Annotation>,
+}
+
+impl Report {
+    /// Reads the entries of a report, one per line. Blank lines are skipped.
+    pub fn parse(text: &str) -> Result<Report, ParseError> {
+        let annotations = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                Annotation::parse(line).map_err(|error| ParseError { line: i + 1, error })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Report { annotations })
+    }
+
+    /// Combines the reports of the packages of a workspace, given with the name of their package,
+    /// such as the files `ALLOW_UNTIL_REPORT` writes to a directory, into one report.
+    ///
+    /// Entries without a [`package`](Annotation::package) get the one of their report, and entries
+    /// identical to an earlier one, as written by several targets of a package, are dropped.
+    pub fn merge(reports: impl IntoIterator<Item = (String, Report)>) -> Report {
+        let mut seen = HashSet::new();
+        let mut annotations = Vec::new();
+
+        for (package, report) in reports {
+            for mut annotation in report.annotations {
+                annotation.package.get_or_insert_with(|| package.clone());
+                if seen.insert(annotation.to_line()) {
+                    annotations.push(annotation);
+                }
+            }
+        }
+        Report { annotations }
+    }
+
+    /// The annotations whose requirement matched the version they were compared against, except
+    /// for `changes_at`, whose items stay.
+    pub fn expired(&self) -> impl Iterator<Item = &Annotation> {
+        self.annotations
+            .iter()
+            .filter(|a| a.outcome.matched && a.annotation_kind == AnnotationKind::AllowUntil)
+    }
+
+    /// The annotations in their warning window, which are about to be removed or only warn about
+    /// their expiry for now.
+    pub fn in_window(&self) -> impl Iterator<Item = &Annotation> {
+        self.annotations.iter().filter(|a| a.outcome.window)
+    }
+}
+
+/// The report as [`Report::parse`] reads it, one line per entry.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for annotation in &self.annotations {
+            writeln!(f, "{}", annotation.to_line())?;
+        }
+        Ok(())
+    }
+}
+
+/// A line of a report that isn't a valid entry.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The 1-based number of the line.
+    pub line: usize,
+    /// Why the line isn't a valid entry.
+    pub error: serde_json::Error,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} is not a valid entry: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The entry of an evaluated annotation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// The layout the entry was written with, see [the compatibility rules](crate#compatibility).
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    /// The attribute the entry is for, `allow_until` in entries written before it was recorded.
+    #[serde(default)]
+    pub annotation_kind: AnnotationKind,
+    /// The requirement and the version it was compared against.
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// How the annotation was evaluated.
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    /// The `reason` argument.
+    pub reason: Option<String>,
+    /// The `id` argument.
+    pub id: Option<String>,
+    /// The `tags` argument.
+    pub tags: Vec<String>,
+    /// The `owner` argument.
+    pub owner: Option<String>,
+    /// The `issue` argument.
+    pub issue: Option<String>,
+    /// The migration the annotation refers to.
+    pub migration: Option<String>,
+    /// The fingerprint identifying the annotation when it has no `id`, such as
+    /// `au-3f0c8e1d52a9b6e4`, `None` in entries written before it was recorded.
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// The name of the package the annotation is in, `None` in entries written before it was
+    /// recorded.
+    #[serde(default)]
+    pub package: Option<String>,
+    /// The kind of the annotated item, e.g. `struct`.
+    pub kind: String,
+    /// The name of the annotated item, such as `Options::legacy` for a field.
+    pub item: Option<String>,
+    /// Whether the item is in the public API listed by `ALLOW_UNTIL_PUBLIC_API`, `None` without a
+    /// listing or when the item couldn't be matched against it.
+    #[serde(default)]
+    pub is_public_api: Option<bool>,
+    /// The FFI symbols of the item.
+    pub symbols: Vec<Symbol>,
+    /// The source file of the annotation, relative to the workspace root with forward slashes, or
+    /// [`UNKNOWN_FILE`]. Version 1 entries have the path given to the compiler, which may be
+    /// absolute.
+    pub file: String,
+    /// The 1-based line of the annotation, 0 when its file is unknown.
+    pub line: usize,
+    /// The 1-based column of the annotation, 0 when its file is unknown or in version 1 entries.
+    #[serde(default)]
+    pub column: usize,
+    /// Where the source invoked the macro that generated the annotation, when another macro did,
+    /// such as a `macro_rules!` macro writing it into its output. `None` for annotations written
+    /// in the source, without the `nightly` feature, which can't tell the two apart, and in
+    /// entries written before it was recorded.
+    #[serde(default)]
+    pub origin: Option<Origin>,
+    /// Who added the annotation, with `ALLOW_UNTIL_BLAME=1`.
+    pub blame: Option<Blame>,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+impl Annotation {
+    /// Reads an entry from a line of a report.
+    pub fn parse(line: &str) -> Result<Annotation, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    /// The entry as a line of a report, without the line break.
+    ///
+    /// Fields are sorted by name, so identical entries are identical lines.
+    pub fn to_line(&self) -> String {
+        serde_json::to_value(self)
+            .expect("entries serialize to JSON")
+            .to_string()
+    }
+}
+
+/// The requirement of an annotation and the version it was compared against.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    /// The `version` requirement, such as `>=2.0`.
+    pub requirement: String,
+    /// The version the requirement was compared against, such as `2.1.0`.
+    pub version: String,
+}
+
+/// How an annotation was evaluated.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    /// Whether the requirement matched, i.e. the annotation has expired.
+    pub matched: bool,
+    /// The configured severity, regardless of whether the annotation has expired.
+    pub severity: Severity,
+    /// Whether the annotation has `silent = true`, and is never reported.
+    pub silent: bool,
+    /// Whether the annotation is in its warning window: expired but within its `grace` period, or
+    /// about to expire within the configured `warn_ahead`. `false` in entries written before it
+    /// was recorded.
+    #[serde(default)]
+    pub window: bool,
+    /// Whether `ALLOW_UNTIL_STRIP=1` left the expired annotation's item out of the expansion.
+    /// `false` in entries written before it was recorded.
+    #[serde(default)]
+    pub stripped: bool,
+}
+
+/// The attribute of an entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// `allow_until`, or a member annotation of the derive or a group, which removes its item once
+    /// the requirement matches.
+    #[default]
+    AllowUntil,
+    /// `changes_at`, which changes the behavior of its item once the requirement matches. Its
+    /// `note` is the [`reason`](Annotation::reason) of the entry.
+    ChangesAt,
+}
+
+/// The severity of an annotation once it has expired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Expiring fails the build.
+    Error,
+    /// Expiring only warns.
+    Warning,
+}
+
+/// An FFI symbol of an annotated item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    /// The name of the symbol.
+    pub name: String,
+    /// The calling convention, for functions.
+    pub abi: Option<String>,
+    /// Whether the symbol comes from an `extern` block rather than being exported.
+    pub imported: bool,
+}
+
+/// The location in the source of the outermost macro invocation an annotation was generated by.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Origin {
+    /// The source file, as [`file`](Annotation::file) is, or [`UNKNOWN_FILE`] when the
+    /// invocation isn't in the package or its workspace.
+    pub file: String,
+    /// The 1-based line, 0 when the file is unknown.
+    pub line: usize,
+    /// The 1-based column, 0 when the file is unknown.
+    pub column: usize,
+}
+
+/// The commit that added an annotation, from `git blame`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blame {
+    /// The name of the author.
+    pub author: String,
+    /// The email of the author.
+    pub email: String,
+    /// The commit date in UTC, such as `2024-03-01T12:00:00Z`.
+    pub date: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: &str = r#"{"annotation_kind":"allow_until","blame":null,"column":5,"file":"src/lib.rs","fingerprint":null,"id":null,"is_public_api":null,"issue":null,"item":"Options::legacy","kind":"field","line":7,"matched":false,"migration":null,"origin":null,"owner":"platform","package":"config","reason":null,"requirement":">=3.0","schema_version":2,"severity":"warning","silent":false,"stripped":false,"symbols":[],"tags":["config"],"version":"2.1.0","window":false}"#;
+
+    #[test]
+    fn round_trip() {
+        let annotation = Annotation::parse(ENTRY).unwrap();
+        assert_eq!(annotation.condition.requirement, ">=3.0");
+        assert_eq!(annotation.outcome.severity, Severity::Warning);
+        assert_eq!(annotation.to_line(), ENTRY);
+    }
+
+    #[test]
+    fn unknown_fields_and_legacy_entries() {
+        let legacy = ENTRY
+            .replace(r#""schema_version":2,"#, r#""added_later":[1],"#)
+            .replace(r#""annotation_kind":"allow_until","#, "")
+            .replace(r#""column":5,"#, "")
+            .replace(r#","window":false"#, "")
+            .replace(r#""stripped":false,"#, "");
+        let legacy = Annotation::parse(&legacy).unwrap();
+        assert_eq!((legacy.schema_version, legacy.column), (1, 0));
+        assert_eq!(legacy.annotation_kind, AnnotationKind::AllowUntil);
+    }
+
+    #[test]
+    fn merge() {
+        let legacy = ENTRY.replace(r#""package":"config","#, "");
+        let other = ENTRY.replace(r#""line":7"#, r#""line":9"#);
+        let merged = Report::merge([
+            (
+                "config".into(),
+                Report::parse(&format!("{}\n{}", legacy, ENTRY)).unwrap(),
+            ),
+            (
+                "config".into(),
+                Report::parse(&format!("{}\n{}", ENTRY, other)).unwrap(),
+            ),
+        ]);
+
+        // the entry without a package is the same as the one with, once it has the report's
+        assert_eq!(merged.to_string(), format!("{}\n{}\n", ENTRY, other));
+        assert_eq!(Report::parse(&merged.to_string()).unwrap(), merged);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let e = Report::parse(&format!("{}\n\n{{\"matched\":true}}\n", ENTRY)).unwrap_err();
+        assert_eq!(e.line, 3);
+        assert!(e
+            .to_string()
+            .starts_with("line 3 is not a valid entry: missing field"));
+    }
+}
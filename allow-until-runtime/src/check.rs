@@ -0,0 +1,85 @@
+//! Requirements evaluated against a version only known at runtime, such as the version of the host
+//! a plugin is loaded into, rather than against the crate's own version at compile time.
+//!
+//! [`runtime_check!`](crate::runtime_check) validates its requirement at compile time, as
+//! annotations do, and expands to a call to [`check`]. [`warn_if_expired!`](crate::warn_if_expired)
+//! expands to a call to [`warn_if_expired`], which reports an expired requirement instead of
+//! returning it.
+//!
+//! ```
+//! use allow_until_runtime::{check::Expired, runtime_check, semver::Version};
+//!
+//! let host_version = Version::new(2, 1, 0);
+//! let Err(expired) = runtime_check!(host_version, ">=2.0", "legacy hook API") else {
+//!     unreachable!();
+//! };
+//! assert_eq!(expired.to_string(), "legacy hook API (version 2.1.0 matches >=2.0)");
+//! ```
+
+use std::{error::Error, fmt, string::ToString};
+
+use semver::{Version, VersionReq};
+
+/// A requirement that matches the version it was checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expired {
+    /// The requirement, such as `>=2.0`.
+    pub requirement: VersionReq,
+    /// What is going away, such as `"legacy hook API"`.
+    pub reason: &'static str,
+    /// The version the requirement matches.
+    pub version: Version,
+}
+
+impl fmt::Display for Expired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (version {} matches {})",
+            self.reason, self.version, self.requirement
+        )
+    }
+}
+
+impl Error for Expired {}
+
+/// Checks `version` against `requirement`, which is an error once it matches.
+///
+/// # Panics
+///
+/// If `requirement` isn't a valid requirement, which `runtime_check!` rules out at compile time.
+pub fn check(version: &Version, requirement: &str, reason: &'static str) -> Result<(), Expired> {
+    let requirement = VersionReq::parse(requirement)
+        .unwrap_or_else(|e| panic!("invalid requirement `{}`: {}", requirement, e));
+    if !requirement.matches(version) {
+        return Ok(());
+    }
+    Err(Expired {
+        requirement,
+        reason,
+        version: version.clone(),
+    })
+}
+
+/// Checks `version` against `requirement` as [`check`] does, reporting the requirement as a
+/// warning once it matches: through `tracing` with the `tracing` feature, else through `log` with
+/// the `log` feature, else on stderr. Returns whether it matched.
+pub fn warn_if_expired(version: &Version, requirement: &str, reason: &'static str) -> bool {
+    let Err(expired) = check(version, requirement, reason) else {
+        return false;
+    };
+    let message = expired.to_string();
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        requirement = %expired.requirement,
+        version = %expired.version,
+        "{}",
+        message
+    );
+    #[cfg(all(feature = "log", not(feature = "tracing")))]
+    log::warn!("{}", message);
+    #[cfg(not(any(feature = "log", feature = "tracing")))]
+    std::eprintln!("warning: {}", message);
+    true
+}
@@ -0,0 +1,213 @@
+//! Deprecated command line options, for CLIs whose arguments are a type deriving both clap's
+//! `Parser` and `AllowUntil`, with the `clap` feature.
+//!
+//! [`decorate`] appends "(deprecated, removed in 2.0)" to the help of the arguments of annotated
+//! fields, and [`parse`] warns about those the user supplied. clap has no hook into the parsing of
+//! a single argument, so the warnings are emitted once the whole command line is parsed, before
+//! the type is built from it.
+//!
+//! ```
+//! use allow_until_runtime::{clap_support, MemberDeprecation};
+//! use clap::{Arg, Command};
+//!
+//! let cmd = Command::new("serve")
+//!     .arg(Arg::new("timeout").long("timeout").help("Seconds to wait"))
+//!     .arg(Arg::new("timeout-ms").long("timeout-ms").help("Milliseconds to wait"));
+//! let members = [MemberDeprecation {
+//!     name: "timeout",
+//!     requirement: ">=2.0",
+//!     reason: None,
+//!     replacement: Some("timeout_ms"),
+//! }];
+//!
+//! let naming = clap_support::Naming::new().kebab_case();
+//! let mut cmd = clap_support::decorate_with(cmd, &members, &naming);
+//! let help = cmd.render_help().to_string();
+//! assert!(help.contains("Seconds to wait (deprecated, removed in 2.0, use `--timeout-ms`)"));
+//!
+//! let matches = cmd.clone().get_matches_from(["serve", "--timeout", "5"]);
+//! let warnings = clap_support::supplied(&cmd, &matches, &members, &naming);
+//! assert_eq!(
+//!     warnings,
+//!     ["`--timeout` is deprecated and will be removed in 2.0, use `--timeout-ms` instead"]
+//! );
+//! ```
+
+use std::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use clap::{parser::ValueSource, Arg, ArgMatches, Command, CommandFactory, FromArgMatches};
+
+use crate::{AllowUntilMetadata, MemberDeprecation};
+
+/// How the names of annotated members map to the ids of clap arguments.
+///
+/// By default the id is the field's name, as `#[derive(Parser)]` names arguments whatever their
+/// `long` is. Commands built by hand often use kebab-case ids instead, and `#[arg(id = "...")]`
+/// renames single arguments.
+#[derive(Clone, Debug, Default)]
+pub struct Naming {
+    kebab_case: bool,
+    renames: Vec<(String, String)>,
+}
+
+impl Naming {
+    /// Ids equal to the field names, as `#[derive(Parser)]` uses.
+    pub fn new() -> Naming {
+        Naming::default()
+    }
+
+    /// Ids in kebab-case, `timeout-ms` for the field `timeout_ms`.
+    pub fn kebab_case(mut self) -> Naming {
+        self.kebab_case = true;
+        self
+    }
+
+    /// The id of the argument of the field `member`, taking precedence over the case.
+    pub fn rename(mut self, member: &str, id: &str) -> Naming {
+        self.renames.push((member.into(), id.into()));
+        self
+    }
+
+    /// The id of the argument of `member`.
+    pub fn id(&self, member: &str) -> String {
+        match self.renames.iter().find(|(name, _)| name == member) {
+            Some((_, id)) => id.clone(),
+            None if self.kebab_case => member.replace('_', "-"),
+            None => member.into(),
+        }
+    }
+}
+
+/// [`decorate_with`] the default [`Naming`].
+pub fn decorate(cmd: Command, members: &[MemberDeprecation]) -> Command {
+    decorate_with(cmd, members, &Naming::new())
+}
+
+/// Appends the deprecation of each member of `members` to the help of its argument of `cmd`, e.g.
+/// "(deprecated, removed in 2.0, use `--timeout-ms`)". Members without an argument, such as enum
+/// variants, and the arguments of subcommands are left alone.
+pub fn decorate_with(mut cmd: Command, members: &[MemberDeprecation], naming: &Naming) -> Command {
+    for member in members {
+        let id = naming.id(member.name);
+        if find(&cmd, &id).is_none() {
+            continue;
+        }
+
+        let mut text = format!("(deprecated, {}", removal(member.requirement));
+        if let Some(replacement) = member.replacement {
+            text.push_str(&format!(
+                ", use `{}`",
+                replacement_name(&cmd, replacement, naming)
+            ));
+        }
+        text.push(')');
+
+        cmd = cmd.mut_arg(id, |arg| {
+            let help = append(arg.get_help().map(ToString::to_string), &text);
+            let long_help = arg
+                .get_long_help()
+                .map(|long| append(Some(long.to_string()), &text));
+            let arg = arg.help(help);
+            match long_help {
+                Some(long_help) => arg.long_help(long_help),
+                None => arg,
+            }
+        });
+    }
+    cmd
+}
+
+/// The warnings for the deprecated arguments of `cmd` that `matches` got from the command line or
+/// the environment, rather than from their defaults.
+pub fn supplied(
+    cmd: &Command,
+    matches: &ArgMatches,
+    members: &[MemberDeprecation],
+    naming: &Naming,
+) -> Vec<String> {
+    members
+        .iter()
+        .filter_map(|member| {
+            let arg = find(cmd, &naming.id(member.name))?;
+            // `value_source` panics in debug builds for ids `matches` doesn't know, so only the
+            // arguments of `cmd` are asked for
+            match matches.value_source(arg.get_id().as_str()) {
+                Some(ValueSource::DefaultValue) | None => return None,
+                Some(_) => (),
+            }
+
+            let mut warning = format!(
+                "`{}` is deprecated and will be {}",
+                display(arg),
+                removal(member.requirement)
+            );
+            if let Some(reason) = member.reason {
+                warning.push_str(&format!(": {}", reason));
+            }
+            if let Some(replacement) = member.replacement {
+                warning.push_str(&format!(
+                    ", use `{}` instead",
+                    replacement_name(cmd, replacement, naming)
+                ));
+            }
+            Some(warning)
+        })
+        .collect()
+}
+
+/// Parses the command line into `T` as `T::parse()` does, with the deprecated arguments decorated,
+/// printing a warning to stderr for each of them that was supplied.
+pub fn parse<T: CommandFactory + FromArgMatches + AllowUntilMetadata>() -> T {
+    parse_with(&Naming::new())
+}
+
+/// [`parse`] with the given [`Naming`].
+pub fn parse_with<T: CommandFactory + FromArgMatches + AllowUntilMetadata>(naming: &Naming) -> T {
+    let cmd = decorate_with(T::command(), T::deprecated_members(), naming);
+    let mut matches = cmd.clone().get_matches();
+    for warning in supplied(&cmd, &matches, T::deprecated_members(), naming) {
+        std::eprintln!("warning: {}", warning);
+    }
+    T::from_arg_matches_mut(&mut matches).unwrap_or_else(|e| e.format(&mut T::command()).exit())
+}
+
+fn find<'c>(cmd: &'c Command, id: &str) -> Option<&'c Arg> {
+    cmd.get_arguments().find(|arg| arg.get_id() == id)
+}
+
+/// How the argument is written on the command line, e.g. "--timeout" or "<PATH>".
+fn display(arg: &Arg) -> String {
+    match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => format!("--{}", long),
+        (None, Some(short)) => format!("-{}", short),
+        (None, None) => match arg.get_value_names().and_then(|names| names.first()) {
+            Some(name) => format!("<{}>", name),
+            None => format!("<{}>", arg.get_id().as_str().to_uppercase()),
+        },
+    }
+}
+
+/// The replacement as written on the command line if it is an argument of `cmd`, as written in
+/// the annotation otherwise.
+fn replacement_name(cmd: &Command, replacement: &str, naming: &Naming) -> String {
+    find(cmd, &naming.id(replacement)).map_or(replacement.into(), display)
+}
+
+/// "removed in 2.0" for `>=2.0`, or the requirement itself when it isn't a lower bound.
+fn removal(requirement: &str) -> String {
+    match requirement.trim().strip_prefix(">=") {
+        Some(version) if !version.contains(',') => format!("removed in {}", version.trim()),
+        _ => format!("removed at `{}`", requirement),
+    }
+}
+
+fn append(help: Option<String>, text: &str) -> String {
+    match help {
+        Some(help) if !help.is_empty() => format!("{} {}", help, text),
+        _ => text.into(),
+    }
+}
@@ -0,0 +1,67 @@
+//! The trait [`allow-until`](https://crates.io/crates/allow-until)'s `#[derive(AllowUntil)]`
+//! implements with its `runtime-metadata` feature, listing the annotated members of the type, so
+//! a config loader can warn about deprecated keys it was given.
+//!
+//! ```
+//! use allow_until_runtime::{AllowUntilMetadata, MemberDeprecation};
+//!
+//! // what the derive generates for a struct with an annotated `timeout` field
+//! struct Config;
+//!
+//! impl AllowUntilMetadata for Config {
+//!     fn deprecated_members() -> &'static [MemberDeprecation] {
+//!         &[MemberDeprecation {
+//!             name: "timeout",
+//!             requirement: ">=2.0",
+//!             reason: Some("in seconds, use `timeout_ms`"),
+//!             replacement: Some("timeout_ms"),
+//!         }]
+//!     }
+//! }
+//!
+//! let timeout = &Config::deprecated_members()[0];
+//! assert_eq!(timeout.replacement, Some("timeout_ms"));
+//! ```
+//!
+//! With the `clap` feature, [`clap_support`] marks the deprecated arguments of a CLI in its help
+//! and warns when they are used. With the `serde` feature, [`serde_support`] finds the deprecated
+//! keys of a config file. With the `check` feature, [`check`] evaluates requirements against a
+//! version only known at runtime, such as a plugin host's.
+
+#![no_std]
+
+#[cfg(any(feature = "clap", feature = "serde", feature = "check"))]
+extern crate std;
+
+#[cfg(feature = "check")]
+pub mod check;
+#[cfg(feature = "clap")]
+pub mod clap_support;
+#[cfg(feature = "serde")]
+pub mod serde_support;
+
+#[cfg(feature = "check")]
+pub use allow_until::{runtime_check, warn_if_expired};
+#[cfg(feature = "check")]
+pub use semver;
+
+/// An annotated member of a type deriving `AllowUntil`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MemberDeprecation {
+    /// The member's name relative to the type: a field such as `"timeout"`, a variant such as
+    /// `"Xml"`, or the field of a variant such as `"Xml::indent"`. Tuple fields are their index.
+    pub name: &'static str,
+    /// The requirement of the annotation, such as `">=2.0"`.
+    pub requirement: &'static str,
+    /// The `reason` of the annotation.
+    pub reason: Option<&'static str>,
+    /// The `replacement` of the annotation, naming what to use instead.
+    pub replacement: Option<&'static str>,
+}
+
+/// The annotated members of a type, implemented by `#[derive(AllowUntil)]` with the
+/// `runtime-metadata` feature of `allow-until`.
+pub trait AllowUntilMetadata {
+    /// The annotated members, in declaration order, whether or not their annotation has expired.
+    fn deprecated_members() -> &'static [MemberDeprecation];
+}
@@ -0,0 +1,210 @@
+//! Deprecated keys in input data, for config types deriving both serde's `Deserialize` and
+//! `AllowUntil`, with the `serde` feature of this crate and of `allow-until`.
+//!
+//! The derive implements [`DeprecatedKeys`], whose
+//! [`check_deprecated_keys`](DeprecatedKeys::check_deprecated_keys) walks a parsed JSON value, or
+//! YAML or TOML converted to one, and lists the keys that deserialize into annotated members, so
+//! they can be logged before the members are removed and the keys silently become unknown ones.
+//! Keys are matched as serde reads them, with the `rename`, `rename_all` and `alias` attributes of
+//! the type, and fields whose type derives `AllowUntil` too are checked recursively, through
+//! `Option`, `Vec` and `Box`, as are `flatten`ed ones.
+//!
+//! ```
+//! use allow_until_runtime::{
+//!     serde_support::{DeprecatedKeyUse, DeprecatedKeys, Value},
+//!     MemberDeprecation,
+//! };
+//!
+//! // what the derive generates for a struct with an annotated `timeout` field
+//! struct Config;
+//!
+//! impl DeprecatedKeys for Config {
+//!     fn check_at(value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>) {
+//!         const TIMEOUT: MemberDeprecation = MemberDeprecation {
+//!             name: "timeout",
+//!             requirement: ">=2.0",
+//!             reason: Some("in seconds, use `timeout_ms`"),
+//!             replacement: Some("timeout_ms"),
+//!         };
+//!         if value.get("timeout").is_some() {
+//!             uses.push(DeprecatedKeyUse::new(path, "timeout", TIMEOUT));
+//!         }
+//!     }
+//! }
+//!
+//! let input: Value = serde_json::from_str(r#"{ "timeout": 5 }"#).unwrap();
+//! let uses = Config::check_deprecated_keys(&input);
+//! assert_eq!(uses[0].path, "timeout");
+//! assert_eq!(uses[0].member.requirement, ">=2.0");
+//! ```
+
+use std::{boxed::Box, format, string::String, vec::Vec};
+
+pub use serde_json::Value;
+
+use crate::MemberDeprecation;
+
+/// A key of the input that deserializes into an annotated member.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeprecatedKeyUse {
+    /// Where the key is in the input, e.g. `"timeout"`, `"server.timeout"` or
+    /// `"servers[1].timeout"`.
+    pub path: String,
+    /// The member, with the requirement it is removed at and its `reason`.
+    pub member: MemberDeprecation,
+}
+
+impl DeprecatedKeyUse {
+    /// The use of `member` by `key` of the object at `path`.
+    pub fn new(path: &str, key: &str, member: MemberDeprecation) -> DeprecatedKeyUse {
+        DeprecatedKeyUse {
+            path: join(path, key),
+            member,
+        }
+    }
+}
+
+/// Checks input data for keys of annotated members, implemented by `#[derive(AllowUntil)]` with
+/// the `serde` feature of `allow-until`.
+pub trait DeprecatedKeys {
+    /// The keys of `value` that deserialize into annotated members, in the order of the members,
+    /// with those of nested types after the key of their field.
+    fn check_deprecated_keys(value: &Value) -> Vec<DeprecatedKeyUse> {
+        let mut uses = Vec::new();
+        Self::check_at(value, "", &mut uses);
+        uses
+    }
+
+    /// Adds the uses in `value`, found at `path` of the input, to `uses`.
+    fn check_at(value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>);
+}
+
+impl<T: DeprecatedKeys> DeprecatedKeys for Option<T> {
+    fn check_at(value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>) {
+        if !value.is_null() {
+            T::check_at(value, path, uses);
+        }
+    }
+}
+
+impl<T: DeprecatedKeys> DeprecatedKeys for Box<T> {
+    fn check_at(value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>) {
+        T::check_at(value, path, uses);
+    }
+}
+
+impl<T: DeprecatedKeys> DeprecatedKeys for Vec<T> {
+    fn check_at(value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>) {
+        for (index, element) in value.as_array().into_iter().flatten().enumerate() {
+            T::check_at(element, &format!("{}[{}]", path, index), uses);
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// What the derive's implementations are made of, not meant to be used otherwise.
+#[doc(hidden)]
+pub mod __private {
+    use core::marker::PhantomData;
+    use std::vec::Vec;
+
+    use super::{join, DeprecatedKeyUse, DeprecatedKeys, Value};
+    use crate::MemberDeprecation;
+
+    /// How a field of a struct is checked.
+    pub struct Field {
+        /// The keys the field is deserialized from, its name and its aliases.
+        pub keys: &'static [&'static str],
+        /// The index of the field's annotation among the type's members, if it has one.
+        pub member: Option<usize>,
+        /// Whether the keys of the field's type are those of the struct itself.
+        pub flatten: bool,
+        /// Checks the value of the field.
+        pub nested: fn(&Value, &str, &mut Vec<DeprecatedKeyUse>),
+    }
+
+    /// Checks the object `value` at `path` for the keys of `fields`.
+    pub fn check_fields(
+        value: &Value,
+        path: &str,
+        fields: &[Field],
+        members: &[MemberDeprecation],
+        uses: &mut Vec<DeprecatedKeyUse>,
+    ) {
+        let Some(object) = value.as_object() else {
+            return;
+        };
+        for field in fields {
+            if field.flatten {
+                (field.nested)(value, path, uses);
+                continue;
+            }
+            for key in field.keys {
+                let Some(value) = object.get(*key) else {
+                    continue;
+                };
+                if let Some(member) = field.member {
+                    uses.push(DeprecatedKeyUse::new(path, key, members[member]));
+                }
+                (field.nested)(value, &join(path, key), uses);
+            }
+        }
+    }
+
+    /// Checks `value` at `path` for the externally tagged variants in `variants`, by their names
+    /// and the index of their annotation among the type's members: a string such as `"Xml"`, or an
+    /// object with the key `Xml`.
+    pub fn check_variants(
+        value: &Value,
+        path: &str,
+        variants: &[(&[&str], usize)],
+        members: &[MemberDeprecation],
+        uses: &mut Vec<DeprecatedKeyUse>,
+    ) {
+        for (names, member) in variants {
+            let used = match value {
+                Value::String(tag) => names.iter().find(|name| **name == tag),
+                Value::Object(object) => names.iter().find(|name| object.contains_key(**name)),
+                _ => None,
+            };
+            if let Some(name) = used {
+                uses.push(DeprecatedKeyUse::new(path, name, members[*member]));
+            }
+        }
+    }
+
+    /// Checks its type's values, if it implements [`DeprecatedKeys`], for fields whose type may or
+    /// may not derive `AllowUntil`: `(&Probe::<T>::new()).check(...)` resolves to [`Nested`] if it
+    /// does, and to [`Leaf`], which does nothing, otherwise.
+    pub struct Probe<T: ?Sized>(PhantomData<T>);
+
+    impl<T: ?Sized> Probe<T> {
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Probe<T> {
+            Probe(PhantomData)
+        }
+    }
+
+    pub trait Nested {
+        fn check(&self, value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>);
+    }
+
+    impl<T: DeprecatedKeys> Nested for Probe<T> {
+        fn check(&self, value: &Value, path: &str, uses: &mut Vec<DeprecatedKeyUse>) {
+            T::check_at(value, path, uses);
+        }
+    }
+
+    pub trait Leaf {
+        fn check(&self, _: &Value, _: &str, _: &mut Vec<DeprecatedKeyUse>) {}
+    }
+
+    impl<T: ?Sized> Leaf for &Probe<T> {}
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "check")]
+
+use allow_until_runtime::{
+    check::Expired,
+    runtime_check,
+    semver::{Version, VersionReq},
+    warn_if_expired,
+};
+
+#[test]
+fn allowed() {
+    let host_version = Version::new(1, 9, 3);
+    assert_eq!(
+        runtime_check!(host_version, ">=2.0", "legacy hook API"),
+        Ok(())
+    );
+    assert!(!warn_if_expired!(host_version, ">=2.0", "legacy hook API"));
+}
+
+#[test]
+fn expired() {
+    let host_version = Version::new(2, 1, 0);
+    let expired = runtime_check!(&host_version, ">=2.0", "legacy hook API").unwrap_err();
+    assert_eq!(
+        expired,
+        Expired {
+            requirement: VersionReq::parse(">=2.0").unwrap(),
+            reason: "legacy hook API",
+            version: host_version.clone(),
+        }
+    );
+    assert_eq!(
+        expired.to_string(),
+        "legacy hook API (version 2.1.0 matches >=2.0)"
+    );
+    assert!(warn_if_expired!(host_version, ">=2.0", "legacy hook API"));
+}
+
+#[test]
+fn requirements_are_normalized() {
+    // wildcards are desugared at compile time, as in annotations
+    let expired = runtime_check!(Version::new(1, 0, 4), ">= 1.0.x", "v1 handshake").unwrap_err();
+    assert_eq!(expired.requirement.to_string(), ">=1.0");
+}
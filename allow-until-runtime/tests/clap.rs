@@ -0,0 +1,76 @@
+#![cfg(feature = "clap")]
+
+use allow_until::AllowUntil;
+use allow_until_runtime::{
+    clap_support::{self, Naming},
+    AllowUntilMetadata,
+};
+use clap::{CommandFactory, Parser};
+
+#[derive(Parser, AllowUntil)]
+#[allow(unused)]
+struct Cli {
+    /// Seconds to wait for a response
+    #[arg(long)]
+    #[allow_until(version = ">=2.0", replacement = "timeout_ms")]
+    timeout: Option<u64>,
+    /// Milliseconds to wait for a response
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+    /// Log every request
+    #[arg(short, long = "trace", id = "log_requests")]
+    #[allow_until(version = ">=2.0", reason = "use `RUST_LOG`")]
+    verbose: bool,
+    /// Where to listen
+    #[arg(long, default_value = "localhost")]
+    #[allow_until(version = ">=3.0")]
+    host: String,
+}
+
+fn naming() -> Naming {
+    Naming::new().rename("verbose", "log_requests")
+}
+
+#[test]
+fn help() {
+    let help = clap_support::decorate_with(Cli::command(), Cli::deprecated_members(), &naming())
+        .render_help()
+        .to_string();
+
+    assert!(help.contains(
+        "Seconds to wait for a response (deprecated, removed in 2.0, use `--timeout-ms`)"
+    ));
+    assert!(help.contains("Log every request (deprecated, removed in 2.0)"));
+    assert!(help.contains("Where to listen (deprecated, removed in 3.0)"));
+    assert!(help.contains("Milliseconds to wait for a response\n"));
+}
+
+#[test]
+fn warnings() {
+    let cmd = clap_support::decorate_with(Cli::command(), Cli::deprecated_members(), &naming());
+    let matches = cmd
+        .clone()
+        .get_matches_from(["cli", "--timeout", "5", "-v"]);
+
+    assert_eq!(
+        clap_support::supplied(&cmd, &matches, Cli::deprecated_members(), &naming()),
+        [
+            "`--timeout` is deprecated and will be removed in 2.0, use `--timeout-ms` instead",
+            "`--trace` is deprecated and will be removed in 2.0: use `RUST_LOG`",
+        ]
+    );
+}
+
+#[test]
+fn defaults_and_renames() {
+    let cmd = clap_support::decorate(Cli::command(), Cli::deprecated_members());
+    let matches = cmd.clone().get_matches_from(["cli", "--trace"]);
+
+    // the default of `--host` isn't supplied, and `verbose` isn't an id without the rename
+    assert!(
+        clap_support::supplied(&cmd, &matches, Cli::deprecated_members(), &Naming::new())
+            .is_empty()
+    );
+    assert_eq!(Naming::new().kebab_case().id("timeout_ms"), "timeout-ms");
+    assert_eq!(naming().kebab_case().id("verbose"), "log_requests");
+}
@@ -0,0 +1,74 @@
+//! Probes whether the unstable `proc_macro` APIs used with the `nightly` feature compile with this
+//! toolchain, and only enables them, through the `allow_until_nightly` cfg, if they do. When they
+//! change on nightly, diagnostics fall back to the stable ones instead of the crate failing to
+//! build.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// Every unstable API the `allow_until_nightly` code paths call, with the signatures they rely on.
+const PROBE: &str = r#"
+#![feature(
+    proc_macro_diagnostic,
+    proc_macro_span,
+    proc_macro_tracked_env,
+    proc_macro_tracked_path
+)]
+
+extern crate proc_macro;
+
+use std::{env::VarError, path::{Path, PathBuf}};
+
+use proc_macro::{Diagnostic, Level, Span};
+
+pub fn probe(span: Span) -> Option<Span> {
+    let _: Result<String, VarError> = proc_macro::tracked::env_var("CARGO_PKG_VERSION");
+    proc_macro::tracked::path(Path::new("Cargo.toml"));
+    for level in [Level::Error, Level::Warning, Level::Note, Level::Help] {
+        Diagnostic::spanned(span, level, String::new())
+            .span_note(span, String::new())
+            .span_help(span, String::new())
+            .note(String::new())
+            .help(String::new())
+            .emit();
+    }
+    let source = span.source();
+    let _: (usize, usize, Option<PathBuf>) = (source.line(), source.column(), source.local_file());
+    let _: Option<Span> = span.parent();
+    source.join(span)
+}
+"#;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(allow_until_nightly)");
+
+    if env::var_os("CARGO_FEATURE_NIGHTLY").is_none() {
+        return;
+    }
+    if probe() {
+        println!("cargo:rustc-cfg=allow_until_nightly");
+    } else {
+        println!(
+            "cargo:warning=the unstable `proc_macro` APIs of the `nightly` feature don't compile \
+             with this toolchain, allow-until falls back to its stable diagnostics"
+        );
+    }
+}
+
+/// Whether [`PROBE`] compiles.
+fn probe() -> bool {
+    let out = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets `OUT_DIR`"));
+    let file = out.join("probe.rs");
+    if fs::write(&file, PROBE).is_err() {
+        return false;
+    }
+
+    Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+        .args(["--edition", "2021", "--crate-type", "lib"])
+        .args(["--crate-name", "allow_until_probe", "--emit", "metadata"])
+        .arg("--out-dir")
+        .arg(&out)
+        .arg(&file)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
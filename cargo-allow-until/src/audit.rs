@@ -0,0 +1,474 @@
+//! `cargo allow-until audit`, which requests the `issue` of every annotation from GitHub or GitLab
+//! and reports the annotations whose issue is closed, missing or was transferred: the removal
+//! probably happened elsewhere, or the plan changed.
+//!
+//! The audit never fails because a tracker can't be asked. Issues that aren't on GitHub or GitLab,
+//! and those of a tracker that is unreachable or whose rate limit ran out, are skipped.
+
+use std::{
+    collections::BTreeMap,
+    process::ExitCode,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{http::Http, scan::Annotation};
+
+/// The states `--fail-on` accepts.
+pub const FAILING: [&str; 3] = ["closed", "missing", "transferred"];
+
+/// The state of an issue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    Open,
+    /// Closed, with the reason GitHub gives, e.g. `not_planned`.
+    Closed(Option<String>),
+    /// Not found or deleted.
+    Missing,
+    /// Moved to another repository or project.
+    Transferred,
+    /// Not requested, or without an answer, and why.
+    Skipped(String),
+}
+
+impl State {
+    /// The name of the state, as given to `--fail-on`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            State::Open => "open",
+            State::Closed(_) => "closed",
+            State::Missing => "missing",
+            State::Transferred => "transferred",
+            State::Skipped(_) => "skipped",
+        }
+    }
+}
+
+/// The trackers, as the environment configures them.
+pub struct Trackers {
+    /// The base URL of the GitHub API, `GITHUB_API_URL` or `https://api.github.com`.
+    pub github_api: String,
+    /// `GITHUB_TOKEN`, needed for private repositories and a higher rate limit.
+    pub github_token: Option<String>,
+    /// `GITLAB_TOKEN`, needed for private projects.
+    pub gitlab_token: Option<String>,
+    /// Why nothing is requested, if the audit runs offline.
+    pub offline: Option<String>,
+}
+
+/// Requests the states of issues, each once, and stops asking a tracker once it is unreachable or
+/// rate limited.
+pub struct Auditor<H> {
+    http: H,
+    trackers: Trackers,
+    states: BTreeMap<String, State>,
+    /// The trackers that are no longer asked, by API host, and why.
+    exhausted: BTreeMap<String, String>,
+}
+
+/// An issue on a tracker whose API is known.
+struct Issue {
+    /// The URL of the issue in the API.
+    api: String,
+    /// The host of the API, whose rate limit the request counts towards.
+    host: String,
+    gitlab: bool,
+}
+
+impl<H: Http> Auditor<H> {
+    pub fn new(http: H, trackers: Trackers) -> Auditor<H> {
+        Auditor {
+            http,
+            trackers,
+            states: BTreeMap::new(),
+            exhausted: BTreeMap::new(),
+        }
+    }
+
+    /// The state of the issue at `url`.
+    pub fn state(&mut self, url: &str) -> State {
+        if let Some(state) = self.states.get(url) {
+            return state.clone();
+        }
+        let state = self.request(url);
+        self.states.insert(url.into(), state.clone());
+        state
+    }
+
+    fn request(&mut self, url: &str) -> State {
+        if let Some(offline) = &self.trackers.offline {
+            return State::Skipped(format!("offline, as {}", offline));
+        }
+        let Some(issue) = self.issue(url) else {
+            return State::Skipped("not a GitHub or GitLab issue".into());
+        };
+        if let Some(why) = self.exhausted.get(&issue.host) {
+            return State::Skipped(why.clone());
+        }
+
+        let authorization;
+        let mut headers = Vec::new();
+        if issue.gitlab {
+            if let Some(token) = &self.trackers.gitlab_token {
+                headers.push(("PRIVATE-TOKEN", token.as_str()));
+            }
+        } else {
+            headers.push(("Accept", "application/vnd.github+json"));
+            if let Some(token) = &self.trackers.github_token {
+                authorization = format!("Bearer {}", token);
+                headers.push(("Authorization", authorization.as_str()));
+            }
+        }
+
+        let response = match self.http.request("GET", &issue.api, &headers, None) {
+            Ok(response) => response,
+            Err(e) => {
+                let why = format!("`{}` is unreachable: {}", issue.host, e);
+                self.exhausted.insert(issue.host, why.clone());
+                return State::Skipped(why);
+            }
+        };
+
+        let remaining = response
+            .header("x-ratelimit-remaining")
+            .or(response.header("ratelimit-remaining"));
+        if remaining == Some("0") || response.status == 429 {
+            let why = rate_limited(&issue.host, &response);
+            self.exhausted.insert(issue.host.clone(), why.clone());
+            if response.status == 403 || response.status == 429 {
+                return State::Skipped(why);
+            }
+        }
+
+        let json = response.json();
+        match response.status {
+            200 if issue.gitlab && !json["moved_to_id"].is_null() => State::Transferred,
+            200 => match json["state"].as_str() {
+                Some("open" | "opened") => State::Open,
+                Some("closed") => State::Closed(json["state_reason"].as_str().map(Into::into)),
+                _ => State::Skipped(format!("`{}` answered an unknown state", issue.api)),
+            },
+            301 | 302 | 307 | 308 => State::Transferred,
+            404 | 410 => State::Missing,
+            401 => State::Skipped(format!(
+                "`{}` rejected the token in `{}`",
+                issue.host,
+                if issue.gitlab {
+                    "GITLAB_TOKEN"
+                } else {
+                    "GITHUB_TOKEN"
+                }
+            )),
+            status => State::Skipped(format!("`{}` answered {}", issue.api, status)),
+        }
+    }
+
+    /// Where the issue at `url` is in its tracker's API: `github.com/org/name/issues/3` (or
+    /// `pull/3`) in `GITHUB_API_URL`, and `host/group/project/-/issues/3` in the API of `host`.
+    fn issue(&self, url: &str) -> Option<Issue> {
+        let rest = url
+            .strip_prefix("https://")
+            .or(url.strip_prefix("http://"))?;
+        let rest = rest.split(['?', '#']).next()?.trim_end_matches('/');
+        let (host, path) = rest.split_once('/')?;
+
+        if let Some((project, number)) = path.split_once("/-/issues/") {
+            number.parse::<u64>().ok()?;
+            let scheme = &url[..url.find("://")?];
+            return Some(Issue {
+                api: format!(
+                    "{}://{}/api/v4/projects/{}/issues/{}",
+                    scheme,
+                    host,
+                    project.replace('/', "%2F"),
+                    number
+                ),
+                host: host.into(),
+                gitlab: true,
+            });
+        }
+
+        let segments: Vec<_> = path.split('/').collect();
+        match (host, &segments[..]) {
+            ("github.com" | "www.github.com", [owner, repo, "issues" | "pull", number])
+                if number.parse::<u64>().is_ok() =>
+            {
+                let api = self.trackers.github_api.trim_end_matches('/');
+                Some(Issue {
+                    api: format!("{}/repos/{}/{}/issues/{}", api, owner, repo, number),
+                    host: api.split("://").last().unwrap_or(api).into(),
+                    gitlab: false,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why the tracker at `host` is no longer asked, with when its limit resets if it says.
+fn rate_limited(host: &str, response: &crate::http::Response) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let wait = response
+        .header("retry-after")
+        .and_then(|after| after.parse::<u64>().ok())
+        .or_else(|| {
+            let reset = response
+                .header("x-ratelimit-reset")
+                .or(response.header("ratelimit-reset"))?;
+            Some(reset.parse::<u64>().ok()?.saturating_sub(now))
+        });
+    match wait {
+        Some(wait) => format!("`{}` is rate limited for {}s", host, wait),
+        None => format!("`{}` is rate limited", host),
+    }
+}
+
+/// Requests the `issue` of every annotation with one, printing those whose issue is closed,
+/// missing or was transferred, and those that were skipped. Fails if any issue is in one of the
+/// states of `fail_on`.
+pub fn audit(
+    annotations: &[Annotation],
+    auditor: &mut Auditor<impl Http>,
+    fail_on: &[String],
+) -> ExitCode {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut failed = false;
+
+    let linked: Vec<_> = annotations
+        .iter()
+        .filter_map(|a| Some((a, a.issue.as_deref()?)))
+        .collect();
+    for (a, issue) in &linked {
+        let state = auditor.state(issue);
+        let prefix = format!("{}: {}", a.location(), a.item);
+        match &state {
+            State::Open => (),
+            State::Closed(None) => println!("{}: `{}` is closed", prefix, issue),
+            State::Closed(Some(reason)) => {
+                println!("{}: `{}` is closed ({})", prefix, issue, reason)
+            }
+            State::Missing => println!("{}: `{}` was not found", prefix, issue),
+            State::Transferred => println!("{}: `{}` was transferred", prefix, issue),
+            State::Skipped(why) => println!("{}: skipped `{}`: {}", prefix, issue, why),
+        }
+        failed |= fail_on.iter().any(|name| name == state.name());
+        *counts.entry(state.name()).or_default() += 1;
+    }
+
+    let counts: Vec<_> = ["open", "closed", "missing", "transferred", "skipped"]
+        .iter()
+        .map(|name| format!("{} {}", counts.get(name).unwrap_or(&0), name))
+        .collect();
+    println!(
+        "audited the issues of {} annotation(s): {}",
+        linked.len(),
+        counts.join(", ")
+    );
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::http::Response;
+
+    /// A canned response: the URL it answers, its status, its headers and its body.
+    type Reply = (
+        &'static str,
+        u16,
+        &'static [(&'static str, &'static str)],
+        &'static str,
+    );
+
+    /// Answers with canned responses by URL, recording the requests.
+    struct Canned {
+        responses: Vec<Reply>,
+        requests: RefCell<Vec<(String, Vec<String>)>>,
+    }
+
+    impl Http for Canned {
+        fn request(
+            &self,
+            _: &str,
+            url: &str,
+            headers: &[(&str, &str)],
+            _: Option<&str>,
+        ) -> Result<Response, String> {
+            self.requests.borrow_mut().push((
+                url.into(),
+                headers
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect(),
+            ));
+            let (_, status, headers, body) = self
+                .responses
+                .iter()
+                .find(|(canned, ..)| *canned == url)
+                .ok_or_else(|| "Could not resolve host".to_string())?;
+            Ok(Response {
+                status: *status,
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                body: body.to_string(),
+            })
+        }
+    }
+
+    fn auditor(responses: Vec<Reply>) -> Auditor<Canned> {
+        Auditor::new(
+            Canned {
+                responses,
+                requests: RefCell::new(Vec::new()),
+            },
+            Trackers {
+                github_api: "https://api.github.com".into(),
+                github_token: Some("secret".into()),
+                gitlab_token: None,
+                offline: None,
+            },
+        )
+    }
+
+    #[test]
+    fn states() {
+        let mut auditor = auditor(vec![
+            (
+                "https://api.github.com/repos/org/name/issues/1",
+                200,
+                &[],
+                r#"{"state":"open"}"#,
+            ),
+            (
+                "https://api.github.com/repos/org/name/issues/2",
+                200,
+                &[],
+                r#"{"state":"closed","state_reason":"not_planned"}"#,
+            ),
+            (
+                "https://api.github.com/repos/org/name/issues/3",
+                301,
+                &[],
+                r#"{"message":"Moved Permanently"}"#,
+            ),
+            (
+                "https://api.github.com/repos/org/name/issues/4",
+                404,
+                &[],
+                "",
+            ),
+            (
+                "https://gitlab.com/api/v4/projects/group%2Fsub%2Fproject/issues/5",
+                200,
+                &[],
+                r#"{"state":"closed","moved_to_id":null}"#,
+            ),
+            (
+                "https://gitlab.com/api/v4/projects/group%2Fproject/issues/6",
+                200,
+                &[],
+                r#"{"state":"closed","moved_to_id":42}"#,
+            ),
+        ]);
+
+        let cases = [
+            ("https://github.com/org/name/issues/1", State::Open),
+            (
+                "https://github.com/org/name/issues/2",
+                State::Closed(Some("not_planned".into())),
+            ),
+            ("https://github.com/org/name/pull/3", State::Transferred),
+            ("https://github.com/org/name/issues/4#top", State::Missing),
+            (
+                "https://gitlab.com/group/sub/project/-/issues/5",
+                State::Closed(None),
+            ),
+            (
+                "https://gitlab.com/group/project/-/issues/6",
+                State::Transferred,
+            ),
+            (
+                "https://jira.example.com/browse/PROJ-7",
+                State::Skipped("not a GitHub or GitLab issue".into()),
+            ),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(auditor.state(url), expected, "{}", url);
+        }
+
+        // each issue is requested once, with the token
+        auditor.state("https://github.com/org/name/issues/1");
+        let requests = auditor.http.requests.borrow();
+        assert_eq!(requests.len(), 6);
+        assert!(requests[0]
+            .1
+            .contains(&"Authorization: Bearer secret".into()));
+    }
+
+    #[test]
+    fn rate_limit() {
+        let mut auditor = auditor(vec![
+            (
+                "https://api.github.com/repos/org/name/issues/1",
+                200,
+                &[("x-ratelimit-remaining", "1")],
+                r#"{"state":"closed"}"#,
+            ),
+            (
+                "https://api.github.com/repos/org/name/issues/2",
+                403,
+                &[("x-ratelimit-remaining", "0"), ("retry-after", "60")],
+                r#"{"message":"API rate limit exceeded"}"#,
+            ),
+        ]);
+
+        assert_eq!(
+            auditor.state("https://github.com/org/name/issues/1"),
+            State::Closed(None)
+        );
+        let limited = State::Skipped("`api.github.com` is rate limited for 60s".into());
+        assert_eq!(
+            auditor.state("https://github.com/org/name/issues/2"),
+            limited
+        );
+        // the tracker isn't asked again
+        assert_eq!(
+            auditor.state("https://github.com/org/name/issues/3"),
+            limited
+        );
+        assert_eq!(auditor.http.requests.borrow().len(), 2);
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut auditor = auditor(vec![]);
+
+        let unreachable =
+            State::Skipped("`gitlab.com` is unreachable: Could not resolve host".into());
+        assert_eq!(
+            auditor.state("https://gitlab.com/group/project/-/issues/1"),
+            unreachable
+        );
+        assert_eq!(
+            auditor.state("https://gitlab.com/group/project/-/issues/2"),
+            unreachable
+        );
+        assert_eq!(auditor.http.requests.borrow().len(), 1);
+
+        auditor.trackers.offline = Some("`ALLOW_UNTIL_OFFLINE=1`".into());
+        assert_eq!(
+            auditor.state("https://github.com/org/name/issues/1"),
+            State::Skipped("offline, as `ALLOW_UNTIL_OFFLINE=1`".into())
+        );
+    }
+}
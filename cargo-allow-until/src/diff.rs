@@ -0,0 +1,404 @@
+//! `cargo allow-until diff`, which lists the annotations added, removed or changed since an older
+//! revision of the workspace, for release notes. The sources of the revision are read with
+//! `git show`, or from a checkout of it, and scanned as those of the workspace are.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::{json, Value};
+
+use crate::{print_table, scan, sources, Package};
+
+/// The output format of the command.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "markdown" => Ok(Format::Markdown),
+            _ => Err(format!(
+                "invalid format `{}`, expected `text`, `json` or `markdown`",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the sources of the older revision come from.
+pub enum Since {
+    /// A git revision of the repository of the workspace, such as `v1.4.0`.
+    Revision(String),
+    /// A checkout of the older revision of the workspace.
+    Dir(PathBuf),
+}
+
+impl Since {
+    fn describe(&self) -> String {
+        match self {
+            Since::Revision(rev) => format!("`{}`", rev),
+            Since::Dir(dir) => format!("`{}`", dir.display()),
+        }
+    }
+}
+
+/// How an annotation changed between the two revisions.
+pub enum Change<'a> {
+    Added(&'a scan::Annotation),
+    Removed(&'a scan::Annotation),
+    /// The requirement, the reason or the item of the annotation changed, the latter when the
+    /// item was renamed.
+    Modified {
+        old: &'a scan::Annotation,
+        new: &'a scan::Annotation,
+    },
+}
+
+impl Change<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Change::Added(_) => "added",
+            Change::Removed(_) => "removed",
+            Change::Modified { .. } => "modified",
+        }
+    }
+}
+
+/// Prints the changes to the annotations of the packages in `root` since `since`.
+pub fn diff(
+    root: &Path,
+    packages: &[Package],
+    current: &[scan::Annotation],
+    since: &Since,
+    format: Format,
+) -> Result<(), String> {
+    let old = match since {
+        Since::Revision(rev) => revision(root, packages, rev)?,
+        Since::Dir(dir) => checkout(root, packages, dir)?,
+    };
+
+    let mut annotations = Vec::new();
+    for (file, source) in old {
+        match scan::file(&file, &source) {
+            Ok((found, _)) => annotations.extend(found),
+            Err(e) => eprintln!(
+                "warning: skipping `{}` of {}: {}",
+                file.display(),
+                since.describe(),
+                e
+            ),
+        }
+    }
+
+    let changes = changes(&annotations, current);
+    match format {
+        Format::Text => text(&changes, since),
+        Format::Json => println!("{:#}", json(&changes)),
+        Format::Markdown => print!("{}", markdown(&changes, since)),
+    }
+    Ok(())
+}
+
+/// The changes from the annotations `old` to the annotations `new`. Annotations are matched by
+/// their `id`, then by their item in the same file, then by their item alone, and last by their
+/// [`fingerprint`], so a renamed item keeps its annotation. Unchanged annotations are left out.
+pub fn changes<'a>(old: &'a [scan::Annotation], new: &'a [scan::Annotation]) -> Vec<Change<'a>> {
+    let mut unmatched_old: Vec<&scan::Annotation> = old.iter().collect();
+    let mut unmatched_new: Vec<&scan::Annotation> = new.iter().collect();
+    let mut pairs = Vec::new();
+
+    let keys: [Key; 4] = [
+        |a| a.id.clone(),
+        |a| Some(format!("{}\0{}", a.file.display(), a.item)),
+        |a| Some(a.item.clone()),
+        |a| Some(fingerprint(a)),
+    ];
+    for key in keys {
+        // only keys that are unique on both sides match, so two `fn new` aren't mixed up
+        let unique = |annotations: &[&scan::Annotation]| {
+            let mut seen = BTreeSet::new();
+            let mut repeated = BTreeSet::new();
+            for key in annotations.iter().filter_map(|a| key(a)) {
+                if !seen.insert(key.clone()) {
+                    repeated.insert(key);
+                }
+            }
+            seen.retain(|key| !repeated.contains(key));
+            seen
+        };
+        let both: BTreeSet<String> = unique(&unmatched_old)
+            .intersection(&unique(&unmatched_new))
+            .cloned()
+            .collect();
+
+        unmatched_old.retain(|&old| {
+            let Some(k) = key(old).filter(|k| both.contains(k)) else {
+                return true;
+            };
+            let index = unmatched_new
+                .iter()
+                .position(|new| key(new).as_ref() == Some(&k))
+                .expect("the key is on both sides");
+            pairs.push((old, unmatched_new.remove(index)));
+            false
+        });
+    }
+
+    let mut changes: Vec<Change> = unmatched_new.into_iter().map(Change::Added).collect();
+    changes.extend(unmatched_old.into_iter().map(Change::Removed));
+    changes.extend(
+        pairs
+            .into_iter()
+            .filter(|(old, new)| {
+                (&old.requirement, &old.reason, &old.item)
+                    != (&new.requirement, &new.reason, &new.item)
+            })
+            .map(|(old, new)| Change::Modified { old, new }),
+    );
+    changes
+}
+
+/// What identifies an annotation in one of the passes of [`changes`].
+type Key = fn(&scan::Annotation) -> Option<String>;
+
+/// What identifies an annotation whatever the name of its item: its requirement, its reason and
+/// the kind of its item, from which the fingerprints of the macro are derived along with the
+/// name.
+fn fingerprint(a: &scan::Annotation) -> String {
+    let kind = a.item.split(" `").next().unwrap_or(&a.item);
+    format!(
+        "{}\0{}\0{}",
+        a.requirement,
+        a.reason.as_deref().unwrap_or_default(),
+        kind
+    )
+}
+
+/// The Rust files of `packages` at the git revision `rev`, relative to `root`.
+fn revision(
+    root: &Path,
+    packages: &[Package],
+    rev: &str,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let listed = git(root, &["ls-tree", "-r", "--name-only", rev])?;
+    let listed: Vec<&str> = listed.lines().collect();
+    let manifests: BTreeSet<&Path> = listed
+        .iter()
+        .filter_map(|path| path.strip_suffix("Cargo.toml"))
+        .map(|dir| Path::new(dir.trim_end_matches('/')))
+        .collect();
+    let packages: BTreeSet<&Path> = packages
+        .iter()
+        .filter_map(|(dir, _)| dir.strip_prefix(root).ok())
+        .collect();
+
+    let mut files = Vec::new();
+    for path in listed.iter().map(Path::new) {
+        let hidden = path.iter().any(|part| {
+            let part = part.to_string_lossy();
+            part == "target" || part.starts_with('.')
+        });
+        // the package a file belongs to is the closest directory with a manifest
+        let package = path.ancestors().skip(1).find(|dir| manifests.contains(dir));
+        if hidden
+            || path.extension().is_none_or(|ext| ext != "rs")
+            || package.is_none_or(|package| !packages.contains(package))
+        {
+            continue;
+        }
+        // `./` makes the path relative to `root` rather than to the root of the repository
+        let source = git(root, &["show", &format!("{}:./{}", rev, path.display())])?;
+        files.push((path.to_owned(), source));
+    }
+    Ok(files)
+}
+
+/// The Rust files of `packages` in `dir`, a checkout of the workspace at `root` at the older
+/// revision, relative to it.
+fn checkout(
+    root: &Path,
+    packages: &[Package],
+    dir: &Path,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut found = Vec::new();
+    for (package, _) in packages {
+        let package = dir.join(package.strip_prefix(root).unwrap_or(package));
+        sources(&package, &package, &mut found);
+    }
+
+    found
+        .into_iter()
+        .map(|path| {
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+            Ok((path.strip_prefix(dir).unwrap_or(&path).to_owned(), source))
+        })
+        .collect()
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run `git {}`: {}", args[0], e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "`git {}` failed:\n{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Prints `changes` as a table, one row per change.
+fn text(changes: &[Change], since: &Since) {
+    if changes.is_empty() {
+        println!("no annotations changed since {}", since.describe());
+        return;
+    }
+
+    let rows: Vec<[String; 5]> = changes
+        .iter()
+        .map(|change| match change {
+            Change::Added(a) | Change::Removed(a) => row(change.name(), a),
+            Change::Modified { old, new } => {
+                let mut row = row(change.name(), new);
+                if old.item != new.item {
+                    row[2] = format!("{} (was {})", new.item, old.item);
+                }
+                if old.requirement != new.requirement {
+                    row[3] = format!("{} -> {}", old.requirement, new.requirement);
+                }
+                if old.reason != new.reason {
+                    row[4] = format!(
+                        "{} -> {}",
+                        old.reason.as_deref().unwrap_or("(none)"),
+                        new.reason.as_deref().unwrap_or("(none)")
+                    );
+                }
+                row
+            }
+        })
+        .collect();
+    print_table(
+        ["CHANGE", "LOCATION", "ITEM", "REQUIREMENT", "REASON"],
+        &rows,
+    );
+    println!("{} change(s) since {}", changes.len(), since.describe());
+}
+
+fn row(change: &str, a: &scan::Annotation) -> [String; 5] {
+    [
+        change.into(),
+        a.location(),
+        a.item.clone(),
+        a.requirement.to_string(),
+        a.reason.clone().unwrap_or_default(),
+    ]
+}
+
+fn json(changes: &[Change]) -> Value {
+    let annotation = |a: &scan::Annotation| {
+        json!({
+            "file": a.file.to_string_lossy().replace('\\', "/"),
+            "line": a.line,
+            "column": a.column,
+            "item": a.item,
+            "requirement": a.requirement.to_string(),
+            "reason": a.reason,
+            "id": a.id,
+        })
+    };
+    changes
+        .iter()
+        .map(|change| match change {
+            Change::Added(a) => json!({ "change": change.name(), "new": annotation(a) }),
+            Change::Removed(a) => json!({ "change": change.name(), "old": annotation(a) }),
+            Change::Modified { old, new } => json!({
+                "change": change.name(),
+                "old": annotation(old),
+                "new": annotation(new),
+            }),
+        })
+        .collect()
+}
+
+fn markdown(changes: &[Change], since: &Since) -> String {
+    let mut out = format!("# Annotations changed since {}\n", since.describe());
+    if changes.is_empty() {
+        out.push_str("\nNone.\n");
+        return out;
+    }
+
+    for (title, name) in [
+        ("Added", "added"),
+        ("Removed", "removed"),
+        ("Modified", "modified"),
+    ] {
+        let rows: Vec<&Change> = changes.iter().filter(|c| c.name() == name).collect();
+        if rows.is_empty() {
+            continue;
+        }
+        out.push_str(&format!(
+            "\n## {}\n\n| Item | Requirement | Reason | Location |\n\
+             |------|-------------|--------|----------|\n",
+            title
+        ));
+        for change in rows {
+            let (item, requirement, reason, location) = match change {
+                Change::Added(a) | Change::Removed(a) => (
+                    a.item.clone(),
+                    format!("`{}`", a.requirement),
+                    cell(a.reason.as_deref()),
+                    a.location(),
+                ),
+                Change::Modified { old, new } => (
+                    if old.item == new.item {
+                        new.item.clone()
+                    } else {
+                        format!("{} (was {})", new.item, old.item)
+                    },
+                    if old.requirement == new.requirement {
+                        format!("`{}`", new.requirement)
+                    } else {
+                        format!("`{}` → `{}`", old.requirement, new.requirement)
+                    },
+                    if old.reason == new.reason {
+                        cell(new.reason.as_deref())
+                    } else {
+                        format!(
+                            "{} → {}",
+                            cell(old.reason.as_deref()),
+                            cell(new.reason.as_deref())
+                        )
+                    },
+                    new.location(),
+                ),
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                item, requirement, reason, location
+            ));
+        }
+    }
+    out
+}
+
+/// `text` as the cell of a Markdown table, which can't hold pipes or line breaks.
+fn cell(text: Option<&str>) -> String {
+    text.unwrap_or_default()
+        .replace('|', "\\|")
+        .replace('\n', " ")
+}
@@ -0,0 +1,87 @@
+//! Source edits that delete expired annotations or the items they are attached to, and that
+//! rewrite the arguments of annotations.
+
+use std::ops::Range;
+
+use proc_macro2::LineColumn;
+
+use crate::scan;
+
+/// A replacement of a byte range of a source file.
+pub type Edit = (Range<usize>, String);
+
+/// The byte offset of `at` in `source`, whose columns count characters.
+pub fn offset(source: &str, at: LineColumn) -> usize {
+    let line = source
+        .split_inclusive('\n')
+        .take(at.line - 1)
+        .map(str::len)
+        .sum::<usize>();
+
+    line + source[line..]
+        .char_indices()
+        .nth(at.column)
+        .map_or(source.len() - line, |(i, _)| i)
+}
+
+/// The bytes to delete to remove `range`.
+///
+/// When nothing else is on its lines, the whole lines are removed, along with the blank line after
+/// them if there is one before them too. Otherwise the spaces after it are removed with it.
+pub fn removal(source: &str, range: scan::Range) -> Range<usize> {
+    let (start, end) = (offset(source, range.0), offset(source, range.1));
+
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = next_line(source, end);
+
+    if !source[line_start..start].trim().is_empty() || !source[end..line_end].trim().is_empty() {
+        let spaces = source[end..].len() - source[end..].trim_start_matches([' ', '\t']).len();
+        return start..end + spaces;
+    }
+
+    let blank_before = line_start == 0
+        || source[..line_start - 1]
+            .rsplit('\n')
+            .next()
+            .is_some_and(|l| l.trim().is_empty());
+    let after_blank = next_line(source, line_end);
+
+    if blank_before && line_end < source.len() && source[line_end..after_blank].trim().is_empty() {
+        line_start..after_blank
+    } else {
+        line_start..line_end
+    }
+}
+
+/// The offset of the start of the line after the one `at` is on.
+fn next_line(source: &str, at: usize) -> usize {
+    source[at..].find('\n').map_or(source.len(), |i| at + i + 1)
+}
+
+/// The replacement of the string literal spanning `range` with `value`.
+pub fn literal(source: &str, range: scan::Range, value: &str) -> Edit {
+    (
+        offset(source, range.0)..offset(source, range.1),
+        format!("{:?}", value),
+    )
+}
+
+/// Applies `edits` to `source`. Edits within other edits, e.g. the attribute of an item that is
+/// removed as a whole, are skipped.
+pub fn apply(source: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|(r, _)| (r.start, std::cmp::Reverse(r.end)));
+
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for (r, text) in edits {
+        if r.start < last {
+            continue;
+        }
+        out.push_str(&source[last..r.start]);
+        out.push_str(&text);
+        last = r.end;
+    }
+    out.push_str(&source[last..]);
+
+    out
+}
@@ -0,0 +1,201 @@
+//! `cargo allow-until fragments`, which writes a towncrier-style changelog fragment for every
+//! annotation that has expired or expires within `warn_ahead`, announcing the removal of its item.
+//! Fragments that already exist are left alone, so the command can be run on every release.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use allow_until_core::{
+    config::{self, Config},
+    fingerprint,
+    version::minimal_version,
+};
+use semver::Version;
+
+use crate::{scan::Annotation, verify, Package};
+
+/// A changelog fragment.
+pub struct Fragment {
+    /// The file name, from the `name` of `fragments`.
+    pub name: String,
+    /// The contents, from the `text` of `fragments`.
+    pub text: String,
+}
+
+/// The fragment of `a`, rendering the `name` and `text` of `fragments` in `config`.
+pub fn render(a: &Annotation, config: &Config) -> Fragment {
+    let (kind, name) = match a.item.split_once(" `") {
+        Some((kind, name)) => (kind, Some(name.trim_end_matches('`'))),
+        None => (&a.item[..], None),
+    };
+    let reason = a.reason.clone().or_else(|| {
+        a.reason_key
+            .as_ref()
+            .and_then(|key| config.reasons.get(key).cloned())
+    });
+    let id = a.id.clone().unwrap_or_else(|| {
+        fingerprint::of(
+            &a.requirement,
+            reason.as_deref(),
+            kind,
+            name,
+            a.generic_default.as_deref(),
+        )
+    });
+    let version = minimal_version(&a.requirement);
+
+    let field = |field: &str| match field {
+        "id" => id.clone(),
+        "item" => a.item.clone(),
+        "reason" => reason.clone().unwrap_or_default(),
+        "replacement" => a.replacement.clone().unwrap_or_default(),
+        "version" => version
+            .as_ref()
+            .map_or_else(|| a.requirement.to_string(), Version::to_string),
+        "requirement" => a.requirement.to_string(),
+        _ => notice(a, reason.as_deref(), version.as_ref()),
+    };
+
+    let name = fill(
+        config
+            .fragment_name
+            .as_deref()
+            .unwrap_or(config::DEFAULT_FRAGMENT_NAME),
+        // so an id such as `net/legacy` can't name a file in another directory
+        |f| field(f).replace(['/', '\\'], "-"),
+    );
+    let mut text = fill(
+        config
+            .fragment_text
+            .as_deref()
+            .unwrap_or(config::DEFAULT_FRAGMENT_TEXT),
+        field,
+    );
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    Fragment { name, text }
+}
+
+/// The one-line removal notice of `a`, such as "Struct `Xml` is removed in 2.0.0: the format is
+/// unmaintained. Use `Json` instead."
+fn notice(a: &Annotation, reason: Option<&str>, version: Option<&Version>) -> String {
+    let mut notice = match version {
+        Some(version) => format!("{} is removed in {}", a.item, version),
+        None => format!(
+            "{} is removed once the version matches `{}`",
+            a.item, a.requirement
+        ),
+    };
+    if let Some(first) = notice.get(..1) {
+        notice.replace_range(..1, &first.to_uppercase());
+    }
+    if let Some(reason) = reason {
+        notice.push_str(&format!(": {}", reason.trim_end_matches('.')));
+    }
+    notice.push('.');
+    if let Some(replacement) = &a.replacement {
+        notice.push_str(&format!(" Use `{}` instead.", replacement));
+    }
+    notice
+}
+
+/// `template` with each `{field}` replaced by `value(field)`. The templates of the configuration
+/// only hold known fields, which [`config::fragment_fields`] checked.
+fn fill(template: &str, value: impl Fn(&str) -> String) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once('{') {
+        let Some((field, after)) = after.split_once('}') else {
+            break;
+        };
+        filled.push_str(before);
+        filled.push_str(&value(field));
+        rest = after;
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Whether `a` has expired at `version`, or expires within the `warn_ahead` of `config`.
+fn due(a: &Annotation, version: &Version, config: &Config) -> bool {
+    if a.requirement.matches(version) {
+        return true;
+    }
+    match (config.warn_ahead, minimal_version(&a.requirement)) {
+        (Some(ahead), Some(expiry)) => expiry > *version && expiry <= ahead.add_to(version),
+        _ => false,
+    }
+}
+
+/// Writes the fragments of the annotations that are due at `version`, or at the version of their
+/// package, to `dir`, each package's configuration naming and rendering its own. Fragments whose
+/// file exists already are skipped.
+pub fn write(
+    root: &Path,
+    packages: &[Package],
+    annotations: &[Annotation],
+    version: Option<&Version>,
+    dir: &Path,
+) -> Result<(), String> {
+    let mut configs: BTreeMap<&Path, Config> = BTreeMap::new();
+    let (mut written, mut existing) = (0, 0);
+
+    for a in annotations {
+        // the innermost package containing the file, as packages can be nested
+        let path = root.join(&a.file);
+        let Some((package, package_version)) = packages
+            .iter()
+            .filter(|(dir, _)| path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+        else {
+            continue;
+        };
+        let Some(version) = version.or(package_version.as_ref()) else {
+            eprintln!(
+                "warning: {}: skipping annotation, its package has no version",
+                a.location()
+            );
+            continue;
+        };
+        let config = configs
+            .entry(package)
+            .or_insert_with(|| config::load(package, package_version.as_ref(), verify::read));
+        if !due(a, version, config) {
+            continue;
+        }
+
+        let fragment = render(a, config);
+        let path: PathBuf = dir.join(&fragment.name);
+        if path.exists() {
+            println!(
+                "{}: {}: `{}` exists, skipping",
+                a.location(),
+                a.item,
+                path.display()
+            );
+            existing += 1;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create `{}`: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, &fragment.text)
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))?;
+        println!("{}: {}: {}", a.location(), a.item, path.display());
+        written += 1;
+    }
+
+    println!(
+        "wrote {} fragment(s) to `{}`, {} already existed",
+        written,
+        dir.display(),
+        existing
+    );
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! `cargo allow-until report --html`, which renders a JSON report as a single HTML page, with the
+//! styles and the script sorting and filtering its table inlined so the page can be passed around
+//! on its own.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use allow_until_report::{Annotation, AnnotationKind, Report};
+use semver::VersionReq;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+.milestones { display: flex; flex-wrap: wrap; gap: 0.75em; margin-bottom: 1.5em; }
+.milestone { border: 1px solid #ccc; border-radius: 4px; padding: 0.5em 0.75em; }
+.milestone code { font-weight: bold; }
+input { padding: 0.4em; width: 24em; margin-bottom: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em 0.6em; text-align: left; }
+th { cursor: pointer; user-select: none; background: #f4f4f4; }
+th[data-order=asc]::after { content: ' \\25B2'; }
+th[data-order=desc]::after { content: ' \\25BC'; }
+.expired { color: #b00020; font-weight: bold; }
+.window { color: #a15c00; }
+";
+
+const SCRIPT: &str = "
+const table = document.querySelector('table');
+const rows = () => Array.from(table.tBodies[0].rows);
+table.querySelectorAll('th').forEach((th, column) => th.addEventListener('click', () => {
+  const order = th.dataset.order === 'asc' ? 'desc' : 'asc';
+  table.querySelectorAll('th').forEach(other => delete other.dataset.order);
+  th.dataset.order = order;
+  const sorted = rows().sort((a, b) => a.cells[column].textContent.localeCompare(
+    b.cells[column].textContent, undefined, { numeric: true }));
+  if (order === 'desc') sorted.reverse();
+  table.tBodies[0].append(...sorted);
+}));
+document.querySelector('input').addEventListener('input', e => {
+  const query = e.target.value.toLowerCase();
+  rows().forEach(row => row.hidden = !row.textContent.toLowerCase().includes(query));
+});
+";
+
+/// The status of an annotation, as shown in the table, with its CSS class.
+fn status(a: &Annotation) -> (&'static str, &'static str) {
+    match (a.annotation_kind, a.outcome.matched, a.outcome.window) {
+        (_, _, true) => ("warning window", "window"),
+        (AnnotationKind::ChangesAt, true, false) => ("changed", ""),
+        (AnnotationKind::AllowUntil, true, false) => ("expired", "expired"),
+        (_, false, false) => ("active", ""),
+    }
+}
+
+/// Renders the annotations of `report` as a page with a table of them, preceded by a summary of
+/// each requirement, the milestone its items go away at.
+pub fn render(report: &Report) -> String {
+    let mut milestones: BTreeMap<(Milestone, &str), [usize; 3]> = BTreeMap::new();
+    for a in &report.annotations {
+        let requirement = &a.condition.requirement[..];
+        let counts = milestones
+            .entry((milestone(requirement), requirement))
+            .or_default();
+        counts[0] += 1;
+        match status(a).0 {
+            "expired" => counts[1] += 1,
+            "warning window" => counts[2] += 1,
+            _ => (),
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>allow-until overview</title>\n");
+    let _ = writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(
+        out,
+        "<h1>Scheduled removals</h1>\n<p>{} annotation(s)</p>",
+        report.annotations.len()
+    );
+
+    out.push_str("<div class=\"milestones\">\n");
+    for ((_, requirement), [total, expired, window]) in &milestones {
+        let _ = writeln!(
+            out,
+            "<div class=\"milestone\"><code>{}</code>: {} annotation(s), {} expired, {} in \
+             warning window</div>",
+            escape(requirement),
+            total,
+            expired,
+            window
+        );
+    }
+    out.push_str("</div>\n");
+
+    out.push_str("<input type=\"search\" placeholder=\"Filter\" aria-label=\"Filter\">\n");
+    out.push_str("<table>\n<thead><tr>");
+    for header in [
+        "Item",
+        "Crate",
+        "Requirement",
+        "Status",
+        "Owner",
+        "Tags",
+        "Issue",
+    ] {
+        let _ = write!(out, "<th>{}</th>", header);
+    }
+    out.push_str("</tr></thead>\n<tbody>\n");
+
+    for a in &report.annotations {
+        let (status, class) = status(a);
+        let item = match &a.item {
+            Some(item) => format!("{} <code>{}</code>", escape(&a.kind), escape(item)),
+            None => escape(&a.kind),
+        };
+        let issue = match &a.issue {
+            Some(issue) if issue.starts_with("https://") || issue.starts_with("http://") => {
+                format!("<a href=\"{0}\">{0}</a>", escape(issue))
+            }
+            Some(issue) => escape(issue),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            out,
+            "<tr><td title=\"{}:{}\">{}</td><td>{}</td><td><code>{}</code></td>\
+             <td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&a.file),
+            a.line,
+            item,
+            escape(a.package.as_deref().unwrap_or_default()),
+            escape(&a.condition.requirement),
+            class,
+            status,
+            escape(a.owner.as_deref().unwrap_or_default()),
+            escape(&a.tags.join(", ")),
+            issue
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "</tbody>\n</table>\n<script>{}</script>\n</body>\n</html>",
+        SCRIPT
+    );
+    out
+}
+
+type Milestone = (u64, u64, u64);
+
+/// The version of the first comparator of `requirement`, so milestones sort by their versions
+/// rather than as text. Requirements that don't parse come last.
+fn milestone(requirement: &str) -> Milestone {
+    match VersionReq::parse(requirement).map(|req| req.comparators.into_iter().next()) {
+        Ok(Some(c)) => (c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0)),
+        Ok(None) => (0, 0, 0),
+        Err(_) => (u64::MAX, 0, 0),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,119 @@
+//! The HTTP requests of the commands that talk to issue trackers, made with `curl`. Commands take
+//! an [`Http`] so they can be tested against canned responses.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// A response, whatever its status.
+pub struct Response {
+    pub status: u16,
+    /// The headers, with lowercase names.
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    /// The value of the header `name`, given in lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body parsed as JSON, `null` if it isn't.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap_or_default()
+    }
+}
+
+/// Makes requests. An error is a request that got no response, such as when the host can't be
+/// reached.
+pub trait Http {
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<Response, String>;
+}
+
+/// Makes requests with `curl`, without following redirects.
+pub struct Curl;
+
+impl Http for Curl {
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<Response, String> {
+        // the headers and the body are passed as a config on stdin, keeping tokens off the
+        // command line
+        let mut config = String::new();
+        for (name, value) in headers {
+            config.push_str(&format!("header = \"{}: {}\"\n", quote(name), quote(value)));
+        }
+        if let Some(body) = body {
+            config.push_str(&format!("data-binary = \"{}\"\n", quote(body)));
+        }
+
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--include", "--max-time", "30"])
+            .args(["--request", method, "--config", "-", "--"])
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to run `curl`: {}", e))?;
+        child
+            .stdin
+            .take()
+            .ok_or("failed to write to `curl`")?
+            .write_all(config.as_bytes())
+            .map_err(|e| format!("failed to write to `curl`: {}", e))?;
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("failed to run `curl`: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().into());
+        }
+
+        parse(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| format!("`{} {}` got an invalid response", method, url))
+    }
+}
+
+/// Parses a response as `curl --include` prints it, skipping interim responses such as
+/// `100 Continue`.
+fn parse(mut output: &str) -> Option<Response> {
+    loop {
+        let (head, body) = output.split_once("\r\n\r\n").unwrap_or((output, ""));
+        let mut lines = head.lines();
+        let status: u16 = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+        if (100..200).contains(&status) {
+            output = body;
+            continue;
+        }
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().into()))
+            .collect();
+        return Some(Response {
+            status,
+            headers,
+            body: body.into(),
+        });
+    }
+}
+
+/// `text` escaped for a quoted string of a `curl` config.
+fn quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
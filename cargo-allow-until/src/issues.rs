@@ -0,0 +1,143 @@
+//! `cargo allow-until issues`, which drafts a tracking issue for every annotation without an
+//! `issue`, and with `--create` opens them on GitHub. Nothing is sent anywhere without `--create`.
+
+use std::path::Path;
+
+use crate::{http::Http, scan::Annotation};
+
+/// A tracking issue for an annotation.
+pub struct Draft {
+    pub title: String,
+    /// The Markdown body.
+    pub body: String,
+}
+
+/// The tracking issue of `a`, titled after its item, with its reason, requirement, owner and
+/// location in the body.
+pub fn draft(a: &Annotation) -> Draft {
+    let title = format!("Remove {} ({})", a.item, a.requirement);
+
+    let mut body = format!(
+        "The `allow_until` annotation of {} expires at `{}`, when the item must be removed.\n\n",
+        a.item, a.requirement
+    );
+    body.push_str(&format!("- Requirement: `{}`\n", a.requirement));
+    match (&a.reason, &a.reason_key) {
+        (Some(reason), _) => body.push_str(&format!("- Reason: {}\n", reason)),
+        (None, Some(key)) => body.push_str(&format!(
+            "- Reason: `{}` in the `reasons` catalogue of `Cargo.toml`\n",
+            key
+        )),
+        (None, None) => (),
+    }
+    if let Some(owner) = &a.owner {
+        body.push_str(&format!("- Owner: {}\n", owner));
+    }
+    if let Some(id) = &a.id {
+        body.push_str(&format!("- Id: `{}`\n", id));
+    }
+    body.push_str(&format!("- Location: `{}`\n", a.location()));
+    body.push_str("\nLink this issue from the annotation with `issue = \"<url>\"`.\n");
+
+    Draft { title, body }
+}
+
+/// Writes the drafts of `annotations` to `dir` as Markdown files numbered in order, e.g.
+/// `001-struct-xml.md`, printing where each went.
+pub fn write(annotations: &[&Annotation], dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("failed to create `{}`: {}", dir.display(), e))?;
+
+    for (index, a) in annotations.iter().enumerate() {
+        let draft = draft(a);
+        let path = dir.join(format!("{:03}-{}.md", index + 1, slug(&a.item)));
+        std::fs::write(&path, format!("# {}\n\n{}", draft.title, draft.body))
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))?;
+        println!("{}: {}: {}", a.location(), a.item, path.display());
+    }
+    println!(
+        "wrote {} draft(s) to `{}`",
+        annotations.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Where issues are opened.
+pub trait Tracker {
+    /// Opens an issue, returning its URL.
+    fn create(&self, draft: &Draft) -> Result<String, String>;
+}
+
+/// Opens the drafts of `annotations` with `tracker`, printing the `issue` argument to add to each
+/// annotation. Stops at the first failure, after printing the issues opened so far, so their
+/// annotations can be updated before the command is run again.
+pub fn create(annotations: &[&Annotation], tracker: &impl Tracker) -> Result<(), String> {
+    for a in annotations {
+        let url = tracker
+            .create(&draft(a))
+            .map_err(|e| format!("failed to open the issue of {}: {}", a.item, e))?;
+        println!("{}: {}: issue = \"{}\"", a.location(), a.item, url);
+    }
+    println!("opened {} issue(s)", annotations.len());
+    Ok(())
+}
+
+/// The issues of a GitHub repository, opened through its REST API.
+pub struct GitHub<H> {
+    pub http: H,
+    /// The base URL of the API, `GITHUB_API_URL` or `https://api.github.com`.
+    pub api: String,
+    /// The repository, such as `org/name`.
+    pub repo: String,
+    pub token: String,
+}
+
+impl<H: Http> Tracker for GitHub<H> {
+    fn create(&self, draft: &Draft) -> Result<String, String> {
+        let url = format!(
+            "{}/repos/{}/issues",
+            self.api.trim_end_matches('/'),
+            self.repo
+        );
+        let data = serde_json::json!({ "title": draft.title, "body": draft.body }).to_string();
+        let authorization = format!("Bearer {}", self.token);
+        let response = self.http.request(
+            "POST",
+            &url,
+            &[
+                ("Accept", "application/vnd.github+json"),
+                ("Content-Type", "application/json"),
+                ("Authorization", &authorization),
+            ],
+            Some(&data),
+        )?;
+
+        let json = response.json();
+        match (response.status, json["html_url"].as_str()) {
+            (201, Some(url)) => Ok(url.into()),
+            (status, _) => Err(format!(
+                "`POST {}` answered {}{}",
+                url,
+                status,
+                json["message"]
+                    .as_str()
+                    .map_or(String::new(), |message| format!(": {}", message))
+            )),
+        }
+    }
+}
+
+/// The item in lowercase with runs of anything but letters and digits replaced by a `-`, e.g.
+/// `struct-xml` for "struct `Xml`".
+fn slug(item: &str) -> String {
+    let mut slug = String::new();
+    for c in item.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').into()
+}
@@ -0,0 +1,810 @@
+//! `cargo allow-until`, which finds the `allow_until` annotations of a workspace by parsing its
+//! source, so they can be listed and checked against a version without building anything.
+//!
+//! ```text
+//! cargo allow-until list
+//! cargo allow-until check --at-version 2.0.0
+//! cargo allow-until fix --dry-run
+//! cargo allow-until bump --expiring-at 2.0.0 --to ">=2.1"
+//! cargo allow-until report --html overview.html
+//! cargo allow-until report --merge
+//! cargo allow-until verify
+//! cargo allow-until issues --out drafts
+//! cargo allow-until audit --fail-on closed
+//! cargo allow-until diff --since v1.4.0 --format markdown
+//! cargo allow-until fragments --out changelog.d
+//! ```
+
+mod audit;
+mod diff;
+mod fix;
+mod fragments;
+mod html;
+mod http;
+mod issues;
+mod scan;
+mod verify;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+use scan::Annotation;
+use semver::{Version, VersionReq};
+
+const USAGE: &str = "\
+usage: cargo allow-until [--manifest-path <path>] <command>
+
+commands:
+    list                          list every annotation in the workspace
+    check --at-version <version>  fail if any annotation would fire at <version>
+    fix [--at-version <version>]  delete the items of expired annotations
+    bump --to <requirement>       replace the requirement of the selected annotations
+    report --html <path>          render a JSON report as an HTML page
+    report --merge                merge the reports of the packages into one
+    verify                        check the annotations against the configured policy
+    issues                        draft tracking issues for the annotations without `issue`
+    audit                         report the annotations whose `issue` is closed, missing or
+                                  was transferred
+    diff --since <revision>       list the annotations added, removed or changed since a git
+                                  revision
+    fragments                     write changelog fragments for the annotations that have expired
+                                  or expire within `warn_ahead`
+
+options for `fix`:
+    --attr-only    only delete the annotations, not the items
+
+options for `bump`, which selects the annotations matching all of the given filters:
+    --id <id>                  select the annotation with this id, can be repeated
+    --tag <tag>                select the annotations with this tag, can be repeated
+    --expiring-at <version>    select the annotations that fire at <version>
+    --require-reason <text>    append <text> to the reason of each bumped annotation
+
+options for `report`:
+    --from <path>  the JSON report to render, `allow-until.jsonl` by default, or with `--merge`
+                   the directory of the reports to merge, `target/allow-until` by default
+    --out <path>   where `--merge` writes the merged report, `allow-until.jsonl` by default,
+                   which `--html` then renders
+    --prune        remove the reports of packages that aren't in the workspace anymore, which
+                   `--merge` otherwise skips with a warning
+
+options for `issues`, which needs at least one of `--out` and `--create`:
+    --out <dir>          write the drafts to <dir> as Markdown files
+    --create             open the issues on GitHub with the token in `GITHUB_TOKEN`, printing
+                         the `issue` argument of each annotation
+    --repo <org/name>    the repository to open the issues in, required by `--create`
+
+options for `audit`, which reads tokens from `GITHUB_TOKEN` and `GITLAB_TOKEN`:
+    --fail-on <states>   fail if any issue is in one of the comma-separated states `closed`,
+                         `missing` and `transferred`, can be repeated
+
+options for `diff`:
+    --since-dir <dir>    compare with a checkout of the workspace at <dir> instead of a revision
+    --format <format>    `text` (the default), `json` or `markdown`
+
+options for `fragments`, which skips the fragments that exist already:
+    --out <dir>               where to write the fragments, `changelog.d` by default
+    --at-version <version>    the version to check against instead of that of each package
+
+options for `fix` and `bump`:
+    --dry-run      print a diff of the edits instead of making them
+    --allow-dirty  edit files with uncommitted changes";
+
+fn main() -> ExitCode {
+    // `cargo allow-until` runs this binary as `cargo-allow-until allow-until ...`
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("allow-until") {
+        args.remove(0);
+    }
+
+    match run(args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<ExitCode, String> {
+    let mut manifest_path = None;
+    let mut at_version = None;
+    let mut command = None;
+    let mut options = EditOptions::default();
+    let mut bump = BumpOptions::default();
+    let mut from = None;
+    let mut html = None;
+    let mut out = None;
+    let mut create = false;
+    let mut repo = None;
+    let mut fail_on = Vec::new();
+    let mut merge = false;
+    let mut prune = false;
+    let mut since = None;
+    let mut format = diff::Format::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--manifest-path" => manifest_path = Some(value(&mut args, &arg)?),
+            "--at-version" => at_version = Some(version(&mut args, &arg)?),
+            "--id" => bump.ids.push(value(&mut args, &arg)?),
+            "--tag" => bump.tags.push(value(&mut args, &arg)?),
+            "--expiring-at" => bump.expiring_at = Some(version(&mut args, &arg)?),
+            "--to" => {
+                let v = value(&mut args, &arg)?;
+                VersionReq::parse(&v).map_err(|e| format!("invalid requirement `{}`: {}", v, e))?;
+                bump.to = Some(v);
+            }
+            "--require-reason" => bump.reason = Some(value(&mut args, &arg)?),
+            "--from" => from = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--html" => html = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--out" => out = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--create" => create = true,
+            "--merge" => merge = true,
+            "--prune" => prune = true,
+            "--repo" => repo = Some(value(&mut args, &arg)?),
+            "--since" => since = Some(diff::Since::Revision(value(&mut args, &arg)?)),
+            "--since-dir" => since = Some(diff::Since::Dir(value(&mut args, &arg)?.into())),
+            "--format" => format = diff::Format::parse(&value(&mut args, &arg)?)?,
+            "--fail-on" => {
+                for state in value(&mut args, &arg)?.split(',') {
+                    if !audit::FAILING.contains(&state.trim()) {
+                        return Err(format!(
+                            "invalid state `{}` for `--fail-on`, expected `closed`, `missing` or \
+                             `transferred`",
+                            state
+                        ));
+                    }
+                    fail_on.push(state.trim().to_string());
+                }
+            }
+            "--attr-only" => options.attr_only = true,
+            "--dry-run" => options.dry_run = true,
+            "--allow-dirty" => options.allow_dirty = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(ExitCode::SUCCESS);
+            }
+            _ if command.is_none() && !arg.starts_with('-') => command = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let annotations = || find(manifest_path.as_deref()).map(|(_, annotations)| annotations);
+
+    match command.as_deref() {
+        Some("list") => {
+            list(&annotations()?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("check") => {
+            let version = at_version.ok_or("`check` requires `--at-version <version>`")?;
+            Ok(check(&annotations()?, &version))
+        }
+        Some("fix") => {
+            let (root, annotations) = find(manifest_path.as_deref())?;
+            fix(&root, &annotations, at_version.as_ref(), &options)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("bump") => {
+            if bump.to.is_none() {
+                return Err("`bump` requires `--to <requirement>`".into());
+            }
+            if bump.ids.is_empty() && bump.tags.is_empty() && bump.expiring_at.is_none() {
+                return Err(
+                    "`bump` requires at least one of `--id`, `--tag` and `--expiring-at`".into(),
+                );
+            }
+            let (root, annotations) = find(manifest_path.as_deref())?;
+            self::bump(&root, &annotations, &bump, &options)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("report") => {
+            if html.is_none() && !merge {
+                return Err("`report` requires `--html <path>` or `--merge`".into());
+            }
+            let mut from = from;
+            if merge {
+                let out = out.unwrap_or_else(|| "allow-until.jsonl".into());
+                self::merge(manifest_path.as_deref(), from.take(), &out, prune)?;
+                from = Some(out);
+            }
+            if let Some(html) = html {
+                let from = from.unwrap_or_else(|| "allow-until.jsonl".into());
+                report(&from, &html)?;
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("verify") => {
+            let (root, packages) = workspace(manifest_path.as_deref())?;
+            Ok(verify::verify(&root, &packages))
+        }
+        Some("issues") => {
+            if out.is_none() && !create {
+                return Err("`issues` requires `--out <dir>` or `--create`".into());
+            }
+            // checked before anything is written, so a missing token doesn't leave drafts behind
+            let github = match (create, repo) {
+                (false, _) => None,
+                (true, None) => return Err("`--create` requires `--repo <org/name>`".into()),
+                (true, Some(repo)) => Some(issues::GitHub {
+                    http: http::Curl,
+                    api: std::env::var("GITHUB_API_URL")
+                        .unwrap_or_else(|_| "https://api.github.com".into()),
+                    repo,
+                    token: std::env::var("GITHUB_TOKEN")
+                        .map_err(|_| "`--create` requires a token in `GITHUB_TOKEN`")?,
+                }),
+            };
+
+            let annotations = annotations()?;
+            let missing: Vec<_> = annotations.iter().filter(|a| a.issue.is_none()).collect();
+            if missing.is_empty() {
+                println!("every annotation has an `issue`");
+                return Ok(ExitCode::SUCCESS);
+            }
+            if let Some(out) = &out {
+                issues::write(&missing, out)?;
+            }
+            if let Some(github) = &github {
+                issues::create(&missing, github)?;
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("audit") => {
+            let token = |var| {
+                std::env::var(var)
+                    .ok()
+                    .filter(|token: &String| !token.is_empty())
+            };
+            let trackers = audit::Trackers {
+                github_api: std::env::var("GITHUB_API_URL")
+                    .unwrap_or_else(|_| "https://api.github.com".into()),
+                github_token: token("GITHUB_TOKEN"),
+                gitlab_token: token("GITLAB_TOKEN"),
+                offline: std::env::var("ALLOW_UNTIL_OFFLINE")
+                    .is_ok_and(|v| v.trim() == "1")
+                    .then(|| "`ALLOW_UNTIL_OFFLINE=1`".into()),
+            };
+            let mut auditor = audit::Auditor::new(http::Curl, trackers);
+            Ok(audit::audit(&annotations()?, &mut auditor, &fail_on))
+        }
+        Some("diff") => {
+            let since =
+                since.ok_or("`diff` requires `--since <revision>` or `--since-dir <dir>`")?;
+            let (root, packages) = workspace(manifest_path.as_deref())?;
+            let (_, annotations) = find(manifest_path.as_deref())?;
+            diff::diff(&root, &packages, &annotations, &since, format)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("fragments") => {
+            let (root, packages) = workspace(manifest_path.as_deref())?;
+            let (_, annotations) = find(manifest_path.as_deref())?;
+            let out = out.unwrap_or_else(|| "changelog.d".into());
+            fragments::write(&root, &packages, &annotations, at_version.as_ref(), &out)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(other) => Err(format!("unknown command `{}`\n\n{}", other, USAGE)),
+        None => Err(USAGE.into()),
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("`{}` requires a value", flag))
+}
+
+fn version(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<Version, String> {
+    let v = value(args, flag)?;
+    Version::parse(&v).map_err(|e| format!("invalid version `{}`: {}", v, e))
+}
+
+fn list(annotations: &[Annotation]) {
+    let rows: Vec<[String; 7]> = annotations
+        .iter()
+        .map(|a| {
+            [
+                a.location(),
+                a.item.clone(),
+                a.requirement.to_string(),
+                a.reason.clone().unwrap_or_default(),
+                a.id.clone().unwrap_or_default(),
+                a.owner.clone().unwrap_or_default(),
+                a.cfg.join(", "),
+            ]
+        })
+        .collect();
+
+    print_table(
+        [
+            "LOCATION",
+            "ITEM",
+            "REQUIREMENT",
+            "REASON",
+            "ID",
+            "OWNER",
+            "CFG",
+        ],
+        &rows,
+    );
+}
+
+fn check(annotations: &[Annotation], version: &Version) -> ExitCode {
+    let fired: Vec<_> = annotations
+        .iter()
+        .filter(|a| a.requirement.matches(version))
+        .collect();
+
+    for a in &fired {
+        println!(
+            "{}: {} not allowed! (version {} matches {}){}",
+            a.location(),
+            a.reason.as_ref().unwrap_or(&a.item),
+            version,
+            a.requirement,
+            if a.cfg.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", a.cfg.join(", "))
+            }
+        );
+    }
+
+    if fired.is_empty() {
+        println!(
+            "none of the {} annotation(s) fire at version {}",
+            annotations.len(),
+            version
+        );
+        ExitCode::SUCCESS
+    } else {
+        println!(
+            "{} of the {} annotation(s) fire at version {}",
+            fired.len(),
+            annotations.len(),
+            version
+        );
+        ExitCode::FAILURE
+    }
+}
+
+#[derive(Default)]
+struct EditOptions {
+    attr_only: bool,
+    dry_run: bool,
+    allow_dirty: bool,
+}
+
+/// Deletes the items (or only the annotations) of the annotations that have expired at `version`,
+/// or at the version of their package.
+fn fix(
+    root: &Path,
+    annotations: &[Annotation],
+    version: Option<&Version>,
+    options: &EditOptions,
+) -> Result<(), String> {
+    let mut edits: BTreeMap<&Path, Vec<&Annotation>> = BTreeMap::new();
+
+    for a in annotations {
+        let Some(version) = version.or(a.package_version.as_ref()) else {
+            eprintln!(
+                "warning: {}: skipping annotation, its package has no version",
+                a.location()
+            );
+            continue;
+        };
+        if !a.requirement.matches(version) {
+            continue;
+        }
+        if options.attr_only && a.attr_shared {
+            eprintln!(
+                "warning: {}: skipping annotation, its `cfg_attr` holds other attributes",
+                a.location()
+            );
+            continue;
+        }
+
+        edits.entry(&a.file).or_default().push(a);
+    }
+
+    edit(root, &edits, options, |source, a| {
+        let range = if options.attr_only {
+            a.attr
+        } else {
+            a.item_range
+        };
+        let report = if options.attr_only {
+            format!("removed the annotation of {}", a.item)
+        } else {
+            format!("removed {}", a.item)
+        };
+        (vec![(fix::removal(source, range), String::new())], report)
+    })
+}
+
+#[derive(Default)]
+struct BumpOptions {
+    ids: Vec<String>,
+    tags: Vec<String>,
+    expiring_at: Option<Version>,
+    to: Option<String>,
+    reason: Option<String>,
+}
+
+impl BumpOptions {
+    fn selects(&self, a: &Annotation) -> bool {
+        (self.ids.is_empty() || a.id.as_ref().is_some_and(|id| self.ids.contains(id)))
+            && (self.tags.is_empty() || a.tags.iter().any(|t| self.tags.contains(t)))
+            && self
+                .expiring_at
+                .as_ref()
+                .is_none_or(|v| a.requirement.matches(v))
+    }
+}
+
+/// Replaces the requirement of the selected annotations with `bump.to`, appending `bump.reason`
+/// to their reasons, except for reasons taken from the catalogue with `reason_key`.
+fn bump(
+    root: &Path,
+    annotations: &[Annotation],
+    bump: &BumpOptions,
+    options: &EditOptions,
+) -> Result<(), String> {
+    let to = bump.to.as_deref().unwrap_or_default();
+
+    let mut edits: BTreeMap<&Path, Vec<&Annotation>> = BTreeMap::new();
+    for a in annotations.iter().filter(|a| bump.selects(a)) {
+        edits.entry(&a.file).or_default().push(a);
+    }
+
+    edit(root, &edits, options, |source, a| {
+        let mut edits = vec![fix::literal(source, a.version_literal, to)];
+        let mut message = format!("bumped {} from `{}` to `{}`", a.item, a.requirement, to);
+
+        if let (Some(_), Some(key)) = (&bump.reason, &a.reason_key) {
+            // a `reason` would conflict with the key, and the catalogue is shared by other annotations
+            message.push_str(&format!(
+                ", leaving its reason from `reason_key = \"{}\"` as it is",
+                key
+            ));
+        } else if let Some(text) = &bump.reason {
+            match (&a.reason, a.reason_literal) {
+                (Some(reason), Some(range)) => edits.push(fix::literal(
+                    source,
+                    range,
+                    &format!("{}; {}", reason, text),
+                )),
+                _ => {
+                    let end = fix::offset(source, a.arguments_end);
+                    edits.push((end..end, format!(", reason = {:?}", text)));
+                }
+            }
+        }
+
+        (edits, message)
+    })
+}
+
+/// Renders the JSON report at `from` as an HTML page at `html`.
+fn report(from: &Path, html: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(from)
+        .map_err(|e| format!("failed to read `{}`: {}", from.display(), e))?;
+    let report = allow_until_report::Report::parse(&text)
+        .map_err(|e| format!("invalid report `{}`: {}", from.display(), e))?;
+
+    std::fs::write(html, html::render(&report))
+        .map_err(|e| format!("failed to write `{}`: {}", html.display(), e))?;
+    println!(
+        "{} annotation(s) written to `{}`",
+        report.annotations.len(),
+        html.display()
+    );
+    Ok(())
+}
+
+/// Merges the reports of the packages in `dir`, `allow-until` in the target directory of the
+/// workspace by default, into the report at `out`. The reports of packages that aren't in the
+/// workspace anymore are skipped with a warning, or removed with `prune`.
+fn merge(
+    manifest_path: Option<&str>,
+    dir: Option<PathBuf>,
+    out: &Path,
+    prune: bool,
+) -> Result<(), String> {
+    let metadata = metadata(manifest_path)?;
+    let dir = match dir {
+        Some(dir) => dir,
+        None => Path::new(
+            metadata["target_directory"]
+                .as_str()
+                .ok_or("`cargo metadata` output has no `target_directory`")?,
+        )
+        .join("allow-until"),
+    };
+    let packages: HashSet<&str> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p["name"].as_str())
+        .collect();
+
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| format!("failed to read `{}`: {}", dir.display(), e))?;
+    let mut reports = BTreeMap::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        // the lock files next to the reports end with `.json.lock`
+        let Some(package) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+        else {
+            continue;
+        };
+
+        if !packages.contains(package) {
+            if prune {
+                std::fs::remove_file(&path)
+                    .map_err(|e| format!("failed to remove `{}`: {}", path.display(), e))?;
+                println!(
+                    "removed `{}`, `{}` is not a package of the workspace",
+                    path.display(),
+                    package
+                );
+            } else {
+                eprintln!(
+                    "warning: skipping `{}`, `{}` is not a package of the workspace, `--prune` \
+                     removes it",
+                    path.display(),
+                    package
+                );
+            }
+            continue;
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+        let report = allow_until_report::Report::parse(&text)
+            .map_err(|e| format!("invalid report `{}`: {}", path.display(), e))?;
+        reports.insert(package.to_string(), report);
+    }
+
+    let count = reports.len();
+    let report = allow_until_report::Report::merge(reports);
+    std::fs::write(out, report.to_string())
+        .map_err(|e| format!("failed to write `{}`: {}", out.display(), e))?;
+    println!(
+        "{} annotation(s) of {} package(s) merged into `{}`",
+        report.annotations.len(),
+        count,
+        out.display()
+    );
+    Ok(())
+}
+
+/// Edits the files of `annotations`, or prints a diff of the edits with `--dry-run`. `edit`
+/// returns the edits for an annotation along with the line to print for it.
+fn edit(
+    root: &Path,
+    annotations: &BTreeMap<&Path, Vec<&Annotation>>,
+    options: &EditOptions,
+    edit: impl Fn(&str, &Annotation) -> (Vec<fix::Edit>, String),
+) -> Result<(), String> {
+    if !options.dry_run && !options.allow_dirty {
+        let dirty: Vec<_> = annotations
+            .keys()
+            .filter_map(|file| match modified(root, file) {
+                Ok(false) => None,
+                Ok(true) => Some(Ok(format!("`{}`", file.display()))),
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        if !dirty.is_empty() {
+            return Err(format!(
+                "refusing to edit files with uncommitted changes: {}\n\
+                 commit them first, or pass `--allow-dirty`",
+                dirty.join(", ")
+            ));
+        }
+    }
+
+    let mut count = 0;
+    for (file, annotations) in annotations {
+        let path = root.join(file);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read `{}`: {}", file.display(), e))?;
+
+        let mut edits = Vec::new();
+        let mut reports = Vec::new();
+        for a in annotations {
+            let (e, report) = edit(&source, a);
+            edits.extend(e);
+            reports.push(format!("{}: {}", a.location(), report));
+        }
+        let edited = fix::apply(&source, edits);
+
+        if options.dry_run {
+            let (old, new) = (
+                format!("a/{}", file.display()),
+                format!("b/{}", file.display()),
+            );
+            print!(
+                "{}",
+                similar::TextDiff::from_lines(&source, &edited)
+                    .unified_diff()
+                    .header(&old, &new)
+            );
+        } else {
+            std::fs::write(&path, edited)
+                .map_err(|e| format!("failed to write `{}`: {}", file.display(), e))?;
+
+            for report in reports {
+                println!("{}", report);
+            }
+        }
+
+        count += annotations.len();
+    }
+
+    if !options.dry_run {
+        println!("{} edit(s) in {} file(s)", count, annotations.len());
+    }
+    Ok(())
+}
+
+/// Whether git reports uncommitted changes to `file`.
+fn modified(root: &Path, file: &Path) -> Result<bool, String> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["status", "--porcelain", "--"])
+        .arg(file)
+        .output()
+        .map_err(|e| format!("failed to run `git status`: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "can't tell whether `{}` has uncommitted changes, pass `--allow-dirty` to edit it \
+             anyway:\n{}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(!output.stdout.is_empty())
+}
+
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: [&str; N]| {
+        let mut out = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            if i + 1 == N {
+                out.push_str(cell);
+            } else {
+                out.push_str(&format!("{:width$}  ", cell, width = widths[i]));
+            }
+        }
+        println!("{}", out.trim_end());
+    };
+
+    line(header);
+    for row in rows {
+        line(row.each_ref().map(String::as_str));
+    }
+}
+
+/// Finds the annotations in every package of the workspace, sorted by location, along with the
+/// root of the workspace.
+fn find(manifest_path: Option<&str>) -> Result<(PathBuf, Vec<Annotation>), String> {
+    let (root, packages) = workspace(manifest_path)?;
+
+    let mut files = BTreeMap::new();
+    for (package, version) in &packages {
+        let mut found = Vec::new();
+        sources(package, package, &mut found);
+        files.extend(found.into_iter().map(|f| (f, version)));
+    }
+
+    let mut annotations = Vec::new();
+    for (file, version) in files {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read `{}`: {}", file.display(), e))?;
+        let relative = file.strip_prefix(&root).unwrap_or(&file);
+
+        match scan::file(relative, &source) {
+            Ok((found, warnings)) => {
+                annotations.extend(found.into_iter().map(|a| Annotation {
+                    package_version: version.clone(),
+                    ..a
+                }));
+                for warning in warnings {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+            Err(e) => eprintln!("warning: skipping `{}`: {}", relative.display(), e),
+        }
+    }
+
+    annotations.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    Ok((root, annotations))
+}
+
+type Package = (PathBuf, Option<Version>);
+
+/// The workspace root and the directories and versions of its packages, from `cargo metadata`.
+fn workspace(manifest_path: Option<&str>) -> Result<(PathBuf, Vec<Package>), String> {
+    let metadata = metadata(manifest_path)?;
+
+    let root = metadata["workspace_root"]
+        .as_str()
+        .ok_or("`cargo metadata` output has no `workspace_root`")?;
+    let packages = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            let dir = Path::new(p["manifest_path"].as_str()?).parent()?.into();
+            let version = p["version"].as_str().and_then(|v| Version::parse(v).ok());
+            Some((dir, version))
+        })
+        .collect();
+
+    Ok((root.into(), packages))
+}
+
+/// The output of `cargo metadata` for the workspace, without its dependencies.
+fn metadata(manifest_path: Option<&str>) -> Result<serde_json::Value, String> {
+    let mut cmd = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    cmd.args(["metadata", "--no-deps", "--format-version", "1"]);
+    if let Some(path) = manifest_path {
+        cmd.args(["--manifest-path", path]);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("failed to run `cargo metadata`: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "`cargo metadata` failed:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("invalid `cargo metadata` output: {}", e))
+}
+
+/// Collects the Rust files of the package in `dir`, without descending into `target` directories,
+/// hidden directories and other packages.
+fn sources(package: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if path.is_dir() {
+            if name == "target" || name.starts_with('.') {
+                continue;
+            }
+            if path != package && path.join("Cargo.toml").exists() {
+                continue;
+            }
+            sources(package, &path, files);
+        } else if name.ends_with(".rs") {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,503 @@
+//! Finding the `allow_until` annotations in source files without compiling them.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{LineColumn, Span, TokenStream};
+use semver::{Version, VersionReq};
+use syn::{
+    punctuated::{Pair, Punctuated},
+    spanned::Spanned,
+    visit::Visit,
+    Attribute, Expr, Fields, Item, Lit, Meta, MetaNameValue, Token,
+};
+
+/// The start and end of a piece of source code.
+pub type Range = (LineColumn, LineColumn);
+
+pub struct Annotation {
+    /// The file the annotation is in, relative to the workspace root.
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// A description of the annotated item such as "struct `Foo`".
+    pub item: String,
+    pub requirement: VersionReq,
+    pub reason: Option<String>,
+    /// The key of the reason in the crate's catalogue, for annotations using `reason_key`.
+    pub reason_key: Option<String>,
+    pub id: Option<String>,
+    pub tags: Vec<String>,
+    pub owner: Option<String>,
+    pub issue: Option<String>,
+    /// What replaces the item.
+    pub replacement: Option<String>,
+    /// The generic parameter whose default is going away rather than the item.
+    pub generic_default: Option<String>,
+    /// The `cfg` predicates the annotation, or an item containing it, is conditional on.
+    pub cfg: Vec<String>,
+    /// The version of the package the annotation is in.
+    pub package_version: Option<Version>,
+    /// The attribute, a whole `#[cfg_attr(...)]` for annotations inside one.
+    pub attr: Range,
+    /// Whether the attribute holds other attributes besides the annotation, i.e. it is a
+    /// `cfg_attr` that can't be removed as a whole.
+    pub attr_shared: bool,
+    /// The annotated item with its attributes, and the comma after it for fields and variants.
+    pub item_range: Range,
+    /// The string literal of the `version` argument.
+    pub version_literal: Range,
+    /// The string literal of the `reason` argument.
+    pub reason_literal: Option<Range>,
+    /// The end of the last argument, where new arguments can be added.
+    pub arguments_end: LineColumn,
+}
+
+impl Annotation {
+    pub fn location(&self) -> String {
+        format!("{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// An annotation as written, whether or not the commands understand its arguments.
+pub struct Written {
+    pub line: usize,
+    pub column: usize,
+    /// A description of the annotated item such as "struct `Foo`".
+    pub item: String,
+    /// The tokens between the parentheses of the annotation.
+    pub arguments: TokenStream,
+}
+
+/// Parses `source` and returns its annotations, along with warnings about annotations that
+/// couldn't be understood.
+pub fn file(path: &Path, source: &str) -> Result<(Vec<Annotation>, Vec<String>), syn::Error> {
+    let scanner = scan(path, source)?;
+    Ok((scanner.annotations, scanner.warnings))
+}
+
+/// Parses `source` and returns every annotation as written.
+pub fn written(path: &Path, source: &str) -> Result<Vec<Written>, syn::Error> {
+    Ok(scan(path, source)?.written)
+}
+
+fn scan<'a>(path: &'a Path, source: &str) -> Result<Scanner<'a>, syn::Error> {
+    let ast = syn::parse_file(source)?;
+
+    let mut scanner = Scanner {
+        file: path,
+        annotations: Vec::new(),
+        written: Vec::new(),
+        warnings: Vec::new(),
+        cfg: Vec::new(),
+        parents: Vec::new(),
+    };
+    scanner.visit_file(&ast);
+
+    Ok(scanner)
+}
+
+struct Scanner<'a> {
+    file: &'a Path,
+    annotations: Vec<Annotation>,
+    written: Vec<Written>,
+    warnings: Vec<String>,
+    cfg: Vec<String>,
+    /// The names of the types and variants being visited, to name their members.
+    parents: Vec<String>,
+}
+
+impl Scanner<'_> {
+    /// Records the annotations among `attrs`, then visits the children of the item with its `cfg`
+    /// predicates in effect.
+    fn item(
+        &mut self,
+        attrs: &[Attribute],
+        item: String,
+        range: Range,
+        children: impl FnOnce(&mut Self),
+    ) {
+        let depth = self.cfg.len();
+        self.cfg.extend(attrs.iter().filter_map(cfg));
+
+        for attr in attrs {
+            for found in annotations(attr) {
+                self.record(found, &item, range);
+            }
+        }
+
+        children(self);
+        self.cfg.truncate(depth);
+    }
+
+    fn record(&mut self, found: Found, item: &str, item_range: Range) {
+        let start = found.attr.0;
+
+        let mut cfg = self.cfg.clone();
+        cfg.extend(found.cfg_attr);
+
+        self.written.push(Written {
+            line: start.line,
+            column: start.column + 1,
+            item: item.into(),
+            arguments: found.args.clone(),
+        });
+        match Args::parse(found.args) {
+            Ok(args) => self.annotations.push(Annotation {
+                file: self.file.into(),
+                line: start.line,
+                column: start.column + 1,
+                item: item.into(),
+                requirement: args.requirement,
+                reason: args.reason,
+                reason_key: args.reason_key,
+                id: args.id,
+                tags: args.tags,
+                owner: args.owner,
+                issue: args.issue,
+                replacement: args.replacement,
+                generic_default: args.generic_default,
+                cfg,
+                package_version: None,
+                attr: found.attr,
+                attr_shared: found.shared,
+                item_range,
+                version_literal: args.version_literal,
+                reason_literal: args.reason_literal,
+                arguments_end: args.end,
+            }),
+            Err(e) => self.warnings.push(format!(
+                "{}:{}:{}: ignoring annotation: {}",
+                self.file.display(),
+                start.line,
+                start.column + 1,
+                e
+            )),
+        }
+    }
+
+    fn member(&mut self, attrs: &[Attribute], kind: &str, name: String, range: Range) {
+        let path = match self.parents.last() {
+            Some(parent) => format!("{}::{}", parent, name),
+            None => name,
+        };
+
+        self.item(attrs, format!("{} `{}`", kind, path), range, |_| ());
+    }
+
+    fn fields(&mut self, fields: &Fields) {
+        let fields = match fields {
+            Fields::Named(f) => &f.named,
+            Fields::Unnamed(f) => &f.unnamed,
+            Fields::Unit => return,
+        };
+
+        for (index, pair) in fields.pairs().enumerate() {
+            let field = pair.value();
+            let name = field
+                .ident
+                .as_ref()
+                .map_or(index.to_string(), |i| unraw(i.to_string()));
+
+            self.member(&field.attrs, "field", name, member_range(&pair));
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Scanner<'_> {
+    fn visit_item(&mut self, item: &'ast Item) {
+        let (attrs, kind, name): (&[Attribute], &str, Option<String>) = match item {
+            Item::Const(i) => (&i.attrs, "const", Some(i.ident.to_string())),
+            Item::Enum(i) => (&i.attrs, "enum", Some(i.ident.to_string())),
+            Item::ExternCrate(i) => (&i.attrs, "extern crate", Some(i.ident.to_string())),
+            Item::Fn(i) => (&i.attrs, "fn", Some(i.sig.ident.to_string())),
+            Item::ForeignMod(i) => (&i.attrs, "extern block", None),
+            Item::Impl(i) => (&i.attrs, "impl", None),
+            Item::Macro(i) => (&i.attrs, "macro", i.ident.as_ref().map(|i| i.to_string())),
+            Item::Mod(i) => (&i.attrs, "module", Some(i.ident.to_string())),
+            Item::Static(i) => (&i.attrs, "static", Some(i.ident.to_string())),
+            Item::Struct(i) => (&i.attrs, "struct", Some(i.ident.to_string())),
+            Item::Trait(i) => (&i.attrs, "trait", Some(i.ident.to_string())),
+            Item::TraitAlias(i) => (&i.attrs, "trait alias", Some(i.ident.to_string())),
+            Item::Type(i) => (&i.attrs, "type alias", Some(i.ident.to_string())),
+            Item::Union(i) => (&i.attrs, "union", Some(i.ident.to_string())),
+            Item::Use(i) => (&i.attrs, "use", None),
+            _ => (&[], "item", None),
+        };
+        let name = name.map(unraw);
+        let described = match &name {
+            Some(name) => format!("{} `{}`", kind, name),
+            None => kind.into(),
+        };
+
+        self.item(attrs, described, range(item.span()), |scanner| match item {
+            // the members of types are visited here rather than through `visit_field` so they can
+            // be named after their parent
+            Item::Struct(i) => {
+                scanner.parents.push(name.unwrap_or_default());
+                scanner.fields(&i.fields);
+                scanner.parents.pop();
+            }
+            Item::Union(i) => {
+                scanner.parents.push(name.unwrap_or_default());
+                scanner.fields(&Fields::Named(i.fields.clone()));
+                scanner.parents.pop();
+            }
+            Item::Enum(i) => {
+                let name = name.unwrap_or_default();
+                for pair in i.variants.pairs() {
+                    let variant = pair.value();
+                    let variant_name = format!("{}::{}", name, unraw(variant.ident.to_string()));
+
+                    scanner.parents.push(name.clone());
+                    scanner.member(
+                        &variant.attrs,
+                        "variant",
+                        unraw(variant.ident.to_string()),
+                        member_range(&pair),
+                    );
+                    scanner.parents.pop();
+
+                    let depth = scanner.cfg.len();
+                    scanner.cfg.extend(variant.attrs.iter().filter_map(cfg));
+                    scanner.parents.push(variant_name);
+                    scanner.fields(&variant.fields);
+                    scanner.parents.pop();
+                    scanner.cfg.truncate(depth);
+                }
+            }
+            _ => syn::visit::visit_item(scanner, item),
+        });
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast syn::ImplItem) {
+        let (attrs, described): (&[Attribute], String) = match item {
+            syn::ImplItem::Const(i) => (&i.attrs, format!("const `{}`", i.ident)),
+            syn::ImplItem::Fn(i) => (&i.attrs, format!("fn `{}`", i.sig.ident)),
+            syn::ImplItem::Type(i) => (&i.attrs, format!("type alias `{}`", i.ident)),
+            syn::ImplItem::Macro(i) => (&i.attrs, "macro".into()),
+            _ => (&[], "item".into()),
+        };
+
+        self.item(attrs, described, range(item.span()), |scanner| {
+            syn::visit::visit_impl_item(scanner, item)
+        });
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast syn::TraitItem) {
+        let (attrs, described): (&[Attribute], String) = match item {
+            syn::TraitItem::Const(i) => (&i.attrs, format!("const `{}`", i.ident)),
+            syn::TraitItem::Fn(i) => (&i.attrs, format!("fn `{}`", i.sig.ident)),
+            syn::TraitItem::Type(i) => (&i.attrs, format!("type alias `{}`", i.ident)),
+            syn::TraitItem::Macro(i) => (&i.attrs, "macro".into()),
+            _ => (&[], "item".into()),
+        };
+
+        self.item(attrs, described, range(item.span()), |scanner| {
+            syn::visit::visit_trait_item(scanner, item)
+        });
+    }
+
+    fn visit_foreign_item(&mut self, item: &'ast syn::ForeignItem) {
+        let (attrs, described): (&[Attribute], String) = match item {
+            syn::ForeignItem::Fn(i) => (&i.attrs, format!("fn `{}`", i.sig.ident)),
+            syn::ForeignItem::Static(i) => (&i.attrs, format!("static `{}`", i.ident)),
+            syn::ForeignItem::Type(i) => (&i.attrs, format!("type `{}`", i.ident)),
+            _ => (&[], "item".into()),
+        };
+
+        self.item(attrs, described, range(item.span()), |_| ());
+    }
+}
+
+fn range(span: Span) -> Range {
+    (span.start(), span.end())
+}
+
+/// The range of a field or variant, including the comma after it.
+fn member_range<T: Spanned>(pair: &Pair<&T, &Token![,]>) -> Range {
+    let (start, end) = range(pair.value().span());
+
+    (start, pair.punct().map_or(end, |comma| comma.span.end()))
+}
+
+/// An annotation found in an attribute.
+struct Found {
+    args: TokenStream,
+    attr: Range,
+    shared: bool,
+    /// The predicate of the `cfg_attr` the annotation is in.
+    cfg_attr: Option<String>,
+}
+
+/// The predicate of a `#[cfg(...)]` attribute.
+fn cfg(attr: &Attribute) -> Option<String> {
+    match &attr.meta {
+        Meta::List(list) if list.path.is_ident("cfg") => Some(format!("cfg({})", list.tokens)),
+        _ => None,
+    }
+}
+
+/// The annotations in `attr`, which is either an annotation itself or a `cfg_attr` containing
+/// annotations.
+fn annotations(attr: &Attribute) -> Vec<Found> {
+    let range = (
+        attr.pound_token.span.start(),
+        attr.bracket_token.span.close().end(),
+    );
+
+    match &attr.meta {
+        Meta::List(list) if is_annotation(&list.path) => vec![Found {
+            args: list.tokens.clone(),
+            attr: range,
+            shared: false,
+            cfg_attr: None,
+        }],
+        Meta::List(list) if list.path.is_ident("cfg_attr") => {
+            let Ok(metas) = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            else {
+                return Vec::new();
+            };
+            let mut metas = metas.into_iter();
+            let Some(predicate) = metas.next() else {
+                return Vec::new();
+            };
+            let predicate = quote_meta(&predicate);
+            let shared = metas.len() > 1;
+
+            metas
+                .filter_map(|meta| match meta {
+                    Meta::List(inner) if is_annotation(&inner.path) => Some(Found {
+                        args: inner.tokens,
+                        attr: range,
+                        shared,
+                        cfg_attr: Some(format!("cfg({})", predicate)),
+                    }),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn is_annotation(path: &syn::Path) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|s| s.ident == "allow_until")
+}
+
+fn quote_meta(meta: &Meta) -> String {
+    use quote::ToTokens;
+
+    meta.to_token_stream().to_string()
+}
+
+fn unraw(name: String) -> String {
+    name.strip_prefix("r#").map(String::from).unwrap_or(name)
+}
+
+/// The arguments of an annotation that the commands use.
+struct Args {
+    requirement: VersionReq,
+    reason: Option<String>,
+    reason_key: Option<String>,
+    id: Option<String>,
+    tags: Vec<String>,
+    owner: Option<String>,
+    issue: Option<String>,
+    replacement: Option<String>,
+    generic_default: Option<String>,
+    version_literal: Range,
+    reason_literal: Option<Range>,
+    end: LineColumn,
+}
+
+impl Args {
+    fn parse(tokens: TokenStream) -> Result<Args, String> {
+        let pairs = syn::parse::Parser::parse2(
+            Punctuated::<MetaNameValue, Token![,]>::parse_terminated,
+            tokens,
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut version = None;
+        let mut migration = None;
+        let mut args = Args {
+            requirement: VersionReq::STAR,
+            reason: None,
+            reason_key: None,
+            id: None,
+            tags: Vec::new(),
+            owner: None,
+            issue: None,
+            replacement: None,
+            generic_default: None,
+            version_literal: (Span::call_site().start(), Span::call_site().end()),
+            reason_literal: None,
+            end: Span::call_site().end(),
+        };
+
+        for pair in pairs {
+            args.end = pair.value.span().end();
+
+            let Some(name) = pair.path.get_ident().map(|i| i.to_string()) else {
+                continue;
+            };
+
+            match &name[..] {
+                "version" => {
+                    let v = string(&pair.value).ok_or("`version` must be a string")?;
+                    version = Some((
+                        VersionReq::parse(&v)
+                            .map_err(|_| format!("invalid requirement `{}`", v))?,
+                        range(pair.value.span()),
+                    ));
+                }
+                "reason" => {
+                    args.reason = string(&pair.value);
+                    args.reason_literal = Some(range(pair.value.span()));
+                }
+                "tags" => {
+                    args.tags = match &pair.value {
+                        Expr::Array(array) => array.elems.iter().filter_map(string).collect(),
+                        value => string(value).into_iter().collect(),
+                    }
+                }
+                "reason_key" => args.reason_key = string(&pair.value),
+                "id" => args.id = string(&pair.value),
+                "owner" => args.owner = string(&pair.value),
+                "issue" => args.issue = string(&pair.value),
+                "replacement" => args.replacement = string(&pair.value),
+                "generic_default" => args.generic_default = string(&pair.value),
+                "migration" => migration = string(&pair.value),
+                _ => (),
+            }
+        }
+
+        (args.requirement, args.version_literal) = match (version, migration) {
+            (Some(version), _) => version,
+            (None, Some(migration)) => {
+                return Err(format!(
+                    "its requirement comes from migration `{}` in `Cargo.toml`",
+                    migration
+                ))
+            }
+            (None, None) => {
+                return Err(
+                    "missing `version`, unless `default_version` in `Cargo.toml` gives it".into(),
+                )
+            }
+        };
+        Ok(args)
+    }
+}
+
+fn string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
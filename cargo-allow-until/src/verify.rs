@@ -0,0 +1,234 @@
+//! `cargo allow-until verify`, which checks the hygiene of the annotations of a workspace without
+//! building it. The arguments are parsed and the configuration is read with `allow-until-core`,
+//! as the proc macro does, so the two agree on what is valid.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::Path,
+    process::ExitCode,
+    sync::Arc,
+};
+
+use allow_until_core::{
+    args::{self, Parsed},
+    config::{self, Config, Document, Location, Manifest, Problem},
+};
+use semver::Version;
+
+use crate::{scan, sources, Package};
+
+/// An annotation of a package, as far as the checks spanning annotations need it.
+struct Seen {
+    location: String,
+    item: String,
+}
+
+/// Checks the annotations of every package, printing each violation, and fails if there are any.
+pub fn verify(root: &Path, packages: &[Package]) -> ExitCode {
+    let mut violations = Vec::new();
+    let mut reported = BTreeSet::new();
+    let mut count = 0;
+
+    for (dir, version) in packages {
+        let config = config::load(dir, version.as_ref(), read);
+        let manifest = dir.strip_prefix(root).unwrap_or(dir).join("Cargo.toml");
+        // the problems of the workspace configuration are the same for every package
+        for problem in &config.problems {
+            let problem = match &problem.location {
+                Some(location) => {
+                    let path = location.path.strip_prefix(root).unwrap_or(&location.path);
+                    Problem {
+                        location: Some(Location {
+                            path: path.into(),
+                            ..location.clone()
+                        }),
+                        ..problem.clone()
+                    }
+                    .to_string()
+                }
+                None => format!("{}: {}", manifest.display(), problem),
+            };
+            if reported.insert(problem.clone()) {
+                violations.push(problem);
+            }
+        }
+
+        let mut files = Vec::new();
+        sources(dir, dir, &mut files);
+        files.sort();
+
+        let mut ids: BTreeMap<String, String> = BTreeMap::new();
+        let mut links = Vec::new();
+        for file in files {
+            let relative = file.strip_prefix(root).unwrap_or(&file);
+            let written = match std::fs::read_to_string(&file)
+                .map_err(|e| e.to_string())
+                .and_then(|source| scan::written(relative, &source).map_err(|e| e.to_string()))
+            {
+                Ok(written) => written,
+                Err(e) => {
+                    eprintln!("warning: skipping `{}`: {}", relative.display(), e);
+                    continue;
+                }
+            };
+
+            for annotation in written {
+                count += 1;
+                let seen = Seen {
+                    location: format!(
+                        "{}:{}:{}",
+                        relative.display(),
+                        annotation.line,
+                        annotation.column
+                    ),
+                    item: annotation.item,
+                };
+
+                let problems = match args::parse(annotation.arguments) {
+                    Ok(parsed) => {
+                        if let Some(id) = &parsed.id {
+                            if let Some(first) = ids.get(id) {
+                                violations.push(format!(
+                                    "{}: {}: the id `{}` is already used by another annotation, \
+                                     first used at {}",
+                                    seen.location, seen.item, id, first
+                                ));
+                            } else {
+                                ids.insert(id.clone(), seen.location.clone());
+                            }
+                        }
+                        if let Some(id) = &parsed.remove_with {
+                            links.push((id.clone(), seen.location.clone(), seen.item.clone()));
+                        }
+                        check(&parsed, &config, version.as_ref())
+                    }
+                    Err(e) => vec![e.message],
+                };
+                for problem in problems {
+                    violations.push(format!("{}: {}: {}", seen.location, seen.item, problem));
+                }
+            }
+        }
+
+        for (id, location, item) in links {
+            if !ids.contains_key(&id) {
+                violations.push(format!(
+                    "{}: {} is linked with `remove_with` to the id `{}`, which no annotation \
+                     declares",
+                    location, item, id
+                ));
+            }
+        }
+    }
+
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    if violations.is_empty() {
+        println!(
+            "verified {} annotation(s) in {} package(s)",
+            count,
+            packages.len()
+        );
+        ExitCode::SUCCESS
+    } else {
+        println!(
+            "{} violation(s) among {} annotation(s)",
+            violations.len(),
+            count
+        );
+        ExitCode::FAILURE
+    }
+}
+
+/// The problems of an annotation on its own: the arguments the configuration requires, the
+/// requirement and migration it refers to, its `since` and the quality of its reason.
+fn check(parsed: &Parsed, config: &Config, version: Option<&Version>) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let migration = match &parsed.migration {
+        Some((name, _)) => match config.migrations.get(name) {
+            Some(migration) => Some(migration),
+            None => {
+                problems.push(format!("unknown migration `{}`", name));
+                None
+            }
+        },
+        None => None,
+    };
+    if parsed.version.is_none() && parsed.migration.is_none() && config.defaults.version.is_none() {
+        problems.push("missing required `version` argument".into());
+    }
+
+    // as for the proc macro, the defaults and the migration count as given
+    let defaults = &config.defaults;
+    let missing: Vec<_> = config
+        .required
+        .iter()
+        .filter(|name| match &name[..] {
+            "reason" => {
+                parsed.reason.is_none()
+                    && parsed.reason_key.is_none()
+                    && migration.is_none_or(|m| m.reason.is_none())
+            }
+            "id" => parsed.id.is_none(),
+            "tags" => parsed.tags.as_ref().is_none_or(Vec::is_empty),
+            "owner" => {
+                parsed.owner.is_none()
+                    && migration.is_none_or(|m| m.owner.is_none())
+                    && defaults.owner.is_none()
+            }
+            "issue" => parsed.issue.is_none() && migration.is_none_or(|m| m.issue.is_none()),
+            "severity" => parsed.severity.is_none() && defaults.severity.is_none(),
+            "grace" => parsed.grace.is_none() && defaults.grace.is_none(),
+            "docsrs" => parsed.docsrs.is_none() && defaults.docsrs.is_none(),
+            "version_source" => {
+                parsed.version_source.is_none() && defaults.version_source.is_none()
+            }
+            _ => false,
+        })
+        .map(|name| format!("`{}`", name))
+        .collect();
+    if !missing.is_empty() {
+        problems.push(format!(
+            "annotation is missing the required {} {}",
+            if missing.len() == 1 {
+                "argument"
+            } else {
+                "arguments"
+            },
+            missing.join(", ")
+        ));
+    }
+
+    if let (Some((since, _)), Some(version)) = (&parsed.since, version) {
+        if since > version {
+            problems.push(format!(
+                "`since` is {}, after {}, the version of the package",
+                since, version
+            ));
+        }
+    }
+
+    if let Some(problem) = parsed
+        .reason
+        .as_deref()
+        .and_then(|reason| config.reason_problem(reason))
+    {
+        problems.push(problem);
+    }
+
+    problems
+}
+
+/// Reads a manifest as the proc macro does, without its cache.
+pub fn read(path: &Path) -> Manifest {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Document::parse(text)
+            .map(|document| Some(Arc::new(document)))
+            .map_err(|e| format!("failed to parse `{}`: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("failed to read `{}`: {}", path.display(), e)),
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+};
+
+use common::{run_with_env, stderr, stdout, workspace};
+
+fn lib(tracker: &str) -> String {
+    format!(
+        r#"
+use allow_until::allow_until;
+
+#[allow_until(version = ">=2.0", issue = "{0}/group/project/-/issues/1")]
+pub struct Xml;
+
+#[allow_until(version = ">=2.0", issue = "{0}/group/project/-/issues/2")]
+pub struct Yaml;
+
+#[allow_until(version = ">=3.0")]
+pub fn legacy() {{}}
+"#,
+        tracker
+    )
+}
+
+#[test]
+fn fail_on_closed() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tracker = format!("http://{}", listener.local_addr().unwrap());
+
+    // answers as the GitLab API does: the first issue is closed, the second open
+    let server = thread::spawn(move || {
+        let mut paths = Vec::new();
+        for _ in 1..=2 {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let response = if request.contains("/issues/1 ") {
+                r#"{"state":"closed","moved_to_id":null}"#
+            } else {
+                r#"{"state":"opened","moved_to_id":null}"#
+            };
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+            paths.push(request);
+        }
+        paths
+    });
+
+    let dir = workspace(&[("src/lib.rs", &lib(&tracker))]);
+    let output = run_with_env(&dir, &["audit", "--fail-on", "closed"], &[]);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "\
+src/lib.rs:4:1: struct `Xml`: `{}/group/project/-/issues/1` is closed
+audited the issues of 2 annotation(s): 1 open, 1 closed, 0 missing, 0 transferred, 0 skipped
+",
+            tracker
+        )
+    );
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /api/v4/projects/group%2Fproject/issues/1 HTTP/1.1"));
+}
+
+#[test]
+fn offline() {
+    let dir = workspace(&[("src/lib.rs", &lib("https://gitlab.example.com"))]);
+
+    let output = run_with_env(
+        &dir,
+        &["audit", "--fail-on", "closed,missing"],
+        &[("ALLOW_UNTIL_OFFLINE", "1")],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains(
+        "src/lib.rs:4:1: struct `Xml`: skipped `https://gitlab.example.com/group/project/-/issues/1`: \
+         offline, as `ALLOW_UNTIL_OFFLINE=1`\n"
+    ));
+    assert!(stdout(&output).ends_with(
+        "audited the issues of 2 annotation(s): 0 open, 0 closed, 0 missing, 0 transferred, 2 skipped\n"
+    ));
+
+    let output = run_with_env(&dir, &["audit", "--fail-on", "reopened"], &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("invalid state `reopened` for `--fail-on`"));
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use common::{commit, run, stderr, stdout, workspace};
+
+const LIB: &str = r#"use allow_until::{allow_until, AllowUntil};
+
+#[allow_until(version = ">=2.0", id = "CORE-1", tags = ["io"], reason = "use `Json`")]
+pub struct Xml;
+
+#[allow_until(
+    version = ">=2.0",
+    tags = ["io", "net"],
+)]
+pub fn fetch() {}
+
+#[derive(AllowUntil)]
+pub struct Options {
+    #[cfg_attr(feature = "extra", allow_until(version   =   "<2.0", id = "CORE-2"))]
+    pub legacy: bool,
+}
+
+#[allow_until(version = ">=3.0", tags = "io")]
+pub struct Later;
+"#;
+
+fn read(dir: &std::path::Path) -> String {
+    std::fs::read_to_string(dir.join("src/lib.rs")).unwrap()
+}
+
+#[test]
+fn round_trips() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(
+        &dir,
+        &[
+            "bump",
+            "--expiring-at",
+            "2.0.0",
+            "--to",
+            ">=2.1",
+            "--allow-dirty",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let bumped = read(&dir);
+    assert!(bumped.contains(r#"#[allow_until(version = ">=2.1", id = "CORE-1""#));
+    assert!(bumped.contains("    version = \">=2.1\",\n    tags = [\"io\", \"net\"],\n"));
+    assert!(bumped.contains(r#"allow_until(version = ">=3.0", tags = "io")"#));
+
+    let stdout = stdout(&output);
+    assert!(stdout.contains("src/lib.rs:3:1: bumped struct `Xml` from `>=2.0` to `>=2.1`"));
+    assert!(stdout.contains("src/lib.rs:6:1: bumped fn `fetch` from `>=2.0` to `>=2.1`"));
+    assert!(stdout.contains("2 edit(s) in 1 file(s)"));
+
+    let output = run(
+        &dir,
+        &[
+            "bump",
+            "--expiring-at",
+            "2.1.0",
+            "--tag",
+            "io",
+            "--to",
+            ">=2.0",
+            "--allow-dirty",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(read(&dir), LIB);
+}
+
+#[test]
+fn preserves_formatting() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(
+        &dir,
+        &["bump", "--id", "CORE-2", "--to", "<2.5", "--allow-dirty"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        read(&dir),
+        LIB.replace(r#"version   =   "<2.0""#, r#"version   =   "<2.5""#)
+    );
+
+    let output = run(
+        &dir,
+        &["bump", "--id", "CORE-2", "--to", "<2.0", "--allow-dirty"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(read(&dir), LIB);
+}
+
+#[test]
+fn require_reason() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(
+        &dir,
+        &[
+            "bump",
+            "--tag",
+            "net",
+            "--tag",
+            "io",
+            "--expiring-at",
+            "2.0.0",
+            "--to",
+            ">=2.1",
+            "--require-reason",
+            "milestone slipped",
+            "--allow-dirty",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let bumped = read(&dir);
+    assert!(bumped.contains(
+        r#"#[allow_until(version = ">=2.1", id = "CORE-1", tags = ["io"], reason = "use `Json`; milestone slipped")]"#
+    ));
+    assert!(bumped.contains(
+        "    version = \">=2.1\",\n    tags = [\"io\", \"net\"], reason = \"milestone slipped\",\n)]"
+    ));
+}
+
+#[test]
+fn require_reason_leaves_catalogued_reasons() {
+    let lib = r#"#[allow_until::allow_until(version = ">=2.0", reason_key = "xml-removal")]
+pub struct Xml;
+"#;
+    let dir = workspace(&[("src/lib.rs", lib)]);
+
+    let output = run(
+        &dir,
+        &[
+            "bump",
+            "--expiring-at",
+            "2.0.0",
+            "--to",
+            ">=2.1",
+            "--require-reason",
+            "milestone slipped",
+            "--allow-dirty",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(read(&dir), lib.replace(">=2.0", ">=2.1"));
+    assert!(stdout(&output).contains(
+        "bumped struct `Xml` from `>=2.0` to `>=2.1`, leaving its reason from `reason_key = \"xml-removal\"` as it is"
+    ));
+}
+
+#[test]
+fn dry_run_prints_a_diff() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(
+        &dir,
+        &["bump", "--id", "CORE-1", "--to", ">=2.1", "--dry-run"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains(
+        r#"+#[allow_until(version = ">=2.1", id = "CORE-1", tags = ["io"], reason = "use `Json`")]"#
+    ));
+    assert_eq!(read(&dir), LIB);
+}
+
+#[test]
+fn refuses_dirty_files() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    commit(&dir);
+    std::fs::write(dir.join("src/lib.rs"), format!("{}\n// wip\n", LIB)).unwrap();
+
+    let output = run(&dir, &["bump", "--id", "CORE-1", "--to", ">=2.1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("refusing to edit files with uncommitted changes: `src/lib.rs`")
+    );
+}
+
+#[test]
+fn requires_a_filter() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(&dir, &["bump", "--to", ">=2.1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output)
+        .contains("`bump` requires at least one of `--id`, `--tag` and `--expiring-at`"));
+    assert_eq!(read(&dir), LIB);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use std::process::Output;
+
+use common::{stderr, stdout, workspace};
+
+const LIB: &str = r#"
+use allow_until::{allow_until, AllowUntil};
+
+#[allow_until(version = ">=2.0", reason = "use `Json`", id = "drop-xml", owner = "platform")]
+pub struct Xml;
+
+#[derive(AllowUntil)]
+pub enum Shape {
+    #[allow_until(version = ">=1.5")]
+    Square,
+    Circle {
+        #[allow_until(version = ">=3.0")]
+        r#radius: f32,
+    },
+}
+
+#[cfg(feature = "legacy")]
+pub mod legacy {
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub fn init() {}
+}
+
+#[cfg_attr(windows, allow_until(version = ">=2.0"))]
+pub fn windows_only() {}
+
+mod inner;
+"#;
+
+const INNER: &str = r#"
+pub struct Config;
+
+impl Config {
+    #[allow_until::allow_until(version = "^1.2")]
+    pub fn reload(&self) {}
+}
+"#;
+
+fn run(args: &[&str]) -> Output {
+    let dir = workspace(&[("src/lib.rs", LIB), ("src/inner.rs", INNER)]);
+    common::run(&dir, args)
+}
+
+#[test]
+fn list() {
+    let output = run(&["list"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let expected = "\
+LOCATION          ITEM                           REQUIREMENT  REASON      ID        OWNER     CFG
+src/inner.rs:5:5  fn `reload`                    ^1.2
+src/lib.rs:4:1    struct `Xml`                   >=2.0        use `Json`  drop-xml  platform
+src/lib.rs:9:5    variant `Shape::Square`        >=1.5
+src/lib.rs:12:9   field `Shape::Circle::radius`  >=3.0
+src/lib.rs:19:5   fn `init`                      >=2.0                                        cfg(feature = \"legacy\")
+src/lib.rs:23:1   fn `windows_only`              >=2.0                                        cfg(windows)
+";
+    assert_eq!(stdout(&output), expected);
+}
+
+#[test]
+fn check_fires() {
+    let output = run(&["check", "--at-version", "2.0.0"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("src/lib.rs:4:1: use `Json` not allowed! (version 2.0.0 matches >=2.0)")
+    );
+    assert!(stdout.contains(
+        "src/lib.rs:9:5: variant `Shape::Square` not allowed! (version 2.0.0 matches >=1.5)"
+    ));
+    assert!(stdout.contains(
+        "src/lib.rs:19:5: fn `init` not allowed! (version 2.0.0 matches >=2.0) [cfg(feature = \"legacy\")]"
+    ));
+    assert!(stdout.contains("4 of the 6 annotation(s) fire at version 2.0.0"));
+}
+
+#[test]
+fn check_passes() {
+    let output = run(&["check", "--at-version", "1.1.0"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("none of the 6 annotation(s) fire at version 1.1.0"));
+}
+
+#[test]
+fn check_requires_a_version() {
+    let output = run(&["check"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("requires `--at-version <version>`"));
+}
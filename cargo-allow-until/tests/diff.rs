@@ -0,0 +1,145 @@
+mod common;
+
+use common::{stdout, workspace};
+use serde_json::Value;
+
+const OLD: &str = r#"
+use allow_until::allow_until;
+
+#[allow_until(version = ">=2.0", reason = "use `Json` instead")]
+pub struct Xml;
+
+#[allow_until(version = ">=2.0", id = "load", reason = "use `Index::open`")]
+pub fn load() {}
+
+#[allow_until(version = ">=1.8", reason = "superseded by `Config::from_env`")]
+pub fn read_config() {}
+
+#[allow_until(version = ">=2.0")]
+pub fn legacy() {}
+"#;
+
+const NEW: &str = r#"
+use allow_until::allow_until;
+
+#[allow_until(version = ">=2.0", reason = "use `Json` instead")]
+pub struct Xml;
+
+#[allow_until(version = ">=3.0", id = "load", reason = "use `Index::open`")]
+pub fn load_index() {}
+
+// renamed, with the same requirement and reason
+#[allow_until(version = ">=1.8", reason = "superseded by `Config::from_env`")]
+pub fn read_settings() {}
+
+#[allow_until(version = ">=2.1", reason = "use `Yaml` | `Toml`")]
+pub struct Ini;
+"#;
+
+#[test]
+fn text() {
+    let old = workspace(&[("src/lib.rs", OLD)]);
+    let new = workspace(&[("src/lib.rs", NEW)]);
+
+    let output = common::run(&new, &["diff", "--since-dir", old.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", common::stderr(&output));
+    let out = stdout(&output);
+    let lines: Vec<&str> = out.lines().map(str::trim_end).collect();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<_>>(),
+        ["CHANGE", "LOCATION", "ITEM", "REQUIREMENT", "REASON"]
+    );
+    assert!(lines[1].starts_with("added     src/lib.rs:14:1  struct `Ini`"));
+    assert!(lines[2].starts_with("removed   src/lib.rs:13:1  fn `legacy`"));
+    assert!(lines[3].contains("fn `load_index` (was fn `load`)"));
+    assert!(lines[3].contains(">=2.0 -> >=3.0"));
+    assert!(lines[4].contains("fn `read_settings` (was fn `read_config`)"));
+    assert_eq!(lines[5], format!("4 change(s) since `{}`", old.display()));
+}
+
+#[test]
+fn json() {
+    let old = workspace(&[("src/lib.rs", OLD)]);
+    let new = workspace(&[("src/lib.rs", NEW)]);
+
+    let output = common::run(
+        &new,
+        &[
+            "diff",
+            "--since-dir",
+            old.to_str().unwrap(),
+            "--format",
+            "json",
+        ],
+    );
+    assert!(output.status.success(), "{}", common::stderr(&output));
+    let changes: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let changes = changes.as_array().unwrap();
+    assert_eq!(changes.len(), 4);
+
+    assert_eq!(changes[0]["change"], "added");
+    assert_eq!(changes[0]["new"]["item"], "struct `Ini`");
+    assert_eq!(changes[0]["new"]["requirement"], ">=2.1");
+    assert_eq!(changes[1]["change"], "removed");
+    assert_eq!(changes[1]["old"]["item"], "fn `legacy`");
+    assert_eq!(changes[1]["old"]["reason"], Value::Null);
+    assert_eq!(changes[2]["change"], "modified");
+    assert_eq!(changes[2]["old"]["requirement"], ">=2.0");
+    assert_eq!(changes[2]["new"]["requirement"], ">=3.0");
+    assert_eq!(changes[2]["new"]["id"], "load");
+}
+
+#[test]
+fn markdown() {
+    let old = workspace(&[("src/lib.rs", OLD)]);
+    let new = workspace(&[("src/lib.rs", NEW)]);
+
+    let output = common::run(
+        &new,
+        &[
+            "diff",
+            "--since-dir",
+            old.to_str().unwrap(),
+            "--format",
+            "markdown",
+        ],
+    );
+    assert!(output.status.success(), "{}", common::stderr(&output));
+    let out = stdout(&output);
+    assert!(out.contains(
+        "## Added\n\n| Item | Requirement | Reason | Location |\n\
+         |------|-------------|--------|----------|\n\
+         | struct `Ini` | `>=2.1` | use `Yaml` \\| `Toml` | src/lib.rs:14:1 |\n"
+    ));
+    assert!(out.contains("## Removed\n"));
+    assert!(out.contains("| fn `load_index` (was fn `load`) | `>=2.0` → `>=3.0` |"));
+}
+
+#[test]
+fn unchanged() {
+    let old = workspace(&[("src/lib.rs", OLD)]);
+    let new = workspace(&[("src/lib.rs", OLD)]);
+
+    let output = common::run(&new, &["diff", "--since-dir", old.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!("no annotations changed since `{}`\n", old.display())
+    );
+}
+
+#[test]
+fn git_revision() {
+    let dir = workspace(&[("src/lib.rs", OLD)]);
+    common::commit(&dir);
+    std::fs::write(dir.join("src/lib.rs"), NEW).unwrap();
+
+    let output = common::run(&dir, &["diff", "--since", "HEAD", "--format", "json"]);
+    assert!(output.status.success(), "{}", common::stderr(&output));
+    let changes: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(changes.as_array().unwrap().len(), 4);
+
+    let output = common::run(&dir, &["diff", "--since", "v0.0.0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(common::stderr(&output).contains("`git ls-tree -r --name-only v0.0.0` failed"));
+}
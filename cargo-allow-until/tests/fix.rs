@@ -0,0 +1,174 @@
+mod common;
+
+use common::{commit, run, stderr, stdout, workspace};
+
+const LIB: &str = r#"use allow_until::{allow_until, AllowUntil};
+
+/// Reads the old XML config.
+#[allow_until(version = ">=1.0", reason = "use `Json`")]
+pub struct Xml;
+
+#[allow_until(version = ">=2.0")]
+pub struct Later;
+
+#[derive(AllowUntil)]
+pub struct Options {
+    pub name: String,
+    #[allow_until(version = ">=1.0")]
+    pub legacy: bool,
+    #[allow_until(version = ">=1.0")] pub last: bool
+}
+
+#[derive(AllowUntil)]
+pub enum Shape {
+    #[allow_until(version = ">=0.5")]
+    Square,
+    Circle,
+}
+
+pub struct Config;
+
+impl Config {
+    #[allow_until::allow_until(version = "^1.0")]
+    pub fn reload(&self) {
+        println!("reloading");
+    }
+}
+"#;
+
+const FIXED: &str = r#"use allow_until::{allow_until, AllowUntil};
+
+#[allow_until(version = ">=2.0")]
+pub struct Later;
+
+#[derive(AllowUntil)]
+pub struct Options {
+    pub name: String,
+}
+
+#[derive(AllowUntil)]
+pub enum Shape {
+    Circle,
+}
+
+pub struct Config;
+
+impl Config {
+}
+"#;
+
+const ATTR_ONLY: &str = r#"use allow_until::{allow_until, AllowUntil};
+
+/// Reads the old XML config.
+pub struct Xml;
+
+#[allow_until(version = ">=2.0")]
+pub struct Later;
+
+#[derive(AllowUntil)]
+pub struct Options {
+    pub name: String,
+    pub legacy: bool,
+    pub last: bool
+}
+
+#[derive(AllowUntil)]
+pub enum Shape {
+    Square,
+    Circle,
+}
+
+pub struct Config;
+
+impl Config {
+    pub fn reload(&self) {
+        println!("reloading");
+    }
+}
+"#;
+
+fn read(dir: &std::path::Path) -> String {
+    std::fs::read_to_string(dir.join("src/lib.rs")).unwrap()
+}
+
+#[test]
+fn removes_expired_items() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    commit(&dir);
+
+    let output = run(&dir, &["fix"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(read(&dir), FIXED);
+
+    let stdout = stdout(&output);
+    assert!(stdout.contains("src/lib.rs:4:1: removed struct `Xml`"));
+    assert!(stdout.contains("src/lib.rs:13:5: removed field `Options::legacy`"));
+    assert!(stdout.contains("src/lib.rs:28:5: removed fn `reload`"));
+    assert!(stdout.contains("5 edit(s) in 1 file(s)"));
+}
+
+#[test]
+fn attr_only() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    commit(&dir);
+
+    let output = run(&dir, &["fix", "--attr-only"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(read(&dir), ATTR_ONLY);
+    assert!(stdout(&output).contains("src/lib.rs:4:1: removed the annotation of struct `Xml`"));
+}
+
+#[test]
+fn at_version() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    commit(&dir);
+
+    let output = run(&dir, &["fix", "--at-version", "0.6.0"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("1 edit(s) in 1 file(s)"));
+    assert!(!read(&dir).contains("Square"));
+    assert!(read(&dir).contains("pub struct Xml;"));
+}
+
+#[test]
+fn dry_run_prints_a_diff() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = run(&dir, &["fix", "--dry-run", "--at-version", "0.6.0"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -17,8 +17,6 @@
+ 
+ #[derive(AllowUntil)]
+ pub enum Shape {
+-    #[allow_until(version = \">=0.5\")]
+-    Square,
+     Circle,
+ }
+ 
+"
+    );
+    assert_eq!(read(&dir), LIB);
+}
+
+#[test]
+fn refuses_dirty_files() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    commit(&dir);
+    std::fs::write(dir.join("src/lib.rs"), format!("{}\n// wip\n", LIB)).unwrap();
+
+    let output = run(&dir, &["fix"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("refusing to edit files with uncommitted changes: `src/lib.rs`")
+    );
+    assert!(read(&dir).contains("pub struct Xml;"));
+
+    let output = run(&dir, &["fix", "--allow-dirty"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!read(&dir).contains("pub struct Xml;"));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{stderr, stdout, workspace};
+
+const LIB: &str = r#"
+use allow_until::allow_until;
+
+#[allow_until(version = ">=1.0", reason = "the format is unmaintained", replacement = "Json", id = "xml")]
+pub struct Xml;
+
+#[allow_until(version = ">=1.0", reason_key = "yaml")]
+pub struct Yaml;
+
+#[allow_until(version = ">=1.1")]
+pub fn legacy() {}
+
+#[allow_until(version = ">=3.0", id = "later")]
+pub fn later() {}
+"#;
+
+const MANIFEST: &str = r#"
+[package]
+name = "fixture"
+version = "1.0.0"
+edition = "2021"
+
+[package.metadata.allow-until.reasons]
+yaml = "YAML support moved to a plugin"
+"#;
+
+#[test]
+fn rendered() {
+    let dir = workspace(&[("Cargo.toml", MANIFEST), ("src/lib.rs", LIB)]);
+    let out = dir.join("changelog.d");
+
+    let output = common::run(&dir, &["fragments", "--out", out.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains(&format!(
+        "src/lib.rs:4:1: struct `Xml`: {}",
+        out.join("xml.removal.md").display()
+    )));
+    assert!(stdout.contains(&format!(
+        "wrote 2 fragment(s) to `{}`, 0 already existed",
+        out.display()
+    )));
+
+    assert_eq!(
+        std::fs::read_to_string(out.join("xml.removal.md")).unwrap(),
+        "Struct `Xml` is removed in 1.0.0: the format is unmaintained. Use `Json` instead.\n"
+    );
+
+    // without an `id`, the fragment is named after the fingerprint of the annotation
+    let names: Vec<String> = std::fs::read_dir(&out)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "xml.removal.md")
+        .collect();
+    assert_eq!(names.len(), 1);
+    assert!(names[0].starts_with("au-") && names[0].ends_with(".removal.md"));
+    assert_eq!(
+        std::fs::read_to_string(out.join(&names[0])).unwrap(),
+        "Struct `Yaml` is removed in 1.0.0: YAML support moved to a plugin.\n"
+    );
+}
+
+#[test]
+fn idempotent() {
+    let dir = workspace(&[("Cargo.toml", MANIFEST), ("src/lib.rs", LIB)]);
+    let out = dir.join("changelog.d");
+    let out = out.to_str().unwrap();
+
+    let output = common::run(&dir, &["fragments", "--out", out]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // edited by hand after it was written
+    let xml = dir.join("changelog.d/xml.removal.md");
+    std::fs::write(&xml, "Removed `Xml`, use `Json`.\n").unwrap();
+
+    let output = common::run(&dir, &["fragments", "--out", out]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains(&format!(
+        "src/lib.rs:4:1: struct `Xml`: `{}` exists, skipping",
+        xml.display()
+    )));
+    assert!(stdout.contains(&format!(
+        "wrote 0 fragment(s) to `{}`, 2 already existed",
+        out
+    )));
+    assert_eq!(
+        std::fs::read_to_string(&xml).unwrap(),
+        "Removed `Xml`, use `Json`.\n"
+    );
+}
+
+#[test]
+fn warn_ahead_and_at_version() {
+    let manifest = format!(
+        "{}\n[package.metadata.allow-until]\nwarn_ahead = \"1 minor\"\n",
+        MANIFEST
+    );
+    let dir = workspace(&[("Cargo.toml", &manifest), ("src/lib.rs", LIB)]);
+    let out = dir.join("changelog.d");
+
+    let output = common::run(&dir, &["fragments", "--out", out.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("src/lib.rs:10:1: fn `legacy`"));
+    assert!(!stdout(&output).contains("fn `later`"));
+
+    let output = common::run(
+        &dir,
+        &[
+            "fragments",
+            "--out",
+            out.to_str().unwrap(),
+            "--at-version",
+            "3.0.0",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("wrote 1 fragment(s)"));
+    assert_eq!(
+        std::fs::read_to_string(out.join("later.removal.md")).unwrap(),
+        "Fn `later` is removed in 3.0.0.\n"
+    );
+}
+
+#[test]
+fn configured() {
+    let manifest = format!(
+        "{}\n[package.metadata.allow-until.fragments]\nname = \"{{version}}-{{id}}.md\"\n\
+         text = \"- Removed {{item}} ({{requirement}}). {{reason}}\"\n",
+        MANIFEST
+    );
+    let dir = workspace(&[("Cargo.toml", &manifest), ("src/lib.rs", LIB)]);
+    let out = dir.join("changelog.d");
+
+    let output = common::run(&dir, &["fragments", "--out", out.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        std::fs::read_to_string(out.join("1.0.0-xml.md")).unwrap(),
+        "- Removed struct `Xml` (>=1.0). the format is unmaintained\n"
+    );
+}
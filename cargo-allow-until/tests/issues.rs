@@ -0,0 +1,143 @@
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use common::{run_with_env, stderr, stdout, workspace};
+
+const LIB: &str = r#"
+use allow_until::allow_until;
+
+#[allow_until(version = ">=2.0", reason = "use `Json` instead", owner = "platform", id = "xml")]
+pub struct Xml;
+
+#[allow_until(version = ">=2.0", issue = "https://github.com/org/name/issues/3")]
+pub struct Yaml;
+
+#[allow_until(version = ">=3.0")]
+pub fn legacy() {}
+"#;
+
+#[test]
+fn drafts() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let drafts = dir.join("drafts");
+
+    let output = common::run(&dir, &["issues", "--out", drafts.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "\
+src/lib.rs:4:1: struct `Xml`: {0}/001-struct-xml.md
+src/lib.rs:10:1: fn `legacy`: {0}/002-fn-legacy.md
+wrote 2 draft(s) to `{0}`
+",
+            drafts.display()
+        )
+    );
+
+    assert_eq!(
+        std::fs::read_to_string(drafts.join("001-struct-xml.md")).unwrap(),
+        "\
+# Remove struct `Xml` (>=2.0)
+
+The `allow_until` annotation of struct `Xml` expires at `>=2.0`, when the item must be removed.
+
+- Requirement: `>=2.0`
+- Reason: use `Json` instead
+- Owner: platform
+- Id: `xml`
+- Location: `src/lib.rs:4:1`
+
+Link this issue from the annotation with `issue = \"<url>\"`.
+"
+    );
+}
+
+#[test]
+fn requires_an_output() {
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+
+    let output = common::run(&dir, &["issues"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("`issues` requires `--out <dir>` or `--create`"));
+
+    let output = run_with_env(&dir, &["issues", "--create"], &[("GITHUB_TOKEN", "secret")]);
+    assert!(stderr(&output).contains("`--create` requires `--repo <org/name>`"));
+}
+
+#[test]
+fn create() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api = format!("http://{}", listener.local_addr().unwrap());
+
+    // answers two requests as the GitHub API does, returning what they were
+    let server = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for number in 1..=2 {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = format!(
+                r#"{{"html_url":"https://github.com/org/name/issues/{}"}}"#,
+                number
+            );
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 201 Created\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+            requests.push((head, String::from_utf8(body).unwrap()));
+        }
+        requests
+    });
+
+    let dir = workspace(&[("src/lib.rs", LIB)]);
+    let output = run_with_env(
+        &dir,
+        &["issues", "--create", "--repo", "org/name"],
+        &[("GITHUB_API_URL", &api), ("GITHUB_TOKEN", "secret")],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+src/lib.rs:4:1: struct `Xml`: issue = \"https://github.com/org/name/issues/1\"
+src/lib.rs:10:1: fn `legacy`: issue = \"https://github.com/org/name/issues/2\"
+opened 2 issue(s)
+"
+    );
+
+    let requests = server.join().unwrap();
+    let (head, body) = &requests[0];
+    assert!(head.starts_with("POST /repos/org/name/issues HTTP/1.1"));
+    assert!(head.contains("Authorization: Bearer secret"));
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["title"], "Remove struct `Xml` (>=2.0)");
+    assert!(body["body"]
+        .as_str()
+        .unwrap()
+        .contains("- Reason: use `Json` instead\n"));
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::{stderr, stdout, workspace};
+
+const REPORT: &str = r#"{"schema_version":2,"requirement":">=2.0","version":"2.0.0","matched":true,"severity":"error","silent":false,"window":false,"reason":"use `Json`","id":"drop-xml","tags":["formats","io"],"owner":"platform","issue":"https://github.com/example/app/issues/12","migration":null,"package":"app-core","kind":"struct","item":"Xml","symbols":[],"file":"core/src/lib.rs","line":4,"column":1,"blame":null}
+{"schema_version":2,"requirement":">=2.0","version":"2.0.0","matched":true,"severity":"warning","silent":false,"window":true,"reason":null,"id":null,"tags":[],"owner":null,"issue":null,"migration":null,"package":"app-cli","kind":"fn","item":"legacy_flags","symbols":[],"file":"cli/src/main.rs","line":30,"column":1,"blame":null}
+{"schema_version":2,"requirement":">=3.0","version":"2.0.0","matched":false,"severity":"error","silent":false,"window":false,"reason":null,"id":null,"tags":[],"owner":"web <ops>","issue":"OPS-7","migration":null,"package":"app-core","kind":"field","item":"Options::legacy","symbols":[],"file":"core/src/lib.rs","line":9,"column":5,"blame":null}
+"#;
+
+#[test]
+fn html() {
+    let dir = workspace(&[("src/lib.rs", ""), ("allow-until.jsonl", REPORT)]);
+    let page = dir.join("overview.html");
+
+    let output = common::run(
+        &dir,
+        &[
+            "report",
+            "--from",
+            dir.join("allow-until.jsonl").to_str().unwrap(),
+            "--html",
+            page.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).starts_with("3 annotation(s) written to"));
+
+    let html = std::fs::read_to_string(page).unwrap();
+    assert!(html.contains(
+        "<tr><td title=\"core/src/lib.rs:4\">struct <code>Xml</code></td><td>app-core</td>\
+         <td><code>&gt;=2.0</code></td><td class=\"expired\">expired</td><td>platform</td>\
+         <td>formats, io</td><td><a href=\"https://github.com/example/app/issues/12\">\
+         https://github.com/example/app/issues/12</a></td></tr>"
+    ));
+    assert!(html.contains("<td class=\"window\">warning window</td>"));
+    assert!(html.contains("<td>web &lt;ops&gt;</td><td></td><td>OPS-7</td>"));
+
+    // the milestones are sorted by version, and nothing is loaded from elsewhere
+    let two = html
+        .find("<code>&gt;=2.0</code>: 2 annotation(s), 1 expired, 1 in warning window")
+        .unwrap();
+    let three = html
+        .find("<code>&gt;=3.0</code>: 1 annotation(s), 0 expired, 0 in warning window")
+        .unwrap();
+    assert!(two < three);
+    assert!(!html.contains(" src=") && !html.contains("<link"));
+}
+
+#[test]
+fn missing_report() {
+    let dir = workspace(&[("src/lib.rs", "")]);
+    let output = common::run(
+        &dir,
+        &[
+            "report",
+            "--from",
+            dir.join("missing.jsonl").to_str().unwrap(),
+            "--html",
+            dir.join("overview.html").to_str().unwrap(),
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("error: failed to read"));
+
+    let output = common::run(&dir, &["report"]);
+    assert!(stderr(&output).contains("`report` requires `--html <path>`"));
+}
+
+/// A workspace of the packages `app-core` and `app-cli`, with the reports the macro wrote for them
+/// in `target/allow-until`, and one of `app-xml`, a package removed since.
+fn reports() -> std::path::PathBuf {
+    let lines: Vec<&str> = REPORT.lines().collect();
+    let core = format!("{}\n{}\n", lines[0], lines[2]);
+    // written before the package was recorded
+    let cli = lines[1].replace(r#""package":"app-cli","#, "");
+
+    workspace(&[
+        ("Cargo.toml", "[workspace]\nmembers = [\"core\", \"cli\"]\n"),
+        (
+            "core/Cargo.toml",
+            "[package]\nname = \"app-core\"\nversion = \"2.0.0\"\nedition = \"2021\"\n",
+        ),
+        ("core/src/lib.rs", ""),
+        (
+            "cli/Cargo.toml",
+            "[package]\nname = \"app-cli\"\nversion = \"2.0.0\"\nedition = \"2021\"\n",
+        ),
+        ("cli/src/main.rs", "fn main() {}\n"),
+        ("target/allow-until/app-core.json", &core),
+        ("target/allow-until/app-core.json.lock", ""),
+        (
+            "target/allow-until/app-cli.json",
+            &format!("{}\n{}\n", cli, cli),
+        ),
+        ("target/allow-until/app-xml.json", lines[0]),
+    ])
+}
+
+#[test]
+fn merge() {
+    let dir = reports();
+    let merged = dir.join("allow-until.jsonl");
+
+    let output = common::run(
+        &dir,
+        &["report", "--merge", "--out", merged.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).starts_with("3 annotation(s) of 2 package(s) merged into"));
+    assert!(stderr(&output).contains("app-xml.json`, `app-xml` is not a package of the workspace"));
+
+    let report =
+        allow_until_report::Report::parse(&std::fs::read_to_string(&merged).unwrap()).unwrap();
+    let packages: Vec<_> = report
+        .annotations
+        .iter()
+        .map(|a| (a.package.as_deref().unwrap(), a.item.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        packages,
+        [
+            ("app-cli", "legacy_flags"),
+            ("app-core", "Xml"),
+            ("app-core", "Options::legacy"),
+        ]
+    );
+    assert!(dir.join("target/allow-until/app-xml.json").exists());
+}
+
+#[test]
+fn merge_and_prune() {
+    let dir = reports();
+    let page = dir.join("overview.html");
+
+    let output = common::run(
+        &dir,
+        &[
+            "report",
+            "--merge",
+            "--prune",
+            "--out",
+            dir.join("allow-until.jsonl").to_str().unwrap(),
+            "--html",
+            page.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("app-xml.json`, `app-xml` is not a package of the workspace"));
+    assert!(stdout(&output).contains("3 annotation(s) written to"));
+    assert!(!dir.join("target/allow-until/app-xml.json").exists());
+    assert!(std::fs::read_to_string(page)
+        .unwrap()
+        .contains("<td>app-cli</td>"));
+}
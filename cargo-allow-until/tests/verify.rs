@@ -0,0 +1,102 @@
+mod common;
+
+use common::{stdout, workspace};
+
+const MANIFEST: &str = r#"
+[package]
+name = "fixture"
+version = "1.2.0"
+edition = "2021"
+
+[package.metadata.allow-until]
+required = ["owner"]
+reason_deny_patterns = ["todo"]
+"#;
+
+const LIB: &str = r#"
+use allow_until::{allow_until, AllowUntil};
+
+#[allow_until(version = ">=2.0", owner = "platform", id = "xml", reason = "use `Json` instead")]
+pub struct Xml;
+
+#[allow_until(version = ">=2.0", owner = "platform", id = "xml", remove_with = "yaml")]
+pub struct XmlReader;
+
+#[allow_until(version = ">=2.0", reason = "TODO")]
+pub fn legacy() {}
+
+#[derive(AllowUntil)]
+pub struct Options {
+    #[allow_until(version = "two", owner = "platform")]
+    pub timeout: u32,
+    #[allow_until(version = ">=3.0", owner = "platform", since = "1.4")]
+    pub retries: u32,
+}
+"#;
+
+#[test]
+fn violations() {
+    let dir = workspace(&[("Cargo.toml", MANIFEST), ("src/lib.rs", LIB)]);
+
+    let output = common::run(&dir, &["verify"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "\
+src/lib.rs:7:1: struct `XmlReader`: the id `xml` is already used by another annotation, first used at src/lib.rs:4:1
+src/lib.rs:10:1: fn `legacy`: annotation is missing the required argument `owner`
+src/lib.rs:10:1: fn `legacy`: the reason contains `todo`, which `reason_deny_patterns` denies
+src/lib.rs:15:5: field `Options::timeout`: invalid semver requirement `two`: unexpected character 't' while parsing major version number
+src/lib.rs:17:5: field `Options::retries`: `since` is 1.4.0, after 1.2.0, the version of the package
+src/lib.rs:7:1: struct `XmlReader` is linked with `remove_with` to the id `yaml`, which no annotation declares
+6 violation(s) among 5 annotation(s)
+"
+    );
+}
+
+#[test]
+fn workspace_configuration() {
+    let dir = workspace(&[
+        (
+            "src/lib.rs",
+            r#"
+            #[allow_until::allow_until(version = ">=2.0", reason = "the XML backend is unmaintained")]
+            pub struct Xml;
+            "#,
+        ),
+        (
+            ".allow-until.toml",
+            "required = [\"issue\"]\ncolour = \"red\"\n",
+        ),
+    ]);
+
+    let output = common::run(&dir, &["verify"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains(".allow-until.toml:2:1: unknown key `colour` in `"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains(
+        "src/lib.rs:2:13: struct `Xml`: annotation is missing the required argument `issue`"
+    ));
+}
+
+#[test]
+fn clean() {
+    let dir = workspace(&[(
+        "src/lib.rs",
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", since = "1.0")]
+        pub struct Xml;
+        "#,
+    )]);
+
+    let output = common::run(&dir, &["verify"]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "verified 1 annotation(s) in 1 package(s)\n"
+    );
+}
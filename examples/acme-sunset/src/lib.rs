@@ -0,0 +1,34 @@
+//! `#[acme_sunset(...)]`, which is `#[allow_until(...)]` with the defaults of the ACME platform
+//! team: expiry is a warning, owned by `@acme/platform` and tagged `acme`, unless a use says
+//! otherwise, and each use must give a `reason`.
+//!
+//! ```ignore
+//! #[acme_sunset(version = ">=2.0", reason = "use `Ledger`", tags = ["billing"])]
+//! pub struct LegacyLedger;
+//! ```
+//!
+//! The crates using it depend on `allow-until` too, which evaluates the annotations.
+
+use allow_until_core::wrapper::Defaults;
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+pub fn acme_sunset(args: TokenStream, item: TokenStream) -> TokenStream {
+    let defaults = Defaults::new("acme_sunset")
+        .args(
+            r#"severity = "warning", owner = "@acme/platform", tags = ["acme"]"#
+                .parse()
+                .unwrap(),
+        )
+        // invalid defaults are a bug of the wrapper, reported on each use
+        .map(|defaults| defaults.require(&["reason"]));
+
+    match defaults {
+        Ok(defaults) => defaults.expand(args.into(), item.into()).into(),
+        Err(e) => {
+            let mut output = TokenStream::from(e.to_compile_error());
+            output.extend(item);
+            output
+        }
+    }
+}
@@ -0,0 +1,606 @@
+//! The arguments of an annotation as every macro evaluates them. `#[allow_until]`, the fields and
+//! variants of `#[derive(AllowUntil)]`, `allow_until_group!` and the specialised attributes such as
+//! `#[allow_members_until]` parse their arguments with `allow-until-core`, then [`resolve`] them
+//! against the crate's configuration into a [`ParsedAnnotation`], and the annotations of items
+//! going away are checked against its policy with [`check_policy`], so no form can miss a
+//! validation another has. `tests/conformance.rs` holds them to that.
+
+use allow_until_core::{
+    args,
+    regex::Regex,
+    severity::{DoctestFence, Enforce, OnExpiry},
+    suggest,
+};
+use proc_macro::Span;
+use semver::VersionReq;
+
+use crate::{
+    code::Code,
+    config::{self, Docsrs, PublicOnly, Severity},
+    diagnostic::{self, Diagnostic},
+    doc_cfg, feature, issue,
+    item::Item,
+    lint::{self, Lint},
+    reason, registry,
+    tool::{self, Tool},
+    tracked,
+    version::{self, Distance, Unit, VersionSource},
+    warning_level, watch,
+};
+
+/// The arguments of an annotation, with the crate's defaults and configuration applied.
+#[derive(Clone)]
+pub(crate) struct ParsedAnnotation {
+    pub version: VersionReq,
+    /// The span of the `version` literal.
+    pub version_span: Span,
+    pub reason: Option<String>,
+    pub id: Option<String>,
+    pub tags: Vec<String>,
+    pub owner: Option<String>,
+    pub issue: Option<String>,
+    pub severity: Option<Severity>,
+    pub grace: Option<Distance>,
+    pub docsrs: Option<Docsrs>,
+    pub version_source: Option<VersionSource>,
+    pub changelog: Option<String>,
+    pub public_only: Option<PublicOnly>,
+    pub silent: bool,
+    /// The name of the migration the annotation belongs to.
+    pub migration: Option<String>,
+    pub test_guard: bool,
+    /// Whether the annotation itself asks for `severity = "warning"`, rather than the defaults.
+    pub warning_requested: bool,
+    pub approved_by: Option<(String, Span)>,
+    /// Whether the requirement is the crate's `default_version`, as the annotation has neither
+    /// `version` nor `migration`.
+    pub default_version: bool,
+    /// The version the item was deprecated in, with the span of its literal.
+    pub deprecate_from: Option<(semver::Version, Span)>,
+    pub min_notice: Option<Distance>,
+    /// The feature the functionality moved behind.
+    pub superseded_by_feature: Option<String>,
+    /// The id of the annotation this one must be removed together with.
+    pub remove_with: Option<String>,
+    /// The oldest version the annotation belongs to, below which it isn't evaluated.
+    pub not_before: Option<semver::Version>,
+    /// The file whose change expires the annotation, from `watch` and `watch_hash`.
+    pub watch: Option<watch::Watch>,
+    /// The file and key the current version is read from, with the span of the table.
+    pub version_from: Option<(version::VersionFrom, Span)>,
+    /// What happens instead of the error once expired, with the span of its literal.
+    pub on_expiry: Option<(OnExpiry, Span)>,
+    /// The precision the version and the requirement are compared at, the finest when `None`.
+    pub match_precision: Option<Unit>,
+    /// The git branch the annotation is enforced on.
+    pub branch: Option<String>,
+    /// The pattern of the git branches the annotation is enforced on.
+    pub branch_pattern: Option<Regex>,
+    /// Whether the item must be `#[deprecated]`.
+    pub require_deprecated: bool,
+    /// The version the annotation was added in, with the span of its literal.
+    pub since: Option<(semver::Version, Span)>,
+    /// What replaces the item.
+    pub replacement: Option<String>,
+    /// Whether the requirement is evaluated as written despite `extend_all_by`.
+    pub no_extend: bool,
+    /// Whether the requirement may first match further ahead than `max_horizon`.
+    pub horizon_ok: bool,
+    /// The environment variable whose being set raises the severity of the annotation.
+    pub escalate_env: Option<String>,
+    /// The generic parameter whose default is going away, with the span of its literal.
+    pub generic_default: Option<(String, Span)>,
+    /// The requirement from which the examples of the item's documentation are no longer tested.
+    pub doctest_ignore_from: Option<VersionReq>,
+    /// How the examples are no longer tested once `doctest_ignore_from` matches.
+    pub doctest_fence: DoctestFence,
+    /// What the item is called instead of its kind and name.
+    pub display_name: Option<String>,
+    /// The requirement the version of the Cargo building the crate must satisfy, with the span of
+    /// its literal.
+    pub cargo: Option<(VersionReq, Span)>,
+    /// The requirement on the version of an external tool.
+    pub tool: Option<Tool>,
+    /// Whether `version` is left out for `cargo` or `tool`, which are then the only conditions, the
+    /// annotation having no `migration` either, so that its requirement matches every version.
+    pub version_omitted: bool,
+    /// The predicate of the `doc(cfg)` the item is given, from `feature` and `cfg`, when `doc_cfg`
+    /// applies.
+    pub doc_cfg: Option<proc_macro2::TokenStream>,
+    /// Which builds fail on expiry. The crate's `enforce` doesn't apply to annotations with a
+    /// `severity` of their own.
+    pub enforce: Option<Enforce>,
+    /// The [fingerprint](crate::fingerprint::of) of the annotation, set once its item is known and
+    /// before its requirement is extended or truncated.
+    pub fingerprint: Option<String>,
+}
+
+/// The diagnostic of an error in the arguments.
+pub(crate) fn error(e: args::Error) -> Diagnostic {
+    let mut diag = diagnostic::error(e.span.unwrap(), e.code, e.message);
+    if let Some((span, note)) = e.note {
+        diag = diag.span_note(span.unwrap(), note);
+    }
+    if let Some(help) = e.help {
+        diag = diag.help(help);
+    }
+    diag
+}
+
+/// Parses the arguments of the annotation at `at`, applying the crate's defaults.
+pub(crate) fn parse(
+    tokens: proc_macro2::TokenStream,
+    at: Span,
+) -> Result<ParsedAnnotation, Diagnostic> {
+    resolve(args::parse(tokens).map_err(error)?, at)
+}
+
+/// Applies the crate's defaults and configuration to the arguments of the annotation at `at`.
+pub(crate) fn resolve(parsed: args::Parsed, at: Span) -> Result<ParsedAnnotation, Diagnostic> {
+    // before the arguments, which may be invalid because of them, e.g. without `default_version`
+    for problem in config::take_problems() {
+        let mut diagnostic = Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::InvalidConfig,
+            problem.message.as_str(),
+        );
+        if let Some(location) = &problem.location {
+            diagnostic = diagnostic.note(format!("at {}", location));
+        }
+        if let Some(help) = &problem.help {
+            diagnostic = diagnostic.help(help);
+        }
+        diagnostic.emit();
+    }
+
+    let config = config::get();
+    let migration = match &parsed.migration {
+        Some((name, span)) => Some((name, migration(name, span.unwrap())?, span.unwrap())),
+        None => None,
+    };
+
+    let tool = tool::resolve(parsed.tool, parsed.tool_version)?;
+    // the first of the other conditions stands in for the requirement
+    let condition = parsed
+        .cargo
+        .as_ref()
+        .map(|(_, span)| span.unwrap())
+        .or(tool.as_ref().map(|tool| tool.span));
+
+    // the migration's requirement was written in `Cargo.toml`, the lints are about literals
+    let version_omitted = parsed.version.is_none() && migration.is_none() && condition.is_some();
+    let default_version = parsed.version.is_none() && migration.is_none() && !version_omitted;
+    let (version, version_span, text) = match (parsed.version, &migration, condition) {
+        (Some((version, span, text)), _, _) => (version, span.unwrap(), Some(text)),
+        (None, Some((_, migration, span)), _) => (migration.version.clone(), *span, None),
+        (None, None, Some(span)) => (VersionReq::STAR, span, None),
+        (None, None, None) if config.defaults.version.is_some() => {
+            (config.defaults.version.clone().unwrap(), at, None)
+        }
+        (None, None, None) => {
+            return Err(diagnostic::error(
+                at,
+                Code::MissingVersion,
+                "missing required `version` argument",
+            )
+            .help(
+                "annotations need at least a requirement, e.g.\n\
+             #[allow_until(version = \">=1.0\", reason = \"...\")]\n\
+             the optional arguments are described in the Arguments section of the README",
+            ))
+        }
+    };
+
+    if let Some(text) = &text {
+        diagnostic::silence(parsed.silent, || {
+            if version::matches_every(&version) {
+                unbounded(&version, version_span);
+            }
+            if !config
+                .allow_lint
+                .iter()
+                .chain(&parsed.allow_lint)
+                .any(|&lint| lint == Lint::ComparatorStyle)
+            {
+                lint::comparator_style(&version, text, version_span);
+            }
+        });
+    }
+
+    if let (Some((_, span)), None) = (&parsed.approved_by, &config.approvers) {
+        diagnostic::silence(parsed.silent, || {
+            Diagnostic::spanned(
+                span.unwrap(),
+                warning_level(at),
+                Code::InvalidConfig,
+                "`approved_by` is given, but no `approvers` are configured",
+            )
+            .help(
+                "list who can sign off with `approvers = [...]` in \
+                 `[package.metadata.allow-until]`, or remove `approved_by`",
+            )
+            .emit()
+        });
+    }
+
+    if let (Some(reason), Some(span)) = (&parsed.reason, parsed.reason_span) {
+        diagnostic::silence(parsed.silent, || reason::check(reason, span.unwrap(), at));
+    }
+
+    let defaults = &config.defaults;
+    let reason = match parsed.reason_key {
+        Some((key, span)) => Some(catalogued_reason(&key, span.unwrap())?.clone()),
+        None => parsed
+            .reason
+            .or_else(|| migration.as_ref().and_then(|(_, m, _)| m.reason.clone())),
+    };
+
+    let issue = parsed
+        .issue
+        .map(|(issue, span)| (issue, span.unwrap()))
+        .or_else(|| {
+            migration
+                .as_ref()
+                .and_then(|(_, m, span)| Some((m.issue.clone()?, *span)))
+        });
+    let issue = match issue {
+        Some((issue, span)) => {
+            issue::check(&issue, &config.issue_patterns).map_err(|e| {
+                let shorthands = match &config.issue_patterns[..] {
+                    [] => "define shorthands with `issue_patterns` in \
+                           `[package.metadata.allow-until]`"
+                        .into(),
+                    patterns => format!(
+                        "use a shorthand matching {}",
+                        patterns
+                            .iter()
+                            .map(|p| format!("`{}`", p.text))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                diagnostic::error(span, Code::InvalidInput, e).help(format!(
+                    "link the issue tracking the removal, e.g. \
+                     `\"https://github.com/org/repo/issues/123\"`, or {}",
+                    shorthands
+                ))
+            })?;
+            #[cfg(feature = "online-checks")]
+            diagnostic::silence(parsed.silent, || issue::verify(&issue, span));
+            Some(issue)
+        }
+        None => None,
+    };
+
+    let superseded_by_feature = match parsed.superseded_by_feature {
+        Some((name, span)) => {
+            feature::check(&name, span.unwrap())?;
+            Some(name)
+        }
+        None => None,
+    };
+
+    let doc_cfg = doc_cfg::resolve(parsed.feature, parsed.cfg, parsed.doc_cfg, defaults.doc_cfg)?;
+
+    let not_before = match parsed.not_before {
+        Some((bound, span)) => {
+            if let Some(first) = version::minimal_version(&version).filter(|first| bound > *first) {
+                return Err(diagnostic::error(
+                    span.unwrap(),
+                    Code::InvalidInput,
+                    format!(
+                        "`not_before` is {}, after {}, the first version `{}` matches",
+                        bound, first, version
+                    ),
+                )
+                .help(format!(
+                    "`not_before` is the oldest version the annotation belongs to, at most {}",
+                    first
+                )));
+            }
+            Some(bound)
+        }
+        None => None,
+    };
+
+    let doctest_ignore_from = match (parsed.doctest_ignore_from, parsed.doctest_fence) {
+        (None, Some((_, span))) => {
+            return Err(diagnostic::error(
+                span.unwrap(),
+                Code::InvalidInput,
+                "`doctest_fence` without `doctest_ignore_from`",
+            )
+            .help("add `doctest_ignore_from`, such as `doctest_ignore_from = \">=0.9\"`"));
+        }
+        (Some((req, span)), _) => {
+            let from = version::minimal_version(&req);
+            if let (Some(from), Some(first)) = (from, version::minimal_version(&version)) {
+                if from >= first {
+                    return Err(diagnostic::error(
+                        span.unwrap(),
+                        Code::InvalidInput,
+                        format!(
+                            "`doctest_ignore_from` first matches {}, no earlier than {}, the \
+                             first version `{}` matches",
+                            from, first, version
+                        ),
+                    )
+                    .help(
+                        "the usual error takes over once the requirement matches, so the \
+                         examples are ignored from an earlier version",
+                    ));
+                }
+            }
+            Some(req)
+        }
+        (None, None) => None,
+    };
+
+    let watch = watch::resolve(parsed.watch, parsed.watch_hash)?;
+
+    Ok(ParsedAnnotation {
+        reason,
+        version,
+        version_span,
+        id: parsed.id,
+        tags: parsed.tags.unwrap_or_default(),
+        owner: parsed
+            .owner
+            .or_else(|| migration.as_ref().and_then(|(_, m, _)| m.owner.clone()))
+            .or_else(|| defaults.owner.clone()),
+        issue,
+        severity: parsed.severity.or(defaults.severity),
+        grace: parsed.grace.or(defaults.grace),
+        docsrs: parsed.docsrs.or(defaults.docsrs),
+        version_source: parsed.version_source.or(defaults.version_source),
+        changelog: parsed.changelog,
+        public_only: parsed.public_only,
+        silent: parsed.silent,
+        migration: migration.map(|(name, _, _)| name.clone()),
+        test_guard: parsed.test_guard,
+        warning_requested: parsed.severity == Some(Severity::Warning),
+        approved_by: parsed.approved_by.map(|(name, span)| (name, span.unwrap())),
+        default_version,
+        deprecate_from: parsed
+            .deprecate_from
+            .map(|(version, span)| (version, span.unwrap())),
+        min_notice: parsed.min_notice.or(defaults.min_notice),
+        superseded_by_feature,
+        remove_with: parsed.remove_with,
+        not_before,
+        watch,
+        version_from: parsed
+            .version_from
+            .map(|(from, span)| (from, span.unwrap())),
+        on_expiry: parsed
+            .on_expiry
+            .map(|(action, span)| (action, span.unwrap())),
+        match_precision: parsed.match_precision.or(defaults.match_precision),
+        branch: parsed.branch,
+        branch_pattern: parsed.branch_pattern,
+        require_deprecated: parsed
+            .require_deprecated
+            .or(defaults.require_deprecated)
+            .unwrap_or(false),
+        since: parsed.since.map(|(version, span)| (version, span.unwrap())),
+        replacement: parsed.replacement,
+        no_extend: parsed.no_extend,
+        horizon_ok: parsed.horizon_ok,
+        escalate_env: parsed.escalate_env,
+        generic_default: parsed
+            .generic_default
+            .map(|(name, span)| (name, span.unwrap())),
+        doctest_ignore_from,
+        doctest_fence: parsed
+            .doctest_fence
+            .map_or(DoctestFence::default(), |(fence, _)| fence),
+        display_name: parsed.display_name,
+        cargo: parsed.cargo.map(|(req, span)| (req, span.unwrap())),
+        tool,
+        version_omitted,
+        doc_cfg,
+        enforce: parsed
+            .enforce
+            .or(defaults.enforce.filter(|_| parsed.severity.is_none())),
+        fingerprint: None,
+    })
+}
+
+/// The migration `name` in `[package.metadata.allow-until.migrations]`, or an error at `span`
+/// listing the migrations that are defined.
+fn migration(name: &str, span: Span) -> Result<&'static config::Migration, Diagnostic> {
+    let migrations = &config::get().migrations;
+    if let Some(migration) = migrations.get(name) {
+        return Ok(migration);
+    }
+
+    let names: Vec<_> = migrations.keys().map(String::as_str).collect();
+    let help = if names.is_empty() {
+        "no migrations are defined, add them to `[package.metadata.allow-until.migrations]` in \
+         `Cargo.toml`"
+            .into()
+    } else {
+        let available = format!(
+            "defined migrations are {}",
+            names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        match suggest::closest(name, &names) {
+            Some(closest) => format!("did you mean `{}`? {}", closest, available),
+            None => available,
+        }
+    };
+
+    Err(diagnostic::error(
+        span,
+        Code::InvalidInput,
+        format!("unknown migration `{}`", name),
+    )
+    .help(help))
+}
+
+/// The reason `key` refers to in `[package.metadata.allow-until.reasons]`, or an error at `span`
+/// listing the keys that are defined.
+fn catalogued_reason(key: &str, span: Span) -> Result<&'static String, Diagnostic> {
+    let reasons = &config::get().reasons;
+    if let Some(reason) = reasons.get(key) {
+        return Ok(reason);
+    }
+
+    let keys: Vec<_> = reasons.keys().map(String::as_str).collect();
+    let help = if keys.is_empty() {
+        "no reasons are defined, add them to `[package.metadata.allow-until.reasons]` in `Cargo.toml`"
+            .into()
+    } else {
+        let available = format!(
+            "available keys are {}",
+            keys.iter()
+                .map(|key| format!("`{}`", key))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        match suggest::closest(key, &keys) {
+            Some(closest) => format!("did you mean `{}`? {}", closest, available),
+            None => available,
+        }
+    };
+
+    Err(diagnostic::error(
+        span,
+        Code::InvalidInput,
+        format!("unknown reason key `{}`", key),
+    )
+    .help(help))
+}
+
+/// Warns about a requirement that every version satisfies, suggesting the next major version of
+/// the crate as a bound.
+fn unbounded(req: &VersionReq, span: Span) {
+    let next = tracked::var("CARGO_PKG_VERSION")
+        .ok()
+        .and_then(|v| semver::Version::parse(&v).ok())
+        .map_or(1, |v| v.major + 1);
+
+    diagnostic::warning(
+        span,
+        Code::Unbounded,
+        format!("the requirement `{}` matches every version", req),
+    )
+    .note("the item is flagged on every build")
+    .help(format!(
+        "use the version from which the item is no longer allowed, e.g. `\">={}.0\"`",
+        next
+    ))
+    .emit();
+}
+
+impl ParsedAnnotation {
+    /// Whether the argument called `name` was given, or filled in from the crate's defaults.
+    fn has(&self, name: &str) -> bool {
+        match name {
+            "reason" => self.reason.is_some(),
+            "id" => self.id.is_some(),
+            "tags" => !self.tags.is_empty(),
+            "owner" => self.owner.is_some(),
+            "issue" => self.issue.is_some(),
+            "severity" => self.severity.is_some(),
+            "grace" => self.grace.is_some(),
+            "docsrs" => self.docsrs.is_some(),
+            "version_source" => self.version_source.is_some(),
+            _ => true,
+        }
+    }
+}
+
+/// Checks the annotation at `at` on `item` against the crate's policy: the arguments it requires,
+/// `require_deprecated`, and ids used by more than one annotation.
+pub(crate) fn check_policy(args: &ParsedAnnotation, at: Span, item: Option<&Item>) {
+    check_required(args, at);
+    check_deprecated(args, at, item);
+    check_duplicate_id(args, at);
+}
+
+/// Errors when the annotation lacks arguments that `required` in the crate's configuration lists,
+/// whether or not it has expired.
+fn check_required(args: &ParsedAnnotation, at: Span) {
+    let missing: Vec<_> = config::get()
+        .required
+        .iter()
+        .filter(|name| !args.has(name))
+        .map(|name| format!("`{}`", name))
+        .collect();
+
+    if !missing.is_empty() {
+        diagnostic::error(
+            at,
+            Code::MissingRequired,
+            format!(
+                "annotation is missing the required {} {}",
+                if missing.len() == 1 {
+                    "argument"
+                } else {
+                    "arguments"
+                },
+                missing.join(", ")
+            ),
+        )
+        .note("`required` in `[package.metadata.allow-until]` asks every annotation to set them")
+        .emit();
+    }
+}
+
+/// Errors when `require_deprecated` is set and the item isn't `#[deprecated]`, unless the
+/// annotation gives the version it was deprecated in with `deprecate_from`.
+fn check_deprecated(args: &ParsedAnnotation, at: Span, item: Option<&Item>) {
+    let Some(item) = item else {
+        return;
+    };
+    if !args.require_deprecated || item.deprecated || args.deprecate_from.is_some() {
+        return;
+    }
+
+    diagnostic::error(
+        at,
+        Code::NotDeprecated,
+        format!(
+            "{} must be `#[deprecated]` before it can get a removal deadline",
+            item.describe()
+        ),
+    )
+    .note("`require_deprecated` asks annotated items to warn their users first")
+    .help("add `#[deprecated]` to the item, or `deprecate_from = \"...\"` to the annotation")
+    .emit();
+}
+
+/// Warns when another annotation of the crate already declared the same id. The same annotation
+/// expanded more than once is recognised by its source location.
+fn check_duplicate_id(args: &ParsedAnnotation, at: Span) {
+    let Some(id) = &args.id else {
+        return;
+    };
+
+    let location = (at.file(), at.line(), at.column());
+    let first = registry::with(|r| {
+        let first = r
+            .declared
+            .entry(id.clone())
+            .or_insert_with(|| location.clone());
+        (*first != location).then(|| first.clone())
+    });
+
+    if let Some((file, line, column)) = first {
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::DuplicateId,
+            format!("the id `{}` is already used by another annotation", id),
+        )
+        .note(format!("first used at {}:{}:{}", file, line, column))
+        .help("ids must be unique to tell annotations apart, give this one a new id")
+        .emit();
+    }
+}
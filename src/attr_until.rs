@@ -0,0 +1,100 @@
+//! `#[attr_until(version = "...", attr = ...)]`, which attaches any attribute to its item while
+//! the requirement doesn't match and drops it from the version on, or with `when = "matched"`
+//! attaches it from the version on. The attribute's tokens are emitted as they were written, so
+//! errors about it point at the annotation, and whether it fits the item is the compiler's to say.
+
+use allow_until_core::severity::When;
+use proc_macro::{Delimiter, Group, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::{annotation, args, code::Code, current_version, diagnostic, extend, trace, truncate};
+
+/// Evaluates the `attr_until` attribute at `at` against the current version, returning the
+/// attribute to put in front of the item, if any.
+pub(crate) fn evaluate(tokens: TokenStream, at: Span) -> Option<TokenStream> {
+    let mut parsed = match args::parse_attr_until(tokens.into()) {
+        Ok(parsed) if parsed.version.is_none() => {
+            missing(at, Code::MissingVersion, "version");
+            return None;
+        }
+        Ok(parsed) if parsed.attr.is_none() => {
+            missing(at, Code::InvalidInput, "attr");
+            return None;
+        }
+        Ok(parsed) => parsed,
+        Err(e) => {
+            annotation::error(e).emit();
+            return None;
+        }
+    };
+    let (attr, span) = parsed.attr.take()?;
+    let when = parsed.when.take().unwrap_or(When::Unmatched);
+    let mut args = match annotation::resolve(parsed, at) {
+        Ok(args) => args,
+        Err(e) => {
+            e.emit();
+            return None;
+        }
+    };
+
+    let mut trace = trace::Trace::new(at);
+    // without a version nothing is enforced, so the requirement is taken as not matching yet
+    let matched = match current_version(&args, at, &mut trace) {
+        Some(current) => {
+            let extension = extend(&mut args, at);
+            let (version, truncation) = truncate(&mut args, &current.version);
+            trace.step(|| {
+                format!(
+                    "version {} from {}",
+                    current.version,
+                    current.describe_source()
+                )
+            });
+            for step in extension.iter().chain(&truncation) {
+                trace.step(|| step.clone());
+            }
+            args.version.matches(&version)
+        }
+        None => false,
+    };
+    trace.step(|| {
+        format!(
+            "requirement `{}` {}",
+            args.version,
+            if matched { "matches" } else { "does not match" }
+        )
+    });
+
+    let attach = matched == (when == When::Matched);
+    trace.decide(if attach {
+        "the attribute is attached"
+    } else {
+        "the attribute is dropped"
+    });
+    attach.then(|| outer_attribute(attr.into(), span.unwrap()))
+}
+
+fn missing(at: Span, code: Code, arg: &str) {
+    diagnostic::error(at, code, format!("missing required `{}` argument", arg))
+        .help(format!(
+            "the attribute needs the requirement and the attribute to attach, e.g.\n\
+             #[attr_until(version = \">=2.0\", attr = deprecated(note = \"...\"))]\n\
+             optional arguments are {}",
+            args::ATTR_UNTIL_ARGS[2..]
+                .iter()
+                .map(|arg| format!("`{}`", arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .emit();
+}
+
+/// `#[tokens]`, with the `#` and the brackets at `span`.
+fn outer_attribute(tokens: TokenStream, span: Span) -> TokenStream {
+    let mut hash = Punct::new('#', Spacing::Alone);
+    hash.set_span(span);
+    let mut brackets = Group::new(Delimiter::Bracket, tokens);
+    brackets.set_span(span);
+    [TokenTree::Punct(hash), TokenTree::Group(brackets)]
+        .into_iter()
+        .collect()
+}
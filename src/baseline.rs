@@ -0,0 +1,137 @@
+//! Freezing the set of allowed annotations: `ALLOW_UNTIL_BASELINE` names a file listing the
+//! annotations that existed when the freeze started, and any annotation missing from it is an
+//! error. With `ALLOW_UNTIL_BASELINE_RECORD=1` the file is written instead, by every crate of the
+//! build taking turns as described in [`lock`].
+
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use proc_macro::Span;
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, env, lock, tracked};
+
+pub(crate) const BASELINE_VAR: &str = "ALLOW_UNTIL_BASELINE";
+pub(crate) const RECORD_VAR: &str = "ALLOW_UNTIL_BASELINE_RECORD";
+
+/// Checks the annotation against the baseline, or records it in record mode.
+///
+/// Annotations are keyed by their `id`, or else by their [fingerprint](crate::fingerprint::of).
+pub(crate) fn check(args: &ParsedAnnotation, at: Span) {
+    let Some(path) = tracked::var_os(BASELINE_VAR).map(tracked::user_path) else {
+        return;
+    };
+    let Some(key) = args.id.clone().or_else(|| args.fingerprint.clone()) else {
+        return;
+    };
+
+    if tracked::var(RECORD_VAR).is_ok_and(|v| v.trim() == "1") {
+        return record(&path, key, at);
+    }
+
+    static BASELINE: OnceLock<Result<BTreeSet<String>, String>> = OnceLock::new();
+
+    // only the baseline being checked against is an input, not the one being recorded
+    tracked::path(&path);
+    match BASELINE.get_or_init(|| read(&path)) {
+        Ok(keys) if keys.contains(&key) => (),
+        Ok(_) => diagnostic::error(
+            at,
+            Code::NotInBaseline,
+            format!(
+                "annotation `{}` is not in the baseline `{}`, a freeze on new allow_until \
+                 annotations is in effect",
+                key,
+                path.display()
+            ),
+        )
+        .help(format!(
+            "if this annotation is approved, re-record the baseline with `{}=1`",
+            RECORD_VAR
+        ))
+        .emit(),
+        Err(e) => {
+            if env::once("baseline") {
+                diagnostic::error(at, Code::FileAccess, e.as_str()).emit();
+            }
+        }
+    }
+}
+
+fn read(path: &PathBuf) -> Result<BTreeSet<String>, String> {
+    let text = std::fs::read(path)
+        .map(|data| tracked::text(&data))
+        .map_err(|e| {
+            format!(
+                "failed to read the baseline `{}`: {}",
+                tracked::shown(path),
+                e
+            )
+        })?;
+
+    serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|v| {
+            v.get("annotations")?
+                .as_array()?
+                .iter()
+                .map(|k| k.as_str().map(String::from))
+                .collect()
+        })
+        .ok_or_else(|| {
+            format!(
+                "the baseline `{}` is malformed, expected `{{\"annotations\": [...]}}`",
+                path.display()
+            )
+        })
+}
+
+fn record(path: &PathBuf, key: String, at: Span) {
+    static RECORDED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+    let mut recorded = RECORDED.lock().unwrap_or_else(|e| e.into_inner());
+    if !recorded.insert(key) {
+        return;
+    }
+
+    // keys recorded by other crates sharing the same baseline, possibly in parallel, are kept
+    let result = lock::locked(path, || {
+        let (mut keys, existing) = match read(path) {
+            Ok(keys) => (keys, Ok(())),
+            Err(_) if !path.exists() => (BTreeSet::new(), Ok(())),
+            Err(e) => (BTreeSet::new(), Err(e)),
+        };
+        keys.extend(recorded.iter().cloned());
+
+        let json = serde_json::json!({ "annotations": keys });
+        lock::replace(path, format!("{:#}\n", json))?;
+        Ok(existing)
+    });
+
+    match result {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => {
+            if env::once("baseline-corrupted") {
+                diagnostic::warning(at, Code::FileAccess, format!("{}, it has been replaced", e))
+                    .help("re-record the baseline from a clean state to restore the other keys")
+                    .emit();
+            }
+        }
+        Err(e) => {
+            if env::once("baseline") {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "failed to write the baseline `{}`: {}",
+                        tracked::shown(path),
+                        e
+                    ),
+                )
+                .emit();
+            }
+        }
+    }
+}
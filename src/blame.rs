@@ -0,0 +1,167 @@
+//! Who added each annotation, from `git blame`, for the report when `ALLOW_UNTIL_BLAME=1` is set.
+//!
+//! This only ever enriches the report: when git is unavailable, the file isn't tracked or the
+//! line isn't committed yet, the entry's `blame` is `null` and nothing is reported.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, OnceLock},
+};
+
+use allow_until_report as report;
+
+use crate::tracked;
+
+pub(crate) const BLAME_VAR: &str = "ALLOW_UNTIL_BLAME";
+
+/// Whether blame was requested with `ALLOW_UNTIL_BLAME=1`.
+pub(crate) fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+
+    *ENABLED.get_or_init(|| tracked::var(BLAME_VAR).is_ok_and(|v| v.trim() == "1"))
+}
+
+/// The commit that last changed a line.
+#[derive(Clone)]
+struct Blame {
+    author: String,
+    email: String,
+    /// The commit time, in seconds since the Unix epoch.
+    time: i64,
+}
+
+/// The author and commit date of the line `line` (1-based) of `file`, as the `blame` field of a
+/// report entry, or `None` when it isn't known.
+///
+/// Each file is blamed as a whole the first time one of its lines is asked for, so a file with
+/// many annotations only runs git once.
+pub(crate) fn line(file: &str, line: usize) -> Option<report::Blame> {
+    static FILES: Mutex<Option<HashMap<PathBuf, Vec<Option<Blame>>>>> = Mutex::new(None);
+
+    let mut files = FILES.lock().unwrap_or_else(|e| e.into_inner());
+    let lines = files
+        .get_or_insert_with(HashMap::new)
+        .entry(file.into())
+        .or_insert_with_key(|path| blame(path).unwrap_or_default());
+
+    let blame = lines.get(line.wrapping_sub(1)).cloned().flatten()?;
+    Some(report::Blame {
+        author: blame.author,
+        email: blame.email,
+        date: date(blame.time),
+    })
+}
+
+/// Every line of `path`, `None` for lines that aren't committed yet.
+fn blame(path: &Path) -> Option<Vec<Option<Blame>>> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let output = Command::new("git")
+        .current_dir(dir.unwrap_or(Path::new(".")))
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path.file_name()?)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses the output of `git blame --line-porcelain`, in which each line of the file is a header
+/// naming its commit, the commit's details, and the line itself prefixed with a tab.
+fn parse(porcelain: &str) -> Vec<Option<Blame>> {
+    let mut lines = Vec::new();
+    let (mut commit, mut author, mut email, mut time) = ("", "", "", 0);
+
+    for line in porcelain.lines() {
+        if line.starts_with('\t') {
+            // the all-zero commit stands for changes that aren't committed
+            let committed = commit.bytes().any(|b| b != b'0');
+            lines.push(committed.then(|| Blame {
+                author: author.into(),
+                email: email.trim_matches(['<', '>']).into(),
+                time,
+            }));
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = value;
+        } else if let Some(value) = line.strip_prefix("author-mail ") {
+            email = value;
+        } else if let Some(value) = line.strip_prefix("committer-time ") {
+            time = value.parse().unwrap_or(0);
+        } else if let Some((hash, _)) = line.split_once(' ') {
+            if hash.len() >= 40 && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                commit = hash;
+            }
+        }
+    }
+
+    lines
+}
+
+/// The UTC date and time of a Unix timestamp, e.g. `2024-03-01T12:00:00Z`.
+fn date(time: i64) -> String {
+    let (days, secs) = (time.div_euclid(86_400), time.rem_euclid(86_400));
+
+    // the proleptic Gregorian calendar from day 0 = 1970-01-01, after Howard Hinnant's
+    // `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_porcelain() {
+        let committed = "3f786850e387550fdab836ed7e6dc881de23001b 1 1 1\n\
+                         author Ada Lovelace\n\
+                         author-mail <ada@example.com>\n\
+                         author-time 1700000000\n\
+                         committer-time 1709294400\n\
+                         filename lib.rs\n\
+                         \t#[allow_until(version = \">=2.0\")]\n";
+        let uncommitted = "0000000000000000000000000000000000000000 2 2 1\n\
+                           author Not Committed Yet\n\
+                           author-mail <not.committed.yet>\n\
+                           committer-time 1709294400\n\
+                           filename lib.rs\n\
+                           \tpub struct Legacy;\n";
+
+        let lines = parse(&format!("{}{}", committed, uncommitted));
+        assert_eq!(lines.len(), 2);
+        let blame = lines[0].as_ref().unwrap();
+        assert_eq!(blame.author, "Ada Lovelace");
+        assert_eq!(blame.email, "ada@example.com");
+        assert_eq!(blame.time, 1_709_294_400);
+        assert!(lines[1].is_none());
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(date(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(date(1_709_294_400), "2024-03-01T12:00:00Z");
+        assert_eq!(date(-1), "1969-12-31T23:59:59Z");
+    }
+}
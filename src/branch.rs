@@ -0,0 +1,93 @@
+//! `branch` and `branch_pattern`, which only enforce an annotation on some git branches, for
+//! release lines maintained from the same sources.
+
+use std::{path::Path, process::Command, sync::OnceLock};
+
+use proc_macro::Span;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, diagnostic, env, offline, trace::Trace, tracked,
+};
+
+pub(crate) const BRANCH_VAR: &str = "ALLOW_UNTIL_BRANCH";
+
+/// The branch being built, from [`BRANCH_VAR`] or else from git, or why it is unknown. Git is
+/// only run by the first annotation asking.
+fn current(at: Span) -> &'static Result<String, String> {
+    static BRANCH: OnceLock<Result<String, String>> = OnceLock::new();
+
+    BRANCH.get_or_init(|| {
+        if let Ok(branch) = tracked::var(BRANCH_VAR) {
+            return Ok(branch.trim().into());
+        }
+        offline::policy(at).check(offline::Source::GitBranch)?;
+        let dir = tracked::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| ".".into());
+        from_git(dir.as_ref())
+    })
+}
+
+/// The branch checked out in the repository `dir` is in.
+fn from_git(dir: &Path) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run `git rev-parse`: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`git rev-parse --abbrev-ref HEAD` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "HEAD" => Err("`HEAD` is detached".into()),
+        branch => Ok(branch.into()),
+    }
+}
+
+/// Whether the annotation at `at` is enforced on the current branch. It always is without
+/// `branch` or `branch_pattern`, and when the branch is unknown, which is warned about once.
+pub(crate) fn applies(args: &ParsedAnnotation, at: Span, trace: &mut Trace) -> bool {
+    let wanted = match (&args.branch, &args.branch_pattern) {
+        (Some(branch), _) => format!("`branch = \"{}\"`", branch),
+        (None, Some(pattern)) => format!("`branch_pattern = \"{}\"`", pattern.text),
+        (None, None) => return true,
+    };
+
+    let branch = match current(at) {
+        Ok(branch) => branch,
+        Err(e) => {
+            trace.step(|| format!("the branch is unknown, {} is treated as matching", wanted));
+            if env::once("branch") {
+                diagnostic::warning(
+                    at,
+                    Code::UnknownBranch,
+                    format!(
+                        "the current git branch is unknown, annotations are enforced as if it \
+                         matched their branch: {}",
+                        e
+                    ),
+                )
+                .help(format!("set `{}` to the branch being built", BRANCH_VAR))
+                .emit();
+            }
+            return true;
+        }
+    };
+
+    let matched = match (&args.branch, &args.branch_pattern) {
+        (Some(wanted), _) => wanted == branch,
+        (None, Some(pattern)) => pattern.is_match(branch),
+        (None, None) => true,
+    };
+    trace.step(|| {
+        format!(
+            "branch `{}` {} {}",
+            branch,
+            if matched { "matches" } else { "does not match" },
+            wanted
+        )
+    });
+    matched
+}
@@ -0,0 +1,141 @@
+//! `cargo`, which conditions an annotation on the version of the Cargo building the crate, for
+//! workarounds of the behavior of older Cargo versions.
+
+use std::{ffi::OsStr, path::Path, process::Command, sync::OnceLock};
+
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, env, trace::Trace, tracked};
+
+pub(crate) const CARGO_VERSION_VAR: &str = "ALLOW_UNTIL_CARGO_VERSION";
+
+/// The version of the Cargo building the crate, from [`CARGO_VERSION_VAR`] or else from running
+/// the binary in `CARGO`, or why it is unknown. Cargo is only run by the first annotation asking.
+fn current() -> &'static Result<Version, String> {
+    static CARGO: OnceLock<Result<Version, String>> = OnceLock::new();
+
+    CARGO.get_or_init(|| {
+        if let Ok(version) = tracked::var(CARGO_VERSION_VAR) {
+            return crate::version::parse_lenient(&version)
+                .map_err(|e| format!("`{}` is invalid: {}", CARGO_VERSION_VAR, e));
+        }
+        let cargo = tracked::var_os("CARGO").ok_or("`CARGO` is not set")?;
+        from_binary(&cargo)
+    })
+}
+
+/// The version of the Cargo binary `cargo`, from its `--version`.
+fn from_binary(cargo: &OsStr) -> Result<Version, String> {
+    let shown = Path::new(cargo).display();
+    let output = Command::new(cargo)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run `{} --version`: {}", shown, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`{} --version` failed: {}",
+            shown,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse(&stdout).ok_or_else(|| {
+        format!(
+            "`{} --version` printed no version: `{}`",
+            shown,
+            stdout.trim()
+        )
+    })
+}
+
+/// The version in the output of `cargo --version`, e.g. 1.70.0 for
+/// `cargo 1.70.0 (ec8a8a0ca 2023-04-25)`. Pre-releases such as `1.72.0-nightly` are taken as their
+/// release, so that a nightly satisfies the requirements its release does.
+fn parse(output: &str) -> Option<Version> {
+    let version = output
+        .trim()
+        .strip_prefix("cargo ")?
+        .split_whitespace()
+        .next()?;
+    let version = Version::parse(version).ok()?;
+    Some(Version::new(version.major, version.minor, version.patch))
+}
+
+/// The version of the Cargo building the crate, once an annotation with `cargo` has asked for it.
+pub(crate) fn version() -> Option<&'static Version> {
+    current().as_ref().ok()
+}
+
+/// Whether the `cargo` requirement of the annotation at `at` is satisfied. It always is without
+/// `cargo`, and never when the version of Cargo is unknown, which is warned about once.
+pub(crate) fn satisfied(args: &ParsedAnnotation, at: Span, trace: &mut Trace) -> bool {
+    let Some((req, _)) = &args.cargo else {
+        return true;
+    };
+
+    let cargo = match current() {
+        Ok(cargo) => cargo,
+        Err(e) => {
+            trace.step(|| {
+                format!(
+                    "the Cargo version is unknown, `cargo = \"{}\"` is not satisfied",
+                    req
+                )
+            });
+            if env::once("cargo") {
+                diagnostic::warning(
+                    at,
+                    Code::UnknownCargo,
+                    format!(
+                        "the version of the Cargo building the crate is unknown, annotations \
+                         conditioned on it don't fire: {}",
+                        e
+                    ),
+                )
+                .help(format!(
+                    "set `{}` to the version of Cargo, such as `1.70.0`",
+                    CARGO_VERSION_VAR
+                ))
+                .emit();
+            }
+            return false;
+        }
+    };
+
+    let matched = req.matches(cargo);
+    trace.step(|| {
+        format!(
+            "Cargo {} {} `cargo = \"{}\"`",
+            cargo,
+            if matched {
+                "satisfies"
+            } else {
+                "does not satisfy"
+            },
+            req
+        )
+    });
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_output() {
+        assert_eq!(
+            parse("cargo 1.70.0 (ec8a8a0ca 2023-04-25)\n"),
+            Some(Version::new(1, 70, 0))
+        );
+        assert_eq!(
+            parse("cargo 1.72.0-nightly (0c14026aa 2023-06-14)"),
+            Some(Version::new(1, 72, 0))
+        );
+        assert_eq!(parse("cargo 1.70.0"), Some(Version::new(1, 70, 0)));
+        assert_eq!(parse("rustc 1.70.0 (90c541806 2023-05-31)"), None);
+        assert_eq!(parse("cargo"), None);
+    }
+}
@@ -0,0 +1,101 @@
+//! The `changelog` check, warning about expired annotations whose removal the Unreleased section
+//! of the package's changelog doesn't mention.
+
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use proc_macro::Span;
+
+pub(crate) use allow_until_core::changelog::*;
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, item::Item, tracked};
+
+/// A changelog as read by the first annotation that checks it.
+struct Entry {
+    /// The Unreleased section, `None` when the changelog has none.
+    unreleased: Result<Option<String>, String>,
+    /// Whether the failure to read it was reported already.
+    reported: bool,
+}
+
+/// Warns when the changelog of an expired annotation with `changelog` set mentions neither the
+/// name of the item nor the annotation's id in its Unreleased section.
+///
+/// Each changelog is read once per compilation, and a changelog that can't be read is only
+/// reported by the first annotation checking it.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    static CHANGELOGS: Mutex<Option<HashMap<PathBuf, Entry>>> = Mutex::new(None);
+
+    let Some(file) = &args.changelog else {
+        return;
+    };
+    let path = tracked::package_path(file);
+
+    let mut changelogs = CHANGELOGS.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = changelogs
+        .get_or_insert_with(HashMap::new)
+        .entry(path.clone())
+        .or_insert_with_key(|path| Entry {
+            unreleased: tracked::read_text(path)
+                .map(|text| unreleased(&text).map(String::from))
+                .map_err(|e| e.to_string()),
+            reported: false,
+        });
+
+    let unreleased = match &entry.unreleased {
+        Ok(unreleased) => unreleased.as_deref(),
+        Err(e) => {
+            if !std::mem::replace(&mut entry.reported, true) {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "`changelog` is set, but `{}` could not be read from `{}`: {}",
+                        file,
+                        tracked::shown(&path),
+                        e
+                    ),
+                )
+                .emit();
+            }
+            return;
+        }
+    };
+
+    let name = item.and_then(|item| item.name.as_deref());
+    // fields and variants are also found by their own name, e.g. `legacy` for `Options::legacy`
+    let names = name
+        .into_iter()
+        .chain(name.and_then(|name| Some(name.rsplit_once("::")?.1)))
+        .chain(args.id.as_deref());
+    if unreleased.is_some_and(|section| names.clone().any(|name| mentions(section, name))) {
+        return;
+    }
+
+    let described = item.map_or("item".into(), Item::describe);
+    let wanted: Vec<_> = names.map(|name| format!("`{}`", name)).collect();
+
+    let mut diag = diagnostic::Diagnostic::spanned(
+        item.and_then(|item| item.span).unwrap_or(at),
+        crate::warning_level(at),
+        Code::NotInChangelog,
+        match unreleased {
+            Some(_) => format!(
+                "the removal of the expired {} is not in the Unreleased section of `{}`",
+                described, file
+            ),
+            None => format!(
+                "the removal of the expired {} is not in `{}`, which has no Unreleased section",
+                described, file
+            ),
+        },
+    );
+    diag = if wanted.is_empty() {
+        diag.help("give the annotation an `id` the changelog entry can mention")
+    } else {
+        diag.help(format!(
+            "add an entry mentioning {} under the Unreleased heading",
+            wanted.join(" or ")
+        ))
+    };
+    diag.emit();
+}
@@ -0,0 +1,129 @@
+//! `#[changes_at(version = "...", note = "...")]`, for items whose behavior changes at a version
+//! rather than being removed. It never fails the build: once the requirement matches, it warns
+//! until the new behavior is implemented and the attribute removed.
+
+use allow_until_report::AnnotationKind;
+use proc_macro::{Span, TokenStream};
+
+use crate::{
+    annotation, args, code::Code, config, current_version, diagnostic, extend, fingerprint,
+    item::Item, registry, report, trace, truncate,
+};
+
+/// Evaluates the `changes_at` attribute at `at` on `item` against the current version, noting it
+/// before the change with `always_note`, and warning once per item from the change on.
+pub(crate) fn evaluate(tokens: TokenStream, item: &Item, at: Span) {
+    let parsed = match args::parse_changes_at(tokens.into()) {
+        Ok(parsed) if parsed.version.is_none() => {
+            diagnostic::error(
+                at,
+                Code::MissingVersion,
+                "missing required `version` argument",
+            )
+            .help(format!(
+                "the attribute needs the requirement the change happens at, e.g.\n\
+                 #[changes_at(version = \">=2.0\", note = \"...\")]\n\
+                 optional arguments are {}",
+                args::CHANGES_AT_ARGS[1..]
+                    .iter()
+                    .map(|arg| format!("`{}`", arg))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .emit();
+            return;
+        }
+        Ok(parsed) => parsed,
+        Err(e) => {
+            annotation::error(e).emit();
+            return;
+        }
+    };
+    let note = parsed.note.clone();
+    let mut args = match annotation::resolve(parsed, at) {
+        // the note stands in for the reason in reports
+        Ok(args) => crate::annotation::ParsedAnnotation {
+            reason: note.clone(),
+            ..args
+        },
+        Err(e) => {
+            e.emit();
+            return;
+        }
+    };
+
+    args.fingerprint = Some(fingerprint::of(&args, Some(item)));
+
+    let mut trace = trace::Trace::new(at);
+    let Some(current) = current_version(&args, at, &mut trace) else {
+        return;
+    };
+    let extension = extend(&mut args, at);
+    let (version, truncation) = truncate(&mut args, &current.version);
+    let version = &version;
+    let matched = args.version.matches(version);
+    trace.step(|| {
+        format!(
+            "version {} from {}",
+            current.version,
+            current.describe_source()
+        )
+    });
+    if let Some(extension) = &extension {
+        trace.step(|| extension.clone());
+    }
+    if let Some(truncation) = &truncation {
+        trace.step(|| truncation.clone());
+    }
+    trace.step(|| {
+        format!(
+            "requirement `{}` {}",
+            args.version,
+            if matched { "matches" } else { "does not match" }
+        )
+    });
+
+    report::record(
+        &args,
+        AnnotationKind::ChangesAt,
+        Some(item),
+        at,
+        version,
+        matched,
+        false,
+    );
+
+    let what = match &note {
+        Some(note) => format!(
+            "{} changes behavior at `{}`: {}",
+            item.describe(),
+            args.version,
+            note
+        ),
+        None => format!("{} changes behavior at `{}`", item.describe(), args.version),
+    };
+    if !matched {
+        if config::get().always_note {
+            trace.decide("noted, as `always_note` is set");
+            diagnostic::note(at, Code::BehaviorChange, what).emit_visible();
+        }
+        return;
+    }
+
+    // an item expanded more than once, e.g. by another attribute, is only reported once
+    let location = (at.file(), at.line(), at.column());
+    if !registry::with(|r| r.changed.insert(location)) {
+        return;
+    }
+    trace.decide("warning");
+    let mut diag = diagnostic::warning(at, Code::BehaviorChange, what)
+        .note(format!("version {} matches {}", version, args.version));
+    if let Some(extension) = extension {
+        diag = diag.note(extension);
+    }
+    if let Some(truncation) = truncation {
+        diag = diag.note(truncation);
+    }
+    diag.help("implement and document the new behavior, then remove `#[changes_at]`")
+        .emit();
+}
@@ -0,0 +1,42 @@
+//! The crate-wide configuration, read once per compilation by `allow-until-core` from the
+//! `[package.metadata.allow-until]` table of the package's `Cargo.toml`, on top of the workspace
+//! configuration. `cargo allow-until verify` reads it the same way.
+
+use std::{path::Path, sync::OnceLock};
+
+pub(crate) use allow_until_core::config::{
+    Config, Migration, Problem, DEFAULT_MAX_ERRORS, DEFAULT_RELEASE_TAGS,
+};
+pub(crate) use allow_until_core::severity::{Docsrs, PublicOnly, Severity};
+
+use crate::tracked;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub(crate) fn get() -> &'static Config {
+    CONFIG.get_or_init(load)
+}
+
+/// Problems found while reading the configuration, returned only to the first caller so they are
+/// reported once per compilation.
+pub(crate) fn take_problems() -> &'static [Problem] {
+    let config = get();
+
+    if crate::env::once("config") {
+        &config.problems
+    } else {
+        &[]
+    }
+}
+
+fn load() -> Config {
+    let Some(dir) = tracked::var_os("CARGO_MANIFEST_DIR") else {
+        // without a manifest there is nothing to configure
+        return Config::default();
+    };
+    let version = tracked::var("CARGO_PKG_VERSION")
+        .ok()
+        .and_then(|v| semver::Version::parse(&v).ok());
+
+    allow_until_core::config::load(Path::new(&dir), version.as_ref(), crate::manifest::get)
+}
@@ -0,0 +1,230 @@
+//! Detection of the environment the macros are being expanded in, and how expired annotations
+//! are reported there.
+
+use std::{path::PathBuf, sync::OnceLock};
+
+pub(crate) use allow_until_core::severity::Context;
+
+use crate::{
+    config::{self, Docsrs, Severity},
+    tracked,
+};
+
+pub(crate) const IDE_VAR: &str = "ALLOW_UNTIL_IDE";
+pub(crate) const CLIPPY_VAR: &str = "ALLOW_UNTIL_CLIPPY";
+
+/// The cfg that downgrades expiry errors to warnings, like `ALLOW_UNTIL_DOWNGRADE=warn`.
+pub(crate) const DISABLED_CFG: &str = "allow_until_disabled";
+
+/// Whether the crate is being documented on docs.rs.
+pub(crate) fn docs_rs() -> bool {
+    tracked::var_os("DOCS_RS").is_some()
+}
+
+/// Whether the macros are being expanded by rust-analyzer's proc-macro server rather than rustc.
+///
+/// The server sets `RUST_ANALYZER_INTERNALS_DO_NOT_USE` for the expansions it performs, and as a
+/// fallback the executable name is checked.
+pub(crate) fn rust_analyzer() -> bool {
+    static DETECTED: OnceLock<bool> = OnceLock::new();
+
+    *DETECTED.get_or_init(|| {
+        tracked::var_os("RUST_ANALYZER_INTERNALS_DO_NOT_USE").is_some()
+            || executable(std::env::current_exe().ok()).is_some_and(|name| {
+                name.contains("rust-analyzer") || name.contains("proc-macro-srv")
+            })
+    })
+}
+
+/// Whether the macros are being expanded by clippy's driver rather than rustc, as for
+/// `cargo clippy`.
+///
+/// `cargo clippy` runs the driver as the `RUSTC_WORKSPACE_WRAPPER`, passing it the lint options in
+/// `CLIPPY_ARGS`, and as a fallback the executable name is checked.
+pub(crate) fn clippy() -> bool {
+    static DETECTED: OnceLock<bool> = OnceLock::new();
+
+    *DETECTED.get_or_init(|| {
+        tracked::var_os("CLIPPY_ARGS").is_some()
+            || [
+                tracked::var_os("RUSTC_WORKSPACE_WRAPPER").map(PathBuf::from),
+                std::env::current_exe().ok(),
+            ]
+            .into_iter()
+            .any(|path| executable(path).is_some_and(|name| name.contains("clippy-driver")))
+    })
+}
+
+/// The name of the executable at `path`, without its extension.
+fn executable(path: Option<PathBuf>) -> Option<String> {
+    Some(path?.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// Whether the crate is being built in CI, where `CI` is set to anything but `false` or `0`, as
+/// GitHub Actions, GitLab and most other providers do.
+pub(crate) fn ci() -> bool {
+    tracked::var("CI").is_ok_and(|v| !matches!(v.trim(), "" | "false" | "0"))
+}
+
+/// The context of the expansion, the first detected of those in [`Context::ALL`].
+pub(crate) fn current() -> Context {
+    if docs_rs() {
+        Context::Docsrs
+    } else if rust_analyzer() {
+        Context::RustAnalyzer
+    } else if clippy() {
+        Context::Clippy
+    } else if ci() {
+        Context::Ci
+    } else {
+        Context::Local
+    }
+}
+
+/// Describes `context` for traces, e.g. "expanded by rust-analyzer".
+pub(crate) fn describe(context: Context) -> &'static str {
+    match context {
+        Context::Docsrs => "building on docs.rs",
+        Context::RustAnalyzer => "expanded by rust-analyzer",
+        Context::Clippy => "expanded by clippy",
+        Context::Ci => "building in CI",
+        Context::Local => "building locally",
+    }
+}
+
+/// The context of the expansion, and how it reports an expired annotation whose `docsrs` is
+/// `docsrs`, `None` if the severity is left as it is.
+pub(crate) fn detect(docsrs: Option<Docsrs>) -> (Context, Option<Docsrs>) {
+    let context = current();
+    let keep_errors = match context {
+        Context::RustAnalyzer => tracked::var(IDE_VAR).is_ok_and(|v| v.trim() == "error"),
+        Context::Clippy => tracked::var(CLIPPY_VAR).is_ok_and(|v| v.trim() == "error"),
+        _ => false,
+    };
+    let configured = config::get().contexts.get(&context).copied();
+
+    (context, handling(context, docsrs, configured, keep_errors))
+}
+
+/// How expired annotations are reported in `context`, given the annotation's `docsrs`, the entry of
+/// the `contexts` table for the context, and whether `ALLOW_UNTIL_IDE` or `ALLOW_UNTIL_CLIPPY`
+/// asks to keep errors.
+///
+/// The editor and the lint run only warn unless configured otherwise, so they stay usable while a
+/// removal is being worked on; `cargo build` still fails.
+fn handling(
+    context: Context,
+    docsrs: Option<Docsrs>,
+    configured: Option<Docsrs>,
+    keep_errors: bool,
+) -> Option<Docsrs> {
+    match context {
+        Context::Docsrs => docsrs.or(configured),
+        Context::RustAnalyzer | Context::Clippy if keep_errors => None,
+        Context::RustAnalyzer | Context::Clippy => {
+            configured.or(Some(Docsrs::Severity(Severity::Warning)))
+        }
+        Context::Ci | Context::Local => configured,
+    }
+}
+
+/// Whether rustc was passed `--cfg allow_until_disabled`, usually through `RUSTFLAGS`.
+///
+/// Proc macros can't evaluate cfgs, but they run in the rustc process, so its command line is
+/// searched, including argument files (`@path`) that cargo uses for long command lines. In
+/// rust-analyzer's proc-macro server, whose command line isn't rustc's, the `RUSTFLAGS` variable
+/// is searched instead.
+pub(crate) fn disabled_by_cfg() -> bool {
+    static DISABLED: OnceLock<bool> = OnceLock::new();
+
+    *DISABLED.get_or_init(|| {
+        if rust_analyzer() {
+            let flags = tracked::var("RUSTFLAGS").unwrap_or_default();
+            has_disabled_cfg(flags.split_whitespace().map(String::from))
+        } else {
+            has_disabled_cfg(std::env::args().skip(1))
+        }
+    })
+}
+
+/// Whether `args` hold `--cfg allow_until_disabled` or `--cfg=allow_until_disabled`.
+fn has_disabled_cfg(args: impl IntoIterator<Item = String>) -> bool {
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if let Some(file) = arg.strip_prefix('@') {
+            let contents = std::fs::read_to_string(file).unwrap_or_default();
+            if has_disabled_cfg(contents.lines().map(String::from)) {
+                return true;
+            }
+            continue;
+        }
+
+        let cfg = match arg.strip_prefix("--cfg") {
+            Some("") => args.next(),
+            Some(rest) => rest.strip_prefix('=').map(String::from),
+            None => None,
+        };
+        if cfg.is_some_and(|cfg| cfg.trim() == DISABLED_CFG) {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(String::from)
+    }
+
+    #[test]
+    fn handlings() {
+        const WARNING: Option<Docsrs> = Some(Docsrs::Severity(Severity::Warning));
+        const ERROR: Option<Docsrs> = Some(Docsrs::Severity(Severity::Error));
+        const IGNORE: Option<Docsrs> = Some(Docsrs::Ignore);
+
+        // context, `docsrs`, `contexts` entry, keep errors, handling
+        let cases = [
+            (Context::Docsrs, None, None, false, None),
+            (Context::Docsrs, None, IGNORE, false, IGNORE),
+            (Context::Docsrs, WARNING, IGNORE, false, WARNING),
+            (Context::RustAnalyzer, None, None, false, WARNING),
+            (Context::RustAnalyzer, None, ERROR, false, ERROR),
+            (Context::RustAnalyzer, None, IGNORE, true, None),
+            (Context::Clippy, IGNORE, None, false, WARNING),
+            (Context::Clippy, None, None, true, None),
+            (Context::Ci, None, None, false, None),
+            (Context::Ci, WARNING, ERROR, false, ERROR),
+            (Context::Local, None, WARNING, true, WARNING),
+        ];
+
+        for (context, docsrs, configured, keep_errors, expected) in cases {
+            assert_eq!(
+                handling(context, docsrs, configured, keep_errors),
+                expected,
+                "{:?} with `docsrs` {:?}, configured {:?}, keeping errors: {}",
+                context,
+                docsrs,
+                configured,
+                keep_errors
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_cfg() {
+        assert!(has_disabled_cfg(args(
+            "--edition 2021 --cfg allow_until_disabled lib.rs"
+        )));
+        assert!(has_disabled_cfg(args("--cfg=allow_until_disabled")));
+        assert!(!has_disabled_cfg(args(
+            "--cfg feature=\"allow_until_disabled\""
+        )));
+        assert!(!has_disabled_cfg(args("--cfg allow_until_disabled_soon")));
+        assert!(!has_disabled_cfg(args("allow_until_disabled --cfg")));
+    }
+}
@@ -0,0 +1,134 @@
+//! `#[default_body_until(version = "...")]`, for trait methods whose default body is only kept for
+//! backwards compatibility. Once the requirement matches, the method must become required, so a
+//! default body left in place is an error.
+
+use proc_macro::{Span, TokenStream};
+use syn::{spanned::Spanned, TraitItemFn};
+
+use crate::{
+    annotation, args,
+    code::Code,
+    current_version,
+    diagnostic::{self, Diagnostic},
+    extend,
+    item::Item,
+    trace, truncate, warning_level,
+};
+
+/// Evaluates the `default_body_until` attribute at `at` on the trait method `input` against the
+/// current version, erroring once it matches if the method still has a default body.
+pub(crate) fn evaluate(tokens: TokenStream, input: TokenStream, item: &Item, at: Span) {
+    // `fn f();` and `fn f() {}` only differ in their last token, but a where clause or an `async`
+    // is easier to get right with the parsed method
+    let Ok(method) = syn::parse::<TraitItemFn>(input) else {
+        diagnostic::error(
+            at,
+            Code::InvalidInput,
+            "`default_body_until` can only be applied to trait methods",
+        )
+        .emit();
+        return;
+    };
+
+    let parsed = match args::parse_default_body_until(tokens.into()) {
+        Ok(parsed) if parsed.version.is_none() => {
+            diagnostic::error(
+                at,
+                Code::MissingVersion,
+                "missing required `version` argument",
+            )
+            .help(format!(
+                "the attribute needs the requirement the method becomes required at, e.g.\n\
+                 #[default_body_until(version = \">=2.0\")]\n\
+                 optional arguments are {}",
+                args::DEFAULT_BODY_UNTIL_ARGS[1..]
+                    .iter()
+                    .map(|arg| format!("`{}`", arg))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .emit();
+            return;
+        }
+        Ok(parsed) => parsed,
+        Err(e) => {
+            annotation::error(e).emit();
+            return;
+        }
+    };
+    let mut args = match annotation::resolve(parsed, at) {
+        Ok(args) => args,
+        Err(e) => {
+            e.emit();
+            return;
+        }
+    };
+
+    let mut trace = trace::Trace::new(at);
+    let Some(current) = current_version(&args, at, &mut trace) else {
+        return;
+    };
+    let extension = extend(&mut args, at);
+    let (version, truncation) = truncate(&mut args, &current.version);
+    let matched = args.version.matches(&version);
+    trace.step(|| {
+        format!(
+            "version {} from {}",
+            current.version,
+            current.describe_source()
+        )
+    });
+    trace.step(|| {
+        format!(
+            "requirement `{}` {}",
+            args.version,
+            if matched { "matches" } else { "does not match" }
+        )
+    });
+    if !matched {
+        trace.decide("nothing to do, the default body is still allowed");
+        return;
+    }
+
+    let Some(body) = &method.default else {
+        trace.decide("warning, the default body is gone");
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::DefaultBody,
+            format!(
+                "{} no longer has a default body, as required from `{}`",
+                item.describe(),
+                args.version
+            ),
+        )
+        .help("remove the `default_body_until` attribute")
+        .emit();
+        return;
+    };
+
+    trace.decide("error, the default body is still there");
+    let mut message = format!(
+        "{} must not have a default body! (version {} matches {})",
+        item.describe(),
+        version,
+        args.version
+    );
+    if let Some(reason) = &args.reason {
+        message.push_str(&format!("\n{}", reason));
+    }
+    let mut diag = diagnostic::error(at, Code::DefaultBody, message)
+        .span_note(body.span().unwrap(), "the default body");
+    if let Some(extension) = extension {
+        diag = diag.note(extension);
+    }
+    if let Some(truncation) = truncation {
+        diag = diag.note(truncation);
+    }
+    diag.help(format!(
+        "delete the body, leaving `fn {}(...);`, so implementors have to supply it, then remove \
+         `#[default_body_until]`",
+        method.sig.ident
+    ))
+    .emit();
+}
@@ -0,0 +1,54 @@
+//! `deprecate_from`, which gives the annotated item the `#[deprecated(since = "...")]` of the
+//! version it was deprecated in, so its users are warned ahead of the removal and the annotation
+//! satisfies `require_deprecated`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use semver::Version;
+
+use crate::item::{Item, Kind};
+
+/// The `#[deprecated(...)]` to give `item` for the `deprecate_from` version `from`, if any, unless
+/// it is `#[deprecated]` already or rustc doesn't accept the attribute on it.
+pub(crate) fn attribute(from: Option<&Version>, item: &Item) -> Option<proc_macro2::TokenStream> {
+    let from = from.filter(|_| !item.deprecated && deprecatable(item.kind))?;
+    let since = from.to_string();
+    // rustc rejects it on the members of trait impls, which the macro can't tell from inherent
+    // ones, so they are left with the warning that it has no effect
+    let allow = (!item.free).then(|| quote!(#[allow(useless_deprecated)]));
+    Some(quote! {
+        #allow
+        #[deprecated(since = #since)]
+    })
+}
+
+/// `input`, the tokens of `item`, with the `#[deprecated(...)]` of [`attribute`] added.
+pub(crate) fn added(from: Option<&Version>, item: &Item, input: TokenStream) -> TokenStream {
+    let Some(attribute) = attribute(from, item) else {
+        return input;
+    };
+    let input = proc_macro2::TokenStream::from(input);
+    quote! {
+        #attribute
+        #input
+    }
+    .into()
+}
+
+/// Whether items of `kind` accept `#[deprecated]`, which rules out extern blocks and the items of
+/// unknown kind, such as trait impls, as well as the fields and variants the derive can't change.
+fn deprecatable(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::Struct
+            | Kind::Enum
+            | Kind::Union
+            | Kind::Fn
+            | Kind::Const
+            | Kind::Static
+            | Kind::TypeAlias
+            | Kind::AssociatedType
+            | Kind::Trait
+            | Kind::Mod
+    )
+}
@@ -0,0 +1,381 @@
+//! Diagnostics that work on stable as well as nightly.
+//!
+//! With the `nightly` feature, diagnostics are emitted through `proc_macro::Diagnostic`, as long as
+//! the build script found the unstable APIs to compile and set the `allow_until_nightly` cfg.
+//! Otherwise they are collected until the macro returns, and [`take`] turns them into tokens appended to
+//! its output: errors become `compile_error!` invocations, and warnings uses of a `#[deprecated]`
+//! item. Notes have no stable equivalent and are dropped, unless emitted with
+//! [`Diagnostic::emit_visible`], which writes them to stderr. The notes attached to errors and
+//! warnings are appended to their message, with the location of the ones that point elsewhere.
+
+use std::cell::Cell;
+
+use proc_macro::{Span, TokenStream};
+
+use crate::{code::Code, github};
+
+pub(crate) use allow_until_core::severity::Level;
+
+pub(crate) struct Diagnostic {
+    span: Span,
+    level: Level,
+    message: String,
+    children: Vec<(Level, Option<Span>, String)>,
+}
+
+pub(crate) fn error(span: Span, code: Code, message: impl Into<String>) -> Diagnostic {
+    Diagnostic::spanned(span, Level::Error, code, message)
+}
+
+pub(crate) fn warning(span: Span, code: Code, message: impl Into<String>) -> Diagnostic {
+    Diagnostic::spanned(span, Level::Warning, code, message)
+}
+
+pub(crate) fn note(span: Span, code: Code, message: impl Into<String>) -> Diagnostic {
+    Diagnostic::spanned(span, Level::Note, code, message)
+}
+
+impl Diagnostic {
+    /// A diagnostic whose message is prefixed with `code`, e.g. "[AU0001] struct `Foo` not allowed!".
+    pub fn spanned(span: Span, level: Level, code: Code, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            span,
+            level,
+            message: format!("[{}] {}", code.as_str(), message.into()),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn note(mut self, message: impl Into<String>) -> Diagnostic {
+        self.children.push((Level::Note, None, message.into()));
+        self
+    }
+
+    /// A note pointing at `span`, such as the secondary location of an error.
+    pub fn span_note(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.children
+            .push((Level::Note, Some(span), message.into()));
+        self
+    }
+
+    pub fn help(mut self, message: impl Into<String>) -> Diagnostic {
+        self.children.push((Level::Help, None, message.into()));
+        self
+    }
+
+    /// Help pointing at `span`, such as the code a fix would change.
+    pub fn span_help(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.children
+            .push((Level::Help, Some(span), message.into()));
+        self
+    }
+
+    /// The message with the notes and help attached to it, laid out the way rustc shows them.
+    fn rendered(&self) -> String {
+        let mut message = self.message.clone();
+        for (level, span, child) in &self.children {
+            let level = if *level == Level::Help {
+                "help"
+            } else {
+                "note"
+            };
+            // continuation lines are aligned with the first, as rustc does
+            let child = child.replace('\n', &format!("\n{:1$}", "", level.len() + 4));
+            message.push_str(&format!("\n= {}: {}", level, child));
+            if let Some(span) = span {
+                message.push_str(&format!(
+                    " (line {}, column {})",
+                    span.line(),
+                    span.column()
+                ));
+            }
+        }
+        message
+    }
+
+    /// Emits a note that stays visible without the nightly APIs: the stable fallback drops notes
+    /// of their own, so it writes them to stderr instead, laid out the way rustc shows them.
+    pub fn emit_visible(self) {
+        if cfg!(allow_until_nightly) || self.level != Level::Note {
+            return self.emit();
+        }
+        if silenced() {
+            return;
+        }
+        let lines = format!(
+            "note: {}\n  --> {}:{}:{}\n",
+            self.rendered().replace("\n= ", "\n   = "),
+            self.span.file(),
+            self.span.line(),
+            self.span.column()
+        );
+        let _ = std::io::Write::write_all(&mut std::io::stderr(), lines.as_bytes());
+    }
+
+    #[cfg(allow_until_nightly)]
+    pub fn emit(self) {
+        if silenced() {
+            return;
+        }
+        github::annotate(self.level, self.span, &self.rendered());
+        let level = |level| match level {
+            Level::Error => proc_macro::Level::Error,
+            Level::Warning => proc_macro::Level::Warning,
+            Level::Note => proc_macro::Level::Note,
+            Level::Help => proc_macro::Level::Help,
+        };
+
+        let mut diag = proc_macro::Diagnostic::spanned(self.span, level(self.level), self.message);
+        for (child, span, message) in self.children {
+            diag = match (child, span) {
+                (Level::Help, Some(span)) => diag.span_help(span, message),
+                (Level::Help, None) => diag.help(message),
+                (_, Some(span)) => diag.span_note(span, message),
+                (_, None) => diag.note(message),
+            };
+        }
+        diag.emit();
+    }
+
+    #[cfg(not(allow_until_nightly))]
+    pub fn emit(self) {
+        if silenced() {
+            return;
+        }
+        github::annotate(self.level, self.span, &self.rendered());
+        stable::PENDING.with(|pending| pending.borrow_mut().push(self));
+    }
+}
+
+/// The span covering `a` and `b`, if they are in the same file and joining spans is supported.
+/// Spans are taken at their source, as the call site of an attribute macro belongs to its
+/// expansion and only joins with the spans of its input as the attribute alone.
+pub(crate) fn join(a: Span, b: Span) -> Option<Span> {
+    #[cfg(allow_until_nightly)]
+    return a.source().join(b.source());
+    #[cfg(not(allow_until_nightly))]
+    {
+        let _ = (a, b);
+        None
+    }
+}
+
+/// The span from the first to the last of `tokens`, if they all come from the source file rather
+/// than from the expansion of another macro, so the code they cover is what the user wrote.
+/// Always `None` without the `nightly` feature, where spans can't be joined.
+pub(crate) fn extent(tokens: TokenStream) -> Option<Span> {
+    #[cfg(allow_until_nightly)]
+    return {
+        let (mut first, mut last) = (None, None);
+        for tok in tokens {
+            let span = tok.span();
+            if span.parent().is_some() {
+                return None;
+            }
+            first.get_or_insert(span);
+            last = Some(span);
+        }
+        first?.join(last?)
+    };
+    #[cfg(not(allow_until_nightly))]
+    {
+        let _ = tokens;
+        None
+    }
+}
+
+/// `span`, if it comes from the source file rather than from the expansion of another macro, so the
+/// code it covers is what the user wrote. Always `None` without the `nightly` feature, where the two
+/// can't be told apart.
+pub(crate) fn in_source(span: Span) -> Option<Span> {
+    #[cfg(allow_until_nightly)]
+    return span.parent().is_none().then_some(span);
+    #[cfg(not(allow_until_nightly))]
+    {
+        let _ = span;
+        None
+    }
+}
+
+/// The invocation in the source file of the outermost macro `span` was generated by, if another
+/// macro generated it rather than the user writing it. Always `None` without the `nightly`
+/// feature, where the two can't be told apart.
+pub(crate) fn origin(span: Span) -> Option<Span> {
+    #[cfg(allow_until_nightly)]
+    return {
+        let mut origin = span;
+        while let Some(parent) = origin.parent() {
+            origin = parent;
+        }
+        // the call site of an attribute macro has a parent of its own, the attribute as written
+        let at = |span: Span| (span.local_file(), span.line(), span.column());
+        (at(origin) != at(span)).then_some(origin)
+    };
+    #[cfg(not(allow_until_nightly))]
+    {
+        let _ = span;
+        None
+    }
+}
+
+/// Where the output of a macro can hold the tokens of the diagnostics emitted while expanding it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Place {
+    /// Among the items of a module or a block, where warnings can be emitted too.
+    Items,
+    /// Possibly among associated or foreign items, so only errors can be emitted.
+    Unknown,
+}
+
+thread_local! {
+    static SILENCED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f`, dropping the diagnostics it emits if `silent`, for annotations with `silent = true`.
+pub(crate) fn silence<T>(silent: bool, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous state, even when `f` panics so the panic is still reported.
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SILENCED.set(self.0);
+        }
+    }
+
+    let _restore = Restore(SILENCED.replace(silent || SILENCED.get()));
+    f()
+}
+
+/// Whether diagnostics are being dropped by [`silence`].
+pub(crate) fn silenced() -> bool {
+    SILENCED.get()
+}
+
+/// Whether there are diagnostics for [`take`] to turn into tokens. Never the case with the
+/// `nightly` feature.
+pub(crate) fn pending() -> bool {
+    #[cfg(allow_until_nightly)]
+    return false;
+    #[cfg(not(allow_until_nightly))]
+    stable::PENDING.with(|pending| !pending.borrow().is_empty())
+}
+
+/// The tokens reporting the diagnostics emitted so far, which the caller appends to its output.
+/// Always empty with the `nightly` feature, as diagnostics have been emitted already.
+pub(crate) fn take(place: Place) -> TokenStream {
+    #[cfg(allow_until_nightly)]
+    return {
+        let _ = place;
+        TokenStream::new()
+    };
+    #[cfg(not(allow_until_nightly))]
+    stable::PENDING
+        .with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+        .into_iter()
+        .filter_map(|diag| match diag.level {
+            Level::Error => Some(stable::compile_error(&diag)),
+            Level::Warning if place == Place::Items => Some(stable::deprecated(&diag)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(not(allow_until_nightly))]
+mod stable {
+    use std::cell::RefCell;
+
+    use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+
+    use super::Diagnostic;
+
+    thread_local! {
+        pub static PENDING: RefCell<Vec<Diagnostic>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// `compile_error! { "..." }`, spanned so the error points at the annotation.
+    pub fn compile_error(diag: &Diagnostic) -> TokenStream {
+        let span = diag.span;
+        let mut message = Literal::string(&diag.rendered());
+        message.set_span(span);
+
+        tokens(
+            span,
+            vec![
+                Ident::new("compile_error", span).into(),
+                Punct::new('!', Spacing::Alone).into(),
+                group(span, Delimiter::Brace, vec![message.into()]),
+            ],
+        )
+    }
+
+    /// A use of a `#[deprecated]` unit struct in an anonymous constant, which rustc reports as a
+    /// warning with the message as the deprecation note:
+    ///
+    /// ```text
+    /// const _: () = {
+    ///     #[deprecated(note = "...")]
+    ///     struct AllowUntilWarning;
+    ///     let _ = AllowUntilWarning;
+    /// };
+    /// ```
+    pub fn deprecated(diag: &Diagnostic) -> TokenStream {
+        let span = diag.span;
+        let ident = |name: &str| TokenTree::from(Ident::new(name, span));
+        let punct = |c: char| TokenTree::from(Punct::new(c, Spacing::Alone));
+        let mut note = Literal::string(&diag.rendered());
+        note.set_span(span);
+
+        let body = vec![
+            punct('#'),
+            group(
+                span,
+                Delimiter::Bracket,
+                vec![
+                    ident("deprecated"),
+                    group(
+                        span,
+                        Delimiter::Parenthesis,
+                        vec![ident("note"), punct('='), note.into()],
+                    ),
+                ],
+            ),
+            ident("struct"),
+            ident("AllowUntilWarning"),
+            punct(';'),
+            ident("let"),
+            ident("_"),
+            punct('='),
+            ident("AllowUntilWarning"),
+            punct(';'),
+        ];
+
+        tokens(
+            span,
+            vec![
+                ident("const"),
+                ident("_"),
+                punct(':'),
+                group(span, Delimiter::Parenthesis, Vec::new()),
+                punct('='),
+                group(span, Delimiter::Brace, body),
+                punct(';'),
+            ],
+        )
+    }
+
+    fn group(span: proc_macro::Span, delimiter: Delimiter, tokens: Vec<TokenTree>) -> TokenTree {
+        let mut group = Group::new(delimiter, tokens.into_iter().collect());
+        group.set_span(span);
+        group.into()
+    }
+
+    fn tokens(span: proc_macro::Span, tokens: Vec<TokenTree>) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|mut tok| {
+                tok.set_span(span);
+                tok
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+//! `feature`, `cfg` and `doc_cfg`, which give an annotated item that only exists under some
+//! configuration the matching `doc(cfg)`, so that its documentation on docs.rs shows where it is
+//! available next to its removal.
+//!
+//! The configuration is given to the annotation because the macro can't see the item's own `cfg`
+//! attributes: rustc evaluates them, and drops those that hold, before expanding it.
+
+use std::{cell::RefCell, sync::OnceLock};
+
+use proc_macro::{Delimiter, TokenStream, TokenTree as TT};
+use quote::quote;
+
+use crate::{
+    code::Code,
+    diagnostic::{self, Diagnostic},
+    feature, manifest,
+};
+
+thread_local! {
+    static PENDING: RefCell<Option<proc_macro2::TokenStream>> = const { RefCell::new(None) };
+}
+
+/// The predicate of the `doc(cfg)` to give the item, combining `feature` and `cfg` with `all`,
+/// when `doc_cfg`, or else the crate's `doc_cfg`, asks for it. Without either, it is asked for
+/// when the package follows the docs.rs convention of `--cfg docsrs`.
+pub(crate) fn resolve(
+    feature: Option<(String, proc_macro2::Span)>,
+    cfg: Option<(proc_macro2::TokenStream, proc_macro2::Span)>,
+    doc_cfg: Option<(bool, proc_macro2::Span)>,
+    default: Option<bool>,
+) -> Result<Option<proc_macro2::TokenStream>, Diagnostic> {
+    if let Some((name, span)) = &feature {
+        feature::check(name, span.unwrap())?;
+    }
+
+    let predicate = match (feature.map(|(name, _)| quote!(feature = #name)), cfg) {
+        (Some(feature), Some((cfg, _))) => Some(quote!(all(#feature, #cfg))),
+        (feature, cfg) => feature.or(cfg.map(|(cfg, _)| cfg)),
+    };
+    match doc_cfg {
+        Some((true, span)) if predicate.is_none() => Err(diagnostic::error(
+            span.unwrap(),
+            Code::MissingRequired,
+            "`doc_cfg` is given without `feature` or `cfg`",
+        )
+        .help("give the configuration the item exists under, such as `feature = \"legacy\"`")),
+        Some((enabled, _)) => Ok(predicate.filter(|_| enabled)),
+        None if default.unwrap_or_else(docsrs_configured) => Ok(predicate),
+        None => Ok(None),
+    }
+}
+
+/// Whether the package builds its documentation with `--cfg docsrs`, in the `rustdoc-args` of
+/// `[package.metadata.docs.rs]`, which is the convention `doc(cfg)` goes with.
+fn docsrs_configured() -> bool {
+    static CONFIGURED: OnceLock<bool> = OnceLock::new();
+
+    *CONFIGURED.get_or_init(|| {
+        let Ok(Some(manifest)) = manifest::package() else {
+            return false;
+        };
+        let args = manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("docs"))
+            .and_then(|d| d.get("rs"))
+            .and_then(|d| d.get("rustdoc-args"))
+            .and_then(|a| a.as_array());
+        let args: Vec<&str> = args
+            .into_iter()
+            .flatten()
+            .filter_map(|arg| arg.as_str())
+            .collect();
+        args.contains(&"--cfg=docsrs") || args.windows(2).any(|pair| pair == ["--cfg", "docsrs"])
+    })
+}
+
+/// Gives the item the current macro is applied to the `doc(cfg)` of `predicate`.
+pub(crate) fn mark(predicate: proc_macro2::TokenStream) {
+    PENDING.with(|pending| *pending.borrow_mut() = Some(predicate));
+}
+
+/// `input` with the `#[cfg_attr(docsrs, doc(cfg(...)))]` asked for since the last call, if any,
+/// unless the item has a `doc(cfg)` of its own.
+pub(crate) fn added(input: TokenStream) -> TokenStream {
+    let Some(predicate) = PENDING.with(|pending| pending.take()) else {
+        return input;
+    };
+
+    let mut tokens = input.clone().into_iter();
+    // the outer attributes, before the visibility of the item
+    while let (Some(TT::Punct(pound)), Some(TT::Group(attr))) = (tokens.next(), tokens.next()) {
+        if pound.as_char() != '#' || attr.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        if has_doc_cfg(attr.stream()) {
+            return input;
+        }
+    }
+
+    let input = proc_macro2::TokenStream::from(input);
+    quote! {
+        #[cfg_attr(docsrs, doc(cfg(#predicate)))]
+        #input
+    }
+    .into()
+}
+
+/// Whether the tokens of an attribute hold a `doc(cfg(...))`, on its own or within a `cfg_attr`.
+fn has_doc_cfg(stream: TokenStream) -> bool {
+    let tokens: Vec<TT> = stream.into_iter().collect();
+    tokens.iter().enumerate().any(|(i, token)| match token {
+        TT::Ident(doc) if doc.to_string() == "doc" => {
+            matches!(tokens.get(i + 1), Some(TT::Group(args)) if args
+                .stream()
+                .into_iter()
+                .any(|t| matches!(t, TT::Ident(cfg) if cfg.to_string() == "cfg")))
+        }
+        TT::Group(group) => has_doc_cfg(group.stream()),
+        _ => false,
+    })
+}
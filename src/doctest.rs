@@ -0,0 +1,263 @@
+//! `doctest_ignore_from`, which marks the Rust examples in the documentation of an item `ignore`
+//! (or `no_run`) once the item is deprecated, so doctests stop endorsing it before the error
+//! takes over.
+
+use std::cell::Cell;
+
+use allow_until_core::severity::DoctestFence;
+use proc_macro::{Delimiter, Group, Literal, TokenStream, TokenTree as TT};
+
+thread_local! {
+    static PENDING: Cell<Option<DoctestFence>> = const { Cell::new(None) };
+}
+
+/// Marks the examples of the item the current macro is applied to with `fence`.
+pub(crate) fn mark(fence: DoctestFence) {
+    PENDING.with(|pending| pending.set(Some(fence)));
+}
+
+/// Drops the mark asked for since the last call, for the macros that don't re-emit the
+/// documentation of the items they evaluate, such as the members of `allow_members_until`.
+pub(crate) fn discard() {
+    PENDING.with(Cell::take);
+}
+
+/// `input` with the mark asked for since the last call added to the Rust code blocks of its
+/// `#[doc = "..."]` attributes, if any. Attributes that don't change keep their tokens.
+pub(crate) fn rewritten(input: TokenStream) -> TokenStream {
+    let Some(fence) = PENDING.with(Cell::take) else {
+        return input;
+    };
+
+    let mut blocks = Blocks::default();
+    let mut tokens: Vec<TT> = input.into_iter().collect();
+    // the outer attributes, before the visibility of the item
+    let mut at = 0;
+    while let [TT::Punct(pound), TT::Group(attr), ..] = &tokens[at..] {
+        if pound.as_char() != '#' || attr.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        if let Some(attr) = rewrite_attr(attr, &mut blocks, fence) {
+            tokens[at + 1] = TT::Group(attr);
+        }
+        at += 2;
+    }
+    tokens.into_iter().collect()
+}
+
+/// The `#[doc = "..."]` attribute `attr` with its code blocks marked, or `None` if it isn't one
+/// or doesn't change, such as `#[doc = include_str!("...")]`.
+fn rewrite_attr(attr: &Group, blocks: &mut Blocks, fence: DoctestFence) -> Option<Group> {
+    let [TT::Ident(doc), TT::Punct(eq), TT::Literal(lit)] =
+        &attr.stream().into_iter().collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    if doc.to_string() != "doc" || eq.as_char() != '=' {
+        return None;
+    }
+    let text = syn::parse_str::<syn::LitStr>(&lit.to_string())
+        .ok()?
+        .value();
+
+    let rewritten = blocks.rewrite(&text, fence)?;
+    let mut literal = Literal::string(&rewritten);
+    literal.set_span(lit.span());
+    let stream = [
+        TT::Ident(doc.clone()),
+        TT::Punct(eq.clone()),
+        TT::Literal(literal),
+    ];
+    let mut group = Group::new(Delimiter::Bracket, stream.into_iter().collect());
+    group.set_span(attr.span());
+    Some(group)
+}
+
+/// The fence of the code block the documentation is in, as its character and length, carried
+/// from one `#[doc]` attribute to the next, as a `///` comment is an attribute per line.
+#[derive(Default)]
+struct Blocks {
+    open: Option<(char, usize)>,
+}
+
+impl Blocks {
+    /// `text` with the opening fences of its Rust code blocks marked with `fence`, or `None` if
+    /// there are none to mark, as each is marked already.
+    fn rewrite(&mut self, text: &str, fence: DoctestFence) -> Option<String> {
+        let mut changed = false;
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| match self.line(line, fence) {
+                Some(marked) => {
+                    changed = true;
+                    marked
+                }
+                None => line.into(),
+            })
+            .collect();
+        changed.then(|| lines.join("\n"))
+    }
+
+    /// `line` with `fence` added to its info string, if it opens a Rust code block lacking it.
+    fn line(&mut self, line: &str, fence: DoctestFence) -> Option<String> {
+        let trimmed = line.trim_start();
+        let c = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+        let length = trimmed.chars().take_while(|&t| t == c).count();
+        if length < 3 {
+            return None;
+        }
+        let info = &trimmed[length..];
+
+        if let Some((open, open_length)) = self.open {
+            if c == open && length >= open_length && info.trim().is_empty() {
+                self.open = None;
+            }
+            return None;
+        }
+        // a backtick fence can't have backticks in its info string, so isn't one
+        if c == '`' && info.contains('`') {
+            return None;
+        }
+        self.open = Some((c, length));
+
+        let words: Vec<&str> = info
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if !words
+            .iter()
+            .all(|word| *word == "rust" || is_attribute(word))
+        {
+            return None;
+        }
+        let marked = match fence {
+            DoctestFence::Ignore => words.contains(&"ignore"),
+            DoctestFence::NoRun => words
+                .iter()
+                .any(|word| ["no_run", "ignore", "compile_fail"].contains(word)),
+        };
+        if marked {
+            return None;
+        }
+
+        let info = info.trim_end();
+        let indent = &line[..line.len() - trimmed.len()];
+        let separator = if words.is_empty() { "" } else { "," };
+        Some(format!(
+            "{}{}{}{}{}",
+            indent,
+            &trimmed[..length],
+            info,
+            separator,
+            fence.as_str()
+        ))
+    }
+}
+
+/// Whether `word` in the info string of a code block is one rustdoc reads as an attribute of a
+/// Rust example, such as `should_panic` or `edition2021`, rather than the name of a language.
+fn is_attribute(word: &str) -> bool {
+    matches!(
+        word,
+        "ignore" | "should_panic" | "no_run" | "compile_fail" | "test_harness" | "standalone_crate"
+    ) || word.starts_with("ignore-")
+        || word.starts_with("edition")
+        || (word.len() > 1
+            && word.starts_with('E')
+            && word[1..].chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(lines: &[&str], fence: DoctestFence) -> Vec<String> {
+        let mut blocks = Blocks::default();
+        lines
+            .iter()
+            .map(|line| {
+                blocks
+                    .rewrite(line, fence)
+                    .unwrap_or_else(|| line.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rust_blocks() {
+        let lines = [
+            " Loads the index.",
+            "",
+            " ```",
+            " let index = load();",
+            " ```",
+            "",
+            " ```rust,should_panic",
+            " load_from(\"missing\");",
+            " ```",
+            "",
+            " ```text",
+            " ```rust",
+            " ```",
+            "",
+            " ~~~~ edition2021",
+            " ```",
+            " ~~~~",
+        ];
+        assert_eq!(
+            rewrite(&lines, DoctestFence::Ignore),
+            [
+                " Loads the index.",
+                "",
+                " ```ignore",
+                " let index = load();",
+                " ```",
+                "",
+                " ```rust,should_panic,ignore",
+                " load_from(\"missing\");",
+                " ```",
+                "",
+                " ```text",
+                " ```rust",
+                " ```",
+                "",
+                " ~~~~ edition2021,ignore",
+                " ```",
+                " ~~~~",
+            ]
+        );
+    }
+
+    #[test]
+    fn idempotent() {
+        let lines = [" ```rust", " load();", " ```", " ```", " load();", " ```"];
+        for fence in [DoctestFence::Ignore, DoctestFence::NoRun] {
+            let once = rewrite(&lines, fence);
+            let once: Vec<&str> = once.iter().map(String::as_str).collect();
+            assert_eq!(rewrite(&once, fence), once);
+        }
+        assert_eq!(
+            rewrite(&[" ```compile_fail", " ```ignore"], DoctestFence::NoRun),
+            [" ```compile_fail", " ```ignore"]
+        );
+        assert_eq!(
+            rewrite(&[" ```no_run"], DoctestFence::Ignore),
+            [" ```no_run,ignore"]
+        );
+    }
+
+    #[test]
+    fn blocks_within_one_attribute() {
+        let mut blocks = Blocks::default();
+        assert_eq!(
+            blocks
+                .rewrite(
+                    "\n```\nload();\n```\n\n```E0308\n```\n",
+                    DoctestFence::NoRun
+                )
+                .as_deref(),
+            Some("\n```no_run\nload();\n```\n\n```E0308,no_run\n```\n")
+        );
+        assert_eq!(blocks.rewrite("no examples", DoctestFence::NoRun), None);
+    }
+}
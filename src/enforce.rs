@@ -0,0 +1,107 @@
+//! `enforce = "tagged-builds"`, which only fails the builds of commits carrying a release tag, the
+//! last line of defence before an artifact ships, and warns about expiry in the others.
+
+use std::{path::Path, process::Command, sync::OnceLock};
+
+use allow_until_core::severity::Enforce;
+use proc_macro::Span;
+
+use crate::{
+    annotation::ParsedAnnotation,
+    config::{self, Severity},
+    offline,
+    trace::Trace,
+    tracked,
+};
+
+/// The pattern of release tags, from `release_tags`.
+fn pattern() -> &'static str {
+    config::get()
+        .release_tags
+        .as_deref()
+        .unwrap_or(config::DEFAULT_RELEASE_TAGS)
+}
+
+/// The release tags of the commit being built, or why they are unknown. Git is only run by the
+/// first annotation asking.
+fn tags(at: Span) -> &'static Result<Vec<String>, String> {
+    static TAGS: OnceLock<Result<Vec<String>, String>> = OnceLock::new();
+
+    TAGS.get_or_init(|| {
+        offline::policy(at).check(offline::Source::GitTag)?;
+        let dir = tracked::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| ".".into());
+        from_git(dir.as_ref(), pattern())
+    })
+}
+
+/// The tags matching `pattern` that point at `HEAD` in the repository `dir` is in.
+fn from_git(dir: &Path, pattern: &str) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .args(["tag", "--points-at", "HEAD", "--list", pattern])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run `git tag`: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`git tag --points-at HEAD` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect())
+}
+
+/// The severity of the expired annotation at `at` with `enforce = "tagged-builds"`, with the note
+/// explaining it: an error when the commit has a release tag, and a warning when it has none or
+/// its tags are unknown, as outside of a git repository. `None` for the other annotations.
+pub(crate) fn severity(
+    args: &ParsedAnnotation,
+    at: Span,
+    trace: &mut Trace,
+) -> Option<(Severity, String)> {
+    if args.enforce != Some(Enforce::TaggedBuilds) {
+        return None;
+    }
+
+    let pattern = pattern();
+    let (severity, note) = match tags(at) {
+        Ok(tags) => match tags.first() {
+            Some(tag) => (
+                Severity::Error,
+                format!(
+                    "this is an error as `enforce = \"tagged-builds\"` is set and the commit is \
+                     tagged `{}`, a release tag matching `{}`",
+                    tag, pattern
+                ),
+            ),
+            None => (
+                Severity::Warning,
+                format!(
+                    "this is a warning as `enforce = \"tagged-builds\"` is set and the commit has \
+                     no tag matching `{}`, it is an error in the builds of release tags",
+                    pattern
+                ),
+            ),
+        },
+        Err(e) => (
+            Severity::Warning,
+            format!(
+                "this is a warning as `enforce = \"tagged-builds\"` is set and the release tags \
+                 of the commit are unknown: {}",
+                e
+            ),
+        ),
+    };
+    trace.step(|| {
+        format!(
+            "`enforce = \"tagged-builds\"` with release tags matching `{}`: {}",
+            pattern,
+            severity.as_str()
+        )
+    });
+    Some((severity, note))
+}
@@ -0,0 +1,245 @@
+//! Switches read from `ALLOW_UNTIL_*` environment variables that change how expired annotations
+//! are reported for a whole build.
+
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+
+use allow_until_core::version::Distance;
+use semver::Version;
+
+use crate::tracked;
+
+pub(crate) const DOWNGRADE_VAR: &str = "ALLOW_UNTIL_DOWNGRADE";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Downgrade {
+    /// Report expired annotations as warnings.
+    Warn,
+    /// Don't report expired annotations at all.
+    Allow,
+}
+
+impl Downgrade {
+    /// The value of [`DOWNGRADE_VAR`] asking for the downgrade.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Downgrade::Warn => "warn",
+            Downgrade::Allow => "allow",
+        }
+    }
+}
+
+/// Reads [`DOWNGRADE_VAR`], returning an error message for values other than `warn` and `allow`.
+pub(crate) fn downgrade() -> Result<Option<Downgrade>, &'static str> {
+    static DOWNGRADE: OnceLock<Result<Option<Downgrade>, String>> = OnceLock::new();
+
+    DOWNGRADE
+        .get_or_init(|| match tracked::var(DOWNGRADE_VAR) {
+            Ok(v) => match v.trim() {
+                "warn" => Ok(Some(Downgrade::Warn)),
+                "allow" => Ok(Some(Downgrade::Allow)),
+                _ => Err(format!(
+                    "ignoring invalid `{}` value `{}`, expected `warn` or `allow`",
+                    DOWNGRADE_VAR, v
+                )),
+            },
+            Err(_) => Ok(None),
+        })
+        .as_ref()
+        .map(|d| *d)
+        .map_err(|e| e.as_str())
+}
+
+pub(crate) const DENY_VAR: &str = "ALLOW_UNTIL_DENY";
+
+/// Reads [`DENY_VAR`], which escalates warnings to errors when set to `1` or `warnings`.
+pub(crate) fn deny() -> Result<bool, &'static str> {
+    static DENY: OnceLock<Result<bool, String>> = OnceLock::new();
+
+    DENY.get_or_init(|| match tracked::var(DENY_VAR) {
+        Ok(v) => match v.trim() {
+            "1" | "warnings" => Ok(true),
+            "" | "0" => Ok(false),
+            _ => Err(format!(
+                "ignoring invalid `{}` value `{}`, expected `1` or `warnings`",
+                DENY_VAR, v
+            )),
+        },
+        Err(_) => Ok(false),
+    })
+    .as_ref()
+    .map(|d| *d)
+    .map_err(|e| e.as_str())
+}
+
+pub(crate) const SUPPRESS_VAR: &str = "ALLOW_UNTIL_SUPPRESS";
+
+/// The annotation ids listed in [`SUPPRESS_VAR`], separated by commas.
+pub(crate) fn suppressed_ids() -> &'static [String] {
+    static IDS: OnceLock<Vec<String>> = OnceLock::new();
+
+    IDS.get_or_init(|| ids(SUPPRESS_VAR))
+}
+
+pub(crate) const EXPECTED_VAR: &str = "ALLOW_UNTIL_EXPECTED";
+
+/// The annotation ids listed in [`EXPECTED_VAR`], separated by commas.
+pub(crate) fn expected_ids() -> &'static [String] {
+    static IDS: OnceLock<Vec<String>> = OnceLock::new();
+
+    IDS.get_or_init(|| ids(EXPECTED_VAR))
+}
+
+fn ids(var: &str) -> Vec<String> {
+    tracked::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub(crate) const VERBOSE_VAR: &str = "ALLOW_UNTIL_VERBOSE";
+
+/// Whether every expiry error should be reported in full, as requested with
+/// `ALLOW_UNTIL_VERBOSE=1`.
+pub(crate) fn verbose() -> bool {
+    static VERBOSE: OnceLock<bool> = OnceLock::new();
+
+    *VERBOSE.get_or_init(|| tracked::var(VERBOSE_VAR).is_ok_and(|v| v.trim() == "1"))
+}
+
+pub(crate) const FAIL_FAST_VAR: &str = "ALLOW_UNTIL_FAIL_FAST";
+
+/// Whether only the first expiry error of the crate should be reported in full, as requested with
+/// `ALLOW_UNTIL_FAIL_FAST=1`.
+pub(crate) fn fail_fast() -> bool {
+    static FAIL_FAST: OnceLock<bool> = OnceLock::new();
+
+    *FAIL_FAST.get_or_init(|| tracked::var(FAIL_FAST_VAR).is_ok_and(|v| v.trim() == "1"))
+}
+
+pub(crate) const MAX_VAR: &str = "ALLOW_UNTIL_MAX";
+
+/// Reads [`MAX_VAR`], the number of annotations a crate may contain.
+pub(crate) fn max() -> Result<Option<usize>, &'static str> {
+    static MAX: OnceLock<Result<Option<usize>, String>> = OnceLock::new();
+
+    MAX.get_or_init(|| match tracked::var(MAX_VAR) {
+        Ok(v) => v.trim().parse().map(Some).map_err(|_| {
+            format!(
+                "ignoring invalid `{}` value `{}`, expected a number",
+                MAX_VAR, v
+            )
+        }),
+        Err(_) => Ok(None),
+    })
+    .as_ref()
+    .map(|m| *m)
+    .map_err(|e| e.as_str())
+}
+
+pub(crate) const SIMULATE_VAR: &str = "ALLOW_UNTIL_SIMULATE";
+
+/// The versions listed in [`SIMULATE_VAR`], separated by commas, along with the entries that
+/// aren't valid semver versions.
+pub(crate) fn simulated() -> (&'static [Version], &'static [String]) {
+    static SIMULATED: OnceLock<(Vec<Version>, Vec<String>)> = OnceLock::new();
+
+    let (versions, invalid) = SIMULATED.get_or_init(|| {
+        let mut versions = Vec::new();
+        let mut invalid = Vec::new();
+
+        for entry in tracked::var(SIMULATE_VAR).unwrap_or_default().split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            match Version::parse(entry) {
+                Ok(v) => versions.push(v),
+                Err(_) => invalid.push(entry.to_string()),
+            }
+        }
+
+        (versions, invalid)
+    });
+
+    (versions, invalid)
+}
+
+/// Returns `true` the first time it is called with `key`, so build-wide messages are only emitted
+/// once per compilation. Never `true` while diagnostics are silenced, which leaves the message to
+/// the next annotation that isn't silent.
+pub(crate) fn once(key: &'static str) -> bool {
+    if crate::diagnostic::silenced() {
+        return false;
+    }
+
+    static SEEN: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    SEEN.get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key)
+}
+
+pub(crate) const EXTEND_VAR: &str = "ALLOW_UNTIL_EXTEND";
+
+/// Reads [`EXTEND_VAR`], which overrides `extend_all_by` with a distance such as `1 major`, or
+/// turns it off with `none`.
+pub(crate) fn extend() -> Result<Option<Option<Distance>>, &'static str> {
+    static EXTEND: OnceLock<Result<Option<Option<Distance>>, String>> = OnceLock::new();
+
+    EXTEND
+        .get_or_init(|| match tracked::var(EXTEND_VAR) {
+            Ok(v) => match v.trim() {
+                "" => Ok(None),
+                "none" => Ok(Some(None)),
+                v => Distance::parse(v).map(|by| Some(Some(by))).map_err(|e| {
+                    format!("ignoring invalid `{}` value: {}, or `none`", EXTEND_VAR, e)
+                }),
+            },
+            Err(_) => Ok(None),
+        })
+        .as_ref()
+        .map(|by| *by)
+        .map_err(|e| e.as_str())
+}
+
+pub(crate) const WARN_INTERVAL_VAR: &str = "ALLOW_UNTIL_WARN_INTERVAL";
+
+/// Reads [`WARN_INTERVAL_VAR`], the number of seconds for which a warning about an annotation is
+/// held back once it was emitted.
+pub(crate) fn warn_interval() -> Result<Option<u64>, &'static str> {
+    static INTERVAL: OnceLock<Result<Option<u64>, String>> = OnceLock::new();
+
+    INTERVAL
+        .get_or_init(|| match tracked::var(WARN_INTERVAL_VAR) {
+            Ok(v) => v.trim().parse().map(Some).map_err(|_| {
+                format!(
+                    "ignoring invalid `{}` value `{}`, expected a number of seconds",
+                    WARN_INTERVAL_VAR, v
+                )
+            }),
+            Err(_) => Ok(None),
+        })
+        .as_ref()
+        .map(|i| *i)
+        .map_err(|e| e.as_str())
+}
+
+/// Set to make the macros panic, to test how internal errors are reported. Only read with the
+/// private `__test-panic` feature, which the tests enable.
+#[cfg(feature = "__test-panic")]
+pub(crate) const TEST_PANIC_VAR: &str = "ALLOW_UNTIL_TEST_PANIC";
+
+pub(crate) fn test_panic() {
+    #[cfg(feature = "__test-panic")]
+    if tracked::var_os(TEST_PANIC_VAR).is_some() {
+        panic!("`{}` is set", TEST_PANIC_VAR);
+    }
+}
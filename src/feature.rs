@@ -0,0 +1,85 @@
+//! `superseded_by_feature`, checked against the features of the package's `Cargo.toml`, since a
+//! wrong feature name in an expiry error sends users looking for something that doesn't exist.
+
+use proc_macro::Span;
+
+use crate::{
+    code::Code,
+    diagnostic::{self, Diagnostic},
+    manifest, suggest,
+};
+
+/// Errors at `span` unless `name` is a feature of the package: a key of its `[features]` table, or
+/// an optional dependency that no feature refers to with `dep:`. Nothing is checked without a
+/// manifest.
+pub(crate) fn check(name: &str, span: Span) -> Result<(), Diagnostic> {
+    let Ok(Some(manifest)) = manifest::package() else {
+        return Ok(());
+    };
+    let features = features(&manifest);
+    if features.iter().any(|feature| feature == name) {
+        return Ok(());
+    }
+
+    let names: Vec<_> = features.iter().map(String::as_str).collect();
+    let help = if names.is_empty() {
+        "the package has no features, add it to `[features]` in `Cargo.toml`".into()
+    } else {
+        let available = format!(
+            "the features are {}",
+            names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        match suggest::closest(name, &names) {
+            Some(closest) => format!("did you mean `{}`? {}", closest, available),
+            None => available,
+        }
+    };
+
+    Err(diagnostic::error(
+        span,
+        Code::InvalidInput,
+        format!("the package has no feature `{}`", name),
+    )
+    .help(help))
+}
+
+/// The features of `manifest`, those of `[features]` first.
+fn features(manifest: &toml::Table) -> Vec<String> {
+    let declared = manifest.get("features").and_then(|f| f.as_table());
+    let mut features: Vec<_> = declared
+        .into_iter()
+        .flat_map(|table| table.keys().cloned())
+        .collect();
+
+    // optional dependencies are features of their own unless a feature refers to them with `dep:`
+    let explicit: Vec<_> = declared
+        .into_iter()
+        .flat_map(|table| table.values())
+        .filter_map(|enables| enables.as_array())
+        .flatten()
+        .filter_map(|enabled| enabled.as_str()?.strip_prefix("dep:"))
+        .collect();
+    let targets = manifest
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|targets| targets.values());
+    let dependencies = std::iter::once(manifest.get("dependencies"))
+        .chain(targets.map(|target| target.get("dependencies")))
+        .flatten()
+        .filter_map(|deps| deps.as_table())
+        .flatten()
+        .filter(|(_, dep)| dep.get("optional").and_then(|o| o.as_bool()) == Some(true))
+        .map(|(name, _)| name);
+    for name in dependencies {
+        if !explicit.contains(&name.as_str()) && !features.contains(name) {
+            features.push(name.clone());
+        }
+    }
+
+    features
+}
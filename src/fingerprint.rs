@@ -0,0 +1,65 @@
+//! Fingerprints identifying annotations that have no `id`, such as `au-3f0c8e1d52a9b6e4`, in
+//! diagnostics, reports, baselines and `ALLOW_UNTIL_SUPPRESS`.
+
+use proc_macro::Span;
+
+use crate::{
+    annotation::ParsedAnnotation,
+    code::Code,
+    item::{Identity, Item},
+    registry, warning_level, Diagnostic,
+};
+
+/// The fingerprint of the annotation on `item`.
+pub(crate) fn of(args: &ParsedAnnotation, item: Option<&Item>) -> String {
+    allow_until_core::fingerprint::of(
+        &args.version,
+        args.reason.as_deref(),
+        item.map_or("item", |i| i.kind.as_str()),
+        item.and_then(|i| {
+            i.name
+                .as_deref()
+                .or(i.identity.as_ref().map(Identity::as_str))
+        }),
+        args.generic_default.as_ref().map(|(name, _)| &name[..]),
+    )
+}
+
+/// Records the fingerprint of the annotation at `at`, warning when it has no `id`, its item has a
+/// name, and another annotation of the crate already has the same fingerprint. Returns the
+/// fingerprint.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) -> String {
+    let fingerprint = of(args, item);
+
+    let location = (at.file(), at.line(), at.column());
+    let first = registry::with(|r| {
+        let first = r
+            .fingerprints
+            .entry(fingerprint.clone())
+            .or_insert_with(|| location.clone());
+        (*first != location).then(|| first.clone())
+    });
+
+    // items without a name are told apart by their tokens, so those sharing a fingerprint are
+    // copies of one another, e.g. expanded more than once
+    let named = item.is_some_and(|item| {
+        item.name.is_some() || matches!(item.identity, Some(Identity::Described(_)))
+    });
+    if let (Some((file, line, column)), None, true) = (first, &args.id, named) {
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::FingerprintCollision,
+            format!(
+                "the fingerprint `{}` is already that of another annotation",
+                fingerprint
+            ),
+        )
+        .note(format!("first used at {}:{}:{}", file, line, column))
+        .help(
+            "baselines and `ALLOW_UNTIL_SUPPRESS` can't tell the two apart, give this one an `id`",
+        )
+        .emit();
+    }
+    fingerprint
+}
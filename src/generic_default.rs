@@ -0,0 +1,69 @@
+//! `generic_default`, which schedules the removal of the default of a generic parameter, such as
+//! the `LegacyHasher` of `struct Index<S = LegacyHasher>`, rather than of the item itself.
+
+use crate::{
+    annotation::ParsedAnnotation,
+    code::Code,
+    diagnostic,
+    item::{Generic, Item},
+    suggest,
+};
+
+/// The parameter named by the `generic_default` of the annotation, if it has one. Errors at the
+/// name, and returns `None`, unless the item declares the parameter with a default, so the
+/// annotation can't outlive the refactor it schedules.
+pub(crate) fn check<'a>(args: &ParsedAnnotation, item: Option<&'a Item>) -> Option<&'a Generic> {
+    let (name, span) = args.generic_default.as_ref()?;
+    let described = item.map_or("item".into(), Item::describe);
+    let generics = item.map_or(&[][..], |item| &item.generics);
+
+    let Some(generic) = generics.iter().find(|generic| generic.name == *name) else {
+        let names: Vec<&str> = generics
+            .iter()
+            .map(|generic| generic.name.as_str())
+            .collect();
+        diagnostic::error(
+            *span,
+            Code::InvalidInput,
+            format!("{} has no generic parameter `{}`", described, name),
+        )
+        .help(match suggest::closest(name, &names) {
+            Some(closest) => format!(
+                "did you mean `{}`? `generic_default` must be updated when a parameter is renamed",
+                closest
+            ),
+            None => "remove `generic_default` if the parameter was removed already".into(),
+        })
+        .emit();
+        return None;
+    };
+
+    if generic.kind == "lifetime" {
+        diagnostic::error(
+            *span,
+            Code::InvalidInput,
+            format!(
+                "{} of {} can't have a default",
+                generic.describe(),
+                described
+            ),
+        )
+        .span_note(generic.span, "declared here")
+        .help("`generic_default` names a type or const parameter")
+        .emit();
+        return None;
+    }
+
+    if generic.default.is_none() {
+        diagnostic::error(
+            *span,
+            Code::InvalidInput,
+            format!("{} of {} has no default", generic.describe(), described),
+        )
+        .span_note(generic.span, "declared here")
+        .help("remove `generic_default` if the default was removed already")
+        .emit();
+        return None;
+    }
+    Some(generic)
+}
@@ -0,0 +1,83 @@
+//! GitHub Actions workflow commands, such as `::error file=src/lib.rs,line=3,col=1,title=...::...`,
+//! printed for every error and warning when `ALLOW_UNTIL_GHA=1` is set in a workflow, so they show
+//! up as annotations of the pull request without parsing the compiler's output.
+//!
+//! The commands are printed to stderr, which the runner reads them from like stdout, and the
+//! diagnostics are still emitted as usual.
+
+use std::{io::Write, path::Path, sync::OnceLock};
+
+use proc_macro::Span;
+
+use crate::{diagnostic::Level, tracked};
+
+pub(crate) const GHA_VAR: &str = "ALLOW_UNTIL_GHA";
+
+/// Whether commands were requested with `ALLOW_UNTIL_GHA=1`, in a workflow run, where
+/// `GITHUB_ACTIONS` is `true`.
+fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+
+    *ENABLED.get_or_init(|| {
+        tracked::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true")
+            && tracked::var(GHA_VAR).is_ok_and(|v| v.trim() == "1")
+    })
+}
+
+/// Prints the command annotating `span` with `message`, if requested. Notes and help have no
+/// annotation of their own and are part of the message of the error or warning they belong to.
+pub(crate) fn annotate(level: Level, span: Span, message: &str) {
+    let command = match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help => return,
+    };
+    if !enabled() {
+        return;
+    }
+
+    let line = format!(
+        "::{} file={},line={},col={},title=allow-until::{}\n",
+        command,
+        property(&relative(&span.file())),
+        span.line(),
+        span.column(),
+        data(message)
+    );
+    // a single write, so the commands of crates compiled in parallel don't interleave
+    let _ = std::io::stderr().write_all(line.as_bytes());
+}
+
+/// `file` relative to the root of the repository, which annotations are resolved against.
+fn relative(file: &str) -> String {
+    let file = tracked::var_os("GITHUB_WORKSPACE")
+        .and_then(|root| Path::new(file).strip_prefix(root).ok().map(Path::to_owned))
+        .map_or(file.into(), |p| p.to_string_lossy().into_owned());
+    file.replace('\\', "/")
+}
+
+/// `text` escaped for the message of a command.
+fn data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// `text` escaped for a property of a command, such as `file`.
+fn property(text: &str) -> String {
+    data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            data("100% removed\n= note: see: a, b"),
+            "100%25 removed%0A= note: see: a, b"
+        );
+        assert_eq!(property("C:\\src\\a,b.rs"), "C%3A\\src\\a%2Cb.rs");
+    }
+}
@@ -0,0 +1,97 @@
+//! `allow_until_group! { version = "...", ..., { items } }`, which applies one annotation to each of
+//! the items in the braces, so a block of items removed together only needs one requirement.
+
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree as TT};
+use quote::ToTokens;
+use syn::Attribute;
+
+use crate::{
+    annotation,
+    code::Code,
+    diagnostic::{self, Place},
+    item::{self, Item},
+};
+
+/// Expands `allow_until_group!` to its items, each evaluated against the group's annotation as if
+/// it carried it, except for those with an annotation of their own.
+pub(crate) fn expand(input: TokenStream) -> TokenStream {
+    let mut toks: Vec<_> = input.into_iter().collect();
+    let items = match toks.pop() {
+        Some(TT::Group(group)) if group.delimiter() == Delimiter::Brace => group.stream(),
+        tok => {
+            diagnostic::error(
+                tok.map_or(Span::call_site(), |tok| tok.span()),
+                Code::InvalidInput,
+                "expected the grouped items in braces after the arguments",
+            )
+            .help("write `allow_until_group! { version = \">=2.0\", { fn a() {} struct B; } }`")
+            .emit();
+            return diagnostic::take(Place::Items);
+        }
+    };
+    if matches!(toks.last(), Some(TT::Punct(p)) if p.as_char() == ',') {
+        toks.pop();
+    }
+
+    // the arguments stand in for the attribute, so diagnostics about the annotation point at them
+    let at = match (toks.first(), toks.last()) {
+        (Some(first), Some(last)) => {
+            diagnostic::join(first.span(), last.span()).unwrap_or(first.span())
+        }
+        _ => Span::call_site(),
+    };
+    let args = match annotation::parse(toks.into_iter().collect::<TokenStream>().into(), at) {
+        Ok(args) => args,
+        Err(e) => {
+            e.emit();
+            return diagnostic::take(Place::Items)
+                .into_iter()
+                .chain(items)
+                .collect();
+        }
+    };
+    // items that don't parse are left for the compiler to report
+    let Ok(file) = syn::parse::<syn::File>(items.clone()) else {
+        return diagnostic::take(Place::Items)
+            .into_iter()
+            .chain(items)
+            .collect();
+    };
+
+    let mut output = TokenStream::new();
+    for item in file.items {
+        let tokens: TokenStream = item.to_token_stream().into();
+        if annotated(item::attrs(&item)) {
+            output.extend(tokens);
+            continue;
+        }
+
+        let parsed = Item {
+            grouped: true,
+            ..Item::parse(tokens.clone())
+        };
+        crate::emit_error_version_match(args.clone(), at, Some(&parsed));
+        let from = args.deprecate_from.as_ref().map(|(from, _)| from);
+        let tokens = crate::deprecate::added(from, &parsed, crate::doctest::rewritten(tokens));
+        output.extend(crate::with_diagnostics(
+            crate::strip::stripped(tokens),
+            &parsed,
+        ));
+    }
+
+    diagnostic::take(Place::Items)
+        .into_iter()
+        .chain(output)
+        .chain(crate::test_guard::take())
+        .collect()
+}
+
+/// Whether `attrs` include an annotation, which is more specific than the group's.
+pub(crate) fn annotated(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "allow_until")
+    })
+}
@@ -0,0 +1,63 @@
+//! `max_horizon`, the policy that deadlines are plans: an annotation whose requirement first
+//! matches more than `max_horizon` after the current version, such as `>=9.0` in a crate at 1.2, is
+//! effectively never removed.
+
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, config, diagnostic::Diagnostic, item::Item,
+    trace::Trace, version, version::Distance, warning_level,
+};
+
+/// Warns when `max_horizon` is configured and the first version the requirement of the annotation
+/// at `at` matches is more than `max_horizon` after `version`, unless it has `horizon_ok = true`.
+pub(crate) fn check(
+    args: &ParsedAnnotation,
+    item: Option<&Item>,
+    at: Span,
+    version: &Version,
+    trace: &mut Trace,
+) {
+    let Some(max_horizon) = config::get().max_horizon else {
+        return;
+    };
+    let Some(expiry) = version::minimal_version(&args.version) else {
+        return;
+    };
+    if expiry < max_horizon.exceeded_at(version) {
+        return;
+    }
+
+    let distance = Distance::between(version, &expiry).map_or(String::new(), |d| d.releases());
+    if args.horizon_ok {
+        trace.step(|| format!("{} away, beyond `max_horizon`, but `horizon_ok`", distance));
+        return;
+    }
+
+    trace.step(|| format!("{} away, beyond `max_horizon`", distance));
+    let nearer = max_horizon.add_to(version);
+    Diagnostic::spanned(
+        args.version_span,
+        warning_level(at),
+        Code::DistantDeadline,
+        format!(
+            "the requirement `{}` of {} first matches {}, {} from now",
+            args.version,
+            item.map_or("item".into(), Item::describe),
+            expiry,
+            distance
+        ),
+    )
+    .note(format!(
+        "`max_horizon` allows at most {}, so a deadline after {} isn't much of a plan",
+        max_horizon.releases(),
+        nearer
+    ))
+    .help(format!(
+        "aim for a nearer milestone, such as `\">={}\"`, or add `horizon_ok = true` if the \
+         deadline is that far on purpose",
+        nearer
+    ))
+    .emit();
+}
@@ -0,0 +1,103 @@
+//! Lookup of the crate's own published versions in the local registry index cache, to catch
+//! annotations whose requirement is already satisfied by a release on crates.io.
+//!
+//! Only the cache Cargo keeps under `$CARGO_HOME/registry/index` is read, the network is never
+//! touched, so the answer is only as fresh as the last `cargo update` or `cargo publish`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use semver::Version;
+
+use crate::tracked;
+
+pub(crate) const INDEX_CHECK_VAR: &str = "ALLOW_UNTIL_INDEX_CHECK";
+
+/// Whether the check was requested with `ALLOW_UNTIL_INDEX_CHECK=1`.
+pub(crate) fn enabled() -> bool {
+    tracked::var(INDEX_CHECK_VAR).is_ok_and(|v| v.trim() == "1")
+}
+
+/// The versions of the crate being compiled that the cached index lists, read once per
+/// compilation. Returns an error message when the cache can't be read.
+pub(crate) fn published() -> Result<&'static [Version], &'static str> {
+    static PUBLISHED: OnceLock<Result<Vec<Version>, String>> = OnceLock::new();
+
+    PUBLISHED
+        .get_or_init(load)
+        .as_ref()
+        .map(Vec::as_slice)
+        .map_err(String::as_str)
+}
+
+fn load() -> Result<Vec<Version>, String> {
+    let name = tracked::var("CARGO_PKG_NAME")
+        .map_err(|_| "`CARGO_PKG_NAME` is not set".to_string())?
+        .to_lowercase();
+    let index = cargo_home()
+        .ok_or("the Cargo home directory could not be found")?
+        .join("registry")
+        .join("index");
+
+    let registries = std::fs::read_dir(&index)
+        .map_err(|e| format!("failed to read `{}`: {}", index.display(), e))?;
+
+    let mut versions = Vec::new();
+    let mut found = false;
+    for registry in registries.flatten() {
+        let path = registry
+            .path()
+            .join(".cache")
+            .join(prefix(&name))
+            .join(&name);
+
+        tracked::path(&path);
+        if let Ok(contents) = std::fs::read(&path) {
+            found = true;
+            versions.extend(parse(&contents));
+        }
+    }
+
+    if !found {
+        return Err(format!(
+            "no cached index entry for `{}` was found in `{}`",
+            name,
+            index.display()
+        ));
+    }
+
+    versions.sort();
+    versions.dedup();
+    Ok(versions)
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    tracked::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(Path::new(&tracked::var_os("HOME")?).join(".cargo")))
+}
+
+/// The directory the index stores a crate in, e.g. `se/rd/serde` or `3/s/syn`.
+fn prefix(name: &str) -> PathBuf {
+    match name.len() {
+        1 => "1".into(),
+        2 => "2".into(),
+        3 => Path::new("3").join(&name[..1]),
+        _ => Path::new(&name[..2]).join(&name[2..4]),
+    }
+}
+
+/// Extracts the versions from a cache file, which interleaves NUL-terminated version strings with
+/// the JSON index line of each version after a short header. Plain newline separated index files
+/// are accepted too.
+fn parse(contents: &[u8]) -> impl Iterator<Item = Version> + '_ {
+    contents
+        .split(|&b| b == 0 || b == b'\n')
+        .filter(|entry| entry.first() == Some(&b'{'))
+        .filter_map(|entry| {
+            let entry: serde_json::Value = serde_json::from_slice(entry).ok()?;
+            Version::parse(entry.get("vers")?.as_str()?).ok()
+        })
+}
@@ -0,0 +1,80 @@
+//! Validation of the `issue` argument, which must link to the issue tracking a removal: either an
+//! absolute http(s) URL, or a shorthand matching one of the crate's `issue_patterns`. The checks
+//! are `allow-until-core`'s.
+//!
+//! With the `online-checks` feature and `ALLOW_UNTIL_VERIFY_ISSUES=1`, URLs are also requested to
+//! catch issues that don't exist (any more).
+
+pub(crate) use allow_until_core::issue::check;
+
+#[cfg(feature = "online-checks")]
+pub(crate) use online::verify;
+
+#[cfg(feature = "online-checks")]
+mod online {
+    use std::{
+        collections::HashMap,
+        process::Command,
+        sync::{Mutex, OnceLock},
+    };
+
+    use proc_macro::Span;
+
+    use crate::{code::Code, diagnostic, offline, tracked};
+
+    pub(crate) const VERIFY_VAR: &str = "ALLOW_UNTIL_VERIFY_ISSUES";
+
+    /// Whether verification was requested with `ALLOW_UNTIL_VERIFY_ISSUES=1`.
+    fn enabled() -> bool {
+        static ENABLED: OnceLock<bool> = OnceLock::new();
+
+        *ENABLED.get_or_init(|| tracked::var(VERIFY_VAR).is_ok_and(|v| v.trim() == "1"))
+    }
+
+    /// Warns at `span` when requesting the URL `issue` answers 404. Network errors and other
+    /// statuses are ignored, the build never depends on the tracker being reachable.
+    pub(crate) fn verify(issue: &str, span: Span) {
+        static STATUSES: Mutex<Option<HashMap<String, Option<u16>>>> = Mutex::new(None);
+
+        if !enabled()
+            || !issue.contains("://")
+            || !offline::policy(span).allows(offline::Source::IssueTracker, span)
+        {
+            return;
+        }
+
+        let status = *STATUSES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(HashMap::new)
+            .entry(issue.into())
+            .or_insert_with(|| status(issue));
+
+        if status == Some(404) {
+            diagnostic::warning(
+                span,
+                Code::IssueNotFound,
+                format!("the issue `{}` was not found", issue),
+            )
+            .note(format!(
+                "requesting it answered 404, as `{}` is set",
+                VERIFY_VAR
+            ))
+            .help("check the URL for typos, or update it if the issue moved")
+            .emit();
+        }
+    }
+
+    /// The status `curl` gets for a HEAD request of `url`, following redirects.
+    fn status(url: &str) -> Option<u16> {
+        let output = Command::new("curl")
+            .args(["--head", "--silent", "--location", "--max-time", "5"])
+            .args(["--output", if cfg!(windows) { "NUL" } else { "/dev/null" }])
+            .args(["--write-out", "%{http_code}", "--"])
+            .arg(url)
+            .output()
+            .ok()?;
+
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+}
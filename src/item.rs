@@ -0,0 +1,659 @@
+//! Classification of the item an `allow_until` attribute is attached to, so diagnostics can
+//! name it and, for FFI items, the symbol it exports.
+
+use proc_macro::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use semver::Version;
+use syn::{
+    ext::IdentExt, punctuated::Punctuated, spanned::Spanned, Attribute, Expr, ForeignItem,
+    GenericParam, Generics, Lit, Meta, Token, TraitItem,
+};
+
+use crate::{diagnostic, version};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Struct,
+    Enum,
+    Union,
+    Fn,
+    Const,
+    Static,
+    TypeAlias,
+    AssociatedType,
+    Trait,
+    Mod,
+    ExternBlock,
+    Field,
+    Variant,
+    Unknown,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Struct => "struct",
+            Kind::Enum => "enum",
+            Kind::Union => "union",
+            Kind::Fn => "fn",
+            Kind::Const => "const",
+            Kind::Static => "static",
+            Kind::TypeAlias => "type alias",
+            Kind::AssociatedType => "associated type",
+            Kind::Trait => "trait",
+            Kind::Mod => "module",
+            Kind::ExternBlock => "extern block",
+            Kind::Field => "field",
+            Kind::Variant => "variant",
+            Kind::Unknown => "item",
+        }
+    }
+}
+
+/// The visibility an item is declared with. This is syntactic: a `pub` item in a private module,
+/// or a private item re-exported with `pub use`, is classified by its own declaration.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Visibility {
+    /// `pub`.
+    Public,
+    /// `pub(crate)`.
+    Crate,
+    /// `pub(super)`, `pub(self)` or `pub(in path)`.
+    Restricted,
+    /// No visibility.
+    Private,
+}
+
+impl Visibility {
+    pub fn of(vis: &syn::Visibility) -> Visibility {
+        match vis {
+            syn::Visibility::Public(_) => Visibility::Public,
+            syn::Visibility::Restricted(r) if r.in_token.is_none() && r.path.is_ident("crate") => {
+                Visibility::Crate
+            }
+            syn::Visibility::Restricted(_) => Visibility::Restricted,
+            syn::Visibility::Inherited => Visibility::Private,
+        }
+    }
+
+    /// The visibility as written, e.g. "`pub(crate)`", or "private".
+    pub fn describe(self) -> &'static str {
+        match self {
+            Visibility::Public => "`pub`",
+            Visibility::Crate => "`pub(crate)`",
+            Visibility::Restricted => "restricted",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+/// A linker-visible symbol defined or imported by an item.
+#[derive(Clone)]
+pub(crate) struct Symbol {
+    pub name: String,
+    /// The calling convention, for functions.
+    pub abi: Option<String>,
+    /// Whether the symbol comes from an `extern` block rather than being exported.
+    pub imported: bool,
+}
+
+impl Symbol {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} symbol `{}`{}",
+            if self.imported {
+                "imported"
+            } else {
+                "exported"
+            },
+            self.name,
+            self.abi
+                .as_ref()
+                .map_or(String::new(), |abi| format!(" ({} ABI)", abi))
+        )
+    }
+}
+
+/// A generic parameter of an item, as named by `generic_default`.
+#[derive(Clone)]
+pub(crate) struct Generic {
+    /// The name, without the `'` of a lifetime.
+    pub name: String,
+    /// "type parameter", "lifetime" or "const parameter".
+    pub kind: &'static str,
+    /// The span of the name.
+    pub span: Span,
+    /// The span of the default, such as the `LegacyHasher` of `S = LegacyHasher`.
+    pub default: Option<Span>,
+    /// The span from the `=` to the end of the default, with the `nightly` feature.
+    pub default_extent: Option<Span>,
+}
+
+impl Generic {
+    /// A short description such as "type parameter `S`", or "lifetime `'a`".
+    pub fn describe(&self) -> String {
+        match self.kind {
+            "lifetime" => format!("lifetime `'{}`", self.name),
+            kind => format!("{} `{}`", kind, self.name),
+        }
+    }
+}
+
+/// The generic parameters declared by `generics`, in order.
+fn generics(generics: &Generics) -> Vec<Generic> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(t) => Generic {
+                name: t.ident.unraw().to_string(),
+                kind: "type parameter",
+                span: t.ident.span().unwrap(),
+                default: t.default.as_ref().map(|ty| ty.span().unwrap()),
+                default_extent: t.default.as_ref().and_then(|ty| {
+                    let eq = &t.eq_token;
+                    diagnostic::extent(quote!(#eq #ty).into())
+                }),
+            },
+            GenericParam::Const(c) => Generic {
+                name: c.ident.unraw().to_string(),
+                kind: "const parameter",
+                span: c.ident.span().unwrap(),
+                default: c.default.as_ref().map(|expr| expr.span().unwrap()),
+                default_extent: c.default.as_ref().and_then(|expr| {
+                    let eq = &c.eq_token;
+                    diagnostic::extent(quote!(#eq #expr).into())
+                }),
+            },
+            GenericParam::Lifetime(l) => Generic {
+                name: l.lifetime.ident.unraw().to_string(),
+                kind: "lifetime",
+                span: l.lifetime.span().unwrap(),
+                default: None,
+                default_extent: None,
+            },
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+pub(crate) struct Item {
+    pub kind: Kind,
+    pub name: Option<String>,
+    /// What the annotation's `display_name` calls the item instead of its kind and name.
+    pub display_name: Option<String>,
+    pub symbols: Vec<Symbol>,
+    /// The target type, for type aliases.
+    pub aliased: Option<String>,
+    /// The declared visibility, unknown for items that have none of their own, such as trait
+    /// items, extern blocks and impls.
+    pub visibility: Option<Visibility>,
+    /// Whether the item can only be at module level, e.g. next to a generated test function, which
+    /// rules out anything that may be an associated item.
+    pub free: bool,
+    /// The span of the item's name, or of the type of a tuple field.
+    pub span: Option<Span>,
+    /// The span of the whole item, from its doc comments and attributes to its end, when it was
+    /// written by the user rather than generated by a macro. Only known with the `nightly` feature.
+    pub extent: Option<Span>,
+    /// Whether the item is in an `allow_until_group!`, or listed by an `allow_members_until`, whose
+    /// annotation isn't attached to it, so the code to remove is the item alone.
+    pub grouped: bool,
+    /// The version of its `#[deprecated(since = "...")]` attribute, if it parses as one.
+    pub deprecated_since: Option<Version>,
+    /// Whether the item has a `#[deprecated]` attribute, possibly within a `#[cfg_attr(...)]`.
+    pub deprecated: bool,
+    /// Whether the item is a function with a `#[test]` attribute, or one such as `#[tokio::test]`.
+    pub test: bool,
+    /// The generic parameters of the item, if it can have any.
+    pub generics: Vec<Generic>,
+    /// What tells the item apart in its fingerprint when it has no name.
+    pub identity: Option<Identity>,
+}
+
+/// What identifies an item without a name of its own, such as an impl block.
+#[derive(Clone)]
+pub(crate) enum Identity {
+    /// The name of a `macro_rules!`, the path of a `use`, or the trait and type of an impl.
+    Described(String),
+    /// Its tokens, for items with nothing else to tell them apart.
+    Tokens(String),
+}
+
+impl Identity {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Identity::Described(s) | Identity::Tokens(s) => s,
+        }
+    }
+}
+
+impl Item {
+    fn new(kind: Kind, ident: Option<&syn::Ident>) -> Item {
+        Item {
+            kind,
+            name: ident.map(|i| i.unraw().to_string()),
+            display_name: None,
+            symbols: Vec::new(),
+            aliased: None,
+            visibility: None,
+            free: false,
+            span: ident.map(|i| i.span().unwrap()),
+            extent: None,
+            grouped: false,
+            deprecated_since: None,
+            deprecated: false,
+            test: false,
+            generics: Vec::new(),
+            identity: None,
+        }
+    }
+
+    /// A short description such as "struct `Foo`", or just "item" when nothing better is known.
+    /// The `display_name` of the annotation replaces it.
+    pub fn describe(&self) -> String {
+        match &self.display_name {
+            Some(display_name) => display_name.clone(),
+            None => self.identify(),
+        }
+    }
+
+    /// The description of the item by its kind and name alone, whatever its `display_name`.
+    pub fn identify(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} `{}`", self.kind.as_str(), name),
+            None => self.kind.as_str().into(),
+        }
+    }
+
+    /// A field or variant of a derive input, `parent` being the path of the type (or variant) it
+    /// belongs to and `name` its name, or its position for tuple fields. `tokens` are those of the
+    /// member and the comma after it, which are removed together.
+    pub fn member(
+        kind: Kind,
+        parent: &str,
+        name: impl std::fmt::Display,
+        visibility: Visibility,
+        span: Span,
+        tokens: impl ToTokens,
+    ) -> Item {
+        Item {
+            name: Some(format!("{}::{}", parent, name)),
+            visibility: Some(visibility),
+            // the derive's output is next to the type
+            free: true,
+            span: Some(span),
+            extent: diagnostic::extent(tokens.into_token_stream().into()),
+            ..Item::new(kind, None)
+        }
+    }
+
+    /// Classifies the item an attribute macro is applied to, which may be an associated item.
+    pub fn parse(stream: TokenStream) -> Item {
+        let item = match syn::parse::<syn::Item>(stream.clone()) {
+            Ok(syn::Item::Verbatim(_)) | Err(_) => match syn::parse::<TraitItem>(stream.clone()) {
+                Ok(item) => Item {
+                    deprecated_since: deprecated_since(trait_item_attrs(&item)),
+                    deprecated: is_deprecated(trait_item_attrs(&item)),
+                    generics: trait_item_generics(&item).map_or(Vec::new(), generics),
+                    ..Item::associated(&item)
+                },
+                Err(_) => Item::new(Kind::Unknown, None),
+            },
+            Ok(item) => Item {
+                visibility: declared(&item).map(Visibility::of),
+                free: free(&item),
+                deprecated_since: deprecated_since(attrs(&item)),
+                deprecated: is_deprecated(attrs(&item)),
+                test: matches!(item, syn::Item::Fn(_)) && is_test(attrs(&item)),
+                generics: item_generics(&item).map_or(Vec::new(), generics),
+                ..Item::from_syn(&item)
+            },
+        };
+        // the tokens only identify what has no name and nothing else to tell it apart
+        let identity = match item.identity {
+            None if item.name.is_none() => {
+                Some(Identity::Tokens(normalized(stream.clone().into())))
+            }
+            identity => identity,
+        };
+        Item {
+            extent: diagnostic::extent(stream),
+            identity,
+            ..item
+        }
+    }
+
+    fn from_syn(item: &syn::Item) -> Item {
+        match item {
+            syn::Item::Struct(s) => Item::new(Kind::Struct, Some(&s.ident)),
+            syn::Item::Enum(e) => Item::new(Kind::Enum, Some(&e.ident)),
+            syn::Item::Union(u) => Item::new(Kind::Union, Some(&u.ident)),
+            syn::Item::Trait(t) => Item::new(Kind::Trait, Some(&t.ident)),
+            syn::Item::Mod(m) => Item::new(Kind::Mod, Some(&m.ident)),
+            syn::Item::Const(c) => Item::new(Kind::Const, Some(&c.ident)),
+            syn::Item::Type(t) => Item {
+                aliased: Some(t.ty.to_token_stream().to_string()),
+                ..Item::new(Kind::TypeAlias, Some(&t.ident))
+            },
+            syn::Item::Static(s) => {
+                let mut item = Item::new(Kind::Static, Some(&s.ident));
+                if let Some(symbol) = exported_name(&s.attrs, &s.ident) {
+                    item.symbols.push(Symbol {
+                        name: symbol,
+                        abi: None,
+                        imported: false,
+                    });
+                }
+                item
+            }
+            syn::Item::Fn(f) => {
+                let mut item = Item::new(Kind::Fn, Some(&f.sig.ident));
+                if let Some(symbol) = exported_name(&f.attrs, &f.sig.ident) {
+                    item.symbols.push(Symbol {
+                        name: symbol,
+                        abi: Some(f.sig.abi.as_ref().map_or("Rust".into(), abi)),
+                        imported: false,
+                    });
+                }
+                item
+            }
+            syn::Item::ForeignMod(m) => Item {
+                symbols: m
+                    .items
+                    .iter()
+                    .filter_map(|item| foreign_symbol(item, abi(&m.abi)))
+                    .collect(),
+                ..Item::new(Kind::ExternBlock, None)
+            },
+            syn::Item::Macro(m) => Item {
+                identity: m
+                    .ident
+                    .as_ref()
+                    .map(|ident| Identity::Described(format!("macro_rules! {}", ident.unraw()))),
+                ..Item::new(Kind::Unknown, None)
+            },
+            syn::Item::Use(u) => Item {
+                identity: Some(Identity::Described(format!(
+                    "use {}",
+                    normalized(u.tree.to_token_stream())
+                ))),
+                ..Item::new(Kind::Unknown, None)
+            },
+            syn::Item::Impl(i) => {
+                let ty = normalized(i.self_ty.to_token_stream());
+                let described = match &i.trait_ {
+                    Some((bang, path, _)) => format!(
+                        "impl {}{} for {}",
+                        if bang.is_some() { "!" } else { "" },
+                        normalized(path.to_token_stream()),
+                        ty
+                    ),
+                    None => format!("impl {}", ty),
+                };
+                Item {
+                    identity: Some(Identity::Described(described)),
+                    ..Item::new(Kind::Unknown, None)
+                }
+            }
+            _ => Item::new(Kind::Unknown, None),
+        }
+    }
+
+    /// Items that only parse as trait items, such as functions without a body.
+    fn associated(item: &TraitItem) -> Item {
+        match item {
+            TraitItem::Fn(f) => Item::new(Kind::Fn, Some(&f.sig.ident)),
+            TraitItem::Const(c) => Item::new(Kind::Const, Some(&c.ident)),
+            TraitItem::Type(t) => Item::new(Kind::AssociatedType, Some(&t.ident)),
+            _ => Item::new(Kind::Unknown, None),
+        }
+    }
+}
+
+/// Whether `item` can't be an associated item. Functions can be, unless their signature rules it
+/// out by having no `self` parameter and not mentioning `Self`.
+fn free(item: &syn::Item) -> bool {
+    match item {
+        syn::Item::Fn(f) => f.sig.receiver().is_none() && !mentions_self(f.sig.to_token_stream()),
+        syn::Item::Struct(_)
+        | syn::Item::Enum(_)
+        | syn::Item::Union(_)
+        | syn::Item::Static(_)
+        | syn::Item::Trait(_)
+        | syn::Item::Mod(_)
+        | syn::Item::ForeignMod(_)
+        | syn::Item::Impl(_)
+        | syn::Item::Use(_)
+        | syn::Item::ExternCrate(_)
+        | syn::Item::TraitAlias(_) => true,
+        // `macro_rules!`, unlike macro calls, which may be among associated items
+        syn::Item::Macro(m) => m.ident.is_some(),
+        _ => false,
+    }
+}
+
+/// `tokens` separated by single spaces, whatever their formatting in the source and however the
+/// compiler prints them.
+fn normalized(tokens: proc_macro2::TokenStream) -> String {
+    let mut out = Vec::new();
+    for tok in tokens {
+        match tok {
+            proc_macro2::TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    proc_macro2::Delimiter::Parenthesis => ("(", ")"),
+                    proc_macro2::Delimiter::Brace => ("{", "}"),
+                    proc_macro2::Delimiter::Bracket => ("[", "]"),
+                    proc_macro2::Delimiter::None => ("", ""),
+                };
+                let inner = normalized(group.stream());
+                out.push(format!("{}{}{}", open, inner, close));
+            }
+            tok => out.push(tok.to_string()),
+        }
+    }
+    out.join(" ")
+}
+
+/// Whether `tokens` use `Self`, which only associated items can.
+pub(crate) fn mentions_self(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|tok| match tok {
+        proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+        proc_macro2::TokenTree::Group(group) => mentions_self(group.stream()),
+        _ => false,
+    })
+}
+
+/// The visibility `item` is declared with, if it can have one.
+fn declared(item: &syn::Item) -> Option<&syn::Visibility> {
+    match item {
+        syn::Item::Const(i) => Some(&i.vis),
+        syn::Item::Enum(i) => Some(&i.vis),
+        syn::Item::ExternCrate(i) => Some(&i.vis),
+        syn::Item::Fn(i) => Some(&i.vis),
+        syn::Item::Mod(i) => Some(&i.vis),
+        syn::Item::Static(i) => Some(&i.vis),
+        syn::Item::Struct(i) => Some(&i.vis),
+        syn::Item::Trait(i) => Some(&i.vis),
+        syn::Item::TraitAlias(i) => Some(&i.vis),
+        syn::Item::Type(i) => Some(&i.vis),
+        syn::Item::Union(i) => Some(&i.vis),
+        syn::Item::Use(i) => Some(&i.vis),
+        _ => None,
+    }
+}
+
+/// The attributes of `item`, if it can have any.
+pub(crate) fn attrs(item: &syn::Item) -> &[Attribute] {
+    match item {
+        syn::Item::Const(i) => &i.attrs,
+        syn::Item::Enum(i) => &i.attrs,
+        syn::Item::ExternCrate(i) => &i.attrs,
+        syn::Item::Fn(i) => &i.attrs,
+        syn::Item::ForeignMod(i) => &i.attrs,
+        syn::Item::Impl(i) => &i.attrs,
+        syn::Item::Macro(i) => &i.attrs,
+        syn::Item::Mod(i) => &i.attrs,
+        syn::Item::Static(i) => &i.attrs,
+        syn::Item::Struct(i) => &i.attrs,
+        syn::Item::Trait(i) => &i.attrs,
+        syn::Item::TraitAlias(i) => &i.attrs,
+        syn::Item::Type(i) => &i.attrs,
+        syn::Item::Union(i) => &i.attrs,
+        syn::Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+/// The generic parameters of `item`, if it can have any.
+fn item_generics(item: &syn::Item) -> Option<&Generics> {
+    match item {
+        syn::Item::Enum(i) => Some(&i.generics),
+        syn::Item::Fn(i) => Some(&i.sig.generics),
+        syn::Item::Impl(i) => Some(&i.generics),
+        syn::Item::Struct(i) => Some(&i.generics),
+        syn::Item::Trait(i) => Some(&i.generics),
+        syn::Item::TraitAlias(i) => Some(&i.generics),
+        syn::Item::Type(i) => Some(&i.generics),
+        syn::Item::Union(i) => Some(&i.generics),
+        _ => None,
+    }
+}
+
+fn trait_item_generics(item: &TraitItem) -> Option<&Generics> {
+    match item {
+        TraitItem::Fn(i) => Some(&i.sig.generics),
+        TraitItem::Type(i) => Some(&i.generics),
+        _ => None,
+    }
+}
+
+fn trait_item_attrs(item: &TraitItem) -> &[Attribute] {
+    match item {
+        TraitItem::Const(i) => &i.attrs,
+        TraitItem::Fn(i) => &i.attrs,
+        TraitItem::Type(i) => &i.attrs,
+        TraitItem::Macro(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+/// The version in the `since` of a `#[deprecated(...)]` among `attrs`.
+pub(crate) fn deprecated_since(attrs: &[Attribute]) -> Option<Version> {
+    let attr = attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("deprecated"))?;
+    let mut since = None;
+    // other keys such as `note` are skipped, as is the whole attribute if it doesn't parse
+    let _ = attr.parse_nested_meta(|meta| {
+        let value = meta.value()?.parse::<syn::LitStr>()?;
+        if meta.path.is_ident("since") {
+            since = version::parse_lenient(&value.value()).ok();
+        }
+        Ok(())
+    });
+    since
+}
+
+/// Whether `attrs` have a `#[deprecated]`, or a `#[cfg_attr(...)]` applying one. The compiler
+/// expands `cfg_attr` before attribute macros, but not in the items of `allow_until_group!`,
+/// where its predicate isn't evaluated.
+pub(crate) fn is_deprecated(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| deprecates(&attr.meta))
+}
+
+fn deprecates(meta: &Meta) -> bool {
+    if meta.path().is_ident("deprecated") {
+        return true;
+    }
+    match meta {
+        // the predicate comes first, then the attributes it applies
+        Meta::List(list) if list.path.is_ident("cfg_attr") => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .is_ok_and(|metas| metas.iter().skip(1).any(deprecates)),
+        _ => false,
+    }
+}
+
+/// Whether `attrs` have a `#[test]`, or another attribute whose path ends in `test`.
+fn is_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "test")
+    })
+}
+
+/// The calling convention named by an `extern` keyword, which defaults to C.
+fn abi(abi: &syn::Abi) -> String {
+    abi.name.as_ref().map_or("C".into(), |name| name.value())
+}
+
+/// The symbol a function or static in an `extern` block imports.
+fn foreign_symbol(item: &ForeignItem, abi: String) -> Option<Symbol> {
+    let (attrs, ident, is_fn) = match item {
+        ForeignItem::Fn(f) => (&f.attrs, &f.sig.ident, true),
+        ForeignItem::Static(s) => (&s.attrs, &s.ident, false),
+        // edition 2024 items marked `safe`, which syn leaves unparsed
+        ForeignItem::Verbatim(tokens) => {
+            let tokens = tokens
+                .clone()
+                .into_iter()
+                .filter(|tok| !matches!(tok, proc_macro2::TokenTree::Ident(i) if i == "safe"));
+            let item = syn::parse2::<ForeignItem>(tokens.collect()).ok()?;
+            return match item {
+                ForeignItem::Verbatim(_) => None,
+                item => foreign_symbol(&item, abi),
+            };
+        }
+        _ => return None,
+    };
+
+    Some(Symbol {
+        name: attrs
+            .iter()
+            .find_map(|attr| string_attr(attr, "link_name"))
+            .unwrap_or_else(|| ident.unraw().to_string()),
+        abi: is_fn.then_some(abi),
+        imported: true,
+    })
+}
+
+/// The symbol name set by `#[no_mangle]` or `#[export_name = "..."]`, if any.
+fn exported_name(attrs: &[Attribute], ident: &syn::Ident) -> Option<String> {
+    attrs
+        .iter()
+        .find_map(|attr| string_attr(attr, "export_name"))
+        .or_else(|| {
+            attrs
+                .iter()
+                .any(|attr| {
+                    unwrap_unsafe(&attr.meta).is_some_and(|m| m.path().is_ident("no_mangle"))
+                })
+                .then(|| ident.unraw().to_string())
+        })
+}
+
+/// The value of a `#[name = "..."]` attribute.
+fn string_attr(attr: &Attribute, name: &str) -> Option<String> {
+    match unwrap_unsafe(&attr.meta)? {
+        Meta::NameValue(nv) if nv.path.is_ident(name) => match &nv.value {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The attribute inside `#[unsafe(...)]`, which edition 2024 requires for `no_mangle` and
+/// `export_name`, or the attribute itself.
+fn unwrap_unsafe(meta: &Meta) -> Option<Meta> {
+    match meta {
+        Meta::List(list) if list.path.is_ident("unsafe") => list.parse_args().ok(),
+        meta => Some(meta.clone()),
+    }
+}
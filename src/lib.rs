@@ -9,6 +9,7 @@
 //! [docs-rs]: https://img.shields.io/badge/docs.rs-66c2a5?style=for-the-badge&labelColor=555555&logo=docs.rs
 //!
 //! ```rust
+//! # use allow_until::allow_until;
 //! #[allow_until(version = ">= 1.0.x", reason = "struct is deprecated from version 1.0.x onwards")]
 //! struct MyStruct {
 //!     //....
@@ -16,6 +17,7 @@
 //! ```
 //! Or with the derive macro:
 //! ```rust
+//! # use allow_until::AllowUntil;
 //! #[derive(AllowUntil)]
 //! struct MyStruct {
 //!     #[allow_until(version = ">= 1.0.x", reason = "member is deprecated from version 1.0.x onwards")]
@@ -23,165 +25,1437 @@
 //! }
 //! ```
 
-#![feature(proc_macro_diagnostic, proc_macro_span)]
+#![cfg_attr(
+    allow_until_nightly,
+    feature(
+        proc_macro_diagnostic,
+        proc_macro_span,
+        proc_macro_tracked_env,
+        proc_macro_tracked_path
+    )
+)]
 
-use proc_macro::{TokenTree as TT, *};
-use semver::{Version, VersionReq};
+mod annotation;
+mod attr_until;
+mod baseline;
+mod blame;
+mod branch;
+mod cargo;
+mod changelog;
+mod changes;
+mod config;
+mod context;
+mod default_body;
+mod deprecate;
+mod diagnostic;
+mod doc_cfg;
+mod doctest;
+mod enforce;
+mod env;
+mod feature;
+mod fingerprint;
+mod generic_default;
+mod github;
+mod group;
+mod horizon;
+#[cfg(feature = "publish-check")]
+mod index;
+mod issue;
+mod item;
+mod link;
+mod lint;
+mod lock;
+mod manifest;
+mod members;
+mod notice;
+mod offline;
+mod pending;
+mod public_api;
+mod reason;
+mod registry;
+mod rename;
+mod report;
+mod runtime;
+mod runtime_check;
+mod sarif;
+mod schedule;
+mod section;
+mod self_test;
+mod serde_keys;
+mod stale;
+mod strip;
+mod test_guard;
+mod throttle;
+mod tool;
+mod trace;
+mod tracked;
+mod version;
+mod watch;
 
-struct Args {
-    pub version: VersionReq,
-    pub reason: Option<String>,
+use allow_until_core::{args, code, message, severity::OnExpiry, suggest};
+use allow_until_report::AnnotationKind;
+use annotation::ParsedAnnotation;
+use code::Code;
+use config::{Docsrs, PublicOnly, Severity};
+use diagnostic::{Diagnostic, Level, Place};
+use item::{Item, Kind, Visibility};
+use proc_macro::{Delimiter, Group, Span, TokenStream, TokenTree as TT};
+use syn::{ext::IdentExt, spanned::Spanned, Attribute, Data, DeriveInput, Fields, Meta};
+use version::{Distance, Unit, VersionSource};
+
+/// Errors when `test_guard` is set on an item that may be an associated item, where the test
+/// couldn't be generated. Returns whether the test can be generated.
+fn check_test_guard(at: Span, item: Option<&Item>) -> bool {
+    match item {
+        Some(item) if item.free => true,
+        _ => {
+            diagnostic::error(
+                at,
+                Code::InvalidInput,
+                format!(
+                    "`test_guard` can't be used on {}",
+                    item.map_or("item".into(), Item::describe)
+                ),
+            )
+            .note("tests can only be generated next to items that can't be associated items")
+            .help(
+                "use it on free functions, which don't take `self` nor mention `Self`, types, \
+                 statics, traits, modules, fields or variants",
+            )
+            .emit();
+            false
+        }
+    }
+}
+
+/// Errors when `on_expiry = "ignore_test"` is set on anything but a `#[test]` function.
+fn check_on_expiry(span: Span, item: Option<&Item>) -> bool {
+    if item.is_some_and(|item| item.test) {
+        return true;
+    }
+    diagnostic::error(
+        span,
+        Code::InvalidInput,
+        format!(
+            "`on_expiry = \"ignore_test\"` can't be used on {}",
+            item.map_or("item".into(), Item::describe)
+        ),
+    )
+    .help("only `#[test]` functions can be ignored, remove `on_expiry`")
+    .emit();
+    false
 }
 
-fn parse_arguments(args: TokenStream) -> Result<Args, Diagnostic> {
-    let mut toks = args.into_iter().peekable();
+/// Whether warnings produced by this crate are escalated to errors by `ALLOW_UNTIL_DENY`.
+fn denied(at: Span) -> bool {
+    env::deny().unwrap_or_else(|e| {
+        if env::once("deny") {
+            diagnostic::warning(at, Code::InvalidEnv, e).emit();
+        }
+        false
+    })
+}
+
+/// The variable named by the annotation's `escalate_env`, when it is set to anything but an empty
+/// value, which raises the severity of the annotation's diagnostics by one level.
+fn escalated(args: &ParsedAnnotation) -> Option<&str> {
+    args.escalate_env
+        .as_deref()
+        .filter(|var| tracked::var(var).is_ok_and(|value| !value.is_empty()))
+}
+
+/// The note of a diagnostic raised to `level` by the variable `var` of `escalate_env`.
+fn escalation_note(var: &str, level: &str) -> String {
+    format!("raised to {} by `escalate_env`, as `{}` is set", level, var)
+}
 
-    let mut version = None;
-    let mut reason = None;
+fn warning_level(at: Span) -> Level {
+    if denied(at) {
+        Level::Error
+    } else {
+        Level::Warning
+    }
+}
+
+fn emit_error_version_match(args: ParsedAnnotation, at: Span, item: Option<&Item>) {
+    env::test_panic();
+
+    // everything describing the item, from diagnostics to schedules, uses the display name
+    let named = args
+        .display_name
+        .as_ref()
+        .zip(item)
+        .map(|(display_name, item)| Item {
+            display_name: Some(display_name.clone()),
+            ..item.clone()
+        });
+    let item = named.as_ref().or(item);
+
+    let downgrade = env::downgrade().unwrap_or_else(|e| {
+        if env::once("downgrade-invalid") {
+            diagnostic::warning(at, Code::InvalidEnv, e).emit();
+        }
+        None
+    });
+
+    // the variable is more specific than the cfg, which can only ask for warnings, so it wins
+    let downgrade = match downgrade {
+        Some(downgrade) => Some((
+            downgrade,
+            format!("`{}={}`", env::DOWNGRADE_VAR, downgrade.as_str()),
+        )),
+        None => context::disabled_by_cfg().then(|| {
+            (
+                env::Downgrade::Warn,
+                format!("`--cfg {}`", context::DISABLED_CFG),
+            )
+        }),
+    };
+
+    if let Some((downgrade, switch)) = &downgrade {
+        if env::once("downgrade") {
+            let effect = match downgrade {
+                env::Downgrade::Warn => "are only reported as warnings",
+                env::Downgrade::Allow => "are not reported",
+            };
+
+            diagnostic::warning(
+                at,
+                Code::Downgraded,
+                format!("{} is set, expired annotations {}", switch, effect),
+            )
+            .help("only use this to get an emergency release out, and unset it afterwards")
+            .emit();
+        }
+    }
 
-    while let Some(tok) = toks.next() {
-        let ident = match tok {
-            TT::Ident(ident) => ident,
-            t => {
-                return Err(t
-                    .span()
-                    .error("expected ident")
-                    .help("valid arguments are `version` and `reason`"));
+    let downgrade = downgrade
+        .as_ref()
+        .map(|(downgrade, switch)| (*downgrade, switch.as_str()));
+    diagnostic::silence(args.silent, || evaluate(args, at, item, downgrade));
+}
+
+/// The version the annotation at `at` is compared against, from its `version_from`, or else its
+/// `version_source`, or Cargo when it has neither. Without a version nothing is enforced, and why
+/// is traced or reported.
+fn current_version(
+    args: &ParsedAnnotation,
+    at: Span,
+    trace: &mut trace::Trace,
+) -> Option<&'static version::Current> {
+    // the override still takes precedence, as over every other source
+    if let Some((from, span)) = args
+        .version_from
+        .as_ref()
+        .filter(|_| tracked::var_os(version::OVERRIDE_VAR).is_none())
+    {
+        return match version::from_file(from) {
+            Ok(current) => Some(current),
+            Err((code, e)) => {
+                trace.step(|| format!("the version could not be read: {}", e));
+                diagnostic::error(*span, code, e)
+                    .help(format!(
+                        "`version_from` reads `{}` relative to the package root, set `{}` to \
+                         override it",
+                        from.file,
+                        version::OVERRIDE_VAR
+                    ))
+                    .emit();
+                None
             }
         };
+    }
 
-        match toks.next() {
-            Some(TT::Punct(p)) if p.as_char() == '=' => (),
-            Some(t) => return Err(t.span().error("expected `=`")),
-            None => {
-                return Err(Span::call_site()
-                    .error("unexpected end of tokens")
-                    .help("expected `=`"))
+    match resolve_version(args.version_source.unwrap_or(VersionSource::Cargo), at) {
+        Ok(Some(current)) => Some(current),
+        Ok(None) => {
+            trace.step(|| "no version is available, nothing is enforced".into());
+            None
+        }
+        Err(e) => {
+            trace.step(|| format!("the version could not be resolved: {}", e));
+            if env::once("version") {
+                diagnostic::error(at, Code::NoVersion, e)
+                    .help(format!(
+                        "set `{}` to the version annotations should be compared against",
+                        version::OVERRIDE_VAR
+                    ))
+                    .emit();
             }
+            None
         }
+    }
+}
 
-        let lit = match toks.next() {
-            Some(TT::Literal(lit)) => lit,
-            Some(t) => return Err(t.span().error("expected literal")),
-            None => {
-                return Err(Span::call_site()
-                    .error("unexpected end of tokens")
-                    .help("expected literal"))
+/// The version from `source`, with a warning at `at` when git can't provide it and the Cargo
+/// version is used instead.
+fn resolve_version(
+    source: VersionSource,
+    at: Span,
+) -> Result<Option<&'static version::Current>, &'static str> {
+    version::resolve(source, offline::policy(at), |e| {
+        diagnostic::warning(
+            at,
+            Code::VersionFallback,
+            format!("{}, falling back to `CARGO_PKG_VERSION`", e),
+        )
+        .emit()
+    })
+}
+
+/// Shifts the requirement of `args` outward by the extension of `ALLOW_UNTIL_EXTEND` or
+/// `extend_all_by`, unless it opts out with `no_extend`, with a note on the extension when one is
+/// active.
+fn extend(args: &mut ParsedAnnotation, at: Span) -> Option<String> {
+    let configured = || {
+        config::get()
+            .extend_all_by
+            .map(|by| (by, "`extend_all_by`".into()))
+    };
+    let (by, from) = match env::extend() {
+        Ok(Some(by)) => (by?, format!("`{}`", env::EXTEND_VAR)),
+        Ok(None) => configured()?,
+        Err(e) => {
+            if env::once("extend") {
+                diagnostic::warning(at, Code::InvalidEnv, e).emit();
             }
-        };
+            configured()?
+        }
+    };
 
-        match &ident.to_string()[..] {
-            "version" => {
-                let lit_str = lit.to_string();
+    if args.no_extend {
+        return Some(format!(
+            "an extension of {} is active with {}, but `no_extend` keeps the requirement `{}` as \
+             written",
+            by, from, args.version
+        ));
+    }
+    let shifted = version::shifted(&args.version, &by);
+    let original = std::mem::replace(&mut args.version, shifted);
+    Some(format!(
+        "an extension of {} is active with {}, so the requirement `{}` is evaluated as `{}`",
+        by, from, original, args.version
+    ))
+}
 
-                let v = lit_str
-                    .get(1..lit_str.len() - 1)
-                    .ok_or(lit.span().error("expected string literal"))?;
+/// Truncates `version` and the requirement of `args` to its `match_precision`, with a note on the
+/// truncation when it changed either.
+fn truncate(
+    args: &mut ParsedAnnotation,
+    version: &semver::Version,
+) -> (semver::Version, Option<String>) {
+    let precision = args.match_precision.unwrap_or(Unit::Patch);
+    let truncated = version::truncate(version, precision);
+    let req = version::truncate_req(&args.version, precision);
+    if truncated == *version && req == args.version {
+        return (truncated, None);
+    }
 
-                version = Some(
-                    VersionReq::parse(v).map_err(|_| lit.span().error("invalid semver version"))?,
-                );
+    let note = format!(
+        "compared at `match_precision = \"{}\"`, as {} against `{}` rather than {} against `{}`",
+        precision.as_str(),
+        truncated,
+        req,
+        version,
+        args.version
+    );
+    args.version = req;
+    (truncated, Some(note))
+}
+
+/// Evaluates the annotation at `at` against the current version, recording it and reporting it
+/// if it expired.
+fn evaluate(
+    mut args: ParsedAnnotation,
+    at: Span,
+    item: Option<&Item>,
+    downgrade: Option<(env::Downgrade, &str)>,
+) {
+    annotation::check_policy(&args, at, item);
+    let generic = generic_default::check(&args, item);
+    if args.generic_default.is_some() && generic.is_none() {
+        return;
+    }
+    args.fingerprint = Some(fingerprint::check(&args, item, at));
+    link::check(&args, item, at);
+    let test_guard = args.test_guard && check_test_guard(at, item);
+    let ignore_test = match args.on_expiry {
+        Some((OnExpiry::IgnoreTest, span)) => check_on_expiry(span, item),
+        None => false,
+    };
+    simulate(&args, at, item);
+    count(at);
+
+    let mut trace = trace::Trace::new(at);
+
+    let Some(current) = current_version(&args, at, &mut trace) else {
+        return;
+    };
+    let extension = extend(&mut args, at);
+    let (version, truncation) = truncate(&mut args, &current.version);
+    let version = &version;
+    let matched = args.version.matches(version);
+
+    trace.step(|| {
+        format!(
+            "version {} from {}",
+            current.version,
+            current.describe_source()
+        )
+    });
+    if let Some(extension) = &extension {
+        trace.step(|| extension.clone());
+    }
+    if let Some(truncation) = &truncation {
+        trace.step(|| truncation.clone());
+    }
+    trace.step(|| {
+        format!(
+            "requirement `{}` {}",
+            args.version,
+            if matched { "matches" } else { "does not match" }
+        )
+    });
+    // every condition must be satisfied, the Cargo version is only asked for once the rest are
+    let matched = matched
+        && cargo::satisfied(&args, at, &mut trace)
+        && tool::satisfied(&args, at, &mut trace);
+
+    if !branch::applies(&args, at, &mut trace) {
+        trace.decide("skipped, as the annotation is for another branch");
+        return;
+    }
+
+    if let Some(bound) = args.not_before.as_ref().filter(|bound| version < *bound) {
+        trace.step(|| format!("version {} is before `not_before` {}", version, bound));
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::WrongBranch,
+            format!(
+                "annotation appears to be on the wrong branch, as version {} is before its \
+                 `not_before` {}",
+                version, bound
+            ),
+        )
+        .note("the requirement is not evaluated before `not_before`")
+        .help("remove the annotation if it was backported by accident")
+        .emit();
+        return;
+    }
+
+    registry::with(|r| r.evaluated += 1);
+    let window = in_warning_window(&args, version, matched);
+    // decided before the report is written, which records it
+    let stripped = matched && strip::check(&args, item, at);
+    if config::get().always_note {
+        note_allowance(&args, at, item, version, matched, window);
+    }
+    report::record(
+        &args,
+        AnnotationKind::AllowUntil,
+        item,
+        at,
+        version,
+        matched,
+        window,
+    );
+    sarif::record(&args, item, at, version, matched);
+    schedule::record(&args, item, at);
+    section::record(&args);
+    self_test::record(&args, item, at, version, matched, window);
+    pending::record(&args, item, at);
+    baseline::check(&args, at);
+
+    if let Some(id) = &args.id {
+        registry::with(|r| {
+            if !r.ids.contains(id) {
+                r.ids.insert(id.clone());
+            }
+            if matched && !r.expired.contains(id) {
+                r.expired.insert(id.clone());
             }
-            "reason" => {
-                let lit_str = lit.to_string();
+        });
+    }
 
-                let v = lit_str
-                    .get(1..lit_str.len() - 1)
-                    .ok_or(lit.span().error("expected string literal"))?;
+    if stripped {
+        trace.decide("stripped, as `ALLOW_UNTIL_STRIP=1` previews its removal");
+        diagnostic::note(
+            item.and_then(|item| item.span).unwrap_or(at),
+            Code::Expired,
+            format!(
+                "{} is stripped to preview its removal (version {} matches {})",
+                item.map_or("item".into(), Item::describe),
+                version,
+                args.version
+            ),
+        )
+        .emit_visible();
+        return;
+    }
 
-                reason = Some(v.into());
+    // listed by its id, or by its fingerprint, which silences its warnings whether or not it
+    // expired
+    let suppressed = [("id", &args.id), ("fingerprint", &args.fingerprint)]
+        .into_iter()
+        .filter_map(|(what, key)| Some((what, key.as_ref()?)))
+        .find(|(_, key)| env::suppressed_ids().contains(key));
+    if let Some((what, key)) = suppressed {
+        trace.step(|| format!("{} `{}` is listed in `{}`", what, key, env::SUPPRESS_VAR));
+        if matched {
+            registry::with(|r| {
+                if !r.suppressed.contains(key) {
+                    r.suppressed.push(key.clone());
+                }
+            });
+            return;
+        }
+    } else {
+        watch::check(&args, item, at);
+    }
+
+    if !matched {
+        if suppressed.is_some() {
+            trace.step(|| "its warnings are suppressed".into());
+        } else {
+            warn_unmatched(&args, at, item, version, &mut trace);
+        }
+        if let Some(from) = args
+            .doctest_ignore_from
+            .as_ref()
+            .filter(|from| from.matches(version))
+        {
+            trace.step(|| {
+                format!(
+                    "`doctest_ignore_from` `{}` matches, the examples are marked `{}`",
+                    from,
+                    args.doctest_fence.as_str()
+                )
+            });
+            doctest::mark(args.doctest_fence);
+        }
+
+        let expiry = version::minimal_version(&args.version).filter(|v| v > version);
+        registry::with(|r| {
+            r.active += 1;
+            if let Some(expiry) = expiry {
+                if r.next_expiry
+                    .as_ref()
+                    .is_none_or(|(next, _)| expiry < *next)
+                {
+                    let what = match &args.id {
+                        Some(id) => format!("id {}", id),
+                        None => item.map_or("item".into(), Item::describe),
+                    };
+                    r.next_expiry = Some((expiry, what));
+                }
             }
-            _ => {
-                return Err(lit
-                    .span()
-                    .error("unknown argument")
-                    .help("valid arguments are `version` and `reason`"))
+        });
+
+        #[cfg(feature = "publish-check")]
+        check_published(&args.version, at, item);
+        return;
+    }
+
+    changelog::check(&args, item, at);
+    notice::check(&args, item, at);
+
+    let pred = args.version.clone();
+    let reason = args.reason.clone();
+
+    let mut severity = args.severity.unwrap_or(Severity::Error);
+    trace.step(|| format!("severity: {}", severity.as_str()));
+    let enforcement = enforce::severity(&args, at, &mut trace);
+    if let Some((enforced, _)) = &enforcement {
+        severity = *enforced;
+    }
+    if args.warning_requested
+        && !approved(
+            args.approved_by.as_ref(),
+            at,
+            "reporting the expired annotation as a warning with `severity = \"warning\"`",
+        )
+    {
+        severity = Severity::Error;
+        trace.step(|| "`severity = \"warning\"` is not signed off: error".into());
+    }
+    let mut grace_end = None;
+
+    if let Some(grace) = args.grace {
+        if let Some(boundary) = version::minimal_version(&pred) {
+            let end = grace.add_to(&boundary);
+
+            if *version < end {
+                severity = Severity::Warning;
+                trace.step(|| {
+                    format!(
+                        "within the {} grace period ending at {}: warning",
+                        grace, end
+                    )
+                });
+                registry::with(|r| r.in_grace += 1);
+                grace_end = Some((grace, end));
+            } else {
+                trace.step(|| format!("the {} grace period ended at {}", grace, end));
             }
         }
+    }
+
+    // items without a visibility of their own, such as trait items, are always enforced
+    let hidden = args
+        .public_only
+        .zip(item.and_then(|item| item.visibility))
+        .filter(|&(_, vis)| vis != Visibility::Public);
+    match hidden {
+        Some((PublicOnly::Ignore, vis)) => {
+            trace.step(|| format!("`public_only` and the item is {}: ignored", vis.describe()));
+            return;
+        }
+        Some((PublicOnly::Warning, vis)) => {
+            severity = Severity::Warning;
+            trace.step(|| format!("`public_only` and the item is {}: warning", vis.describe()));
+        }
+        None => (),
+    }
+
+    // `public_only` only ever lowers the severity of items outside of the public API
+    let public_api = severity == Severity::Warning
+        && config::get().public_api == Some(Severity::Error)
+        && public_api::check(item, at) == Some(true);
+    if public_api {
+        severity = Severity::Error;
+        trace.step(|| {
+            format!(
+                "`public_api` and the item is listed in `{}`: error",
+                public_api::PUBLIC_API_VAR
+            )
+        });
+    }
+
+    if severity == Severity::Warning && denied(at) {
+        severity = Severity::Error;
+        trace.step(|| format!("`{}` escalates the warning: error", env::DENY_VAR));
+    }
+
+    // the `contexts` table deliberately wins over `ALLOW_UNTIL_DENY`, the downgrade over both
+    match context::detect(args.docsrs) {
+        (context, Some(Docsrs::Ignore)) => {
+            trace.step(|| format!("{}: ignored", context::describe(context)));
+            return;
+        }
+        (context, Some(Docsrs::Severity(s))) => {
+            severity = s;
+            trace.step(|| format!("{}: {}", context::describe(context), s.as_str()));
+        }
+        (_, None) => (),
+    }
+
+    // the annotation's own `escalate_env` wins over the contexts, the emergency downgrade over it
+    let escalation = escalated(&args).filter(|_| severity == Severity::Warning);
+    if let Some(var) = escalation {
+        severity = Severity::Error;
+        trace.step(|| format!("`{}` is set: escalated to an error", var));
+    }
+
+    let downgrade = downgrade.filter(|(_, switch)| {
+        let what = format!("downgrading the expired annotation with {}", switch);
+        let approved = approved(args.approved_by.as_ref(), at, &what);
+        if !approved {
+            trace.step(|| format!("{} is not signed off: ignored", switch));
+        }
+        approved
+    });
+
+    // the emergency downgrade deliberately wins over `ALLOW_UNTIL_DENY`
+    match downgrade {
+        Some((env::Downgrade::Allow, switch)) => {
+            trace.step(|| format!("{}: ignored", switch));
+            return;
+        }
+        Some((env::Downgrade::Warn, switch)) => {
+            severity = Severity::Warning;
+            trace.step(|| format!("{}: warning", switch));
+        }
+        None => (),
+    }
 
-        match toks.peek() {
-            Some(TT::Punct(p)) if p.as_char() == ',' => {
-                toks.next();
+    let mut described = item.map_or("item".into(), Item::describe);
+    if let Some(generic) = generic {
+        described = format!("the default of {} of {}", generic.describe(), described);
+    }
+
+    if let Some(item) = item.filter(|_| test_guard && severity == Severity::Error) {
+        trace.step(|| "`test_guard`: a failing test is generated instead".into());
+        let mut message = expired_message(&args, &described, reason.as_deref(), version);
+        if let Some(issue) = &args.issue {
+            message.push_str(&format!("\ntracking issue: {}", issue));
+        }
+        test_guard::push(item, at, &message);
+        return;
+    }
+
+    // the test is skipped loudly rather than the crate failing to compile
+    let ignored = ignore_test && severity == Severity::Error;
+    if ignored {
+        trace.step(|| "`on_expiry = \"ignore_test\"`: the test is ignored instead".into());
+        test_guard::ignore(match reason.as_deref() {
+            Some(reason) => format!("expired: {}", message::first_line(reason).0),
+            None => format!("expired: version {} matches {}", version, pred),
+        });
+        severity = Severity::Warning;
+    }
+
+    // the error points at the item's name, or at the default going away, with notes pointing at
+    // the attribute and the requirement
+    let name = generic
+        .and_then(|generic| generic.default)
+        .or(item.and_then(|item| item.span));
+
+    let fail_fast = severity == Severity::Error && env::fail_fast();
+    if fail_fast && !first_expired(at) {
+        trace.decide("noted briefly, as an earlier expiry error was reported in full");
+        diagnostic::note(
+            name.unwrap_or(at),
+            Code::Expired,
+            format!(
+                "{} expired too (version {} matches {})",
+                described, version, pred
+            ),
+        )
+        .emit_visible();
+        return;
+    }
+    if severity == Severity::Error && !fail_fast && !within_error_limit(at) {
+        trace.decide("omitted, as `max_errors` expiry errors were reported");
+        return;
+    }
+
+    let mut diag = Diagnostic::spanned(
+        name.unwrap_or(at),
+        severity.level(),
+        Code::Expired,
+        expired_message(
+            &args,
+            &described,
+            reason.as_deref().map(|r| message::first_line(r).0),
+            version,
+        ),
+    );
+    if let Some(rest) = reason.as_deref().and_then(|r| message::first_line(r).1) {
+        diag = diag.note(rest);
+    }
+    if name.is_some() {
+        diag = diag.span_note(at, "removal scheduled here");
+    }
+    if !args.version_omitted {
+        diag = diag.span_note(
+            args.version_span,
+            format!("the requirement `{}` matches version {}", pred, version),
+        );
+    }
+    if let (Some((req, span)), Some(cargo)) = (&args.cargo, cargo::version()) {
+        diag = diag.span_note(
+            *span,
+            format!("the `cargo` requirement `{}` matches Cargo {}", req, cargo),
+        );
+    }
+    if let Some((tool, found)) = args
+        .tool
+        .as_ref()
+        .and_then(|tool| Some((tool, tool::version(&tool.name)?)))
+    {
+        diag = diag.span_note(
+            tool.span,
+            format!(
+                "the `tool_version` requirement `{}` matches `{}` {}",
+                tool.req, tool.name, found
+            ),
+        );
+    }
+    if args.default_version {
+        diag = diag.note(format!("using crate default requirement {}", pred));
+    }
+    if let Some(extension) = &extension {
+        diag = diag.note(extension);
+    }
+    if let Some(truncation) = truncation {
+        diag = diag.note(truncation);
+    }
+    if let (None, Some(fingerprint)) = (&args.id, &args.fingerprint) {
+        diag = diag.note(format!("fingerprint: {}", fingerprint));
+    }
+    if ignored {
+        diag = diag
+            .note("`on_expiry = \"ignore_test\"` ignores the test instead of failing the build");
+    }
+    if let Some(origin) = diagnostic::origin(at) {
+        diag = diag.span_note(origin, "originating from this macro invocation");
+    }
+    if fail_fast {
+        diag = diag.note(format!(
+            "`{}=1` is set, so the items expiring after this one are only noted briefly",
+            env::FAIL_FAST_VAR
+        ));
+    }
+
+    // the attribute isn't part of the tokens of the item, so the extent is joined with it
+    let extent = item.and_then(|item| match item.extent {
+        Some(extent) if !item.grouped => diagnostic::join(at, extent),
+        extent => extent,
+    });
+    if let Some(generic) = generic {
+        if let Some(extent) = generic.default_extent {
+            diag = diag.span_help(extent, "remove the default, so users choose the parameter");
+        }
+    } else if let Some(extent) = extent {
+        diag = diag.span_help(extent, format!("remove the expired {}", described));
+    }
+    // the requirement of a migration or of the defaults isn't written in the annotation, and an
+    // extended one isn't the one written
+    let shifted = extension.is_some() && !args.no_extend;
+    let literal =
+        (!args.default_version && args.migration.is_none() && !args.version_omitted && !shifted)
+            .then_some(args.version_span)
+            .and_then(diagnostic::in_source);
+    if let Some((literal, extended)) = literal.zip(version::extended(&pred, version)) {
+        diag = diag.span_help(
+            literal,
+            format!(
+                "extend the deadline (requires team approval): `\"{}\"`",
+                extended
+            ),
+        );
+    }
+
+    if let Some(item) = item {
+        if reason.is_some() && item.name.is_some() {
+            diag = diag.note(format!("{} must be removed", described));
+        }
+        if item.display_name.is_some() {
+            diag = diag.note(format!("the item is {}", item.identify()));
+        }
+        if let (Some(name), Some(target)) = (&item.name, &item.aliased) {
+            diag = diag.note(format!("`{}` is an alias of `{}`", name, target));
+        }
+        // the item itself stays when only the default of a parameter goes
+        for symbol in item.symbols.iter().filter(|_| generic.is_none()) {
+            diag = diag.note(format!("{} must be removed", symbol.describe()));
+        }
+    }
+
+    if let Some(feature) = &args.superseded_by_feature {
+        diag = diag.help(format!(
+            "the functionality moved behind the `{}` feature, enable it with `features = [\"{}\"]`",
+            feature, feature
+        ));
+    }
+
+    if let Some(owner) = &args.owner {
+        diag = diag.note(format!("owner: {}", owner));
+    }
+    if let Some(issue) = &args.issue {
+        diag = diag.note(format!("tracking issue: {}", issue));
+    }
+
+    if let Some((_, note)) = enforcement.filter(|(enforced, _)| *enforced == severity) {
+        diag = diag.note(note);
+    }
+
+    if let Some((grace, end)) = grace_end {
+        diag = diag.note(format!(
+            "this is a warning during the {} grace period, it becomes an error at version {}",
+            grace, end
+        ));
+    }
+
+    if let Some((_, vis)) = hidden {
+        diag = diag.note(format!(
+            "this is a warning as `public_only` is set and the {} is {}",
+            item.map_or("item", |item| item.kind.as_str()),
+            vis.describe()
+        ));
+    }
+
+    if public_api && severity == Severity::Error {
+        diag = diag.note(format!(
+            "this is an error as `public_api = \"error\"` is set and the {} is in the public API \
+             listed by `{}`",
+            item.map_or("item", |item| item.kind.as_str()),
+            public_api::PUBLIC_API_VAR
+        ));
+    }
+
+    if let Some(var) = escalation.filter(|_| severity == Severity::Error) {
+        diag = diag.note(escalation_note(var, "an error"));
+    }
+
+    if let Some(since) = message::expired_since(&pred, version) {
+        diag = diag.note(since);
+    }
+
+    diag = diag.note(format!(
+        "current version {} (from {})",
+        version,
+        current.describe_source()
+    ));
+    if current.source == version::Source::CargoComponents {
+        diag = diag.note(
+            "`CARGO_PKG_VERSION` is not valid semver, so the version was reconstructed from \
+             `CARGO_PKG_VERSION_MAJOR`, `_MINOR` and `_PATCH`",
+        );
+    }
+
+    if severity == Severity::Warning {
+        match throttle::check(&args, Code::Expired, at) {
+            Some(0) => (),
+            Some(held_back) => diag = diag.note(throttle::note(held_back)),
+            None => {
+                trace.decide("held back, as the warning was emitted within the interval");
+                return;
             }
-            Some(t) => {
-                return Err(t
-                    .span()
-                    .error("unexpected token")
-                    .help("expected end of tokens or `,`"))
+        }
+    }
+
+    trace.decide(match severity {
+        Severity::Error => "reported as an error",
+        Severity::Warning => "reported as a warning",
+    });
+    diag.emit();
+}
+
+/// Whether `what`, relaxing the expired annotation at `at`, is signed off by one of the `approvers`
+/// with `approved_by`, erroring if it isn't. Nothing needs a sign-off unless `approvers` are
+/// configured.
+fn approved(approved_by: Option<&(String, Span)>, at: Span, what: &str) -> bool {
+    let Some(approvers) = &config::get().approvers else {
+        return true;
+    };
+    let listed = match &approvers[..] {
+        [] => "nobody is listed".into(),
+        approvers => format!(
+            "the approvers are {}",
+            approvers
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let diag = match approved_by {
+        Some((name, _)) if approvers.contains(name) => return true,
+        Some((name, span)) => diagnostic::error(
+            *span,
+            Code::Unapproved,
+            format!(
+                "`{}` is not one of the `approvers`, who must sign off on {}",
+                name, what
+            ),
+        )
+        .help(format!("{}, in `[package.metadata.allow-until]`", listed)),
+        None => diagnostic::error(
+            at,
+            Code::Unapproved,
+            format!("{} needs the sign-off of an approver", what),
+        )
+        .help(format!(
+            "add `approved_by = \"name\"` once an approver agreed; {}",
+            listed
+        )),
+    };
+    diag.note("the annotation is reported as an error until then")
+        .emit();
+    false
+}
+
+/// The message of the expired annotation on `described`, naming the version of Cargo or of the
+/// tool when `version` is left out for them.
+fn expired_message(
+    args: &ParsedAnnotation,
+    described: &str,
+    reason: Option<&str>,
+    version: &semver::Version,
+) -> String {
+    if args.version_omitted {
+        if let (Some((req, _)), Some(cargo)) = (&args.cargo, cargo::version()) {
+            return message::expired_tool(described, reason, "Cargo", cargo, req);
+        }
+        if let Some((tool, found)) = args
+            .tool
+            .as_ref()
+            .and_then(|tool| Some((tool, tool::version(&tool.name)?)))
+        {
+            return message::expired_tool(described, reason, &tool.name, &found, &tool.req);
+        }
+    }
+    message::expired(described, reason, version, &args.version)
+}
+
+/// Warns about an annotation that hasn't expired at `version` if it can never fire, expires soon,
+/// or has a requirement that is stale or beyond the horizon.
+fn warn_unmatched(
+    args: &ParsedAnnotation,
+    at: Span,
+    item: Option<&Item>,
+    version: &semver::Version,
+    trace: &mut trace::Trace,
+) {
+    if !version::can_fire(&args.version, version) {
+        trace.step(|| "no later version matches, the annotation can never fire".into());
+        diagnostic::warning(
+            at,
+            Code::Dead,
+            format!(
+                "annotation can never fire, as no version from {} on matches `{}`",
+                version, args.version
+            ),
+        )
+        .help("delete the annotation, or correct the requirement")
+        .emit();
+    }
+
+    if let Some(ahead) = config::get().warn_ahead {
+        warn_ahead(args, ahead, version, at, item, trace);
+    }
+    stale::check(args, item, at, version, trace);
+    horizon::check(args, item, at, version, trace);
+}
+
+/// Warns about an annotation that hasn't expired yet when the version from which its requirement
+/// matches is at most `ahead` after `version`.
+fn warn_ahead(
+    args: &ParsedAnnotation,
+    ahead: Distance,
+    version: &semver::Version,
+    at: Span,
+    item: Option<&Item>,
+    trace: &mut trace::Trace,
+) {
+    let pred = &args.version;
+    let Some(expiry) = version::minimal_version(pred) else {
+        trace.step(|| {
+            format!(
+                "the first version matching `{}` is unknown, so `warn_ahead` is skipped",
+                pred
+            )
+        });
+        return;
+    };
+
+    let horizon = ahead.add_to(version);
+    if expiry <= *version || expiry > horizon {
+        return;
+    }
+
+    trace.step(|| {
+        format!(
+            "expires at {}, within the {} of `warn_ahead`",
+            expiry, ahead
+        )
+    });
+    let escalation = escalated(args);
+    let level = match escalation {
+        Some(_) => Level::Error,
+        None => warning_level(at),
+    };
+    let held_back = match level {
+        Level::Warning => throttle::check(args, Code::ExpiringSoon, at),
+        _ => Some(0),
+    };
+    let Some(held_back) = held_back else {
+        trace.step(|| "held back, as the warning was emitted within the interval".into());
+        return;
+    };
+    let mut diag = Diagnostic::spanned(
+        item.and_then(|item| item.span).unwrap_or(at),
+        level,
+        Code::ExpiringSoon,
+        format!(
+            "{} expires at version {} (requirement `{}`)",
+            item.map_or("item".into(), Item::describe),
+            expiry,
+            pred
+        ),
+    )
+    .note(format!(
+        "`warn_ahead` warns {} ahead, and the current version is {}",
+        ahead, version
+    ));
+    if let Some(var) = escalation {
+        diag = diag.note(escalation_note(var, "an error"));
+    }
+    if held_back > 0 {
+        diag = diag.note(throttle::note(held_back));
+    }
+    diag.emit();
+}
+
+/// Counts the annotation against the budget set by `ALLOW_UNTIL_MAX`, erroring at the annotation
+/// that exceeds it.
+fn count(at: Span) {
+    let max = match env::max() {
+        Ok(Some(max)) => max,
+        Ok(None) => return,
+        Err(e) => {
+            if env::once("max") {
+                diagnostic::warning(at, Code::InvalidEnv, e).emit();
             }
-            None => {}
+            return;
         }
+    };
+
+    let location = (at.file(), at.line(), at.column());
+    let count = registry::with(|r| {
+        r.counted.insert(location);
+        r.counted.len()
+    });
+
+    if count == max + 1 {
+        diagnostic::error(
+            at,
+            Code::OverBudget,
+            format!(
+                "annotation number {} exceeds the budget of {} set by `{}`",
+                count,
+                max,
+                env::MAX_VAR
+            ),
+        )
+        .help("remove expired annotations, or raise the budget deliberately")
+        .note("`allow_until_summary!()` reports the total")
+        .emit();
+    }
+}
+
+/// Whether the expiry error of the annotation at `at` is reported in full, which only the first
+/// `max_errors` of a crate are unless `ALLOW_UNTIL_VERBOSE=1` is set. The first annotation over
+/// the limit reports that more items expired instead, and `allow_until_summary!()` counts them.
+fn within_error_limit(at: Span) -> bool {
+    if env::verbose() || diagnostic::silenced() {
+        return true;
     }
+    let max = config::get()
+        .max_errors
+        .unwrap_or(config::DEFAULT_MAX_ERRORS);
+
+    let location = (at.file(), at.line(), at.column());
+    let omitted = registry::with(|r| {
+        if r.errors.contains(&location) || r.errors.len() < max {
+            r.errors.insert(location);
+            return None;
+        }
+        r.omitted.insert(location);
+        Some(r.omitted.len())
+    });
 
-    if version.is_none() {
-        return Err(Span::call_site().error("missing required `version` argument"));
+    if omitted == Some(1) {
+        diagnostic::error(
+            at,
+            Code::ErrorsOmitted,
+            format!(
+                "more items expired than the {} reported; run with `{}=1` for the full list",
+                max,
+                env::VERBOSE_VAR
+            ),
+        )
+        .note("`allow_until_summary!()` counts them")
+        .help("raise `max_errors` in the configuration to report more of them")
+        .emit();
     }
+    omitted.is_none()
+}
 
-    Ok(Args {
-        reason,
-        version: version.unwrap(),
+/// Whether the expiry error of the annotation at `at` is the first of the crate, the only one
+/// reported in full with `ALLOW_UNTIL_FAIL_FAST=1`. The others are noted briefly, and
+/// `allow_until_summary!()` counts them.
+fn first_expired(at: Span) -> bool {
+    if diagnostic::silenced() {
+        return true;
+    }
+    let location = (at.file(), at.line(), at.column());
+    registry::with(|r| {
+        if *r.first_expired.get_or_insert_with(|| location.clone()) == location {
+            return true;
+        }
+        r.briefly_noted.insert(location);
+        false
     })
 }
 
-fn emit_error_version_match(pred: VersionReq, reason: Option<String>, at: Span) {
-    if let Ok(pkg_ver) = std::env::var("CARGO_PKG_VERSION") {
-        let version = Version::parse(&pkg_ver).expect("invalid cargo semver ver");
+/// Whether the annotation is in its warning window at `version`: expired but within its `grace`
+/// period, or about to expire within the configured `warn_ahead`.
+fn in_warning_window(args: &ParsedAnnotation, version: &semver::Version, matched: bool) -> bool {
+    let Some(boundary) = version::minimal_version(&args.version) else {
+        return false;
+    };
+    if matched {
+        args.grace
+            .is_some_and(|grace| *version < grace.add_to(&boundary))
+    } else {
+        config::get()
+            .warn_ahead
+            .is_some_and(|ahead| boundary > *version && boundary <= ahead.add_to(version))
+    }
+}
 
-        if pred.matches(&version) {
-            at.error(reason.map_or(
-                format!("item not allowed! (version {} matches {})", version, pred),
-                |r| format!("{} (version {} matches {})", r, version, pred),
-            ))
-            .emit();
+/// Notes the requirement, reason and status of the annotation at `at`, for `always_note`. Only the
+/// first `max_errors` annotations of a crate are noted unless `ALLOW_UNTIL_VERBOSE=1` is set, the
+/// first one over the limit notes that there are more, and `allow_until_summary!()` counts them.
+fn note_allowance(
+    args: &ParsedAnnotation,
+    at: Span,
+    item: Option<&Item>,
+    version: &semver::Version,
+    matched: bool,
+    window: bool,
+) {
+    let max = config::get()
+        .max_errors
+        .unwrap_or(config::DEFAULT_MAX_ERRORS);
+    let location = (at.file(), at.line(), at.column());
+    let unnoted = registry::with(|r| {
+        if env::verbose() || r.noted.contains(&location) || r.noted.len() < max {
+            r.noted.insert(location);
+            return None;
+        }
+        r.unnoted.insert(location);
+        Some(r.unnoted.len())
+    });
+
+    match unnoted {
+        None => (),
+        Some(1) => {
+            diagnostic::note(
+                at,
+                Code::Allowance,
+                format!(
+                    "more allowances than the {} noted; run with `{}=1` for the full list",
+                    max,
+                    env::VERBOSE_VAR
+                ),
+            )
+            .emit_visible();
+            return;
         }
+        Some(_) => return,
+    }
+
+    let status = match (matched, window) {
+        (_, true) => "warning window",
+        (false, false) => "active",
+        (true, false) => "expired",
+    };
+
+    let span = item.and_then(|item| item.span).unwrap_or(at);
+    let message = format!(
+        "{}: {}, requirement `{}` at version {}",
+        item.map_or("item".into(), Item::describe),
+        status,
+        args.version,
+        version
+    );
+    let escalation = escalated(args);
+    let mut diag = match escalation {
+        Some(_) => Diagnostic::spanned(span, warning_level(at), Code::Allowance, message),
+        None => diagnostic::note(span, Code::Allowance, message),
+    };
+    if let Some(reason) = &args.reason {
+        diag = diag.note(format!("reason: {}", message::first_line(reason).0));
+    }
+    match escalation {
+        Some(var) => diag.note(escalation_note(var, "a warning")).emit(),
+        None => diag.emit_visible(),
     }
 }
 
-fn recurse_find_attr(group: Group) {
-    let mut toks = group.stream().into_iter();
-
-    loop {
-        match toks.next() {
-            Some(TT::Group(g)) => recurse_find_attr(g),
-            Some(TT::Punct(hash)) if hash.as_char() == '#' => match toks.next() {
-                Some(TT::Group(inner_g)) => {
-                    let mut toks = inner_g.stream().into_iter();
-
-                    match toks.next() {
-                        Some(TT::Ident(ident)) if &ident.to_string()[..] == "allow_until" => {
-                            match toks.next() {
-                                Some(TT::Group(g)) => {
-                                    let args = parse_arguments(g.stream());
-                                    let args = match args {
-                                        Err(e) => {
-                                            e.emit();
-                                            return;
-                                        }
-                                        Ok(a) => a,
-                                    };
-
-                                    emit_error_version_match(
-                                        args.version,
-                                        args.reason,
-                                        hash.span()
-                                            .join(inner_g.span())
-                                            .unwrap()
-                                            .join(ident.span())
-                                            .unwrap(),
-                                    );
-
-                                    continue;
-                                }
-                                _ => continue,
-                            }
-                        }
-                        _ => continue,
-                    }
-                }
-                _ => continue,
-            },
-            None => break,
-            _ => continue,
+/// Warns about the annotation for each version in `ALLOW_UNTIL_SIMULATE` that it would fire at,
+/// regardless of the real version.
+fn simulate(args: &ParsedAnnotation, at: Span, item: Option<&Item>) {
+    let (versions, invalid) = env::simulated();
+
+    if !invalid.is_empty() && env::once("simulate") {
+        diagnostic::warning(
+            at,
+            Code::InvalidEnv,
+            format!(
+                "ignoring invalid `{}` versions: {}",
+                env::SIMULATE_VAR,
+                invalid
+                    .iter()
+                    .map(|v| format!("`{}`", v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+        .emit();
+    }
+
+    for version in versions.iter().filter(|v| args.version.matches(v)) {
+        let message = message::expired(
+            &item.map_or("item".into(), Item::describe),
+            args.reason.as_deref().map(|r| message::first_line(r).0),
+            version,
+            &args.version,
+        );
+
+        let mut diag = diagnostic::warning(
+            at,
+            Code::Simulated,
+            format!("[simulated {}] {}", version, message),
+        );
+        if let Some(rest) = args
+            .reason
+            .as_deref()
+            .and_then(|r| message::first_line(r).1)
+        {
+            diag = diag.note(rest);
+        }
+        diag.emit();
+    }
+}
+
+/// Warns when a version of the crate that satisfies `pred` has already been published, since the
+/// item should then have been removed before the release.
+#[cfg(feature = "publish-check")]
+fn check_published(pred: &semver::VersionReq, at: Span, item: Option<&Item>) {
+    if !index::enabled() || !offline::policy(at).allows(offline::Source::RegistryIndex, at) {
+        return;
+    }
+
+    match index::published() {
+        Ok(published) => {
+            if let Some(released) = published.iter().find(|v| pred.matches(v)) {
+                diagnostic::warning(
+                    at,
+                    Code::Published,
+                    format!(
+                        "{} still exists, but the published version {} already matches {}",
+                        item.map_or("item".into(), Item::describe),
+                        released,
+                        pred
+                    ),
+                )
+                .help("the item was meant to be removed by that release")
+                .emit();
+            }
+        }
+        Err(e) => {
+            if env::once("index") {
+                diagnostic::note(
+                    at,
+                    Code::IndexUnavailable,
+                    format!(
+                        "`{}` is set, but the published versions are unknown: {}",
+                        index::INDEX_CHECK_VAR,
+                        e
+                    ),
+                )
+                .emit();
+            }
+        }
+    }
+}
+
+/// Evaluates the `#[allow_until(...)]` helper attributes of a member of a derive input.
+fn member_attrs(attrs: &[Attribute], item: Item) {
+    let item = Item {
+        deprecated_since: item::deprecated_since(attrs),
+        deprecated: item::is_deprecated(attrs),
+        ..item
+    };
+    for attr in attrs {
+        if !attr.path().is_ident("allow_until") {
+            continue;
+        }
+
+        let pound = attr.pound_token.span.unwrap();
+        let at = diagnostic::join(pound, attr.bracket_token.span.join().unwrap()).unwrap_or(pound);
+        // a bare `#[allow_until]` gets the same help as `#[allow_until()]`
+        let tokens = match &attr.meta {
+            Meta::List(list) => list.tokens.clone(),
+            Meta::Path(_) => proc_macro2::TokenStream::new(),
+            Meta::NameValue(nv) => {
+                diagnostic::error(
+                    nv.eq_token.span.unwrap(),
+                    Code::InvalidInput,
+                    "expected arguments in parentheses",
+                )
+                .help("write `#[allow_until(version = \"...\")]`")
+                .emit();
+                continue;
+            }
+        };
+        match annotation::parse(tokens, at) {
+            Ok(args) => {
+                runtime::record(&item, &args);
+                emit_error_version_match(args, at, Some(&item));
+            }
+            Err(e) => e.emit(),
+        }
+    }
+}
+
+/// Evaluates the annotations of the fields in `fields`, which belong to `parent`. Fields of enum
+/// variants have the `visibility` of the enum, and other fields their own.
+fn fields(fields: &Fields, parent: &str, visibility: Option<Visibility>) {
+    let fields = match fields {
+        Fields::Named(fields) => &fields.named,
+        Fields::Unnamed(fields) => &fields.unnamed,
+        Fields::Unit => return,
+    };
+    for (index, pair) in fields.pairs().enumerate() {
+        let field = pair.value();
+        let vis = visibility.unwrap_or_else(|| Visibility::of(&field.vis));
+        let item = match &field.ident {
+            Some(ident) => Item::member(
+                Kind::Field,
+                parent,
+                ident.unraw(),
+                vis,
+                ident.span().unwrap(),
+                pair,
+            ),
+            None => Item::member(
+                Kind::Field,
+                parent,
+                index,
+                vis,
+                field.ty.span().unwrap(),
+                pair,
+            ),
+        };
+        member_attrs(&field.attrs, item);
+    }
+}
+
+/// Runs the expansion of a macro, turning a panic into an error that asks for a bug report rather
+/// than rustc's bare "proc macro panicked", and returning `fallback` so the rest of the crate still
+/// compiles.
+fn guard(fallback: TokenStream, place: Place, expand: impl FnOnce() -> TokenStream) -> TokenStream {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(expand)) {
+        Ok(output) => output,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+
+            diagnostic::error(
+                Span::call_site(),
+                Code::Internal,
+                format!("internal error in `allow_until`: {}", message),
+            )
+            .note(format!(
+                "this is a bug, please report it at {}/issues",
+                env!("CARGO_PKG_REPOSITORY")
+            ))
+            .emit();
+
+            fallback
+                .into_iter()
+                .chain(diagnostic::take(place))
+                .collect()
         }
     }
 }
@@ -189,6 +1463,7 @@ fn recurse_find_attr(group: Group) {
 /// Allows an item until a specified semver version, and then errors on compilation.
 ///
 /// ```rust
+/// # use allow_until::allow_until;
 /// #[allow_until(version = ">= 1.0.x", reason = "struct is deprecated from version 1.0.x onwards")]
 /// struct MyStruct {
 ///     //....
@@ -196,24 +1471,232 @@ fn recurse_find_attr(group: Group) {
 /// ```
 #[proc_macro_attribute]
 pub fn allow_until(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args = parse_arguments(args);
+    guard(input.clone(), Place::Unknown, || {
+        let item = Item::parse(input.clone());
 
-    let args = match args {
-        Err(e) => {
-            e.emit();
-            return input;
+        let mut deprecate_from = None;
+        match annotation::parse(args.into(), Span::call_site()) {
+            Err(e) => e.emit(),
+            Ok(args) => {
+                if let Some(predicate) = &args.doc_cfg {
+                    doc_cfg::mark(predicate.clone());
+                }
+                deprecate_from = args.deprecate_from.as_ref().map(|(from, _)| from.clone());
+                emit_error_version_match(args, Span::call_site(), Some(&item))
+            }
         }
-        Ok(a) => a,
-    };
 
-    emit_error_version_match(args.version, args.reason, Span::call_site());
+        let input = doctest::rewritten(test_guard::ignored(input));
+        let input = deprecate::added(deprecate_from.as_ref(), &item, doc_cfg::added(input));
+        with_diagnostics(strip::stripped(input), &item)
+            .into_iter()
+            .chain(test_guard::take())
+            .collect()
+    })
+}
+
+/// Keeps a deprecated function under the old name of a renamed function, forwarding to it, until
+/// a version matching the requirement.
+///
+/// ```rust
+/// # use allow_until::renamed_until;
+/// #[renamed_until(old = "connect_tls", version = ">=2.0")]
+/// pub fn connect_secure(host: &str) -> bool {
+///     //....
+/// #   !host.is_empty()
+/// }
+///
+/// #[allow(deprecated)]
+/// let connected = connect_tls("example.com");
+/// ```
+#[proc_macro_attribute]
+pub fn renamed_until(args: TokenStream, input: TokenStream) -> TokenStream {
+    guard(input.clone(), Place::Unknown, || {
+        let item = Item::parse(input.clone());
+        let shim = rename::expand(args, input.clone());
+
+        with_diagnostics(input, &item)
+            .into_iter()
+            .chain(shim)
+            .collect()
+    })
+}
+
+/// Notes that the behavior of the item changes at a version matching the requirement, rather than
+/// the item being removed. It never fails the build: once the requirement matches, it warns until
+/// the attribute is removed.
+///
+/// ```rust
+/// # use allow_until::changes_at;
+/// #[changes_at(version = ">=2.0", note = "returns `Err` instead of panicking")]
+/// pub fn parse(input: &str) -> u32 {
+///     //....
+/// #   input.len() as u32
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn changes_at(args: TokenStream, input: TokenStream) -> TokenStream {
+    guard(input.clone(), Place::Unknown, || {
+        let item = Item::parse(input.clone());
+        changes::evaluate(args, &item, Span::call_site());
+
+        with_diagnostics(input, &item)
+    })
+}
+
+/// Keeps the default body of a trait method until a version matching the requirement, from which
+/// the method is required and the body an error.
+///
+/// ```rust
+/// # use allow_until::default_body_until;
+/// pub trait Transport {
+///     fn send(&mut self, bytes: &[u8]);
+///
+///     #[default_body_until(version = ">=2.0", reason = "implementors must flush themselves")]
+///     fn flush(&mut self) {}
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn default_body_until(args: TokenStream, input: TokenStream) -> TokenStream {
+    guard(input.clone(), Place::Unknown, || {
+        let item = Item::parse(input.clone());
+        default_body::evaluate(args, input.clone(), &item, Span::call_site());
+
+        with_diagnostics(input, &item)
+    })
+}
+
+/// Attaches an attribute to the item while the requirement doesn't match, and drops it from a
+/// matching version on. With `when = "matched"`, the attribute is attached from that version on
+/// instead.
+///
+/// ```rust
+/// # use allow_until::attr_until;
+/// #[attr_until(version = ">=2.0", attr = deprecated(note = "use `connect_async`"))]
+/// pub fn connect() {}
+///
+/// #[attr_until(version = ">=2.0", attr = doc(hidden), when = "matched")]
+/// pub fn legacy_connect() {}
+/// ```
+#[proc_macro_attribute]
+pub fn attr_until(args: TokenStream, input: TokenStream) -> TokenStream {
+    guard(input.clone(), Place::Unknown, || {
+        let item = Item::parse(input.clone());
+        let attr = attr_until::evaluate(args, Span::call_site());
+
+        attr.into_iter()
+            .flatten()
+            .chain(with_diagnostics(input, &item))
+            .collect()
+    })
+}
+
+/// Applies one annotation to the members of an impl block or a trait, or to those listed in
+/// `members`, each reported on its own once the requirement matches. Members with an
+/// `#[allow_until(...)]` of their own keep it, and those marked `#[allow_until(skip)]` are exempt.
+/// Every listed name must be a function, constant or type of the block.
+///
+/// ```rust
+/// # use allow_until::allow_members_until;
+/// pub struct Client;
+///
+/// #[allow_members_until(version = ">=2.0", members = ["connect_tls", "TIMEOUT"])]
+/// impl Client {
+///     pub const TIMEOUT: u64 = 30;
+///
+///     pub fn connect(&self) {}
+///
+///     pub fn connect_tls(&self) {}
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn allow_members_until(args: TokenStream, input: TokenStream) -> TokenStream {
+    guard(input.clone(), Place::Items, || {
+        members::expand(args, input, Span::call_site())
+    })
+}
+
+/// Applies one annotation to each of the items in braces after its arguments, for items that are
+/// removed together. Each item is reported on its own once the requirement matches, and items
+/// with an annotation of their own keep it instead.
+///
+/// ```rust
+/// allow_until::allow_until_group! {
+///     version = ">=2.0", reason = "use the async API",
+///     {
+///         pub fn connect() {}
+///         pub struct SyncClient;
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn allow_until_group(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Items, || group::expand(input))
+}
+
+/// Adds the tokens of the diagnostics emitted on stable to the annotated item: into the body of
+/// functions, as it may be an associated function, and otherwise after the item. Warnings are
+/// only kept where they can't end up among associated or foreign items.
+fn with_diagnostics(input: TokenStream, item: &Item) -> TokenStream {
+    // the item is returned as is rather than walked, always the case with the `nightly` feature
+    // unless `link-section` is enabled
+    if !diagnostic::pending() && !section::pending() {
+        return input;
+    }
+
+    let mut toks: Vec<_> = input.into_iter().collect();
+
+    if item.kind == Kind::Fn {
+        if let Some(TT::Group(body)) = toks.last_mut() {
+            if body.delimiter() == Delimiter::Brace {
+                let mut inner: Vec<_> = body.stream().into_iter().collect();
+                // after the inner attributes of the body
+                let mut at = 0;
+                while matches!(&inner[at..], [TT::Punct(hash), TT::Punct(bang), TT::Group(_), ..]
+                    if hash.as_char() == '#' && bang.as_char() == '!')
+                {
+                    at += 3;
+                }
+                inner.splice(
+                    at..at,
+                    diagnostic::take(Place::Items)
+                        .into_iter()
+                        .chain(section::take()),
+                );
+                let stream = inner.into_iter().collect();
+
+                let mut group = Group::new(Delimiter::Brace, stream);
+                group.set_span(body.span());
+                *body = group;
+                return toks.into_iter().collect();
+            }
+        }
+    }
 
-    input
+    // a sibling item is valid next to anything that can only be in a module, impl blocks, `use`
+    // and `macro_rules!` included
+    let place = if item.free {
+        Place::Items
+    } else {
+        Place::Unknown
+    };
+    let section = match place {
+        Place::Items => section::take(),
+        Place::Unknown => {
+            section::defer();
+            TokenStream::new()
+        }
+    };
+    toks.into_iter()
+        .chain(diagnostic::take(place))
+        .chain(section)
+        .collect()
 }
 
 /// Allows an item until a specified semver version, and then errors on compilation.
 ///
 /// ```rust
+/// # use allow_until::AllowUntil;
 /// #[derive(AllowUntil)]
 /// struct MyStruct {
 ///     #[allow_until(version = ">= 1.0.x", reason = "member is deprecated from version 1.0.x onwards")]
@@ -222,14 +1705,334 @@ pub fn allow_until(args: TokenStream, input: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_derive(AllowUntil, attributes(allow_until))]
 pub fn allow_until_derive(stream: TokenStream) -> TokenStream {
-    let toks = stream.into_iter();
+    guard(TokenStream::new(), Place::Items, || {
+        let input = match syn::parse::<DeriveInput>(stream) {
+            Ok(input) => input,
+            Err(e) => {
+                diagnostic::error(e.span().unwrap(), Code::InvalidInput, e.to_string()).emit();
+                return diagnostic::take(Place::Items);
+            }
+        };
+        let parent = input.ident.unraw().to_string();
 
-    for tok in toks {
-        match tok {
-            TT::Group(g) => recurse_find_attr(g),
-            _ => continue,
+        match &input.data {
+            Data::Struct(data) => fields(&data.fields, &parent, None),
+            Data::Union(data) => fields(&Fields::Named(data.fields.clone()), &parent, None),
+            Data::Enum(data) => {
+                let vis = Visibility::of(&input.vis);
+                for pair in data.variants.pairs() {
+                    let variant = pair.value();
+                    let ident = variant.ident.unraw();
+                    member_attrs(
+                        &variant.attrs,
+                        Item::member(
+                            Kind::Variant,
+                            &parent,
+                            &ident,
+                            vis,
+                            variant.ident.span().unwrap(),
+                            pair,
+                        ),
+                    );
+                    fields(
+                        &variant.fields,
+                        &format!("{}::{}", parent, ident),
+                        Some(vis),
+                    );
+                }
+            }
         }
+
+        // the documentation of the fields and variants is left as it is
+        doctest::discard();
+        diagnostic::take(Place::Items)
+            .into_iter()
+            .chain(test_guard::take())
+            .chain(section::take())
+            .chain(runtime::take(&input))
+            .collect()
+    })
+}
+
+/// Reports build-wide findings about the annotations expanded before it, such as the ids that
+/// were suppressed by `ALLOW_UNTIL_SUPPRESS` or that match no annotation, along with an overview
+/// note of how many annotations are active and which expires next.
+///
+/// Macros are expanded in source order, so place it at the end of the crate root, after every
+/// module containing annotations. Annotations expanded after it are not included.
+///
+/// ```rust
+/// allow_until::allow_until_summary!();
+/// ```
+#[proc_macro]
+pub fn allow_until_summary(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Items, || {
+        if let Some(tok) = input.into_iter().next() {
+            diagnostic::error(
+                tok.span(),
+                Code::InvalidInput,
+                "`allow_until_summary!` takes no arguments",
+            )
+            .emit();
+        }
+
+        env::test_panic();
+        registry::summarize(Span::call_site());
+        sarif::finish(Span::call_site());
+
+        if trace::enabled() {
+            diagnostic::note(
+                Span::call_site(),
+                Code::Trace,
+                format!(
+                    "allow_until trace: {} manifest read(s) in this compilation",
+                    manifest::reads()
+                ),
+            )
+            .emit();
+        }
+
+        diagnostic::take(Place::Items)
+            .into_iter()
+            .chain(section::take_deferred())
+            .collect()
+    })
+}
+
+/// Errors when more of the annotations expanded before it are still pending for a milestone than
+/// the budget allows, listing them in notes. An annotation is pending for the milestone when its
+/// requirement first matches at or before the first version the milestone's requirement matches,
+/// e.g. `>=1.5` and `>=2.0` for `version = ">=2.0"`.
+///
+/// Macros are expanded in source order, so place it at the end of the crate root, after every
+/// module containing annotations, as for [`allow_until_summary!`]. Annotations expanded after it are
+/// not counted.
+///
+/// ```rust
+/// allow_until::max_pending!(5, version = ">=2.0");
+/// ```
+#[proc_macro]
+pub fn max_pending(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Items, || {
+        pending::expand(input);
+        diagnostic::take(Place::Items)
+    })
+}
+
+/// Expands to an empty module named `deprecation_schedule` documented with the removal plan of the
+/// crate, so it is published with its documentation, e.g. on docs.rs. The annotations expanded
+/// before it are listed by the version they expire at, with their reasons.
+///
+/// Macros are expanded in source order, so place it at the end of the crate root, after every
+/// module containing annotations, as for [`allow_until_summary!`]. Annotations expanded after it are
+/// not included.
+///
+/// ```rust
+/// allow_until::deprecation_schedule_module!();
+/// ```
+#[proc_macro]
+pub fn deprecation_schedule_module(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Items, || {
+        if let Some(tok) = input.into_iter().next() {
+            diagnostic::error(
+                tok.span(),
+                Code::InvalidInput,
+                "`deprecation_schedule_module!` takes no arguments",
+            )
+            .emit();
+        }
+
+        schedule::module()
+            .into_iter()
+            .chain(diagnostic::take(Place::Items))
+            .collect()
+    })
+}
+
+/// Expands to a `#[test]` that fails if any of the annotations expanded before it has expired,
+/// and prints those in their warning window, so `cargo test` reports upcoming expirations even
+/// while the crate compiles. With `strict`, annotations in their warning window fail it too.
+///
+/// Macros are expanded in source order, so place it in a test module at the end of the crate
+/// root, after every module containing annotations, as for [`allow_until_summary!`].
+///
+/// ```rust
+/// #[cfg(test)]
+/// mod allow_until_tests {
+///     allow_until::self_test!();
+/// }
+/// ```
+#[proc_macro]
+pub fn self_test(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Items, || {
+        self_test::expand(input)
+            .into_iter()
+            .chain(diagnostic::take(Place::Items))
+            .collect()
+    })
+}
+
+/// Expands to the version annotations without `version_source` are compared against, as a string
+/// literal, resolved as they resolve it: from `ALLOW_UNTIL_VERSION`, or else from the crate's
+/// `version_source`, or `CARGO_PKG_VERSION`.
+///
+/// ```rust
+/// let version: &str = allow_until::version!();
+/// assert_eq!(version, env!("CARGO_PKG_VERSION"));
+/// ```
+#[proc_macro]
+pub fn version(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Unknown, || {
+        expand_version(input, "version", |current| current.version.to_string())
+    })
+}
+
+/// Expands to where the version of [`version!`] was taken from, as a string literal such as
+/// "`CARGO_PKG_VERSION`" or "the git tag `v2.0.0`", worded as in the notes of expired annotations.
+///
+/// ```rust
+/// assert_eq!(allow_until::version_source!(), "`CARGO_PKG_VERSION`");
+/// ```
+#[proc_macro]
+pub fn version_source(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Unknown, || {
+        expand_version(input, "version_source", version::Current::describe_source)
+    })
+}
+
+/// The string literal `describe` makes of the current version for the macro `name`, which takes
+/// no arguments. Failing to resolve the version is an error at the call site.
+fn expand_version(
+    input: TokenStream,
+    name: &str,
+    describe: impl FnOnce(&version::Current) -> String,
+) -> TokenStream {
+    let at = Span::call_site();
+    if let Some(tok) = input.into_iter().next() {
+        diagnostic::error(
+            tok.span(),
+            Code::InvalidInput,
+            format!("`{}!` takes no arguments", name),
+        )
+        .emit();
+    }
+
+    let source = config::get()
+        .defaults
+        .version_source
+        .unwrap_or(VersionSource::Cargo);
+    let literal = match resolve_version(source, at) {
+        Ok(Some(current)) => describe(current),
+        Ok(None) => {
+            diagnostic::error(
+                at,
+                Code::NoVersion,
+                "no version is available, as `CARGO_PKG_VERSION` is not set",
+            )
+            .help(format!(
+                "set `{}` to the version annotations should be compared against",
+                version::OVERRIDE_VAR
+            ))
+            .emit();
+            String::new()
+        }
+        Err(e) => {
+            diagnostic::error(at, Code::NoVersion, e)
+                .help(format!(
+                    "set `{}` to the version annotations should be compared against",
+                    version::OVERRIDE_VAR
+                ))
+                .emit();
+            String::new()
+        }
+    };
+
+    // a block, so the `compile_error!` emitted on stable is still an expression
+    let mut body = diagnostic::take(Place::Unknown);
+    if body.is_empty() {
+        return TT::Literal(proc_macro::Literal::string(&literal)).into();
     }
+    body.extend([TT::Literal(proc_macro::Literal::string(&literal))]);
+    TT::Group(Group::new(Delimiter::Brace, body)).into()
+}
+
+/// Checks a version known at runtime, such as the version of the host a plugin is loaded into,
+/// against a requirement validated at compile time, returning
+/// `Result<(), allow_until_runtime::check::Expired>`. The crate must depend on
+/// `allow-until-runtime` with its `check` feature, which re-exports the macro.
+///
+/// ```rust
+/// use allow_until_runtime::semver::Version;
+///
+/// let host_version = Version::new(1, 4, 0);
+/// assert!(allow_until::runtime_check!(host_version, ">=2.0", "legacy hook API").is_ok());
+/// ```
+#[proc_macro]
+pub fn runtime_check(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Unknown, || {
+        runtime_check::expand(input, "runtime_check", "check")
+    })
+}
+
+/// Checks a version known at runtime against a requirement validated at compile time, as
+/// [`runtime_check!`] does, and warns once the requirement matches, through `tracing` or `log`
+/// with the features of `allow-until-runtime` of the same name, or else on stderr. Evaluates to
+/// whether it matched.
+///
+/// ```rust
+/// use allow_until_runtime::semver::Version;
+///
+/// let host_version = Version::new(1, 4, 0);
+/// assert!(!allow_until::warn_if_expired!(host_version, ">=2.0", "legacy hook API"));
+/// ```
+#[proc_macro]
+pub fn warn_if_expired(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Unknown, || {
+        runtime_check::expand(input, "warn_if_expired", "warn_if_expired")
+    })
+}
 
-    TokenStream::new()
+/// Expands to the long-form explanation of a diagnostic code, such as the `AU0001` prefixed to the
+/// errors of expired annotations, as a string literal.
+///
+/// ```rust
+/// let explanation: &str = allow_until::explain!("AU0001");
+/// assert!(explanation.starts_with("An annotation has expired."));
+/// ```
+#[proc_macro]
+pub fn explain(input: TokenStream) -> TokenStream {
+    guard(TokenStream::new(), Place::Unknown, || {
+        let code = syn::parse::<syn::LitStr>(input).map_err(|e| {
+            diagnostic::error(
+                e.span().unwrap(),
+                Code::InvalidInput,
+                "expected a diagnostic code, such as \"AU0001\"",
+            )
+        });
+        let code = code.and_then(|lit| {
+            Code::parse(&lit.value()).ok_or_else(|| {
+                diagnostic::error(
+                    lit.span().unwrap(),
+                    Code::InvalidInput,
+                    format!("unknown diagnostic code `{}`", lit.value()),
+                )
+                .help(format!(
+                    "codes range from {} to {}",
+                    Code::ALL[0].as_str(),
+                    Code::ALL[Code::ALL.len() - 1].as_str()
+                ))
+            })
+        });
+
+        match code {
+            Ok(code) => TT::Literal(proc_macro::Literal::string(code.explanation())).into(),
+            Err(e) => {
+                e.emit();
+                // a block, so the `compile_error!` emitted on stable is still an expression
+                let mut body = diagnostic::take(Place::Unknown);
+                body.extend([TT::Literal(proc_macro::Literal::string(""))]);
+                TT::Group(Group::new(Delimiter::Brace, body)).into()
+            }
+        }
+    })
 }
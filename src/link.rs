@@ -0,0 +1,157 @@
+//! `remove_with`, which links an annotation to the annotation with another `id`, so items that must
+//! be removed in the same release can't drift apart.
+//!
+//! The annotations of a compilation are expanded one at a time, so each link is checked by
+//! whichever of its two annotations comes second, and links whose id no annotation declares are
+//! reported by `allow_until_summary!()`.
+
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{
+    annotation::ParsedAnnotation,
+    code::Code,
+    diagnostic::{self, Diagnostic},
+    item::Item,
+    registry, version, warning_level,
+};
+
+/// A source location, as the file, line and column.
+type Location = (String, usize, usize);
+
+/// The annotation declaring an id, as far as its links are concerned.
+pub(crate) struct Declared {
+    /// The first version its requirement matches, if known.
+    pub removal: Option<Version>,
+    pub described: String,
+    pub location: Location,
+}
+
+/// A `remove_with` link to an id no annotation has declared so far.
+pub(crate) struct Link {
+    pub id: String,
+    /// The first version the requirement of the linking annotation matches, if known.
+    pub removal: Option<Version>,
+    pub described: String,
+    pub location: Location,
+}
+
+/// Records the id and the link of the annotation at `at`, warning about the links between it and
+/// annotations seen before that resolve to different removal versions.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    let removal = version::minimal_version(&args.version);
+    let described = item.map_or("item".into(), Item::describe);
+    let location = (at.file(), at.line(), at.column());
+
+    let (linking, target) = registry::with(|r| {
+        // the links waiting for this annotation's id, only the first annotation declaring it counts
+        let mut linking = Vec::new();
+        if let Some(id) = args.id.as_ref().filter(|id| !r.removals.contains_key(*id)) {
+            r.removals.insert(
+                id.clone(),
+                Declared {
+                    removal: removal.clone(),
+                    described: described.clone(),
+                    location: location.clone(),
+                },
+            );
+            let (resolved, pending) = std::mem::take(&mut r.links)
+                .into_iter()
+                .partition(|link: &Link| link.id == *id);
+            r.links = pending;
+            linking = resolved;
+        }
+
+        let mut target = None;
+        if let Some(id) = args
+            .remove_with
+            .as_ref()
+            .filter(|&id| Some(id) != args.id.as_ref())
+        {
+            match r.removals.get(id) {
+                Some(declared) => {
+                    target = Some((
+                        id.clone(),
+                        declared.removal.clone(),
+                        declared.described.clone(),
+                        declared.location.clone(),
+                    ))
+                }
+                None => r.links.push(Link {
+                    id: id.clone(),
+                    removal: removal.clone(),
+                    described: described.clone(),
+                    location: location.clone(),
+                }),
+            }
+        }
+        (linking, target)
+    });
+
+    let Some(removal) = removal else {
+        return;
+    };
+    let help = "give both requirements the same first matching version, so the items are removed \
+                in the same release";
+
+    if let Some((id, Some(theirs), what, (file, line, column))) = target {
+        if theirs != removal {
+            Diagnostic::spanned(
+                at,
+                warning_level(at),
+                Code::LinkMismatch,
+                format!(
+                    "{} is removed at {}, but `{}`, which `remove_with` links it to, is removed at \
+                     {}",
+                    described, removal, id, theirs
+                ),
+            )
+            .note(format!(
+                "`{}` is the id of the annotation of {} at {}:{}:{}",
+                id, what, file, line, column
+            ))
+            .help(help)
+            .emit();
+        }
+    }
+
+    for link in linking {
+        let Some(theirs) = link.removal.filter(|theirs| *theirs != removal) else {
+            continue;
+        };
+        let (file, line, column) = link.location;
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::LinkMismatch,
+            format!(
+                "{} is removed at {}, but {}, which `remove_with` links to it, is removed at {}",
+                described, removal, link.described, theirs
+            ),
+        )
+        .note(format!(
+            "linked with `remove_with = \"{}\"` at {}:{}:{}",
+            link.id, file, line, column
+        ))
+        .help(help)
+        .emit();
+    }
+}
+
+/// Warns about the links to ids that no annotation of the compilation declared.
+pub(crate) fn dangling(links: &[Link], at: Span) {
+    for link in links {
+        let (file, line, column) = &link.location;
+        diagnostic::warning(
+            at,
+            Code::LinkMismatch,
+            format!(
+                "{} is linked with `remove_with` to the id `{}`, which no annotation declares",
+                link.described, link.id
+            ),
+        )
+        .note(format!("linked at {}:{}:{}", file, line, column))
+        .help("check the id for typos, or remove `remove_with` if that annotation was deleted")
+        .emit();
+    }
+}
@@ -0,0 +1,18 @@
+//! Lints about the arguments of annotations, which are on by default and can be allowed with
+//! `allow_lint`, or for the whole crate with `allow_lint` in `[package.metadata.allow-until]`.
+
+use proc_macro::Span;
+use semver::VersionReq;
+
+pub(crate) use allow_until_core::lint::Lint;
+
+use crate::{code::Code, diagnostic};
+
+/// Warns about a requirement written as `text` that uses caret or tilde comparators.
+pub(crate) fn comparator_style(req: &VersionReq, text: &str, span: Span) {
+    if let Some(finding) = allow_until_core::lint::comparator_style(req, text) {
+        diagnostic::warning(span, Code::ComparatorStyle, finding.message)
+            .help(finding.help)
+            .emit();
+    }
+}
@@ -0,0 +1,46 @@
+//! Writing the files shared by the crates of a build, such as the report, the SARIF log and the
+//! baseline, which cargo compiles in parallel.
+//!
+//! Writers take turns through an advisory lock on a lock file next to the shared file, and files
+//! that are rewritten as a whole are replaced with a rename so readers never see them half-written.
+
+use std::{
+    fs::OpenOptions,
+    path::{Path, PathBuf},
+};
+
+/// Runs `f` while holding the lock file of `path`.
+///
+/// The lock is taken with `flock` (`LockFileEx` on Windows), which the OS releases when the
+/// process exits, so a crashed build can't leave it held. The file itself stays, as removing it
+/// would let a writer still waiting on it and one creating it anew hold the lock at once.
+pub(crate) fn locked<R>(path: &Path, f: impl FnOnce() -> std::io::Result<R>) -> std::io::Result<R> {
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sibling(path, "lock"))?;
+    lock.lock()?;
+
+    let result = f();
+    let _ = lock.unlock();
+    result
+}
+
+/// Replaces the contents of `path` with `contents` through a temporary file, which is only renamed
+/// over `path` once it was written completely.
+pub(crate) fn replace(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let tmp = sibling(path, &format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// `path` with `extension` appended to its file name, e.g. `report.jsonl.lock`.
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
@@ -0,0 +1,218 @@
+//! A cache of parsed manifests shared by everything that reads a `Cargo.toml`, so a crate with
+//! thousands of annotations still reads and parses each manifest once per compilation.
+//!
+//! Entries are keyed by path and invalidated when the file's modification time changes. The
+//! filesystem access goes through [`Loader`] so the cache can be exercised with manifests that
+//! only exist in memory.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::SystemTime,
+};
+
+use crate::tracked;
+
+use allow_until_core::config::Document;
+pub(crate) use allow_until_core::config::Manifest;
+
+pub(crate) trait Loader: Send + Sync {
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+struct Fs;
+
+impl Loader for Fs {
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        tracked::read_to_string(path)
+    }
+}
+
+pub(crate) struct Cache<L> {
+    loader: L,
+    entries: Mutex<HashMap<PathBuf, (Option<SystemTime>, Manifest)>>,
+    reads: AtomicUsize,
+}
+
+impl<L: Loader> Cache<L> {
+    pub fn new(loader: L) -> Cache<L> {
+        Cache {
+            loader,
+            entries: Mutex::new(HashMap::new()),
+            reads: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Manifest {
+        let modified = self.loader.modified(path);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some((cached, manifest)) = entries.get(path) {
+            if *cached == modified {
+                return manifest.clone();
+            }
+        }
+
+        let manifest = match self.loader.read(path) {
+            Ok(text) => {
+                self.reads.fetch_add(1, Ordering::Relaxed);
+                Document::parse(text)
+                    .map(|document| Some(Arc::new(document)))
+                    .map_err(|e| format!("failed to parse `{}`: {}", path.display(), e))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("failed to read `{}`: {}", path.display(), e)),
+        };
+
+        entries.insert(path.into(), (modified, manifest.clone()));
+        manifest
+    }
+
+    /// How many times a manifest was read and parsed, rather than taken from the cache. Looking for
+    /// a file that doesn't exist isn't counted.
+    pub fn reads(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+}
+
+fn cache() -> &'static Cache<Fs> {
+    static CACHE: OnceLock<Cache<Fs>> = OnceLock::new();
+
+    CACHE.get_or_init(|| Cache::new(Fs))
+}
+
+/// Reads the manifest at `path` through the shared cache.
+pub(crate) fn get(path: &Path) -> Manifest {
+    cache().get(path)
+}
+
+/// The manifest of the package being compiled.
+pub(crate) fn package() -> Manifest {
+    match tracked::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => get(&Path::new(&dir).join("Cargo.toml")),
+        None => Ok(None),
+    }
+}
+
+/// The root of the workspace of the package being compiled: the closest directory above it whose
+/// `Cargo.toml` has a `[workspace]` table, or else the package's own directory.
+pub(crate) fn workspace_root() -> Option<&'static Path> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    ROOT.get_or_init(|| {
+        let dir = PathBuf::from(tracked::var_os("CARGO_MANIFEST_DIR")?);
+        let root = dir.ancestors().find(|dir| {
+            get(&dir.join("Cargo.toml"))
+                .is_ok_and(|m| m.is_some_and(|m| m.contains_key("workspace")))
+        });
+        Some(root.unwrap_or(&dir).into())
+    })
+    .as_deref()
+}
+
+/// The target directory of the workspace: `CARGO_TARGET_DIR`, or `target` in the workspace root.
+pub(crate) fn target_dir() -> PathBuf {
+    let root = workspace_root().unwrap_or(Path::new(""));
+    // a relative `CARGO_TARGET_DIR` is taken as relative to the workspace root, where cargo usually
+    // runs
+    tracked::var_os("CARGO_TARGET_DIR").map_or(root.join("target"), |dir| root.join(dir))
+}
+
+/// How many manifests were read from disk in this compilation.
+pub(crate) fn reads() -> usize {
+    cache().reads()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Memory {
+        files: Mutex<HashMap<PathBuf, (SystemTime, String)>>,
+    }
+
+    impl Memory {
+        fn write(&self, path: &str, contents: &str) {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.into(), (SystemTime::now(), contents.into()));
+        }
+    }
+
+    impl Loader for Memory {
+        fn modified(&self, path: &Path) -> Option<SystemTime> {
+            self.files.lock().unwrap().get(path).map(|f| f.0)
+        }
+
+        fn read(&self, path: &Path) -> io::Result<String> {
+            self.files
+                .lock()
+                .unwrap()
+                .get(path)
+                .map(|f| f.1.clone())
+                .ok_or(io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn version(manifest: &Manifest) -> String {
+        manifest.as_ref().unwrap().as_ref().unwrap()["package"]["version"]
+            .as_str()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn reads_once() {
+        let cache = Cache::new(Memory::default());
+        cache
+            .loader
+            .write("Cargo.toml", "package.version = \"1.0.0\"");
+
+        for _ in 0..100 {
+            assert_eq!(version(&cache.get(Path::new("Cargo.toml"))), "1.0.0");
+        }
+        assert_eq!(cache.reads(), 1);
+    }
+
+    #[test]
+    fn invalidated_by_modification() {
+        let cache = Cache::new(Memory::default());
+        cache
+            .loader
+            .write("Cargo.toml", "package.version = \"1.0.0\"");
+        assert_eq!(version(&cache.get(Path::new("Cargo.toml"))), "1.0.0");
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        cache
+            .loader
+            .write("Cargo.toml", "package.version = \"2.0.0\"");
+
+        assert_eq!(version(&cache.get(Path::new("Cargo.toml"))), "2.0.0");
+        assert_eq!(cache.reads(), 2);
+    }
+
+    #[test]
+    fn missing_and_malformed() {
+        let cache = Cache::new(Memory::default());
+        cache.loader.write("bad/Cargo.toml", "[package");
+
+        assert!(matches!(cache.get(Path::new("Cargo.toml")), Ok(None)));
+        assert!(cache
+            .get(Path::new("bad/Cargo.toml"))
+            .unwrap_err()
+            .starts_with("failed to parse `bad/Cargo.toml`"));
+    }
+}
@@ -0,0 +1,339 @@
+//! `#[allow_members_until(version = "...")]` on an impl block or a trait, which applies one
+//! annotation to its members, so a block where several members are going away doesn't need an
+//! annotation on each.
+//!
+//! Members with an `#[allow_until(...)]` of their own keep it instead, and members marked
+//! `#[allow_until(skip)]` are exempt, the marker being removed from the block. `members = [...]`
+//! restricts the annotation to the listed members, every one of which must be a member of the
+//! block, so a rename can't leave the schedule pointing at nothing.
+
+use proc_macro::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{
+    ext::IdentExt, parse::Parser, punctuated::Punctuated, spanned::Spanned, Attribute, Ident,
+    ImplItem, Meta, Token, TraitItem,
+};
+
+use crate::{
+    annotation, args,
+    code::Code,
+    deprecate,
+    diagnostic::{self, Place},
+    item::{Item, Kind},
+    section, strip, suggest, test_guard,
+};
+
+/// A member of the block.
+struct Member {
+    name: String,
+    /// The span of its name, which its diagnostics point at.
+    span: Span,
+    tokens: TokenStream,
+    /// Its own `#[allow_until(...)]`, which takes precedence over the block's annotation.
+    own: Option<Span>,
+    /// Its `#[allow_until(skip)]`, which exempts it from the block's annotation.
+    skip: Option<Span>,
+}
+
+/// An `#[allow_until(...)]` among the attributes of a member.
+enum Marker {
+    /// `#[allow_until(skip)]`, with its span, or an annotation combining `skip` with arguments,
+    /// with the spans of `skip` and of the first other argument.
+    Skip(Span, Option<Span>),
+    /// An annotation of the member's own.
+    Own(Span),
+}
+
+/// Evaluates the annotation at `at` for each of the members of the block in `input`, or the
+/// listed ones, as if each carried it, except for those with an annotation of their own or marked
+/// `skip`. Returns the block, without the `skip` markers, with the diagnostics after it.
+pub(crate) fn expand(tokens: TokenStream, input: TokenStream, at: Span) -> TokenStream {
+    let mut block = match syn::parse::<syn::Item>(input.clone()) {
+        Ok(block @ (syn::Item::Impl(_) | syn::Item::Trait(_))) => block,
+        // anything that doesn't parse is left for the compiler to report
+        _ => {
+            diagnostic::error(
+                at,
+                Code::InvalidInput,
+                "`allow_members_until` only applies to impl blocks and traits",
+            )
+            .help("annotate the item itself with `#[allow_until(...)]`")
+            .emit();
+            return finish(input);
+        }
+    };
+    let described = match &block {
+        syn::Item::Trait(block) => format!("trait `{}`", block.ident.unraw()),
+        _ => "the impl block".to_string(),
+    };
+    let members = members(&mut block);
+
+    // rustc rejects `#[deprecated]` on the members of trait impls
+    let trait_impl = matches!(&block, syn::Item::Impl(block) if block.trait_.is_some());
+
+    // the markers are removed from the block whatever happens to the annotation
+    let mut changes = Changes::default();
+    let output = |block: &mut syn::Item, changes: &Changes| {
+        if members.iter().all(|member| member.skip.is_none())
+            && changes.stripped.is_empty()
+            && changes.deprecated.is_empty()
+        {
+            return finish(input.clone());
+        }
+        rewrite(block, changes);
+        finish(block.to_token_stream().into())
+    };
+
+    for member in &members {
+        if let (Some(skip), Some(own)) = (member.skip, member.own) {
+            diagnostic::error(
+                own,
+                Code::InvalidInput,
+                format!(
+                    "`{}` is marked `skip`, but has an annotation of its own",
+                    member.name
+                ),
+            )
+            .span_note(skip, "marked `skip` here")
+            .help("remove `#[allow_until(skip)]`, or the member's annotation")
+            .emit();
+        }
+    }
+
+    let mut parsed = match args::parse_allow_members_until(tokens.into()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            annotation::error(e).emit();
+            return output(&mut block, &changes);
+        }
+    };
+    let applied: Vec<&Member> = match parsed.members.take() {
+        Some(listed) => listed
+            .iter()
+            .filter_map(|(name, span)| listed_member(&members, &described, name, span.unwrap()))
+            .collect(),
+        None => members.iter().collect(),
+    };
+
+    let args = match annotation::resolve(parsed, at) {
+        Ok(args) => args,
+        Err(e) => {
+            e.emit();
+            return output(&mut block, &changes);
+        }
+    };
+    for member in applied {
+        if member.own.is_some() || member.skip.is_some() {
+            continue;
+        }
+        // the diagnostics point at the member's name, without a note pointing at it again
+        let mut item = Item {
+            grouped: true,
+            span: None,
+            ..Item::parse(member.tokens.clone())
+        };
+        // a `type` of an impl block parses as a type alias
+        if item.kind == Kind::TypeAlias {
+            item.kind = Kind::AssociatedType;
+        }
+        crate::emit_error_version_match(args.clone(), member.span, Some(&item));
+        if strip::take() {
+            changes.stripped.push(member.name.clone());
+        }
+        let from = args.deprecate_from.as_ref().map(|(from, _)| from);
+        if let Some(attribute) = deprecate::attribute(from, &item).filter(|_| !trait_impl) {
+            changes.deprecated.push((member.name.clone(), attribute));
+        }
+    }
+
+    output(&mut block, &changes)
+}
+
+/// What the annotation changes about the members of the block.
+#[derive(Default)]
+struct Changes {
+    /// The members that `ALLOW_UNTIL_STRIP` leaves out.
+    stripped: Vec<String>,
+    /// The members given a `#[deprecated(...)]` by `deprecate_from`, with its tokens.
+    deprecated: Vec<(String, proc_macro2::TokenStream)>,
+}
+
+/// The member `name` of the block listed in `members` at `span`, erroring when the block has no
+/// such member or it is marked `skip`.
+fn listed_member<'a>(
+    members: &'a [Member],
+    described: &str,
+    name: &str,
+    span: Span,
+) -> Option<&'a Member> {
+    let Some(member) = members.iter().find(|member| member.name == name) else {
+        let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).collect();
+        diagnostic::error(
+            span,
+            Code::InvalidInput,
+            format!("{} has no member `{}`", described, name),
+        )
+        .help(match suggest::closest(name, &names) {
+            Some(closest) => format!(
+                "did you mean `{}`? `members` must be updated when a member is renamed",
+                closest
+            ),
+            None => "remove it from `members` if it was removed already".into(),
+        })
+        .emit();
+        return None;
+    };
+
+    if let Some(skip) = member.skip {
+        diagnostic::error(
+            span,
+            Code::InvalidInput,
+            format!("`{}` is listed in `members`, but marked `skip`", name),
+        )
+        .span_note(skip, "marked `skip` here")
+        .help("remove it from `members`, or remove `#[allow_until(skip)]`")
+        .emit();
+    }
+    Some(member)
+}
+
+/// The members of `block`, an impl block or a trait, erroring about the annotations combining
+/// `skip` with arguments.
+fn members(block: &mut syn::Item) -> Vec<Member> {
+    let mut members = Vec::new();
+    let mut push = |tokens: proc_macro2::TokenStream,
+                    parts: Option<(&Ident, &mut Vec<Attribute>)>| {
+        let Some((ident, attrs)) = parts else {
+            return;
+        };
+        let mut member = Member {
+            name: ident.unraw().to_string(),
+            span: ident.span().unwrap(),
+            tokens: tokens.into(),
+            own: None,
+            skip: None,
+        };
+        for marker in attrs.iter().filter_map(marker) {
+            match marker {
+                Marker::Skip(skip, other) => {
+                    if let Some(other) = other {
+                        diagnostic::error(
+                            other,
+                            Code::InvalidInput,
+                            "`skip` can't be combined with other arguments",
+                        )
+                        .span_note(skip, "`skip` given here")
+                        .help(
+                            "a skipped member isn't annotated, remove `skip` or the other \
+                             arguments",
+                        )
+                        .emit();
+                    }
+                    member.skip.get_or_insert(skip);
+                }
+                Marker::Own(own) => {
+                    member.own.get_or_insert(own);
+                }
+            }
+        }
+        members.push(member);
+    };
+
+    match block {
+        syn::Item::Impl(block) => {
+            for item in &mut block.items {
+                push(item.to_token_stream(), impl_parts(item));
+            }
+        }
+        syn::Item::Trait(block) => {
+            for item in &mut block.items {
+                push(item.to_token_stream(), trait_parts(item));
+            }
+        }
+        _ => (),
+    }
+    members
+}
+
+/// Removes the `skip` markers from the members of `block`, and the members that
+/// `ALLOW_UNTIL_STRIP` leaves out, and deprecates those `deprecate_from` asks for.
+fn rewrite(block: &mut syn::Item, changes: &Changes) {
+    let kept = |parts: Option<(&Ident, &mut Vec<Attribute>)>| {
+        let Some((ident, attrs)) = parts else {
+            return true;
+        };
+        let name = ident.unraw().to_string();
+        attrs.retain(|attr| !matches!(marker(attr), Some(Marker::Skip(..))));
+        if let Some((_, attribute)) = changes.deprecated.iter().find(|(n, _)| *n == name) {
+            attrs.extend(
+                Attribute::parse_outer
+                    .parse2(attribute.clone())
+                    .unwrap_or_default(),
+            );
+        }
+        !changes.stripped.contains(&name)
+    };
+    match block {
+        syn::Item::Impl(block) => block.items.retain_mut(|item| kept(impl_parts(item))),
+        syn::Item::Trait(block) => block.items.retain_mut(|item| kept(trait_parts(item))),
+        _ => (),
+    }
+}
+
+/// The `#[allow_until(...)]` that `attr` is, if any.
+fn marker(attr: &Attribute) -> Option<Marker> {
+    let is_annotation = attr
+        .path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "allow_until");
+    if !is_annotation {
+        return None;
+    }
+
+    let own = Marker::Own(attr.span().unwrap());
+    let Ok(args) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+        return Some(own);
+    };
+    let Some(skip) = args.iter().find(|arg| arg.path().is_ident("skip")) else {
+        return Some(own);
+    };
+    // a marker on its own is pointed at as a whole
+    Some(match args.iter().find(|arg| !arg.path().is_ident("skip")) {
+        Some(other) => Marker::Skip(skip.span().unwrap(), Some(other.span().unwrap())),
+        None => Marker::Skip(attr.span().unwrap(), None),
+    })
+}
+
+/// The name and the attributes of a member of an impl block, `None` for macros and the like.
+fn impl_parts(item: &mut ImplItem) -> Option<(&Ident, &mut Vec<Attribute>)> {
+    match item {
+        ImplItem::Fn(f) => Some((&f.sig.ident, &mut f.attrs)),
+        ImplItem::Const(c) => Some((&c.ident, &mut c.attrs)),
+        ImplItem::Type(t) => Some((&t.ident, &mut t.attrs)),
+        _ => None,
+    }
+}
+
+/// The name and the attributes of a member of a trait, `None` for macros and the like.
+fn trait_parts(item: &mut TraitItem) -> Option<(&Ident, &mut Vec<Attribute>)> {
+    match item {
+        TraitItem::Fn(f) => Some((&f.sig.ident, &mut f.attrs)),
+        TraitItem::Const(c) => Some((&c.ident, &mut c.attrs)),
+        TraitItem::Type(t) => Some((&t.ident, &mut t.attrs)),
+        _ => None,
+    }
+}
+
+/// The block, followed by the diagnostics emitted on stable and what else is pending, as impl
+/// blocks and traits are always among items.
+fn finish(input: TokenStream) -> TokenStream {
+    // the documentation of the members is left as it is
+    crate::doctest::discard();
+    input
+        .into_iter()
+        .chain(diagnostic::take(Place::Items))
+        .chain(section::take())
+        .chain(test_guard::take())
+        .collect()
+}
@@ -0,0 +1,81 @@
+//! `min_notice`, the policy that an item is deprecated for long enough before it is removed, checked
+//! once its annotation has expired.
+
+use proc_macro::Span;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, diagnostic, item::Item, version, version::Distance,
+};
+
+/// Errors when `min_notice` applies to the expired annotation at `at` and its item wasn't
+/// deprecated, with `deprecate_from` or `#[deprecated(since = "...")]`, at least that long before
+/// the first version its requirement matches.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    let Some(notice) = args.min_notice else {
+        return;
+    };
+    let described = item.map_or("item".into(), Item::describe);
+    let name = item.and_then(|item| item.span).unwrap_or(at);
+
+    // the argument is more specific than the attribute, which may predate the annotation
+    let (deprecated, span) = match (
+        &args.deprecate_from,
+        item.and_then(|item| item.deprecated_since.as_ref()),
+    ) {
+        (Some((version, span)), _) => (version, Some(*span)),
+        (None, Some(version)) => (version, None),
+        (None, None) => {
+            diagnostic::error(
+                name,
+                Code::ShortNotice,
+                format!(
+                    "`min_notice` requires the version the expired {} was deprecated in",
+                    described
+                ),
+            )
+            .help(
+                "add `deprecate_from = \"...\"` to the annotation, or `#[deprecated(since = \
+                 \"...\")]` to the item, with the version it was deprecated in",
+            )
+            .emit();
+            return;
+        }
+    };
+
+    let Some(removal) = version::minimal_version(&args.version) else {
+        return;
+    };
+    let required = notice.add_to(deprecated);
+    if removal >= required {
+        return;
+    }
+
+    let notice_given = match Distance::between(deprecated, &removal) {
+        Some(given) if removal > *deprecated => format!("{} after", given.releases()),
+        _ if removal == *deprecated => "in the same release as".into(),
+        _ => "before".into(),
+    };
+    let mut diag = diagnostic::error(
+        name,
+        Code::ShortNotice,
+        format!(
+            "{} is removed at {}, {} its deprecation at {}, but `min_notice` requires {}",
+            described,
+            removal,
+            notice_given,
+            deprecated,
+            notice.releases()
+        ),
+    );
+    if let Some(span) = span {
+        diag = diag.span_note(span, format!("deprecated at {}", deprecated));
+    }
+    diag.help(format!(
+        "remove it from {} on, with `version = \">={}\"`, or deprecate it at least {} before {}",
+        required,
+        required,
+        notice.releases(),
+        removal
+    ))
+    .emit();
+}
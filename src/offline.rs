@@ -0,0 +1,131 @@
+//! The offline mode for hermetic builds, such as Nix or Bazel sandboxes, asked for with
+//! `ALLOW_UNTIL_OFFLINE=1` or the `deterministic` feature.
+//!
+//! Every source of information from outside the source tree and the build's environment asks the
+//! [`Policy`] of the compilation before it is consulted, so the expansion only depends on the
+//! inputs the build declares. Sources that aren't consulted are warned about, and values that can
+//! be given through the environment instead, such as the version a git tag would provide, are used
+//! as usual.
+
+use std::sync::OnceLock;
+
+use proc_macro::Span;
+
+use crate::{code::Code, diagnostic, env, tracked};
+
+pub(crate) const OFFLINE_VAR: &str = "ALLOW_UNTIL_OFFLINE";
+
+/// A source of information from outside the source tree.
+#[derive(Clone, Copy)]
+// without the optional features, only the git sources remain
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum Source {
+    /// Requesting `issue` URLs, with `ALLOW_UNTIL_VERIFY_ISSUES=1`.
+    #[cfg(feature = "online-checks")]
+    IssueTracker,
+    /// The local registry index cache, with `ALLOW_UNTIL_INDEX_CHECK=1`.
+    #[cfg(feature = "publish-check")]
+    RegistryIndex,
+    /// `git describe`, for `version_source = "git"`.
+    GitDescribe,
+    /// `git blame`, with `ALLOW_UNTIL_BLAME=1`.
+    GitBlame,
+    /// `git rev-parse`, for `branch` and `branch_pattern`.
+    GitBranch,
+    /// `git tag`, for `enforce = "tagged-builds"`.
+    GitTag,
+}
+
+impl Source {
+    fn describe(self) -> &'static str {
+        match self {
+            #[cfg(feature = "online-checks")]
+            Source::IssueTracker => "the issue tracker, for `ALLOW_UNTIL_VERIFY_ISSUES`,",
+            #[cfg(feature = "publish-check")]
+            Source::RegistryIndex => "the registry index cache, for `ALLOW_UNTIL_INDEX_CHECK`,",
+            Source::GitDescribe => "`git describe`",
+            Source::GitBlame => "`git blame`, for `ALLOW_UNTIL_BLAME`,",
+            Source::GitBranch => "`git rev-parse`, for `branch`,",
+            Source::GitTag => "`git tag`, for `enforce`,",
+        }
+    }
+
+    /// The key of the warning about the source not being consulted, emitted once.
+    fn key(self) -> &'static str {
+        match self {
+            #[cfg(feature = "online-checks")]
+            Source::IssueTracker => "offline-issue-tracker",
+            #[cfg(feature = "publish-check")]
+            Source::RegistryIndex => "offline-registry-index",
+            Source::GitDescribe => "offline-git-describe",
+            Source::GitBlame => "offline-git-blame",
+            Source::GitBranch => "offline-git-branch",
+            Source::GitTag => "offline-git-tag",
+        }
+    }
+}
+
+/// Which sources outside the source tree the compilation may consult.
+#[derive(Clone, Copy)]
+pub(crate) struct Policy {
+    /// The switch that asked for the offline mode, `None` when it wasn't.
+    offline: Option<&'static str>,
+}
+
+impl Policy {
+    /// `Ok` when `source` may be consulted, otherwise why it isn't.
+    pub fn check(self, source: Source) -> Result<(), String> {
+        match self.offline {
+            None => Ok(()),
+            Some(switch) => Err(format!(
+                "{} is not consulted, as {} asks for a hermetic build",
+                source.describe(),
+                switch
+            )),
+        }
+    }
+
+    /// Whether `source` may be consulted, warning once per source at `at` when it isn't.
+    pub fn allows(self, source: Source, at: Span) -> bool {
+        let Err(e) = self.check(source) else {
+            return true;
+        };
+        if env::once(source.key()) {
+            diagnostic::warning(at, Code::Offline, e).emit();
+        }
+        false
+    }
+}
+
+/// The policy of the compilation. An invalid [`OFFLINE_VAR`] value is warned about once, at the
+/// `at` of the first call, and ignored.
+pub(crate) fn policy(at: Span) -> Policy {
+    static OFFLINE: OnceLock<Result<Option<&'static str>, String>> = OnceLock::new();
+
+    let offline = OFFLINE.get_or_init(|| {
+        if cfg!(feature = "deterministic") {
+            return Ok(Some("the `deterministic` feature"));
+        }
+        match tracked::var(OFFLINE_VAR) {
+            Ok(v) => match v.trim() {
+                "1" => Ok(Some("`ALLOW_UNTIL_OFFLINE=1`")),
+                "" | "0" => Ok(None),
+                _ => Err(format!(
+                    "ignoring invalid `{}` value `{}`, expected `1`",
+                    OFFLINE_VAR, v
+                )),
+            },
+            Err(_) => Ok(None),
+        }
+    });
+
+    match offline {
+        Ok(offline) => Policy { offline: *offline },
+        Err(e) => {
+            if env::once("offline-invalid") {
+                diagnostic::warning(at, Code::InvalidEnv, e.as_str()).emit();
+            }
+            Policy { offline: None }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! `max_pending!(5, version = ">=2.0")`, which caps how many of the annotations expanded before it
+//! may still be pending for a milestone: those whose requirement first matches at or before the
+//! first version the milestone's requirement matches.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use proc_macro::{Span, TokenStream};
+use semver::Version;
+use syn::{parse::Parser, punctuated::Punctuated, spanned::Spanned, Expr, ExprLit, Lit, Token};
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, item::Item, schedule, version};
+
+/// An evaluated annotation, as counted against the budgets.
+struct Entry {
+    /// The first version its requirement matches, `None` when it has no lower bound.
+    expiry: Option<Version>,
+    /// The item, its requirement, its id or fingerprint and its location, e.g. "struct `Xml`
+    /// (`>=2.0`, id `xml`) at src/lib.rs:3:1".
+    described: String,
+}
+
+/// The annotations evaluated so far by their location, so an annotation expanded more than once
+/// is only counted once.
+static ENTRIES: Mutex<BTreeMap<(String, usize, usize), Entry>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn record(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    let location = (schedule::relative(&at.file()), at.line(), at.column());
+    let identity = match (&args.id, &args.fingerprint) {
+        (Some(id), _) => format!(", id `{}`", id),
+        (None, Some(fingerprint)) => format!(", fingerprint `{}`", fingerprint),
+        (None, None) => String::new(),
+    };
+    let described = format!(
+        "{} (`{}`{}) at {}:{}:{}",
+        item.map_or("item".into(), Item::describe),
+        args.version,
+        identity,
+        location.0,
+        location.1,
+        location.2
+    );
+
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        location,
+        Entry {
+            expiry: version::minimal_version(&args.version),
+            described,
+        },
+    );
+}
+
+/// Errors when more of the annotations recorded so far are pending for the milestone than the
+/// budget allows, with a note for each of them.
+pub(crate) fn expand(input: TokenStream) {
+    let Some((budget, milestone, text)) = parse(input) else {
+        return;
+    };
+
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let pending: Vec<&Entry> = entries
+        .values()
+        .filter(|entry| entry.expiry.as_ref().is_some_and(|v| *v <= milestone))
+        .collect();
+    if pending.len() <= budget {
+        return;
+    }
+
+    let mut diag = diagnostic::error(
+        Span::call_site(),
+        Code::PendingOverBudget,
+        format!(
+            "{} annotations are pending for `{}`, but `max_pending!` allows {}",
+            pending.len(),
+            text,
+            budget
+        ),
+    );
+    for entry in pending {
+        diag = diag.note(format!("pending: {}", entry.described));
+    }
+    diag.help("remove the items that are due, or raise the budget deliberately")
+        .emit();
+}
+
+/// The budget, the first version the milestone's requirement matches and the requirement as
+/// written, or `None` once the error is emitted.
+fn parse(input: TokenStream) -> Option<(usize, Version, String)> {
+    let usage = "write `max_pending!(5, version = \">=2.0\")`";
+    let error = |span: Span, message: String| {
+        diagnostic::error(span, Code::InvalidInput, message)
+            .help(usage)
+            .emit();
+        None
+    };
+
+    let args = match Punctuated::<Expr, Token![,]>::parse_terminated.parse(input) {
+        Ok(args) => args,
+        Err(e) => return error(e.span().unwrap(), e.to_string()),
+    };
+    let [budget, milestone]: [Expr; 2] = match args.into_iter().collect::<Vec<_>>().try_into() {
+        Ok(args) => args,
+        Err(args) => {
+            return error(
+                Span::call_site(),
+                format!(
+                    "`max_pending!` takes a budget and a `version`, but {} arguments were given",
+                    args.len()
+                ),
+            )
+        }
+    };
+
+    let budget = match &budget {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => match int.base10_parse::<usize>() {
+            Ok(budget) => budget,
+            Err(e) => return error(int.span().unwrap(), e.to_string()),
+        },
+        expr => {
+            return error(
+                expr.span().unwrap(),
+                "expected the budget as an integer literal".into(),
+            )
+        }
+    };
+
+    let text = match &milestone {
+        Expr::Assign(assign) => match (&*assign.left, &*assign.right) {
+            (
+                Expr::Path(path),
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }),
+            ) if path.path.is_ident("version") => s.clone(),
+            _ => {
+                return error(
+                    assign.span().unwrap(),
+                    "expected `version = \"...\"`".into(),
+                )
+            }
+        },
+        expr => return error(expr.span().unwrap(), "expected `version = \"...\"`".into()),
+    };
+
+    let req = match version::parse_req(&text.value()) {
+        Ok(req) => req,
+        Err(e) => {
+            diagnostic::error(
+                text.span().unwrap(),
+                Code::InvalidRequirement,
+                format!("invalid semver requirement `{}`: {}", text.value(), e),
+            )
+            .help("requirements look like `\">=2.0\"` or `\">=1.4, <2\"`")
+            .emit();
+            return None;
+        }
+    };
+    match version::minimal_version(&req) {
+        Some(milestone) => Some((budget, milestone, text.value())),
+        None => error(
+            text.span().unwrap(),
+            format!("`{}` has no first version to count up to", text.value()),
+        ),
+    }
+}
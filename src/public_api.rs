@@ -0,0 +1,194 @@
+//! Cross-referencing annotations with a listing of the crate's public API, such as the output of
+//! `cargo public-api`, named by `ALLOW_UNTIL_PUBLIC_API`, so reports tell annotations on public
+//! items from those on internal ones and `public_api` in the configuration can report the former
+//! more severely.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use proc_macro::Span;
+
+use crate::{
+    code::Code,
+    diagnostic, env,
+    item::{Item, Kind},
+    tracked,
+};
+
+pub(crate) const PUBLIC_API_VAR: &str = "ALLOW_UNTIL_PUBLIC_API";
+
+/// The paths of a listing, without generics, and the last segment of each.
+struct Listing {
+    paths: BTreeSet<String>,
+    names: BTreeSet<String>,
+}
+
+/// Whether `item`, annotated at `at`, is in the listing of `ALLOW_UNTIL_PUBLIC_API`: `Some(true)`
+/// when its path is listed, `Some(false)` when no listed path even ends in its name, and `None`
+/// when there is no listing or it can't tell, such as for a method whose type isn't known.
+pub(crate) fn check(item: Option<&Item>, at: Span) -> Option<bool> {
+    let path = tracked::var_os(PUBLIC_API_VAR).map(tracked::user_path)?;
+    let name = item?.name.as_deref()?;
+
+    static LISTING: OnceLock<Result<Listing, String>> = OnceLock::new();
+
+    tracked::path(&path);
+    let listing = match LISTING.get_or_init(|| read(&path)) {
+        Ok(listing) => listing,
+        Err(e) => {
+            if env::once("public-api") {
+                diagnostic::warning(at, Code::FileAccess, e.as_str()).emit();
+            }
+            return None;
+        }
+    };
+
+    let last = name.rsplit("::").next().unwrap_or(name);
+    if !listing.names.contains(last) {
+        return Some(false);
+    }
+    let path = item_path(item?, name, at)?;
+    listing.paths.contains(&path).then_some(true)
+}
+
+/// The path of `item` as the listing would have it, such as `acme::config::Loader`, if it can be
+/// told from the file of the annotation. Items of inline modules are taken to be in the module of
+/// the file, and associated items can't be told from free ones of the same name.
+fn item_path(item: &Item, name: &str, at: Span) -> Option<String> {
+    if !item.free && !matches!(item.kind, Kind::Field | Kind::Variant) {
+        return None;
+    }
+    let krate = tracked::var("CARGO_CRATE_NAME").ok()?;
+    let file = at.local_file()?;
+    let file = std::env::current_dir().map_or(file.clone(), |dir| dir.join(&file));
+    let root = PathBuf::from(tracked::var_os("CARGO_MANIFEST_DIR")?);
+    let relative = file.strip_prefix(&root).ok()?;
+
+    let mut path = vec![krate];
+    path.extend(module(relative)?);
+    path.push(name.into());
+    Some(path.join("::"))
+}
+
+/// The modules of the file at `relative`, from the package root: none for the crate root, `a::b`
+/// for `src/a/b.rs` or `src/a/b/mod.rs`, and `None` outside of `src`.
+fn module(relative: &Path) -> Option<Vec<String>> {
+    let parts: Vec<&str> = relative
+        .iter()
+        .map(|part| part.to_str())
+        .collect::<Option<_>>()?;
+    match &parts[..] {
+        ["lib.rs" | "main.rs"] | ["src", "lib.rs" | "main.rs"] => Some(Vec::new()),
+        ["src", modules @ .., file] => {
+            let mut modules: Vec<String> = modules.iter().map(|&m| m.into()).collect();
+            match file.strip_suffix(".rs")? {
+                "mod" => (),
+                file => modules.push(file.into()),
+            }
+            Some(modules)
+        }
+        _ => None,
+    }
+}
+
+fn read(path: &Path) -> Result<Listing, String> {
+    let contents = std::fs::read(path)
+        .map(|data| tracked::text(&data))
+        .map_err(|e| {
+            format!(
+                "failed to read the public API listing `{}`: {}",
+                tracked::shown(path),
+                e
+            )
+        })?;
+
+    let paths: BTreeSet<String> = contents.lines().filter_map(listed_path).collect();
+    let names = paths
+        .iter()
+        .map(|path| path.rsplit("::").next().unwrap_or(path).into())
+        .collect();
+    Ok(Listing { paths, names })
+}
+
+/// The words that may come between `pub` and the path of an item in a listing.
+const QUALIFIERS: &[&str] = &[
+    "async", "auto", "const", "enum", "extern", "fn", "macro", "mod", "mut", "static", "struct",
+    "trait", "type", "union", "unsafe",
+];
+
+/// The path of the item on a line of a listing, without generics, such as `acme::Index::new` for
+/// `pub fn acme::Index<K, S>::new() -> Self`. Lines other than `pub` items, such as impls, have
+/// none.
+fn listed_path(line: &str) -> Option<String> {
+    let mut rest = line.trim().strip_prefix("pub ")?;
+    while let Some((word, after)) = rest.split_once(' ') {
+        if !QUALIFIERS.contains(&word) && !word.starts_with('"') {
+            break;
+        }
+        rest = after;
+    }
+
+    let mut path = String::new();
+    let mut depth = 0;
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth > 0 => depth -= 1,
+            _ if depth > 0 => (),
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                path.push_str("::");
+            }
+            c if c.is_alphanumeric() || c == '_' => path.push(c),
+            _ => break,
+        }
+    }
+    (!path.is_empty()).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_of_a_listing() {
+        let cases = [
+            ("pub mod acme::config", Some("acme::config")),
+            ("pub struct acme::Index<K, S>", Some("acme::Index")),
+            (
+                "pub fn acme::Index<K, S>::new() -> Self",
+                Some("acme::Index::new"),
+            ),
+            ("pub const fn acme::limit() -> usize", Some("acme::limit")),
+            (
+                "pub unsafe extern \"C\" fn acme::acme_init()",
+                Some("acme::acme_init"),
+            ),
+            (
+                "pub acme::Index::keys: alloc::vec::Vec<K>",
+                Some("acme::Index::keys"),
+            ),
+            ("pub acme::Mode::Legacy(u8)", Some("acme::Mode::Legacy")),
+            ("pub const acme::MAX: usize", Some("acme::MAX")),
+            ("pub type acme::Alias<'a> = &'a str", Some("acme::Alias")),
+            ("impl core::clone::Clone for acme::Index", None),
+        ];
+        for (line, path) in cases {
+            assert_eq!(listed_path(line).as_deref(), path, "{}", line);
+        }
+    }
+
+    #[test]
+    fn modules_of_files() {
+        let module = |path: &str| module(Path::new(path)).map(|m| m.join("::"));
+        assert_eq!(module("src/lib.rs").as_deref(), Some(""));
+        assert_eq!(module("src/config.rs").as_deref(), Some("config"));
+        assert_eq!(module("src/config/mod.rs").as_deref(), Some("config"));
+        assert_eq!(module("src/config/xml.rs").as_deref(), Some("config::xml"));
+        assert_eq!(module("tests/config.rs"), None);
+    }
+}
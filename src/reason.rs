@@ -0,0 +1,31 @@
+//! The quality of `reason` literals, checked against `reason_min_words` and `reason_deny_patterns`
+//! when the annotation is written rather than once it expires.
+
+use proc_macro::Span;
+
+use crate::{
+    code::Code,
+    config::{self, Severity},
+    diagnostic::{Diagnostic, Level},
+    warning_level,
+};
+
+/// Reports the `reason` literal at `span` of the annotation at `at` when it has fewer words than
+/// `reason_min_words`, or contains one of the `reason_deny_patterns`, ignoring case.
+pub(crate) fn check(reason: &str, span: Span, at: Span) {
+    let config = config::get();
+    let Some(message) = config.reason_problem(reason) else {
+        return;
+    };
+
+    let level = match config.reason_severity {
+        Some(Severity::Error) => Level::Error,
+        _ => warning_level(at),
+    };
+    Diagnostic::spanned(span, level, Code::PoorReason, message)
+        .help(
+            "say why the item goes and what replaces it, e.g. `reason = \"the XML backend is \
+             unmaintained, use `Json`\"`",
+        )
+        .emit();
+}
@@ -0,0 +1,269 @@
+//! State shared between all the annotations expanded in one compilation, reported on by
+//! `allow_until_summary!`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{code::Code, diagnostic, env, link};
+
+#[derive(Default)]
+pub(crate) struct Registry {
+    /// The ids of every annotation evaluated so far.
+    pub ids: BTreeSet<String>,
+    /// The source location of the first annotation declaring each id.
+    pub declared: BTreeMap<String, (String, usize, usize)>,
+    /// The source location of the first annotation with each fingerprint.
+    pub fingerprints: BTreeMap<String, (String, usize, usize)>,
+    /// The ids of every expired annotation evaluated so far, whether or not it was reported.
+    pub expired: BTreeSet<String>,
+    /// The ids of expired annotations whose diagnostics were suppressed by `ALLOW_UNTIL_SUPPRESS`.
+    pub suppressed: Vec<String>,
+    /// The source locations of the annotations counted against `ALLOW_UNTIL_MAX`, so an
+    /// annotation that is expanded more than once is only counted once.
+    pub counted: BTreeSet<(String, usize, usize)>,
+    /// The source locations of the expiry errors reported in full, at most `max_errors`.
+    pub errors: BTreeSet<(String, usize, usize)>,
+    /// The source locations of the expiry errors omitted as `max_errors` was reached.
+    pub omitted: BTreeSet<(String, usize, usize)>,
+    /// The source location of the expiry error reported in full with `ALLOW_UNTIL_FAIL_FAST=1`.
+    pub first_expired: Option<(String, usize, usize)>,
+    /// The source locations of the expiry errors noted briefly after it.
+    pub briefly_noted: BTreeSet<(String, usize, usize)>,
+    /// The source locations of the annotations noted with `always_note`, at most `max_errors`.
+    pub noted: BTreeSet<(String, usize, usize)>,
+    /// The source locations of the annotations not noted as `max_errors` was reached.
+    pub unnoted: BTreeSet<(String, usize, usize)>,
+    /// How many annotations were evaluated against a version.
+    pub evaluated: usize,
+    /// How many annotations haven't expired yet.
+    pub active: usize,
+    /// How many expired annotations are still in their grace period.
+    pub in_grace: usize,
+    /// The earliest version at which an active annotation expires, and what expires then.
+    pub next_expiry: Option<(Version, String)>,
+    /// The first annotation declaring each id, for the `remove_with` links to it.
+    pub removals: BTreeMap<String, link::Declared>,
+    /// The `remove_with` links to ids no annotation declared so far.
+    pub links: Vec<link::Link>,
+    /// The source locations of the `changes_at` attributes warned about, so an item expanded more
+    /// than once is only warned about once.
+    pub changed: BTreeSet<(String, usize, usize)>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    ids: BTreeSet::new(),
+    declared: BTreeMap::new(),
+    fingerprints: BTreeMap::new(),
+    expired: BTreeSet::new(),
+    suppressed: Vec::new(),
+    counted: BTreeSet::new(),
+    errors: BTreeSet::new(),
+    omitted: BTreeSet::new(),
+    first_expired: None,
+    briefly_noted: BTreeSet::new(),
+    noted: BTreeSet::new(),
+    unnoted: BTreeSet::new(),
+    evaluated: 0,
+    active: 0,
+    in_grace: 0,
+    next_expiry: None,
+    removals: BTreeMap::new(),
+    links: Vec::new(),
+    changed: BTreeSet::new(),
+});
+
+pub(crate) fn with<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    f(&mut REGISTRY.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Emits the build-wide diagnostics that can only be decided once every annotation was seen.
+pub(crate) fn summarize(at: Span) {
+    with(|registry| {
+        if registry.evaluated > 0 {
+            let mut overview = format!(
+                "allow-until: {} active allowance{}, {} in warning window",
+                registry.active,
+                if registry.active == 1 { "" } else { "s" },
+                registry.in_grace
+            );
+            if let Some((version, what)) = &registry.next_expiry {
+                overview.push_str(&format!(", next expiry at {} ({})", version, what));
+            }
+
+            diagnostic::note(at, Code::Summary, overview).emit();
+        }
+
+        if !registry.suppressed.is_empty() {
+            diagnostic::warning(
+                at,
+                Code::Suppressed,
+                format!(
+                    "`{}` suppressed {} expired annotation(s): {}",
+                    env::SUPPRESS_VAR,
+                    registry.suppressed.len(),
+                    list(&registry.suppressed)
+                ),
+            )
+            .emit();
+        }
+
+        if let Ok(Some(max)) = env::max() {
+            if registry.counted.len() > max {
+                diagnostic::error(at, Code::OverBudget, format!(
+                    "the crate contains {} allow_until annotations, but the budget set by `{}` is {}",
+                    registry.counted.len(),
+                    env::MAX_VAR,
+                    max
+                ))
+                .emit();
+            }
+        }
+
+        if !registry.omitted.is_empty() {
+            diagnostic::error(
+                at,
+                Code::ErrorsOmitted,
+                format!(
+                    "…and {} more item{} expired; run with `{}=1` for the full list",
+                    registry.omitted.len(),
+                    if registry.omitted.len() == 1 { "" } else { "s" },
+                    env::VERBOSE_VAR
+                ),
+            )
+            .emit();
+        }
+
+        if !registry.briefly_noted.is_empty() {
+            diagnostic::note(
+                at,
+                Code::ErrorsOmitted,
+                format!(
+                    "…and {} more item{} expired after the first, noted briefly as `{}=1` is set",
+                    registry.briefly_noted.len(),
+                    if registry.briefly_noted.len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    },
+                    env::FAIL_FAST_VAR
+                ),
+            )
+            .emit_visible();
+        }
+
+        if !registry.unnoted.is_empty() {
+            diagnostic::note(
+                at,
+                Code::Allowance,
+                format!(
+                    "…and {} more allowance{} not noted; run with `{}=1` for the full list",
+                    registry.unnoted.len(),
+                    if registry.unnoted.len() == 1 { "" } else { "s" },
+                    env::VERBOSE_VAR
+                ),
+            )
+            .emit_visible();
+        }
+
+        link::dangling(&registry.links, at);
+
+        let stale: Vec<_> = env::suppressed_ids()
+            .iter()
+            .filter(|id| !registry.ids.contains(*id) && !registry.fingerprints.contains_key(*id))
+            .cloned()
+            .collect();
+
+        if !stale.is_empty() {
+            diagnostic::warning(
+                at,
+                Code::StaleSuppression,
+                format!(
+                    "`{}` lists ids that match no annotation: {}",
+                    env::SUPPRESS_VAR,
+                    list(&stale)
+                ),
+            )
+            .help("remove them from the variable if the annotations were deleted")
+            .emit();
+        }
+
+        expectations(registry, at);
+    });
+}
+
+/// Errors about the ids listed in `ALLOW_UNTIL_EXPECTED` whose annotations haven't expired.
+fn expectations(registry: &Registry, at: Span) {
+    let expected = env::expected_ids();
+    let (unknown, unexpired, suppressed) = expected.iter().fold(
+        (Vec::new(), Vec::new(), Vec::new()),
+        |(mut unknown, mut unexpired, mut suppressed), id| {
+            if !registry.ids.contains(id) {
+                unknown.push(id.clone());
+            } else if !registry.expired.contains(id) {
+                unexpired.push(id.clone());
+            }
+            if env::suppressed_ids().contains(id) {
+                suppressed.push(id.clone());
+            }
+            (unknown, unexpired, suppressed)
+        },
+    );
+
+    if !unexpired.is_empty() {
+        diagnostic::error(
+            at,
+            Code::NotExpired,
+            format!(
+                "`{}` expects these annotations to have expired, but their requirements don't \
+                 match: {}",
+                env::EXPECTED_VAR,
+                list(&unexpired)
+            ),
+        )
+        .note("the release being built is expected to remove them")
+        .help("check their `version` requirements against the version being built")
+        .emit();
+    }
+    if !unknown.is_empty() {
+        diagnostic::error(
+            at,
+            Code::NotExpired,
+            format!(
+                "`{}` expects annotations that weren't seen to have expired: {}",
+                env::EXPECTED_VAR,
+                list(&unknown)
+            ),
+        )
+        .help("check the ids for typos; if the annotations were already removed, remove the ids")
+        .emit();
+    }
+    if !suppressed.is_empty() {
+        diagnostic::error(
+            at,
+            Code::NotExpired,
+            format!(
+                "ids are both expected to expire and suppressed: {}",
+                list(&suppressed)
+            ),
+        )
+        .note(format!(
+            "`{}` would hide the expiry that `{}` asks to prove",
+            env::SUPPRESS_VAR,
+            env::EXPECTED_VAR
+        ))
+        .help("remove them from one of the two variables")
+        .emit();
+    }
+}
+
+fn list(ids: &[String]) -> String {
+    ids.iter()
+        .map(|id| format!("`{}`", id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,263 @@
+//! `#[renamed_until(old = "...", version = "...")]`, which keeps a deprecated function under the old
+//! name of a renamed one, forwarding to it, until the requirement matches.
+
+use proc_macro::{Span, TokenStream};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
+use semver::VersionReq;
+use syn::{
+    parse::Parser, punctuated::Punctuated, spanned::Spanned, Expr, ExprLit, FnArg, GenericParam,
+    ItemFn, Lit, MetaNameValue, Token, TraitItemFn,
+};
+
+use crate::{
+    code::Code,
+    config,
+    diagnostic::{self, Diagnostic},
+    item::mentions_self,
+    offline,
+    version::{self, VersionSource},
+};
+
+/// The arguments of `renamed_until`.
+struct Args {
+    /// The name the function had before.
+    old: syn::Ident,
+    version: VersionReq,
+}
+
+fn parse_args(tokens: TokenStream2) -> Result<Args, Diagnostic> {
+    let pairs = Punctuated::<MetaNameValue, Token![,]>::parse_terminated
+        .parse2(tokens)
+        .map_err(|e| {
+            diagnostic::error(e.span().unwrap(), Code::InvalidInput, e.to_string())
+                .help("arguments are `old = \"name\"` and `version = \"requirement\"`")
+        })?;
+
+    let (mut old, mut version) = (None, None);
+    for pair in pairs {
+        let span = pair.value.span().unwrap();
+        let Expr::Lit(ExprLit {
+            lit: Lit::Str(value),
+            ..
+        }) = &pair.value
+        else {
+            return Err(diagnostic::error(
+                span,
+                Code::InvalidInput,
+                "expected string literal",
+            ));
+        };
+
+        if pair.path.is_ident("old") {
+            old = Some(value.parse::<syn::Ident>().map_err(|_| {
+                diagnostic::error(
+                    span,
+                    Code::InvalidInput,
+                    format!("`{}` is not a valid function name", value.value()),
+                )
+            })?);
+        } else if pair.path.is_ident("version") {
+            version = Some(version::parse_req(&value.value()).map_err(|e| {
+                diagnostic::error(
+                    span,
+                    Code::InvalidRequirement,
+                    format!("invalid semver requirement `{}`: {}", value.value(), e),
+                )
+                .help("requirements look like `\">=2.0\"` or `\">=1.4, <2\"`")
+            })?);
+        } else {
+            return Err(diagnostic::error(
+                pair.path.span().unwrap(),
+                Code::InvalidInput,
+                format!("unknown argument `{}`", pair.path.to_token_stream()),
+            )
+            .help("valid arguments are `old` and `version`"));
+        }
+    }
+
+    let (code, missing) = match (old, version) {
+        (Some(old), Some(version)) => return Ok(Args { old, version }),
+        (None, _) => (Code::InvalidInput, "old"),
+        (_, None) => (Code::MissingVersion, "version"),
+    };
+    Err(diagnostic::error(
+        Span::call_site(),
+        code,
+        format!("missing required `{}` argument", missing),
+    )
+    .help("e.g. #[renamed_until(old = \"connect_tls\", version = \">=2.0\")]"))
+}
+
+/// The function `renamed_until` is applied to, which may be a method or a trait item.
+struct Function {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    sig: syn::Signature,
+    /// Whether the function has no body, which only trait items can lack.
+    bodyless: bool,
+}
+
+fn parse_function(input: TokenStream) -> Option<Function> {
+    if let Ok(f) = syn::parse::<ItemFn>(input.clone()) {
+        return Some(Function {
+            attrs: f.attrs,
+            vis: f.vis,
+            sig: f.sig,
+            bodyless: false,
+        });
+    }
+    // functions without a body, in traits
+    syn::parse::<TraitItemFn>(input).ok().map(|f| Function {
+        attrs: f.attrs,
+        vis: syn::Visibility::Inherited,
+        bodyless: f.default.is_none(),
+        sig: f.sig,
+    })
+}
+
+/// Expands `#[renamed_until]` on the function `input` to the forwarding function under the old name,
+/// or to nothing once the requirement matches.
+pub(crate) fn expand(args: TokenStream, input: TokenStream) -> TokenStream {
+    let at = Span::call_site();
+    let Some(function) = parse_function(input) else {
+        diagnostic::error(
+            at,
+            Code::InvalidInput,
+            "`renamed_until` can only be applied to functions",
+        )
+        .emit();
+        return TokenStream::new();
+    };
+    let args = match parse_args(args.into()) {
+        Ok(args) => args,
+        Err(e) => {
+            e.emit();
+            return TokenStream::new();
+        }
+    };
+
+    let source = config::get()
+        .defaults
+        .version_source
+        .unwrap_or(VersionSource::Cargo);
+    let current = version::resolve(source, offline::policy(at), |e| {
+        diagnostic::warning(
+            at,
+            Code::VersionFallback,
+            format!("{}, falling back to `CARGO_PKG_VERSION`", e),
+        )
+        .emit()
+    });
+
+    // without a version, the old name is kept rather than breaking its callers
+    if let Ok(Some(current)) = current {
+        if args.version.matches(&current.version) {
+            Diagnostic::spanned(
+                at,
+                crate::warning_level(at),
+                Code::RenameExpired,
+                format!(
+                    "`{}` is no longer generated, as version {} matches {}",
+                    args.old, current.version, args.version
+                ),
+            )
+            .note(format!(
+                "it forwarded to `{}`, which callers must use now",
+                function.sig.ident
+            ))
+            .help("remove the `renamed_until` attribute")
+            .emit();
+            return TokenStream::new();
+        }
+    }
+
+    match shim(&function, &args) {
+        Ok(shim) => shim.into(),
+        Err(e) => {
+            e.emit();
+            TokenStream::new()
+        }
+    }
+}
+
+/// A deprecated function called `args.old` with the signature of `function`, calling it with its
+/// arguments.
+fn shim(function: &Function, args: &Args) -> Result<TokenStream2, Diagnostic> {
+    let new = &function.sig.ident;
+    let mut sig = function.sig.clone();
+    sig.ident = args.old.clone();
+
+    if let Some(variadic) = &sig.variadic {
+        return Err(diagnostic::error(
+            variadic.span().unwrap(),
+            Code::InvalidInput,
+            "variadic functions can't be forwarded",
+        ));
+    }
+
+    // patterns such as `(a, b): (u8, u8)` or `mut a: u8` become plain names
+    let mut receiver = None;
+    let mut forwarded = Vec::new();
+    for (i, input) in sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(r) => {
+                // `mut self`, as opposed to `&mut self`
+                if r.reference.is_none() {
+                    r.mutability = None;
+                }
+                receiver = Some(quote!(self));
+            }
+            FnArg::Typed(typed) => {
+                let name = format_ident!("arg{}", i);
+                *typed.pat = syn::parse_quote!(#name);
+                forwarded.push(name);
+            }
+        }
+    }
+
+    let turbofish: Vec<_> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(t) => Some(t.ident.to_token_stream()),
+            GenericParam::Const(c) => Some(c.ident.to_token_stream()),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = (!turbofish.is_empty()).then(|| quote!(::<#(#turbofish),*>));
+
+    // associated functions can't be told apart from free ones, except by their use of `Self`
+    let associated =
+        receiver.is_some() || function.bodyless || mentions_self(function.sig.to_token_stream());
+    let mut call = match (receiver, associated) {
+        (Some(receiver), _) => quote!(Self::#new #turbofish(#receiver, #(#forwarded),*)),
+        (None, true) => quote!(Self::#new #turbofish(#(#forwarded),*)),
+        (None, false) => quote!(#new #turbofish(#(#forwarded),*)),
+    };
+    if sig.asyncness.is_some() {
+        call = quote!(#call.await);
+    }
+    if sig.unsafety.is_some() {
+        call = quote!(unsafe { #call });
+    }
+
+    let note = format!("renamed to `{}`, removed in {}", new, args.version);
+    let doc = format!("Renamed to [`{}`].", new);
+    let cfgs = function
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("cfg_attr"));
+    let vis = &function.vis;
+
+    Ok(quote! {
+        #(#cfgs)*
+        #[doc = #doc]
+        #[deprecated(note = #note)]
+        #[inline]
+        #vis #sig {
+            #call
+        }
+    })
+}
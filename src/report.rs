@@ -0,0 +1,258 @@
+//! The machine-readable report of every evaluated annotation, appended to the file named by
+//! `ALLOW_UNTIL_REPORT` as one JSON object per line, or to a file of each package in the directory
+//! it names.
+//!
+//! Entries are the [`Annotation`]s of the `allow-until-report` crate, which tools reading the report
+//! deserialize them with. Their fields are documented in the README.
+//!
+//! Crates built in parallel append to the same report, taking turns as described in [`lock`].
+//! Entries already in the report are not appended again, so the targets of a package that compile
+//! the same file, such as its library and its tests, don't duplicate its entries.
+
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use allow_until_report::{
+    Annotation, AnnotationKind, Condition, Origin, Outcome, Symbol, SCHEMA_VERSION, UNKNOWN_FILE,
+};
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{
+    annotation::ParsedAnnotation, blame, code::Code, config::Severity, diagnostic, env, item::Item,
+    lock, manifest, offline, public_api, strip, tracked,
+};
+
+pub(crate) const REPORT_VAR: &str = "ALLOW_UNTIL_REPORT";
+
+/// What the compilation has read of the report so far.
+#[derive(Default)]
+struct Seen {
+    /// The length of the report when it was last read or written.
+    len: u64,
+    /// The number of lines up to `len`.
+    lines: usize,
+    /// The entries up to `len`, as serialized by `serde_json`.
+    entries: HashSet<String>,
+}
+
+/// Appends an entry for the annotation to the report, if one was requested and the report doesn't
+/// have an identical entry yet.
+///
+/// Failing to write the report never fails the build, it only produces a (single) warning. Lines
+/// that aren't entries are left alone, and reported with a (single) warning.
+pub(crate) fn record(
+    args: &ParsedAnnotation,
+    annotation_kind: AnnotationKind,
+    item: Option<&Item>,
+    at: Span,
+    version: &Version,
+    matched: bool,
+    window: bool,
+) {
+    let Some(value) = tracked::var_os(REPORT_VAR) else {
+        return;
+    };
+
+    let (file, line, column) = location(at);
+    let entry = Annotation {
+        schema_version: SCHEMA_VERSION,
+        annotation_kind,
+        condition: Condition {
+            requirement: args.version.to_string(),
+            version: version.to_string(),
+        },
+        outcome: Outcome {
+            matched,
+            severity: match args.severity.unwrap_or(Severity::Error) {
+                Severity::Error => allow_until_report::Severity::Error,
+                Severity::Warning => allow_until_report::Severity::Warning,
+            },
+            silent: args.silent,
+            window,
+            stripped: strip::pending(),
+        },
+        reason: args.reason.clone(),
+        id: args.id.clone(),
+        tags: args.tags.clone(),
+        owner: args.owner.clone(),
+        issue: args.issue.clone(),
+        migration: args.migration.clone(),
+        fingerprint: args.fingerprint.clone(),
+        package: tracked::var("CARGO_PKG_NAME").ok(),
+        kind: item.map_or("item", |i| i.kind.as_str()).into(),
+        item: item.and_then(|i| i.display_name.clone().or_else(|| i.name.clone())),
+        is_public_api: public_api::check(item, at),
+        symbols: item.map_or(Vec::new(), |i| {
+            i.symbols
+                .iter()
+                .map(|s| Symbol {
+                    name: s.name.clone(),
+                    abi: s.abi.clone(),
+                    imported: s.imported,
+                })
+                .collect()
+        }),
+        file,
+        line,
+        column,
+        origin: diagnostic::origin(at).map(|origin| {
+            let (file, line, column) = location(origin);
+            Origin { file, line, column }
+        }),
+        blame: (blame::enabled() && offline::policy(at).allows(offline::Source::GitBlame, at))
+            .then(|| blame::line(&at.file(), at.line()))
+            .flatten(),
+    };
+
+    let (path, per_package) = destination(&value);
+    let created = match path.parent() {
+        Some(dir) if per_package => std::fs::create_dir_all(dir),
+        _ => Ok(()),
+    };
+    match created.and_then(|()| lock::locked(&path, || append(&path, entry.to_line()))) {
+        Ok(None) => (),
+        Ok(Some(line)) => {
+            if env::once("report-corrupted") {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "line {} of the allow-until report `{}` is not a valid entry, the report \
+                         may be corrupted",
+                        line,
+                        path.display()
+                    ),
+                )
+                .help("remove the report before the build to get a fresh one")
+                .emit();
+            }
+        }
+        Err(e) => {
+            if env::once("report") {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "failed to write the allow-until report to `{}`: {}",
+                        tracked::shown(&path),
+                        e
+                    ),
+                )
+                .emit();
+            }
+        }
+    }
+}
+
+/// The file entries are appended to, and whether it is one of a directory of files per package:
+/// the path in `ALLOW_UNTIL_REPORT`, or `<package>.json` in it when it is a directory, which is
+/// `allow-until` in the target directory of the workspace for `1`.
+fn destination(value: &OsStr) -> (PathBuf, bool) {
+    let dir = if value == "1" {
+        manifest::target_dir().join("allow-until")
+    } else if Path::new(value).is_dir() || value.to_string_lossy().ends_with(['/', '\\']) {
+        tracked::user_path(value)
+    } else {
+        return (tracked::user_path(value), false);
+    };
+
+    let package = tracked::var("CARGO_PKG_NAME").unwrap_or_else(|_| "unknown".into());
+    (dir.join(format!("{}.json", package)), true)
+}
+
+/// The file of `at`, relative to the workspace root with forward slashes so reports of different
+/// machines can be compared, with its line and column. The file is [`UNKNOWN_FILE`] and the line
+/// and column are 0 when the span has no source file on disk or one outside of the package and its
+/// workspace. Annotations generated by another macro have the location in its definition, and
+/// their entries the invocation they originate from as well.
+fn location(at: Span) -> (String, usize, usize) {
+    let unknown = (UNKNOWN_FILE.into(), 0, 0);
+    let Some(file) = at.local_file() else {
+        return unknown;
+    };
+    // rustc is given paths relative to the directory it runs in, the workspace root under cargo
+    let file = std::env::current_dir().map_or(file.clone(), |dir| dir.join(&file));
+
+    let roots = [
+        manifest::workspace_root().map(Path::to_owned),
+        tracked::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from),
+    ];
+    let Some(relative) = roots
+        .iter()
+        .flatten()
+        .find_map(|root| file.strip_prefix(root).ok())
+    else {
+        return unknown;
+    };
+
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (relative, at.line(), at.column())
+}
+
+/// Appends `entry` to the report at `path` unless it is there already, returning the number of
+/// the first line that isn't a valid entry, if any. Only what other crates appended since the
+/// last call is read.
+fn append(path: &Path, entry: String) -> std::io::Result<Option<usize>> {
+    static READ: Mutex<Option<Seen>> = Mutex::new(None);
+
+    let mut read = READ.lock().unwrap_or_else(|e| e.into_inner());
+    let read = read.get_or_insert_with(Seen::default);
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+
+    // the report was removed or truncated since
+    if file.metadata()?.len() < read.len {
+        *read = Seen::default();
+    }
+
+    let mut new = Vec::new();
+    file.seek(SeekFrom::Start(read.len))?;
+    file.read_to_end(&mut new)?;
+
+    let mut corrupted = None;
+    for line in String::from_utf8_lossy(&new).split_inclusive('\n') {
+        read.lines += 1;
+        // a line cut short by a crashed build is followed by the next entry on the same line
+        match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(value) if line.ends_with('\n') && value.is_object() => {
+                read.entries.insert(value.to_string());
+            }
+            _ => {
+                corrupted.get_or_insert(read.lines);
+            }
+        }
+    }
+    read.len += new.len() as u64;
+
+    if read.entries.insert(entry.clone()) {
+        // a single `write_all` of a whole line, after the end of a line cut short
+        let mut line = if new.last().is_some_and(|&b| b != b'\n') {
+            "\n".to_string()
+        } else {
+            String::new()
+        };
+        line.push_str(&entry);
+        line.push('\n');
+
+        file.write_all(line.as_bytes())?;
+        read.len += line.len() as u64;
+        read.lines += 1;
+    }
+
+    Ok(corrupted)
+}
@@ -0,0 +1,96 @@
+//! The `runtime-metadata` feature, with which `#[derive(AllowUntil)]` implements
+//! `allow_until_runtime::AllowUntilMetadata`, listing the annotated members of the type so they
+//! can be inspected at runtime, e.g. by a config loader warning about deprecated keys. With the
+//! `serde` feature, [`serde_keys`](crate::serde_keys) implements finding those keys on top.
+
+use std::cell::RefCell;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{ext::IdentExt, DeriveInput};
+
+use crate::{annotation::ParsedAnnotation, item::Item};
+
+/// An annotated member, as it is listed.
+struct Member {
+    name: String,
+    requirement: String,
+    reason: Option<String>,
+    replacement: Option<String>,
+}
+
+thread_local! {
+    static MEMBERS: RefCell<Vec<Member>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Lists the member `item` annotated with `args` in the output of the current derive, with the
+/// feature enabled.
+pub(crate) fn record(item: &Item, args: &ParsedAnnotation) {
+    if !cfg!(feature = "runtime-metadata") {
+        return;
+    }
+    let member = Member {
+        name: item.name.clone().unwrap_or_default(),
+        requirement: args.version.to_string(),
+        reason: args.reason.clone(),
+        replacement: args.replacement.clone(),
+    };
+    MEMBERS.with(|members| members.borrow_mut().push(member));
+}
+
+/// The implementation of the trait for the derive's `input`, listing the members recorded since the
+/// last call, and nothing without the feature.
+pub(crate) fn take(input: &DeriveInput) -> TokenStream {
+    let members = MEMBERS.with(|members| members.take());
+    if !cfg!(feature = "runtime-metadata") {
+        return TokenStream::new();
+    }
+
+    // the names of members start with the type's, which the trait's user already knows
+    let prefix = format!("{}::", input.ident.unraw());
+    let names: Vec<_> = members
+        .iter()
+        .map(|member| member.name.strip_prefix(&prefix).unwrap_or(&member.name))
+        .collect();
+    let keys = if cfg!(feature = "serde") {
+        crate::serde_keys::implement(input, &names)
+    } else {
+        proc_macro2::TokenStream::new()
+    };
+    let members = members.iter().zip(&names).map(|(member, name)| {
+        let requirement = &member.requirement;
+        let reason = option(member.reason.as_deref());
+        let replacement = option(member.replacement.as_deref());
+        quote! {
+            ::allow_until_runtime::MemberDeprecation {
+                name: #name,
+                requirement: #requirement,
+                reason: #reason,
+                replacement: #replacement,
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::allow_until_runtime::AllowUntilMetadata for #ident #ty_generics
+        #where_clause
+        {
+            fn deprecated_members() -> &'static [::allow_until_runtime::MemberDeprecation] {
+                const MEMBERS: &[::allow_until_runtime::MemberDeprecation] = &[#(#members),*];
+                MEMBERS
+            }
+        }
+
+        #keys
+    }
+    .into()
+}
+
+fn option(value: Option<&str>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    }
+}
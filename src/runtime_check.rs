@@ -0,0 +1,105 @@
+//! `runtime_check!(version, "requirement", "reason")` and `warn_if_expired!`, whose requirement is
+//! validated at compile time as an annotation's is, and evaluated at runtime against a version the
+//! caller supplies, by the `check` feature of `allow-until-runtime`.
+
+use proc_macro::{Delimiter, Group, Span, TokenStream, TokenTree as TT};
+use quote::{format_ident, quote};
+use syn::{parse::Parser, punctuated::Punctuated, spanned::Spanned, Expr, ExprLit, Lit, Token};
+
+use crate::{
+    code::Code,
+    diagnostic::{self, Place},
+    version,
+};
+
+/// Expands the macro `name` to a call to `function` in `allow_until_runtime::check`, with the
+/// requirement normalized so that it parses at runtime.
+pub(crate) fn expand(input: TokenStream, name: &str, function: &str) -> TokenStream {
+    let Some((value, requirement, reason)) = parse(input, name) else {
+        // a block, so the `compile_error!` emitted on stable is still an expression
+        let mut body = diagnostic::take(Place::Unknown);
+        body.extend(TokenStream::from(quote!(::core::unreachable!())));
+        return TT::Group(Group::new(Delimiter::Brace, body)).into();
+    };
+
+    let function = format_ident!("{}", function);
+    quote! {
+        ::allow_until_runtime::check::#function(&(#value), #requirement, #reason)
+    }
+    .into()
+}
+
+/// The version expression, the normalized requirement and the reason, or `None` once the error
+/// is emitted.
+fn parse(input: TokenStream, name: &str) -> Option<(Expr, String, String)> {
+    let usage = || {
+        format!(
+            "write `{}!(host_version, \">=2.0\", \"legacy hook API\")`",
+            name
+        )
+    };
+
+    let args = match Punctuated::<Expr, Token![,]>::parse_terminated.parse(input) {
+        Ok(args) => args,
+        Err(e) => {
+            diagnostic::error(e.span().unwrap(), Code::InvalidInput, e.to_string())
+                .help(usage())
+                .emit();
+            return None;
+        }
+    };
+    let [value, requirement, reason] = match args.into_iter().collect::<Vec<_>>().try_into() {
+        Ok(args) => args,
+        Err(args) => {
+            let args: Vec<Expr> = args;
+            diagnostic::error(
+                Span::call_site(),
+                Code::InvalidInput,
+                format!(
+                    "`{}!` takes a version, a requirement and a reason, but {} arguments were \
+                     given",
+                    name,
+                    args.len()
+                ),
+            )
+            .help(usage())
+            .emit();
+            return None;
+        }
+    };
+
+    let literal = |expr: &Expr, what: &str| {
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) = expr
+        {
+            return Some(s.value());
+        }
+        diagnostic::error(
+            expr.span().unwrap(),
+            Code::InvalidInput,
+            format!("expected the {} as a string literal", what),
+        )
+        .help(usage())
+        .emit();
+        None
+    };
+    let (text, reason) = (
+        literal(&requirement, "requirement")?,
+        literal(&reason, "reason")?,
+    );
+
+    match version::parse_req(&text) {
+        Ok(req) => Some((value, req.to_string(), reason)),
+        Err(e) => {
+            diagnostic::error(
+                requirement.span().unwrap(),
+                Code::InvalidRequirement,
+                format!("invalid semver requirement `{}`: {}", text, e),
+            )
+            .help("requirements look like `\">=2.0\"` or `\">=1.4, <2\"`")
+            .emit();
+            None
+        }
+    }
+}
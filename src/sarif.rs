@@ -0,0 +1,167 @@
+//! The SARIF 2.1.0 log of every evaluated annotation, written to the file named by
+//! `ALLOW_UNTIL_SARIF` for code scanning dashboards.
+//!
+//! Each crate contributes one run, identified by its crate name, that is rewritten whenever an
+//! annotation is evaluated. Crates built in parallel share the file as described in [`lock`].
+
+use std::{path::Path, sync::Mutex};
+
+use proc_macro::Span;
+use semver::Version;
+use serde_json::{json, Value};
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, config::Severity, diagnostic, env, item::Item, lock,
+    message, tracked,
+};
+
+pub(crate) const SARIF_VAR: &str = "ALLOW_UNTIL_SARIF";
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+static RESULTS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// Adds a result for the annotation to the crate's run, if a SARIF log was requested.
+pub(crate) fn record(
+    args: &ParsedAnnotation,
+    item: Option<&Item>,
+    at: Span,
+    version: &Version,
+    matched: bool,
+) {
+    let Some(path) = tracked::var_os(SARIF_VAR) else {
+        return;
+    };
+
+    let severity = args.severity.unwrap_or(Severity::Error);
+    let described = item.map_or("item".into(), Item::describe);
+    let message = if matched {
+        message::expired(&described, args.reason.as_deref(), version, &args.version)
+    } else {
+        message::allowed(&described, version, &args.version)
+    };
+
+    let result = json!({
+        "ruleId": rule_id(severity),
+        "level": if matched { severity.as_str() } else { "note" },
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": at.file().replace('\\', "/") },
+                "region": { "startLine": at.line(), "startColumn": at.column() },
+            },
+        }],
+        "partialFingerprints": { "allowUntil/v1": args.fingerprint },
+        "properties": {
+            "id": args.id,
+            "tags": args.tags,
+            "requirement": args.version.to_string(),
+            "version": version.to_string(),
+            "matched": matched,
+            "silent": args.silent,
+            "migration": args.migration,
+        },
+    });
+
+    let mut results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    results.push(result);
+    write(&tracked::user_path(path), &results, at);
+}
+
+/// Makes sure the crate's run exists even when it has no annotations.
+pub(crate) fn finish(at: Span) {
+    let Some(path) = tracked::var_os(SARIF_VAR) else {
+        return;
+    };
+
+    let results = RESULTS.lock().unwrap_or_else(|e| e.into_inner());
+    write(&tracked::user_path(path), &results, at);
+}
+
+fn rule_id(severity: Severity) -> String {
+    format!("allow-until/{}", severity.as_str())
+}
+
+fn write(path: &Path, results: &[Value], at: Span) {
+    match lock::locked(path, || replace_run(path, results)) {
+        Ok(true) => (),
+        // the runs of other crates are lost, but the log is valid again
+        Ok(false) => {
+            if env::once("sarif-corrupted") {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "the SARIF log `{}` was not valid SARIF and has been replaced",
+                        path.display()
+                    ),
+                )
+                .emit();
+            }
+        }
+        Err(e) => {
+            if env::once("sarif") {
+                diagnostic::warning(
+                    at,
+                    Code::FileAccess,
+                    format!(
+                        "failed to write the SARIF log to `{}`: {}",
+                        path.display(),
+                        e
+                    ),
+                )
+                .emit();
+            }
+        }
+    }
+}
+
+/// Replaces the crate's run in the log, returning whether the existing log was valid. A log that
+/// isn't is replaced by one only holding the crate's run.
+fn replace_run(path: &Path, results: &[Value]) -> std::io::Result<bool> {
+    let crate_name = tracked::var("CARGO_CRATE_NAME").unwrap_or_default();
+
+    let (mut runs, valid) = match std::fs::read(path) {
+        Ok(contents) if contents.trim_ascii().is_empty() => (Vec::new(), true),
+        Ok(contents) => match serde_json::from_slice::<Value>(&contents)
+            .ok()
+            .and_then(|log| log.get("runs")?.as_array().cloned())
+        {
+            Some(runs) => (runs, true),
+            None => (Vec::new(), false),
+        },
+        Err(_) => (Vec::new(), true),
+    };
+    runs.retain(|run| run["automationDetails"]["id"] != crate_name.as_str());
+
+    let rules: Vec<_> = [Severity::Error, Severity::Warning]
+        .into_iter()
+        .map(|s| {
+            json!({
+                "id": rule_id(s),
+                "shortDescription": {
+                    "text": format!("allow_until annotation with severity `{}`", s.as_str()),
+                },
+                "defaultConfiguration": { "level": s.as_str() },
+            })
+        })
+        .collect();
+
+    runs.push(json!({
+        "tool": {
+            "driver": {
+                "name": "allow-until",
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                "rules": rules,
+            },
+        },
+        "automationDetails": { "id": crate_name },
+        "results": results,
+    }));
+
+    let log = json!({ "$schema": SCHEMA, "version": "2.1.0", "runs": runs });
+
+    lock::replace(path, format!("{:#}\n", log))?;
+    Ok(valid)
+}
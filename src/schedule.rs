@@ -0,0 +1,197 @@
+//! The Markdown deprecation schedule written to the file named by `ALLOW_UNTIL_SCHEDULE`, which
+//! groups annotations by the version they expire at for release planning, and the documentation of
+//! the module generated by `deprecation_schedule_module!`, which publishes it in rustdoc.
+//!
+//! The document only depends on the annotations, not on the order in which they were expanded, so
+//! it can be committed and diffed.
+
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+use proc_macro::{Span, TokenStream};
+use semver::Version;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, diagnostic, env, item::Item, lock, message, tracked,
+    version,
+};
+
+pub(crate) const SCHEDULE_VAR: &str = "ALLOW_UNTIL_SCHEDULE";
+
+struct Entry {
+    /// `(file, line, column)`, the order of the entries within a section.
+    location: (String, usize, usize),
+    requirement: String,
+    item: String,
+    reason: Option<String>,
+    owner: Option<String>,
+    issue: Option<String>,
+}
+
+static ENTRIES: Mutex<BTreeMap<Option<Version>, Vec<Entry>>> = Mutex::new(BTreeMap::new());
+
+/// Adds the annotation to the schedule and rewrites the document, if one was requested.
+///
+/// A `{crate}` in the path is replaced by the crate name, to give every crate of a workspace its
+/// own document.
+pub(crate) fn record(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    let entry = Entry {
+        location: (relative(&at.file()), at.line(), at.column()),
+        requirement: args.version.to_string(),
+        item: item.map_or("item".into(), Item::describe),
+        reason: args.reason.clone(),
+        owner: args.owner.clone(),
+        issue: args.issue.clone(),
+    };
+
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let section = entries
+        .entry(version::minimal_version(&args.version))
+        .or_default();
+    let at_index = section.partition_point(|e| e.location < entry.location);
+    section.insert(at_index, entry);
+
+    let Ok(path) = tracked::var(SCHEDULE_VAR) else {
+        return;
+    };
+    let crate_name = tracked::var("CARGO_PKG_NAME").unwrap_or_default();
+    let path = path.replace("{crate}", &crate_name);
+
+    // each crate has its own document, but its targets, such as its library and its tests, may be
+    // compiled in parallel
+    let path = &tracked::user_path(path);
+    if let Err(e) = lock::locked(path, || lock::replace(path, render(&crate_name, &entries))) {
+        if env::once("schedule") {
+            diagnostic::warning(
+                at,
+                Code::FileAccess,
+                format!(
+                    "failed to write the deprecation schedule to `{}`: {}",
+                    path.display(),
+                    e
+                ),
+            )
+            .emit();
+        }
+    }
+}
+
+/// The empty `deprecation_schedule` module, documented with the schedule of the annotations
+/// recorded so far.
+pub(crate) fn module() -> TokenStream {
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let doc = render_doc(&entries);
+
+    quote::quote! {
+        #[doc = #doc]
+        pub mod deprecation_schedule {}
+    }
+    .into()
+}
+
+/// The sections of the schedule, by the version they expire at, with the annotations without a
+/// lower bound last.
+fn sections(
+    entries: &BTreeMap<Option<Version>, Vec<Entry>>,
+) -> impl Iterator<Item = (&Option<Version>, &Vec<Entry>)> {
+    entries
+        .iter()
+        .filter(|(v, _)| v.is_some())
+        .chain(entries.iter().filter(|(v, _)| v.is_none()))
+}
+
+/// The documentation of the `deprecation_schedule` module: a heading per version, listing the
+/// items removed then with the first line of their reason, and the rest of it, usually how to
+/// replace them, below.
+fn render_doc(entries: &BTreeMap<Option<Version>, Vec<Entry>>) -> String {
+    let mut out = String::from(
+        "The removal plan of this crate: the items that will be removed, by the first version \
+         without them.\n",
+    );
+    if entries.is_empty() {
+        out.push_str("\nNothing is scheduled for removal.\n");
+    }
+
+    for (version, section) in sections(entries) {
+        match version {
+            Some(v) => out.push_str(&format!("\n# {}\n\n", v)),
+            None => out.push_str("\n# Without a minimum version\n\n"),
+        }
+
+        for e in section {
+            out.push_str(&format!("- {}, `{}`", e.item, e.requirement));
+            let (reason, rest) = e.reason.as_deref().map_or(("", None), message::first_line);
+            if !reason.is_empty() {
+                out.push_str(&format!(": {}", reason));
+            }
+            if let Some(issue) = &e.issue {
+                out.push_str(&format!(" ({})", issue));
+            }
+            out.push('\n');
+            // the rest of the reason is a paragraph of its own within the list item
+            if let Some(rest) = rest.map(str::trim).filter(|rest| !rest.is_empty()) {
+                out.push('\n');
+                for line in rest.lines().map(str::trim) {
+                    match line {
+                        "" => out.push('\n'),
+                        line => out.push_str(&format!("  {}\n", line)),
+                    }
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+fn render(crate_name: &str, entries: &BTreeMap<Option<Version>, Vec<Entry>>) -> String {
+    let mut out = format!("# Deprecation schedule for `{}`\n", crate_name);
+
+    for (version, section) in sections(entries) {
+        match version {
+            Some(v) => out.push_str(&format!("\n## {}\n\n", v)),
+            None => out.push_str("\n## Without a minimum version\n\n"),
+        }
+        out.push_str("| Item | Requirement | Reason | Owner | Issue | Location |\n");
+        out.push_str("|------|-------------|--------|-------|-------|----------|\n");
+
+        for e in section {
+            let issue = e.issue.as_deref().map(|issue| {
+                if issue.starts_with("http://") || issue.starts_with("https://") {
+                    format!("[{}]({})", issue, issue)
+                } else {
+                    issue.into()
+                }
+            });
+
+            out.push_str(&format!(
+                "| {} | `{}` | {} | {} | {} | {}:{} |\n",
+                cell(&e.item),
+                e.requirement,
+                cell(e.reason.as_deref().unwrap_or("")),
+                cell(e.owner.as_deref().unwrap_or("")),
+                cell(issue.as_deref().unwrap_or("")),
+                cell(&e.location.0),
+                e.location.1
+            ));
+        }
+    }
+
+    out
+}
+
+/// Escapes the characters that would break out of a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// The path of a source file relative to the package root, when it is inside it.
+pub(crate) fn relative(file: &str) -> String {
+    let Some(root) = tracked::var_os("CARGO_MANIFEST_DIR") else {
+        return file.into();
+    };
+
+    Path::new(file)
+        .strip_prefix(root)
+        .map_or(file.into(), |p| p.to_string_lossy().replace('\\', "/"))
+}
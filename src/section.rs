@@ -0,0 +1,84 @@
+//! The `link-section` feature, which embeds the annotations of a crate into the binaries it is
+//! linked into, so deployed artifacts can be inspected for pending removals.
+//!
+//! Each annotation becomes a `#[used]` static in a dedicated link section, holding a line of JSON
+//! with its `id`, `requirement` and `reason`. The linker concatenates the statics of every crate,
+//! so the section reads as JSON lines. The static is placed next to the item, or in the body of an
+//! annotated function. Items that nothing can be placed next to, such as fields or associated
+//! items without a body, are embedded by `allow_until_summary!()` instead.
+
+use std::{cell::RefCell, sync::Mutex};
+
+use proc_macro::TokenStream;
+use quote::quote;
+
+use crate::annotation::ParsedAnnotation;
+
+thread_local! {
+    static PENDING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The entries of annotations that couldn't be placed, left for `allow_until_summary!()`.
+static DEFERRED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Adds the entry of the annotation to the output of the current macro, with the feature enabled.
+pub(crate) fn record(args: &ParsedAnnotation) {
+    if !cfg!(feature = "link-section") {
+        return;
+    }
+    let entry = serde_json::json!({
+        "id": args.id,
+        "requirement": args.version.to_string(),
+        "reason": args.reason,
+    });
+    PENDING.with(|pending| pending.borrow_mut().push(format!("{}\n", entry)));
+}
+
+/// Whether entries were recorded since the last call to [`take`] or [`defer`].
+pub(crate) fn pending() -> bool {
+    PENDING.with(|pending| !pending.borrow().is_empty())
+}
+
+/// The static holding the entries recorded since the last call, to be placed among items.
+pub(crate) fn take() -> TokenStream {
+    embed(&PENDING.with(|pending| pending.take()).concat())
+}
+
+/// Leaves the entries recorded since the last call to `allow_until_summary!()`.
+pub(crate) fn defer() {
+    let entries = PENDING.with(|pending| pending.take());
+    DEFERRED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(entries);
+}
+
+/// The static holding the entries deferred so far, for `allow_until_summary!()`.
+pub(crate) fn take_deferred() -> TokenStream {
+    let entries = std::mem::take(&mut *DEFERRED.lock().unwrap_or_else(|e| e.into_inner()));
+    embed(&entries.concat())
+}
+
+/// A static holding `entries` in the section, in an anonymous constant so its name can't collide.
+/// Mach-O requires a segment in the name, and PE/COFF names of at most 8 characters.
+fn embed(entries: &str) -> TokenStream {
+    if entries.is_empty() {
+        return TokenStream::new();
+    }
+    let len = entries.len();
+    let bytes = proc_macro2::Literal::byte_string(entries.as_bytes());
+
+    quote! {
+        const _: () = {
+            #[used]
+            #[cfg_attr(target_vendor = "apple", unsafe(link_section = "__DATA,__allow_until"))]
+            #[cfg_attr(windows, unsafe(link_section = ".allowun"))]
+            #[cfg_attr(
+                not(any(target_vendor = "apple", windows)),
+                unsafe(link_section = ".allow_until")
+            )]
+            static ALLOW_UNTIL: [u8; #len] = *#bytes;
+        };
+    }
+    .into()
+}
@@ -0,0 +1,113 @@
+//! `self_test!()`, which turns the annotations expanded before it into a `#[test]`, so `cargo test`
+//! reports the annotations in their warning window even though the crate compiles, and fails on
+//! expired ones the build only warned about.
+//!
+//! The annotations are evaluated when the crate is compiled for its tests, and the test embeds the
+//! outcome as constants, so it doesn't depend on any file written during the build.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use proc_macro::{Span, TokenStream, TokenTree};
+use quote::quote;
+use semver::Version;
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, item::Item, schedule};
+
+/// An evaluated annotation, as the test reports it.
+struct Entry {
+    status: &'static str,
+    /// The item, its requirement and its location, e.g. "struct `Xml` (`>=2.0`) at src/lib.rs:3:1".
+    described: String,
+}
+
+/// The annotations evaluated so far by their location, so an annotation expanded more than once
+/// is only tested once.
+static ENTRIES: Mutex<BTreeMap<(String, usize, usize), Entry>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn record(
+    args: &ParsedAnnotation,
+    item: Option<&Item>,
+    at: Span,
+    version: &Version,
+    matched: bool,
+    window: bool,
+) {
+    let location = (schedule::relative(&at.file()), at.line(), at.column());
+    let status = match (matched, window) {
+        (_, true) => "warning window",
+        (false, false) => "active",
+        (true, false) => "expired",
+    };
+    let described = format!(
+        "{} (`{}` at version {}) at {}:{}:{}",
+        item.map_or("item".into(), Item::describe),
+        args.version,
+        version,
+        location.0,
+        location.1,
+        location.2
+    );
+
+    ENTRIES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(location, Entry { status, described });
+}
+
+/// The `#[test]` asserting that none of the annotations recorded so far expired, nor, with
+/// `strict`, is in its warning window.
+pub(crate) fn expand(input: TokenStream) -> TokenStream {
+    let mut strict = false;
+    for tok in input {
+        match tok {
+            TokenTree::Ident(ident) if !strict && ident.to_string() == "strict" => strict = true,
+            tok => {
+                diagnostic::error(
+                    tok.span(),
+                    Code::InvalidInput,
+                    "expected `strict` or nothing",
+                )
+                .help("write `self_test!()`, or `self_test!(strict)` to fail on warnings too")
+                .emit();
+                return TokenStream::new();
+            }
+        }
+    }
+
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let statuses = entries.values().map(|entry| entry.status);
+    let described = entries.values().map(|entry| &entry.described);
+
+    quote! {
+        #[test]
+        fn allow_until_self_test() {
+            const ANNOTATIONS: &[(&str, &str)] = &[#((#statuses, #described)),*];
+            const STRICT: bool = #strict;
+
+            let count = |status| ANNOTATIONS.iter().filter(|(s, _)| *s == status).count();
+            let mut summary = ::std::format!(
+                "allow-until: {} annotation(s), {} in warning window, {} expired\n",
+                ANNOTATIONS.len(),
+                count("warning window"),
+                count("expired"),
+            );
+            for (status, described) in ANNOTATIONS {
+                if *status != "active" {
+                    summary.push_str(&::std::format!("  {}: {}\n", status, described));
+                }
+            }
+            // the test harness only captures `print!` and `eprint!`, so the summary is shown even
+            // when the test passes
+            let _ = ::std::io::Write::write_all(&mut ::std::io::stderr(), summary.as_bytes());
+
+            let failing = count("expired") + if STRICT { count("warning window") } else { 0 };
+            ::std::assert!(
+                failing == 0,
+                "{} annotation(s) {}",
+                failing,
+                if STRICT { "expired or in their warning window" } else { "expired" },
+            );
+        }
+    }
+    .into()
+}
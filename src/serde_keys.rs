@@ -0,0 +1,289 @@
+//! The `serde` feature, with which `#[derive(AllowUntil)]` also implements
+//! `allow_until_runtime::serde_support::DeprecatedKeys`, finding the keys of input data that
+//! deserialize into annotated members. Keys are named as serde names them, from the `#[serde]`
+//! attributes of the type.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    ext::IdentExt, Attribute, Data, DeriveInput, Fields, GenericParam, Ident, LitStr, Token, Type,
+};
+
+/// What the `#[serde]` attributes of a type, a field or a variant say about its name.
+#[derive(Default)]
+struct Serde {
+    /// The name it is deserialized from instead of its own.
+    rename: Option<String>,
+    /// The names it is also deserialized from.
+    aliases: Vec<String>,
+    /// How the names of the fields or variants of a type are renamed.
+    rename_all: Option<String>,
+    flatten: bool,
+    /// Whether it is never deserialized.
+    skip: bool,
+}
+
+impl Serde {
+    fn of(attrs: &[Attribute]) -> Serde {
+        let mut serde = Serde::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            // invalid attributes are serde's to report
+            let _ = attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(Ident::to_string)
+                    .unwrap_or_default();
+                match &key[..] {
+                    "rename" => serde.rename = deserialize_name(&meta)?.or(serde.rename.take()),
+                    "rename_all" => {
+                        serde.rename_all = deserialize_name(&meta)?.or(serde.rename_all.take())
+                    }
+                    "alias" => serde.aliases.push(meta.value()?.parse::<LitStr>()?.value()),
+                    "flatten" => serde.flatten = true,
+                    "skip" | "skip_deserializing" => serde.skip = true,
+                    _ if meta.input.peek(Token![=]) => {
+                        meta.value()?.parse::<syn::Expr>()?;
+                    }
+                    _ if meta.input.peek(syn::token::Paren) => {
+                        meta.parse_nested_meta(|meta| {
+                            if meta.input.peek(Token![=]) {
+                                meta.value()?.parse::<syn::Expr>()?;
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    _ => (),
+                }
+                Ok(())
+            });
+        }
+        serde
+    }
+
+    /// The names `name` is deserialized from, renamed by its container's `rename_all`.
+    fn keys(&self, name: &str, rename_all: Option<&str>, variant: bool) -> Vec<String> {
+        let name = match (&self.rename, rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) if variant => rename_variant(name, rule),
+            (None, Some(rule)) => rename_field(name, rule),
+            (None, None) => name.into(),
+        };
+        std::iter::once(name)
+            .chain(self.aliases.iter().cloned())
+            .collect()
+    }
+}
+
+/// The name of `rename = "..."`, or of `deserialize` in `rename(serialize = "...", deserialize =
+/// "...")`.
+fn deserialize_name(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse::<LitStr>()?.value()));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|meta| {
+        let value = meta.value()?.parse::<LitStr>()?.value();
+        if meta.path.is_ident("deserialize") {
+            name = Some(value);
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// `field`, a snake_case field name, renamed by the `rename_all` rule `rule` as serde does.
+fn rename_field(field: &str, rule: &str) -> String {
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => field
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_ascii_uppercase().to_string() + chars.as_str()
+                })
+            })
+            .collect(),
+        "camelCase" => lower_first(&rename_field(field, "PascalCase")),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_ascii_uppercase(),
+        _ => field.into(),
+    }
+}
+
+/// `variant`, a PascalCase variant name, renamed by the `rename_all` rule `rule` as serde does.
+fn rename_variant(variant: &str, rule: &str) -> String {
+    let snake = || {
+        let mut snake = String::new();
+        for (i, c) in variant.char_indices() {
+            if c.is_uppercase() && i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+        snake
+    };
+    match rule {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "camelCase" => lower_first(variant),
+        "snake_case" => snake(),
+        "SCREAMING_SNAKE_CASE" => snake().to_ascii_uppercase(),
+        "kebab-case" => snake().replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake().replace('_', "-").to_ascii_uppercase(),
+        _ => variant.into(),
+    }
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or(String::new(), |first| {
+        first.to_lowercase().chain(chars).collect()
+    })
+}
+
+/// The implementation of the trait for the derive's `input`, whose annotated members are named
+/// `members` relative to the type, in the order they are listed in.
+pub(crate) fn implement(input: &DeriveInput, members: &[&str]) -> TokenStream {
+    let private = quote!(::allow_until_runtime::serde_support::__private);
+    let container = Serde::of(&input.attrs);
+    let rename_all = container.rename_all.as_deref();
+    let index = |name: &str| members.iter().position(|member| *member == name);
+
+    let check = match &input.data {
+        Data::Enum(data) => {
+            let variants = data.variants.iter().filter_map(|variant| {
+                let name = variant.ident.unraw().to_string();
+                let member = index(&name)?;
+                let serde = Serde::of(&variant.attrs);
+                if serde.skip {
+                    return None;
+                }
+                let keys = serde.keys(&name, rename_all, true);
+                Some(quote!((&[#(#keys),*], #member)))
+            });
+            quote!(#private::check_variants(value, path, &[#(#variants),*], members, uses))
+        }
+        Data::Struct(data) => {
+            let generics = generic_types(input);
+            let fields = match &data.fields {
+                Fields::Named(fields) => fields.named.iter().collect(),
+                _ => Vec::new(),
+            };
+            let fields = fields.into_iter().filter_map(|field| {
+                let name = field.ident.as_ref()?.unraw().to_string();
+                let serde = Serde::of(&field.attrs);
+                if serde.skip {
+                    return None;
+                }
+                let keys = serde.keys(&name, rename_all, false);
+                let member = match index(&name) {
+                    Some(index) => quote!(::core::option::Option::Some(#index)),
+                    None => quote!(::core::option::Option::None),
+                };
+                let flatten = serde.flatten;
+                // whether a type parameter implements the trait can't be told in the derive, so
+                // fields mentioning one aren't checked
+                let nested = if mentions(&field.ty, &generics) {
+                    quote!(|_, _, _| {})
+                } else {
+                    let ty = &field.ty;
+                    quote! {
+                        |value, path, uses| {
+                            use #private::{Leaf as _, Nested as _};
+                            (&#private::Probe::<#ty>::new()).check(value, path, uses)
+                        }
+                    }
+                };
+                Some(quote! {
+                    #private::Field {
+                        keys: &[#(#keys),*],
+                        member: #member,
+                        flatten: #flatten,
+                        nested: #nested,
+                    }
+                })
+            });
+            quote!(#private::check_fields(value, path, &[#(#fields),*], members, uses))
+        }
+        Data::Union(_) => quote!(let _ = (value, path, members, uses);),
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::allow_until_runtime::serde_support::DeprecatedKeys
+            for #ident #ty_generics #where_clause
+        {
+            fn check_at(
+                value: &::allow_until_runtime::serde_support::Value,
+                path: &str,
+                uses: &mut ::std::vec::Vec<::allow_until_runtime::serde_support::DeprecatedKeyUse>,
+            ) {
+                let members =
+                    <Self as ::allow_until_runtime::AllowUntilMetadata>::deprecated_members();
+                #check
+            }
+        }
+    }
+}
+
+fn generic_types(input: &DeriveInput) -> Vec<String> {
+    input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `ty` mentions any of the type parameters `generics`.
+fn mentions(ty: &Type, generics: &[String]) -> bool {
+    fn search(tokens: TokenStream, generics: &[String]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => generics.contains(&ident.to_string()),
+            proc_macro2::TokenTree::Group(group) => search(group.stream(), generics),
+            _ => false,
+        })
+    }
+    !generics.is_empty() && search(quote!(#ty), generics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_rules() {
+        let fields = [
+            ("camelCase", "requestTimeoutMs"),
+            ("PascalCase", "RequestTimeoutMs"),
+            ("kebab-case", "request-timeout-ms"),
+            ("SCREAMING_SNAKE_CASE", "REQUEST_TIMEOUT_MS"),
+            ("lowercase", "request_timeout_ms"),
+        ];
+        for (rule, expected) in fields {
+            assert_eq!(
+                rename_field("request_timeout_ms", rule),
+                expected,
+                "{}",
+                rule
+            );
+        }
+
+        let variants = [
+            ("camelCase", "legacyXml"),
+            ("snake_case", "legacy_xml"),
+            ("kebab-case", "legacy-xml"),
+            ("SCREAMING-KEBAB-CASE", "LEGACY-XML"),
+            ("lowercase", "legacyxml"),
+        ];
+        for (rule, expected) in variants {
+            assert_eq!(rename_variant("LegacyXml", rule), expected, "{}", rule);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! `max_age`, the policy that annotations don't linger: one that hasn't expired more than `max_age`
+//! after the version it was added in, given by `since`, is stale, as its deadline likely slipped.
+
+use proc_macro::Span;
+use semver::Version;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, config, diagnostic::Diagnostic, item::Item,
+    trace::Trace, version::Distance, warning_level,
+};
+
+/// Warns when `max_age` is configured and the annotation at `at`, which hasn't expired, was added
+/// more than `max_age` before `version`, or doesn't say when it was added.
+pub(crate) fn check(
+    args: &ParsedAnnotation,
+    item: Option<&Item>,
+    at: Span,
+    version: &Version,
+    trace: &mut Trace,
+) {
+    let Some(max_age) = config::get().max_age else {
+        return;
+    };
+    let described = item.map_or("item".into(), Item::describe);
+
+    let Some((since, span)) = &args.since else {
+        trace.step(|| "no `since`, so `max_age` can't tell its age".into());
+        Diagnostic::spanned(
+            at,
+            warning_level(at),
+            Code::Stale,
+            format!("the annotation of {} has no `since`", described),
+        )
+        .note(format!(
+            "`max_age` warns about annotations allowed for more than {}",
+            max_age.releases()
+        ))
+        .help(format!(
+            "add `since = \"{}\"`, or the version the annotation was added in",
+            version
+        ))
+        .emit();
+        return;
+    };
+
+    let limit = max_age.exceeded_at(since);
+    if *version < limit {
+        return;
+    }
+
+    let age = Distance::between(since, version).map_or(String::new(), |age| age.releases());
+    trace.step(|| format!("allowed since {}, {} ago", since, age));
+    Diagnostic::spanned(
+        at,
+        warning_level(at),
+        Code::Stale,
+        format!(
+            "{} is stale, allowed since {}, {} ago",
+            described, since, age
+        ),
+    )
+    .span_note(
+        *span,
+        format!(
+            "`max_age` allows at most {}, so it is stale from {} on",
+            max_age.releases(),
+            limit
+        ),
+    )
+    .help(format!(
+        "remove it now, or snooze it with `since = \"{}\"` and `approved_by` naming who agreed",
+        version
+    ))
+    .emit();
+}
@@ -0,0 +1,86 @@
+//! `ALLOW_UNTIL_STRIP=1`, which previews the removal of expired items: instead of reporting an
+//! expired annotation, the macro leaves its item out of the expansion, so `cargo check` reports
+//! the callers that the real removal breaks and the helpers it leaves unused.
+
+use std::cell::Cell;
+
+use proc_macro::{Span, TokenStream};
+
+use crate::{
+    annotation::ParsedAnnotation,
+    code::Code,
+    diagnostic,
+    item::{Item, Kind},
+    tracked,
+};
+
+pub(crate) const STRIP_VAR: &str = "ALLOW_UNTIL_STRIP";
+
+thread_local! {
+    static STRIPPED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether expired items are stripped, as requested with `ALLOW_UNTIL_STRIP=1`.
+fn requested() -> bool {
+    tracked::var(STRIP_VAR).is_ok_and(|v| v.trim() == "1")
+}
+
+/// Whether the item of the expired annotation at `at` is stripped, asking the current macro to
+/// leave it out if so. The derive can't remove the fields and variants it is given, and only the
+/// default of a `generic_default` annotation goes, so those are warned about and reported as
+/// usual.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) -> bool {
+    if !requested() {
+        return false;
+    }
+
+    let unsupported = match item.map(|item| item.kind) {
+        Some(kind @ (Kind::Field | Kind::Variant)) => Some(format!(
+            "the derive can't remove {}s, so the expired annotation is reported as usual",
+            kind.as_str()
+        )),
+        _ if args.generic_default.is_some() => Some(
+            "only the default of the generic parameter goes, so the expired annotation is \
+             reported as usual"
+                .into(),
+        ),
+        _ => None,
+    };
+    if let Some(note) = unsupported {
+        diagnostic::warning(
+            item.and_then(|item| item.span).unwrap_or(at),
+            Code::StripUnsupported,
+            format!(
+                "{} can't be stripped by `{}=1`",
+                item.map_or("item".into(), Item::describe),
+                STRIP_VAR
+            ),
+        )
+        .note(note)
+        .help("remove it by hand to see the effect of its removal")
+        .emit();
+        return false;
+    }
+
+    STRIPPED.with(|stripped| stripped.set(true));
+    true
+}
+
+/// Whether the item evaluated last is stripped, without taking the request, for the report.
+pub(crate) fn pending() -> bool {
+    STRIPPED.with(Cell::get)
+}
+
+/// Whether the current macro was asked to leave out the item evaluated since the last call.
+pub(crate) fn take() -> bool {
+    STRIPPED.with(Cell::take)
+}
+
+/// `input`, or nothing when the current macro was asked to leave it out since the last call.
+pub(crate) fn stripped(input: TokenStream) -> TokenStream {
+    if take() {
+        TokenStream::new()
+    } else {
+        input
+    }
+}
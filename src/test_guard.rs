@@ -0,0 +1,87 @@
+//! The failing tests generated for expired annotations with `test_guard = true`, in place of the
+//! error, so `cargo test` fails while `cargo build` still succeeds, and the `#[ignore]` added to
+//! expired `#[test]` functions with `on_expiry = "ignore_test"`.
+
+use std::cell::RefCell;
+
+use proc_macro::{Span, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::item::Item;
+
+thread_local! {
+    static PENDING: RefCell<Vec<proc_macro2::TokenStream>> = const { RefCell::new(Vec::new()) };
+    static IGNORED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Adds a `#[cfg(test)]` test panicking with `message` to the output of the current macro.
+pub(crate) fn push(item: &Item, at: Span, message: &str) {
+    let name = format_ident!("{}", name(item, at));
+
+    PENDING.with(|pending| {
+        pending.borrow_mut().push(quote! {
+            #[cfg(test)]
+            #[test]
+            fn #name() {
+                ::core::panic!("{}", #message);
+            }
+        })
+    });
+}
+
+/// The tests pushed since the last call.
+pub(crate) fn take() -> TokenStream {
+    PENDING
+        .with(|pending| pending.take())
+        .into_iter()
+        .collect::<proc_macro2::TokenStream>()
+        .into()
+}
+
+/// Ignores the test the current macro is applied to with `message`.
+pub(crate) fn ignore(message: String) {
+    IGNORED.with(|ignored| *ignored.borrow_mut() = Some(message));
+}
+
+/// `input` with the `#[ignore = "..."]` asked for since the last call, if any.
+pub(crate) fn ignored(input: TokenStream) -> TokenStream {
+    let Some(message) = IGNORED.with(|ignored| ignored.take()) else {
+        return input;
+    };
+    let input = proc_macro2::TokenStream::from(input);
+    quote! {
+        #[ignore = #message]
+        #input
+    }
+    .into()
+}
+
+/// The name of the test for the annotation at `at`, such as `allow_until_struct_xml_config_12`
+/// for a struct `XmlConfig` annotated on line 12. The line tells apart the tests of items with the
+/// same kind and name, such as `cfg`-gated alternatives.
+fn name(item: &Item, at: Span) -> String {
+    let mut name = format!("allow_until_{}", snake_case(item.kind.as_str()));
+    if let Some(item) = &item.name {
+        name.push('_');
+        name.push_str(&snake_case(item));
+    }
+    format!("{}_{}", name, at.line())
+}
+
+/// `name` in snake case, with anything but letters and digits turned into underscores.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous = '_';
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if c.is_uppercase() && (previous.is_lowercase() || previous.is_ascii_digit()) {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else if !snake.is_empty() && !snake.ends_with('_') {
+            snake.push('_');
+        }
+        previous = c;
+    }
+    snake.trim_end_matches('_').into()
+}
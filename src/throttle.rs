@@ -0,0 +1,110 @@
+//! `ALLOW_UNTIL_WARN_INTERVAL`, which emits each warning about an annotation at most once per
+//! interval on a machine, so the same warnings repeated on every incremental rebuild don't train
+//! people to ignore them. Errors are never held back.
+//!
+//! When each warning was last emitted is kept in `state.json` in the `allow-until` directory of the
+//! target directory, which crates built in parallel update in turns. A missing or unreadable state
+//! is taken as empty, so deleting it only lets every warning through once more.
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use proc_macro::Span;
+use serde::{Deserialize, Serialize};
+
+use crate::{annotation::ParsedAnnotation, code::Code, diagnostic, env, lock, manifest};
+
+/// What the state records about a warning.
+#[derive(Default, Serialize, Deserialize)]
+struct Entry {
+    /// When the warning was last emitted, in seconds since the Unix epoch.
+    emitted: u64,
+    /// How many times it was held back since.
+    held_back: u64,
+}
+
+/// Whether the warning `code` about the annotation at `at` is emitted now, with how many times it
+/// was held back since it was last emitted, or `None` when it is held back. The annotation is told
+/// apart from others by its id, or else its fingerprint.
+pub(crate) fn check(args: &ParsedAnnotation, code: Code, at: Span) -> Option<u64> {
+    let interval = match env::warn_interval() {
+        Ok(Some(interval)) => interval,
+        Ok(None) => return Some(0),
+        Err(e) => {
+            if env::once("warn-interval") {
+                diagnostic::warning(at, Code::InvalidEnv, e).emit();
+            }
+            return Some(0);
+        }
+    };
+    // a warning that isn't shown isn't counted as emitted
+    let key = args.id.as_ref().or(args.fingerprint.as_ref());
+    let (Some(key), false) = (key, diagnostic::silenced()) else {
+        return Some(0);
+    };
+    let key = format!("{} {}", code.as_str(), key);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let dir = manifest::target_dir().join("allow-until");
+    let path = dir.join("state.json");
+    let updated = std::fs::create_dir_all(&dir).and_then(|()| {
+        lock::locked(&path, || {
+            let mut state: BTreeMap<String, Entry> = std::fs::read(&path)
+                .ok()
+                .and_then(|state| serde_json::from_slice(&state).ok())
+                .unwrap_or_default();
+
+            let entry = state.entry(key).or_default();
+            // a warning emitted in the future, by the clock of this machine, is due too
+            let due = now
+                .checked_sub(entry.emitted)
+                .is_none_or(|elapsed| elapsed >= interval);
+            let emitted = if due {
+                let held_back = entry.held_back;
+                *entry = Entry {
+                    emitted: now,
+                    held_back: 0,
+                };
+                Some(held_back)
+            } else {
+                entry.held_back += 1;
+                None
+            };
+
+            lock::replace(&path, serde_json::to_vec(&state)?)?;
+            Ok(emitted)
+        })
+    });
+
+    updated.unwrap_or_else(|e| {
+        if env::once("warn-interval-state") {
+            diagnostic::warning(
+                at,
+                Code::FileAccess,
+                format!(
+                    "failed to update the warning state `{}`, warnings are not held back: {}",
+                    path.display(),
+                    e
+                ),
+            )
+            .emit();
+        }
+        Some(0)
+    })
+}
+
+/// The note of a warning that was held back `held_back` times since it was last emitted.
+pub(crate) fn note(held_back: u64) -> String {
+    format!(
+        "held back {} time{} since it was last emitted, as `{}` emits it at most once every {} \
+         seconds",
+        held_back,
+        if held_back == 1 { "" } else { "s" },
+        env::WARN_INTERVAL_VAR,
+        env::warn_interval().ok().flatten().unwrap_or_default()
+    )
+}
@@ -0,0 +1,194 @@
+//! `tool` and `tool_version`, which condition an annotation on the version of an external tool of
+//! the build, such as the `protoc` its code is generated with.
+
+use std::{collections::HashMap, io::ErrorKind, path::Path, process::Command, sync::Mutex};
+
+use proc_macro::Span;
+use semver::{Version, VersionReq};
+
+use crate::{
+    annotation::ParsedAnnotation,
+    code::Code,
+    diagnostic::{self, Diagnostic},
+    trace::Trace,
+    tracked,
+};
+
+/// A requirement on the version of a tool.
+#[derive(Clone)]
+pub(crate) struct Tool {
+    /// The name of the tool, as run from `PATH`.
+    pub name: String,
+    pub req: VersionReq,
+    /// The span of the `tool_version` literal.
+    pub span: Span,
+}
+
+/// The version of a tool as asked for by the first annotation naming it.
+struct Entry {
+    version: Result<Version, String>,
+    /// Whether the failure to determine it was reported already.
+    reported: bool,
+}
+
+static TOOLS: Mutex<Option<HashMap<String, Entry>>> = Mutex::new(None);
+
+/// The environment variable giving the binary of the tool `name` to run instead of the one on
+/// `PATH`, e.g. `ALLOW_UNTIL_TOOL_PROTOC_GEN_GO` for `protoc-gen-go`.
+pub(crate) fn override_var(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+    format!("ALLOW_UNTIL_TOOL_{}", name)
+}
+
+/// Checks that `tool` and `tool_version` are given together.
+pub(crate) fn resolve(
+    tool: Option<(String, proc_macro2::Span)>,
+    tool_version: Option<(VersionReq, proc_macro2::Span)>,
+) -> Result<Option<Tool>, Diagnostic> {
+    match (tool, tool_version) {
+        (Some((name, _)), Some((req, span))) => Ok(Some(Tool {
+            name,
+            req,
+            span: span.unwrap(),
+        })),
+        (Some((name, span)), None) => Err(diagnostic::error(
+            span.unwrap(),
+            Code::MissingRequired,
+            format!("`tool` is given without the `tool_version` of `{}`", name),
+        )
+        .help("add the requirement on its version, such as `tool_version = \">=25\"`")),
+        (None, Some((_, span))) => Err(diagnostic::error(
+            span.unwrap(),
+            Code::InvalidInput,
+            "`tool_version` is given without `tool`",
+        )
+        .help("name the tool it is the version of with `tool = \"...\"`")),
+        (None, None) => Ok(None),
+    }
+}
+
+/// The version of the tool `name`, running the binary of its [`override_var`] or else the one on
+/// `PATH` with `--version`.
+fn from_binary(name: &str) -> Result<Version, String> {
+    let var = override_var(name);
+    let (binary, found) = match tracked::var_os(&var) {
+        Some(binary) => (binary, format!("of `{}`", var)),
+        None => (name.into(), "on `PATH`".into()),
+    };
+    let shown = Path::new(&binary).display();
+    let output = Command::new(&binary)
+        .arg("--version")
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => format!("`{}` was not found {}", shown, found),
+            _ => format!("failed to run `{} --version`: {}", shown, e),
+        })?;
+
+    // some tools print their version to stderr
+    let printed = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if !output.status.success() {
+        return Err(format!("`{} --version` failed: {}", shown, printed.trim()));
+    }
+    crate::version::in_output(&printed).ok_or_else(|| {
+        format!(
+            "`{} --version` printed no version: `{}`",
+            shown,
+            printed.trim()
+        )
+    })
+}
+
+/// The version of the tool `name`, once an annotation with `tool` has asked for it.
+pub(crate) fn version(name: &str) -> Option<Version> {
+    let tools = TOOLS.lock().unwrap_or_else(|e| e.into_inner());
+    tools.as_ref()?.get(name)?.version.clone().ok()
+}
+
+/// Whether the `tool_version` requirement of the annotation at `at` is satisfied. It always is
+/// without `tool`, and never when the version of the tool is unknown, which is warned about once
+/// per tool. Each tool is only run by the first annotation naming it.
+pub(crate) fn satisfied(args: &ParsedAnnotation, at: Span, trace: &mut Trace) -> bool {
+    let Some(tool) = &args.tool else {
+        return true;
+    };
+
+    let mut tools = TOOLS.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = tools
+        .get_or_insert_with(HashMap::new)
+        .entry(tool.name.clone())
+        .or_insert_with_key(|name| Entry {
+            version: from_binary(name),
+            reported: false,
+        });
+
+    let version = match &entry.version {
+        Ok(version) => version,
+        Err(e) => {
+            trace.step(|| {
+                format!(
+                    "the version of `{}` is unknown, `tool_version = \"{}\"` is not satisfied",
+                    tool.name, tool.req
+                )
+            });
+            if !diagnostic::silenced() && !std::mem::replace(&mut entry.reported, true) {
+                diagnostic::warning(
+                    at,
+                    Code::UnknownTool,
+                    format!(
+                        "the version of `{}` is unknown, annotations conditioned on it don't \
+                         fire: {}",
+                        tool.name, e
+                    ),
+                )
+                .help(format!(
+                    "install `{}` on `PATH`, or set `{}` to its binary",
+                    tool.name,
+                    override_var(&tool.name)
+                ))
+                .emit();
+            }
+            return false;
+        }
+    };
+
+    let matched = tool.req.matches(version);
+    trace.step(|| {
+        format!(
+            "`{}` {} {} `tool_version = \"{}\"`",
+            tool.name,
+            version,
+            if matched {
+                "satisfies"
+            } else {
+                "does not satisfy"
+            },
+            tool.req
+        )
+    });
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_vars() {
+        assert_eq!(override_var("protoc"), "ALLOW_UNTIL_TOOL_PROTOC");
+        assert_eq!(
+            override_var("protoc-gen-go"),
+            "ALLOW_UNTIL_TOOL_PROTOC_GEN_GO"
+        );
+        assert_eq!(override_var("clang.18"), "ALLOW_UNTIL_TOOL_CLANG_18");
+    }
+}
@@ -0,0 +1,85 @@
+//! The `ALLOW_UNTIL_TRACE` explanation of how each annotation was evaluated, for working out why
+//! an annotation did or didn't fire in a particular build.
+
+use std::sync::OnceLock;
+
+use proc_macro::Span;
+
+use crate::{code::Code, diagnostic, env, tracked};
+
+pub(crate) const TRACE_VAR: &str = "ALLOW_UNTIL_TRACE";
+
+/// How diagnostics are emitted, as selected by the build script when the crate was built.
+const DIAGNOSTICS: &str = if cfg!(allow_until_nightly) {
+    "diagnostics are emitted with the nightly `proc_macro::Diagnostic` API"
+} else if cfg!(feature = "nightly") {
+    "diagnostics use the stable fallback, as the nightly `proc_macro` APIs didn't compile with \
+     the toolchain allow-until was built with"
+} else {
+    "diagnostics use the stable fallback, as the `nightly` feature is off"
+};
+
+/// Collects the evaluation steps of one annotation and emits them as a note when dropped, so every
+/// early return still produces a trace.
+///
+/// When tracing is disabled nothing is collected, and the closures passed to [`Trace::step`] are
+/// never called.
+pub(crate) struct Trace {
+    at: Span,
+    steps: Option<Vec<String>>,
+    decision: &'static str,
+}
+
+/// Whether tracing was requested with `ALLOW_UNTIL_TRACE=1`.
+pub(crate) fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+
+    *ENABLED.get_or_init(|| tracked::var(TRACE_VAR).is_ok_and(|v| v.trim() == "1"))
+}
+
+impl Trace {
+    pub fn new(at: Span) -> Trace {
+        if enabled() && env::once("trace-diagnostics") {
+            diagnostics(at);
+        }
+        Trace {
+            at,
+            steps: enabled().then(Vec::new),
+            decision: "not reported",
+        }
+    }
+
+    pub fn step(&mut self, step: impl FnOnce() -> String) {
+        if let Some(steps) = &mut self.steps {
+            steps.push(step());
+        }
+    }
+
+    pub fn decide(&mut self, decision: &'static str) {
+        self.decision = decision;
+    }
+}
+
+/// Notes once how diagnostics are emitted.
+fn diagnostics(at: Span) {
+    diagnostic::note(
+        at,
+        Code::Trace,
+        format!("allow_until trace: {}", DIAGNOSTICS),
+    )
+    .emit_visible();
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        let Some(steps) = self.steps.take() else {
+            return;
+        };
+
+        let mut diag = diagnostic::note(self.at, Code::Trace, "allow_until trace");
+        for step in steps {
+            diag = diag.note(step);
+        }
+        diag.note(format!("decision: {}", self.decision)).emit();
+    }
+}
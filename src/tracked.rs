@@ -0,0 +1,148 @@
+//! Reads of the environment and of files the expansion depends on, declared to the compiler.
+//!
+//! With the `nightly` feature, the reads go through `proc_macro::tracked`, so the variables and
+//! files end up in the dep-info of the compilation and a change to them, such as a version bump,
+//! re-expands the macros rather than serving a cached expansion. Otherwise, and outside of a
+//! macro expansion, they are plain untracked reads.
+
+use std::{
+    collections::HashMap,
+    env::VarError,
+    ffi::{OsStr, OsString},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The value of the environment variable `key`, as [`std::env::var`].
+pub(crate) fn var(key: &str) -> Result<String, VarError> {
+    #[cfg(allow_until_nightly)]
+    if proc_macro::is_available() {
+        return proc_macro::tracked::env_var(key);
+    }
+    std::env::var(key)
+}
+
+/// The value of the environment variable `key`, as [`std::env::var_os`].
+pub(crate) fn var_os(key: &str) -> Option<OsString> {
+    // only UTF-8 values can be read tracked, the read declares the variable either way
+    #[cfg(allow_until_nightly)]
+    if proc_macro::is_available() {
+        let _ = proc_macro::tracked::env_var(key);
+    }
+    std::env::var_os(key)
+}
+
+/// Declares the file at `path` as an input of the compilation. Files that don't exist aren't
+/// declared, as a missing input would make every build stale.
+pub(crate) fn path(path: &Path) {
+    #[cfg(allow_until_nightly)]
+    if proc_macro::is_available() && path.is_file() {
+        proc_macro::tracked::path(path);
+    }
+    #[cfg(not(allow_until_nightly))]
+    let _ = path;
+}
+
+/// The contents of the file at `path`, declared as an input of the compilation.
+pub(crate) fn read_to_string(path: &Path) -> io::Result<String> {
+    self::path(path);
+    std::fs::read_to_string(path)
+}
+
+/// The bytes of the file at `path`, declared as an input of the compilation.
+pub(crate) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    self::path(path);
+    std::fs::read(path)
+}
+
+/// The bytes of the file at `path` as [`read`], read once per compilation and shared by every
+/// annotation reading it.
+pub(crate) fn read_shared(path: &Path) -> Result<&'static [u8], String> {
+    type Files = HashMap<PathBuf, Result<&'static [u8], String>>;
+    static FILES: Mutex<Option<Files>> = Mutex::new(None);
+
+    let mut files = FILES.lock().unwrap_or_else(|e| e.into_inner());
+    files
+        .get_or_insert_with(HashMap::new)
+        .entry(path.into())
+        .or_insert_with_key(|path| {
+            read(path)
+                .map(|data| &*Box::leak(data.into_boxed_slice()))
+                .map_err(|e| e.to_string())
+        })
+        .clone()
+}
+
+/// The path a user wrote, in an argument or a variable, taking both `/` and `\` as separators so
+/// paths written on Windows work elsewhere and the other way around.
+pub(crate) fn user_path(path: impl AsRef<OsStr>) -> PathBuf {
+    let path = path.as_ref();
+    // Windows takes both already, and elsewhere only UTF-8 paths are rewritten
+    match path.to_str() {
+        Some(text) if !cfg!(windows) && text.contains('\\') => text.replace('\\', "/").into(),
+        _ => path.into(),
+    }
+}
+
+/// The file at `file`, given by an annotation relative to the package root.
+pub(crate) fn package_path(file: &str) -> PathBuf {
+    let root = var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| ".".into());
+    Path::new(&root).join(user_path(file))
+}
+
+/// `path` as diagnostics show it: canonicalised when it exists, so that it is clear which file was
+/// meant whatever directory it was relative to, without the `\\?\` prefix Windows gives it.
+pub(crate) fn shown(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path.into(),
+    });
+    let path = path.display().to_string();
+    match path.strip_prefix(r"\\?\") {
+        Some(path) if !path.starts_with("UNC") => path.into(),
+        _ => path,
+    }
+}
+
+/// The text of a file as [`String::from_utf8_lossy`], without the byte order mark some Windows
+/// editors start UTF-8 files with.
+pub(crate) fn text(data: &[u8]) -> String {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    String::from_utf8_lossy(data).into_owned()
+}
+
+/// The contents of the file at `path` as [`read_to_string`], without a byte order mark.
+pub(crate) fn read_text(path: &Path) -> io::Result<String> {
+    let mut text = read_to_string(path)?;
+    if text.starts_with('\u{feff}') {
+        text.remove(0);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators() {
+        assert_eq!(
+            user_path(r"config\release\version.toml"),
+            Path::new("config/release/version.toml")
+        );
+        assert_eq!(
+            user_path("config/version.toml"),
+            Path::new("config/version.toml")
+        );
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        assert_eq!(
+            text(b"\xEF\xBB\xBFversion = \"1.0\"\r\n"),
+            "version = \"1.0\"\r\n"
+        );
+        assert_eq!(text(b"version"), "version");
+    }
+}
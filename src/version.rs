@@ -0,0 +1,147 @@
+//! Resolution of the version annotations are compared against, shared by every annotation of the
+//! compilation.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+pub(crate) use allow_until_core::version::*;
+
+use crate::{
+    code::Code,
+    offline::{self, Policy},
+    tracked,
+};
+
+/// Resolves the current version from `source`, with [`OVERRIDE_VAR`] taking precedence over
+/// every source.
+///
+/// Each source is only read and parsed by the first call, every later annotation in the
+/// compilation shares the result. Returns `Ok(None)` when no version is available at all, in which
+/// case nothing is enforced. When git can't provide a version, or `policy` doesn't allow running
+/// it, `on_git_failure` is called (once per compilation) with the reason and the Cargo version is
+/// used instead.
+pub(crate) fn resolve(
+    source: VersionSource,
+    policy: Policy,
+    on_git_failure: impl FnOnce(&str),
+) -> Result<Option<&'static Current>, &'static str> {
+    static CURRENT: OnceLock<Result<Option<Current>, String>> = OnceLock::new();
+    static GIT: OnceLock<Result<Current, String>> = OnceLock::new();
+
+    if source == VersionSource::Git && tracked::var_os(OVERRIDE_VAR).is_none() {
+        let dir = tracked::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| ".".into());
+        // the policy is the same for the whole compilation
+        let git = GIT.get_or_init(|| {
+            policy.check(offline::Source::GitDescribe)?;
+            from_git(dir.as_ref())
+        });
+        match git {
+            Ok(current) => return Ok(Some(current)),
+            Err(e) => {
+                if crate::env::once("git") {
+                    on_git_failure(e);
+                }
+            }
+        }
+    }
+
+    match CURRENT.get_or_init(|| from_vars(|key| tracked::var(key).ok())) {
+        Ok(current) => Ok(current.as_ref()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The version at the key of `from` in its file, relative to the package root, parsed leniently
+/// so that `"2.1"` is 2.1.0.
+///
+/// The file is read through [`tracked::read_shared`], and each key is only looked up by the first
+/// annotation reading it. Errors come with the code they are reported with.
+pub(crate) fn from_file(from: &VersionFrom) -> Result<&'static Current, (Code, String)> {
+    type Versions = HashMap<(String, String), Result<&'static Current, (Code, String)>>;
+    static VERSIONS: Mutex<Option<Versions>> = Mutex::new(None);
+
+    let mut versions = VERSIONS.lock().unwrap_or_else(|e| e.into_inner());
+    versions
+        .get_or_insert_with(HashMap::new)
+        .entry((from.file.clone(), from.key.clone()))
+        .or_insert_with(|| {
+            let version = lookup(from)?;
+            Ok(Box::leak(Box::new(Current {
+                version,
+                source: Source::File,
+                tag: None,
+                from: Some(from.clone()),
+            })))
+        })
+        .clone()
+}
+
+fn lookup(from: &VersionFrom) -> Result<semver::Version, (Code, String)> {
+    let file = &from.file;
+    let path = tracked::package_path(file);
+    let data = tracked::read_shared(&path).map_err(|e| {
+        (
+            Code::FileAccess,
+            format!(
+                "`{}` could not be read from `{}`: {}",
+                file,
+                tracked::shown(&path),
+                e
+            ),
+        )
+    })?;
+    let text = tracked::text(data);
+
+    // TOML is converted, so that keys are looked up the same way in both formats
+    let value = match from.format {
+        Format::Toml => toml::from_str::<toml::Value>(&text)
+            .map_err(|e| e.message().to_string())
+            .and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string())),
+        Format::Json => serde_json::from_str(&text).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| {
+        let format = match from.format {
+            Format::Toml => "TOML",
+            Format::Json => "JSON",
+        };
+        (
+            Code::FileAccess,
+            format!("`{}` is not valid {}: {}", file, format, e),
+        )
+    })?;
+
+    let mut found = &value;
+    for segment in from.key.split('.') {
+        found = found.get(segment.trim()).ok_or_else(|| {
+            (
+                Code::NoVersion,
+                format!("`{}` has no key `{}`", file, from.key),
+            )
+        })?;
+    }
+
+    let serde_json::Value::String(text) = found else {
+        let kind = match found {
+            serde_json::Value::Null => "null",
+            serde_json::Value::Bool(_) => "a boolean",
+            serde_json::Value::Number(_) => "a number",
+            serde_json::Value::Array(_) => "an array",
+            _ => "a table",
+        };
+        return Err((
+            Code::NoVersion,
+            format!(
+                "`{}` in `{}` is {}, not a version string",
+                from.key, file, kind
+            ),
+        ));
+    };
+    parse_lenient(text).map_err(|e| {
+        (
+            Code::NoVersion,
+            format!("`{}` in `{}` is not a version: {}", from.key, file, e),
+        )
+    })
+}
@@ -0,0 +1,125 @@
+//! `watch` and `watch_hash`, expiring annotations once a file they work around changes, such as a
+//! schema or a vendored dependency.
+
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use proc_macro::Span;
+
+use allow_until_core::hash;
+
+use crate::{
+    annotation::ParsedAnnotation, code::Code, config::Docsrs, context, diagnostic, item::Item,
+    tracked, Diagnostic, Severity,
+};
+
+/// A watched file, with the hash it had when the annotation was written and the one it has now.
+#[derive(Clone)]
+pub(crate) struct Watch {
+    /// The file, relative to the package root.
+    pub(crate) file: String,
+    pub(crate) expected: String,
+    pub(crate) expected_span: Span,
+    pub(crate) current: String,
+}
+
+/// The hash of `file` in the package root, each file being hashed once per compilation.
+fn current(file: &str) -> Result<String, String> {
+    static HASHES: Mutex<Option<HashMap<PathBuf, Result<String, String>>>> = Mutex::new(None);
+
+    let path = tracked::package_path(file);
+
+    let mut hashes = HASHES.lock().unwrap_or_else(|e| e.into_inner());
+    hashes
+        .get_or_insert_with(HashMap::new)
+        .entry(path)
+        .or_insert_with_key(|path| tracked::read_shared(path).map(hash::sha256))
+        .clone()
+}
+
+/// Hashes the file of `watch`, checking that it can be read and that `watch` and `watch_hash` are
+/// given together.
+pub(crate) fn resolve(
+    watch: Option<(String, proc_macro2::Span)>,
+    watch_hash: Option<(String, proc_macro2::Span)>,
+) -> Result<Option<Watch>, Diagnostic> {
+    let (file, span) = match (watch, &watch_hash) {
+        (Some((file, span)), _) => (file, span.unwrap()),
+        (None, Some((_, span))) => {
+            return Err(diagnostic::error(
+                span.unwrap(),
+                Code::InvalidInput,
+                "`watch_hash` is given without `watch`",
+            )
+            .help("name the file it is the hash of with `watch = \"...\"`"))
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let current = current(&file).map_err(|e| {
+        diagnostic::error(
+            span,
+            Code::FileAccess,
+            format!(
+                "the watched file `{}` could not be read from `{}`: {}",
+                file,
+                tracked::shown(&tracked::package_path(&file)),
+                e
+            ),
+        )
+        .help("`watch` is relative to the package root, the directory of its `Cargo.toml`")
+    })?;
+
+    let Some((expected, expected_span)) = watch_hash else {
+        return Err(diagnostic::error(
+            span,
+            Code::MissingRequired,
+            format!("`watch` is given without the `watch_hash` of `{}`", file),
+        )
+        .help(format!(
+            "add `watch_hash = \"{}\"`, the hash the file has now",
+            current
+        )));
+    };
+
+    Ok(Some(Watch {
+        file,
+        expected,
+        expected_span: expected_span.unwrap(),
+        current,
+    }))
+}
+
+/// Reports the annotation at `at` once its watched file no longer has its `watch_hash`, with the
+/// annotation's `severity`.
+pub(crate) fn check(args: &ParsedAnnotation, item: Option<&Item>, at: Span) {
+    let Some(watch) = args.watch.as_ref().filter(|w| w.current != w.expected) else {
+        return;
+    };
+
+    // as with expiry, the context decides in the end, e.g. the editor and the lint run only warn
+    let severity = match context::detect(args.docsrs) {
+        (_, Some(Docsrs::Ignore)) => return,
+        (_, Some(Docsrs::Severity(severity))) => severity,
+        (_, None) => args.severity.unwrap_or(Severity::Error),
+    };
+
+    let described = item.map_or("item".into(), Item::describe);
+    Diagnostic::spanned(
+        item.and_then(|item| item.span).unwrap_or(at),
+        severity.level(),
+        Code::WatchedFileChanged,
+        format!(
+            "{} not allowed, as the watched `{}` changed",
+            described, watch.file
+        ),
+    )
+    .span_note(
+        watch.expected_span,
+        format!("its hash is now {}", watch.current),
+    )
+    .help(format!(
+        "revisit the workaround, then remove it or update `watch_hash` to \"{}\"",
+        watch.current
+    ))
+    .emit();
+}
@@ -0,0 +1,289 @@
+//! `allow_members_until`, which applies one annotation to the members of an impl block or a trait,
+//! or to the listed ones.
+
+mod common;
+
+use common::Fixture;
+
+const CLIENT: &str = r#"
+    pub struct Client;
+
+    #[allow_until::allow_members_until(
+        version = ">=2.0",
+        members = ["connect_tls", "connect_plain", "LEGACY_TIMEOUT"],
+    )]
+    impl Client {
+        pub const LEGACY_TIMEOUT: u64 = 30;
+
+        pub fn connect(&self) {}
+
+        pub fn connect_tls(&self) {}
+
+        pub fn connect_plain(&self) {}
+    }
+"#;
+
+#[test]
+fn present_members() {
+    Fixture::new(CLIENT)
+        .version("1.4.0")
+        .compile()
+        .assert_success();
+
+    Fixture::new(CLIENT)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("fn `connect_tls` not allowed!")
+        .assert_contains("fn `connect_plain` not allowed!")
+        .assert_contains("const `LEGACY_TIMEOUT` not allowed!")
+        .assert_not_contains("fn `connect` not allowed!");
+}
+
+#[test]
+fn trait_members() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["flush", "Buffer"])]
+        pub trait Transport {
+            type Buffer;
+
+            fn send(&mut self, bytes: &[u8]);
+
+            fn flush(&mut self);
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("fn `flush` not allowed!")
+    .assert_contains("associated type `Buffer` not allowed!")
+    .assert_not_contains("`send`");
+}
+
+#[test]
+fn missing_member() {
+    // reported whether or not the requirement matches
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["connect", "shutdown"])]
+        impl Client {
+            pub fn connect(&self) {}
+        }
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("the impl block has no member `shutdown`")
+    .assert_contains("remove it from `members` if it was removed already");
+}
+
+#[test]
+fn renamed_member() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["legacy_timeout"])]
+        pub trait Connect {
+            fn legacy_timeout_ms(&self) -> u64;
+        }
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("trait `Connect` has no member `legacy_timeout`")
+    .assert_contains("did you mean `legacy_timeout_ms`?");
+}
+
+#[test]
+fn invalid_uses() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["a"])]
+        pub struct Client;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("`allow_members_until` only applies to impl blocks and traits");
+}
+
+const SKIPPED: &str = r#"
+    use allow_until::allow_until;
+
+    pub struct Client;
+
+    #[allow_until::allow_members_until(version = ">=2.0")]
+    impl Client {
+        pub const LEGACY_TIMEOUT: u64 = 30;
+
+        #[allow_until(skip)]
+        pub const TIMEOUT: u64 = 60;
+
+        pub fn connect_tls(&self) {}
+
+        #[allow_until(version = ">=1.8")]
+        pub fn legacy_handshake(&self) {}
+
+        #[allow_until(skip)]
+        pub fn connect(&self) {}
+    }
+
+    #[allow_until::allow_members_until(version = ">=2.0")]
+    pub trait Transport {
+        type LegacyBuffer;
+
+        #[allow_until(skip)]
+        type Buffer;
+
+        fn send(&mut self, bytes: &[u8]);
+    }
+"#;
+
+#[test]
+fn every_member_without_members() {
+    let outcome = Fixture::new(SKIPPED).version("2.0.0").compile();
+    outcome
+        .assert_failure()
+        .assert_contains("const `LEGACY_TIMEOUT` not allowed!")
+        .assert_contains("fn `connect_tls` not allowed!")
+        .assert_contains("fn `send` not allowed!")
+        .assert_contains("`LegacyBuffer` not allowed!");
+    // the markers are removed, so the attribute never sees `skip`
+    outcome
+        .assert_not_contains("`TIMEOUT` not allowed!")
+        .assert_not_contains("fn `connect` not allowed!")
+        .assert_not_contains("`Buffer` not allowed!")
+        .assert_not_contains("skip");
+}
+
+#[test]
+fn own_annotation_takes_precedence() {
+    // the member's earlier deadline fires on its own
+    Fixture::new(SKIPPED)
+        .version("1.9.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("fn `legacy_handshake` not allowed! (version 1.9.0 matches >=1.8)")
+        .assert_not_contains("`connect_tls` not allowed!");
+
+    // and the block's doesn't apply to it
+    Fixture::new(SKIPPED)
+        .version("2.0.0")
+        .compile()
+        .assert_contains("fn `legacy_handshake` not allowed! (version 2.0.0 matches >=1.8)")
+        .assert_not_contains("fn `legacy_handshake` not allowed! (version 2.0.0 matches >=2.0)");
+}
+
+#[test]
+fn skip_conflicts() {
+    Fixture::new(
+        r#"
+        use allow_until::allow_until;
+
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0")]
+        impl Client {
+            #[allow_until(skip)]
+            #[allow_until(version = ">=1.8")]
+            pub fn connect(&self) {}
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `connect` is marked `skip`, but has an annotation of its own")
+    .assert_contains("lib.rs:9:13")
+    .assert_contains("marked `skip` here")
+    .assert_contains("lib.rs:8:13");
+
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["connect"])]
+        impl Client {
+            #[allow_until(skip)]
+            pub fn connect(&self) {}
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("`connect` is listed in `members`, but marked `skip`");
+
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0")]
+        impl Client {
+            #[allow_until(skip, version = ">=1.8")]
+            pub fn connect(&self) {}
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `skip` can't be combined with other arguments")
+    .assert_contains("`skip` given here");
+}
+
+#[test]
+fn one_error_per_member() {
+    let outcome = Fixture::new(CLIENT).version("2.0.0").compile();
+    let stderr = outcome.assert_failure().normalized_stderr();
+    assert_eq!(stderr.matches("error: [AU0001]").count(), 3, "{}", stderr);
+    // each at the member's name rather than at the block's annotation
+    for expected in [
+        "error: [AU0001] fn `connect_tls` not allowed! (version 2.0.0 matches >=2.0)\n  \
+         --> $DIR/lib.rs:13:16\n",
+        "error: [AU0001] fn `connect_plain` not allowed! (version 2.0.0 matches >=2.0)\n  \
+         --> $DIR/lib.rs:15:16\n",
+        "error: [AU0001] const `LEGACY_TIMEOUT` not allowed! (version 2.0.0 matches >=2.0)\n \
+         --> $DIR/lib.rs:9:19\n",
+    ] {
+        assert!(stderr.contains(expected), "{}", stderr);
+    }
+    assert!(!stderr.contains("removal scheduled here"), "{}", stderr);
+}
+
+#[test]
+fn listed_member_with_own_annotation() {
+    let source = r#"
+        use allow_until::allow_until;
+
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["connect", "close"])]
+        impl Client {
+            #[allow_until(version = ">=3.0")]
+            pub fn connect(&self) {}
+
+            pub fn close(&self) {}
+        }
+    "#;
+
+    // the member's later deadline keeps it past the block's
+    Fixture::new(source)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("fn `close` not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_not_contains("`connect` not allowed!");
+
+    Fixture::new(source)
+        .version("3.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("fn `connect` not allowed! (version 3.0.0 matches >=3.0)")
+        .assert_not_contains("fn `connect` not allowed! (version 3.0.0 matches >=2.0)");
+}
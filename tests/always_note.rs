@@ -0,0 +1,114 @@
+//! `always_note`, which notes every evaluated annotation rather than only the expired ones.
+
+mod common;
+
+use common::Fixture;
+
+const ALWAYS_NOTE: &str = "[package.metadata.allow-until]\nalways_note = true\n";
+
+const ANNOTATIONS: &str = r#"
+        #[allow_until::allow_until(version = ">=3.0", reason = "use `Json`\nonce it is stable")]
+        pub struct Xml;
+
+        #[allow_until::allow_until(version = ">=2.0", grace = "1 minor")]
+        pub fn connect() {}
+
+        #[allow_until::allow_until(version = ">=1.0", severity = "warning")]
+        pub const TIMEOUT: u32 = 30;
+        "#;
+
+#[test]
+fn off_by_default() {
+    Fixture::new(ANNOTATIONS)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] const `TIMEOUT` not allowed!")
+        .assert_not_contains("AU0033");
+}
+
+#[test]
+fn every_status() {
+    let outcome = Fixture::new(ANNOTATIONS)
+        .manifest(ALWAYS_NOTE)
+        .version("2.0.0")
+        .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains(
+            "note: [AU0033] struct `Xml`: active, requirement `>=3.0` at version 2.0.0",
+        )
+        .assert_contains("= note: reason: use `Json`\n")
+        .assert_contains(
+            "note: [AU0033] fn `connect`: warning window, requirement `>=2.0` at version 2.0.0",
+        )
+        .assert_contains(
+            "note: [AU0033] const `TIMEOUT`: expired, requirement `>=1.0` at version 2.0.0",
+        )
+        // the expiry is reported as without the switch
+        .assert_contains("warning: [AU0001] const `TIMEOUT` not allowed!");
+    assert_eq!(outcome.stderr.matches("[AU0033]").count(), 3);
+}
+
+#[test]
+fn stable() {
+    Fixture::new(ANNOTATIONS)
+        .stable()
+        .manifest(ALWAYS_NOTE)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "note: [AU0033] struct `Xml`: active, requirement `>=3.0` at version 2.0.0",
+        )
+        .assert_contains("lib.rs:3:20")
+        .assert_contains("= note: reason: use `Json`\n");
+}
+
+#[test]
+fn capped() {
+    let outcome = Fixture::new(&format!(
+        "{}\n        allow_until::allow_until_summary!();\n",
+        ANNOTATIONS
+    ))
+    .manifest(&format!("{}max_errors = 1\n", ALWAYS_NOTE))
+    .version("2.0.0")
+    .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains("note: [AU0033] struct `Xml`: active")
+        .assert_contains(
+            "note: [AU0033] more allowances than the 1 noted; run with `ALLOW_UNTIL_VERBOSE=1` \
+             for the full list",
+        )
+        .assert_contains(
+            "note: [AU0033] …and 2 more allowances not noted; run with `ALLOW_UNTIL_VERBOSE=1` \
+             for the full list",
+        )
+        .assert_not_contains("fn `connect`:")
+        .assert_not_contains("const `TIMEOUT`:");
+
+    Fixture::new(ANNOTATIONS)
+        .manifest(&format!("{}max_errors = 1\n", ALWAYS_NOTE))
+        .env("ALLOW_UNTIL_VERBOSE", "1")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("fn `connect`: warning window")
+        .assert_contains("const `TIMEOUT`: expired")
+        .assert_not_contains("more allowances");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(ANNOTATIONS)
+        .manifest("[package.metadata.allow-until]\nalways_note = \"yes\"\n")
+        .version("2.0.0")
+        .compile()
+        .assert_contains(
+            "`always_note` in `[package.metadata.allow-until]` must be `true` or `false`",
+        )
+        .assert_not_contains("AU0033");
+}
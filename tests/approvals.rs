@@ -0,0 +1,113 @@
+mod common;
+
+use common::Fixture;
+
+const APPROVERS: &str = "[package.metadata.allow-until]\napprovers = [\"alice\", \"bob\"]\n";
+
+fn warning(approved_by: &str) -> String {
+    format!(
+        "#[allow_until::allow_until(version = \">=1.0\", severity = \"warning\"{})]\npub struct Legacy;\n",
+        approved_by
+    )
+}
+
+#[test]
+fn approved() {
+    Fixture::new(&warning(r#", approved_by = "alice""#))
+        .manifest(APPROVERS)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("AU0032");
+
+    Fixture::new("#[allow_until::allow_until(version = \">=1.0\", approved_by = \"bob\")]\npub struct Legacy;\n")
+        .manifest(APPROVERS)
+        .env("ALLOW_UNTIL_DOWNGRADE", "allow")
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn unapproved() {
+    Fixture::new(&warning(""))
+        .manifest(APPROVERS)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0032] reporting the expired annotation as a warning with \
+             `severity = \"warning\"` needs the sign-off of an approver",
+        )
+        .assert_contains("the approvers are `alice`, `bob`")
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(&warning(r#", approved_by = "carol""#))
+        .manifest(APPROVERS)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0032] `carol` is not one of the `approvers`")
+        .assert_contains("lib.rs:1:83")
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new("#[allow_until::allow_until(version = \">=1.0\")]\npub struct Legacy;\n")
+        .manifest(APPROVERS)
+        .env("ALLOW_UNTIL_DOWNGRADE", "warn")
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0032] downgrading the expired annotation with `ALLOW_UNTIL_DOWNGRADE=warn` \
+             needs the sign-off of an approver",
+        )
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn only_relaxed_expired_annotations() {
+    // not expired yet
+    Fixture::new(&warning(""))
+        .manifest(APPROVERS)
+        .version("0.9.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0032");
+
+    // expired, but not relaxed
+    Fixture::new("#[allow_until::allow_until(version = \">=1.0\")]\npub struct Legacy;\n")
+        .manifest(APPROVERS)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("AU0032");
+}
+
+#[test]
+fn missing_approver_list() {
+    Fixture::new(&warning(""))
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("AU0032");
+
+    Fixture::new(&warning(r#", approved_by = "alice""#))
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0008] `approved_by` is given, but no `approvers` are configured",
+        )
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(&warning(""))
+        .manifest("[package.metadata.allow-until]\napprovers = \"alice\"\n")
+        .version("1.0.0")
+        .compile()
+        .assert_contains(
+            "`approvers` in `[package.metadata.allow-until]` must be an array of names",
+        );
+}
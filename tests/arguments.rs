@@ -0,0 +1,358 @@
+mod common;
+
+use common::Fixture;
+
+fn annotate(args: &str) -> String {
+    format!(
+        "#[allow_until::allow_until({})]\npub struct Legacy;\n\n\
+         #[derive(allow_until::AllowUntil)]\npub struct Options {{\n    \
+         #[allow_until({})]\n    pub legacy: bool,\n}}\n",
+        args, args
+    )
+}
+
+#[test]
+fn missing_value() {
+    Fixture::new(&annotate(r#"version = ">=1.0", reason"#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected `=`")
+        .assert_contains("lib.rs:1:47")
+        .assert_contains("lib.rs:6:38");
+}
+
+#[test]
+fn not_a_string() {
+    Fixture::new(&annotate("version = 1"))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected string literal")
+        .assert_contains("lib.rs:1:38");
+
+    Fixture::new(&annotate("version = VERSION"))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected literal");
+
+    Fixture::new(&annotate(r#"version = ">=1.0", tags = ["a", 2]"#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected string literal")
+        .assert_contains("lib.rs:1:60");
+}
+
+#[test]
+fn unknown_argument() {
+    Fixture::new(&annotate(r#"version = ">=1.0", colour = "red""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] unknown argument `colour`")
+        .assert_contains("lib.rs:1:47")
+        .assert_contains(
+            "help: valid arguments are `version`, `reason`, `reason_key`, `id`, `tags`, `owner`, \
+             `issue`, `severity`, `grace`, `docsrs`, `version_source`, `allow_lint`, `changelog`, \
+             `public_only`, `silent`, `migration`, `test_guard`, `approved_by`, `deprecate_from`, \
+             `min_notice`, `superseded_by_feature`, `remove_with`, `not_before`, `watch`, \
+             `watch_hash`, `version_from`, `on_expiry`, `match_precision`, `branch`, \
+             `branch_pattern`, `require_deprecated`, `since`, `replacement`, `no_extend`, \
+             `horizon_ok`, `escalate_env`, `generic_default`, `doctest_ignore_from`, \
+             `doctest_fence`, `display_name`, `cargo`, `tool`, `tool_version`, `feature`, `cfg`, \
+             `doc_cfg` and `enforce`",
+        );
+}
+
+#[test]
+fn misspelled_argument() {
+    Fixture::new(&annotate(r#"verson = ">=1.0""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] unknown argument `verson`")
+        .assert_contains("lib.rs:1:28")
+        .assert_contains("= help: did you mean `version`?")
+        .assert_not_contains("valid arguments are");
+
+    Fixture::new(&annotate(r#"version = ">=1.0", versoin_source = "git""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("= help: did you mean `version_source`?");
+}
+
+#[test]
+fn missing_version() {
+    Fixture::new(&annotate(r#"reason = "old""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0006] missing required `version` argument")
+        .assert_contains("lib.rs:6:5");
+}
+
+#[test]
+fn malformed() {
+    Fixture::new(&annotate(r#"version = ">=1.0" reason = "old""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected `,`");
+
+    Fixture::new(&annotate(r#"version = ">=1.0",, "#))
+        .compile()
+        .assert_failure()
+        .assert_contains("help: valid arguments are");
+}
+
+#[test]
+fn trailing_comma() {
+    Fixture::new(&annotate(r#"version = ">=2.0", reason = "old","#))
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn requirement_matching_every_version() {
+    Fixture::new(&annotate(r#"version = "*""#))
+        .version("1.3.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("warning: [AU0022] the requirement `*` matches every version")
+        .assert_contains("lib.rs:1:38")
+        .assert_contains("= note: the item is flagged on every build")
+        .assert_contains(
+            r#"= help: use the version from which the item is no longer allowed, e.g. `">=2.0"`"#,
+        );
+
+    Fixture::new(&annotate(r#"version = ">=0.0.0""#))
+        .version("1.3.0")
+        .compile()
+        .assert_contains("warning: [AU0022] the requirement `>=0.0.0` matches every version");
+}
+
+#[test]
+fn bounded_requirement() {
+    Fixture::new(&annotate(r#"version = ">=1.0""#))
+        .version("0.9.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0022");
+}
+
+#[test]
+fn invalid_requirement_positions() {
+    for (req, column, error) in [
+        (
+            "two",
+            39,
+            "unexpected character 't' while parsing major version number",
+        ),
+        (
+            ">=1.0,, <2",
+            45,
+            "unexpected character ',' while parsing major version number",
+        ),
+        (
+            ">=1.0 <2",
+            45,
+            "expected comma after minor version number, found '<'",
+        ),
+        (
+            ">=",
+            41,
+            "unexpected end of input while parsing major version number",
+        ),
+    ] {
+        Fixture::new(&annotate(&format!("version = {:?}", req)))
+            .compile()
+            .assert_failure()
+            .assert_contains(&format!(
+                "error: [AU0002] invalid semver requirement `{}`: {}",
+                req, error
+            ))
+            .assert_contains(&format!("lib.rs:1:{}", column))
+            .assert_contains(r#"= help: requirements look like `">=2.0"` or `">=1.4, <2"`"#);
+    }
+}
+
+#[test]
+fn invalid_requirement_in_raw_string() {
+    Fixture::new(&annotate(r##"version = r#">=1.x.0"#"##))
+        .compile()
+        .assert_failure()
+        .assert_contains("unexpected character after wildcard in version req")
+        .assert_contains("lib.rs:1:46");
+}
+
+#[test]
+fn invalid_requirement_with_escapes() {
+    Fixture::new(&annotate(r#"version = "\x3e=1.0 <2""#))
+        .compile()
+        .assert_failure()
+        .assert_contains("invalid semver requirement `>=1.0 <2`")
+        .assert_contains("lib.rs:1:38");
+}
+
+#[test]
+fn string_literal_forms() {
+    for version in [r#"">=1.0""#, r#"r">=1.0""#, r##"r#">=1.0"#"##] {
+        for (reason, decoded) in [
+            (r#""don't use legacy mode""#, "don't use legacy mode"),
+            (r#"r"don't use \legacy mode""#, r"don't use \legacy mode"),
+            (
+                r##"r#"don't use "legacy" mode"#"##,
+                r#"don't use "legacy" mode"#,
+            ),
+        ] {
+            Fixture::new(&annotate(&format!(
+                "version = {}, reason = {}",
+                version, reason
+            )))
+            .version("1.0.0")
+            .compile()
+            .assert_failure()
+            .assert_contains(&format!(
+                "error: [AU0001] {} (version 1.0.0 matches >=1.0)",
+                decoded
+            ));
+        }
+    }
+}
+
+#[test]
+fn byte_strings() {
+    for literal in [r#"b">=1.0""#, r##"br#">=1.0"#"##, r#"c">=1.0""#] {
+        Fixture::new(&annotate(&format!("version = {}", literal)))
+            .compile()
+            .assert_failure()
+            .assert_contains("error: [AU0005] expected string literal, found a byte string")
+            .assert_contains("lib.rs:1:38")
+            .assert_contains("= help: remove the `b` or `c` prefix");
+    }
+}
+
+#[test]
+fn reason_escapes() {
+    Fixture::new(&annotate(
+        r#"version = ">=1.0", reason = "use \"modern\" mode \\ \u{2713} instead""#,
+    ))
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] use \"modern\" mode \\ \u{2713} instead (version 1.0.0");
+}
+
+#[test]
+fn multi_line_reason() {
+    let outcome = Fixture::new(&annotate(
+        r#"version = ">=1.0", reason = "legacy mode is gone\nuse `Options::modern`\nsee the changelog""#,
+    ))
+    .version("1.0.0")
+    .compile();
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] legacy mode is gone (version 1.0.0 matches >=1.0)");
+    assert!(outcome
+        .normalized_stderr()
+        .contains("  = note: use `Options::modern`\n          see the changelog\n"));
+}
+
+#[test]
+fn duplicate_arguments() {
+    for (key, value) in [
+        ("version", r#"">=2.0""#),
+        ("reason", r#""soon""#),
+        ("id", r#""legacy""#),
+        ("tags", r#"["config"]"#),
+        ("owner", r#""io-team""#),
+        ("issue", r#""12""#),
+        ("severity", r#""warning""#),
+        ("grace", r#""1 minor""#),
+        ("docsrs", r#""ignore""#),
+        ("version_source", r#""cargo""#),
+    ] {
+        let args = if key == "version" {
+            format!("{} = {}, {} = {}", key, value, key, value)
+        } else {
+            format!(
+                r#"version = ">=2.0", {} = {}, {} = {}"#,
+                key, value, key, value
+            )
+        };
+        let first = if key == "version" { 28 } else { 47 };
+        let second = first + key.len() + value.len() + 5;
+
+        Fixture::new(&annotate(&args))
+            .compile()
+            .assert_failure()
+            .assert_contains(&format!("error: [AU0005] duplicate `{}` argument", key))
+            .assert_contains(&format!("lib.rs:1:{}", second))
+            .assert_contains("note: first given here")
+            .assert_contains(&format!("lib.rs:1:{}", first));
+    }
+}
+
+#[test]
+fn repeated_allow_lint() {
+    Fixture::new(&annotate(
+        r#"version = "1.0", allow_lint = "comparator_style", allow_lint = "comparator_style""#,
+    ))
+    .version("0.9.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("duplicate");
+}
+
+#[test]
+fn literal_kinds() {
+    for key in ["version", "reason"] {
+        for (literal, found, help) in [
+            (
+                "1.0",
+                "a number",
+                Some(r#"= help: write it as a string, `"1.0"`"#),
+            ),
+            (
+                "2",
+                "a number",
+                Some(r#"= help: write it as a string, `"2"`"#),
+            ),
+            (
+                "0x1f",
+                "a number",
+                Some(r#"= help: write it as a string, `"0x1f"`"#),
+            ),
+            ("'v'", "a character", None),
+            ("b'v'", "a byte", None),
+            ("true", "a boolean", None),
+        ] {
+            let args = if key == "version" {
+                format!("version = {}", literal)
+            } else {
+                format!(r#"reason = {}, version = ">=2.0""#, literal)
+            };
+            let outcome = Fixture::new(&annotate(&args)).compile();
+            outcome
+                .assert_failure()
+                .assert_contains(&format!(
+                    "error: [AU0005] expected string literal, found {}",
+                    found
+                ))
+                .assert_contains(&format!("lib.rs:1:{}", 31 + key.len()));
+            match help {
+                Some(help) => outcome.assert_contains(help),
+                None => outcome.assert_not_contains("help: write"),
+            };
+        }
+    }
+}
+
+#[test]
+fn bare_helper_attribute() {
+    Fixture::new(
+        "#[derive(allow_until::AllowUntil)]\npub struct Options {\n    \
+         #[allow_until]\n    pub legacy: bool,\n    \
+         #[allow_until = \">=1.0\"]\n    pub other: bool,\n}\n",
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0006] missing required `version` argument")
+    .assert_contains("lib.rs:3:5")
+    .assert_contains("error: [AU0005] expected arguments in parentheses")
+    .assert_contains("lib.rs:5:19");
+}
@@ -0,0 +1,105 @@
+//! `attr_until`, which attaches an attribute to its item on one side of a version.
+
+mod common;
+
+use common::Fixture;
+
+const DEPRECATED: &str = r#"
+    #[allow_until::attr_until(version = ">=2.0", attr = deprecated(note = "use `connect_async`"))]
+    pub fn connect() {}
+
+    pub fn caller() {
+        connect();
+    }
+"#;
+
+#[test]
+fn attached_until_the_version() {
+    Fixture::new(DEPRECATED)
+        .version("1.4.0")
+        .compile()
+        .assert_success()
+        .assert_contains("use of deprecated function `connect`: use `connect_async`");
+
+    Fixture::new(DEPRECATED)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("deprecated");
+}
+
+#[test]
+fn attached_from_the_version() {
+    let source = r#"
+        #[allow_until::attr_until(version = ">=2.0", attr = allow(dead_code), when = "matched")]
+        fn unused() {}
+    "#;
+
+    Fixture::new(source)
+        .version("1.4.0")
+        .compile()
+        .assert_success()
+        .assert_contains("function `unused` is never used");
+
+    Fixture::new(source)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("never used");
+}
+
+#[test]
+fn attribute_with_a_value() {
+    let source = r#"
+        #[allow_until::attr_until(version = ">=2.0", attr = doc = "Deprecated, see `Next`.")]
+        pub struct Legacy;
+    "#;
+
+    // the item compiles with the attribute attached, and without
+    for version in ["1.4.0", "2.0.0"] {
+        Fixture::new(source)
+            .version(version)
+            .compile()
+            .assert_success();
+    }
+}
+
+#[test]
+fn tokens_keep_their_spans() {
+    Fixture::new(
+        r#"#[allow_until::attr_until(version = ">=2.0", attr = deprecated(note = 5))]
+pub fn connect() {}
+"#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    // the attribute is placed at the `attr` argument, its tokens where they were written
+    .assert_contains("lib.rs:1:46");
+}
+
+#[test]
+fn missing_arguments() {
+    Fixture::new(
+        r#"
+        #[allow_until::attr_until(version = ">=2.0")]
+        pub fn connect() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("missing required `attr` argument")
+    .assert_contains(
+        "optional arguments are `when`, `id`, `tags`, `owner`, `issue`, `version_source`",
+    );
+
+    Fixture::new(
+        r#"
+        #[allow_until::attr_until(attr = must_use, when = "before")]
+        pub fn connect() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid `when` value `before`, expected `\"matched\"` or `\"unmatched\"`");
+}
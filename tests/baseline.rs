@@ -0,0 +1,124 @@
+mod common;
+
+use common::Fixture;
+
+const FROZEN: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+    pub struct XmlConfig;
+
+    #[allow_until::allow_until(version = ">=3.0", reason = "use `connect`")]
+    pub fn connect_tls() {}
+
+    #[derive(allow_until::AllowUntil)]
+    pub struct Options {
+        #[allow_until(version = ">=3.0")]
+        pub legacy: bool,
+    }
+"#;
+
+#[test]
+fn only_new_annotations_error() {
+    let baseline =
+        std::env::temp_dir().join(format!("allow-until-baseline-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&baseline);
+    let baseline = baseline.to_str().unwrap();
+
+    Fixture::new(FROZEN)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", baseline)
+        .env("ALLOW_UNTIL_BASELINE_RECORD", "1")
+        .compile()
+        .assert_success();
+
+    let recorded = std::fs::read_to_string(baseline).unwrap();
+    assert!(recorded.contains("\"drop-xml-config\""), "{}", recorded);
+
+    // unchanged sources, with unrelated code added in between
+    let shifted = format!("pub struct Unrelated;\n{}", FROZEN);
+    Fixture::new(&shifted)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", baseline)
+        .compile()
+        .assert_success();
+
+    let added = format!(
+        "{}{}",
+        FROZEN,
+        r#"
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub struct NewDebt;
+        "#
+    );
+    let outcome = Fixture::new(&added)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", baseline)
+        .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("is not in the baseline")
+        .assert_contains("a freeze on new allow_until annotations is in effect");
+    assert_eq!(outcome.stderr.matches("is not in the baseline").count(), 1);
+
+    let _ = std::fs::remove_file(baseline);
+}
+
+#[test]
+fn missing_baseline() {
+    Fixture::new(FROZEN)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", "/nonexistent/baseline.json")
+        .compile()
+        .assert_failure()
+        .assert_contains("failed to read the baseline `/nonexistent/baseline.json`");
+}
+
+#[test]
+fn recording_over_a_corrupted_baseline() {
+    let baseline = std::env::temp_dir().join(format!(
+        "allow-until-baseline-corrupted-{}.json",
+        std::process::id()
+    ));
+    std::fs::write(&baseline, "{\"annotations\": [\"au-").unwrap();
+    let baseline = baseline.to_str().unwrap();
+
+    let outcome = Fixture::new(FROZEN)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", baseline)
+        .env("ALLOW_UNTIL_BASELINE_RECORD", "1")
+        .compile();
+
+    outcome.assert_success().assert_contains(&format!(
+        "the baseline `{}` is malformed, expected `{{\"annotations\": [...]}}`, it has been replaced",
+        baseline
+    ));
+    assert_eq!(outcome.stderr.matches("is malformed").count(), 1);
+
+    Fixture::new(FROZEN)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_BASELINE", baseline)
+        .compile()
+        .assert_success();
+
+    let _ = std::fs::remove_file(baseline);
+}
+
+#[test]
+fn written_on_windows() {
+    // with a byte order mark, CRLF line endings and a path with backslashes
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .file(
+        "baselines/frozen.json",
+        "\u{feff}{\r\n  \"annotations\": [\r\n    \"drop-xml-config\"\r\n  ]\r\n}\r\n",
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_BASELINE", r"baselines\frozen.json")
+    .compile()
+    .assert_success()
+    .assert_not_contains("baseline");
+}
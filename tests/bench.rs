@@ -0,0 +1,110 @@
+//! Expansion benchmarks: compiles synthetic crates with 100, 1k and 10k annotations, through the
+//! attribute and through the derive, and compares each against the same crate without
+//! annotations, so the time rustc itself takes is subtracted.
+//!
+//! Run with `cargo +nightly test --test bench -- --nocapture` to print the timings. The proc macro
+//! is a debug build, as in the other tests, so absolute numbers are pessimistic.
+//!
+//! Recorded on the machine the thresholds were chosen on, in microseconds per annotation:
+//!
+//! | annotations | attribute | derive |
+//! |------------:|----------:|-------:|
+//! |         100 |        70 |     30 |
+//! |       1 000 |        60 |     65 |
+//! |      10 000 |        65 |     85 |
+//!
+//! The runs with 100 annotations are too short to be more than indicative, so only the larger two
+//! are checked. [`MAX_OVERHEAD`] leaves room for slower CI machines, while [`MAX_GROWTH`] is
+//! independent of the machine and catches work that grows with the number of annotations already
+//! expanded, such as searching a list of every id seen so far.
+
+mod common;
+
+use std::time::Duration;
+
+use common::Fixture;
+
+/// The largest overhead per annotation, at 10k annotations, before the benchmark fails.
+const MAX_OVERHEAD: Duration = Duration::from_millis(1);
+
+/// How much larger the overhead per annotation may be at 10k annotations than at 1k.
+const MAX_GROWTH: f64 = 2.0;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn attribute(n: usize, annotated: bool) -> String {
+    let mut source = String::new();
+    for i in 0..n {
+        if annotated {
+            source.push_str(&format!(
+                "#[allow_until::allow_until(version = \">=2.0\", reason = \"gone\", id = \"item-{i}\")]\n"
+            ));
+        }
+        source.push_str(&format!("pub struct Item{i};\n"));
+    }
+    source
+}
+
+fn derive(n: usize, annotated: bool) -> String {
+    let mut source = String::from("#[derive(allow_until::AllowUntil)]\npub struct Fields {\n");
+    for i in 0..n {
+        if annotated {
+            source.push_str(&format!(
+                "    #[allow_until(version = \">=2.0\", reason = \"gone\", id = \"field-{i}\")]\n"
+            ));
+        }
+        source.push_str(&format!("    pub field{i}: u8,\n"));
+    }
+    source.push_str("}\n");
+    source
+}
+
+fn time(source: &str) -> Duration {
+    let start = std::time::Instant::now();
+    Fixture::new(source)
+        .version("1.0.0")
+        .compile()
+        .assert_success();
+    start.elapsed()
+}
+
+/// The time the annotations add to the compilation, per annotation, for each of [`SIZES`].
+fn overhead(name: &str, generate: fn(usize, bool) -> String) -> Vec<Duration> {
+    // builds the proc macro, so it isn't part of the first measurement
+    time(&generate(1, true));
+
+    SIZES
+        .iter()
+        .map(|&n| {
+            let plain = time(&generate(n, false));
+            let annotated = time(&generate(n, true));
+            let per = annotated.saturating_sub(plain) / n as u32;
+            eprintln!("{name}: {n} annotations in {annotated:?} ({plain:?} without), {per:?} each");
+            per
+        })
+        .collect()
+}
+
+#[track_caller]
+fn check(name: &str, overhead: &[Duration]) {
+    let (thousand, largest) = (overhead[1], overhead[2]);
+
+    assert!(
+        largest <= MAX_OVERHEAD,
+        "{name}: {largest:?} per annotation exceeds {MAX_OVERHEAD:?}"
+    );
+    assert!(
+        largest.as_secs_f64() <= thousand.as_secs_f64() * MAX_GROWTH,
+        "{name}: {largest:?} per annotation at 10k annotations against {thousand:?} at 1k"
+    );
+}
+
+// a single test, so the measurements don't compete with each other for the machine
+#[test]
+fn expansion() {
+    let attribute = overhead("attribute", attribute);
+    let derive = overhead("derive", derive);
+
+    check("attribute", &attribute);
+    check("derive", &derive);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::Fixture;
+use serde_json::Value;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+pub struct XmlConfig;
+"#;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args([
+            "-c",
+            "user.name=Ada Lovelace",
+            "-c",
+            "user.email=ada@example.com",
+        ])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", "2024-03-01T12:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2024-03-01T12:00:00Z")
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn committed(dir: &Path) {
+    git(dir, &["init", "-q"]);
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-qm", "init"]);
+}
+
+fn report_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "allow-until-blame-{}-{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn compile(fixture: Fixture, report: &Path) -> Vec<Value> {
+    fixture
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .env("ALLOW_UNTIL_BLAME", "1")
+        .compile()
+        .assert_success()
+        .assert_not_contains("warning");
+
+    let entries = std::fs::read_to_string(report)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let _ = std::fs::remove_file(report);
+    entries
+}
+
+#[test]
+fn authorship() {
+    let entries = compile(
+        Fixture::new(SOURCE).setup(committed),
+        &report_path("committed"),
+    );
+
+    assert_eq!(
+        entries[0]["blame"],
+        serde_json::json!({
+            "author": "Ada Lovelace",
+            "email": "ada@example.com",
+            "date": "2024-03-01T12:00:00Z",
+        })
+    );
+}
+
+#[test]
+fn uncommitted_lines() {
+    let entries = compile(
+        Fixture::new(SOURCE).setup(|dir| {
+            committed(dir);
+            let source = std::fs::read_to_string(dir.join("lib.rs")).unwrap();
+            std::fs::write(
+                dir.join("lib.rs"),
+                format!(
+                    "#[allow_until::allow_until(version = \">=3.0\")]\npub struct New;\n{}",
+                    source
+                ),
+            )
+            .unwrap();
+        }),
+        &report_path("dirty"),
+    );
+
+    assert_eq!(entries[0]["blame"], Value::Null);
+    assert_eq!(entries[1]["blame"]["author"], "Ada Lovelace");
+}
+
+#[test]
+fn outside_of_git() {
+    let entries = compile(Fixture::new(SOURCE), &report_path("untracked"));
+    assert_eq!(entries[0]["blame"], Value::Null);
+}
+
+#[test]
+fn opt_in() {
+    let report = report_path("disabled");
+    Fixture::new(SOURCE)
+        .setup(committed)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let entry: Value =
+        serde_json::from_str(std::fs::read_to_string(&report).unwrap().trim()).unwrap();
+    assert_eq!(entry["blame"], Value::Null);
+    let _ = std::fs::remove_file(&report);
+}
@@ -0,0 +1,125 @@
+//! `branch` and `branch_pattern`, which only enforce an annotation on some git branches.
+
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::Fixture;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn on_branch(branch: &'static str) -> impl FnOnce(&Path) {
+    move |dir| {
+        git(dir, &["init", "-q"]);
+        git(dir, &["checkout", "-qb", branch]);
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-qm", "init"]);
+    }
+}
+
+const PATTERN: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", branch_pattern = "release/.*")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn pattern() {
+    Fixture::new(PATTERN)
+        .version("2.0.0")
+        .setup(on_branch("release/2.x"))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(PATTERN)
+        .version("2.0.0")
+        .setup(on_branch("main"))
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("branch `main` does not match `branch_pattern = \"release/.*\"`")
+        .assert_contains("skipped, as the annotation is for another branch");
+}
+
+#[test]
+fn name() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0", branch = "main")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .version("2.0.0")
+        .setup(on_branch("main"))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(source)
+        .version("2.0.0")
+        .setup(on_branch("main-backport"))
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn override_beats_git() {
+    Fixture::new(PATTERN)
+        .version("2.0.0")
+        .setup(on_branch("main"))
+        .env("ALLOW_UNTIL_BRANCH", "release/2.x")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn unknown_branch() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", branch = "main")]
+        pub struct Legacy;
+        #[allow_until::allow_until(version = ">=3.0", branch = "main")]
+        pub struct Older;
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("warning: [AU0041] the current git branch is unknown")
+    .assert_contains("help: set `ALLOW_UNTIL_BRANCH` to the branch being built")
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", branch_pattern = "release/(.*")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid pattern `release/(.*`: unclosed group");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", branch = "main", branch_pattern = "release/.*")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("`branch_pattern` can't be combined with `branch`");
+}
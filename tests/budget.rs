@@ -0,0 +1,70 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct A;
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct B;
+    #[allow_until::allow_until(version = ">=3.0")]
+    pub struct C;
+
+    allow_until::allow_until_summary!();
+"#;
+
+#[test]
+fn exceeding_the_budget() {
+    Fixture::new(SOURCE)
+        .env("ALLOW_UNTIL_MAX", "2")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0012] annotation number 3 exceeds the budget of 2 set by `ALLOW_UNTIL_MAX`")
+        .assert_contains("version = \">=3.0\"")
+        .assert_contains(
+            "error: [AU0012] the crate contains 3 allow_until annotations, but the budget set by `ALLOW_UNTIL_MAX` is 2",
+        );
+}
+
+#[test]
+fn within_the_budget() {
+    Fixture::new(SOURCE)
+        .env("ALLOW_UNTIL_MAX", "3")
+        .compile()
+        .assert_success();
+
+    Fixture::new(SOURCE).compile().assert_success();
+}
+
+#[test]
+fn repeated_expansion_counts_once() {
+    Fixture::new(
+        r#"
+        macro_rules! twice {
+            ($item:item) => {
+                pub mod first { $item }
+                pub mod second { $item }
+            };
+        }
+
+        twice! {
+            #[allow_until::allow_until(version = ">=2.0")]
+            pub struct A;
+        }
+
+        allow_until::allow_until_summary!();
+    "#,
+    )
+    .env("ALLOW_UNTIL_MAX", "1")
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn invalid_budget() {
+    Fixture::new(SOURCE)
+        .env("ALLOW_UNTIL_MAX", "lots")
+        .compile()
+        .assert_success()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_MAX` value `lots`, expected a number");
+}
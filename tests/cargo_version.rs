@@ -0,0 +1,109 @@
+//! `cargo`, which conditions an annotation on the version of the Cargo building the crate.
+
+mod common;
+
+use common::Fixture;
+
+const CARGO_ONLY: &str = r#"
+    #[allow_until::allow_until(cargo = ">=1.70", reason = "sparse index by default")]
+    pub struct Legacy;
+"#;
+
+const BOTH: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", cargo = ">=1.70")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn before_the_cargo_version() {
+    Fixture::new(CARGO_ONLY)
+        .env("ALLOW_UNTIL_CARGO_VERSION", "1.69.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn from_the_cargo_version() {
+    Fixture::new(CARGO_ONLY)
+        .env("ALLOW_UNTIL_CARGO_VERSION", "1.75")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] sparse index by default (Cargo 1.75.0 matches >=1.70)")
+        .assert_contains("the `cargo` requirement `>=1.70` matches Cargo 1.75.0")
+        .assert_not_contains("the requirement `*`")
+        .assert_not_contains("extend the deadline");
+}
+
+#[test]
+fn every_condition_must_be_satisfied() {
+    for (version, cargo) in [("1.0.0", "1.75.0"), ("2.0.0", "1.69.0")] {
+        Fixture::new(BOTH)
+            .version(version)
+            .env("ALLOW_UNTIL_CARGO_VERSION", cargo)
+            .compile()
+            .assert_success()
+            .assert_not_contains("AU0001");
+    }
+
+    Fixture::new(BOTH)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_CARGO_VERSION", "1.75.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `Legacy` not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_contains("the requirement `>=2.0` matches version 2.0.0")
+        .assert_contains("the `cargo` requirement `>=1.70` matches Cargo 1.75.0");
+}
+
+#[test]
+fn building_cargo() {
+    // the Cargo running the tests is the one asked
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(cargo = ">=1.0")]
+        pub struct Legacy;
+        #[allow_until::allow_until(cargo = "<1.0")]
+        pub struct Future;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("struct `Legacy` not allowed! (Cargo 1.")
+    .assert_not_contains("struct `Future` not allowed!")
+    .assert_not_contains("AU0047");
+}
+
+#[test]
+fn unknown_cargo_version() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(cargo = ">=1.70")]
+        pub struct Legacy;
+        #[allow_until::allow_until(version = ">=1.0", cargo = ">=1.70")]
+        pub struct Other;
+        "#,
+    )
+    .env("ALLOW_UNTIL_CARGO_VERSION", "latest")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0047] the version of the Cargo building the crate is unknown, annotations \
+         conditioned on it don't fire: `ALLOW_UNTIL_CARGO_VERSION` is invalid",
+    )
+    .assert_contains("set `ALLOW_UNTIL_CARGO_VERSION` to the version of Cargo")
+    .assert_not_contains("AU0001");
+}
+
+#[test]
+fn invalid_requirement() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(cargo = "new")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0002] invalid semver requirement `new`");
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", changelog = true)]
+    pub struct XmlConfig;
+
+    #[allow_until::allow_until(version = ">=2.0", id = "drop-json-v1", changelog = true)]
+    pub struct JsonV1;
+
+    #[derive(allow_until::AllowUntil)]
+    pub struct Options {
+        #[allow_until(version = ">=2.0", changelog = true)]
+        pub legacy: bool,
+    }
+"#;
+
+#[test]
+fn mentioned() {
+    Fixture::new(SOURCE)
+        .file(
+            "CHANGELOG.md",
+            "# Changelog\n\n\
+             ## [Unreleased]\n\n\
+             ### Removed\n\
+             - `XmlConfig`, use `Config` instead\n\
+             - the v1 JSON format (drop-json-v1)\n\
+             - the `legacy` option\n\n\
+             ## [1.0.0] - 2024-01-01\n",
+        )
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `XmlConfig` not allowed!")
+        .assert_not_contains("AU0026");
+}
+
+#[test]
+fn not_mentioned() {
+    let outcome = Fixture::new(SOURCE)
+        .file(
+            "CHANGELOG.md",
+            "# Changelog\n\n\
+             ## [Unreleased]\n\
+             - the `legacy` option\n\n\
+             ## [1.0.0]\n\
+             - `XmlConfig` and drop-json-v1\n",
+        )
+        .version("2.0.0")
+        .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains(
+            "warning: [AU0026] the removal of the expired struct `XmlConfig` is not in the \
+             Unreleased section of `CHANGELOG.md`",
+        )
+        .assert_contains("help: add an entry mentioning `XmlConfig` under the Unreleased heading")
+        .assert_contains("help: add an entry mentioning `JsonV1` or `drop-json-v1`");
+    assert_eq!(outcome.stderr.matches("AU0026").count(), 2);
+}
+
+#[test]
+fn only_once_expired() {
+    Fixture::new(SOURCE)
+        .file("CHANGELOG.md", "## Unreleased\n")
+        .version("1.9.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0026");
+}
+
+#[test]
+fn no_unreleased_section() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning", changelog = true)]
+        pub struct XmlConfig;
+        "#,
+    )
+    .file("CHANGELOG.md", "# Changelog\n\n## 1.0.0\n- `XmlConfig`\n")
+    .version("2.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0026] the removal of the expired struct `XmlConfig` is not in \
+         `CHANGELOG.md`, which has no Unreleased section",
+    );
+}
+
+#[test]
+fn missing_file() {
+    let outcome = Fixture::new(SOURCE).version("2.0.0").compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains(
+            "warning: [AU0020] `changelog` is set, but `CHANGELOG.md` could not be read",
+        )
+        .assert_not_contains("AU0026");
+    assert_eq!(outcome.stderr.matches("could not be read").count(), 1);
+}
+
+#[test]
+fn path_override() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning", changelog = "docs/CHANGES.md")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning", changelog = false)]
+        pub struct JsonV1;
+    "#;
+
+    Fixture::new(source)
+        .file("docs/CHANGES.md", "## Unreleased\n- removed XmlConfig\n")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0026")
+        .assert_not_contains("AU0020");
+
+    Fixture::new(source)
+        .file("CHANGELOG.md", "## Unreleased\n- removed XmlConfig\n")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("`changelog` is set, but `docs/CHANGES.md` could not be read");
+}
@@ -0,0 +1,91 @@
+//! `#[changes_at]`, which reminds the maintainer of a behavior change once its version is reached,
+//! without ever failing the build.
+
+mod common;
+
+use allow_until_report::{AnnotationKind, Report};
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::changes_at(version = ">=2.0", note = "returns `Err` instead of panicking")]
+    pub fn parse(input: &str) -> u32 {
+        input.parse().unwrap()
+    }
+"#;
+
+#[test]
+fn before_the_change() {
+    Fixture::new(SOURCE)
+        .version("1.9.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0038");
+
+    Fixture::new(SOURCE)
+        .version("1.9.0")
+        .manifest("[package.metadata.allow-until]\nalways_note = true\n")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "[AU0038] fn `parse` changes behavior at `>=2.0`: returns `Err` instead of panicking",
+        );
+}
+
+#[test]
+fn from_the_change() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0038] fn `parse` changes behavior at `>=2.0`: returns `Err` instead of \
+             panicking",
+        )
+        .assert_contains("version 2.0.0 matches >=2.0")
+        .assert_contains("implement and document the new behavior, then remove `#[changes_at]`");
+}
+
+#[test]
+fn report() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-changes-at-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let text = std::fs::read_to_string(&report).unwrap();
+    let _ = std::fs::remove_file(&report);
+    let report = Report::parse(&text).unwrap();
+    let entry = &report.annotations[0];
+    assert_eq!(entry.annotation_kind, AnnotationKind::ChangesAt);
+    assert_eq!(
+        entry.reason.as_deref(),
+        Some("returns `Err` instead of panicking")
+    );
+    assert!(entry.outcome.matched);
+    // the item stays, so the entry isn't an expired annotation
+    assert_eq!(report.expired().count(), 0);
+}
+
+#[test]
+fn invalid() {
+    Fixture::new("#[allow_until::changes_at(note = \"panics\")]\npub fn parse() {}\n")
+        .compile()
+        .assert_failure()
+        .assert_contains("missing required `version` argument")
+        .assert_contains("optional arguments are `note`, `id`, `tags`");
+
+    Fixture::new(
+        "#[allow_until::changes_at(version = \">=2.0\", severity = \"error\")]\npub fn parse() {}\n",
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("unknown argument `severity`")
+    .assert_contains("valid arguments are `version`, `note`");
+}
@@ -0,0 +1,254 @@
+//! Every kind of diagnostic is prefixed with its code, and `explain!` expands to the explanation
+//! of a code.
+
+mod common;
+
+use common::Fixture;
+
+const EXPIRED: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+"#;
+
+const PENDING: &str = r#"
+    #[allow_until::allow_until(version = ">=3.0", id = "legacy")]
+    pub struct Legacy;
+
+    allow_until::allow_until_summary!();
+"#;
+
+#[test]
+fn expired() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn invalid_requirement() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = "two")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0002] invalid semver requirement");
+}
+
+#[test]
+fn no_version() {
+    Fixture::new(EXPIRED)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_VERSION", "two")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0003] invalid `ALLOW_UNTIL_VERSION` value `two`");
+}
+
+#[test]
+fn invalid_input() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", colour = "red")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown argument");
+}
+
+#[test]
+fn missing_version() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(reason = "soon")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0006] missing required `version` argument");
+}
+
+#[test]
+fn missing_required() {
+    Fixture::new(EXPIRED)
+        .manifest(
+            r#"
+            [package]
+            name = "fixture"
+            version = "1.0.0"
+
+            [package.metadata.allow-until]
+            required = ["owner"]
+            "#,
+        )
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0007] annotation is missing the required argument `owner`");
+}
+
+#[test]
+fn invalid_config() {
+    Fixture::new(EXPIRED)
+        .manifest(
+            r#"
+            [package]
+            name = "fixture"
+            version = "1.0.0"
+
+            [package.metadata.allow-until]
+            severty = "warning"
+            "#,
+        )
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0008] unknown key `severty`");
+}
+
+#[test]
+fn invalid_env() {
+    Fixture::new(EXPIRED)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_MAX", "many")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0009]");
+}
+
+#[test]
+fn downgraded() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "warn")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0010] `ALLOW_UNTIL_DOWNGRADE=warn` is set")
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn simulated() {
+    Fixture::new(EXPIRED)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_SIMULATE", "2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0011] [simulated 2.0.0] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn over_budget() {
+    Fixture::new(PENDING)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_MAX", "0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0012] annotation number 1 exceeds the budget of 0");
+}
+
+#[test]
+fn not_in_baseline() {
+    Fixture::new(PENDING)
+        .version("1.0.0")
+        .file("baseline.json", r#"{"annotations": []}"#)
+        .env("ALLOW_UNTIL_BASELINE", "baseline.json")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0013] annotation `legacy` is not in the baseline");
+}
+
+#[test]
+fn suppressed() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "legacy")]
+        pub struct Legacy;
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("2.0.0")
+    .env("ALLOW_UNTIL_SUPPRESS", "legacy")
+    .compile()
+    .assert_success()
+    .assert_contains("warning: [AU0016] `ALLOW_UNTIL_SUPPRESS` suppressed 1 expired annotation(s)");
+}
+
+#[test]
+fn stale_suppression() {
+    Fixture::new(PENDING)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_SUPPRESS", "removed")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0017] `ALLOW_UNTIL_SUPPRESS` lists ids that match no annotation",
+        );
+}
+
+#[test]
+fn summary_and_trace() {
+    Fixture::new(PENDING)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("note: [AU0018] allow-until: 1 active allowance")
+        .assert_contains("note: [AU0019] allow_until trace");
+}
+
+#[test]
+fn file_access() {
+    Fixture::new(EXPIRED)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", "/nonexistent/dir/report.jsonl")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0020] failed to write the allow-until report");
+}
+
+#[test]
+fn internal() {
+    Fixture::new(EXPIRED)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_TEST_PANIC", "1")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0021] internal error in `allow_until`");
+}
+
+#[test]
+fn explain() {
+    Fixture::new(
+        r#"
+        const EXPLANATION: &str = allow_until::explain!("AU0001");
+        const _: () = assert!(EXPLANATION.len() > 0);
+        "#,
+    )
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn explain_unknown_code() {
+    Fixture::new(
+        r#"
+        const EXPLANATION: &str = allow_until::explain!("AU9999");
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown diagnostic code `AU9999`")
+    .assert_contains("codes range from AU0001 to AU0050");
+}
@@ -0,0 +1,346 @@
+//! Compiles small fixture crates against the freshly built proc macro so tests can
+//! observe the diagnostics it emits under a chosen environment.
+//!
+//! The proc macro is built with the `nightly` feature, unless [`Fixture::stable`] asks for it to
+//! be built, and the fixture compiled, with the stable toolchain. [`Fixture::fallback`] builds it
+//! with the `nightly` feature on the stable toolchain, where the unstable APIs don't compile.
+
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+type Setup = Box<dyn FnOnce(&Path)>;
+
+pub struct Fixture {
+    source: String,
+    manifest: Option<String>,
+    files: Vec<(String, String)>,
+    envs: Vec<(String, String)>,
+    args: Vec<String>,
+    member: Option<String>,
+    examples: Vec<String>,
+    setup: Option<Setup>,
+    stable: bool,
+    nightly_feature: bool,
+}
+
+pub struct Outcome {
+    pub success: bool,
+    pub stderr: String,
+    pub stdout: String,
+    pub dir: PathBuf,
+}
+
+impl Fixture {
+    pub fn new(source: &str) -> Self {
+        Fixture {
+            source: source.into(),
+            manifest: None,
+            files: Vec::new(),
+            envs: Vec::new(),
+            args: Vec::new(),
+            member: None,
+            examples: Vec::new(),
+            setup: None,
+            stable: false,
+            nightly_feature: true,
+        }
+    }
+
+    /// Sets the `CARGO_PKG_VERSION` seen by the macro.
+    pub fn version(self, version: &str) -> Self {
+        self.env("CARGO_PKG_VERSION", version)
+    }
+
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Passes `arg` to rustc when compiling the fixture, e.g. `--cfg` flags.
+    pub fn rustc_arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Compiles the fixture as the package at `path`, relative to the fixture's root, which is then
+    /// the root of a workspace. Only `CARGO_MANIFEST_DIR` changes, the source stays at the root.
+    pub fn member(mut self, path: &str) -> Self {
+        self.member = Some(path.into());
+        self
+    }
+
+    /// Makes the proc macro crate of the workspace package `package`, one of the examples, available
+    /// to the fixture next to `allow_until`.
+    pub fn example(mut self, package: &str) -> Self {
+        self.examples.push(package.into());
+        self
+    }
+
+    /// Writes a `Cargo.toml` next to the fixture source.
+    pub fn manifest(mut self, manifest: &str) -> Self {
+        self.manifest = Some(manifest.into());
+        self
+    }
+
+    /// Writes an additional file, relative to the fixture's package root.
+    pub fn file(mut self, path: &str, contents: &str) -> Self {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// Runs `setup` in the package root once the fixture's files are written, before compiling.
+    pub fn setup(mut self, setup: impl FnOnce(&Path) + 'static) -> Self {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Compiles the fixture, and the proc macro without the `nightly` feature, with the stable
+    /// toolchain.
+    pub fn stable(mut self) -> Self {
+        self.stable = true;
+        self.nightly_feature = false;
+        self
+    }
+
+    /// Compiles the fixture with the stable toolchain, and the proc macro with the `nightly`
+    /// feature, so the build script falls back to the stable diagnostics as it would if the
+    /// unstable APIs changed.
+    pub fn fallback(mut self) -> Self {
+        self.stable = true;
+        self.nightly_feature = true;
+        self
+    }
+
+    pub fn compile(self) -> Outcome {
+        let dir = env::temp_dir().join(format!(
+            "allow-until-fixture-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let lib = dir.join("lib.rs");
+        fs::write(&lib, &self.source).unwrap();
+        if let Some(manifest) = &self.manifest {
+            fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+        }
+        for (path, contents) in &self.files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        if let Some(setup) = self.setup {
+            setup(&dir);
+        }
+
+        let mut cmd = if self.stable {
+            let mut cmd = Command::new("rustup");
+            cmd.args(["run", "stable", "rustc"]);
+            cmd
+        } else {
+            Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+        };
+        cmd.current_dir(&dir)
+            .args([
+                "--edition",
+                "2021",
+                "--crate-type",
+                "lib",
+                "--crate-name",
+                "fixture",
+            ])
+            .args(["--emit", "metadata", "--out-dir"])
+            .arg(&dir)
+            .arg("--extern")
+            .arg(format!(
+                "allow_until={}",
+                proc_macro_path(self.stable, self.nightly_feature).display()
+            ))
+            .args(self.examples.iter().flat_map(|package| {
+                let path = example_path(package);
+                [
+                    "--extern".into(),
+                    format!("{}={}", package.replace('-', "_"), path.display()),
+                ]
+            }))
+            .args(&self.args)
+            .arg(&lib)
+            .env(
+                "CARGO_MANIFEST_DIR",
+                self.member
+                    .as_ref()
+                    .map_or(dir.clone(), |member| dir.join(member)),
+            )
+            .env("CARGO_CRATE_NAME", "fixture");
+
+        // don't let the environment of this test run leak into the fixture
+        for (key, _) in env::vars() {
+            if key.starts_with("ALLOW_UNTIL_") || key.starts_with("CARGO_PKG_") {
+                cmd.env_remove(key);
+            }
+        }
+        cmd.env("CARGO_PKG_NAME", "fixture")
+            .env("CARGO_PKG_VERSION", "0.1.0");
+        for (key, value) in &self.envs {
+            cmd.env(key, value);
+        }
+
+        let output = cmd.output().expect("failed to run rustc");
+
+        Outcome {
+            success: output.status.success(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            dir,
+        }
+    }
+}
+
+impl Outcome {
+    /// The stderr with the fixture's directory replaced by `$DIR`, for comparing whole diagnostics.
+    pub fn normalized_stderr(&self) -> String {
+        self.stderr.replace(&self.dir.display().to_string(), "$DIR")
+    }
+
+    #[track_caller]
+    pub fn assert_contains(&self, needle: &str) -> &Self {
+        assert!(
+            self.stderr.contains(needle),
+            "expected stderr to contain {needle:?}, got:\n{}",
+            self.stderr
+        );
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_not_contains(&self, needle: &str) -> &Self {
+        assert!(
+            !self.stderr.contains(needle),
+            "expected stderr not to contain {needle:?}, got:\n{}",
+            self.stderr
+        );
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_success(&self) -> &Self {
+        assert!(
+            self.success,
+            "expected fixture to compile, got:\n{}",
+            self.stderr
+        );
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_failure(&self) -> &Self {
+        assert!(
+            !self.success,
+            "expected fixture to fail, got:\n{}",
+            self.stderr
+        );
+        self
+    }
+}
+
+/// Builds the proc macro into a target directory of its own, once per test binary, as the one
+/// built for the tests may not have the features they expect.
+pub fn proc_macro_path(stable: bool, nightly_feature: bool) -> PathBuf {
+    static NIGHTLY: OnceLock<PathBuf> = OnceLock::new();
+    static STABLE: OnceLock<PathBuf> = OnceLock::new();
+    static FALLBACK: OnceLock<PathBuf> = OnceLock::new();
+
+    let (cell, name) = match (stable, nightly_feature) {
+        (true, true) => (&FALLBACK, "fallback"),
+        (true, false) => (&STABLE, "stable"),
+        (false, _) => (&NIGHTLY, "nightly"),
+    };
+
+    cell.get_or_init(|| {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let target = root.join("target").join("fixtures").join(name);
+
+        let mut cmd = if stable {
+            let mut cmd = Command::new("rustup");
+            cmd.args(["run", "stable", "cargo"]);
+            cmd
+        } else {
+            Command::new(env!("CARGO"))
+        };
+        cmd.args(["build", "--quiet", "--lib", "--manifest-path"])
+            .arg(root.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target);
+
+        // `publish-check` does nothing without `ALLOW_UNTIL_INDEX_CHECK=1`, so it is always tested,
+        // and `__test-panic` nothing without `ALLOW_UNTIL_TEST_PANIC`
+        let mut features = vec!["publish-check", "__test-panic"];
+        if nightly_feature {
+            features.push("nightly");
+        }
+        if cfg!(feature = "link-section") {
+            features.push("link-section");
+        }
+        cmd.args(["--features", &features.join(",")]);
+
+        let status = cmd.status().expect("failed to build the proc macro");
+        assert!(status.success(), "failed to build the {} proc macro", name);
+
+        target.join("debug").join(format!(
+            "{}allow_until{}",
+            env::consts::DLL_PREFIX,
+            env::consts::DLL_SUFFIX
+        ))
+    })
+    .clone()
+}
+
+/// Builds the example proc macro `package` into a target directory of its own, once per test
+/// binary.
+fn example_path(package: &str) -> PathBuf {
+    static BUILT: Mutex<Vec<(String, PathBuf)>> = Mutex::new(Vec::new());
+
+    let mut built = BUILT.lock().unwrap();
+    if let Some((_, path)) = built.iter().find(|(name, _)| name == package) {
+        return path.clone();
+    }
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target = root.join("target").join("fixtures").join("examples");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "build",
+            "--quiet",
+            "--lib",
+            "--package",
+            package,
+            "--manifest-path",
+        ])
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target)
+        .status()
+        .expect("failed to build the example");
+    assert!(status.success(), "failed to build the {} example", package);
+
+    let path = target.join("debug").join(format!(
+        "{}{}{}",
+        env::consts::DLL_PREFIX,
+        package.replace('-', "_"),
+        env::consts::DLL_SUFFIX
+    ));
+    built.push((package.into(), path.clone()));
+    path
+}
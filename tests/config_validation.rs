@@ -0,0 +1,96 @@
+//! Malformed `[package.metadata.allow-until]` tables, each problem of which is warned about with
+//! where it is written.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=3.0")]
+    pub struct Legacy;
+"#;
+
+fn validate(manifest: &str) -> common::Outcome {
+    Fixture::new(SOURCE).manifest(manifest).compile()
+}
+
+#[test]
+fn misspelled_key() {
+    validate("[package.metadata.allow-until]\nseverity = \"warning\"\nrequire = [\"reason\"]\n")
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0008] unknown key `require` in `[package.metadata.allow-until]`",
+        )
+        .assert_contains("Cargo.toml:3:1")
+        .assert_contains("did you mean `required`?");
+}
+
+#[test]
+fn misspelled_required_argument() {
+    validate("[package.metadata.allow-until]\nrequired = [\"reasn\"]\n")
+        .assert_success()
+        .assert_contains("warning: [AU0008] ignoring `\"reasn\"` in `required`, expected one of")
+        .assert_contains("Cargo.toml:2:13")
+        .assert_contains("did you mean `reason`?");
+}
+
+#[test]
+fn unknown_table() {
+    validate(
+        "[package.metadata.allow-until]\n\n[package.metadata.allow-until.reason]\nxml = \"x\"\n",
+    )
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0008] unknown table `[package.metadata.allow-until.reason]`, expected one \
+         of `reasons`, `migrations`, `contexts`",
+    )
+    .assert_contains("Cargo.toml:3:31")
+    .assert_contains("did you mean `reasons`?");
+}
+
+#[test]
+fn wrong_types() {
+    validate(
+        "[package.metadata.allow-until]\nalways_note = \"yes\"\nreason_min_words = -1\n\
+         approvers = [\"ana\", 7]\n",
+    )
+    .assert_success()
+    .assert_contains(
+        "`always_note` in `[package.metadata.allow-until]` must be `true` or `false`, found a \
+         string",
+    )
+    .assert_contains("Cargo.toml:2:1")
+    .assert_contains(
+        "`reason_min_words` in `[package.metadata.allow-until]` must be a positive integer, found \
+         an integer",
+    )
+    .assert_contains("Cargo.toml:3:1")
+    .assert_contains(
+        "`approvers` in `[package.metadata.allow-until]` must be an array of names, found an \
+         integer",
+    )
+    .assert_contains("Cargo.toml:4:21");
+}
+
+#[test]
+fn invalid_values() {
+    validate(
+        "[package.metadata.allow-until]\ngrace = \"3 weeks\"\ndefault_version = \"soon\"\n\n\
+         [package.metadata.allow-until.migrations.json]\nversion = \"2\"\nreson = \"x\"\n",
+    )
+    .assert_success()
+    .assert_contains(
+        "`grace` in `[package.metadata.allow-until]`: invalid distance `3 weeks`, expected e.g.",
+    )
+    .assert_contains("Cargo.toml:2:1")
+    .assert_contains(
+        "`default_version` in `[package.metadata.allow-until]`: invalid semver requirement `soon`",
+    )
+    .assert_contains("Cargo.toml:3:1")
+    .assert_contains(
+        "ignoring migration `json` in `[package.metadata.allow-until.migrations]`: unknown key \
+         `reson`",
+    )
+    .assert_contains("Cargo.toml:7:1")
+    .assert_contains("did you mean `reason`?");
+}
@@ -0,0 +1,134 @@
+//! The same arguments given to each form of annotation: the attribute, a field of the derive, the
+//! function-like group and the attribute on the members of a block. Each must reach the same
+//! outcome with the same diagnostics, as the forms share the parsing, validation, defaults and
+//! policy checks of the arguments.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package.metadata.allow-until]
+required = ["owner"]
+frobnicate = true
+
+[package.metadata.allow-until.migrations.v2]
+version = ">=1.0"
+owner = "core"
+"#;
+
+/// Arguments with their diagnostics, each of which the forms must agree on.
+const CASES: &[&str] = &[
+    r#"version = "two""#,
+    r#"version = ">=9.0", owner = "core", reson = "typo""#,
+    r#"reason = "no requirement", owner = "core""#,
+    r#"version = ">=9.0", owner = "core", doctest_fence = "no_run""#,
+    r#"migration = "v3""#,
+    r#"version = ">=9.0", reason = "missing the required owner""#,
+    r#"version = ">=1.0", owner = "core", display_name = "the legacy loader""#,
+    r#"migration = "v2", display_name = "the v2 loader""#,
+];
+
+fn attribute() -> String {
+    CASES
+        .iter()
+        .enumerate()
+        .map(|(i, args)| {
+            format!(
+                "#[allow_until::allow_until({})]\npub struct Item{};\n",
+                args, i
+            )
+        })
+        .collect()
+}
+
+fn derive() -> String {
+    let fields: String = CASES
+        .iter()
+        .enumerate()
+        .map(|(i, args)| format!("    #[allow_until({})]\n    pub field{}: u8,\n", args, i))
+        .collect();
+    format!(
+        "#[derive(allow_until::AllowUntil)]\npub struct Options {{\n{}}}\n",
+        fields
+    )
+}
+
+fn group() -> String {
+    CASES
+        .iter()
+        .enumerate()
+        .map(|(i, args)| {
+            format!(
+                "allow_until::allow_until_group! {{ {}, {{ pub struct Item{}; }} }}\n",
+                args, i
+            )
+        })
+        .collect()
+}
+
+fn members() -> String {
+    CASES
+        .iter()
+        .enumerate()
+        .map(|(i, args)| {
+            format!(
+                "pub struct Item{i};\n\
+                 #[allow_until::allow_members_until({args}, members = [\"member{i}\"])]\n\
+                 impl Item{i} {{\n    pub fn member{i}() {{}}\n}}\n",
+                i = i,
+                args = args
+            )
+        })
+        .collect()
+}
+
+/// The headlines of the diagnostics about the annotations, sorted, as the forms emit them in
+/// different orders on stable.
+fn headlines(source: &str) -> Vec<String> {
+    let outcome = Fixture::new(source)
+        .manifest(MANIFEST)
+        .version("1.0.0")
+        .compile();
+    outcome.assert_failure();
+
+    let mut headlines: Vec<String> = outcome
+        .stderr
+        .lines()
+        .filter(|line| line.starts_with("error: [AU") || line.starts_with("warning: [AU"))
+        .map(String::from)
+        .collect();
+    headlines.sort();
+    headlines
+}
+
+#[test]
+fn every_form_agrees() {
+    let expected = headlines(&attribute());
+    for headline in [
+        "warning: [AU0008] unknown key `frobnicate` in `[package.metadata.allow-until]`",
+        "error: [AU0002] invalid semver requirement `two`",
+        "error: [AU0005] unknown argument `reson`",
+        "error: [AU0006] missing required `version` argument",
+        "error: [AU0005] `doctest_fence` without `doctest_ignore_from`",
+        "error: [AU0005] unknown migration `v3`",
+        "error: [AU0007] annotation is missing the required argument `owner`",
+        "error: [AU0001] the legacy loader not allowed! (version 1.0.0 matches >=1.0)",
+        "error: [AU0001] the v2 loader not allowed! (version 1.0.0 matches >=1.0)",
+    ] {
+        assert!(
+            expected.iter().any(|line| line.starts_with(headline)),
+            "no `{}` in {:#?}",
+            headline,
+            expected
+        );
+    }
+
+    for (form, source) in [
+        ("derive", derive()),
+        ("group", group()),
+        ("members", members()),
+    ] {
+        assert_eq!(headlines(&source), expected, "the {} form disagrees", form);
+    }
+}
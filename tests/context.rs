@@ -0,0 +1,162 @@
+mod common;
+
+use common::Fixture;
+
+const EXPIRED: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+"#;
+
+const RUST_ANALYZER: &str = "RUST_ANALYZER_INTERNALS_DO_NOT_USE";
+
+#[test]
+fn rust_analyzer_softens_errors() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env(RUST_ANALYZER, "this is unstable")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn rust_analyzer_error_opt_in() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env(RUST_ANALYZER, "this is unstable")
+        .env("ALLOW_UNTIL_IDE", "error")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn clippy_softens_errors() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env(
+            "CLIPPY_ARGS",
+            "-W__CLIPPY_HACKERY__clippy::pedantic__CLIPPY_HACKERY__",
+        )
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env(
+            "RUSTC_WORKSPACE_WRAPPER",
+            "/home/dev/.cargo/bin/clippy-driver",
+        )
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn clippy_error_opt_in() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("CLIPPY_ARGS", "")
+        .env("ALLOW_UNTIL_CLIPPY", "error")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn rustc_keeps_errors() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_IDE", "warn")
+        .env("ALLOW_UNTIL_CLIPPY", "warn")
+        .env("RUSTC_WORKSPACE_WRAPPER", "/usr/bin/sccache")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+const CONTEXTS: &str = r#"
+[package]
+name = "fixture"
+version = "2.0.0"
+
+[package.metadata.allow-until.contexts]
+docsrs = "ignore"
+rust-analyzer = "error"
+ci = "error"
+local = "warn"
+"#;
+
+#[test]
+fn contexts_table() {
+    Fixture::new(EXPIRED)
+        .manifest(CONTEXTS)
+        .version("2.0.0")
+        .env("CI", "false")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(EXPIRED)
+        .manifest(CONTEXTS)
+        .version("2.0.0")
+        .env("CI", "true")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(EXPIRED)
+        .manifest(CONTEXTS)
+        .version("2.0.0")
+        .env(RUST_ANALYZER, "this is unstable")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    Fixture::new(EXPIRED)
+        .manifest(CONTEXTS)
+        .version("2.0.0")
+        .env("DOCS_RS", "1")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn annotation_overrides_contexts_table() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", docsrs = "error")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(CONTEXTS)
+    .version("2.0.0")
+    .env("DOCS_RS", "1")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn unknown_context() {
+    Fixture::new(EXPIRED)
+        .manifest(
+            r#"
+            [package]
+            name = "fixture"
+            version = "2.0.0"
+
+            [package.metadata.allow-until.contexts]
+            ide = "warn"
+            "#,
+        )
+        .version("2.0.0")
+        .env("CI", "false")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "ignoring unknown context `ide` in `[package.metadata.allow-until.contexts]`",
+        );
+}
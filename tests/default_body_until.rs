@@ -0,0 +1,127 @@
+//! `#[default_body_until]`, which errors once a trait method that becomes required still has a
+//! default body.
+
+mod common;
+
+use common::Fixture;
+
+const WITH_BODY: &str = r#"
+pub trait Transport {
+    fn send(&mut self, bytes: &[u8]);
+
+    #[allow_until::default_body_until(version = ">=2.0", reason = "implementors must flush")]
+    fn flush(&mut self) where Self: Sized {}
+}
+"#;
+
+const WITHOUT_BODY: &str = r#"
+pub trait Transport {
+    fn send(&mut self, bytes: &[u8]);
+
+    #[allow_until::default_body_until(version = ">=2.0")]
+    fn flush(&mut self) where Self: Sized;
+}
+"#;
+
+#[test]
+fn before() {
+    for source in [WITH_BODY, WITHOUT_BODY] {
+        Fixture::new(source)
+            .version("1.9.0")
+            .compile()
+            .assert_success()
+            .assert_not_contains("AU0043");
+    }
+}
+
+#[test]
+fn default_body_left() {
+    Fixture::new(WITH_BODY)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0043] fn `flush` must not have a default body! (version 2.0.0 matches \
+             >=2.0)",
+        )
+        .assert_contains("implementors must flush")
+        .assert_contains("lib.rs:5:5")
+        .assert_contains(
+            "help: delete the body, leaving `fn flush(...);`, so implementors have to supply it",
+        );
+}
+
+#[test]
+fn default_body_removed() {
+    Fixture::new(WITHOUT_BODY)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0043] fn `flush` no longer has a default body, as required from `>=2.0`",
+        );
+}
+
+#[test]
+fn async_methods() {
+    let source = |body: &str| {
+        format!(
+            r#"
+            pub trait Transport {{
+                #[allow_until::default_body_until(version = ">=2.0")]
+                async fn flush(&mut self) -> Result<(), ()>{}
+            }}
+            "#,
+            body
+        )
+    };
+
+    Fixture::new(&source(" { Ok(()) }"))
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0043] fn `flush` must not have a default body!");
+
+    Fixture::new(&source(";"))
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("error");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::default_body_until(version = ">=2.0")]
+        pub struct Transport;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `default_body_until` can only be applied to trait methods");
+
+    Fixture::new(
+        r#"
+        pub trait Transport {
+            #[allow_until::default_body_until(reason = "implementors must flush")]
+            fn flush(&mut self) {}
+        }
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0006] missing required `version` argument");
+
+    Fixture::new(
+        r#"
+        pub trait Transport {
+            #[allow_until::default_body_until(version = ">=2.0", severity = "warning")]
+            fn flush(&mut self) {}
+        }
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("unknown argument `severity`");
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::Fixture;
+
+const DEFAULT: &str = "[package.metadata.allow-until]\ndefault_version = \">=2.0.0\"\n";
+
+#[test]
+fn used_without_version() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(reason = "use `Json`")]
+        pub struct Xml;
+
+        #[allow_until::allow_until]
+        pub struct Yaml;
+
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .manifest(DEFAULT)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] use `Json` (version 2.0.0 matches >=2.0.0)")
+    .assert_contains("struct `Yaml` not allowed!")
+    .assert_contains("field `Options::legacy` not allowed!")
+    .assert_contains("= note: using crate default requirement >=2.0.0");
+
+    Fixture::new("#[allow_until::allow_until(reason = \"use `Json`\")]\npub struct Xml;\n")
+        .manifest(DEFAULT)
+        .version("1.9.0")
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn precedence() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub struct Explicit;
+
+        #[allow_until::allow_until(migration = "drop-xml")]
+        pub struct Migrated;
+
+        #[allow_until::allow_until(version = ">=1.0")]
+        pub struct Expired;
+        "#,
+    )
+    .manifest(&format!(
+        "{}\n[package.metadata.allow-until.migrations.drop-xml]\nversion = \">=4.0\"\n",
+        DEFAULT
+    ))
+    .version("2.0.0")
+    .compile();
+
+    outcome
+        .assert_failure()
+        .assert_not_contains("`Explicit`")
+        .assert_not_contains("`Migrated`")
+        .assert_contains("struct `Expired` not allowed!")
+        .assert_not_contains("using crate default requirement");
+}
+
+#[test]
+fn missing_without_default() {
+    Fixture::new("#[allow_until::allow_until(reason = \"use `Json`\")]\npub struct Xml;\n")
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0006] missing required `version` argument");
+}
+
+#[test]
+fn invalid_default() {
+    Fixture::new("#[allow_until::allow_until(reason = \"use `Json`\")]\npub struct Xml;\n")
+        .manifest("[package.metadata.allow-until]\ndefault_version = \"next-major\"\n")
+        .version("1.4.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "`default_version = \"next-major\"` would move along with the version and never be \
+             reached, write the requirement instead, e.g. `default_version = \">=2.0.0\"`",
+        )
+        .assert_contains("error: [AU0006] missing required `version` argument");
+
+    Fixture::new("#[allow_until::allow_until(reason = \"use `Json`\")]\npub struct Xml;\n")
+        .manifest("[package.metadata.allow-until]\ndefault_version = \">=2.0 <3\"\n")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "`default_version` in `[package.metadata.allow-until]`: invalid semver requirement \
+             `>=2.0 <3`",
+        );
+}
@@ -0,0 +1,117 @@
+//! `display_name`, which names the item in diagnostics, reports and schedules instead of its
+//! generated identifier.
+
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", display_name = "XML config loader")]
+pub struct __acme_generated_cfg_v2;
+"#;
+
+#[test]
+fn attribute() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("[AU0001] XML config loader not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_contains("note: the item is struct `__acme_generated_cfg_v2`")
+        .assert_not_contains("struct `__acme_generated_cfg_v2` not allowed!");
+}
+
+#[test]
+fn reports_and_schedules() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-display-name-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    let outcome = Fixture::new(SOURCE)
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .env("ALLOW_UNTIL_SCHEDULE", "SCHEDULE.md")
+        .version("1.0.0")
+        .compile();
+    outcome.assert_success();
+
+    let entry: Value =
+        serde_json::from_str(std::fs::read_to_string(&report).unwrap().trim()).unwrap();
+    assert_eq!(entry["item"], "XML config loader");
+    assert_eq!(entry["kind"], "struct");
+
+    let schedule = std::fs::read_to_string(outcome.dir.join("SCHEDULE.md")).unwrap();
+    assert!(
+        schedule.contains("| XML config loader | `>=2.0` |"),
+        "{}",
+        schedule
+    );
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn derive() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=2.0", display_name = "legacy XML flag")]
+            pub __acme_xml: bool,
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("[AU0001] legacy XML flag not allowed!")
+    .assert_contains("note: the item is field `Options::__acme_xml`");
+}
+
+#[test]
+fn blanket_attributes() {
+    Fixture::new(
+        r#"
+        allow_until::allow_until_group! {
+            version = ">=2.0", display_name = "sync API",
+            {
+                pub fn __acme_sync_connect() {}
+            }
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("[AU0001] sync API not allowed!")
+    .assert_contains("note: the item is fn `__acme_sync_connect`");
+
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_members_until(
+            version = ">=2.0",
+            members = ["__acme_connect_v1"],
+            display_name = "v1 handshake",
+        )]
+        impl Client {
+            pub fn __acme_connect_v1(&self) {}
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("[AU0001] v1 handshake not allowed!")
+    .assert_contains("note: the item is fn `__acme_connect_v1`");
+}
+
+#[test]
+fn empty() {
+    Fixture::new(&SOURCE.replace("XML config loader", " "))
+        .compile()
+        .assert_failure()
+        .assert_contains("`display_name` can't be empty");
+}
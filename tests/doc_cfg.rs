@@ -0,0 +1,185 @@
+//! `feature`, `cfg` and `doc_cfg`, which give annotated items the `doc(cfg)` of the configuration
+//! they exist under.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package]
+name = "fixture"
+version = "1.0.0"
+
+[features]
+legacy = []
+"#;
+
+const DOCSRS: &str = r#"
+[package]
+name = "fixture"
+version = "1.0.0"
+
+[features]
+legacy = []
+
+[package.metadata.docs.rs]
+rustdoc-args = ["--cfg", "docsrs"]
+"#;
+
+/// The `doc(cfg)` attributes of the fixture expanded with `--cfg docsrs`, one per line.
+fn doc_cfgs(source: &str, manifest: &str) -> String {
+    let outcome = Fixture::new(&format!(
+        "#![cfg_attr(docsrs, feature(doc_cfg))]\n{}",
+        source
+    ))
+    .manifest(manifest)
+    .rustc_arg("-Zunpretty=expanded")
+    .rustc_arg("--cfg")
+    .rustc_arg("docsrs")
+    .compile();
+    outcome.assert_success();
+    outcome
+        .stdout
+        .lines()
+        .filter(|line| line.contains("doc(cfg"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn feature() {
+    assert_eq!(
+        doc_cfgs(
+            r#"
+            #[allow_until::allow_until(version = ">=2.0", feature = "legacy", doc_cfg = true)]
+            pub struct Legacy;
+            "#,
+            MANIFEST,
+        ),
+        "#[doc(cfg(feature = \"legacy\"))]"
+    );
+}
+
+#[test]
+fn raw_cfg() {
+    assert_eq!(
+        doc_cfgs(
+            r#"
+            #[allow_until::allow_until(
+                version = ">=2.0",
+                cfg = "all(unix, not(target_os = \"macos\"))",
+                doc_cfg = true,
+            )]
+            pub fn legacy() {}
+            "#,
+            MANIFEST,
+        ),
+        "#[doc(cfg(all(unix, not(target_os = \"macos\"))))]"
+    );
+    assert_eq!(
+        doc_cfgs(
+            r#"
+            #[allow_until::allow_until(
+                version = ">=2.0",
+                feature = "legacy",
+                cfg = "unix",
+                doc_cfg = true,
+            )]
+            pub fn legacy() {}
+            "#,
+            MANIFEST,
+        ),
+        "#[doc(cfg(all(feature = \"legacy\", unix)))]"
+    );
+}
+
+#[test]
+fn no_duplicate() {
+    assert_eq!(
+        doc_cfgs(
+            r#"
+            #[allow_until::allow_until(version = ">=2.0", feature = "legacy", doc_cfg = true)]
+            #[cfg_attr(docsrs, doc(cfg(feature = "legacy")))]
+            pub struct Legacy;
+            "#,
+            MANIFEST,
+        ),
+        "#[doc(cfg(feature = \"legacy\"))]"
+    );
+}
+
+#[test]
+fn from_docsrs_convention() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0", feature = "legacy")]
+        pub struct Legacy;
+    "#;
+
+    assert_eq!(doc_cfgs(source, MANIFEST), "");
+    assert_eq!(
+        doc_cfgs(source, DOCSRS),
+        "#[doc(cfg(feature = \"legacy\"))]"
+    );
+    assert_eq!(
+        doc_cfgs(
+            &source.replace("\"legacy\"", "\"legacy\", doc_cfg = false"),
+            DOCSRS
+        ),
+        ""
+    );
+}
+
+#[test]
+fn from_configuration() {
+    let manifest = format!(
+        "{}\n[package.metadata.allow-until]\ndoc_cfg = true\n",
+        MANIFEST
+    );
+    assert_eq!(
+        doc_cfgs(
+            r#"
+            #[allow_until::allow_until(version = ">=2.0", feature = "legacy")]
+            pub struct Legacy;
+            #[allow_until::allow_until(version = ">=2.0")]
+            pub struct Everywhere;
+            "#,
+            &manifest,
+        ),
+        "#[doc(cfg(feature = \"legacy\"))]"
+    );
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", doc_cfg = true)]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0007] `doc_cfg` is given without `feature` or `cfg`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", feature = "legacyy")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .compile()
+    .assert_failure()
+    .assert_contains("the package has no feature `legacyy`")
+    .assert_contains("did you mean `legacy`?");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", cfg = "unix,")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] invalid `cfg` predicate `unix,`");
+}
@@ -0,0 +1,146 @@
+//! `doctest_ignore_from`, which marks the examples in the documentation of an item `ignore` (or
+//! `no_run`) once it is deprecated, until the error of the requirement takes over.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+/// Loads the index.
+///
+/// ```
+/// let index = fixture::load();
+/// ```
+///
+/// ```text
+/// not an example
+/// ```
+#[allow_until::allow_until(version = ">=1.0", doctest_ignore_from = ">=0.9")]
+/** ```rust,should_panic
+fixture::load_from("missing");
+``` */
+pub fn load() {}
+"#;
+
+/// The `#[doc]` attributes of the expanded fixture, one per line.
+fn docs(source: &str, version: &str) -> String {
+    let outcome = Fixture::new(source)
+        .version(version)
+        .rustc_arg("-Zunpretty=expanded")
+        .compile();
+    outcome.assert_success();
+    outcome
+        .stdout
+        .replace("#[doc =\n", "#[doc = ")
+        .lines()
+        .filter(|line| line.starts_with("#[doc"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn examples_are_marked() {
+    assert_eq!(
+        docs(SOURCE, "0.9.0"),
+        r#"#[doc = " Loads the index."]
+#[doc = ""]
+#[doc = " ```ignore"]
+#[doc = " let index = fixture::load();"]
+#[doc = " ```"]
+#[doc = ""]
+#[doc = " ```text"]
+#[doc = " not an example"]
+#[doc = " ```"]
+#[doc = " ```rust,should_panic,ignore\nfixture::load_from(\"missing\");\n``` "]"#
+    );
+
+    // before `doctest_ignore_from`, the examples are left as they are
+    let unmarked = docs(SOURCE, "0.8.0");
+    assert!(unmarked.contains(r#"#[doc = " ```"]"#), "{}", unmarked);
+    assert!(!unmarked.contains("ignore"), "{}", unmarked);
+}
+
+#[test]
+fn no_run() {
+    let source = SOURCE.replace(
+        "doctest_ignore_from = \">=0.9\"",
+        "doctest_ignore_from = \">=0.9\", doctest_fence = \"no_run\"",
+    );
+    let docs = docs(&source, "0.9.0");
+    assert!(docs.contains(r#"#[doc = " ```no_run"]"#), "{}", docs);
+    assert!(
+        docs.contains(r#"" ```rust,should_panic,no_run\n"#),
+        "{}",
+        docs
+    );
+}
+
+#[test]
+fn marked_already() {
+    let source = r#"
+        /// ```ignore
+        /// fixture::load();
+        /// ```
+        ///
+        /// ```compile_fail,E0308
+        /// let n: u8 = "1";
+        /// ```
+        #[allow_until::allow_until(version = ">=1.0", doctest_ignore_from = ">=0.9")]
+        pub fn load() {}
+    "#;
+    assert_eq!(
+        docs(source, "0.9.0"),
+        r#"#[doc = " ```ignore"]
+#[doc = " fixture::load();"]
+#[doc = " ```"]
+#[doc = ""]
+#[doc = " ```compile_fail,E0308,ignore"]
+#[doc = " let n: u8 = \"1\";"]
+#[doc = " ```"]"#
+    );
+}
+
+#[test]
+fn error_takes_over() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("[AU0001] fn `load` not allowed! (version 1.0.0 matches >=1.0)");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", doctest_fence = "no_run")]
+        pub fn load() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("`doctest_fence` without `doctest_ignore_from`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", doctest_ignore_from = ">=1.2")]
+        pub fn load() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "`doctest_ignore_from` first matches 1.2.0, no earlier than 1.0.0, the first version \
+         `>=1.0` matches",
+    );
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", doctest_ignore_from = ">=0.9", doctest_fence = "skip")]
+        pub fn load() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid `doctest_fence` value `skip`");
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn duplicate() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "old-config")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0", id = "old-tls-shim")]
+        pub struct TlsShim;
+
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=3.0", id = "old-config")]
+            pub xml: bool,
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0029] the id `old-config` is already used by another annotation",
+        )
+        .assert_not_contains("`old-tls-shim` is already used");
+    assert!(outcome
+        .normalized_stderr()
+        .contains("= note: first used at $DIR/lib.rs:2:9"));
+    assert_eq!(outcome.stderr.matches("[AU0029]").count(), 1);
+}
+
+#[test]
+fn denied() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "old-config")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0", id = "old-config")]
+        pub struct YamlConfig;
+        "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_DENY", "1")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0029] the id `old-config` is already used");
+}
+
+const SHARED: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", id = "old-config")]
+    pub struct XmlConfig;
+"#;
+
+/// A file included by two modules expands the same annotation twice, as lib and test targets do
+/// in separate compilations.
+#[test]
+fn expanded_more_than_once() {
+    for test in [false, true] {
+        let mut fixture = Fixture::new(
+            "#[path = \"shared.rs\"]\nmod first;\n#[path = \"shared.rs\"]\nmod second;\n",
+        )
+        .file("shared.rs", SHARED)
+        .version("1.0.0");
+        if test {
+            fixture = fixture.rustc_arg("--test");
+        }
+
+        fixture
+            .compile()
+            .assert_success()
+            .assert_not_contains("[AU0029]");
+    }
+}
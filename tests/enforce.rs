@@ -0,0 +1,164 @@
+//! `enforce = "tagged-builds"`, which only reports expiry as an error in builds of release tags.
+
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::Fixture;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A repository with a single commit, tagged `tag` unless it is `None`.
+fn committed(tag: Option<&'static str>) -> impl FnOnce(&Path) {
+    move |dir| {
+        git(dir, &["init", "-q"]);
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-qm", "init"]);
+        if let Some(tag) = tag {
+            git(dir, &["tag", tag]);
+        }
+    }
+}
+
+const TAGGED_BUILDS: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", enforce = "tagged-builds")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn release_tag() {
+    Fixture::new(TAGGED_BUILDS)
+        .version("2.0.0")
+        .setup(committed(Some("v2.0.0")))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "this is an error as `enforce = \"tagged-builds\"` is set and the commit is tagged \
+             `v2.0.0`, a release tag matching `v*`",
+        );
+}
+
+#[test]
+fn untagged() {
+    Fixture::new(TAGGED_BUILDS)
+        .version("2.0.0")
+        .setup(committed(Some("nightly-2")))
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "this is a warning as `enforce = \"tagged-builds\"` is set and the commit has no tag \
+             matching `v*`, it is an error in the builds of release tags",
+        );
+}
+
+#[test]
+fn unknown_tags() {
+    Fixture::new(TAGGED_BUILDS)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "this is a warning as `enforce = \"tagged-builds\"` is set and the release tags of \
+             the commit are unknown",
+        );
+
+    Fixture::new(TAGGED_BUILDS)
+        .version("2.0.0")
+        .setup(committed(Some("v2.0.0")))
+        .env("ALLOW_UNTIL_OFFLINE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "the release tags of the commit are unknown: `git tag`, for `enforce`, is not \
+             consulted, as `ALLOW_UNTIL_OFFLINE=1` asks for a hermetic build",
+        );
+}
+
+#[test]
+fn always() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", enforce = "always")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("2.0.0")
+    .setup(committed(None))
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+    .assert_not_contains("tagged-builds");
+}
+
+#[test]
+fn configuration() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "error")]
+        pub struct Strict;
+    "#;
+    let manifest =
+        "[package.metadata.allow-until]\nenforce = \"tagged-builds\"\nrelease_tags = \"release-*\"\n";
+
+    let outcome = Fixture::new(source)
+        .manifest(manifest)
+        .version("2.0.0")
+        .setup(committed(Some("v2.0.0")))
+        .compile();
+    outcome
+        .assert_failure()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains("the commit has no tag matching `release-*`")
+        .assert_contains("error: [AU0001] struct `Strict` not allowed!");
+
+    Fixture::new(source)
+        .manifest(manifest)
+        .version("2.0.0")
+        .setup(committed(Some("release-2.0")))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains("the commit is tagged `release-2.0`, a release tag matching `release-*`");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", enforce = "sometimes")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005]");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "error", enforce = "tagged-builds")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `enforce` can't be combined with `severity`")
+    .assert_contains(
+        "help: `enforce` decides the severity from the release tags of the commit, remove \
+         `severity` or `enforce`",
+    );
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::Fixture;
+
+const EXPIRED: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Other;
+"#;
+
+#[test]
+fn downgrade_unset() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("ALLOW_UNTIL_DOWNGRADE");
+}
+
+#[test]
+fn downgrade_warn() {
+    let outcome = Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "warn")
+        .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains("warning: [AU0001] struct `Other` not allowed!");
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("`ALLOW_UNTIL_DOWNGRADE=warn` is set")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn downgrade_allow() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "allow")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "`ALLOW_UNTIL_DOWNGRADE=allow` is set, expired annotations are not reported",
+        )
+        .assert_not_contains("not allowed!");
+}
+
+#[test]
+fn downgrade_invalid() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "yes")
+        .compile()
+        .assert_failure()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_DOWNGRADE` value `yes`")
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn disabled_cfg() {
+    for flags in [
+        &["--cfg", "allow_until_disabled"][..],
+        &["--cfg=allow_until_disabled"],
+    ] {
+        let outcome = flags
+            .iter()
+            .fold(Fixture::new(EXPIRED), |fixture, flag| {
+                fixture.rustc_arg(flag)
+            })
+            .version("2.0.0")
+            .compile();
+
+        outcome
+            .assert_success()
+            .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+            .assert_contains("warning: [AU0001] struct `Other` not allowed!");
+        assert_eq!(
+            outcome
+                .stderr
+                .matches(
+                    "`--cfg allow_until_disabled` is set, expired annotations are only reported \
+                     as warnings"
+                )
+                .count(),
+            1
+        );
+    }
+}
+
+#[test]
+fn disabled_cfg_from_an_argument_file() {
+    Fixture::new(EXPIRED)
+        .file("args", "--cfg\nallow_until_disabled\n")
+        .rustc_arg("@args")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("`--cfg allow_until_disabled` is set");
+}
+
+#[test]
+fn downgrade_variable_wins_over_disabled_cfg() {
+    Fixture::new(EXPIRED)
+        .rustc_arg("--cfg=allow_until_disabled")
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "allow")
+        .compile()
+        .assert_success()
+        .assert_contains("`ALLOW_UNTIL_DOWNGRADE=allow` is set")
+        .assert_not_contains("`--cfg allow_until_disabled` is set")
+        .assert_not_contains("not allowed!");
+
+    // an invalid value is ignored, leaving the cfg
+    Fixture::new(EXPIRED)
+        .rustc_arg("--cfg=allow_until_disabled")
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DOWNGRADE", "yes")
+        .compile()
+        .assert_success()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_DOWNGRADE` value `yes`")
+        .assert_contains("`--cfg allow_until_disabled` is set")
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn disabled_cfg_wins_over_deny() {
+    Fixture::new(EXPIRED)
+        .rustc_arg("--cfg=allow_until_disabled")
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DENY", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn downgrade_keeps_parse_errors() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = "not a version")]
+        pub struct Legacy;
+        "#,
+    )
+    .env("ALLOW_UNTIL_DOWNGRADE", "allow")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0002] invalid semver requirement");
+}
+
+const WARNING: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", severity = "warning", owner = "io-team")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn deny_unset() {
+    Fixture::new(WARNING)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn deny_escalates_warnings() {
+    for value in ["1", "warnings"] {
+        Fixture::new(WARNING)
+            .version("2.0.0")
+            .env("ALLOW_UNTIL_DENY", value)
+            .compile()
+            .assert_failure()
+            .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+            .assert_contains("= note: owner: io-team");
+    }
+}
+
+#[test]
+fn deny_leaves_unexpired_alone() {
+    Fixture::new(WARNING)
+        .version("1.9.0")
+        .env("ALLOW_UNTIL_DENY", "1")
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn downgrade_wins_over_deny() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_DENY", "1")
+        .env("ALLOW_UNTIL_DOWNGRADE", "warn")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!");
+}
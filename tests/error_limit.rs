@@ -0,0 +1,114 @@
+mod common;
+
+use common::Fixture;
+
+/// `count` expired structs, followed by the summary.
+fn expired(count: usize) -> String {
+    let mut source: String = (0..count)
+        .map(|i| {
+            format!(
+                "#[allow_until::allow_until(version = \">=1.0\")]\npub struct Legacy{};\n",
+                i
+            )
+        })
+        .collect();
+    source.push_str("allow_until::allow_until_summary!();\n");
+    source
+}
+
+#[test]
+fn capped() {
+    let outcome = Fixture::new(&expired(13)).version("1.0.0").compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("struct `Legacy9` not allowed!")
+        .assert_not_contains("`Legacy10`")
+        .assert_not_contains("`Legacy12`")
+        .assert_contains(
+            "error: [AU0031] more items expired than the 10 reported; run with \
+             `ALLOW_UNTIL_VERBOSE=1` for the full list",
+        )
+        .assert_contains(
+            "error: [AU0031] …and 3 more items expired; run with `ALLOW_UNTIL_VERBOSE=1` for the \
+             full list",
+        );
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 10);
+    assert_eq!(outcome.stderr.matches("error: [AU0031]").count(), 2);
+}
+
+#[test]
+fn verbose() {
+    let outcome = Fixture::new(&expired(13))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_VERBOSE", "1")
+        .compile();
+
+    outcome.assert_failure().assert_not_contains("[AU0031]");
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 13);
+}
+
+#[test]
+fn configured() {
+    let outcome = Fixture::new(&expired(4))
+        .manifest("[package.metadata.allow-until]\nmax_errors = 1\n")
+        .version("1.0.0")
+        .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("more items expired than the 1 reported")
+        .assert_contains("…and 3 more items expired");
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 1);
+
+    Fixture::new(&expired(1))
+        .manifest("[package.metadata.allow-until]\nmax_errors = 0\n")
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "`max_errors` in `[package.metadata.allow-until]` must be a positive integer",
+        );
+}
+
+#[test]
+fn warnings_are_not_counted() {
+    let mut source = expired(2);
+    source.push_str(
+        &(0..12)
+            .map(|i| {
+                format!(
+                    "#[allow_until::allow_until(version = \">=1.0\", severity = \"warning\")]\n\
+                     pub struct Warned{};\n",
+                    i
+                )
+            })
+            .collect::<String>(),
+    );
+
+    Fixture::new(&source)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("`Warned11` not allowed!")
+        .assert_not_contains("[AU0031]");
+}
+
+#[test]
+fn report_lists_every_annotation() {
+    let report =
+        std::env::temp_dir().join(format!("allow-until-limit-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(&expired(13))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_failure();
+
+    let report_text = std::fs::read_to_string(&report).unwrap();
+    assert_eq!(report_text.lines().count(), 13);
+    assert!(report_text.contains("\"item\":\"Legacy12\""));
+
+    let _ = std::fs::remove_file(&report);
+}
@@ -0,0 +1,113 @@
+//! `escalate_env`, which raises the severity of an annotation by one level while the environment
+//! variable it names is set.
+
+mod common;
+
+use common::Fixture;
+
+const WARNING: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", severity = "warning", escalate_env = "RELEASE_CUT")]
+pub struct Legacy;
+"#;
+
+#[test]
+fn warning_to_error() {
+    Fixture::new(WARNING)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("raised to");
+
+    Fixture::new(WARNING)
+        .version("2.0.0")
+        .env("RELEASE_CUT", "1")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains("raised to an error by `escalate_env`, as `RELEASE_CUT` is set");
+
+    // set, but empty
+    Fixture::new(WARNING)
+        .version("2.0.0")
+        .env("RELEASE_CUT", "")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001]");
+
+    // errors stay errors, and the downgrade still applies
+    Fixture::new(&WARNING.replace("severity = \"warning\", ", ""))
+        .version("2.0.0")
+        .env("RELEASE_CUT", "1")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("raised to");
+    Fixture::new(WARNING)
+        .version("2.0.0")
+        .env("RELEASE_CUT", "1")
+        .env("ALLOW_UNTIL_DOWNGRADE", "warn")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001]");
+}
+
+#[test]
+fn before_expiry() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.1", escalate_env = "RELEASE_CUT")]
+        pub struct Legacy;
+    "#;
+
+    // the `warn_ahead` warning becomes an error
+    let ahead = "[package.metadata.allow-until]\nwarn_ahead = \"1 minor\"\n";
+    Fixture::new(source)
+        .manifest(ahead)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0025] struct `Legacy` expires at version 2.1.0");
+    Fixture::new(source)
+        .manifest(ahead)
+        .version("2.0.0")
+        .env("RELEASE_CUT", "1")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0025] struct `Legacy` expires at version 2.1.0")
+        .assert_contains("raised to an error by `escalate_env`, as `RELEASE_CUT` is set");
+
+    // the `always_note` note becomes a warning
+    Fixture::new(source)
+        .manifest("[package.metadata.allow-until]\nalways_note = true\n")
+        .version("1.0.0")
+        .env("RELEASE_CUT", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0033] struct `Legacy`: active, requirement `>=2.1`")
+        .assert_contains("raised to a warning by `escalate_env`, as `RELEASE_CUT` is set");
+
+    // and nothing else is reported
+    Fixture::new(source)
+        .version("1.0.0")
+        .env("RELEASE_CUT", "1")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU00");
+}
+
+#[test]
+fn invalid_name() {
+    for name in ["RELEASE-CUT", "1ST", "", "$RELEASE_CUT"] {
+        Fixture::new(&format!(
+            "#[allow_until::allow_until(version = \">=2.0\", escalate_env = {:?})]\n\
+             pub struct Legacy;\n",
+            name
+        ))
+        .compile()
+        .assert_failure()
+        .assert_contains(&format!(
+            "invalid environment variable name `{}`, expected letters, digits and underscores, \
+             not starting with a digit",
+            name
+        ));
+    }
+}
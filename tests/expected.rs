@@ -0,0 +1,78 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+    pub struct XmlConfig;
+
+    #[allow_until::allow_until(version = ">=3.0", id = "old-tls-shim")]
+    pub struct TlsShim;
+
+    allow_until::allow_until_summary!();
+"#;
+
+#[test]
+fn met() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_EXPECTED", "drop-xml-config")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `XmlConfig` not allowed!")
+        .assert_not_contains("[AU0027]");
+}
+
+#[test]
+fn unmet() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_EXPECTED", "drop-xml-config, old-tls-shim")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0027] `ALLOW_UNTIL_EXPECTED` expects these annotations to have expired, \
+             but their requirements don't match: `old-tls-shim`",
+        )
+        .assert_not_contains("weren't seen");
+}
+
+#[test]
+fn unmet_without_expired_annotations() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_EXPECTED", "drop-xml-config")
+        .compile()
+        .assert_failure()
+        .assert_contains("requirements don't match: `drop-xml-config`")
+        .assert_not_contains("not allowed!");
+}
+
+#[test]
+fn unknown_id() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_EXPECTED", "drop-xml-config,drop-yaml-config")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0027] `ALLOW_UNTIL_EXPECTED` expects annotations that weren't seen to have \
+             expired: `drop-yaml-config`",
+        )
+        .assert_not_contains("requirements don't match");
+}
+
+#[test]
+fn suppressed() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_EXPECTED", "drop-xml-config")
+        .env("ALLOW_UNTIL_SUPPRESS", "drop-xml-config")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("not allowed!")
+        .assert_contains(
+            "error: [AU0027] ids are both expected to expire and suppressed: `drop-xml-config`",
+        )
+        .assert_contains("help: remove them from one of the two variables");
+}
@@ -0,0 +1,95 @@
+//! `extend_all_by`, which shifts the requirement of every annotation outward, and the
+//! `ALLOW_UNTIL_EXTEND` override.
+
+mod common;
+
+use common::Fixture;
+
+const EXTENDED: &str = "[package.metadata.allow-until]\nextend_all_by = \"1 major\"\n";
+
+#[test]
+fn extended() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .version("2.4.0")
+        .manifest(EXTENDED)
+        .compile()
+        .assert_success();
+
+    Fixture::new(source)
+        .version("3.0.0")
+        .manifest(EXTENDED)
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "an extension of 1 major is active with `extend_all_by`, so the requirement `>=2.0` \
+             is evaluated as `>=3.0`",
+        )
+        .assert_not_contains("extend the deadline");
+}
+
+#[test]
+fn opted_out() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", no_extend = true, reason = "insecure")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("2.0.0")
+    .manifest(EXTENDED)
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] insecure")
+    .assert_contains(
+        "an extension of 1 major is active with `extend_all_by`, but `no_extend` keeps the \
+         requirement `>=2.0` as written",
+    );
+}
+
+#[test]
+fn env_override() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=1.4")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .version("1.5.0")
+        .env("ALLOW_UNTIL_EXTEND", "2 minors")
+        .compile()
+        .assert_success();
+
+    // the override replaces the configured extension
+    Fixture::new(source)
+        .version("1.6.0")
+        .manifest(EXTENDED)
+        .env("ALLOW_UNTIL_EXTEND", "2 minors")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "an extension of 2 minors is active with `ALLOW_UNTIL_EXTEND`, so the requirement \
+             `>=1.4` is evaluated as `>=1.6`",
+        );
+
+    // and `none` turns it off
+    Fixture::new(source)
+        .version("1.4.0")
+        .manifest(EXTENDED)
+        .env("ALLOW_UNTIL_EXTEND", "none")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("extension");
+
+    Fixture::new(source)
+        .version("1.4.0")
+        .env("ALLOW_UNTIL_EXTEND", "a while")
+        .compile()
+        .assert_failure()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_EXTEND` value: invalid distance `a while`");
+}
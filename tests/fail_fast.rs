@@ -0,0 +1,84 @@
+//! `ALLOW_UNTIL_FAIL_FAST=1`, which reports only the first expiry error of a crate in full.
+
+mod common;
+
+use common::Fixture;
+
+/// `count` expired structs, followed by the summary.
+fn expired(count: usize) -> String {
+    let mut source: String = (0..count)
+        .map(|i| {
+            format!(
+                "#[allow_until::allow_until(version = \">=1.0\")]\npub struct Legacy{};\n",
+                i
+            )
+        })
+        .collect();
+    source.push_str("allow_until::allow_until_summary!();\n");
+    source
+}
+
+#[test]
+fn only_the_first_in_full() {
+    let full = Fixture::new(&expired(30))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_VERBOSE", "1")
+        .compile();
+    full.assert_failure();
+    assert_eq!(full.stderr.matches("error: [AU0001]").count(), 30);
+
+    let outcome = Fixture::new(&expired(30))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_FAIL_FAST", "1")
+        .env("ALLOW_UNTIL_VERBOSE", "1")
+        .compile();
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy0` not allowed!")
+        .assert_contains(
+            "`ALLOW_UNTIL_FAIL_FAST=1` is set, so the items expiring after this one are only \
+             noted briefly",
+        )
+        .assert_contains(
+            "note: [AU0001] struct `Legacy29` expired too (version 1.0.0 matches >=1.0)",
+        )
+        .assert_contains(
+            "note: [AU0031] …and 29 more items expired after the first, noted briefly as \
+             `ALLOW_UNTIL_FAIL_FAST=1` is set",
+        )
+        .assert_not_contains("[AU0001] struct `Legacy1` not allowed!");
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 1);
+    assert_eq!(outcome.stderr.matches("note: [AU0001]").count(), 29);
+    // `max_errors` doesn't apply on top
+    assert_eq!(outcome.stderr.matches("[AU0031]").count(), 1);
+}
+
+#[test]
+fn warnings_are_unaffected() {
+    let source = expired(3).replace("\")]", "\", severity = \"warning\")]");
+    let outcome = Fixture::new(&source)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_FAIL_FAST", "1")
+        .compile();
+    outcome.assert_success();
+    assert_eq!(outcome.stderr.matches("warning: [AU0001]").count(), 3);
+}
+
+#[test]
+fn report_lists_every_annotation() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-fail-fast-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(&expired(12))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_FAIL_FAST", "1")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_failure();
+
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert_eq!(report.matches(r#""matched":true"#).count(), 12);
+}
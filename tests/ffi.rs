@@ -0,0 +1,93 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn extern_block() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        extern "C" {
+            fn acme_old_init();
+            #[link_name = "acme_old_state"]
+            static OLD_STATE: u32;
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("extern block not allowed!")
+    .assert_contains("imported symbol `acme_old_init` (C ABI) must be removed")
+    .assert_contains("imported symbol `acme_old_state` must be removed");
+}
+
+#[test]
+fn no_mangle_fn() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", reason = "use acme_init")]
+        #[no_mangle]
+        pub extern "C" fn acme_old_init() {}
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("use acme_init (version 1.0.0 matches >=1.0)")
+    .assert_contains("exported symbol `acme_old_init` (C ABI) must be removed");
+}
+
+#[test]
+fn export_name_override() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        #[export_name = "acme_legacy"]
+        pub fn legacy() {}
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("fn `legacy` not allowed!")
+    .assert_contains("exported symbol `acme_legacy` (Rust ABI) must be removed");
+}
+
+#[test]
+fn not_expired() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        #[no_mangle]
+        pub extern "C" fn acme_old_init() {}
+        "#,
+    )
+    .version("0.9.0")
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn unsafe_attributes() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        unsafe extern "C" {
+            pub safe fn acme_old_abs(x: i32) -> i32;
+            #[link_name = "acme_old_state"]
+            pub static OLD_STATE: u32;
+        }
+
+        #[allow_until::allow_until(version = ">=1.0")]
+        #[unsafe(export_name = "acme_legacy")]
+        pub extern "system" fn legacy() {}
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("imported symbol `acme_old_abs` (C ABI) must be removed")
+    .assert_contains("imported symbol `acme_old_state` must be removed")
+    .assert_contains("exported symbol `acme_legacy` (system ABI) must be removed");
+}
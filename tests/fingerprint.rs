@@ -0,0 +1,165 @@
+//! The fingerprints identifying annotations without an `id`.
+
+mod common;
+
+use common::{Fixture, Outcome};
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", reason = "use `Json`")]
+    pub struct Xml;
+"#;
+
+fn fingerprint(outcome: &Outcome) -> String {
+    let start = outcome
+        .stderr
+        .find("fingerprint: au-")
+        .unwrap_or_else(|| panic!("no fingerprint in:\n{}", outcome.stderr));
+    outcome.stderr[start + "fingerprint: ".len()..][.."au-".len() + 16].into()
+}
+
+fn compile(source: &str) -> Outcome {
+    let outcome = Fixture::new(source).version("2.0.0").compile();
+    outcome.assert_failure();
+    outcome
+}
+
+#[test]
+fn stable_across_unrelated_changes() {
+    let original = fingerprint(&compile(SOURCE));
+
+    // other items before it, and the annotation reformatted
+    let moved = compile(
+        r#"
+        pub fn unrelated() {}
+
+        pub struct Json;
+
+        #[allow_until::allow_until(
+            reason = "use `Json`",
+            version = ">= 2.0",
+        )]
+        pub struct Xml;
+        "#,
+    );
+    assert_eq!(fingerprint(&moved), original);
+
+    let changed = compile(
+        r#"
+        #[allow_until::allow_until(version = ">=1.9", reason = "use `Json`")]
+        pub struct Xml;
+        "#,
+    );
+    assert_ne!(fingerprint(&changed), original);
+}
+
+#[test]
+fn suppressed_by_fingerprint() {
+    let fingerprint = fingerprint(&compile(SOURCE));
+
+    Fixture::new(&format!("{}\nallow_until::allow_until_summary!();", SOURCE))
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_SUPPRESS", &fingerprint)
+        .compile()
+        .assert_success()
+        .assert_contains(&format!(
+            "`ALLOW_UNTIL_SUPPRESS` suppressed 1 expired annotation(s): `{}`",
+            fingerprint
+        ))
+        .assert_not_contains("match no annotation");
+}
+
+#[test]
+fn not_noted_with_an_id() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "xml")]
+        pub struct Xml;
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_not_contains("fingerprint");
+}
+
+#[test]
+fn collision() {
+    Fixture::new(
+        r#"
+        pub struct Client;
+        pub struct Server;
+
+        impl Client {
+            #[allow_until::allow_until(version = ">=2.0")]
+            pub fn new() {}
+        }
+
+        impl Server {
+            #[allow_until::allow_until(version = ">=2.0")]
+            pub fn new() {}
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("warning: [AU0045] the fingerprint `au-")
+    .assert_contains("is already that of another annotation")
+    .assert_contains("first used at");
+}
+
+#[test]
+fn items_without_a_name() {
+    let outcome = Fixture::new(
+        r#"
+        pub struct Client;
+        pub struct Server;
+
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+        macro_rules! mm { () => {}; }
+
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+        macro_rules! nn { () => {}; }
+
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+        impl Clone for Client { fn clone(&self) -> Self { Client } }
+
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+        impl Clone for Server { fn clone(&self) -> Self { Server } }
+
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+        #[allow(unused_imports)]
+        use std::fmt;
+        "#,
+    )
+    .version("2.0.0")
+    .compile();
+
+    outcome.assert_failure().assert_not_contains("AU0045");
+    let mut fingerprints: Vec<_> = outcome
+        .stderr
+        .match_indices("fingerprint: au-")
+        .map(|(start, _)| &outcome.stderr[start..start + "fingerprint: au-".len() + 16])
+        .collect();
+    assert_eq!(fingerprints.len(), 5, "{}", outcome.stderr);
+    fingerprints.sort();
+    fingerprints.dedup();
+    assert_eq!(fingerprints.len(), 5, "{}", outcome.stderr);
+
+    // items with nothing to tell them apart but their tokens aren't warned about
+    Fixture::new(
+        r#"
+        macro_rules! noop { () => {}; }
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        noop!();
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        noop!();
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0045");
+}
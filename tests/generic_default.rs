@@ -0,0 +1,140 @@
+//! `generic_default`, which schedules the removal of the default of a generic parameter rather
+//! than of the item.
+
+mod common;
+
+use common::Fixture;
+
+const INDEX: &str = r#"
+pub struct LegacyHasher;
+
+#[allow_until::allow_until(version = ">=2.0", generic_default = "S")]
+pub struct Index<K, S = LegacyHasher> {
+    pub keys: Vec<K>,
+    pub hasher: S,
+}
+"#;
+
+#[test]
+fn default_of_a_type_parameter() {
+    Fixture::new(INDEX)
+        .version("1.4.0")
+        .compile()
+        .assert_success();
+
+    Fixture::new(INDEX)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "[AU0001] the default of type parameter `S` of struct `Index` not allowed! (version \
+             2.0.0 matches >=2.0)",
+        )
+        // at the default rather than at the name of the struct
+        .assert_contains("lib.rs:5:25")
+        .assert_contains("help: remove the default, so users choose the parameter");
+}
+
+#[test]
+fn default_of_a_const_parameter() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "N", reason = "pick a size")]
+        pub struct Buffer<const N: usize = 16>([u8; N]);
+    "#;
+
+    Fixture::new(source)
+        .version("1.4.0")
+        .compile()
+        .assert_success();
+
+    Fixture::new(source)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("[AU0001] pick a size (version 2.0.0 matches >=2.0)")
+        .assert_contains("the default of const parameter `N` of struct `Buffer` must be removed");
+}
+
+#[test]
+fn parameter_without_a_default() {
+    Fixture::new(&INDEX.replace("S = LegacyHasher", "S"))
+        .version("1.4.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("type parameter `S` of struct `Index` has no default")
+        .assert_contains("remove `generic_default` if the default was removed already");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "S")]
+        pub fn hash<S: Default>() -> S {
+            S::default()
+        }
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("type parameter `S` of fn `hash` has no default");
+}
+
+#[test]
+fn default_of_a_fn_parameter() {
+    // the compiler rejects defaults on functions too, but the annotation still finds it
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "S")]
+        pub fn hash<S = u64>() {}
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("[AU0001] the default of type parameter `S` of fn `hash` not allowed!");
+}
+
+#[test]
+fn missing_parameter() {
+    Fixture::new(&INDEX.replace("generic_default = \"S\"", "generic_default = \"Hasher\""))
+        .version("1.4.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `Index` has no generic parameter `Hasher`")
+        .assert_contains("remove `generic_default` if the parameter was removed already");
+
+    // renamed without updating the annotation
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "Hasher")]
+        pub struct Index<Hashr = u64>(Hashr);
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("did you mean `Hashr`?");
+}
+
+#[test]
+fn lifetime() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "'a")]
+        pub struct Borrowed<'a>(&'a str);
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("lifetime `'a` of struct `Borrowed` can't have a default");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", generic_default = "S T")]
+        pub struct Index<S = u64>(S);
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid generic parameter name `S T`");
+}
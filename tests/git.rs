@@ -0,0 +1,107 @@
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", version_source = "git")]
+    pub struct Legacy;
+"#;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn tagged(tag: &'static str) -> impl FnOnce(&Path) {
+    move |dir| {
+        git(dir, &["init", "-q"]);
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-qm", "init"]);
+        git(dir, &["tag", tag]);
+    }
+}
+
+#[test]
+fn version_from_tag() {
+    Fixture::new(SOURCE)
+        .version("0.0.0")
+        .setup(tagged("v2.1.0"))
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `Legacy` not allowed! (version 2.1.0 matches >=2.0)")
+        .assert_contains("current version 2.1.0 (from the git tag `v2.1.0`)");
+
+    Fixture::new(SOURCE)
+        .version("0.0.0")
+        .setup(tagged("1.9.0"))
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn override_beats_git() {
+    Fixture::new(SOURCE)
+        .setup(tagged("v2.1.0"))
+        .env("ALLOW_UNTIL_VERSION", "1.0.0")
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn falls_back_to_cargo() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("`git describe --tags` failed")
+        .assert_contains("falling back to `CARGO_PKG_VERSION`")
+        .assert_contains("version 2.0.0 matches >=2.0");
+
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .setup(tagged("release-latest"))
+        .compile()
+        .assert_contains("the git tag `release-latest` is not a semver version");
+}
+
+#[test]
+fn manifest_default() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until]
+        version_source = "git"
+    "#,
+    )
+    .version("0.0.0")
+    .setup(tagged("v2.0.0"))
+    .compile()
+    .assert_failure()
+    .assert_contains("current version 2.0.0 (from the git tag `v2.0.0`)");
+}
+
+#[test]
+fn invalid_source() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", version_source = "svn")]
+        pub struct Legacy;
+    "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid version source `svn`, expected `cargo` or `git`");
+}
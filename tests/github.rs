@@ -0,0 +1,83 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=1.0", reason = "use `Json`, 100% supported")]
+pub struct XmlConfig;
+
+#[allow_until::allow_until(version = ">=1.0", severity = "warning")]
+pub fn legacy() {}
+
+#[allow_until::allow_until(version = ">=2.0")]
+pub fn later() {}
+"#;
+
+#[test]
+fn commands() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("GITHUB_ACTIONS", "true")
+        .env("ALLOW_UNTIL_GHA", "1")
+        .compile();
+    let stderr = outcome.assert_failure().normalized_stderr();
+
+    assert!(
+        stderr.contains(
+            "::error file=$DIR/lib.rs,line=3,col=12,title=allow-until::[AU0001] use `Json`, 100%25 \
+             supported (version 1.0.0 matches >=1.0)%0A= note: removal scheduled here (line 2, \
+             column 1)%0A"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(
+            "::warning file=$DIR/lib.rs,line=6,col=8,title=allow-until::[AU0001] fn `legacy` not \
+             allowed! (version 1.0.0 matches >=1.0)"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("`later`"), "{}", stderr);
+    // the diagnostics are emitted as well
+    assert!(
+        stderr.contains("error: [AU0001] use `Json`, 100% supported"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn notes_are_part_of_the_message() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", severity = "warning", silent = true)]
+        pub struct Legacy;
+        "#,
+    )
+    .env("GITHUB_ACTIONS", "true")
+    .env("ALLOW_UNTIL_GHA", "1")
+    .compile();
+
+    outcome.assert_failure().assert_contains(
+        "title=allow-until::[AU0005] `silent` can't be combined with `severity`%0A= note: ",
+    );
+}
+
+#[test]
+fn outside_of_workflows() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_GHA", "1")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("::error");
+
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("GITHUB_ACTIONS", "true")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("::error");
+}
@@ -0,0 +1,114 @@
+//! `allow_until_group!`, which applies one annotation to several items.
+
+mod common;
+
+use common::Fixture;
+
+const GROUP: &str = r#"allow_until::allow_until_group! {
+    version = ">=2.0", reason = "the sync API is gone",
+    {
+        pub fn connect() -> SyncClient {
+            SyncClient
+        }
+
+        pub struct SyncClient;
+
+        pub mod sync {
+            pub const TIMEOUT: u32 = 30;
+        }
+
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub fn block_on() {}
+    }
+}
+
+pub fn caller() -> u32 {
+    let _ = connect();
+    sync::TIMEOUT
+}
+"#;
+
+#[test]
+fn not_expired() {
+    Fixture::new(GROUP)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("warning");
+}
+
+#[test]
+fn each_item_is_reported() {
+    let outcome = Fixture::new(GROUP).version("2.0.0").compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] the sync API is gone (version 2.0.0 matches >=2.0)")
+        .assert_contains("lib.rs:4:16")
+        .assert_contains("lib.rs:8:20")
+        .assert_contains("lib.rs:10:17")
+        .assert_contains("= note: fn `connect` must be removed")
+        .assert_contains("= note: struct `SyncClient` must be removed")
+        .assert_contains("= note: module `sync` must be removed")
+        .assert_contains("note: the requirement `>=2.0` matches version 2.0.0")
+        .assert_not_contains("block_on");
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 3);
+    // the code to remove is the item alone, not the group's arguments
+    assert!(outcome
+        .normalized_stderr()
+        .contains("help: remove the expired struct `SyncClient`\n --> $DIR/lib.rs:8:9"));
+}
+
+#[test]
+fn own_annotation_wins() {
+    Fixture::new(GROUP)
+        .version("3.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0001] fn `block_on` not allowed! (version 3.0.0 matches >=3.0)",
+        );
+}
+
+#[test]
+fn stable() {
+    let outcome = Fixture::new(GROUP).stable().version("2.0.0").compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("= note: removal scheduled here (line 2, column 5)")
+        .assert_contains("lib.rs:4:16")
+        .assert_contains("lib.rs:8:20")
+        .assert_contains("lib.rs:10:17");
+    assert_eq!(outcome.stderr.matches("error: [AU0001]").count(), 3);
+}
+
+#[test]
+fn invalid_input() {
+    Fixture::new(
+        r#"allow_until::allow_until_group! {
+    version = ">=2.0"
+}
+"#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] expected the grouped items in braces after the arguments");
+
+    Fixture::new(
+        r#"allow_until::allow_until_group! {
+    reason = "gone",
+    { pub fn a() {} }
+}
+
+pub fn b() {
+    a()
+}
+"#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0006] missing required `version` argument")
+    .assert_contains("lib.rs:2:5")
+    .assert_not_contains("cannot find function");
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=1.2")]
+    pub struct Legacy;
+"#;
+
+const CACHE: &str = "home/registry/index/index.crates.io-6f17d22bba15001f/.cache/fi/xt/fixture";
+
+/// A cache file in Cargo's format: a header followed by NUL-terminated version and JSON pairs.
+fn cache(versions: &[&str]) -> String {
+    let mut contents = String::from("\u{3}\u{2}\0\0\0etag\0");
+    for v in versions {
+        contents.push_str(&format!(
+            "{v}\0{{\"name\":\"fixture\",\"vers\":\"{v}\",\"deps\":[],\"cksum\":\"00\",\"features\":{{}},\"yanked\":false}}\0"
+        ));
+    }
+    contents
+}
+
+#[test]
+fn published_version_matches() {
+    Fixture::new(SOURCE)
+        .version("1.1.0")
+        .env("ALLOW_UNTIL_INDEX_CHECK", "1")
+        .env("CARGO_HOME", "home")
+        .file(CACHE, &cache(&["1.0.0", "1.2.0", "1.2.1"]))
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "struct `Legacy` still exists, but the published version 1.2.0 already matches >=1.2",
+        );
+}
+
+#[test]
+fn nothing_published_matches() {
+    Fixture::new(SOURCE)
+        .version("1.1.0")
+        .env("ALLOW_UNTIL_INDEX_CHECK", "1")
+        .env("CARGO_HOME", "home")
+        .file(CACHE, &cache(&["1.0.0", "1.1.0"]))
+        .compile()
+        .assert_success()
+        .assert_not_contains("warning");
+}
+
+#[test]
+fn requires_opt_in() {
+    Fixture::new(SOURCE)
+        .version("1.1.0")
+        .env("CARGO_HOME", "home")
+        .file(CACHE, &cache(&["1.2.0"]))
+        .compile()
+        .assert_success()
+        .assert_not_contains("warning");
+}
+
+#[test]
+fn missing_cache_is_a_note() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.2")]
+        pub struct A;
+        #[allow_until::allow_until(version = ">=1.2")]
+        pub struct B;
+    "#,
+    )
+    .version("1.1.0")
+    .env("ALLOW_UNTIL_INDEX_CHECK", "1")
+    .env("CARGO_HOME", "home")
+    .compile()
+    .assert_success()
+    .assert_not_contains("warning")
+    .assert_contains(
+        "note: [AU0015] `ALLOW_UNTIL_INDEX_CHECK` is set, but the published versions are unknown",
+    );
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::Fixture;
+
+fn annotate(issue: &str) -> String {
+    format!(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", issue = {:?})]
+        pub struct Legacy;
+        "#,
+        issue
+    )
+}
+
+#[test]
+fn urls() {
+    for issue in [
+        "https://github.com/DexterHill0/allow-until/issues/12",
+        "http://jira.example.com/browse/CORE-12",
+        "https://localhost:8080/issues/1",
+    ] {
+        Fixture::new(&annotate(issue))
+            .version("1.0.0")
+            .compile()
+            .assert_success()
+            .assert_not_contains("[AU0005]");
+    }
+}
+
+#[test]
+fn invalid_urls() {
+    for (issue, why) in [
+        ("TODO", "expected an absolute http(s) URL"),
+        (
+            "github.com/org/repo/issues/1",
+            "expected an absolute http(s) URL",
+        ),
+        (
+            "ftp://example.com/issues/1",
+            "only `http` and `https` URLs are accepted",
+        ),
+        (
+            "https://github.com/",
+            "the URL points to `github.com` rather than an issue",
+        ),
+        ("https://github/org/repo", "`github` is not a valid host"),
+        (
+            "https://github.com/org/repo/issues/1 ",
+            "URLs can't contain ' '",
+        ),
+    ] {
+        Fixture::new(&annotate(issue))
+            .version("1.0.0")
+            .compile()
+            .assert_failure()
+            .assert_contains(&format!(
+                "error: [AU0005] invalid `issue` `{}`: {}",
+                issue, why
+            ))
+            .assert_contains("help: link the issue tracking the removal")
+            .assert_contains("define shorthands with `issue_patterns`");
+    }
+}
+
+#[test]
+fn shorthands() {
+    let manifest = r#"
+        [package.metadata.allow-until]
+        issue_patterns = ["{name}/{name}#{number}", "CORE-{number}"]
+    "#;
+
+    for issue in [
+        "DexterHill0/allow-until#12",
+        "CORE-7",
+        "https://example.com/issues/7",
+    ] {
+        Fixture::new(&annotate(issue))
+            .manifest(manifest)
+            .version("1.0.0")
+            .compile()
+            .assert_success();
+    }
+
+    Fixture::new(&annotate("allow-until#12"))
+        .manifest(manifest)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "invalid `issue` `allow-until#12`: expected an absolute http(s) URL or a shorthand \
+             matching `issue_patterns`",
+        )
+        .assert_contains("use a shorthand matching `{name}/{name}#{number}`, `CORE-{number}`");
+}
+
+#[test]
+fn derive_helpers() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=2.0", issue = "TODO")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] invalid `issue` `TODO`");
+}
+
+#[test]
+fn invalid_patterns() {
+    Fixture::new(&annotate("https://example.com/issues/7"))
+        .manifest(
+            r#"
+            [package.metadata.allow-until]
+            issue_patterns = ["{org}/{repo}#{number}", 12]
+            "#,
+        )
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "ignoring `\"{org}/{repo}#{number}\"` in `issue_patterns`: unknown placeholder \
+             `{org}` in issue pattern `{org}/{repo}#{number}`, expected `{name}` or `{number}`",
+        )
+        .assert_contains("ignoring `12` in `issue_patterns`: `12` is not a string");
+}
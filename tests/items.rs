@@ -0,0 +1,113 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn const_item() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        pub const OLD_TIMEOUT: u64 = 30;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("const `OLD_TIMEOUT` not allowed! (version 1.0.0 matches >=1.0)");
+}
+
+#[test]
+fn static_item() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", reason = "caching moved to the client")]
+        pub static mut GLOBAL_CACHE: Option<u32> = None;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("caching moved to the client (version 1.0.0 matches >=1.0)")
+    .assert_contains("static `GLOBAL_CACHE` must be removed");
+}
+
+#[test]
+fn type_alias() {
+    Fixture::new(
+        r#"
+        pub struct New;
+
+        #[allow_until::allow_until(version = ">=1.0")]
+        pub type OldAlias = New;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("type alias `OldAlias` not allowed!")
+    .assert_contains("`OldAlias` is an alias of `New`");
+}
+
+#[test]
+fn derive_members() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Pair<A, B: Into<Vec<u8>>>(
+            A,
+            #[allow_until(version = ">=1.0")] B,
+        );
+
+        #[derive(allow_until::AllowUntil)]
+        pub enum Shape<const N: usize> {
+            #[allow_until(version = ">=1.0")]
+            r#Square,
+            Polygon {
+                sides: [u8; N],
+                #[allow_until(version = ">=1.0")]
+                r#type: u8,
+            },
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("field `Pair::1` not allowed!")
+    .assert_contains("variant `Shape::Square` not allowed!")
+    .assert_contains("field `Shape::Polygon::type` not allowed!");
+}
+
+#[test]
+fn associated_fn_without_body() {
+    Fixture::new(
+        r#"
+        pub trait Reload {
+            #[allow_until::allow_until(version = ">=1.0")]
+            fn r#reload(&self);
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("fn `reload` not allowed!");
+}
+
+#[test]
+fn escaped_strings() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", reason = "use \"New\" instead")]
+        pub struct Old;
+
+        #[allow_until::allow_until(version = r">=1.0", reason = r"C:\legacy")]
+        pub struct Raw;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(r#"use "New" instead (version 1.0.0 matches >=1.0)"#)
+    .assert_contains(r"C:\legacy (version 1.0.0 matches >=1.0)");
+}
@@ -0,0 +1,69 @@
+//! The `link-section` feature, which embeds the annotations into the binaries the crate is linked
+//! into.
+#![cfg(all(feature = "link-section", target_os = "linux"))]
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", id = "xml", reason = "XML output is removed in 2.0")]
+pub struct XmlWriter;
+
+pub trait Format {
+    #[allow_until::allow_until(version = ">=3.0", reason = "use `render`")]
+    fn write(&self);
+}
+
+#[allow_until::allow_until(version = ">=2.5")]
+fn legacy_main() {}
+
+allow_until::allow_until_summary!();
+"#;
+
+/// The contents of the section called `name` of the ELF file `elf`.
+fn section<'a>(elf: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    let u16_at = |at: usize| u16::from_le_bytes(elf[at..at + 2].try_into().unwrap()) as usize;
+    let u32_at = |at: usize| u32::from_le_bytes(elf[at..at + 4].try_into().unwrap()) as usize;
+    let u64_at = |at: usize| u64::from_le_bytes(elf[at..at + 8].try_into().unwrap()) as usize;
+
+    assert_eq!(&elf[..4], b"\x7fELF");
+    let (headers, size, count) = (u64_at(0x28), u16_at(0x3a), u16_at(0x3c));
+    let header = |index: usize| headers + index * size;
+    let names = u64_at(header(u16_at(0x3e)) + 0x18);
+
+    (0..count).map(header).find_map(|header| {
+        let start = names + u32_at(header);
+        let end = start + elf[start..].iter().position(|&b| b == 0)?;
+        (&elf[start..end] == name.as_bytes()).then(|| {
+            let offset = u64_at(header + 0x18);
+            &elf[offset..offset + u64_at(header + 0x20)]
+        })
+    })
+}
+
+#[test]
+fn embedded() {
+    // a test harness, as the fixture is compiled as a library
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("--test")
+        .rustc_arg("--emit=link")
+        .compile();
+    outcome.assert_success();
+
+    let binary = std::fs::read(outcome.dir.join("fixture")).unwrap();
+    let contents = section(&binary, ".allow_until").expect("no `.allow_until` section");
+    let entries: BTreeSet<_> = std::str::from_utf8(contents).unwrap().lines().collect();
+
+    assert_eq!(
+        entries,
+        BTreeSet::from([
+            r#"{"id":"xml","reason":"XML output is removed in 2.0","requirement":">=2.0"}"#,
+            r#"{"id":null,"reason":"use `render`","requirement":">=3.0"}"#,
+            r#"{"id":null,"reason":null,"requirement":">=2.5"}"#,
+        ])
+    );
+}
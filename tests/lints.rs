@@ -0,0 +1,111 @@
+mod common;
+
+use common::Fixture;
+
+fn annotate(args: &str) -> String {
+    format!(
+        "#[allow_until::allow_until({})]\npub struct Legacy;\n",
+        args
+    )
+}
+
+#[test]
+fn bare_version() {
+    Fixture::new(&annotate(r#"version = "1.0""#))
+        .version("0.9.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0024] `1.0` is a bare version, which semver treats as a `^` requirement, \
+             matching only versions from 1.0.0 up to, but excluding, 2.0.0",
+        )
+        .assert_contains("lib.rs:1:38")
+        .assert_contains(
+            r#"= help: use `">=1.0"` to match every version from 1.0.0 on, or `allow_lint = "comparator_style"` to keep it"#,
+        );
+}
+
+#[test]
+fn caret() {
+    Fixture::new(&annotate(r#"version = "^0.3""#))
+        .version("0.2.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0024] `^0.3` is a `^` requirement matching only versions from 0.3.0 up \
+             to, but excluding, 0.4.0",
+        )
+        .assert_contains(r#"use `">=0.3"`"#);
+}
+
+#[test]
+fn tilde() {
+    Fixture::new(&annotate(r#"version = "~1.2, <1.2.5""#))
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0024] `~1.2, <1.2.5` is a `~` requirement matching only versions from \
+             1.2.0 up to, but excluding, 1.3.0",
+        )
+        .assert_contains(r#"use `">=1.2, <1.2.5"`"#);
+}
+
+#[test]
+fn explicit_comparators() {
+    for req in [">=1.0", ">1.0, <=3", "=1.2.3", "<2"] {
+        Fixture::new(&annotate(&format!("version = {:?}", req)))
+            .version("0.9.0")
+            .compile()
+            .assert_not_contains("AU0024");
+    }
+}
+
+#[test]
+fn allowed_by_argument() {
+    Fixture::new(&annotate(
+        r#"version = "1.0", allow_lint = "comparator_style""#,
+    ))
+    .version("0.9.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0024");
+
+    Fixture::new(&annotate(
+        r#"allow_lint = ["comparator_style"], version = "~1.0""#,
+    ))
+    .version("0.9.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0024");
+}
+
+#[test]
+fn allowed_by_metadata() {
+    Fixture::new(&annotate(r#"version = "1.0""#))
+        .manifest(
+            r#"
+            [package]
+            name = "fixture"
+            version = "0.9.0"
+
+            [package.metadata.allow-until]
+            allow_lint = ["comparator_style"]
+            "#,
+        )
+        .version("0.9.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0024");
+}
+
+#[test]
+fn unknown_lint() {
+    Fixture::new(&annotate(r#"version = ">=1.0", allow_lint = "style""#))
+        .version("0.9.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0005] unknown lint `style`, expected one of `comparator_style`",
+        );
+}
@@ -0,0 +1,68 @@
+//! `match_precision`, which compares the version and the requirement at a coarser precision.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn minor() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.4.3", match_precision = "minor")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.4.1")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+    .assert_contains(
+        "compared at `match_precision = \"minor\"`, as 1.4.0 against `>=1.4` rather than 1.4.1 \
+         against `>=1.4.3`",
+    );
+}
+
+#[test]
+fn crate_default() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=1.4.3")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .version("1.4.1")
+        .compile()
+        .assert_success();
+
+    Fixture::new(source)
+        .version("1.4.1")
+        .manifest("[package.metadata.allow-until]\nmatch_precision = \"minor\"\n")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+
+    // the annotation's own precision wins
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.4.3", match_precision = "patch")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.4.1")
+    .manifest("[package.metadata.allow-until]\nmatch_precision = \"minor\"\n")
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.4.3", match_precision = "build")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid precision `build`, expected `major`, `minor` or `patch`");
+}
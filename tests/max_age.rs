@@ -0,0 +1,92 @@
+//! `max_age`, which warns about annotations allowed for too long since their `since`.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = "[package.metadata.allow-until]\nmax_age = \"2 minors\"\n";
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=3.0", since = "1.0.3")]
+pub struct Legacy;
+"#;
+
+#[test]
+fn boundary() {
+    Fixture::new(SOURCE)
+        .manifest(MANIFEST)
+        .version("1.2.9")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0044");
+
+    Fixture::new(SOURCE)
+        .manifest(MANIFEST)
+        .version("1.3.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0044] struct `Legacy` is stale, allowed since 1.0.3, 3 minor releases ago",
+        )
+        .assert_contains("`max_age` allows at most 2 minor releases, so it is stale from 1.3.0 on")
+        .assert_contains("help: remove it now, or snooze it with `since = \"1.3.0\"`");
+
+    Fixture::new(SOURCE)
+        .manifest(MANIFEST)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("struct `Legacy` is stale, allowed since 1.0.3, 1 major release ago");
+}
+
+#[test]
+fn expired_annotations_are_not_stale() {
+    Fixture::new(SOURCE)
+        .manifest(MANIFEST)
+        .version("3.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001]")
+        .assert_not_contains("AU0044");
+}
+
+#[test]
+fn without_since() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .manifest(MANIFEST)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0044] the annotation of struct `Legacy` has no `since`")
+        .assert_contains("help: add `since = \"1.0.0\"`");
+
+    // only with the policy enabled
+    Fixture::new(source)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0044");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(SOURCE)
+        .manifest("[package.metadata.allow-until]\nmax_age = \"2 releases\"\n")
+        .compile()
+        .assert_contains("invalid distance `2 releases`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=3.0", since = "someday")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("someday");
+}
@@ -0,0 +1,83 @@
+//! `max_horizon`, which warns about requirements that first match too far ahead of the current
+//! version.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = "[package.metadata.allow-until]\nmax_horizon = \"2 majors\"\n";
+
+fn annotated(requirement: &str, extra: &str) -> String {
+    format!(
+        "#[allow_until::allow_until(version = {:?}{})]\npub struct Legacy;\n",
+        requirement, extra
+    )
+}
+
+#[test]
+fn inside() {
+    for requirement in [">=3.9", ">=1.3"] {
+        Fixture::new(&annotated(requirement, ""))
+            .manifest(MANIFEST)
+            .version("1.2.0")
+            .compile()
+            .assert_success()
+            .assert_not_contains("AU0046");
+    }
+}
+
+#[test]
+fn outside() {
+    Fixture::new(&annotated(">=9.0", ""))
+        .manifest(MANIFEST)
+        .version("1.2.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0046] the requirement `>=9.0` of struct `Legacy` first matches 9.0.0, 8 \
+             major releases from now",
+        )
+        .assert_contains("`max_horizon` allows at most 2 major releases")
+        .assert_contains("help: aim for a nearer milestone, such as `\">=3.0.0\"`")
+        .assert_contains("lib.rs:1:38");
+
+    // the boundary
+    Fixture::new(&annotated(">=4.0", ""))
+        .manifest(MANIFEST)
+        .version("1.2.0")
+        .compile()
+        .assert_success()
+        .assert_contains("first matches 4.0.0, 3 major releases from now");
+
+    // only with the policy enabled
+    Fixture::new(&annotated(">=9.0", ""))
+        .version("1.2.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0046");
+}
+
+#[test]
+fn opt_out() {
+    Fixture::new(&annotated(">=9.0", ", horizon_ok = true"))
+        .manifest(MANIFEST)
+        .version("1.2.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0046");
+
+    Fixture::new(&annotated(">=9.0", ", horizon_ok = \"yes\""))
+        .manifest(MANIFEST)
+        .compile()
+        .assert_failure()
+        .assert_contains("expected `true` or `false`");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(&annotated(">=9.0", ""))
+        .manifest("[package.metadata.allow-until]\nmax_horizon = \"far\"\n")
+        .version("1.2.0")
+        .compile()
+        .assert_contains("invalid distance `far`");
+}
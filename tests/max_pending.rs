@@ -0,0 +1,109 @@
+//! `max_pending!`, which caps how many annotations may still be pending for a milestone.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=1.5", id = "xml")]
+    pub struct Xml;
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Yaml;
+    #[allow_until::allow_until(version = ">=2.0, <3")]
+    pub fn legacy() {}
+    #[allow_until::allow_until(version = ">=2.1")]
+    pub fn later() {}
+"#;
+
+#[test]
+fn within_the_budget() {
+    Fixture::new(&format!(
+        "{}\nallow_until::max_pending!(3, version = \">=2.0\");",
+        SOURCE
+    ))
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0049");
+
+    Fixture::new(&format!(
+        "{}\nallow_until::max_pending!(1, version = \">=1.5\");",
+        SOURCE
+    ))
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0049");
+}
+
+#[test]
+fn over_the_budget() {
+    let outcome = Fixture::new(&format!(
+        "{}\nallow_until::max_pending!(2, version = \">=2.0\");",
+        SOURCE
+    ))
+    .compile();
+    outcome
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0049] 3 annotations are pending for `>=2.0`, but `max_pending!` allows 2",
+        )
+        .assert_contains("pending: struct `Xml` (`>=1.5`, id `xml`) at lib.rs:2:5")
+        .assert_contains("pending: struct `Yaml` (`>=2.0`, fingerprint `au-")
+        .assert_contains("pending: fn `legacy` (`>=2.0, <3`")
+        .assert_not_contains("fn `later`");
+}
+
+#[test]
+fn annotations_after_it_are_not_counted() {
+    Fixture::new(&format!(
+        "allow_until::max_pending!(0, version = \">=2.0\");\n{}",
+        SOURCE
+    ))
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0049");
+}
+
+#[test]
+fn repeated_expansion_counts_once() {
+    Fixture::new(
+        r#"
+        macro_rules! twice {
+            ($item:item) => {
+                pub mod first { $item }
+                pub mod second { $item }
+            };
+        }
+
+        twice! {
+            #[allow_until::allow_until(version = ">=2.0")]
+            pub struct A;
+        }
+
+        allow_until::max_pending!(1, version = ">=2.0");
+        "#,
+    )
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0049");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new("allow_until::max_pending!(5);")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0005] `max_pending!` takes a budget and a `version`, but 1 arguments were \
+             given",
+        );
+
+    Fixture::new("allow_until::max_pending!(5, since = \">=2.0\");")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected `version = \"...\"`");
+
+    Fixture::new("allow_until::max_pending!(5, version = \"soon\");")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0002] invalid semver requirement `soon`");
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package]
+name = "fixture"
+version = "2.0.0"
+
+[package.metadata.allow-until]
+severity = "warning"
+owner = "platform-team"
+"#;
+
+#[test]
+fn defaults_only() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("2.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+    .assert_contains("owner: platform-team");
+}
+
+#[test]
+fn arguments_override_defaults() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "error", owner = "io-team")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+    .assert_contains("owner: io-team")
+    .assert_not_contains("platform-team");
+}
+
+#[test]
+fn grace_and_docsrs_defaults() {
+    let manifest = r#"
+        [package]
+        name = "fixture"
+        version = "2.0.0"
+
+        [package.metadata.allow-until]
+        grace = "1 minor"
+        docsrs = "ignore"
+    "#;
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#;
+
+    Fixture::new(source)
+        .manifest(manifest)
+        .version("2.0.3")
+        .compile()
+        .assert_success()
+        .assert_contains("during the 1 minor grace period, it becomes an error at version 2.1.0");
+
+    Fixture::new(source)
+        .manifest(manifest)
+        .version("2.1.0")
+        .compile()
+        .assert_failure();
+
+    Fixture::new(source)
+        .manifest(manifest)
+        .version("2.1.0")
+        .env("DOCS_RS", "1")
+        .compile()
+        .assert_success()
+        .assert_not_contains("not allowed");
+}
+
+#[test]
+fn unknown_key() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Other;
+        "#,
+    )
+    .manifest(
+        r#"
+        [package]
+        name = "fixture"
+        version = "1.0.0"
+
+        [package.metadata.allow-until]
+        severty = "warning"
+        "#,
+    )
+    .version("1.0.0")
+    .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains("unknown key `severty` in `[package.metadata.allow-until]`");
+    assert_eq!(outcome.stderr.matches("unknown key").count(), 1);
+}
+
+#[test]
+fn malformed() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest("[package\nname = ")
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("failed to parse")
+    .assert_contains("struct `Legacy` not allowed!");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until]
+        severity = "fatal"
+        grace = 3
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("invalid severity `fatal`")
+    .assert_contains("`grace` in `[package.metadata.allow-until]` must be a string");
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+const MANIFEST: &str = r#"
+[package.metadata.allow-until.migrations.drop-xml]
+version = ">=2.0"
+reason = "XML config removed"
+issue = "https://example.com/issues/12"
+owner = "config-team"
+
+[package.metadata.allow-until.migrations.json-v1]
+version = ">=3.0"
+"#;
+
+#[test]
+fn fields_from_the_migration() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xml")]
+        pub struct XmlConfig;
+
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(migration = "drop-xml", reason = "use `json` instead")]
+            pub xml: bool,
+            #[allow_until(migration = "json-v1")]
+            pub json_v1: bool,
+        }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] XML config removed (version 2.0.0 matches >=2.0)")
+    .assert_contains("= note: tracking issue: https://example.com/issues/12")
+    // only the reason can be overridden
+    .assert_contains("error: [AU0001] use `json` instead (version 2.0.0 matches >=2.0)")
+    .assert_not_contains("json_v1");
+}
+
+#[test]
+fn required_arguments_from_the_migration() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xml")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .manifest(&format!(
+        "{}\n[package.metadata.allow-until]\nrequired = [\"reason\", \"issue\", \"owner\"]\n",
+        MANIFEST
+    ))
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("missing the required");
+}
+
+#[test]
+fn only_the_reason_is_overridden() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xml", version = ">=3.0")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `version` can't be combined with `migration`")
+    .assert_contains(
+        "help: the migration defines the requirement, remove `version` or `migration`",
+    );
+}
+
+#[test]
+fn unknown_migration() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xm")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(migration = "yaml")]
+        pub struct YamlConfig;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0005] unknown migration `drop-xm`")
+        .assert_contains("lib.rs:2:48")
+        .assert_contains(
+            "help: did you mean `drop-xml`? defined migrations are `drop-xml`, `json-v1`",
+        )
+        .assert_contains("error: [AU0005] unknown migration `yaml`")
+        .assert_contains("help: defined migrations are `drop-xml`, `json-v1`");
+}
+
+#[test]
+fn no_migrations() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xml")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown migration `drop-xml`")
+    .assert_contains(
+        "help: no migrations are defined, add them to \
+         `[package.metadata.allow-until.migrations]` in `Cargo.toml`",
+    );
+}
+
+#[test]
+fn invalid_migrations() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until.migrations.no-version]
+        reason = "forgot the version"
+
+        [package.metadata.allow-until.migrations.bad-version]
+        version = ">=two"
+
+        [package.metadata.allow-until.migrations.typo]
+        version = ">=2.0"
+        isue = "CORE-1"
+        "#,
+    )
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "ignoring migration `no-version` in `[package.metadata.allow-until.migrations]`: missing \
+         required key `version`",
+    )
+    .assert_contains("ignoring migration `bad-version` in `[package.metadata.allow-until.migrations]`: invalid semver requirement `>=two`")
+    .assert_contains(
+        "ignoring migration `typo` in `[package.metadata.allow-until.migrations]`: unknown key \
+         `isue`, expected one of `version`, `reason`, `issue`, `owner`",
+    );
+}
+
+#[test]
+fn report_groups_by_migration() {
+    let dir = std::env::temp_dir().join(format!("allow-until-migrations-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let report = dir.join("report.jsonl");
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(migration = "drop-xml")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Other;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile()
+    .assert_success();
+
+    let entries: Vec<Value> = std::fs::read_to_string(&report)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(entries[0]["migration"], "drop-xml");
+    assert_eq!(entries[0]["requirement"], ">=2.0");
+    assert_eq!(entries[0]["reason"], "XML config removed");
+    assert_eq!(entries[0]["issue"], "https://example.com/issues/12");
+    assert_eq!(entries[0]["owner"], "config-team");
+    assert_eq!(entries[1]["migration"], Value::Null);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
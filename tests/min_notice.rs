@@ -0,0 +1,145 @@
+//! `min_notice`, which requires items to be deprecated for long enough before they are removed.
+
+mod common;
+
+use common::Fixture;
+
+const MIN_NOTICE: &str = "[package.metadata.allow-until]\nmin_notice = \"1 minor\"\n";
+
+#[test]
+fn compliant() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.5", deprecate_from = "1.4.2", severity = "warning")]
+        pub struct Xml;
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        #[deprecated(since = "1.4.0", note = "use `Json`")]
+        pub struct Yaml;
+        "#,
+    )
+    .manifest(MIN_NOTICE)
+    .version("2.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("warning: [AU0001] struct `Xml` not allowed!")
+    .assert_not_contains("AU0035");
+}
+
+#[test]
+fn not_compliant() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.4.5", deprecate_from = "1.4.2", severity = "warning")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MIN_NOTICE)
+    .version("1.4.5")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0035] struct `Xml` is removed at 1.4.5, 3 patch releases after its \
+         deprecation at 1.4.2, but `min_notice` requires 1 minor release",
+    )
+    .assert_contains("deprecated at 1.4.2")
+    .assert_contains(
+        "remove it from 1.5.0 on, with `version = \">=1.5.0\"`, or deprecate it at least 1 minor \
+         release before 1.4.5",
+    );
+}
+
+#[test]
+fn deprecated_attribute() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", min_notice = "2 majors")]
+        #[deprecated(since = "1.9.0")]
+        pub fn connect() {}
+        "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0035] fn `connect` is removed at 2.0.0, 1 major release after its deprecation \
+         at 1.9.0, but `min_notice` requires 2 major releases",
+    )
+    .assert_contains("remove it from 3.0.0 on")
+    // the note only points at `deprecate_from`
+    .assert_not_contains("note: deprecated at");
+}
+
+#[test]
+fn missing_deprecation() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        #[deprecated = "use `Json`"]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MIN_NOTICE)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0035] `min_notice` requires the version the expired struct `Xml` was \
+         deprecated in",
+    )
+    .assert_contains("add `deprecate_from = \"...\"` to the annotation");
+}
+
+#[test]
+fn only_once_expired() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MIN_NOTICE)
+    .version("1.9.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0035");
+}
+
+#[test]
+fn derive_members() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=1.5", min_notice = "1 minor", severity = "warning")]
+            #[deprecated(since = "1.5")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .version("1.5.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0035] field `Options::legacy` is removed at 1.5.0, in the same release as its \
+         deprecation at 1.5.0",
+    );
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", deprecate_from = "soon")]
+        pub struct Xml;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid version `soon`");
+
+    Fixture::new("#[allow_until::allow_until(version = \">=2.0\")]\npub struct Xml;\n")
+        .manifest("[package.metadata.allow-until]\nmin_notice = \"a while\"\n")
+        .compile()
+        .assert_contains("invalid distance `a while`");
+}
@@ -0,0 +1,72 @@
+//! `not_before`, which catches annotations backported to a release branch older than they are.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", not_before = "1.5.0")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn before_bound() {
+    Fixture::new(SOURCE)
+        .version("1.4.3")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0037] annotation appears to be on the wrong branch, as version 1.4.3 is \
+             before its `not_before` 1.5.0",
+        )
+        .assert_contains("remove the annotation if it was backported by accident");
+}
+
+#[test]
+fn between_bound_and_expiry() {
+    Fixture::new(SOURCE)
+        .version("1.5.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0037");
+}
+
+#[test]
+fn expired() {
+    // the bound doesn't get in the way of the requirement
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("version 2.0.0 matches >=2.0")
+        .assert_not_contains("AU0037");
+}
+
+#[test]
+fn after_requirement() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", not_before = "2.1")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0005] `not_before` is 2.1.0, after 2.0.0, the first version `>=2.0` matches",
+    );
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", not_before = ">=1.5")]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid version `>=1.5`");
+}
@@ -0,0 +1,117 @@
+//! `ALLOW_UNTIL_OFFLINE=1`, which keeps the expansion from consulting anything outside the source
+//! tree and the build's environment.
+
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", version_source = "git")]
+    pub struct Legacy;
+"#;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn tagged(dir: &Path) {
+    git(dir, &["init", "-q"]);
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-qm", "init"]);
+    git(dir, &["tag", "v2.1.0"]);
+}
+
+#[test]
+fn git_describe() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .setup(tagged)
+        .env("ALLOW_UNTIL_OFFLINE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "`git describe` is not consulted, as `ALLOW_UNTIL_OFFLINE=1` asks for a hermetic build",
+        )
+        .assert_contains("falling back to `CARGO_PKG_VERSION`");
+
+    // the version can still be given through the environment
+    Fixture::new(SOURCE)
+        .setup(tagged)
+        .env("ALLOW_UNTIL_OFFLINE", "1")
+        .env("ALLOW_UNTIL_VERSION", "2.1.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("version 2.1.0 matches >=2.0")
+        .assert_not_contains("not consulted");
+}
+
+#[test]
+fn git_blame() {
+    let report =
+        std::env::temp_dir().join(format!("allow-until-offline-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new("#[allow_until::allow_until(version = \">=2.0\")]\npub struct Legacy;\n")
+        .version("1.0.0")
+        .setup(tagged)
+        .env("ALLOW_UNTIL_OFFLINE", "1")
+        .env("ALLOW_UNTIL_BLAME", "1")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0034] `git blame`, for `ALLOW_UNTIL_BLAME`, is not consulted, as \
+             `ALLOW_UNTIL_OFFLINE=1` asks for a hermetic build",
+        );
+
+    let entry = std::fs::read_to_string(&report).unwrap();
+    let _ = std::fs::remove_file(&report);
+    assert!(entry.contains("\"blame\":null"), "{}", entry);
+}
+
+#[test]
+fn registry_index() {
+    Fixture::new("#[allow_until::allow_until(version = \">=1.2\")]\npub struct Legacy;\n")
+        .version("1.1.0")
+        .env("ALLOW_UNTIL_OFFLINE", "1")
+        .env("ALLOW_UNTIL_INDEX_CHECK", "1")
+        .env("CARGO_HOME", "home")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0034] the registry index cache, for `ALLOW_UNTIL_INDEX_CHECK`, is not \
+             consulted",
+        );
+}
+
+#[test]
+fn online_by_default() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .setup(tagged)
+        .compile()
+        .assert_failure()
+        .assert_contains("version 2.1.0 matches >=2.0")
+        .assert_not_contains("AU0034");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .setup(tagged)
+        .env("ALLOW_UNTIL_OFFLINE", "yes")
+        .compile()
+        .assert_failure()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_OFFLINE` value `yes`, expected `1`")
+        .assert_contains("version 2.1.0 matches >=2.0");
+}
@@ -0,0 +1,128 @@
+//! `on_expiry = "ignore_test"`, which ignores an expired `#[test]` function with a warning rather
+//! than failing the build.
+
+mod common;
+
+use std::process::Command;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(
+    version = ">=2.0",
+    reason = "XML output is removed in 2.0",
+    on_expiry = "ignore_test"
+)]
+#[test]
+fn writes_xml() {
+    panic!("XML output is gone");
+}
+"#;
+
+#[test]
+fn ignored_with_a_warning() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0001] XML output is removed in 2.0 (version 2.0.0 matches >=2.0)",
+        )
+        .assert_contains(
+            "`on_expiry = \"ignore_test\"` ignores the test instead of failing the build",
+        );
+}
+
+#[test]
+fn expansion() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .rustc_arg("--test")
+        .rustc_arg("-Zunpretty=expanded")
+        .rustc_arg("-o")
+        .rustc_arg("expanded.rs")
+        .compile();
+    outcome.assert_success();
+
+    let expanded = std::fs::read_to_string(outcome.dir.join("expanded.rs")).unwrap();
+    assert!(
+        expanded.contains("expired: XML output is removed in 2.0"),
+        "{}",
+        expanded
+    );
+
+    // not before expiry
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("--test")
+        .rustc_arg("-Zunpretty=expanded")
+        .rustc_arg("-o")
+        .rustc_arg("expanded.rs")
+        .compile();
+    outcome.assert_success();
+
+    let expanded = std::fs::read_to_string(outcome.dir.join("expanded.rs")).unwrap();
+    assert!(!expanded.contains("expired:"), "{}", expanded);
+}
+
+#[test]
+fn test_is_skipped() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .rustc_arg("--test")
+        .rustc_arg("--emit=link")
+        .compile();
+    outcome.assert_success();
+
+    let output = Command::new(outcome.dir.join("fixture")).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("test writes_xml ... ignored, expired: XML output is removed in 2.0"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn not_a_test() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", on_expiry = "ignore_test")]
+        pub fn write_xml() {}
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0005] `on_expiry = \"ignore_test\"` can't be used on fn `write_xml`",
+    )
+    .assert_contains("lib.rs:2:67");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", on_expiry = "skip")]
+        #[test]
+        fn writes_xml() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid `on_expiry` action `skip`, expected `\"ignore_test\"`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", test_guard = true, on_expiry = "ignore_test")]
+        #[test]
+        fn writes_xml() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("`on_expiry` can't be combined with `test_guard`");
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::Fixture;
+
+const PANIC: &str = "ALLOW_UNTIL_TEST_PANIC";
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+
+    #[derive(allow_until::AllowUntil)]
+    pub struct Options {
+        #[allow_until(version = ">=2.0")]
+        pub legacy: bool,
+    }
+
+    pub fn uses(_: Legacy, _: Options) {}
+
+    allow_until::allow_until_summary!();
+"#;
+
+#[test]
+fn panics_become_errors() {
+    let outcome = Fixture::new(SOURCE).env(PANIC, "1").compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0021] internal error in `allow_until`: `ALLOW_UNTIL_TEST_PANIC` is set",
+        )
+        .assert_contains(
+            "this is a bug, please report it at https://github.com/DexterHill0/allow-until/issues",
+        )
+        .assert_not_contains("proc macro panicked")
+        .assert_not_contains("panicked at")
+        // the annotated items are kept, so nothing else fails
+        .assert_not_contains("cannot find type");
+    assert_eq!(outcome.stderr.matches("internal error").count(), 3);
+}
+
+#[test]
+fn panics_become_errors_on_stable() {
+    Fixture::new(SOURCE)
+        .stable()
+        .env(PANIC, "1")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0021] internal error in `allow_until`: `ALLOW_UNTIL_TEST_PANIC` is set",
+        )
+        .assert_not_contains("proc macro panicked")
+        .assert_not_contains("cannot find type");
+}
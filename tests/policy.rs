@@ -0,0 +1,112 @@
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package.metadata.allow-until]
+required = ["reason", "issue", "owner"]
+issue_patterns = ["CORE-{number}"]
+"#;
+
+#[test]
+fn partially_compliant() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "use `New`", owner = "core")]
+        pub struct MissingIssue;
+
+        #[allow_until::allow_until(version = ">=2.0", issue = "CORE-12")]
+        pub struct MissingTwo;
+
+        #[allow_until::allow_until(
+            version = ">=2.0",
+            reason = "use `New`",
+            owner = "core",
+            issue = "CORE-12"
+        )]
+        pub struct Compliant;
+    "#,
+    )
+    .manifest(MANIFEST)
+    .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0007] annotation is missing the required argument `issue`")
+        .assert_contains(
+            "error: [AU0007] annotation is missing the required arguments `reason`, `owner`",
+        );
+    assert_eq!(outcome.stderr.matches("is missing the required").count(), 2);
+}
+
+#[test]
+fn derive_helpers() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Fields {
+            #[allow_until(version = ">=2.0", reason = "unused", owner = "core", issue = "CORE-3")]
+            pub fine: u8,
+            #[allow_until(version = ">=2.0", reason = "unused", issue = "CORE-3")]
+            pub ownerless: u8,
+        }
+    "#,
+    )
+    .manifest(MANIFEST)
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0007] annotation is missing the required argument `owner`");
+}
+
+#[test]
+fn defaults_satisfy_requirements() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "use `New`", issue = "CORE-12")]
+        pub struct Legacy;
+    "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until]
+        owner = "core"
+        required = ["reason", "issue", "owner"]
+        issue_patterns = ["CORE-{number}"]
+    "#,
+    )
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn unknown_required_argument() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until]
+        required = ["reason", "ticket"]
+    "#,
+    )
+    .compile()
+    .assert_contains("ignoring `\"ticket\"` in `required`")
+    .assert_contains("error: [AU0007] annotation is missing the required argument `reason`");
+}
+
+#[test]
+fn issue_is_shown() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", issue = "https://example.com/issues/12")]
+        pub struct Legacy;
+    "#,
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("= note: tracking issue: https://example.com/issues/12");
+}
@@ -0,0 +1,76 @@
+//! The build script's probe of the unstable `proc_macro` APIs, which selects between the nightly
+//! diagnostics and the stable fallback, and the trace note naming the selected one.
+
+mod common;
+
+use common::Fixture;
+
+const EXPIRED: &str = r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "use `Json`")]
+        pub struct Legacy;
+        "#;
+
+#[test]
+fn nightly_apis() {
+    Fixture::new(EXPIRED)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "note: [AU0019] allow_until trace: diagnostics are emitted with the nightly \
+             `proc_macro::Diagnostic` API",
+        )
+        // notes of their own, rather than appended to the message
+        .assert_contains("note: removal scheduled here\n");
+}
+
+#[test]
+fn fallback_when_the_apis_do_not_compile() {
+    let outcome = Fixture::new(EXPIRED)
+        .fallback()
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] use `Json` (version 2.0.0 matches >=2.0)")
+        .assert_contains("= note: removal scheduled here (line 2, column 9)")
+        .assert_contains(
+            "note: [AU0019] allow_until trace: diagnostics use the stable fallback, as the \
+             nightly `proc_macro` APIs didn't compile with the toolchain allow-until was built \
+             with",
+        );
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("allow_until trace: diagnostics")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn fallback_without_the_feature() {
+    Fixture::new(EXPIRED)
+        .stable()
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "note: [AU0019] allow_until trace: diagnostics use the stable fallback, as the \
+             `nightly` feature is off",
+        );
+}
+
+#[test]
+fn not_traced() {
+    Fixture::new(EXPIRED)
+        .fallback()
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_not_contains("allow_until trace");
+}
@@ -0,0 +1,155 @@
+//! `ALLOW_UNTIL_PUBLIC_API`, which matches annotated items against a listing of the public API, and
+//! `public_api`, which raises the severity of those in it.
+
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+/// As `cargo public-api` lists it.
+const LISTING: &str = "\
+pub mod fixture
+pub mod fixture::legacy
+pub struct fixture::Index<K, S>
+pub fixture::Index::keys: alloc::vec::Vec<K>
+pub fn fixture::Index<K, S>::new() -> Self
+pub fn fixture::legacy::load(path: &str) -> u8
+impl<K, S> core::clone::Clone for fixture::Index<K, S>
+";
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+pub struct Index<K, S> {
+    pub keys: Vec<K>,
+    pub hasher: S,
+}
+
+impl<K, S: Default> Index<K, S> {
+    #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+    pub fn new() -> Self {
+        Index { keys: Vec::new(), hasher: S::default() }
+    }
+}
+
+#[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+pub(crate) struct Internal;
+
+mod inner {
+    // listed under another path, if at all
+    #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+    pub fn keys() {}
+}
+
+#[path = "src/legacy.rs"]
+pub mod legacy;
+"#;
+
+const LEGACY: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+pub fn load(_path: &str) -> u8 {
+    0
+}
+"#;
+
+#[test]
+fn report_entries() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-public-api-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(SOURCE)
+        .file("src/legacy.rs", LEGACY)
+        .file("api.txt", LISTING)
+        .env("ALLOW_UNTIL_PUBLIC_API", "api.txt")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let entries: Vec<Value> = std::fs::read_to_string(&report)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let public = |item: &str| {
+        entries
+            .iter()
+            .find(|entry| entry["item"] == item)
+            .unwrap_or_else(|| panic!("no entry for `{}`", item))["is_public_api"]
+            .clone()
+    };
+    assert_eq!(public("Index"), Value::Bool(true));
+    assert_eq!(public("load"), Value::Bool(true));
+    assert_eq!(public("Internal"), Value::Bool(false));
+    // a method, whose type isn't known, and an item of an inline module
+    assert_eq!(public("new"), Value::Null);
+    assert_eq!(public("keys"), Value::Null);
+}
+
+#[test]
+fn without_a_listing() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-public-api-none-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(SOURCE)
+        .file("src/legacy.rs", LEGACY)
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert_eq!(report.matches(r#""is_public_api":null"#).count(), 5);
+}
+
+#[test]
+fn severity_of_public_items() {
+    let outcome = Fixture::new(SOURCE)
+        .file("src/legacy.rs", LEGACY)
+        .file("api.txt", LISTING)
+        .manifest("[package.metadata.allow-until]\npublic_api = \"error\"\n")
+        .env("ALLOW_UNTIL_PUBLIC_API", "api.txt")
+        .version("2.0.0")
+        .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Index` not allowed!")
+        .assert_contains("error: [AU0001] fn `load` not allowed!")
+        .assert_contains(
+            "this is an error as `public_api = \"error\"` is set and the struct is in the public \
+             API listed by `ALLOW_UNTIL_PUBLIC_API`",
+        )
+        .assert_contains("warning: [AU0001] struct `Internal` not allowed!")
+        .assert_contains("warning: [AU0001] fn `new` not allowed!");
+
+    // without the listing, nothing is known to be public
+    Fixture::new(SOURCE)
+        .file("src/legacy.rs", LEGACY)
+        .manifest("[package.metadata.allow-until]\npublic_api = \"error\"\n")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Index` not allowed!");
+}
+
+#[test]
+fn missing_listing() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-public-api-missing-{}.jsonl",
+        std::process::id()
+    ));
+
+    Fixture::new(SOURCE)
+        .file("src/legacy.rs", LEGACY)
+        .env("ALLOW_UNTIL_PUBLIC_API", "missing.txt")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success()
+        .assert_contains("failed to read the public API listing `/")
+        .assert_contains("/missing.txt`: ");
+    let _ = std::fs::remove_file(&report);
+}
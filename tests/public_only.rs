@@ -0,0 +1,111 @@
+mod common;
+
+use common::Fixture;
+
+fn annotate(vis: &str, public_only: &str) -> String {
+    format!(
+        r#"
+        mod inner {{
+            #[allow_until::allow_until(version = ">=1.0", public_only = {})]
+            {}struct Legacy;
+        }}
+        "#,
+        public_only, vis
+    )
+}
+
+#[test]
+fn public_is_enforced() {
+    Fixture::new(&annotate("pub ", "true"))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("`public_only` is set");
+}
+
+#[test]
+fn non_public_is_a_warning() {
+    for (vis, described) in [
+        ("pub(crate) ", "`pub(crate)`"),
+        ("pub(super) ", "restricted"),
+        ("pub(self) ", "restricted"),
+        ("pub(in crate::inner) ", "restricted"),
+        ("", "private"),
+    ] {
+        Fixture::new(&annotate(vis, "true"))
+            .version("1.0.0")
+            .compile()
+            .assert_success()
+            .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+            .assert_contains(&format!(
+                "note: this is a warning as `public_only` is set and the struct is {}",
+                described
+            ));
+    }
+}
+
+#[test]
+fn ignore() {
+    Fixture::new(&annotate("pub(crate) ", r#""ignore""#))
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn disabled() {
+    Fixture::new(&annotate("", "false"))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn fields_and_variants() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=1.0", public_only = true)]
+            pub modern: bool,
+            #[allow_until(version = ">=1.0", public_only = true)]
+            legacy: bool,
+        }
+
+        #[derive(allow_until::AllowUntil)]
+        pub enum Format {
+            #[allow_until(version = ">=1.0", public_only = true)]
+            Xml,
+        }
+
+        #[derive(allow_until::AllowUntil)]
+        enum Private {
+            #[allow_until(version = ">=1.0", public_only = true)]
+            Old { #[allow_until(version = ">=1.0", public_only = true)] value: u8 },
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] field `Options::modern` not allowed!")
+    .assert_contains("warning: [AU0001] field `Options::legacy` not allowed!")
+    // variants are as visible as their enum
+    .assert_contains("error: [AU0001] variant `Format::Xml` not allowed!")
+    .assert_contains("warning: [AU0001] variant `Private::Old` not allowed!")
+    .assert_contains("warning: [AU0001] field `Private::Old::value` not allowed!");
+}
+
+#[test]
+fn invalid_handling() {
+    Fixture::new(&annotate("", r#""error""#))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "invalid `public_only` handling `error`, expected `true`, `\"warning\"` or `\"ignore\"`",
+        );
+}
@@ -0,0 +1,97 @@
+//! `reason_min_words` and `reason_deny_patterns`, which report low-quality reasons where they are
+//! written.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package.metadata.allow-until]
+reason_min_words = 3
+reason_deny_patterns = ["TODO", "fix later"]
+"#;
+
+#[test]
+fn too_few_words() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "remove soon")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("warning: [AU0039] the reason has 2 words, but `reason_min_words` requires 3")
+    .assert_contains("lib.rs:2:64");
+}
+
+#[test]
+fn denied_pattern() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "todo: Fix Later, once we can")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0039] the reason contains `todo`, which `reason_deny_patterns` denies",
+    );
+
+    // as an error when configured
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "we will fix later I promise")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(&format!("{}reason_severity = \"error\"\n", MANIFEST))
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0039] the reason contains `fix later`, which `reason_deny_patterns` denies",
+    );
+}
+
+#[test]
+fn compliant() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "the XML backend is unmaintained")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0039");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "remove soon")]
+        pub struct Xml;
+        "#,
+    )
+    .manifest(
+        "[package.metadata.allow-until]\nreason_min_words = 0\nreason_deny_patterns = \"TODO\"\n",
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_contains(
+        "`reason_min_words` in `[package.metadata.allow-until]` must be a positive integer",
+    )
+    .assert_contains(
+        "`reason_deny_patterns` in `[package.metadata.allow-until]` must be an array of strings",
+    )
+    .assert_not_contains("AU0039");
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package.metadata.allow-until]
+required = ["reason"]
+
+[package.metadata.allow-until.reasons]
+xml-removal = "XML config support ends with 2.0; see docs/migrations/xml.md"
+json-v1 = "the v1 JSON format is gone\nconvert files with `migrate-json`"
+"#;
+
+#[test]
+fn reason_from_the_catalogue() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason_key = "xml-removal")]
+        pub struct XmlConfig;
+
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=2.0", reason_key = "json-v1")]
+            pub json_v1: bool,
+        }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0001] XML config support ends with 2.0; see docs/migrations/xml.md \
+         (version 2.0.0 matches >=2.0)",
+    )
+    .assert_contains("error: [AU0001] the v1 JSON format is gone")
+    .assert_contains("note: convert files with `migrate-json`")
+    .assert_not_contains("missing the required");
+}
+
+#[test]
+fn unknown_key() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason_key = "xml-remova")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0", reason_key = "yaml")]
+        pub struct YamlConfig;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile();
+
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0005] unknown reason key `xml-remova`")
+        .assert_contains("lib.rs:2:68")
+        .assert_contains(
+            "help: did you mean `xml-removal`? available keys are `json-v1`, `xml-removal`",
+        )
+        .assert_contains("error: [AU0005] unknown reason key `yaml`")
+        .assert_contains("help: available keys are `json-v1`, `xml-removal`");
+}
+
+#[test]
+fn no_catalogue() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason_key = "xml-removal")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown reason key `xml-removal`")
+    .assert_contains(
+        "help: no reasons are defined, add them to `[package.metadata.allow-until.reasons]`",
+    );
+}
+
+#[test]
+fn reason_and_reason_key() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "gone", reason_key = "xml-removal")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `reason_key` can't be combined with `reason`")
+    .assert_contains("note: `reason` given here");
+}
+
+#[test]
+fn invalid_catalogue() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason_key = "xml-removal")]
+        pub struct XmlConfig;
+        "#,
+    )
+    .manifest(
+        r#"
+        [package.metadata.allow-until.reasons]
+        xml-removal = "XML config support ends with 2.0"
+        count = 3
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "ignoring reason `count` in `[package.metadata.allow-until.reasons]`, it must be a string",
+    );
+}
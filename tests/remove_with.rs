@@ -0,0 +1,96 @@
+//! `remove_with`, which links annotations whose items must be removed in the same release.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn matched() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "xml")]
+        pub struct Xml;
+
+        #[allow_until::allow_until(version = ">=2.0.0", remove_with = "xml")]
+        pub fn parse_xml() {}
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0036");
+}
+
+#[test]
+fn mismatched() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "xml")]
+        pub struct Xml;
+
+        #[allow_until::allow_until(version = ">=2.1", remove_with = "xml")]
+        pub fn parse_xml() {}
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0036] fn `parse_xml` is removed at 2.1.0, but `xml`, which `remove_with` \
+             links it to, is removed at 2.0.0",
+    )
+    .assert_contains("note: `xml` is the id of the annotation of struct `Xml` at ")
+    .assert_contains("lib.rs:2:9")
+    .assert_contains("give both requirements the same first matching version");
+}
+
+#[test]
+fn linked_before_declared() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.1", remove_with = "xml")]
+        pub fn parse_xml() {}
+
+        #[allow_until::allow_until(version = ">=2.0", id = "xml")]
+        pub struct Xml;
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0036] struct `Xml` is removed at 2.0.0, but fn `parse_xml`, which \
+         `remove_with` links to it, is removed at 2.1.0",
+    )
+    .assert_contains("note: linked with `remove_with = \"xml\"` at ")
+    .assert_contains("lib.rs:2:9")
+    .assert_not_contains("no annotation declares");
+}
+
+#[test]
+fn dangling() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "xml")]
+        pub struct Xml;
+
+        #[allow_until::allow_until(version = ">=2.0", remove_with = "xlm")]
+        pub fn parse_xml() {}
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0036] fn `parse_xml` is linked with `remove_with` to the id `xlm`, which no \
+         annotation declares",
+    )
+    .assert_contains("note: linked at ")
+    .assert_contains("lib.rs:5:9");
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn forwards() {
+    Fixture::new(
+        r#"
+        #[allow_until::renamed_until(old = "connect_tls", version = ">=2.0")]
+        pub fn connect_secure(host: &str, (port, retries): (u16, u8), mut timeout: u32) -> bool {
+            timeout += 1;
+            !host.is_empty() && port > 0 && retries < 3 && timeout > 0
+        }
+
+        pub fn caller() -> bool {
+            connect_tls("example.com", (443, 1), 30)
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "use of deprecated function `connect_tls`: renamed to `connect_secure`, removed in >=2.0",
+    );
+}
+
+#[test]
+fn generics_async_and_where_clauses() {
+    Fixture::new(
+        r#"
+        use std::fmt::Display;
+
+        #[allow_until::renamed_until(old = "render", version = ">=2.0")]
+        pub fn format_all<'a, T, const N: usize>(items: &'a [T; N], sep: impl AsRef<str>) -> String
+        where
+            T: Display + 'a,
+        {
+            items.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(sep.as_ref())
+        }
+
+        #[allow_until::renamed_until(old = "parse_old", version = ">=2.0")]
+        pub fn parse_new<T: std::str::FromStr>(text: &str) -> Option<T> {
+            text.parse().ok()
+        }
+
+        #[allow_until::renamed_until(old = "size_old", version = ">=2.0")]
+        pub const fn size_new<T>() -> usize {
+            std::mem::size_of::<T>()
+        }
+
+        #[allow_until::renamed_until(old = "fetch_old", version = ">=2.0")]
+        pub async fn fetch_new<F>(f: F) -> u8
+        where
+            F: std::future::Future<Output = u8>,
+        {
+            f.await
+        }
+
+        #[allow_until::renamed_until(old = "raw_old", version = ">=2.0")]
+        pub unsafe fn raw_new(p: *const u8) -> u8 {
+            *p
+        }
+
+        #[allow(deprecated)]
+        pub async fn callers() -> (String, Option<u8>, usize, u8, u8) {
+            (
+                render(&[1, 2], ", "),
+                parse_old("1"),
+                size_old::<u32>(),
+                fetch_old(async { 1 }).await,
+                unsafe { raw_old(&1) },
+            )
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("deprecated");
+}
+
+#[test]
+fn methods() {
+    Fixture::new(
+        r#"
+        pub struct Client(u8);
+
+        impl Client {
+            #[allow_until::renamed_until(old = "create", version = ">=2.0")]
+            pub fn new(value: u8) -> Self {
+                Client(value)
+            }
+
+            #[allow_until::renamed_until(old = "value_old", version = ">=2.0")]
+            pub fn value(&self) -> u8 {
+                self.0
+            }
+
+            #[allow_until::renamed_until(old = "set_old", version = ">=2.0")]
+            pub fn set(mut self: Box<Self>, value: u8) -> Box<Self> {
+                self.0 = value;
+                self
+            }
+        }
+
+        pub trait Connect {
+            #[allow_until::renamed_until(old = "connect_tls", version = ">=2.0")]
+            fn connect_secure(&mut self) -> bool;
+        }
+
+        impl Connect for Client {
+            fn connect_secure(&mut self) -> bool {
+                true
+            }
+        }
+
+        #[allow(deprecated)]
+        pub fn callers() -> (u8, bool) {
+            let mut client = Client::create(1);
+            let boxed = Box::new(Client(2)).set_old(3);
+            (client.value_old() + boxed.0, client.connect_tls())
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn visibility() {
+    Fixture::new(
+        r#"
+        mod inner {
+            #[allow_until::renamed_until(old = "crate_old", version = ">=2.0")]
+            pub(crate) fn crate_new() {}
+
+            #[allow_until::renamed_until(old = "private_old", version = ">=2.0")]
+            fn private_new() {}
+        }
+
+        #[allow(deprecated)]
+        pub fn callers() {
+            inner::crate_old();
+            inner::private_old();
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("function `private_old` is private")
+    .assert_not_contains("`crate_old` is private");
+}
+
+#[test]
+fn expired() {
+    let source = r#"
+        #[allow_until::renamed_until(old = "connect_tls", version = ">=2.0")]
+        pub fn connect_secure() {}
+    "#;
+
+    Fixture::new(source)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0030] `connect_tls` is no longer generated, as version 2.0.0 matches >=2.0",
+        )
+        .assert_contains("it forwarded to `connect_secure`, which callers must use now")
+        .assert_contains("help: remove the `renamed_until` attribute");
+
+    Fixture::new(&format!("{}\npub fn caller() {{ connect_tls() }}", source))
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("cannot find function `connect_tls`");
+}
+
+#[test]
+fn invalid_arguments() {
+    for (args, message) in [
+        (
+            r#"version = ">=2.0""#,
+            "error: [AU0005] missing required `old` argument",
+        ),
+        (
+            r#"old = "connect_tls""#,
+            "error: [AU0006] missing required `version` argument",
+        ),
+        (
+            r#"old = "connect tls", version = ">=2.0""#,
+            "`connect tls` is not a valid function name",
+        ),
+        (
+            r#"old = "connect_tls", version = ">=two""#,
+            "error: [AU0002] invalid semver requirement",
+        ),
+        (
+            r#"old = "connect_tls", verson = ">=2.0""#,
+            "error: [AU0005] unknown argument `verson`",
+        ),
+    ] {
+        Fixture::new(&format!(
+            "#[allow_until::renamed_until({})]\npub fn connect_secure() {{}}",
+            args
+        ))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(message);
+    }
+
+    Fixture::new(
+        r#"
+        #[allow_until::renamed_until(old = "Old", version = ">=2.0")]
+        pub struct New;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `renamed_until` can only be applied to functions");
+}
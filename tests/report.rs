@@ -0,0 +1,457 @@
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+fn read_report(path: &std::path::Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+#[test]
+fn report_lists_every_annotation() {
+    let report =
+        std::env::temp_dir().join(format!("allow-until-report-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(
+            version = ">=2.0",
+            reason = "use the new config loader",
+            id = "drop-xml-config",
+            tags = ["config", "xml"],
+            owner = "platform-team",
+        )]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=3.0", severity = "warning")]
+        #[no_mangle]
+        pub extern "C" fn acme_old_init() {}
+        "#,
+    )
+    .version("2.1.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile()
+    .assert_failure();
+
+    let entries = read_report(&report);
+    assert_eq!(entries.len(), 2);
+
+    let xml = &entries[0];
+    assert_eq!(xml["requirement"], ">=2.0");
+    assert_eq!(xml["reason"], "use the new config loader");
+    assert_eq!(xml["id"], "drop-xml-config");
+    assert_eq!(xml["tags"], serde_json::json!(["config", "xml"]));
+    assert_eq!(xml["owner"], "platform-team");
+    assert_eq!(xml["severity"], "error");
+    assert_eq!(xml["version"], "2.1.0");
+    assert_eq!(xml["matched"], true);
+    assert_eq!(xml["kind"], "struct");
+    assert_eq!(xml["item"], "XmlConfig");
+    assert!(xml["file"].as_str().unwrap().ends_with("lib.rs"));
+    assert_eq!(xml["line"], 2);
+
+    let ffi = &entries[1];
+    assert_eq!(ffi["matched"], false);
+    assert_eq!(ffi["severity"], "warning");
+    assert_eq!(ffi["reason"], Value::Null);
+    assert_eq!(ffi["symbols"][0]["name"], "acme_old_init");
+    assert_eq!(ffi["symbols"][0]["abi"], "C");
+    assert_eq!(ffi["line"], 11);
+
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn unwritable_report_only_warns() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct A;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct B;
+        "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_REPORT", "/nonexistent/dir/report.jsonl")
+    .compile();
+
+    outcome.assert_success().assert_contains(
+        "failed to write the allow-until report to `/nonexistent/dir/report.jsonl`",
+    );
+    assert_eq!(outcome.stderr.matches("failed to write").count(), 1);
+}
+
+fn temp_report(name: &str) -> std::path::PathBuf {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-report-{}-{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+    report
+}
+
+#[test]
+fn parallel_writers() {
+    const CRATES: usize = 8;
+    const ANNOTATIONS: usize = 50;
+
+    let report = temp_report("parallel");
+
+    let writers: Vec<_> = (0..CRATES)
+        .map(|c| {
+            let report = report.clone();
+            std::thread::spawn(move || {
+                let source: String = (0..ANNOTATIONS)
+                    .map(|a| {
+                        format!(
+                            "#[allow_until::allow_until(version = \">=2.0\", id = \"c{}-a{}\")]\n\
+                             pub struct Item{};\n",
+                            c, a, a
+                        )
+                    })
+                    .collect();
+                Fixture::new(&source)
+                    .version("1.0.0")
+                    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+                    .compile()
+                    .assert_success();
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // every line is a whole entry, and none is missing or duplicated
+    let mut ids: Vec<_> = read_report(&report)
+        .iter()
+        .map(|e| e["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids.len(), CRATES * ANNOTATIONS);
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), CRATES * ANNOTATIONS);
+
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn targets_compiling_the_same_file_are_deduplicated() {
+    let report = temp_report("dedup");
+    // like the library and the tests of a package, both compiling `shared.rs`
+    let shared = std::env::temp_dir().join(format!("allow-until-shared-{}.rs", std::process::id()));
+    std::fs::write(
+        &shared,
+        "#[allow_until::allow_until(version = \">=2.0\")]\npub struct Shared;\n",
+    )
+    .unwrap();
+
+    for own in ["Lib", "Tests"] {
+        Fixture::new(&format!(
+            "#[path = {:?}]\nmod shared;\n\n\
+             #[allow_until::allow_until(version = \">=2.0\")]\npub struct {};\n",
+            shared, own
+        ))
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+    }
+
+    let items: Vec<_> = read_report(&report)
+        .iter()
+        .map(|e| e["item"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(items, ["Shared", "Lib", "Tests"]);
+
+    let _ = std::fs::remove_file(&report);
+    let _ = std::fs::remove_file(&shared);
+}
+
+#[test]
+fn file_per_package() {
+    let dir = std::env::temp_dir().join(format!("allow-until-reports-{}/", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // the directory is created, as a trailing slash names one
+    for package in ["app-core", "app-cli", "app-core"] {
+        Fixture::new(&format!(
+            "#[allow_until::allow_until(version = \">=2.0\")]\npub struct {};\n",
+            package.replace('-', "_")
+        ))
+        .version("1.0.0")
+        .env("CARGO_PKG_NAME", package)
+        .env("ALLOW_UNTIL_REPORT", dir.to_str().unwrap())
+        .compile()
+        .assert_success();
+    }
+
+    for package in ["app-core", "app-cli"] {
+        let entries = read_report(&dir.join(format!("{}.json", package)));
+        assert_eq!(entries.len(), 1, "{}", package);
+        assert_eq!(entries[0]["package"], package);
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn target_directory() {
+    let target = std::env::temp_dir().join(format!("allow-until-target-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&target);
+
+    Fixture::new("#[allow_until::allow_until(version = \">=2.0\")]\npub struct A;\n")
+        .version("1.0.0")
+        .env("CARGO_TARGET_DIR", target.to_str().unwrap())
+        .env("ALLOW_UNTIL_REPORT", "1")
+        .compile()
+        .assert_success();
+
+    let entries = read_report(&target.join("allow-until").join("fixture.json"));
+    assert_eq!(entries[0]["item"], "A");
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn corrupted_report_only_warns() {
+    let report = temp_report("corrupted");
+    // a line cut short, as left by a writer that crashed
+    std::fs::write(&report, "{\"requirement\":\">=2.0\",\"rea").unwrap();
+
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct A;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct B;
+        "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile();
+
+    outcome.assert_success().assert_contains(&format!(
+        "line 1 of the allow-until report `{}` is not a valid entry, the report may be corrupted",
+        report.display()
+    ));
+    assert_eq!(outcome.stderr.matches("not a valid entry").count(), 1);
+
+    // the entries still start on lines of their own
+    let contents = std::fs::read_to_string(&report).unwrap();
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for line in &lines[1..] {
+        serde_json::from_str::<Value>(line).unwrap();
+    }
+
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn typed_entries() {
+    use allow_until_report::{Report, Severity, SCHEMA_VERSION};
+
+    let report = temp_report("typed");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "use `Json`", tags = ["config"])]
+        pub struct XmlConfig;
+
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=3.0", severity = "warning", owner = "platform")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .version("2.1.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile()
+    .assert_failure();
+
+    let text = std::fs::read_to_string(&report).unwrap();
+    let parsed = Report::parse(&text).unwrap();
+    assert_eq!(parsed.annotations.len(), 2);
+
+    let xml = &parsed.annotations[0];
+    assert_eq!(xml.schema_version, SCHEMA_VERSION);
+    assert_eq!(xml.condition.requirement, ">=2.0");
+    assert_eq!(xml.condition.version, "2.1.0");
+    assert!(xml.outcome.matched);
+    assert_eq!(xml.outcome.severity, Severity::Error);
+    assert_eq!(xml.reason.as_deref(), Some("use `Json`"));
+    assert_eq!(xml.tags, ["config"]);
+    assert_eq!(xml.item.as_deref(), Some("XmlConfig"));
+    assert_eq!(xml.blame, None);
+
+    let legacy = &parsed.annotations[1];
+    assert!(!legacy.outcome.matched);
+    assert_eq!(legacy.outcome.severity, Severity::Warning);
+    assert_eq!(legacy.owner.as_deref(), Some("platform"));
+    assert_eq!(legacy.kind, "field");
+    assert_eq!(legacy.item.as_deref(), Some("Options::legacy"));
+
+    assert_eq!(parsed.expired().count(), 1);
+    // the typed entries serialize back to the lines written by the macro
+    let lines: Vec<_> = parsed.annotations.iter().map(|a| a.to_line()).collect();
+    assert_eq!(lines, text.lines().collect::<Vec<_>>());
+
+    let _ = std::fs::remove_file(&report);
+}
+
+const LOCATIONS: &str = r#"
+#[path = "members/inventory/src/xml.rs"]
+mod xml;
+
+  #[allow_until::allow_until(version = ">=2.0")]
+pub struct Legacy;
+
+macro_rules! legacy {
+    ($name:ident) => {
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct $name;
+    };
+}
+
+legacy!(Generated);
+"#;
+
+/// A workspace whose member `inventory` is compiled, with `lib.rs` at the root of the workspace.
+fn locations(report: &std::path::Path) -> Fixture {
+    Fixture::new(LOCATIONS)
+        .manifest("[workspace]\nmembers = [\"members/*\"]\n")
+        .file(
+            "members/inventory/Cargo.toml",
+            "[package]\nname = \"inventory\"\nversion = \"1.0.0\"\n",
+        )
+        .file(
+            "members/inventory/src/xml.rs",
+            "#[allow_until::allow_until(version = \">=2.0\")]\npub struct XmlConfig;\n",
+        )
+        .member("members/inventory")
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+}
+
+fn read_locations(report: &std::path::Path) -> Vec<(String, String, u64, u64)> {
+    read_report(report)
+        .iter()
+        .map(|e| {
+            (
+                e["item"].as_str().unwrap().into(),
+                e["file"].as_str().unwrap().into(),
+                e["line"].as_u64().unwrap(),
+                e["column"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn relative_locations() {
+    let report = temp_report("locations");
+    locations(&report).compile().assert_success();
+
+    assert_eq!(
+        read_locations(&report),
+        [
+            (
+                "XmlConfig".into(),
+                "members/inventory/src/xml.rs".into(),
+                1,
+                1
+            ),
+            ("Legacy".into(), "lib.rs".into(), 5, 3),
+            // in the definition of the macro, originating from its invocation
+            ("Generated".into(), "lib.rs".into(), 10, 9),
+        ]
+    );
+    let origins: Vec<Value> = read_report(&report)
+        .iter()
+        .map(|e| e["origin"].clone())
+        .collect();
+    assert_eq!(
+        origins,
+        [
+            Value::Null,
+            Value::Null,
+            serde_json::json!({ "file": "lib.rs", "line": 15, "column": 1 }),
+        ]
+    );
+
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn relative_locations_on_stable() {
+    let report = temp_report("locations-stable");
+    locations(&report).stable().compile().assert_success();
+
+    // generated annotations can't be told apart without the `nightly` feature, and point into the
+    // definition of the macro
+    assert_eq!(
+        read_locations(&report),
+        [
+            (
+                "XmlConfig".into(),
+                "members/inventory/src/xml.rs".into(),
+                1,
+                1
+            ),
+            ("Legacy".into(), "lib.rs".into(), 5, 3),
+            ("Generated".into(), "lib.rs".into(), 10, 9),
+        ]
+    );
+    assert!(read_report(&report).iter().all(|e| e["origin"].is_null()));
+
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn unresolved_origin() {
+    let report = temp_report("origin");
+    let outside =
+        std::env::temp_dir().join(format!("allow-until-outside-{}.rs", std::process::id()));
+    std::fs::write(&outside, "legacy!(Generated);\n").unwrap();
+
+    // invoked in a file outside of the package
+    Fixture::new(&format!(
+        r#"
+        macro_rules! legacy {{
+            ($name:ident) => {{
+                #[allow_until::allow_until(version = ">=2.0")]
+                pub struct $name;
+            }};
+        }}
+
+        #[path = {:?}]
+        mod outside;
+        "#,
+        outside.display()
+    ))
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile()
+    .assert_success();
+
+    let entries = read_report(&report);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["line"], 4);
+    assert_eq!(
+        entries[0]["origin"],
+        serde_json::json!({ "file": "unknown", "line": 0, "column": 0 })
+    );
+
+    let _ = std::fs::remove_file(&report);
+    let _ = std::fs::remove_file(&outside);
+}
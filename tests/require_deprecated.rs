@@ -0,0 +1,228 @@
+//! `require_deprecated`, which only lets items that are `#[deprecated]` get a removal deadline.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = "[package.metadata.allow-until]\nrequire_deprecated = true\n";
+
+#[test]
+fn deprecated() {
+    Fixture::new(
+        r#"
+        #[deprecated(note = "use `JsonWriter`")]
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct XmlWriter;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        #[deprecated]
+        pub fn write_xml() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0042");
+}
+
+#[test]
+fn not_deprecated() {
+    Fixture::new(
+        r#"#[allow_until::allow_until(version = ">=2.0")]
+        pub struct XmlWriter;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0042] struct `XmlWriter` must be `#[deprecated]` before it can get a removal \
+         deadline",
+    )
+    .assert_contains("lib.rs:1:1")
+    .assert_contains("help: add `#[deprecated]` to the item, or `deprecate_from = \"...\"`");
+}
+
+#[test]
+fn cfg_attr() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        #[cfg_attr(not(feature = "xml"), deprecated = "use `JsonWriter`")]
+        pub struct XmlWriter;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        #[cfg_attr(all(), cfg_attr(all(), allow(dead_code), deprecated))]
+        pub fn write_xml() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0042");
+
+    // not deprecated in this configuration
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        #[cfg_attr(feature = "xml", deprecated)]
+        pub struct XmlWriter;
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0042] struct `XmlWriter` must be `#[deprecated]`");
+}
+
+#[test]
+fn group() {
+    Fixture::new(
+        r#"
+        allow_until::allow_until_group! {
+            version = ">=2.0",
+            {
+                #[cfg_attr(all(), deprecated)]
+                pub struct XmlWriter;
+                pub fn write_xml() {}
+            }
+        }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0042] fn `write_xml` must be `#[deprecated]`")
+    .assert_not_contains("struct `XmlWriter` must be");
+}
+
+#[test]
+fn deprecate_from() {
+    // the item is deprecated by the annotation, so its users are warned
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", deprecate_from = "1.2")]
+        pub struct XmlWriter;
+
+        pub fn writer() -> XmlWriter { XmlWriter }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0042")
+    .assert_contains("use of deprecated unit struct `XmlWriter`");
+
+    // the item's own attribute is kept
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", deprecate_from = "1.2")]
+        #[deprecated(since = "1.2.0", note = "use `JsonWriter`")]
+        pub struct XmlWriter;
+
+        #[allow(deprecated)]
+        pub fn writer() -> XmlWriter { XmlWriter }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("multiple `deprecated` attributes");
+}
+
+#[test]
+fn deprecate_from_groups_and_members() {
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        allow_until::allow_until_group! {
+            version = ">=2.0",
+            deprecate_from = "1.2",
+            {
+                pub fn connect_plain() {}
+            }
+        }
+
+        #[allow_until::allow_members_until(version = ">=2.0", deprecate_from = "1.2")]
+        impl Client {
+            pub fn connect_tls(&self) {}
+        }
+
+        // rustc rejects the attribute on the members of trait impls, which are left alone
+        #[allow_until::allow_members_until(version = ">=2.0", deprecate_from = "1.2")]
+        impl Clone for Client {
+            fn clone(&self) -> Self { Client }
+        }
+
+        pub fn run(client: Client) {
+            connect_plain();
+            client.connect_tls();
+            let _ = client.clone();
+        }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("AU0042")
+    .assert_contains("use of deprecated function `connect_plain`")
+    .assert_contains("use of deprecated method `Client::connect_tls`")
+    .assert_not_contains("`#[deprecated]` attribute cannot be used");
+}
+
+#[test]
+fn per_annotation() {
+    let source = |require: bool| {
+        format!(
+            r#"
+            #[allow_until::allow_until(version = ">=2.0", require_deprecated = {})]
+            pub struct XmlWriter;
+            "#,
+            require
+        )
+    };
+
+    Fixture::new(&source(true))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0042] struct `XmlWriter` must be `#[deprecated]`");
+
+    Fixture::new(&source(false))
+        .manifest(MANIFEST)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0042");
+}
+
+#[test]
+fn fields() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Config {
+            #[allow_until(version = ">=2.0")]
+            #[deprecated]
+            pub xml: bool,
+            #[allow_until(version = ">=2.0")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0042] field `Config::legacy` must be `#[deprecated]`")
+    .assert_not_contains("field `Config::xml` must be");
+}
@@ -0,0 +1,50 @@
+//! `runtime_check!` and `warn_if_expired!`, whose requirement is validated at compile time.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn invalid_requirement() {
+    Fixture::new(
+        r#"
+        pub fn load(host_version: &semver::Version) {
+            let _ = allow_until::runtime_check!(host_version, ">=2.0.0.0", "legacy hook API");
+        }
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid semver requirement `>=2.0.0.0`")
+    .assert_contains("lib.rs:3:63");
+}
+
+#[test]
+fn invalid_arguments() {
+    Fixture::new(
+        r#"
+        pub fn load(host_version: &semver::Version) -> bool {
+            allow_until::warn_if_expired!(host_version, ">=2.0")
+        }
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "`warn_if_expired!` takes a version, a requirement and a reason, but 2 arguments were \
+         given",
+    );
+
+    Fixture::new(
+        r#"
+        const REASON: &str = "legacy hook API";
+
+        pub fn load(host_version: &semver::Version) {
+            let _ = allow_until::runtime_check!(host_version, ">=2.0", REASON);
+        }
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("expected the reason as a string literal");
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+fn sarif_path(name: &str) -> std::path::PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("allow-until-{}-{}.sarif", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn read(path: &std::path::Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn results_per_annotation() {
+    let path = sarif_path("results");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", id = "drop-xml", tags = ["config"])]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=3.0", severity = "warning")]
+        pub fn old() {}
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("2.1.0")
+    .env("ALLOW_UNTIL_SARIF", path.to_str().unwrap())
+    .compile()
+    .assert_failure();
+
+    let log = read(&path);
+    assert_eq!(log["version"], "2.1.0");
+    assert!(log["$schema"].as_str().unwrap().contains("sarif-2.1.0"));
+
+    let runs = log["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run["tool"]["driver"]["name"], "allow-until");
+    assert_eq!(run["automationDetails"]["id"], "fixture");
+
+    let rules: Vec<_> = run["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules, ["allow-until/error", "allow-until/warning"]);
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let xml = &results[0];
+    assert_eq!(xml["ruleId"], "allow-until/error");
+    assert_eq!(xml["level"], "error");
+    assert_eq!(
+        xml["message"]["text"],
+        "struct `XmlConfig` not allowed! (version 2.1.0 matches >=2.0)"
+    );
+    let location = &xml["locations"][0]["physicalLocation"];
+    assert!(location["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("lib.rs"));
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 9);
+    assert_eq!(xml["properties"]["id"], "drop-xml");
+    assert_eq!(xml["properties"]["tags"], serde_json::json!(["config"]));
+    assert_eq!(xml["properties"]["requirement"], ">=2.0");
+    assert_eq!(xml["properties"]["version"], "2.1.0");
+
+    let old = &results[1];
+    assert_eq!(old["ruleId"], "allow-until/warning");
+    assert_eq!(old["level"], "note");
+    assert_eq!(
+        old["message"]["text"],
+        "fn `old` is allowed until >=3.0 (version 2.1.0)"
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn valid_without_annotations() {
+    let path = sarif_path("empty");
+
+    Fixture::new("allow_until::allow_until_summary!();")
+        .env("ALLOW_UNTIL_SARIF", path.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let log = read(&path);
+    assert_eq!(log["runs"][0]["results"], serde_json::json!([]));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn one_run_per_crate() {
+    let path = sarif_path("crates");
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct A;
+    "#;
+
+    for name in ["first", "second", "first"] {
+        Fixture::new(source)
+            .env("CARGO_CRATE_NAME", name)
+            .env("ALLOW_UNTIL_SARIF", path.to_str().unwrap())
+            .compile()
+            .assert_success();
+    }
+
+    let ids: Vec<_> = read(&path)["runs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["automationDetails"]["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids, ["second", "first"]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn corrupted_log_is_replaced() {
+    let path = sarif_path("corrupted");
+    std::fs::write(&path, "{\"runs\": [{\"tool\"").unwrap();
+
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct A;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct B;
+        "#,
+    )
+    .env("ALLOW_UNTIL_SARIF", path.to_str().unwrap())
+    .compile();
+
+    outcome.assert_success().assert_contains(&format!(
+        "the SARIF log `{}` was not valid SARIF and has been replaced",
+        path.display()
+    ));
+    assert_eq!(outcome.stderr.matches("not valid SARIF").count(), 1);
+    assert_eq!(
+        read(&path)["runs"][0]["results"].as_array().unwrap().len(),
+        2
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", reason = "use `Json`", owner = "platform")]
+    pub struct Xml;
+
+    #[allow_until::allow_until(
+        version = ">=1.8",
+        reason = "superseded | replaced",
+        issue = "https://example.com/issues/7"
+    )]
+    pub fn old_init() {}
+
+    #[allow_until::allow_until(version = "^2.0.0", issue = "CORE-12")]
+    pub const LIMIT: u8 = 1;
+
+    #[allow_until::allow_until(version = "<1.0")]
+    pub static PRE_RELEASE: u8 = 1;
+"#;
+
+const EXPECTED: &str = "\
+# Deprecation schedule for `fixture`
+
+## 0.0.0
+
+| Item | Requirement | Reason | Owner | Issue | Location |
+|------|-------------|--------|-------|-------|----------|
+| static `PRE_RELEASE` | `<1.0` |  |  |  | lib.rs:15 |
+
+## 1.8.0
+
+| Item | Requirement | Reason | Owner | Issue | Location |
+|------|-------------|--------|-------|-------|----------|
+| fn `old_init` | `>=1.8` | superseded \\| replaced |  | [https://example.com/issues/7](https://example.com/issues/7) | lib.rs:5 |
+
+## 2.0.0
+
+| Item | Requirement | Reason | Owner | Issue | Location |
+|------|-------------|--------|-------|-------|----------|
+| struct `Xml` | `>=2.0` | use `Json` | platform |  | lib.rs:2 |
+| const `LIMIT` | `^2.0.0` |  |  | CORE-12 | lib.rs:12 |
+";
+
+#[test]
+fn snapshot() {
+    let outcome = Fixture::new(SOURCE)
+        .manifest("[package.metadata.allow-until]\nissue_patterns = [\"CORE-{number}\"]\n")
+        .env("ALLOW_UNTIL_SCHEDULE", "{crate}-SCHEDULE.md")
+        .version("1.0.0")
+        .compile();
+    outcome.assert_success();
+
+    let schedule = std::fs::read_to_string(outcome.dir.join("fixture-SCHEDULE.md")).unwrap();
+    assert_eq!(schedule, EXPECTED);
+}
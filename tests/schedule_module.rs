@@ -0,0 +1,104 @@
+//! `deprecation_schedule_module!`, which documents the removal plan in rustdoc. The documentation
+//! is read from the expanded source of the fixture.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+mod formats {
+    #[allow_until::allow_until(
+        version = ">=2.0",
+        reason = "use `Json`
+
+            Parse with `Json::from_str` instead, the fields are the same."
+    )]
+    pub struct Xml;
+}
+
+#[allow_until::allow_until(version = ">=1.8", reason = "superseded", issue = "https://example.com/issues/12")]
+pub fn old_init() {}
+
+#[allow_until::allow_until(version = ">=2.0.0")]
+pub const LIMIT: u8 = 1;
+
+allow_until::deprecation_schedule_module!();
+
+#[allow_until::allow_until(version = ">=3.0")]
+pub fn after_the_schedule() {}
+"#;
+
+const EXPECTED: &str = "\
+The removal plan of this crate: the items that will be removed, by the first version without them.
+
+# 1.8.0
+
+- fn `old_init`, `>=1.8`: superseded (https://example.com/issues/12)
+
+# 2.0.0
+
+- struct `Xml`, `>=2.0`: use `Json`
+
+  Parse with `Json::from_str` instead, the fields are the same.
+
+- const `LIMIT`, `>=2.0.0`
+";
+
+/// The documentation of the `deprecation_schedule` module in the expanded source.
+fn doc(expanded: &str) -> String {
+    let start = expanded
+        .find("#[doc =")
+        .unwrap_or_else(|| panic!("no documentation in:\n{}", expanded));
+    let literal = &expanded[start..];
+    let literal = &literal[literal.find('"').unwrap() + 1..literal.find("\"]").unwrap()];
+    assert!(expanded[start..].contains("pub mod deprecation_schedule {"));
+
+    literal
+        .replace("\\n", "\n")
+        .replace("\\\"", "\"")
+        .replace("\\'", "'")
+        .replace("\\\\", "\\")
+}
+
+#[test]
+fn documents_the_schedule() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("-Zunpretty=expanded")
+        .compile();
+
+    outcome.assert_success();
+    assert_eq!(doc(&outcome.stdout), EXPECTED);
+}
+
+#[test]
+fn nothing_scheduled() {
+    let outcome = Fixture::new("allow_until::deprecation_schedule_module!();\n")
+        .rustc_arg("-Zunpretty=expanded")
+        .compile();
+
+    outcome.assert_success();
+    assert_eq!(
+        doc(&outcome.stdout),
+        "The removal plan of this crate: the items that will be removed, by the first version \
+         without them.\n\nNothing is scheduled for removal.\n"
+    );
+}
+
+#[test]
+fn usable_as_a_module() {
+    Fixture::new(&format!("#![warn(missing_docs)]\n//! A crate.\n{}", SOURCE))
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("missing documentation for a function")
+        .assert_not_contains("missing documentation for a module");
+}
+
+#[test]
+fn no_arguments() {
+    Fixture::new("allow_until::deprecation_schedule_module!(docs);\n")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] `deprecation_schedule_module!` takes no arguments");
+}
@@ -0,0 +1,109 @@
+//! `self_test!()`, which turns the annotations into a test that fails once any of them expired.
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::Fixture;
+
+fn source(annotation: &str, strict: bool) -> String {
+    format!(
+        r#"
+        #[allow_until::allow_until({})]
+        pub struct Legacy;
+
+        #[cfg(test)]
+        mod tests {{
+            allow_until::self_test!({});
+        }}
+        "#,
+        annotation,
+        if strict { "strict" } else { "" }
+    )
+}
+
+/// Compiles `fixture` as a test harness and runs it, with what it printed to stdout and stderr.
+fn run(fixture: Fixture) -> (Output, String) {
+    let outcome = fixture
+        .rustc_arg("--test")
+        .rustc_arg("--emit=link")
+        .compile();
+    outcome.assert_success();
+
+    let output = Command::new(outcome.dir.join("fixture")).output().unwrap();
+    let printed = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (output, printed)
+}
+
+#[test]
+fn clean() {
+    let (output, printed) =
+        run(Fixture::new(&source(r#"version = ">=2.0""#, true)).version("1.0.0"));
+
+    assert!(output.status.success(), "{}", printed);
+    assert!(
+        printed.contains("allow-until: 1 annotation(s), 0 in warning window, 0 expired"),
+        "{}",
+        printed
+    );
+    assert!(
+        printed.contains("test tests::allow_until_self_test ... ok"),
+        "{}",
+        printed
+    );
+}
+
+#[test]
+fn warning_window() {
+    let fixture = |strict| {
+        Fixture::new(&source(r#"version = ">=1.5""#, strict))
+            .version("1.4.0")
+            .manifest("[package.metadata.allow-until]\nwarn_ahead = \"1 minor\"\n")
+    };
+
+    let (output, printed) = run(fixture(false));
+    assert!(output.status.success(), "{}", printed);
+    assert!(
+        printed
+            .contains("warning window: struct `Legacy` (`>=1.5` at version 1.4.0) at lib.rs:2:9"),
+        "{}",
+        printed
+    );
+
+    let (output, printed) = run(fixture(true));
+    assert!(!output.status.success(), "{}", printed);
+    assert!(
+        printed.contains("1 annotation(s) expired or in their warning window"),
+        "{}",
+        printed
+    );
+}
+
+#[test]
+fn expired() {
+    let (output, printed) = run(Fixture::new(&source(
+        r#"version = ">=2.0", severity = "warning""#,
+        false,
+    ))
+    .version("2.0.0"));
+
+    assert!(!output.status.success(), "{}", printed);
+    assert!(
+        printed.contains("expired: struct `Legacy` (`>=2.0` at version 2.0.0) at lib.rs:2:9"),
+        "{}",
+        printed
+    );
+    assert!(printed.contains("1 annotation(s) expired"), "{}", printed);
+}
+
+#[test]
+fn invalid() {
+    Fixture::new("allow_until::self_test!(lenient);")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0005] expected `strict` or nothing");
+}
@@ -0,0 +1,107 @@
+use allow_until::AllowUntil;
+use allow_until_runtime::serde_support::{DeprecatedKeys, Value};
+use serde::Deserialize;
+
+#[derive(Deserialize, AllowUntil)]
+#[serde(rename_all = "camelCase")]
+#[allow(unused)]
+struct Renamed {
+    #[allow_until(version = ">=2.0", replacement = "timeout_ms")]
+    request_timeout: Option<u64>,
+    timeout_ms: Option<u64>,
+    #[serde(rename = "log-level", alias = "verbosity")]
+    #[allow_until(version = ">=3.0", reason = "use `RUST_LOG`")]
+    log_level: Option<String>,
+    format: Format,
+}
+
+#[derive(Deserialize, AllowUntil)]
+#[allow(unused)]
+enum Format {
+    #[allow_until(version = ">=2.0")]
+    Xml,
+    Json,
+}
+
+#[derive(Deserialize, AllowUntil)]
+#[allow(unused)]
+struct Server {
+    host: String,
+    #[allow_until(version = ">=2.0", reason = "in seconds")]
+    timeout: Option<u64>,
+}
+
+#[derive(Deserialize, AllowUntil)]
+#[allow(unused)]
+struct Retries {
+    retries: u32,
+    #[allow_until(version = ">=2.0")]
+    legacy_tls: bool,
+}
+
+#[derive(Deserialize, AllowUntil)]
+#[allow(unused)]
+struct Nested {
+    server: Server,
+    mirrors: Vec<Server>,
+    #[serde(flatten)]
+    retries: Retries,
+}
+
+// whether `T` derives `AllowUntil` can't be told in the derive, so `value` isn't checked
+#[derive(Deserialize, AllowUntil)]
+#[allow(unused)]
+struct Generic<T> {
+    value: T,
+    #[allow_until(version = ">=2.0")]
+    fallback: Option<T>,
+}
+
+fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/serde/{}", env!("CARGO_MANIFEST_DIR"), name);
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn paths(uses: &[allow_until_runtime::serde_support::DeprecatedKeyUse]) -> Vec<&str> {
+    uses.iter().map(|key| key.path.as_str()).collect()
+}
+
+#[test]
+fn renamed() {
+    let input = fixture("renamed.json");
+    // the fixture deserializes, so the keys are those serde reads
+    serde_json::from_value::<Renamed>(input.clone()).unwrap();
+
+    let uses = Renamed::check_deprecated_keys(&input);
+    assert_eq!(paths(&uses), ["requestTimeout", "log-level", "format.Xml"]);
+    assert_eq!(uses[0].member.name, "request_timeout");
+    assert_eq!(uses[0].member.replacement, Some("timeout_ms"));
+    assert_eq!(uses[1].member.requirement, ">=3.0");
+    assert_eq!(uses[1].member.reason, Some("use `RUST_LOG`"));
+
+    let aliased: Value =
+        serde_json::from_str(r#"{ "verbosity": "debug", "format": "Json" }"#).unwrap();
+    assert_eq!(
+        paths(&Renamed::check_deprecated_keys(&aliased)),
+        ["verbosity"]
+    );
+}
+
+#[test]
+fn nested() {
+    let input = fixture("nested.json");
+    serde_json::from_value::<Nested>(input.clone()).unwrap();
+
+    let uses = Nested::check_deprecated_keys(&input);
+    assert_eq!(
+        paths(&uses),
+        ["server.timeout", "mirrors[1].timeout", "legacy_tls"]
+    );
+    assert_eq!(uses[0].member.reason, Some("in seconds"));
+
+    let generic = serde_json::json!({ "value": input["server"], "fallback": null });
+    assert_eq!(
+        paths(&Generic::<Server>::check_deprecated_keys(&generic)),
+        ["fallback"]
+    );
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=1.0", id = "inventory-xml", silent = true)]
+    pub struct XmlConfig;
+
+    #[derive(allow_until::AllowUntil)]
+    pub struct Options {
+        #[allow_until(version = ">=1.0", silent = true)]
+        pub legacy: bool,
+    }
+"#;
+
+#[test]
+fn recorded_but_not_reported() {
+    let dir = std::env::temp_dir().join(format!("allow-until-silent-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (report, sarif) = (dir.join("report.jsonl"), dir.join("report.sarif"));
+    let _ = std::fs::remove_file(&report);
+    let _ = std::fs::remove_file(&sarif);
+
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .env("ALLOW_UNTIL_SARIF", sarif.to_str().unwrap())
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_success()
+        .assert_not_contains("not allowed!")
+        .assert_not_contains("[AU00");
+
+    let entries: Vec<Value> = std::fs::read_to_string(&report)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["id"], "inventory-xml");
+    assert_eq!(entries[0]["matched"], true);
+    assert_eq!(entries[0]["silent"], true);
+    assert_eq!(entries[1]["item"], "Options::legacy");
+    assert_eq!(entries[1]["silent"], true);
+
+    let log: Value = serde_json::from_str(&std::fs::read_to_string(&sarif).unwrap()).unwrap();
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["properties"]["id"], "inventory-xml");
+    assert_eq!(results[0]["properties"]["silent"], true);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn lints_are_silenced() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = "*", silent = true)]
+        pub struct Forever;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("[AU00");
+}
+
+#[test]
+fn other_annotations_are_reported() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", silent = true)]
+        pub struct Quiet;
+
+        #[allow_until::allow_until(version = ">=1.0", silent = false)]
+        pub struct Loud;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Loud` not allowed!")
+    .assert_not_contains("`Quiet`");
+}
+
+#[test]
+fn crate_wide_messages_are_left_to_reported_annotations() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", silent = true)]
+        pub struct Quiet;
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Later;
+        "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_MAX", "lots")
+    .compile()
+    .assert_success()
+    .assert_contains("[AU0009]");
+}
+
+#[test]
+fn contradicts_severity() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0", severity = "warning", silent = true)]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `silent` can't be combined with `severity`")
+    .assert_contains("help: silent annotations are never reported, remove `severity` or `silent`");
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=1.8")]
+    pub struct Old;
+
+    #[allow_until::allow_until(version = ">=2.0", reason = "use `New` instead")]
+    pub struct Older;
+"#;
+
+#[test]
+fn two_versions() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_SIMULATE", "1.8.0, 2.0.0")
+        .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0011] [simulated 1.8.0] struct `Old` not allowed! (version 1.8.0 matches >=1.8)",
+        )
+        .assert_contains(
+            "warning: [AU0011] [simulated 2.0.0] struct `Old` not allowed! (version 2.0.0 matches >=1.8)",
+        )
+        .assert_contains(
+            "warning: [AU0011] [simulated 2.0.0] use `New` instead (version 2.0.0 matches >=2.0)",
+        )
+        .assert_not_contains("[simulated 1.8.0] use `New` instead");
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("warning: [AU0011] [simulated")
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn real_version_still_enforced() {
+    Fixture::new(SOURCE)
+        .version("1.9.0")
+        .env("ALLOW_UNTIL_SIMULATE", "2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Old` not allowed! (version 1.9.0 matches >=1.8)")
+        .assert_contains("warning: [AU0011] [simulated 2.0.0] use `New` instead");
+}
+
+#[test]
+fn invalid_entries() {
+    let outcome = Fixture::new(SOURCE)
+        .env("ALLOW_UNTIL_SIMULATE", "2.0,1.8.0,next")
+        .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains("ignoring invalid `ALLOW_UNTIL_SIMULATE` versions: `2.0`, `next`")
+        .assert_contains("[simulated 1.8.0] struct `Old`");
+    assert_eq!(outcome.stderr.matches("ignoring invalid").count(), 1);
+}
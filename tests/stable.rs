@@ -0,0 +1,221 @@
+//! The `compile_error!` and deprecation warning fallback used without the `nightly` feature,
+//! compiled with the stable toolchain.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn expired_is_a_compile_error() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", owner = "core-team")]
+        pub struct Legacy;
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] struct `Legacy` not allowed! (version 2.0.0 matches >=2.0)")
+    .assert_contains("= note: removal scheduled here (line 2, column 9)")
+    .assert_contains("= note: owner: core-team")
+    .assert_contains("lib.rs:3:20");
+}
+
+#[test]
+fn not_expired() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .stable()
+    .version("1.0.0")
+    .compile()
+    .assert_success()
+    .assert_not_contains("warning");
+}
+
+#[test]
+fn warnings_are_deprecation_warnings() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        pub struct Legacy;
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("struct `Legacy` not allowed! (version 2.0.0 matches >=2.0)")
+    .assert_contains("#[warn(deprecated)]");
+}
+
+#[test]
+fn module_level_items_without_a_name() {
+    // impl blocks, `use` and `macro_rules!` are only ever among items, next to the deprecated item
+    let outcome = Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        impl Clone for Client {
+            fn clone(&self) -> Self { Client }
+        }
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        #[allow(unused_imports)]
+        use std::fmt;
+
+        #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+        macro_rules! legacy { () => {}; }
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile();
+
+    outcome
+        .assert_success()
+        .assert_contains("item not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_contains("#[warn(deprecated)]");
+    assert_eq!(outcome.stderr.matches("item not allowed!").count(), 3);
+}
+
+#[test]
+fn associated_items() {
+    Fixture::new(
+        r#"
+        pub struct Config;
+
+        impl Config {
+            #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+            pub fn reload(&self) {
+                #![allow(unused_variables)]
+                let unused = 1;
+            }
+
+            #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+            pub const LIMIT: u8 = 1;
+        }
+
+        pub trait Reload {
+            #[allow_until::allow_until(version = ">=2.0", severity = "warning")]
+            fn reload(&self);
+        }
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile()
+    .assert_success()
+    .assert_contains("fn `reload` not allowed!")
+    .assert_not_contains("unused variable");
+
+    Fixture::new(
+        r#"
+        pub trait Reload {
+            #[allow_until::allow_until(version = ">=2.0")]
+            fn reload(&self);
+        }
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] fn `reload` not allowed!");
+}
+
+#[test]
+fn derive_members() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=2.0", reason = "use `name`")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] use `name` (version 2.0.0 matches >=2.0)")
+    .assert_contains("= note: field `Options::legacy` must be removed")
+    .assert_contains("= note: removal scheduled here (line 4, column 13)")
+    .assert_contains("lib.rs:5:17");
+}
+
+#[test]
+fn argument_errors() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", colour = "red")]
+        pub fn legacy() {}
+        "#,
+    )
+    .stable()
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown argument")
+    .assert_contains("= help: valid arguments are");
+}
+
+#[test]
+fn explain() {
+    Fixture::new(
+        r#"
+        pub const EXPLANATION: &str = allow_until::explain!("AU0001");
+        pub const UNKNOWN: &str = allow_until::explain!("AU9999");
+        "#,
+    )
+    .stable()
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] unknown diagnostic code `AU9999`")
+    .assert_contains("aborting due to 1 previous error");
+}
+
+#[test]
+fn invalid_requirement_spans_the_literal() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0 <2")]
+        pub struct Legacy;
+        "#,
+    )
+    .stable()
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "error: [AU0002] invalid semver requirement `>=1.0 <2`: expected comma after minor \
+         version number, found '<'",
+    )
+    .assert_contains("lib.rs:2:46");
+}
+
+#[test]
+fn multi_line_reason() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", reason = "legacy mode is gone\nuse `modern`\nsee the changelog")]
+        pub struct Legacy;
+        "#,
+    )
+    .stable()
+    .version("2.0.0")
+    .compile();
+    outcome
+        .assert_failure()
+        .assert_contains("error: [AU0001] legacy mode is gone (version 2.0.0 matches >=2.0)");
+    assert!(outcome
+        .normalized_stderr()
+        .contains("       = note: use `modern`\n               see the changelog\n"));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::Fixture;
+
+fn many_annotations(n: usize) -> String {
+    let mut source = String::new();
+
+    for i in 0..n {
+        source.push_str(&format!(
+            "#[allow_until::allow_until(version = \">=2.0\")]\npub struct Item{i};\n"
+        ));
+    }
+
+    source.push_str("#[derive(allow_until::AllowUntil)]\npub struct Fields {\n");
+    for i in 0..n {
+        source.push_str(&format!(
+            "    #[allow_until(version = \">=2.0\")]\n    pub field{i}: u8,\n"
+        ));
+    }
+    source.push_str("}\n");
+
+    source
+}
+
+#[test]
+fn thousands_of_annotations() {
+    let start = std::time::Instant::now();
+
+    Fixture::new(&many_annotations(2000))
+        .version("1.0.0")
+        .compile()
+        .assert_success();
+
+    assert!(start.elapsed().as_secs() < 60, "took {:?}", start.elapsed());
+}
+
+#[test]
+fn unresolvable_version_reported_once() {
+    let outcome = Fixture::new(&many_annotations(2000))
+        .version("not-a-version")
+        .compile();
+
+    outcome.assert_failure();
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("is not a valid semver version")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn manifest_read_once() {
+    let mut source = many_annotations(2000);
+    source.push_str("allow_until::allow_until_summary!();\n");
+
+    Fixture::new(&source)
+        .manifest("[package.metadata.allow-until]\nowner = \"core\"\n")
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_TRACE", "1")
+        .compile()
+        .assert_success()
+        .assert_contains("allow_until trace: 1 manifest read(s) in this compilation");
+}
+
+#[test]
+fn report_lock_contention() {
+    const CRATES: usize = 8;
+    const ANNOTATIONS: usize = 50;
+
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-stress-lock-{}.jsonl",
+        std::process::id()
+    ));
+    let lock = report.with_extension("jsonl.lock");
+    let _ = std::fs::remove_file(&report);
+
+    // left behind by a crashed build, which holds no lock any more
+    let file = std::fs::File::create(&lock).unwrap();
+    file.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(60))
+        .unwrap();
+    drop(file);
+
+    // a build holding the lock makes the others wait, however long it takes
+    let held = std::fs::File::options().write(true).open(&lock).unwrap();
+    held.lock().unwrap();
+
+    let writers: Vec<_> = (0..CRATES)
+        .map(|c| {
+            let report = report.clone();
+            std::thread::spawn(move || {
+                let source: String = (0..ANNOTATIONS)
+                    .map(|a| {
+                        format!(
+                            "#[allow_until::allow_until(version = \">=2.0\", id = \"c{}-a{}\")]\n\
+                             pub struct Item{};\n",
+                            c, a, a
+                        )
+                    })
+                    .collect();
+                Fixture::new(&source)
+                    .version("1.0.0")
+                    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+                    .compile()
+                    .assert_success();
+            })
+        })
+        .collect();
+
+    std::thread::sleep(std::time::Duration::from_secs(12));
+    assert!(!report.exists(), "written while the lock was held");
+    held.unlock().unwrap();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // every line is a whole entry, and none is missing or duplicated
+    let mut ids: Vec<String> = std::fs::read_to_string(&report)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+            entry["id"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(ids.len(), CRATES * ANNOTATIONS);
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), CRATES * ANNOTATIONS);
+
+    let _ = std::fs::remove_file(&report);
+    let _ = std::fs::remove_file(&lock);
+}
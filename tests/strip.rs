@@ -0,0 +1,166 @@
+//! `ALLOW_UNTIL_STRIP=1`, which leaves the items of expired annotations out of the expansion to
+//! preview their removal.
+
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+
+    #[allow_until::allow_until(version = ">=3.0")]
+    pub struct Current;
+"#;
+
+#[test]
+fn expansion() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_STRIP", "1")
+        .rustc_arg("-Zunpretty=expanded")
+        .compile();
+    outcome
+        .assert_success()
+        .assert_not_contains("error: [AU0001]");
+    assert!(
+        !outcome.stdout.contains("struct Legacy"),
+        "{}",
+        outcome.stdout
+    );
+    assert!(
+        outcome.stdout.contains("pub struct Current;"),
+        "{}",
+        outcome.stdout
+    );
+}
+
+#[test]
+fn dependents_break() {
+    let outcome = Fixture::new(&format!(
+        "{}\n{}",
+        SOURCE,
+        r#"
+        pub fn build() -> Legacy { Legacy }
+        "#
+    ))
+    .version("2.0.0")
+    .env("ALLOW_UNTIL_STRIP", "1")
+    .compile();
+    outcome
+        .assert_failure()
+        .assert_not_contains("error: [AU0001]")
+        .assert_contains("note: [AU0001] struct `Legacy` is stripped to preview its removal")
+        .assert_contains("cannot find type `Legacy` in this scope");
+
+    // helpers only the removed items used are left unused
+    Fixture::new(
+        r#"
+        fn helper() -> u32 { 1 }
+
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub fn legacy() -> u32 { helper() }
+        "#,
+    )
+    .version("2.0.0")
+    .env("ALLOW_UNTIL_STRIP", "1")
+    .compile()
+    .assert_success()
+    .assert_contains("fn `legacy` is stripped to preview its removal")
+    .assert_contains("function `helper` is never used");
+
+    // the error as usual without the variable
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `Legacy` not allowed!");
+}
+
+#[test]
+fn groups_and_members() {
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        #[allow_until::allow_members_until(version = ">=2.0", members = ["connect_tls"])]
+        impl Client {
+            pub fn connect_tls(&self) {}
+            pub fn connect(&self) {}
+        }
+
+        allow_until::allow_until_group! {
+            version = ">=2.0",
+            {
+                pub fn old_a() {}
+                pub fn old_b() {}
+            }
+        }
+
+        pub fn run(client: Client) {
+            client.connect();
+            client.connect_tls();
+            old_a();
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .env("ALLOW_UNTIL_STRIP", "1")
+    .compile()
+    .assert_failure()
+    .assert_contains("no method named `connect_tls` found")
+    .assert_contains("cannot find function `old_a` in this scope")
+    .assert_not_contains("no method named `connect` found");
+}
+
+#[test]
+fn unsupported() {
+    Fixture::new(
+        r#"
+        #[derive(allow_until::AllowUntil)]
+        pub struct Options {
+            #[allow_until(version = ">=2.0")]
+            pub legacy: bool,
+        }
+        "#,
+    )
+    .version("2.0.0")
+    .env("ALLOW_UNTIL_STRIP", "1")
+    .compile()
+    .assert_failure()
+    .assert_contains(
+        "warning: [AU0050] field `Options::legacy` can't be stripped by `ALLOW_UNTIL_STRIP=1`",
+    )
+    .assert_contains(
+        "the derive can't remove fields, so the expired annotation is reported as usual",
+    )
+    .assert_contains("error: [AU0001] field `Options::legacy` not allowed!");
+}
+
+#[test]
+fn report() {
+    let report = std::env::temp_dir().join(format!(
+        "allow-until-strip-report-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_STRIP", "1")
+        .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+        .compile()
+        .assert_success();
+
+    let entries: Vec<serde_json::Value> = std::fs::read_to_string(&report)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let stripped: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry["item"].as_str().unwrap(), entry["stripped"] == true))
+        .collect();
+    assert_eq!(stripped, [("Legacy", true), ("Current", false)]);
+    let _ = std::fs::remove_file(&report);
+}
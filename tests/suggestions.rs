@@ -0,0 +1,58 @@
+//! The suggestions of expired annotations: no help to remove or extend items generated by a macro,
+//! where the code to change isn't the annotated item, and no help to extend deadlines that are not
+//! written in the annotation.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn macro_generated() {
+    let outcome = Fixture::new(
+        r#"macro_rules! legacy {
+    ($name:ident) => {
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct $name;
+    };
+}
+
+legacy!(Legacy);
+"#,
+    )
+    .version("2.0.0")
+    .compile();
+
+    outcome
+        .assert_failure()
+        .assert_not_contains("help: remove")
+        .assert_not_contains("help: extend the deadline")
+        .assert_contains("note: originating from this macro invocation")
+        .assert_contains("lib.rs:8:1");
+}
+
+#[test]
+fn deadline_not_in_the_annotation() {
+    Fixture::new(
+        r#"#[allow_until::allow_until(migration = "drop-xml")]
+pub struct Xml;
+
+#[allow_until::allow_until]
+pub struct Yaml;
+
+#[allow_until::allow_until(version = ">=1.0, <2.1")]
+pub struct Toml;
+"#,
+    )
+    .manifest(
+        "[package.metadata.allow-until]\ndefault_version = \">=2.0\"\n\n\
+         [package.metadata.allow-until.migrations.drop-xml]\nversion = \">=2.0\"\n",
+    )
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("help: remove the expired struct `Xml`")
+    .assert_contains("help: remove the expired struct `Yaml`")
+    // the upper bound excludes the extended deadline
+    .assert_contains("help: remove the expired struct `Toml`")
+    .assert_not_contains("help: extend the deadline");
+}
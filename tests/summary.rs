@@ -0,0 +1,64 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn overview() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub struct Later;
+
+        #[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+        pub struct XmlConfig;
+
+        #[allow_until::allow_until(version = ">=2.0", id = "old-tls-shim")]
+        pub struct TlsShim;
+
+        #[allow_until::allow_until(version = ">=1.4", grace = "1 minor")]
+        pub struct Grace;
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("1.4.2")
+    .compile();
+
+    outcome.assert_success().assert_contains(
+        "note: [AU0018] allow-until: 3 active allowances, 1 in warning window, next expiry at 2.0.0 \
+         (id drop-xml-config)",
+    );
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("note: [AU0018] allow-until:")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn next_expiry_without_id() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=1.0")]
+        pub fn single() {}
+
+        allow_until::allow_until_summary!();
+        "#,
+    )
+    .version("0.9.0")
+    .compile()
+    .assert_contains(
+        "note: [AU0018] allow-until: 1 active allowance, 0 in warning window, next expiry at 1.0.0 \
+         (fn `single`)",
+    );
+}
+
+#[test]
+fn silent_without_annotations() {
+    Fixture::new("pub struct Plain; allow_until::allow_until_summary!();")
+        .compile()
+        .assert_success()
+        .assert_not_contains("allow-until:");
+}
@@ -0,0 +1,98 @@
+//! `superseded_by_feature`, which points users of an expired item to the feature it moved behind.
+
+mod common;
+
+use common::Fixture;
+
+const MANIFEST: &str = r#"
+[package]
+name = "fixture"
+version = "0.1.0"
+
+[features]
+default = []
+async-client = ["dep:tokio"]
+
+[dependencies]
+tokio = { version = "1", optional = true }
+serde = { version = "1", optional = true }
+"#;
+
+#[test]
+fn help_on_expiry() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", superseded_by_feature = "async-client")]
+        pub fn connect() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] fn `connect` not allowed!")
+    .assert_contains(
+        "help: the functionality moved behind the `async-client` feature, enable it with \
+         `features = [\"async-client\"]`",
+    );
+}
+
+#[test]
+fn optional_dependencies() {
+    // `serde` is a feature of its own, `tokio` isn't as `async-client` refers to it with `dep:`
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", superseded_by_feature = "serde")]
+        pub fn to_json() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_success();
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", superseded_by_feature = "tokio")]
+        pub fn connect() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("the package has no feature `tokio`");
+}
+
+#[test]
+fn unknown_feature() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", superseded_by_feature = "async-clinet")]
+        pub fn connect() {}
+        "#,
+    )
+    .manifest(MANIFEST)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] the package has no feature `async-clinet`")
+    .assert_contains("lib.rs:2:79")
+    .assert_contains(
+        "help: did you mean `async-client`? the features are `async-client`, `default`, `serde`",
+    );
+}
+
+#[test]
+fn no_features() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", superseded_by_feature = "async-client")]
+        pub fn connect() {}
+        "#,
+    )
+    .manifest("[package]\nname = \"fixture\"\nversion = \"0.1.0\"\n")
+    .compile()
+    .assert_failure()
+    .assert_contains("the package has no features, add it to `[features]` in `Cargo.toml`");
+}
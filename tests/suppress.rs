@@ -0,0 +1,91 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", id = "drop-xml-config")]
+    pub struct XmlConfig;
+
+    #[allow_until::allow_until(version = ">=2.0", id = "old-tls-shim")]
+    pub struct TlsShim;
+
+    pub mod inner {
+        #[allow_until::allow_until(version = ">=2.0", id = "old-tls-shim-fn")]
+        pub fn tls_shim() {}
+    }
+
+    allow_until::allow_until_summary!();
+"#;
+
+#[test]
+fn matched_ids() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env(
+            "ALLOW_UNTIL_SUPPRESS",
+            "drop-xml-config, old-tls-shim, old-tls-shim-fn",
+        )
+        .compile();
+
+    outcome
+        .assert_success()
+        .assert_not_contains("not allowed!")
+        .assert_contains(
+            "`ALLOW_UNTIL_SUPPRESS` suppressed 3 expired annotation(s): `drop-xml-config`, `old-tls-shim`, `old-tls-shim-fn`",
+        )
+        .assert_not_contains("match no annotation");
+    assert_eq!(outcome.stderr.matches("warning: [AU").count(), 1);
+}
+
+#[test]
+fn unmatched_ids() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_SUPPRESS", "drop-yaml-config")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "`ALLOW_UNTIL_SUPPRESS` lists ids that match no annotation: `drop-yaml-config`",
+        )
+        .assert_not_contains("suppressed");
+}
+
+#[test]
+fn mixed_ids() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_SUPPRESS", "old-tls-shim,old-tls-shim-fn,gone")
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `XmlConfig` not allowed!")
+        .assert_not_contains("struct `TlsShim` not allowed!")
+        .assert_not_contains("fn `tls_shim` not allowed!")
+        .assert_contains("suppressed 2 expired annotation(s): `old-tls-shim`, `old-tls-shim-fn`")
+        .assert_contains("match no annotation: `gone`");
+}
+
+#[test]
+fn unset() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("warning:");
+}
+
+#[test]
+fn warn_ahead() {
+    let outcome = Fixture::new(SOURCE)
+        .manifest("[package.metadata.allow-until]\nwarn_ahead = \"1 major\"\n")
+        .version("1.9.0")
+        .env("ALLOW_UNTIL_SUPPRESS", "drop-xml-config")
+        .compile();
+
+    // its expiry ahead isn't warned about either, and it isn't counted as suppressed yet
+    outcome
+        .assert_success()
+        .assert_not_contains("struct `XmlConfig` expires at")
+        .assert_contains("warning: [AU0025] struct `TlsShim` expires at version 2.0.0")
+        .assert_not_contains("suppressed")
+        .assert_not_contains("match no annotation");
+}
@@ -1,29 +1,53 @@
 use allow_until::{allow_until as allow_until_attr, AllowUntil};
 
+#[allow(unused)]
+#[derive(AllowUntil)]
+struct Foo {
+    #[allow_until(version = ">=1.0.x", replacement = "b")]
+    a: usize,
+    b: usize,
+}
+
+#[allow(unused)]
+#[derive(AllowUntil)]
+enum FooBar {
+    #[allow_until(version = ">=1.0.x")]
+    A,
+    B(#[allow_until(version = ">=1.0.x")] usize),
+    C {
+        #[allow_until(version = ">=1.0.x", reason = "for fun!")]
+        bar: bool,
+    },
+}
+
 #[allow(unused)]
 #[test]
 fn test() {
-    #[derive(AllowUntil)]
-    struct Foo {
-        #[allow_until(version = ">=1.0.x")]
-        a: usize,
-        b: usize,
-    }
-
     #[allow_until_attr(version = ">=1.0.x", reason = "for fun!")]
     struct Bar {
         a: usize,
         b: usize,
     }
+}
 
-    #[derive(AllowUntil)]
-    enum FooBar {
-        #[allow_until(version = ">=1.0.x")]
-        A,
-        B(#[allow_until(version = ">=1.0.x")] usize),
-        C {
-            #[allow_until(version = ">=1.0.x")]
-            bar: bool,
-        },
-    }
+#[test]
+fn runtime_metadata() {
+    use allow_until_runtime::{AllowUntilMetadata, MemberDeprecation};
+
+    assert_eq!(
+        Foo::deprecated_members(),
+        [MemberDeprecation {
+            name: "a",
+            requirement: ">=1.0",
+            reason: None,
+            replacement: Some("b"),
+        }]
+    );
+
+    let names: Vec<_> = FooBar::deprecated_members()
+        .iter()
+        .map(|member| member.name)
+        .collect();
+    assert_eq!(names, ["A", "B::0", "C::bar"]);
+    assert_eq!(FooBar::deprecated_members()[2].reason, Some("for fun!"));
 }
@@ -0,0 +1,137 @@
+mod common;
+
+use std::process::Command;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", reason = "use `Json`", test_guard = true)]
+pub struct XmlConfig;
+
+#[derive(allow_until::AllowUntil)]
+pub struct Options {
+    #[allow_until(
+        version = ">=2.0",
+        issue = "https://example.com/issues/7",
+        test_guard = true
+    )]
+    pub legacy_mode: bool,
+}
+
+#[allow_until::allow_until(version = ">=3.0", test_guard = true)]
+pub fn later() {}
+"#;
+
+#[test]
+fn build_succeeds() {
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("[AU0001]");
+}
+
+#[test]
+fn generated_tests_fail() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .rustc_arg("--test")
+        .rustc_arg("--emit=link")
+        .compile();
+    outcome.assert_success();
+
+    let output = Command::new(outcome.dir.join("fixture"))
+        .arg("--test-threads=1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("running 2 tests"), "{}", stdout);
+    assert!(
+        stdout.contains("test allow_until_struct_xml_config_2 ... FAILED"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("test allow_until_field_options_legacy_mode_7 ... FAILED"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("use `Json` (version 2.0.0 matches >=2.0)"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "field `Options::legacy_mode` not allowed! (version 2.0.0 matches >=2.0)\n\
+             tracking issue: https://example.com/issues/7"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn gated_on_cfg_test() {
+    let outcome = Fixture::new(SOURCE)
+        .version("2.0.0")
+        .rustc_arg("-Zunpretty=expanded")
+        .rustc_arg("-o")
+        .rustc_arg("expanded.rs")
+        .compile();
+    outcome.assert_success();
+
+    let expanded = std::fs::read_to_string(outcome.dir.join("expanded.rs")).unwrap();
+    assert!(expanded.contains("pub struct XmlConfig;"), "{}", expanded);
+    assert!(!expanded.contains("fn allow_until_"), "{}", expanded);
+}
+
+#[test]
+fn not_expired() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("-Zunpretty=expanded")
+        .rustc_arg("-o")
+        .rustc_arg("expanded.rs")
+        .compile();
+    outcome.assert_success();
+
+    let expanded = std::fs::read_to_string(outcome.dir.join("expanded.rs")).unwrap();
+    assert!(!expanded.contains("fn allow_until_"), "{}", expanded);
+}
+
+#[test]
+fn contradicts_error_severity() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", severity = "error", test_guard = true)]
+        pub struct Legacy;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `test_guard` can't be combined with `severity = \"error\"`")
+    .assert_contains(
+        "help: the generated test replaces the error, remove `severity` or `test_guard`",
+    );
+}
+
+#[test]
+fn associated_items() {
+    Fixture::new(
+        r#"
+        pub struct Client;
+
+        impl Client {
+            #[allow_until::allow_until(version = ">=2.0", test_guard = true)]
+            pub fn connect(&self) {}
+        }
+        "#,
+    )
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `test_guard` can't be used on fn `connect`");
+}
@@ -0,0 +1,162 @@
+//! `tool` and `tool_version`, which condition an annotation on the version of an external tool.
+
+mod common;
+
+use common::Fixture;
+
+/// A stand-in for `protoc` printing `output` for `--version`.
+#[cfg(unix)]
+fn protoc(name: &str, output: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.display().to_string()
+}
+
+const SHIM: &str = r#"
+    #[allow_until::allow_until(tool = "protoc", tool_version = ">=25")]
+    pub fn shim() {}
+"#;
+
+#[test]
+#[cfg(unix)]
+fn from_the_tool_version() {
+    Fixture::new(SHIM)
+        .env(
+            "ALLOW_UNTIL_TOOL_PROTOC",
+            &protoc("protoc-25", "libprotoc 25.1"),
+        )
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] fn `shim` not allowed! (protoc 25.1.0 matches >=25)")
+        .assert_contains("the `tool_version` requirement `>=25` matches `protoc` 25.1.0")
+        .assert_not_contains("the requirement `*`");
+}
+
+#[test]
+#[cfg(unix)]
+fn before_the_tool_version() {
+    Fixture::new(SHIM)
+        .env(
+            "ALLOW_UNTIL_TOOL_PROTOC",
+            &protoc("protoc-24", "libprotoc 24.4"),
+        )
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+#[cfg(unix)]
+fn every_condition_must_be_satisfied() {
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0", tool = "protoc", tool_version = ">=25")]
+        pub fn shim() {}
+    "#;
+    let protoc = protoc("protoc-25-both", "libprotoc 25.1");
+
+    Fixture::new(source)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_TOOL_PROTOC", &protoc)
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+    Fixture::new(source)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_TOOL_PROTOC", &protoc)
+        .compile()
+        .assert_failure()
+        .assert_contains("fn `shim` not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_contains("the `tool_version` requirement `>=25` matches `protoc` 25.1.0");
+}
+
+#[test]
+#[cfg(unix)]
+fn no_version_printed() {
+    Fixture::new(SHIM)
+        .env(
+            "ALLOW_UNTIL_TOOL_PROTOC",
+            &protoc("protoc-odd", "protoc, unreleased"),
+        )
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0048] the version of `protoc` is unknown")
+        .assert_contains("--version` printed no version: `protoc, unreleased`");
+}
+
+#[test]
+fn from_path() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(tool = "rustc", tool_version = ">=1.0")]
+        pub fn old() {}
+        #[allow_until::allow_until(tool = "rustc", tool_version = "<1.0")]
+        pub fn new() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("fn `old` not allowed! (rustc 1.")
+    .assert_not_contains("fn `new` not allowed!")
+    .assert_not_contains("AU0048");
+}
+
+#[test]
+fn missing_tool_warns_once() {
+    let outcome = Fixture::new(
+        r#"
+        #[allow_until::allow_until(tool = "allow-until-missing-tool", tool_version = ">=1")]
+        pub fn a() {}
+        #[allow_until::allow_until(tool = "allow-until-missing-tool", tool_version = ">=2")]
+        pub fn b() {}
+        "#,
+    )
+    .compile();
+    outcome
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0048] the version of `allow-until-missing-tool` is unknown, annotations \
+             conditioned on it don't fire: `allow-until-missing-tool` was not found on `PATH`",
+        )
+        .assert_contains(
+            "install `allow-until-missing-tool` on `PATH`, or set \
+             `ALLOW_UNTIL_TOOL_ALLOW_UNTIL_MISSING_TOOL` to its binary",
+        )
+        .assert_not_contains("AU0001");
+    assert_eq!(outcome.stderr.matches("[AU0048]").count(), 1);
+}
+
+#[test]
+fn arguments_go_together() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", tool = "protoc")]
+        pub fn shim() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0007] `tool` is given without the `tool_version` of `protoc`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(tool_version = ">=25")]
+        pub fn shim() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] `tool_version` is given without `tool`");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(tool = "bin/protoc", tool_version = ">=25")]
+        pub fn shim() {}
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0005] invalid tool name `bin/protoc`");
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn explains_mixed_conditions() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", grace = "1 minor", severity = "warning")]
+        pub struct Legacy;
+    "#,
+    )
+    .version("2.0.3")
+    .env("ALLOW_UNTIL_TRACE", "1")
+    .env("ALLOW_UNTIL_DENY", "1")
+    .compile()
+    .assert_failure()
+    .assert_contains("note: [AU0019] allow_until trace")
+    .assert_contains("= note: version 2.0.3 from `CARGO_PKG_VERSION`")
+    .assert_contains("= note: requirement `>=2.0` matches")
+    .assert_contains("= note: severity: warning")
+    .assert_contains("= note: within the 1 minor grace period ending at 2.1.0: warning")
+    .assert_contains("= note: `ALLOW_UNTIL_DENY` escalates the warning: error")
+    .assert_contains("= note: decision: reported as an error");
+}
+
+#[test]
+fn explains_unmatched() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_TRACE", "1")
+    .env("ALLOW_UNTIL_VERSION", "1.5.0")
+    .compile()
+    .assert_success()
+    .assert_contains("= note: version 1.5.0 from the `ALLOW_UNTIL_VERSION` override")
+    .assert_contains("= note: requirement `>=2.0` does not match")
+    .assert_contains("= note: decision: not reported");
+}
+
+#[test]
+fn off_by_default() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#,
+    )
+    .compile()
+    .assert_success()
+    .assert_not_contains("trace");
+}
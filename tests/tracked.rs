@@ -0,0 +1,80 @@
+//! The inputs the expansion depends on are declared to the compiler, so changing them, such as
+//! bumping the version, re-expands the macros. They are read from the dep-info of the fixture,
+//! which Cargo compares to decide whether to recompile.
+
+mod common;
+
+use common::{Fixture, Outcome};
+
+const SOURCE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0")]
+pub struct Legacy;
+"#;
+
+fn dep_info(outcome: &Outcome) -> String {
+    std::fs::read_to_string(outcome.dir.join("fixture.d")).unwrap()
+}
+
+#[test]
+fn version() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("--emit=dep-info")
+        .compile();
+
+    outcome.assert_success();
+    let dep_info = dep_info(&outcome);
+    assert!(
+        dep_info.contains("# env-dep:CARGO_PKG_VERSION=1.0.0"),
+        "{}",
+        dep_info
+    );
+    // unset variables are inputs too, setting them changes the expansion
+    assert!(
+        dep_info.contains("# env-dep:ALLOW_UNTIL_VERSION\n"),
+        "{}",
+        dep_info
+    );
+    assert!(
+        dep_info.contains("# env-dep:ALLOW_UNTIL_DOWNGRADE\n"),
+        "{}",
+        dep_info
+    );
+}
+
+#[test]
+fn manifest() {
+    let outcome = Fixture::new(SOURCE)
+        .manifest("[package.metadata.allow-until]\nalways_note = true\n")
+        .version("1.0.0")
+        .rustc_arg("--emit=dep-info")
+        .compile();
+
+    outcome.assert_success();
+    let dep_info = dep_info(&outcome);
+    let manifest = outcome.dir.join("Cargo.toml").display().to_string();
+    assert!(dep_info.contains(&manifest), "{}", dep_info);
+}
+
+#[test]
+fn missing_files_are_not_declared() {
+    let outcome = Fixture::new(SOURCE)
+        .version("1.0.0")
+        .rustc_arg("--emit=dep-info")
+        .compile();
+
+    outcome.assert_success();
+    assert!(!dep_info(&outcome).contains("Cargo.toml"));
+}
+
+#[test]
+fn stable() {
+    let outcome = Fixture::new(SOURCE)
+        .stable()
+        .version("1.0.0")
+        .rustc_arg("--emit=dep-info")
+        .compile();
+
+    outcome.assert_success();
+    assert!(!dep_info(&outcome).contains("CARGO_PKG_VERSION"));
+}
@@ -0,0 +1,55 @@
+//! The rendered output of expired annotations, which point at the item's name with notes at the
+//! attribute and the requirement, and help spanning the code to remove, and of invalid arguments.
+//! Each `tests/ui/*.rs` fixture is compiled against the proc macro, built with the `nightly`
+//! feature, and its stderr compared with the `.stderr` file next to it. These snapshots are the
+//! contract for the wording and spans of diagnostics, so changes to them should be deliberate:
+//! rewrite them with `cargo +nightly test --test ui -- --bless`, or with `BLESS=1`, and review the
+//! diff.
+//!
+//! Fixtures set the environment the macro sees with `//@rustc-env: KEY=VALUE`, on top of
+//! `CARGO_PKG_VERSION=0.1.0`, and expect to fail to compile unless marked `//@check-pass`.
+
+mod common;
+
+use std::{env, fs};
+
+use ui_test::{bless_output_files, run_tests, spanned::Spanned, Config};
+
+fn main() -> ui_test::color_eyre::Result<()> {
+    let mut config = Config::rustc("tests/ui");
+    config.bless_command = Some("cargo +nightly test --test ui -- --bless".into());
+    if env::var_os("BLESS").is_some_and(|bless| bless != "0") {
+        config.output_conflict_handling = bless_output_files;
+    }
+    // the snapshots are the expectations, there are no `//~` annotations
+    config.comment_defaults.base().require_annotations = Spanned::dummy(false).into();
+
+    let proc_macro = common::proc_macro_path(false, true);
+    config.program.args.extend([
+        "--crate-type".into(),
+        "lib".into(),
+        "--crate-name".into(),
+        "fixture".into(),
+        "--extern".into(),
+        format!("allow_until={}", proc_macro.display()).into(),
+    ]);
+
+    // an empty package root, so no manifest configures the macro
+    let dir = env::temp_dir().join(format!("allow-until-ui-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    // don't let the environment of this test run leak into the fixtures
+    for (key, _) in env::vars() {
+        if key.starts_with("ALLOW_UNTIL_") || key.starts_with("CARGO_PKG_") {
+            config.program.envs.push((key.into(), None));
+        }
+    }
+    config.program.envs.extend([
+        ("CARGO_MANIFEST_DIR".into(), Some(dir.into())),
+        ("CARGO_CRATE_NAME".into(), Some("fixture".into())),
+        ("CARGO_PKG_NAME".into(), Some("fixture".into())),
+        ("CARGO_PKG_VERSION".into(), Some("0.1.0".into())),
+    ]);
+
+    run_tests(config)
+}
@@ -0,0 +1,8 @@
+//@rustc-env: CARGO_PKG_VERSION=2.0.0
+
+#[derive(allow_until::AllowUntil)]
+pub enum Shape {
+    #[allow_until(version = ">=2.0", severity = "warning")]
+    Square,
+    Circle(#[allow_until(version = "^2")] u8),
+}
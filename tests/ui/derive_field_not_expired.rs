@@ -0,0 +1,10 @@
+//@rustc-env: ALLOW_UNTIL_VERSION=1.3.9
+//@check-pass
+
+#[derive(allow_until::AllowUntil)]
+pub struct Options {
+    /// Parse the legacy format.
+    #[allow_until(version = ">=1.4", reason = "the legacy format is gone")]
+    pub legacy: bool,
+    pub strict: bool,
+}
@@ -0,0 +1,10 @@
+//@rustc-env: CARGO_PKG_VERSION=2.0.0
+
+pub struct Kept;
+
+/// Superseded by `Kept`.
+#[derive(Debug)]
+#[allow_until::allow_until(version = ">=2.0")]
+pub struct Legacy;
+
+pub struct AlsoKept;
@@ -0,0 +1,4 @@
+//@rustc-env: CARGO_PKG_VERSION=2.0.0
+
+#[allow_until::allow_until(version = ">=2.0")]
+extern "C" {}
@@ -0,0 +1,11 @@
+#[allow_until::allow_until(verson = ">=2.0")]
+pub struct A;
+
+#[allow_until::allow_until(version = ">=2.0", version = ">=3.0")]
+pub struct B;
+
+#[allow_until::allow_until(version = ">=2.0 <3")]
+pub struct C;
+
+#[allow_until::allow_until(version = 2.0)]
+pub struct D;
@@ -0,0 +1,8 @@
+#[allow_until::allow_until(version = , reason = "use `Json`")]
+pub struct A;
+
+#[allow_until::allow_until(version = >=1.0 <2)]
+pub struct B;
+
+#[allow_until::allow_until(version = >=1.0 [2])]
+pub struct C;
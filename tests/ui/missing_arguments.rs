@@ -0,0 +1,8 @@
+#[allow_until::allow_until]
+pub struct Legacy;
+
+#[derive(allow_until::AllowUntil)]
+pub struct Options {
+    #[allow_until()]
+    pub legacy: bool,
+}
@@ -0,0 +1,7 @@
+//@rustc-env: ALLOW_UNTIL_VERSION=1.2.0
+
+#[allow_until::allow_until(version = >=1.0, <2.0, reason = "use `Json`")]
+pub struct A;
+
+#[allow_until::allow_until(version = ^0.4)]
+pub struct B;
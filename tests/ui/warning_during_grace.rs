@@ -0,0 +1,5 @@
+//@rustc-env: ALLOW_UNTIL_VERSION=2.0.3
+//@check-pass
+
+#[allow_until::allow_until(version = ">=2.0", severity = "warning", grace = "1 minor")]
+pub fn legacy() {}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::Fixture;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+"#;
+
+#[test]
+fn override_takes_precedence() {
+    Fixture::new(SOURCE)
+        .version("1.4.0")
+        .env("ALLOW_UNTIL_VERSION", "2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("struct `Legacy` not allowed! (version 2.0.0 matches >=2.0)")
+        .assert_contains("= note: current version 2.0.0 (from the `ALLOW_UNTIL_VERSION` override)");
+
+    Fixture::new(SOURCE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_VERSION", "1.9.0")
+        .compile()
+        .assert_success();
+}
+
+#[test]
+fn cargo_version_without_override() {
+    Fixture::new(SOURCE)
+        .version("2.1.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("= note: current version 2.1.0 (from `CARGO_PKG_VERSION`)")
+        .assert_not_contains("override");
+}
+
+#[test]
+fn time_since_expiry() {
+    Fixture::new(SOURCE)
+        .version("2.4.1")
+        .compile()
+        .assert_failure()
+        .assert_contains("= note: expired since 2.0.0, 4 minor releases ago");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = "^1.2")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.2.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("= note: expired since 1.2.0, the current version");
+}
+
+#[test]
+fn invalid_override() {
+    Fixture::new(SOURCE)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_VERSION", "two")
+        .compile()
+        .assert_failure()
+        .assert_contains("invalid `ALLOW_UNTIL_VERSION` value `two`");
+}
+
+const TWO_ANNOTATIONS: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Legacy;
+
+    #[allow_until::allow_until(version = ">=2.0")]
+    pub struct Other;
+"#;
+
+#[test]
+fn junk_cargo_version_reported_once() {
+    let outcome = Fixture::new(TWO_ANNOTATIONS)
+        .version("release-candidate")
+        .compile();
+
+    outcome.assert_failure().assert_contains(
+        "`CARGO_PKG_VERSION` value `release-candidate` is not a valid semver version",
+    );
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("is not a valid semver version")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn junk_cargo_version_falls_back_to_components() {
+    Fixture::new(SOURCE)
+        .version("2.0-custom")
+        .env("CARGO_PKG_VERSION_MAJOR", "2")
+        .env("CARGO_PKG_VERSION_MINOR", "0")
+        .env("CARGO_PKG_VERSION_PATCH", "1")
+        .compile()
+        .assert_failure()
+        .assert_contains("version 2.0.1 matches >=2.0")
+        .assert_contains("the version was reconstructed");
+}
+
+#[test]
+fn junk_override_reported_once() {
+    let outcome = Fixture::new(TWO_ANNOTATIONS)
+        .version("1.0.0")
+        .env("ALLOW_UNTIL_VERSION", "soon")
+        .compile();
+
+    outcome.assert_failure();
+    assert_eq!(
+        outcome
+            .stderr
+            .matches("invalid `ALLOW_UNTIL_VERSION`")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn dead_requirement() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = "<0.5")]
+        pub struct Legacy;
+        "#,
+    )
+    .version("1.2.0")
+    .compile()
+    .assert_success()
+    .assert_contains(
+        "warning: [AU0023] annotation can never fire, as no version from 1.2.0 on matches `<0.5`",
+    )
+    .assert_contains("lib.rs:2:9")
+    .assert_contains("= help: delete the annotation, or correct the requirement");
+
+    Fixture::new(SOURCE)
+        .version("1.2.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0023");
+}
@@ -0,0 +1,129 @@
+//! `version_from`, which compares an annotation against a version read from a key of a TOML or
+//! JSON file rather than against Cargo's.
+
+mod common;
+
+use common::Fixture;
+
+const TOML: &str = r#"
+[tool.api]
+version = "2.1"
+"#;
+
+const JSON: &str = r#"{ "tool": { "api": { "version": "1.4.0", "stable": true } } }"#;
+
+fn annotated(file: &str, key: &str) -> String {
+    format!(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", version_from = {{ file = "{}", key = "{}" }})]
+        pub struct LegacyEndpoint;
+        "#,
+        file, key
+    )
+}
+
+#[test]
+fn toml() {
+    Fixture::new(&annotated("config.toml", "tool.api.version"))
+        .file("config.toml", TOML)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] struct `LegacyEndpoint` not allowed!")
+        .assert_contains("matches version 2.1.0");
+}
+
+#[test]
+fn json() {
+    Fixture::new(&annotated("api.json", "tool.api.version"))
+        .file("api.json", JSON)
+        .version("3.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn override_takes_precedence() {
+    Fixture::new(&annotated("config.toml", "tool.api.version"))
+        .file("config.toml", TOML)
+        .env("ALLOW_UNTIL_VERSION", "1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+}
+
+#[test]
+fn written_on_windows() {
+    Fixture::new(&annotated(r"config\\api.toml", "tool.api.version"))
+        .file(
+            "config/api.toml",
+            "\u{feff}[tool.api]\r\nversion = \"2.1\"\r\n",
+        )
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("matches version 2.1.0");
+
+    Fixture::new(&annotated("api.json", "tool.api.version"))
+        .file("api.json", &format!("\u{feff}{}\r\n", JSON))
+        .version("3.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0020");
+}
+
+#[test]
+fn missing_file() {
+    Fixture::new(&annotated("config.toml", "tool.api.version"))
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0020] `config.toml` could not be read from `/")
+        .assert_contains("/config.toml`: ")
+        .assert_contains("lib.rs:2:70");
+}
+
+#[test]
+fn missing_key() {
+    Fixture::new(&annotated("config.toml", "tool.api.release"))
+        .file("config.toml", TOML)
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0003] `config.toml` has no key `tool.api.release`")
+        .assert_contains("lib.rs:2:70");
+}
+
+#[test]
+fn not_a_string() {
+    Fixture::new(&annotated("api.json", "tool.api.stable"))
+        .file("api.json", JSON)
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0003] `tool.api.stable` in `api.json` is a boolean, not a version string",
+        );
+
+    Fixture::new(&annotated("api.json", "tool.api"))
+        .file("api.json", JSON)
+        .compile()
+        .assert_failure()
+        .assert_contains("`tool.api` in `api.json` is a table, not a version string");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(&annotated("config.yaml", "tool.api.version"))
+        .compile()
+        .assert_failure()
+        .assert_contains("`config.yaml` is neither a `.toml` nor a `.json` file");
+
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", version_source = "git", version_from = { file = "config.toml", key = "version" })]
+        pub struct LegacyEndpoint;
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("remove `version_source` or `version_from`");
+}
@@ -0,0 +1,72 @@
+//! `version!` and `version_source!`, which expand to the version annotations are compared against
+//! and where it came from.
+
+mod common;
+
+use common::Fixture;
+
+// the error shows what the macros expanded to
+const SOURCE: &str = r#"
+    compile_error!(concat!(
+        "version ",
+        allow_until::version!(),
+        " from ",
+        allow_until::version_source!()
+    ));
+"#;
+
+#[test]
+fn cargo_version() {
+    Fixture::new(SOURCE)
+        .version("1.4.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: version 1.4.0 from `CARGO_PKG_VERSION`");
+}
+
+#[test]
+fn override_takes_precedence() {
+    Fixture::new(SOURCE)
+        .version("1.4.0")
+        .env("ALLOW_UNTIL_VERSION", "2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: version 2.0.0 from the `ALLOW_UNTIL_VERSION` override");
+}
+
+#[test]
+fn usable_as_a_constant() {
+    Fixture::new(
+        r#"
+        pub const VERSION: &str = allow_until::version!();
+        pub const SOURCE: &str = allow_until::version_source!();
+        "#,
+    )
+    .version("1.4.0")
+    .compile()
+    .assert_success();
+}
+
+#[test]
+fn errors_at_the_call_site() {
+    Fixture::new(
+        r#"
+        pub const VERSION: &str = allow_until::version!();
+        "#,
+    )
+    .version("1.0.0")
+    .env("ALLOW_UNTIL_VERSION", "two")
+    .compile()
+    .assert_failure()
+    .assert_contains("invalid `ALLOW_UNTIL_VERSION` value `two`")
+    .assert_contains("lib.rs:2:35");
+
+    Fixture::new(
+        r#"
+        pub const VERSION: &str = allow_until::version!(cargo);
+        "#,
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("`version!` takes no arguments");
+}
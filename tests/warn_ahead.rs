@@ -0,0 +1,87 @@
+mod common;
+
+use common::Fixture;
+
+fn ahead(distance: &str, req: &str, version: &str) -> common::Outcome {
+    Fixture::new(&format!(
+        "#[allow_until::allow_until(version = {:?})]\npub struct Legacy;\n",
+        req
+    ))
+    .manifest(&format!(
+        "[package.metadata.allow-until]\nwarn_ahead = {:?}\n",
+        distance
+    ))
+    .version(version)
+    .env("ALLOW_UNTIL_TRACE", "1")
+    .compile()
+}
+
+#[test]
+fn within_distance() {
+    ahead("1 minor", ">=1.5", "1.4.2")
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0025] struct `Legacy` expires at version 1.5.0 (requirement `>=1.5`)",
+        )
+        .assert_contains("lib.rs:2:12")
+        .assert_contains(
+            "= note: `warn_ahead` warns 1 minor ahead, and the current version is 1.4.2",
+        );
+}
+
+#[test]
+fn boundaries() {
+    ahead("1 minor", ">=1.5.0", "1.4.0").assert_contains("AU0025");
+    ahead("1 minor", ">=1.5.1", "1.4.9").assert_not_contains("AU0025");
+    ahead("1 minor", ">=1.6", "1.4.9").assert_not_contains("AU0025");
+    ahead("2 patches", ">=1.4.4", "1.4.2").assert_contains("AU0025");
+    ahead("2 patches", ">=1.4.5", "1.4.2").assert_not_contains("AU0025");
+    ahead("1 major", ">=2.3", "1.0.0").assert_not_contains("AU0025");
+    ahead("1 major", ">=2.0", "1.0.0").assert_contains("AU0025");
+}
+
+#[test]
+fn zero_major_versions() {
+    ahead("1 minor", ">=0.5", "0.4.1").assert_contains("expires at version 0.5.0");
+    ahead("1 minor", ">=0.6", "0.4.1").assert_not_contains("AU0025");
+    ahead("1 major", ">=1.0", "0.9.3").assert_contains("expires at version 1.0.0");
+    ahead("1 patch", "^0.0.4", "0.0.3").assert_contains("expires at version 0.0.4");
+}
+
+#[test]
+fn expired_annotations_are_not_warned_ahead() {
+    ahead("1 minor", ">=1.4", "1.4.2")
+        .assert_failure()
+        .assert_contains("error: [AU0001]")
+        .assert_not_contains("AU0025");
+}
+
+#[test]
+fn unknown_minimal_version() {
+    ahead("1 minor", ">=2, <1", "1.4.2")
+        .assert_success()
+        .assert_not_contains("AU0025")
+        .assert_contains(
+            "the first version matching `>=2, <1` is unknown, so `warn_ahead` is skipped",
+        );
+}
+
+#[test]
+fn off_by_default() {
+    Fixture::new("#[allow_until::allow_until(version = \">=1.5\")]\npub struct Legacy;\n")
+        .version("1.4.2")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0025");
+}
+
+#[test]
+fn invalid_distance() {
+    ahead("soon", ">=1.5", "1.4.2")
+        .assert_success()
+        .assert_contains(
+            "warning: [AU0008] `warn_ahead` in `[package.metadata.allow-until]`: invalid distance \
+             `soon`",
+        )
+        .assert_not_contains("AU0025");
+}
@@ -0,0 +1,167 @@
+//! `ALLOW_UNTIL_WARN_INTERVAL`, which holds back warnings emitted within the interval by an
+//! earlier build.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{Fixture, Outcome};
+
+const GRACE: &str = r#"
+#[allow_until::allow_until(version = ">=2.0", grace = "1 minor", id = "drop-legacy")]
+pub struct Legacy;
+"#;
+
+fn target(name: &str) -> PathBuf {
+    let target = std::env::temp_dir().join(format!(
+        "allow-until-warn-interval-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&target);
+    target
+}
+
+fn build(source: &str, target: &Path) -> Outcome {
+    Fixture::new(source)
+        .version("2.0.0")
+        .env("CARGO_TARGET_DIR", target.to_str().unwrap())
+        .env("ALLOW_UNTIL_WARN_INTERVAL", "86400")
+        .compile()
+}
+
+/// Moves the last emission of every warning in the state back by a day and a second.
+fn backdate(target: &Path) {
+    let path = target.join("allow-until").join("state.json");
+    let mut state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    for entry in state.as_object_mut().unwrap().values_mut() {
+        let emitted = entry["emitted"].as_u64().unwrap();
+        entry["emitted"] = (emitted - 86401).into();
+    }
+    std::fs::write(path, state.to_string()).unwrap();
+}
+
+#[test]
+fn within_and_beyond_the_interval() {
+    let target = target("grace");
+
+    build(GRACE, &target)
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_not_contains("held back");
+    build(GRACE, &target)
+        .assert_success()
+        .assert_not_contains("AU0001");
+    build(GRACE, &target)
+        .assert_success()
+        .assert_not_contains("AU0001");
+
+    backdate(&target);
+    build(GRACE, &target)
+        .assert_success()
+        .assert_contains("warning: [AU0001] struct `Legacy` not allowed!")
+        .assert_contains(
+            "held back 2 times since it was last emitted, as `ALLOW_UNTIL_WARN_INTERVAL` emits it \
+             at most once every 86400 seconds",
+        );
+    build(GRACE, &target)
+        .assert_success()
+        .assert_not_contains("AU0001");
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn errors_are_never_held_back() {
+    let target = target("errors");
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.0")]
+        pub struct Legacy;
+    "#;
+
+    for _ in 0..2 {
+        build(source, &target)
+            .assert_failure()
+            .assert_contains("error: [AU0001]");
+    }
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn expiring_soon() {
+    let target = target("ahead");
+    let source = r#"
+        #[allow_until::allow_until(version = ">=2.1")]
+        pub struct Legacy;
+    "#;
+    let ahead = |target: &Path| {
+        Fixture::new(source)
+            .manifest("[package.metadata.allow-until]\nwarn_ahead = \"1 minor\"\n")
+            .version("2.0.0")
+            .env("CARGO_TARGET_DIR", target.to_str().unwrap())
+            .env("ALLOW_UNTIL_WARN_INTERVAL", "86400")
+            .compile()
+    };
+
+    ahead(&target).assert_contains("struct `Legacy` expires at version 2.1.0");
+    ahead(&target).assert_not_contains("expires at");
+    backdate(&target);
+    ahead(&target)
+        .assert_contains("struct `Legacy` expires at version 2.1.0")
+        .assert_contains("held back 1 time since it was last emitted");
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn clearing_the_state() {
+    let target = target("clear");
+
+    build(GRACE, &target).assert_contains("AU0001");
+    std::fs::remove_file(target.join("allow-until").join("state.json")).unwrap();
+    build(GRACE, &target)
+        .assert_contains("AU0001")
+        .assert_not_contains("held back");
+
+    // an unreadable state is taken as empty, and replaced
+    std::fs::write(target.join("allow-until").join("state.json"), "{\"trunc").unwrap();
+    build(GRACE, &target).assert_contains("AU0001");
+    build(GRACE, &target).assert_not_contains("AU0001");
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn parallel_builds() {
+    let target = target("parallel");
+
+    let outcomes: Vec<Outcome> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..6)
+            .map(|_| scope.spawn(|| build(GRACE, &target)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    let warned = outcomes
+        .iter()
+        .filter(|outcome| outcome.stderr.contains("AU0001"))
+        .count();
+    assert_eq!(warned, 1);
+
+    let _ = std::fs::remove_dir_all(&target);
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(GRACE)
+        .version("2.0.0")
+        .env("ALLOW_UNTIL_WARN_INTERVAL", "daily")
+        .compile()
+        .assert_success()
+        .assert_contains(
+            "ignoring invalid `ALLOW_UNTIL_WARN_INTERVAL` value `daily`, expected a number of \
+             seconds",
+        )
+        .assert_contains("warning: [AU0001]");
+}
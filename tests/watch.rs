@@ -0,0 +1,87 @@
+//! `watch` and `watch_hash`, which expire an annotation once the file it works around changes.
+
+mod common;
+
+use common::Fixture;
+
+const SCHEMA: &str = "struct Config @0xdbb9ad1f14bf0b36 {}\n";
+const HASH: &str = "sha256:71f39fc6cee2885fb455f999ed42e19d9b49c74f9e7ce96dfadf5fb490765270";
+
+fn annotated(hash: &str) -> String {
+    format!(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", watch = "schemas/config.capnp", watch_hash = "{}")]
+        pub struct LegacyConfig;
+        "#,
+        hash
+    )
+}
+
+#[test]
+fn unchanged() {
+    Fixture::new(&annotated(HASH))
+        .file("schemas/config.capnp", SCHEMA)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0040");
+}
+
+#[test]
+fn changed() {
+    Fixture::new(&annotated(HASH))
+        .file(
+            "schemas/config.capnp",
+            "struct Config @0xdbb9ad1f14bf0b36 { port @0 :UInt16; }\n",
+        )
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains(
+            "error: [AU0040] struct `LegacyConfig` not allowed, as the watched \
+             `schemas/config.capnp` changed",
+        )
+        .assert_contains("its hash is now sha256:")
+        .assert_contains("update `watch_hash`");
+
+    // the hash is compared regardless of case
+    Fixture::new(&annotated(&HASH.to_uppercase().replace("SHA256", "sha256")))
+        .file("schemas/config.capnp", SCHEMA)
+        .version("1.0.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0040");
+}
+
+#[test]
+fn missing_file() {
+    Fixture::new(&annotated(HASH))
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("the watched file `schemas/config.capnp` could not be read")
+        .assert_contains("lib.rs:2:63");
+}
+
+#[test]
+fn invalid() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=2.0", watch = "schemas/config.capnp")]
+        pub struct LegacyConfig;
+        "#,
+    )
+    .file("schemas/config.capnp", SCHEMA)
+    .version("1.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("`watch` is given without the `watch_hash` of `schemas/config.capnp`")
+    .assert_contains(&format!("add `watch_hash = \"{}\"`", HASH));
+
+    Fixture::new(&annotated("sha1:da39a3ee5e6b4b0d3255bfef95601890afd80709"))
+        .file("schemas/config.capnp", SCHEMA)
+        .version("1.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("expected `sha256:` followed by the hex digest");
+}
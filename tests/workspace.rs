@@ -0,0 +1,149 @@
+mod common;
+
+use common::Fixture;
+
+const WORKSPACE: &str = r#"
+[workspace]
+members = ["members/*"]
+
+[workspace.metadata.allow-until]
+severity = "warning"
+owner = "platform-team"
+required = ["reason"]
+
+[workspace.metadata.allow-until.reasons]
+xml = "XML support is going away"
+"#;
+
+const PLAIN: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", reason = "gone")]
+    pub struct Legacy;
+"#;
+
+const SOURCE: &str = r#"
+    #[allow_until::allow_until(version = ">=2.0", reason_key = "xml")]
+    pub struct XmlConfig;
+
+    #[allow_until::allow_until(version = ">=2.0", reason_key = "json", severity = "error")]
+    pub struct JsonV1;
+"#;
+
+/// The member `name`, whose `[package.metadata.allow-until]` is `metadata`.
+fn member(name: &str, metadata: &str) -> Fixture {
+    Fixture::new(SOURCE)
+        .manifest(WORKSPACE)
+        .file(
+            &format!("members/{}/Cargo.toml", name),
+            &format!(
+                "[package]\nname = {:?}\nversion = \"2.0.0\"\n\n\
+                 [package.metadata.allow-until]\n{}",
+                name, metadata
+            ),
+        )
+        .member(&format!("members/{}", name))
+        .version("2.0.0")
+}
+
+#[test]
+fn precedence() {
+    // the workspace's defaults apply as they are
+    member(
+        "plain",
+        "[package.metadata.allow-until.reasons]\njson = \"JSON v1 is replaced by v2\"\n",
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("warning: [AU0001] XML support is going away")
+    .assert_contains("owner: platform-team")
+    // the annotation's own `severity` overrides both
+    .assert_contains("error: [AU0001] JSON v1 is replaced by v2");
+
+    // the package overrides the workspace key by key, and adds to its reasons
+    member(
+        "custom",
+        "owner = \"io-team\"\nrequired = []\n\n\
+         [package.metadata.allow-until.reasons]\njson = \"JSON v1 is replaced by v2\"\n",
+    )
+    .compile()
+    .assert_failure()
+    .assert_contains("warning: [AU0001] XML support is going away")
+    .assert_contains("owner: io-team")
+    .assert_not_contains("owner: platform-team")
+    .assert_contains("error: [AU0001] JSON v1 is replaced by v2");
+}
+
+#[test]
+fn required_by_the_workspace() {
+    Fixture::new(
+        r#"
+        #[allow_until::allow_until(version = ">=3.0")]
+        pub struct Legacy;
+        "#,
+    )
+    .manifest(WORKSPACE)
+    .file(
+        "members/a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"2.0.0\"\n",
+    )
+    .member("members/a")
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("annotation is missing the required argument `reason`");
+}
+
+#[test]
+fn configuration_file() {
+    Fixture::new(SOURCE)
+        // the file is found before the workspace root, and wins over its metadata
+        .manifest(WORKSPACE)
+        .file(
+            "members/.allow-until.toml",
+            "owner = \"members-team\"\n\n[reasons]\nxml = \"XML is gone\"\njson = \"so is JSON v1\"\n",
+        )
+        .file("members/a/Cargo.toml", "[package]\nname = \"a\"\nversion = \"2.0.0\"\n")
+        .member("members/a")
+        .version("2.0.0")
+        .compile()
+        .assert_failure()
+        .assert_contains("error: [AU0001] XML is gone")
+        .assert_contains("owner: members-team");
+}
+
+#[test]
+fn malformed_configuration_file() {
+    let outcome = Fixture::new(PLAIN)
+        .manifest(WORKSPACE)
+        .file(".allow-until.toml", "owner = \n")
+        .file(
+            "members/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"2.0.0\"\n",
+        )
+        .member("members/a")
+        .version("2.0.0")
+        .compile();
+
+    outcome.assert_failure().assert_contains(&format!(
+        "warning: [AU0008] failed to parse `{}`",
+        outcome.dir.join(".allow-until.toml").display()
+    ));
+    assert_eq!(outcome.stderr.matches("[AU0008]").count(), 1);
+    assert!(outcome
+        .stderr
+        .contains("ignoring the workspace configuration"));
+}
+
+#[test]
+fn invalid_workspace_values_are_named() {
+    Fixture::new(PLAIN)
+        .manifest("[workspace]\n\n[workspace.metadata.allow-until]\ngrace = 3\n")
+        .file(
+            "members/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"2.0.0\"\n",
+        )
+        .member("members/a")
+        .version("2.0.0")
+        .compile()
+        .assert_contains("`grace` in `[workspace.metadata.allow-until]` of `")
+        .assert_contains("Cargo.toml` must be a string");
+}
@@ -0,0 +1,109 @@
+//! Wrapper attributes applying an organisation's defaults to `allow_until`, through the
+//! `acme-sunset` example.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn defaults_apply() {
+    let source = r#"
+        #[acme_sunset::acme_sunset(version = ">=2.0", reason = "use `Ledger`")]
+        pub struct LegacyLedger;
+    "#;
+
+    Fixture::new(source)
+        .example("acme-sunset")
+        .version("1.4.0")
+        .compile()
+        .assert_success()
+        .assert_not_contains("AU0001");
+
+    // a warning rather than an error, owned by the platform team
+    Fixture::new(source)
+        .example("acme-sunset")
+        .version("2.0.0")
+        .compile()
+        .assert_success()
+        .assert_contains("warning: [AU0001] use `Ledger` (version 2.0.0 matches >=2.0)")
+        .assert_contains("owner: @acme/platform");
+}
+
+#[test]
+fn uses_override_defaults() {
+    Fixture::new(
+        r#"
+        #[acme_sunset::acme_sunset(
+            version = ">=2.0",
+            reason = "use `Ledger`",
+            severity = "error",
+            owner = "@billing",
+        )]
+        pub struct LegacyLedger;
+        "#,
+    )
+    .example("acme-sunset")
+    .version("2.0.0")
+    .compile()
+    .assert_failure()
+    .assert_contains("error: [AU0001] use `Ledger`")
+    .assert_contains("owner: @billing")
+    .assert_not_contains("@acme/platform");
+}
+
+#[test]
+fn tags_are_combined() {
+    let report =
+        std::env::temp_dir().join(format!("allow-until-wrapper-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&report);
+
+    Fixture::new(
+        r#"
+        #[acme_sunset::acme_sunset(version = ">=2.0", reason = "use `Ledger`", tags = ["billing"])]
+        pub struct LegacyLedger;
+        "#,
+    )
+    .example("acme-sunset")
+    .version("1.4.0")
+    .env("ALLOW_UNTIL_REPORT", report.to_str().unwrap())
+    .compile()
+    .assert_success();
+
+    let report = std::fs::read_to_string(&report).unwrap();
+    assert!(
+        report.contains(r#""tags":["acme","billing"]"#),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn required_arguments() {
+    Fixture::new(
+        r#"
+        #[acme_sunset::acme_sunset(version = ">=2.0")]
+        pub struct LegacyLedger;
+        "#,
+    )
+    .example("acme-sunset")
+    .compile()
+    .assert_failure()
+    .assert_contains("[AU0007] `acme_sunset` requires a `reason` argument");
+}
+
+#[test]
+fn arguments_are_validated_by_allow_until() {
+    Fixture::new(
+        r#"
+#[acme_sunset::acme_sunset(version = ">=2.0", reason = "use `Ledger`", sevrity = "error")]
+pub struct LegacyLedger;
+"#,
+    )
+    .example("acme-sunset")
+    .compile()
+    .assert_failure()
+    .assert_contains("unknown argument `sevrity`")
+    .assert_contains("did you mean `severity`?")
+    // at the argument as written in the use
+    .assert_contains("lib.rs:2:72");
+}